@@ -30,7 +30,8 @@ use rusoto_kinesis::{
 };
 use rusoto_s3::{
     AbortMultipartUploadError, CompleteMultipartUploadError, CreateMultipartUploadError,
-    DeleteObjectError, GetObjectError, HeadObjectError, PutObjectError, UploadPartError,
+    DeleteObjectError, GetObjectError, HeadObjectError, ListObjectsV2Error, PutObjectError,
+    UploadPartError,
 };
 
 use crate::retry::Retryable;
@@ -126,6 +127,12 @@ impl Retryable for HeadObjectError {
     }
 }
 
+impl Retryable for ListObjectsV2Error {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
 #[cfg(feature = "kinesis")]
 impl Retryable for GetRecordsError {
     fn is_retryable(&self) -> bool {
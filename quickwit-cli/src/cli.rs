@@ -23,6 +23,7 @@ use quickwit_config::DEFAULT_QW_CONFIG_PATH;
 use tracing::Level;
 
 use crate::index::{build_index_command, IndexCliCommand};
+use crate::metastore::{build_metastore_command, MetastoreCliCommand};
 use crate::service::{build_run_command, RunCliCommand};
 use crate::source::{build_source_command, SourceCliCommand};
 use crate::split::{build_split_command, SplitCliCommand};
@@ -41,6 +42,7 @@ pub fn build_cli<'a>() -> Command<'a> {
         .subcommand(build_index_command().display_order(2))
         .subcommand(build_source_command().display_order(3))
         .subcommand(build_split_command().display_order(4))
+        .subcommand(build_metastore_command().display_order(5))
         .arg_required_else_help(true)
         .disable_help_subcommand(true)
         .subcommand_required(true)
@@ -52,6 +54,7 @@ pub enum CliCommand {
     Index(IndexCliCommand),
     Split(SplitCliCommand),
     Source(SourceCliCommand),
+    Metastore(MetastoreCliCommand),
 }
 
 impl CliCommand {
@@ -61,6 +64,7 @@ impl CliCommand {
             CliCommand::Index(subcommand) => subcommand.default_log_level(),
             CliCommand::Source(_) => Level::ERROR,
             CliCommand::Split(_) => Level::ERROR,
+            CliCommand::Metastore(_) => Level::ERROR,
         }
     }
 
@@ -73,6 +77,9 @@ impl CliCommand {
             "run" => RunCliCommand::parse_cli_args(submatches).map(CliCommand::Run),
             "source" => SourceCliCommand::parse_cli_args(submatches).map(CliCommand::Source),
             "split" => SplitCliCommand::parse_cli_args(submatches).map(CliCommand::Split),
+            "metastore" => {
+                MetastoreCliCommand::parse_cli_args(submatches).map(CliCommand::Metastore)
+            }
             _ => bail!("Subcommand `{}` is not implemented.", subcommand),
         }
     }
@@ -83,6 +90,7 @@ impl CliCommand {
             CliCommand::Run(subcommand) => subcommand.execute().await,
             CliCommand::Source(subcommand) => subcommand.execute().await,
             CliCommand::Split(subcommand) => subcommand.execute().await,
+            CliCommand::Metastore(subcommand) => subcommand.execute().await,
         }
     }
 }
@@ -32,10 +32,11 @@ use quickwit_actors::{ActorHandle, ObservationType, Universe};
 use quickwit_common::uri::Uri;
 use quickwit_common::GREEN_COLOR;
 use quickwit_config::{
-    IndexConfig, IndexerConfig, SourceConfig, SourceParams, CLI_INGEST_SOURCE_ID,
+    FileSourceParams, IndexConfig, IndexerConfig, SourceConfig, SourceParams, CLI_INGEST_SOURCE_ID,
 };
-use quickwit_core::{clear_cache_directory, remove_indexing_directory, IndexService};
+use quickwit_core::{clear_cache_directory, remove_indexing_directory, IndexAnomaly, IndexService};
 use quickwit_doc_mapper::tag_pruning::match_tag_field_name;
+use quickwit_doc_mapper::InputFormat;
 use quickwit_indexing::actors::{IndexingPipeline, IndexingService};
 use quickwit_indexing::models::{
     DetachPipeline, IndexingStatistics, SpawnMergePipeline, SpawnPipeline,
@@ -87,7 +88,11 @@ pub fn build_index_command<'a>() -> Command<'a> {
                     arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
                         .env("QW_DATA_DIR")
                         .required(false),
-                    arg!(--"input-path" <INPUT_PATH> "Location of the input file.")
+                    arg!(--"input-path" <INPUT_PATH> "Location of the input file, of a directory of input files, or glob pattern matching the input files. Files with a `.gz` or `.zst` extension are decompressed.")
+                        .required(false),
+                    arg!(--"input-format" <INPUT_FORMAT> "Format of the input records: `json` (one JSON object per line), `csv` (with a header row), or `logfmt`.")
+                        .possible_values(&["json", "csv", "logfmt"])
+                        .default_value("json")
                         .required(false),
                     arg!(--overwrite "Overwrites pre-existing index.")
                         .required(false),
@@ -166,6 +171,18 @@ pub fn build_index_command<'a>() -> Command<'a> {
                         .required(false),
                 ])
             )
+        .subcommand(
+            Command::new("check")
+                .about("Checks that the splits of an index recorded in the metastore match the split files in storage.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index"),
+                    arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
+                        .env("QW_DATA_DIR")
+                        .required(false),
+                    arg!(--repair "Deletes the orphaned split files and marks the splits with a missing or truncated file for deletion.")
+                        .required(false),
+                ])
+            )
         .subcommand(
             Command::new("delete")
                 .about("Delete an index.")
@@ -200,6 +217,7 @@ pub struct CreateIndexArgs {
 pub struct IngestDocsArgs {
     pub index_id: String,
     pub input_path_opt: Option<PathBuf>,
+    pub input_format: InputFormat,
     pub config_uri: Uri,
     pub data_dir: Option<PathBuf>,
     pub overwrite: bool,
@@ -237,6 +255,14 @@ pub struct GarbageCollectIndexArgs {
     pub data_dir: Option<PathBuf>,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct CheckIndexArgs {
+    pub index_id: String,
+    pub repair: bool,
+    pub config_uri: Uri,
+    pub data_dir: Option<PathBuf>,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct MergeOrDemuxArgs {
     pub index_id: String,
@@ -259,6 +285,7 @@ pub enum IndexCliCommand {
     Demux(MergeOrDemuxArgs),
     Merge(MergeOrDemuxArgs),
     GarbageCollect(GarbageCollectIndexArgs),
+    Check(CheckIndexArgs),
     Ingest(IngestDocsArgs),
     Search(SearchIndexArgs),
 }
@@ -284,6 +311,7 @@ impl IndexCliCommand {
             "demux" => Self::parse_demux_args(submatches),
             "describe" => Self::parse_describe_args(submatches),
             "gc" => Self::parse_garbage_collect_args(submatches),
+            "check" => Self::parse_check_args(submatches),
             "ingest" => Self::parse_ingest_args(submatches),
             _ => bail!("Index subcommand `{}` is not implemented.", subcommand),
         }
@@ -355,6 +383,11 @@ impl IndexCliCommand {
         } else {
             None
         };
+        let input_format = matches
+            .value_of("input-format")
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or_default();
         let config_uri = matches
             .value_of("config")
             .map(Uri::try_new)
@@ -366,6 +399,7 @@ impl IndexCliCommand {
         Ok(Self::Ingest(IngestDocsArgs {
             index_id,
             input_path_opt,
+            input_format,
             overwrite,
             config_uri,
             data_dir,
@@ -476,6 +510,25 @@ impl IndexCliCommand {
         }))
     }
 
+    fn parse_check_args(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let index_id = matches
+            .value_of("index")
+            .expect("`index` is a required arg.")
+            .to_string();
+        let repair = matches.is_present("repair");
+        let config_uri = matches
+            .value_of("config")
+            .map(Uri::try_new)
+            .expect("`config` is a required arg.")?;
+        let data_dir = matches.value_of("data-dir").map(PathBuf::from);
+        Ok(Self::Check(CheckIndexArgs {
+            index_id,
+            repair,
+            config_uri,
+            data_dir,
+        }))
+    }
+
     fn parse_delete_args(matches: &ArgMatches) -> anyhow::Result<Self> {
         let index_id = matches
             .value_of("index")
@@ -505,6 +558,7 @@ impl IndexCliCommand {
             Self::Merge(args) => merge_or_demux_cli(args, true, false).await,
             Self::Demux(args) => merge_or_demux_cli(args, false, true).await,
             Self::GarbageCollect(args) => garbage_collect_index_cli(args).await,
+            Self::Check(args) => check_index_cli(args).await,
             Self::Delete(args) => delete_index_cli(args).await,
         }
     }
@@ -798,11 +852,13 @@ pub async fn ingest_docs_cli(args: IngestDocsArgs) -> anyhow::Result<()> {
 
     let config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
 
-    let source_params = if let Some(filepath) = args.input_path_opt.as_ref() {
-        SourceParams::file(filepath)
+    let mut file_source_params = if let Some(filepath) = args.input_path_opt.as_ref() {
+        FileSourceParams::file(filepath)
     } else {
-        SourceParams::stdin()
+        FileSourceParams::stdin()
     };
+    file_source_params.input_format = args.input_format;
+    let source_params = SourceParams::File(file_source_params);
     let source = SourceConfig {
         source_id: CLI_INGEST_SOURCE_ID.to_string(),
         source_params,
@@ -896,6 +952,7 @@ pub async fn search_index(args: SearchIndexArgs) -> anyhow::Result<SearchRespons
         sort_order: None,
         sort_by_field: None,
         aggregation_request: args.aggregation,
+        timeout_millis: None,
     };
     let search_response: SearchResponse =
         single_node_search(&search_request, &*metastore, storage_uri_resolver.clone()).await?;
@@ -1032,6 +1089,66 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
     Ok(())
 }
 
+pub async fn check_index_cli(args: CheckIndexArgs) -> anyhow::Result<()> {
+    debug!(args = ?args, "check-index");
+    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
+    let metastore = quickwit_metastore_uri_resolver()
+        .resolve(&quickwit_config.metastore_uri())
+        .await?;
+    let index_service = IndexService::new(
+        metastore,
+        quickwit_storage_uri_resolver().clone(),
+        quickwit_config.default_index_root_uri(),
+    );
+    let anomalies = index_service
+        .check_index(&args.index_id, args.repair)
+        .await?;
+    if anomalies.is_empty() {
+        println!("No anomaly found in index `{}`.", args.index_id);
+        return Ok(());
+    }
+    let num_repairable_anomalies = anomalies
+        .iter()
+        .filter(|anomaly| anomaly.is_repairable())
+        .count();
+    println!("{}", make_index_anomalies_table(&anomalies));
+
+    if num_repairable_anomalies == 0 {
+        return Ok(());
+    }
+    if args.repair {
+        println!(
+            "{} anomalies repaired in index `{}`.",
+            num_repairable_anomalies, args.index_id
+        );
+    } else {
+        println!(
+            "{} anomalies can be repaired by running the command again with the `--repair` flag.",
+            num_repairable_anomalies
+        );
+    }
+    Ok(())
+}
+
+fn make_index_anomalies_table(anomalies: &[IndexAnomaly]) -> Table {
+    let rows = anomalies.iter().map(|anomaly| IndexAnomalyRow {
+        kind: anomaly.kind(),
+        description: anomaly.to_string(),
+        repairable: anomaly.is_repairable(),
+    });
+    make_table("Anomalies", rows, false)
+}
+
+#[derive(Tabled)]
+struct IndexAnomalyRow {
+    #[tabled(rename = "Kind")]
+    kind: &'static str,
+    #[tabled(rename = "Description")]
+    description: String,
+    #[tabled(rename = "Repairable")]
+    repairable: bool,
+}
+
 /// Starts a tokio task that displays the indexing statistics
 /// every once in awhile.
 pub async fn start_statistics_reporting_loop(
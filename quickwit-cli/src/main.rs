@@ -131,7 +131,7 @@ fn runtime_configuration_for_cmd(command: &CliCommand) -> Option<RuntimesConfigu
             }
         }
         CliCommand::Index(_) => Some(RuntimesConfiguration::default()),
-        CliCommand::Split(_) | CliCommand::Source(_) => None,
+        CliCommand::Split(_) | CliCommand::Source(_) | CliCommand::Metastore(_) => None,
     }
 }
 
@@ -212,11 +212,13 @@ mod tests {
 
     use quickwit_cli::cli::{build_cli, CliCommand};
     use quickwit_cli::index::{
-        CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, GarbageCollectIndexArgs,
-        IndexCliCommand, IngestDocsArgs, MergeOrDemuxArgs, SearchIndexArgs,
+        CheckIndexArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs,
+        GarbageCollectIndexArgs, IndexCliCommand, IngestDocsArgs, MergeOrDemuxArgs,
+        SearchIndexArgs,
     };
     use quickwit_cli::split::{DescribeSplitArgs, ExtractSplitArgs, SplitCliCommand};
     use quickwit_common::uri::Uri;
+    use quickwit_doc_mapper::InputFormat;
 
     #[test]
     fn test_parse_create_args() -> anyhow::Result<()> {
@@ -289,6 +291,7 @@ mod tests {
                     config_uri,
                     index_id,
                     input_path_opt: None,
+                    input_format: InputFormat::Json,
                     overwrite: false,
                     data_dir: None,
                     clear_cache: true,
@@ -306,6 +309,8 @@ mod tests {
             "/config.yaml",
             "--keep-cache",
             "--overwrite",
+            "--input-format",
+            "csv",
         ])?;
         let command = CliCommand::parse_cli_args(&matches)?;
         assert!(matches!(
@@ -315,6 +320,7 @@ mod tests {
                     config_uri,
                     index_id,
                     input_path_opt: None,
+                    input_format: InputFormat::Csv,
                     overwrite: true,
                     data_dir: None,
                     clear_cache: false
@@ -489,6 +495,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_check_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "check",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_command = CliCommand::Index(IndexCliCommand::Check(CheckIndexArgs {
+            index_id: "wikipedia".to_string(),
+            repair: false,
+            config_uri: Uri::try_new("file:///config.yaml").unwrap(),
+            data_dir: None,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "check",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--repair",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Check(CheckIndexArgs { repair: true, .. }))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_parse_merge_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
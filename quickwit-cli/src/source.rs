@@ -17,15 +17,19 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::cmp::Ordering;
+use std::str::FromStr;
+
 use anyhow::{bail, Context};
 use clap::{arg, ArgMatches, Command};
 use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_config::SourceConfig;
-use quickwit_indexing::check_source_connectivity;
-use quickwit_metastore::checkpoint::SourceCheckpoint;
+use quickwit_core::IndexService;
+use quickwit_indexing::{check_source_connectivity, CheckpointResetTarget};
+use quickwit_metastore::checkpoint::{CheckpointReset, PartitionId, SourceCheckpoint};
 use quickwit_metastore::{quickwit_metastore_uri_resolver, IndexMetadata};
-use quickwit_storage::load_file;
+use quickwit_storage::{load_file, quickwit_storage_uri_resolver};
 use serde_json::Value;
 use tabled::{Table, Tabled};
 
@@ -58,6 +62,19 @@ pub fn build_source_command<'a>() -> Command<'a> {
                     arg!(--source <SOURCE_ID> "ID of the source."),
                 ])
             )
+        .subcommand(
+            Command::new("reset-checkpoint")
+                .about("Resets the checkpoint of a source, i.e. the positions from which the indexing pipelines resume reading the partitions of the source.")
+                .args(&[
+                    arg!(--index <INDEX_ID> "ID of the target index"),
+                    arg!(--source <SOURCE_ID> "ID of the source."),
+                    arg!(--to <TARGET> "Position to reset the partitions to: `beginning`, `end`, `offset:<offset>`, or `timestamp:<timestamp in milliseconds>`. Only the Kafka source supports `end`, `offset`, and `timestamp`."),
+                    arg!(--partitions <PARTITION_IDS> "Resets only the partitions included in this comma-separated list of partition IDs.")
+                        .required(false)
+                        .use_value_delimiter(true),
+                    arg!(--"dry-run" "Executes the command in dry run mode and only displays the current and new positions of the partitions."),
+                ])
+            )
         .subcommand(
             Command::new("list")
                 .about("Lists the sources of an index.")
@@ -89,6 +106,16 @@ pub struct DescribeSourceArgs {
     pub source_id: String,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct ResetSourceCheckpointArgs {
+    pub config_uri: Uri,
+    pub index_id: String,
+    pub source_id: String,
+    pub target: CheckpointResetTarget,
+    pub partition_ids: Vec<PartitionId>,
+    pub dry_run: bool,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ListSourcesArgs {
     pub config_uri: Uri,
@@ -100,6 +127,7 @@ pub enum SourceCliCommand {
     CreateSource(CreateSourceArgs),
     DeleteSource(DeleteSourceArgs),
     DescribeSource(DescribeSourceArgs),
+    ResetSourceCheckpoint(ResetSourceCheckpointArgs),
     ListSources(ListSourcesArgs),
 }
 
@@ -109,6 +137,7 @@ impl SourceCliCommand {
             Self::CreateSource(args) => create_source_cli(args).await,
             Self::DeleteSource(args) => delete_source_cli(args).await,
             Self::DescribeSource(args) => describe_source_cli(args).await,
+            Self::ResetSourceCheckpoint(args) => reset_source_checkpoint_cli(args).await,
             Self::ListSources(args) => list_sources_cli(args).await,
         }
     }
@@ -121,6 +150,9 @@ impl SourceCliCommand {
             "create" => Self::parse_create_args(submatches).map(Self::CreateSource),
             "delete" => Self::parse_delete_args(submatches).map(Self::DeleteSource),
             "describe" => Self::parse_describe_args(submatches).map(Self::DescribeSource),
+            "reset-checkpoint" => {
+                Self::parse_reset_checkpoint_args(submatches).map(Self::ResetSourceCheckpoint)
+            }
             "list" => Self::parse_list_args(submatches).map(Self::ListSources),
             _ => bail!("Source subcommand `{}` is not implemented.", subcommand),
         }
@@ -186,6 +218,40 @@ impl SourceCliCommand {
         })
     }
 
+    fn parse_reset_checkpoint_args(
+        matches: &ArgMatches,
+    ) -> anyhow::Result<ResetSourceCheckpointArgs> {
+        let config_uri = matches
+            .value_of("config")
+            .map(Uri::try_new)
+            .expect("`config` is a required arg.")?;
+        let index_id = matches
+            .value_of("index")
+            .map(String::from)
+            .expect("`index` is a required arg.");
+        let source_id = matches
+            .value_of("source")
+            .map(String::from)
+            .expect("`source` is a required arg.");
+        let target = matches
+            .value_of("to")
+            .map(CheckpointResetTarget::from_str)
+            .expect("`to` is a required arg.")?;
+        let partition_ids = matches
+            .values_of("partitions")
+            .map(|values| values.map(PartitionId::from).collect())
+            .unwrap_or_default();
+        let dry_run = matches.is_present("dry-run");
+        Ok(ResetSourceCheckpointArgs {
+            config_uri,
+            index_id,
+            source_id,
+            target,
+            partition_ids,
+            dry_run,
+        })
+    }
+
     fn parse_list_args(matches: &ArgMatches) -> anyhow::Result<ListSourcesArgs> {
         let config_uri = matches
             .value_of("config")
@@ -289,6 +355,69 @@ where
     Ok((source_table, params_table, checkpoint_table))
 }
 
+async fn reset_source_checkpoint_cli(args: ResetSourceCheckpointArgs) -> anyhow::Result<()> {
+    let quickwit_config = load_quickwit_config(&args.config_uri, None).await?;
+    let metastore = quickwit_metastore_uri_resolver()
+        .resolve(&quickwit_config.metastore_uri())
+        .await?;
+    let index_service = IndexService::new(
+        metastore,
+        quickwit_storage_uri_resolver().clone(),
+        quickwit_config.default_index_root_uri(),
+    );
+    let checkpoint_reset = index_service
+        .reset_source_checkpoint(
+            &args.index_id,
+            &args.source_id,
+            args.target,
+            &args.partition_ids,
+            args.dry_run,
+        )
+        .await?;
+    if checkpoint_reset.is_empty() {
+        println!(
+            "The checkpoint of source `{}` has no partitions to reset.",
+            args.source_id
+        );
+        return Ok(());
+    }
+    display_tables(&[make_checkpoint_reset_table(&checkpoint_reset)]);
+
+    if args.dry_run {
+        println!(
+            "The checkpoint of source `{}` will be reset to `{}` for index `{}`.",
+            args.source_id, args.target, args.index_id
+        );
+    } else {
+        println!(
+            "Checkpoint of source `{}` successfully reset to `{}` for index `{}`. Running \
+             indexing pipelines still reading from the previous positions will fail to publish \
+             their splits and restart from the new positions.",
+            args.source_id, args.target, args.index_id
+        );
+    }
+    Ok(())
+}
+
+fn make_checkpoint_reset_table(checkpoint_reset: &CheckpointReset) -> Table {
+    let rows = checkpoint_reset
+        .iter()
+        .map(|(partition_id, current_position, new_position)| {
+            let direction = match new_position.cmp(current_position) {
+                Ordering::Less => "rewind",
+                Ordering::Equal => "unchanged",
+                Ordering::Greater => "fast-forward",
+            };
+            CheckpointResetRow {
+                partition_id: partition_id.0.to_string(),
+                current_position: current_position.as_str().to_string(),
+                new_position: new_position.as_str().to_string(),
+                direction: direction.to_string(),
+            }
+        });
+    make_table("Checkpoint reset", rows, false)
+}
+
 async fn list_sources_cli(args: ListSourcesArgs) -> anyhow::Result<()> {
     let quickwit_config = load_quickwit_config(&args.config_uri, None).await?;
     let index_metadata = resolve_index(&quickwit_config.metastore_uri(), &args.index_id).await?;
@@ -333,6 +462,18 @@ struct CheckpointRow {
     offset: String,
 }
 
+#[derive(Tabled)]
+struct CheckpointResetRow {
+    #[tabled(rename = "Partition ID")]
+    partition_id: String,
+    #[tabled(rename = "Current offset")]
+    current_position: String,
+    #[tabled(rename = "New offset")]
+    new_position: String,
+    #[tabled(rename = "Direction")]
+    direction: String,
+}
+
 fn display_tables(tables: &[Table]) {
     println!(
         "{}",
@@ -377,7 +518,7 @@ async fn resolve_index(metastore_uri: &Uri, index_id: &str) -> anyhow::Result<In
 #[cfg(test)]
 mod tests {
     use quickwit_config::SourceParams;
-    use quickwit_metastore::checkpoint::{PartitionId, Position};
+    use quickwit_metastore::checkpoint::Position;
     use serde_json::json;
 
     use super::*;
@@ -475,6 +616,122 @@ mod tests {
         assert_eq!(command, expected_command);
     }
 
+    #[test]
+    fn test_parse_reset_source_checkpoint_args() {
+        {
+            let app = build_cli().no_binary_name(true);
+            let matches = app
+                .try_get_matches_from(vec![
+                    "source",
+                    "reset-checkpoint",
+                    "--index",
+                    "hdfs-logs",
+                    "--source",
+                    "hdfs-logs-source",
+                    "--to",
+                    "beginning",
+                    "--config",
+                    "/conf.yaml",
+                ])
+                .unwrap();
+            let command = CliCommand::parse_cli_args(&matches).unwrap();
+            let expected_command = CliCommand::Source(SourceCliCommand::ResetSourceCheckpoint(
+                ResetSourceCheckpointArgs {
+                    config_uri: Uri::try_new("file:///conf.yaml").unwrap(),
+                    index_id: "hdfs-logs".to_string(),
+                    source_id: "hdfs-logs-source".to_string(),
+                    target: CheckpointResetTarget::Beginning,
+                    partition_ids: Vec::new(),
+                    dry_run: false,
+                },
+            ));
+            assert_eq!(command, expected_command);
+        }
+        {
+            let app = build_cli().no_binary_name(true);
+            let matches = app
+                .try_get_matches_from(vec![
+                    "source",
+                    "reset-checkpoint",
+                    "--index",
+                    "hdfs-logs",
+                    "--source",
+                    "hdfs-logs-source",
+                    "--to",
+                    "offset:1234",
+                    "--partitions",
+                    "0,2",
+                    "--dry-run",
+                    "--config",
+                    "/conf.yaml",
+                ])
+                .unwrap();
+            let command = CliCommand::parse_cli_args(&matches).unwrap();
+            let expected_command = CliCommand::Source(SourceCliCommand::ResetSourceCheckpoint(
+                ResetSourceCheckpointArgs {
+                    config_uri: Uri::try_new("file:///conf.yaml").unwrap(),
+                    index_id: "hdfs-logs".to_string(),
+                    source_id: "hdfs-logs-source".to_string(),
+                    target: CheckpointResetTarget::Offset(1234),
+                    partition_ids: vec![PartitionId::from("0"), PartitionId::from("2")],
+                    dry_run: true,
+                },
+            ));
+            assert_eq!(command, expected_command);
+        }
+        {
+            let app = build_cli().no_binary_name(true);
+            let matches = app
+                .try_get_matches_from(vec![
+                    "source",
+                    "reset-checkpoint",
+                    "--index",
+                    "hdfs-logs",
+                    "--source",
+                    "hdfs-logs-source",
+                    "--to",
+                    "yesterday",
+                    "--config",
+                    "/conf.yaml",
+                ])
+                .unwrap();
+            assert!(CliCommand::parse_cli_args(&matches).is_err());
+        }
+    }
+
+    #[test]
+    fn test_make_checkpoint_reset_table() {
+        let mut checkpoint_reset = CheckpointReset::default();
+        checkpoint_reset.record_partition_reset(
+            PartitionId::from("0"),
+            Position::from(10u64),
+            Position::Beginning,
+        );
+        checkpoint_reset.record_partition_reset(
+            PartitionId::from("1"),
+            Position::from(10u64),
+            Position::from(20u64),
+        );
+        let expected_rows = vec![
+            CheckpointResetRow {
+                partition_id: "0".to_string(),
+                current_position: Position::from(10u64).as_str().to_string(),
+                new_position: "".to_string(),
+                direction: "rewind".to_string(),
+            },
+            CheckpointResetRow {
+                partition_id: "1".to_string(),
+                current_position: Position::from(10u64).as_str().to_string(),
+                new_position: Position::from(20u64).as_str().to_string(),
+                direction: "fast-forward".to_string(),
+            },
+        ];
+        assert_eq!(
+            make_checkpoint_reset_table(&checkpoint_reset).to_string(),
+            make_table("Checkpoint reset", expected_rows, false).to_string()
+        );
+    }
+
     #[test]
     fn test_make_describe_source_tables() {
         assert!(make_describe_source_tables(
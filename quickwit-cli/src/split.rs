@@ -28,7 +28,7 @@ use quickwit_directories::{
     get_hotcache_from_split, read_split_footer, BundleDirectory, HotDirectory,
 };
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
-use quickwit_metastore::{quickwit_metastore_uri_resolver, Split, SplitState};
+use quickwit_metastore::{quickwit_metastore_uri_resolver, ListSplitsQuery, Split, SplitState};
 use quickwit_storage::{quickwit_storage_uri_resolver, BundleStorage, Storage};
 use tabled::{Table, Tabled};
 use time::{format_description, Date, OffsetDateTime, PrimitiveDateTime};
@@ -318,16 +318,24 @@ async fn list_split_cli(args: ListSplitArgs) -> anyhow::Result<()> {
     let metastore = metastore_uri_resolver
         .resolve(&quickwit_config.metastore_uri())
         .await?;
-    let splits = metastore.list_all_splits(&args.index_id).await?;
-
-    let filtered_splits = filter_splits(
-        splits,
+    let mut query = build_list_splits_query(
         args.split_states,
+        args.create_date.map(OffsetDateTime::unix_timestamp),
         args.start_date.map(OffsetDateTime::unix_timestamp),
         args.end_date.map(OffsetDateTime::unix_timestamp),
-        args.create_date.map(OffsetDateTime::unix_timestamp),
         args.tags,
     );
+    let mut filtered_splits = Vec::new();
+    loop {
+        let splits_page = metastore
+            .list_splits_page(&args.index_id, query.clone())
+            .await?;
+        filtered_splits.extend(splits_page.splits);
+        match splits_page.next_cursor {
+            Some(next_cursor) => query.cursor = Some(next_cursor),
+            None => break,
+        }
+    }
     let table = make_split_table(&filtered_splits, "Splits");
     println!("{table}");
 
@@ -386,12 +394,17 @@ async fn describe_split_cli(args: DescribeSplitArgs) -> anyhow::Result<()> {
     let index_metadata = metastore.index_metadata(&args.index_id).await?;
     let index_storage = storage_uri_resolver.resolve(&index_metadata.index_uri)?;
 
+    let query = ListSplitsQuery {
+        split_id_prefix: Some(args.split_id.clone()),
+        limit: Some(1),
+        ..Default::default()
+    };
     let split_metadata = metastore
-        .list_all_splits(&args.index_id)
+        .list_splits_page(&args.index_id, query)
         .await?
-        .iter()
+        .splits
+        .into_iter()
         .find(|split| split.split_id() == args.split_id)
-        .cloned()
         .with_context(|| {
             format!(
                 "Could not find split metadata in metastore {}",
@@ -466,61 +479,35 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn filter_splits(
-    splits: Vec<Split>,
+/// Number of splits fetched from the metastore at a time when listing splits.
+const LIST_SPLITS_PAGE_SIZE: usize = 1_000;
+
+fn build_list_splits_query(
     split_states_opt: Option<Vec<SplitState>>,
     create_ts_opt: Option<i64>,
     start_ts_opt: Option<i64>,
     end_ts_opt: Option<i64>,
     tag_filter_ast_opt: Option<TagFilterAst>,
-) -> Vec<Split> {
-    let split_state_filter = |split: &Split| {
-        split_states_opt
-            .as_ref()
-            .map(|split_states| split_states.contains(&split.split_state))
-            .unwrap_or(true)
-    };
-    let create_ts_filter = |split: &Split| {
-        create_ts_opt
-            .map(|create_ts| create_ts >= split.split_metadata.create_timestamp)
-            .unwrap_or(true)
-    };
-    let start_ts_filter = |split: &Split| {
-        start_ts_opt
-            .and_then(|start_ts| {
-                split
-                    .split_metadata
-                    .time_range
-                    .as_ref()
-                    .map(|time_range| start_ts <= *time_range.end())
-            })
-            .unwrap_or(true)
+) -> ListSplitsQuery {
+    // The end and creation dates are inclusive whereas the query ranges are not.
+    let time_range = if start_ts_opt.is_some() || end_ts_opt.is_some() {
+        Some(
+            start_ts_opt.unwrap_or(i64::MIN)
+                ..end_ts_opt
+                    .map(|end_ts| end_ts.saturating_add(1))
+                    .unwrap_or(i64::MAX),
+        )
+    } else {
+        None
     };
-    let end_ts_filter = |split: &Split| {
-        end_ts_opt
-            .and_then(|end_ts| {
-                split
-                    .split_metadata
-                    .time_range
-                    .as_ref()
-                    .map(|time_range| end_ts >= *time_range.start())
-            })
-            .unwrap_or(true)
-    };
-    let tag_filter = |split: &Split| {
-        tag_filter_ast_opt
-            .as_ref()
-            .map(|tag_filter_ast| tag_filter_ast.evaluate(&split.split_metadata.tags))
-            .unwrap_or(true)
-    };
-    splits
-        .into_iter()
-        .filter(split_state_filter)
-        .filter(create_ts_filter)
-        .filter(start_ts_filter)
-        .filter(end_ts_filter)
-        .filter(tag_filter)
-        .collect()
+    ListSplitsQuery {
+        split_states: split_states_opt.unwrap_or_default(),
+        time_range,
+        tags: tag_filter_ast_opt,
+        create_timestamp: create_ts_opt.map(|create_ts| i64::MIN..create_ts.saturating_add(1)),
+        limit: Some(LIST_SPLITS_PAGE_SIZE),
+        ..Default::default()
+    }
 }
 
 fn make_split_table(splits: &[Split], title: &str) -> Table {
@@ -786,6 +773,14 @@ mod tests {
         }
     }
 
+    fn filter_splits(splits: Vec<Split>, query: ListSplitsQuery) -> Vec<String> {
+        splits
+            .into_iter()
+            .filter(|split| query.matches(split))
+            .map(|split| split.split_metadata.split_id)
+            .collect()
+    }
+
     #[test]
     fn test_filter_splits_by_state() {
         let splits = vec![
@@ -793,20 +788,14 @@ mod tests {
             make_split("two", SplitState::Published, 0, None, &[]),
             make_split("three", SplitState::MarkedForDeletion, 0, None, &[]),
         ];
-        assert_eq!(
-            filter_splits(
-                splits,
-                Some(vec![SplitState::Staged, SplitState::Published]),
-                None,
-                None,
-                None,
-                None
-            )
-            .into_iter()
-            .map(|split| split.split_metadata.split_id)
-            .collect::<Vec<_>>(),
-            ["one", "two"]
+        let query = build_list_splits_query(
+            Some(vec![SplitState::Staged, SplitState::Published]),
+            None,
+            None,
+            None,
+            None,
         );
+        assert_eq!(filter_splits(splits, query), ["one", "two"]);
     }
 
     #[test]
@@ -816,13 +805,8 @@ mod tests {
             make_split("two", SplitState::Staged, 5, None, &[]),
             make_split("three", SplitState::Staged, 10, None, &[]),
         ];
-        assert_eq!(
-            filter_splits(splits, None, Some(5), None, None, None)
-                .into_iter()
-                .map(|split| split.split_metadata.split_id)
-                .collect::<Vec<_>>(),
-            ["one", "two"]
-        );
+        let query = build_list_splits_query(None, Some(5), None, None, None);
+        assert_eq!(filter_splits(splits, query), ["one", "two"]);
     }
 
     #[test]
@@ -834,11 +818,9 @@ mod tests {
             make_split("four", SplitState::Staged, 0, Some(10..=20), &[]),
             make_split("five", SplitState::Staged, 0, Some(15..=20), &[]),
         ];
+        let query = build_list_splits_query(None, None, Some(10), None, None);
         assert_eq!(
-            filter_splits(splits, None, None, Some(10), None, None)
-                .into_iter()
-                .map(|split| split.split_metadata.split_id)
-                .collect::<Vec<_>>(),
+            filter_splits(splits, query),
             ["two", "three", "four", "five"]
         );
     }
@@ -852,11 +834,9 @@ mod tests {
             make_split("four", SplitState::Staged, 0, Some(10..=20), &[]),
             make_split("five", SplitState::Staged, 0, Some(15..=20), &[]),
         ];
+        let query = build_list_splits_query(None, None, None, Some(10), None);
         assert_eq!(
-            filter_splits(splits, None, None, None, Some(10), None)
-                .into_iter()
-                .map(|split| split.split_metadata.split_id)
-                .collect::<Vec<_>>(),
+            filter_splits(splits, query),
             ["one", "two", "three", "four"]
         );
     }
@@ -867,23 +847,17 @@ mod tests {
             make_split("one", SplitState::Staged, 0, None, &[]),
             make_split("two", SplitState::Staged, 0, None, &["tenant:a"]),
         ];
-        assert_eq!(
-            filter_splits(
-                splits,
-                None,
-                None,
-                None,
-                None,
-                Some(TagFilterAst::Tag {
-                    is_present: true,
-                    tag: "tenant:a".to_string()
-                })
-            )
-            .into_iter()
-            .map(|split| split.split_metadata.split_id)
-            .collect::<Vec<_>>(),
-            ["two"]
+        let query = build_list_splits_query(
+            None,
+            None,
+            None,
+            None,
+            Some(TagFilterAst::Tag {
+                is_present: true,
+                tag: "tenant:a".to_string(),
+            }),
         );
+        assert_eq!(filter_splits(splits, query), ["two"]);
     }
 
     #[test]
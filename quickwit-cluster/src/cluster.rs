@@ -276,6 +276,26 @@ impl Cluster {
         Ok(grpc_addresses)
     }
 
+    /// Returns the unique IDs of the members providing the specified service.
+    pub async fn members_node_ids_for_service(&self, service: QuickwitService) -> Vec<String> {
+        let chitchat = self.chitchat_handle.chitchat();
+        let chitchat_guard = chitchat.lock().await;
+        self.members()
+            .into_iter()
+            .filter(|member| {
+                chitchat_guard
+                    .node_state(&NodeId::from(member.clone()))
+                    .and_then(|node_state| node_state.get(AVAILABLE_SERVICES_KEY))
+                    .map(|available_services_val| {
+                        parse_available_services_val(available_services_val, member)
+                            .contains(&service)
+                    })
+                    .unwrap_or(false)
+            })
+            .map(|member| member.node_unique_id)
+            .collect()
+    }
+
     /// Set a key-value pair on the cluster node's state.
     pub async fn set_key_value<K: ToString, V: ToString>(&self, key: K, value: V) {
         let chitchat = self.chitchat_handle.chitchat();
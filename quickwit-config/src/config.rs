@@ -84,6 +84,10 @@ pub struct IndexerConfig {
     pub split_store_max_num_bytes: Byte,
     #[serde(default = "IndexerConfig::default_split_store_max_num_splits")]
     pub split_store_max_num_splits: usize,
+    /// If true, the ingest API flushes each ingested batch to disk (fsync) before acknowledging
+    /// it, so that acknowledged documents survive a crash of the machine.
+    #[serde(default)]
+    pub ingest_api_fsync: bool,
 }
 
 impl IndexerConfig {
@@ -100,6 +104,7 @@ impl IndexerConfig {
         let indexer_config = IndexerConfig {
             split_store_max_num_bytes: Byte::from_bytes(1_000_000),
             split_store_max_num_splits: 3,
+            ingest_api_fsync: false,
         };
         Ok(indexer_config)
     }
@@ -110,6 +115,7 @@ impl Default for IndexerConfig {
         Self {
             split_store_max_num_bytes: Self::default_split_store_max_num_bytes(),
             split_store_max_num_splits: Self::default_split_store_max_num_splits(),
+            ingest_api_fsync: false,
         }
     }
 }
@@ -127,10 +133,16 @@ pub struct SearcherConfig {
     pub fast_field_cache_capacity: Byte,
     #[serde(default = "SearcherConfig::default_split_footer_cache_capacity")]
     pub split_footer_cache_capacity: Byte,
+    #[serde(default = "SearcherConfig::default_leaf_search_cache_capacity")]
+    pub leaf_search_cache_capacity: Byte,
     #[serde(default = "SearcherConfig::default_max_num_concurrent_split_searches")]
     pub max_num_concurrent_split_searches: usize,
     #[serde(default = "SearcherConfig::default_max_num_concurrent_split_streams")]
     pub max_num_concurrent_split_streams: usize,
+    #[serde(default = "SearcherConfig::default_split_replication_factor")]
+    pub split_replication_factor: usize,
+    #[serde(default)]
+    pub search_timeout_millis: Option<u64>,
 }
 
 impl SearcherConfig {
@@ -142,6 +154,10 @@ impl SearcherConfig {
         Byte::from_bytes(500_000_000) // 500M
     }
 
+    fn default_leaf_search_cache_capacity() -> Byte {
+        Byte::from_bytes(64_000_000) // 64M
+    }
+
     fn default_max_num_concurrent_split_searches() -> usize {
         100
     }
@@ -149,6 +165,10 @@ impl SearcherConfig {
     fn default_max_num_concurrent_split_streams() -> usize {
         100
     }
+
+    fn default_split_replication_factor() -> usize {
+        2
+    }
 }
 
 impl Default for SearcherConfig {
@@ -156,8 +176,11 @@ impl Default for SearcherConfig {
         Self {
             fast_field_cache_capacity: Self::default_fast_field_cache_capacity(),
             split_footer_cache_capacity: Self::default_split_footer_cache_capacity(),
+            leaf_search_cache_capacity: Self::default_leaf_search_cache_capacity(),
             max_num_concurrent_split_streams: Self::default_max_num_concurrent_split_streams(),
             max_num_concurrent_split_searches: Self::default_max_num_concurrent_split_searches(),
+            split_replication_factor: Self::default_split_replication_factor(),
+            search_timeout_millis: None,
         }
     }
 }
@@ -289,6 +312,12 @@ impl QuickwitConfig {
                 self.data_dir_path.display()
             );
         }
+        if self.searcher_config.split_replication_factor == 0 {
+            bail!("Searcher config `split_replication_factor` must be greater than 0.");
+        }
+        if self.searcher_config.search_timeout_millis == Some(0) {
+            bail!("Searcher config `search_timeout_millis` must be greater than 0.");
+        }
         Ok(())
     }
 
@@ -538,6 +567,7 @@ mod tests {
                     IndexerConfig {
                         split_store_max_num_bytes: Byte::from_str("1T").unwrap(),
                         split_store_max_num_splits: 10_000,
+                        ingest_api_fsync: true,
                     }
                 );
 
@@ -546,8 +576,11 @@ mod tests {
                     SearcherConfig {
                         fast_field_cache_capacity: Byte::from_str("10G").unwrap(),
                         split_footer_cache_capacity: Byte::from_str("1G").unwrap(),
+                        leaf_search_cache_capacity: Byte::from_str("100M").unwrap(),
                         max_num_concurrent_split_searches: 150,
                         max_num_concurrent_split_streams: 120,
+                        split_replication_factor: 3,
+                        search_timeout_millis: Some(5_000),
                     }
                 );
 
@@ -653,6 +686,13 @@ mod tests {
             .unwrap();
         quickwit_config.data_dir_path = env::current_dir().unwrap();
         assert!(quickwit_config.validate().is_ok());
+
+        quickwit_config.searcher_config.split_replication_factor = 0;
+        assert!(quickwit_config.validate().is_err());
+
+        quickwit_config.searcher_config.split_replication_factor = 1;
+        quickwit_config.searcher_config.search_timeout_millis = Some(0);
+        assert!(quickwit_config.validate().is_err());
     }
 
     #[tokio::test]
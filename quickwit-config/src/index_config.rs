@@ -26,8 +26,8 @@ use byte_unit::Byte;
 use json_comments::StripComments;
 use quickwit_common::uri::{Extension, Uri};
 use quickwit_doc_mapper::{
-    DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, ModeType, QuickwitJsonOptions, SortBy,
-    SortByConfig, SortOrder,
+    DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, FieldTransformEntry, ModeType,
+    QuickwitJsonOptions, SortBy, SortByConfig, SortOrder, TokenizerEntry,
 };
 use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
@@ -54,6 +54,12 @@ pub struct DocMapping {
     pub mode: ModeType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dynamic_mapping: Option<QuickwitJsonOptions>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tokenizers: Vec<TokenizerEntry>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<FieldTransformEntry>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -128,6 +134,63 @@ impl Default for MergePolicy {
     }
 }
 
+/// Destination of the documents rejected by the doc mapper during indexing.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DeadLetterQueueConfig {
+    /// Appends the rejected documents to an NDJSON file in the data directory of the indexer.
+    File,
+    /// Appends the rejected documents to a local ingest API queue. Defaults to the queue
+    /// `<index_id>-dead-letter`.
+    IngestApi {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        queue_id: Option<String>,
+    },
+    /// Sends the rejected documents to another index through its ingest API queue.
+    Index { index_id: String },
+}
+
+impl DeadLetterQueueConfig {
+    /// Returns the ID of the local ingest API queue holding the rejected documents of the index
+    /// `index_id`, if any.
+    pub fn ingest_api_queue_id(&self, index_id: &str) -> Option<String> {
+        match self {
+            DeadLetterQueueConfig::IngestApi { queue_id } => Some(
+                queue_id
+                    .clone()
+                    .unwrap_or_else(|| format!("{index_id}-dead-letter")),
+            ),
+            DeadLetterQueueConfig::File | DeadLetterQueueConfig::Index { .. } => None,
+        }
+    }
+
+    fn validate(&self, index_id: &str) -> anyhow::Result<()> {
+        match self {
+            DeadLetterQueueConfig::File => {}
+            DeadLetterQueueConfig::IngestApi { queue_id } => {
+                if let Some(queue_id) = queue_id {
+                    validate_identifier("Dead-letter queue ID", queue_id)?;
+                    if queue_id == index_id {
+                        bail!(
+                            "Dead-letter queue `{queue_id}` cannot be the queue of the index \
+                             itself."
+                        );
+                    }
+                }
+            }
+            DeadLetterQueueConfig::Index {
+                index_id: dead_letter_index_id,
+            } => {
+                validate_identifier("Dead-letter index ID", dead_letter_index_id)?;
+                if dead_letter_index_id == index_id {
+                    bail!("Index `{index_id}` cannot be its own dead-letter index.");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct IndexingSettings {
@@ -156,6 +219,9 @@ pub struct IndexingSettings {
     pub merge_policy: MergePolicy,
     #[serde(default)]
     pub resources: IndexingResources,
+    /// Where to send the documents that fail to parse. When unset, they are dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_letter_queue: Option<DeadLetterQueueConfig>,
 }
 
 impl IndexingSettings {
@@ -215,6 +281,7 @@ impl Default for IndexingSettings {
             merge_enabled: Self::default_merge_enabled(),
             merge_policy: MergePolicy::default(),
             resources: IndexingResources::default(),
+            dead_letter_queue: None,
         }
     }
 }
@@ -317,6 +384,9 @@ impl IndexConfig {
                  `merge_factor`."
             )
         }
+        if let Some(dead_letter_queue) = &self.indexing_settings.dead_letter_queue {
+            dead_letter_queue.validate(&self.index_id)?;
+        }
         Ok(())
     }
 }
@@ -341,6 +411,8 @@ pub fn build_doc_mapper(
         demux_field: indexing_settings.demux_field.clone(),
         mode: doc_mapping.mode,
         dynamic_mapping: doc_mapping.dynamic_mapping.clone(),
+        tokenizers: doc_mapping.tokenizers.clone(),
+        transforms: doc_mapping.transforms.clone(),
     };
     Ok(Arc::new(builder.try_build()?))
 }
@@ -595,6 +667,19 @@ mod tests {
                 .to_string()
                 .contains("must contain a `filepath`"));
         }
+        {
+            // Send the rejected documents to the index itself.
+            let mut invalid_index_config = index_config.clone();
+            invalid_index_config.indexing_settings.dead_letter_queue =
+                Some(DeadLetterQueueConfig::Index {
+                    index_id: index_config.index_id.clone(),
+                });
+            assert!(invalid_index_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("cannot be its own dead-letter index"));
+        }
         {
             // Add a demux field not declared in the mapping.
             let mut invalid_index_config = index_config;
@@ -630,4 +715,46 @@ mod tests {
         let minimal_config = serde_yaml::from_str::<IndexConfig>(config_yaml).unwrap();
         assert_eq!(minimal_config.doc_mapping.mode, ModeType::Lenient);
     }
+
+    #[test]
+    fn test_dead_letter_queue_config() {
+        let config_yaml = r#"
+            version: 0
+            index_id: hdfs-logs
+            doc_mapping: {}
+            indexing_settings:
+              dead_letter_queue:
+                type: ingest_api
+                queue_id: hdfs-logs-rejected
+        "#;
+        let index_config = serde_yaml::from_str::<IndexConfig>(config_yaml).unwrap();
+        assert_eq!(
+            index_config.indexing_settings.dead_letter_queue,
+            Some(DeadLetterQueueConfig::IngestApi {
+                queue_id: Some("hdfs-logs-rejected".to_string())
+            })
+        );
+        let config_yaml = r#"
+            version: 0
+            index_id: hdfs-logs
+            doc_mapping: {}
+            indexing_settings:
+              dead_letter_queue:
+                type: file
+        "#;
+        let index_config = serde_yaml::from_str::<IndexConfig>(config_yaml).unwrap();
+        assert_eq!(
+            index_config.indexing_settings.dead_letter_queue,
+            Some(DeadLetterQueueConfig::File)
+        );
+        let config_yaml = r#"
+            version: 0
+            index_id: hdfs-logs
+            doc_mapping: {}
+            indexing_settings:
+              dead_letter_queue:
+                type: index
+        "#;
+        serde_yaml::from_str::<IndexConfig>(config_yaml).unwrap_err();
+    }
 }
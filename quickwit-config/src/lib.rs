@@ -30,13 +30,14 @@ pub use config::{
     DEFAULT_QW_CONFIG_PATH, SEARCHER_CONFIG_INSTANCE,
 };
 pub use index_config::{
-    build_doc_mapper, DocMapping, IndexConfig, IndexingResources, IndexingSettings, MergePolicy,
-    SearchSettings,
+    build_doc_mapper, DeadLetterQueueConfig, DocMapping, IndexConfig, IndexingResources,
+    IndexingSettings, MergePolicy, SearchSettings,
 };
 pub use source_config::{
-    FileSourceParams, IngestApiSourceParams, KafkaSourceParams, KinesisSourceParams,
-    RegionOrEndpoint, SourceConfig, SourceParams, VecSourceParams, VoidSourceParams,
-    CLI_INGEST_SOURCE_ID,
+    parse_listen_address, Compression, FileSourceParams, IngestApiSourceParams, KafkaMessageFormat,
+    KafkaSourceParams, KinesisSourceParams, ObjectStorageSourceParams, OtlpSourceParams,
+    PulsarSourceParams, RegionOrEndpoint, SourceConfig, SourceParams, SyslogProtocol,
+    SyslogSourceParams, VecSourceParams, VoidSourceParams, CLI_INGEST_SOURCE_ID,
 };
 
 fn is_false(val: &bool) -> bool {
@@ -17,11 +17,13 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context};
 use json_comments::StripComments;
 use quickwit_common::uri::{Extension, Uri};
+use quickwit_doc_mapper::InputFormat;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 
@@ -104,7 +106,12 @@ impl SourceConfig {
                 }
                 Ok(())
             }
-            SourceParams::Kafka(_) | SourceParams::Kinesis(_) => {
+            SourceParams::Kafka(kafka_params) => kafka_params.message_format.validate(),
+            SourceParams::Pulsar(pulsar_params) => pulsar_params.validate(),
+            SourceParams::ObjectStorage(object_storage_params) => object_storage_params.validate(),
+            SourceParams::Syslog(syslog_params) => syslog_params.validate(),
+            SourceParams::Otlp(otlp_params) => otlp_params.validate(),
+            SourceParams::Kinesis(_) => {
                 // TODO consider any validation opportunity
                 Ok(())
             }
@@ -117,18 +124,46 @@ impl SourceConfig {
             SourceParams::File(_) => "file",
             SourceParams::Kafka(_) => "kafka",
             SourceParams::Kinesis(_) => "kinesis",
+            SourceParams::Pulsar(_) => "pulsar",
+            SourceParams::ObjectStorage(_) => "object-storage",
+            SourceParams::Syslog(_) => "syslog",
+            SourceParams::Otlp(_) => "otlp",
             SourceParams::Vec(_) => "vec",
             SourceParams::Void(_) => "void",
             SourceParams::IngestApi(_) => "ingest-api",
         }
     }
 
+    /// Returns whether the source is a receiver, i.e. a listener that writes the records it
+    /// receives to the ingest API queue of the index. Receivers do not have their own indexing
+    /// pipeline: they are run by the receiver service of each indexer node.
+    pub fn is_receiver(&self) -> bool {
+        matches!(
+            self.source_params,
+            SourceParams::Syslog(_) | SourceParams::Otlp(_)
+        )
+    }
+
+    /// Returns the format of the records read by the source. Sources that do not read text
+    /// records, such as Kafka, always return [`InputFormat::Json`].
+    pub fn input_format(&self) -> InputFormat {
+        match &self.source_params {
+            SourceParams::File(params) => params.input_format,
+            SourceParams::ObjectStorage(params) => params.input_format,
+            _ => InputFormat::Json,
+        }
+    }
+
     // TODO: Remove after source factory refactor.
     pub fn params(&self) -> serde_json::Value {
         match &self.source_params {
             SourceParams::File(params) => serde_json::to_value(params),
             SourceParams::Kafka(params) => serde_json::to_value(params),
             SourceParams::Kinesis(params) => serde_json::to_value(params),
+            SourceParams::Pulsar(params) => serde_json::to_value(params),
+            SourceParams::ObjectStorage(params) => serde_json::to_value(params),
+            SourceParams::Syslog(params) => serde_json::to_value(params),
+            SourceParams::Otlp(params) => serde_json::to_value(params),
             SourceParams::Vec(params) => serde_json::to_value(params),
             SourceParams::Void(params) => serde_json::to_value(params),
             SourceParams::IngestApi(params) => serde_json::to_value(params),
@@ -146,6 +181,14 @@ pub enum SourceParams {
     Kafka(KafkaSourceParams),
     #[serde(rename = "kinesis")]
     Kinesis(KinesisSourceParams),
+    #[serde(rename = "pulsar")]
+    Pulsar(PulsarSourceParams),
+    #[serde(rename = "object-storage")]
+    ObjectStorage(ObjectStorageSourceParams),
+    #[serde(rename = "syslog")]
+    Syslog(SyslogSourceParams),
+    #[serde(rename = "otlp")]
+    Otlp(OtlpSourceParams),
     #[serde(rename = "vec")]
     Vec(VecSourceParams),
     #[serde(rename = "void")]
@@ -171,11 +214,18 @@ impl SourceParams {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct FileSourceParams {
-    /// Path of the file to read. Assume stdin if None.
+    /// Path of the file to read, of a directory whose files are read, or glob pattern matching
+    /// the files to read. Assume stdin if None.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     #[serde(deserialize_with = "absolute_filepath_from_str")]
     pub filepath: Option<PathBuf>, //< If None read from stdin.
+    /// Compression of the files.
+    #[serde(default)]
+    pub compression: Compression,
+    /// Format of the records of the files.
+    #[serde(default)]
+    pub input_format: InputFormat,
 }
 
 // Deserializing a filepath string into an absolute filepath.
@@ -194,11 +244,17 @@ impl FileSourceParams {
     pub fn file<P: AsRef<Path>>(filepath: P) -> Self {
         FileSourceParams {
             filepath: Some(filepath.as_ref().to_path_buf()),
+            compression: Compression::default(),
+            input_format: InputFormat::default(),
         }
     }
 
     pub fn stdin() -> Self {
-        FileSourceParams { filepath: None }
+        FileSourceParams {
+            filepath: None,
+            compression: Compression::default(),
+            input_format: InputFormat::default(),
+        }
     }
 }
 
@@ -214,6 +270,57 @@ pub struct KafkaSourceParams {
     #[serde(default = "serde_json::Value::default")]
     #[serde(skip_serializing_if = "serde_json::Value::is_null")]
     pub client_params: serde_json::Value,
+    /// Encoding of the message payloads.
+    #[serde(default, skip_serializing_if = "KafkaMessageFormat::is_json")]
+    pub message_format: KafkaMessageFormat,
+}
+
+/// Encoding of the payloads of the messages consumed by a Kafka source. Avro and Protobuf
+/// payloads are expected to be framed with the Confluent wire format, which prefixes the payload
+/// with the ID of its schema in the schema registry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum KafkaMessageFormat {
+    /// UTF-8 encoded JSON documents.
+    Json,
+    /// Avro records, decoded with the schemas of a Confluent schema registry.
+    Avro { schema_registry_url: String },
+    /// Protobuf messages, decoded with the schemas of a Confluent schema registry.
+    Protobuf { schema_registry_url: String },
+}
+
+impl Default for KafkaMessageFormat {
+    fn default() -> Self {
+        KafkaMessageFormat::Json
+    }
+}
+
+impl KafkaMessageFormat {
+    fn is_json(&self) -> bool {
+        *self == KafkaMessageFormat::Json
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            KafkaMessageFormat::Json => Ok(()),
+            KafkaMessageFormat::Avro {
+                schema_registry_url,
+            }
+            | KafkaMessageFormat::Protobuf {
+                schema_registry_url,
+            } => {
+                if !schema_registry_url.starts_with("http://")
+                    && !schema_registry_url.starts_with("https://")
+                {
+                    bail!(
+                        "Schema registry URL `{schema_registry_url}` must start with `http://` or \
+                         `https://`."
+                    )
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -266,6 +373,200 @@ impl TryFrom<KinesisSourceParamsInner> for KinesisSourceParams {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PulsarSourceParams {
+    /// Topics that the source consumes.
+    pub topics: Vec<String>,
+    /// Address of the Pulsar service, for instance `pulsar://localhost:6650`.
+    pub address: String,
+    /// Name of the subscription used to consume the topics. Defaults to `quickwit-{source_id}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub subscription_name: Option<String>,
+}
+
+impl PulsarSourceParams {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.topics.is_empty() {
+            bail!("Pulsar source must consume at least one topic.");
+        }
+        if !self.address.starts_with("pulsar://") && !self.address.starts_with("pulsar+ssl://") {
+            bail!(
+                "Pulsar service address `{}` must start with `pulsar://` or `pulsar+ssl://`.",
+                self.address
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectStorageSourceParams {
+    /// URI of the objects to ingest, i.e. a bucket followed by a key prefix, for instance
+    /// `s3://my-bucket/logs/` or `s3://my-bucket/logs/app-`.
+    pub uri: String,
+    /// Interval between two listings of the objects, in seconds.
+    #[serde(default = "ObjectStorageSourceParams::default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Compression of the objects.
+    #[serde(default)]
+    pub compression: Compression,
+    /// Format of the records of the objects.
+    #[serde(default)]
+    pub input_format: InputFormat,
+}
+
+impl ObjectStorageSourceParams {
+    fn default_poll_interval_secs() -> u64 {
+        30
+    }
+
+    /// Splits the URI of the source into the URI of the storage to list and the key prefix of the
+    /// objects within that storage. The storage URI is the longest prefix of the URI ending with a
+    /// `/`, for instance `s3://my-bucket/logs/app-` is split into `s3://my-bucket/logs` and
+    /// `app-`.
+    pub fn storage_uri_and_key_prefix(&self) -> (&str, &str) {
+        let path_start = self
+            .uri
+            .find("://")
+            .map(|separator_idx| separator_idx + 3)
+            .unwrap_or(0);
+        match self.uri[path_start..].rfind('/') {
+            Some(slash_idx) => (
+                &self.uri[..path_start + slash_idx],
+                &self.uri[path_start + slash_idx + 1..],
+            ),
+            None => (&self.uri, ""),
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let (storage_uri, _) = self.storage_uri_and_key_prefix();
+        let storage_uri = Uri::try_new(storage_uri)
+            .with_context(|| format!("Object storage URI `{}` is invalid.", self.uri))?;
+        if matches!(storage_uri.protocol(), "postgres" | "postgresql") {
+            bail!(
+                "Object storage URI `{}` must point to a storage, such as `s3://` or `file://`.",
+                self.uri
+            );
+        }
+        if self.poll_interval_secs == 0 {
+            bail!("Object storage source `poll_interval_secs` must be strictly positive.");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogSourceParams {
+    /// Socket address the receiver listens on, for instance `0.0.0.0:1514`.
+    pub listen_address: String,
+    /// Transport protocol of the receiver.
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+}
+
+impl SyslogSourceParams {
+    fn validate(&self) -> anyhow::Result<()> {
+        parse_listen_address(&self.listen_address)?;
+        Ok(())
+    }
+}
+
+/// Transport protocol of a syslog receiver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    /// One message per datagram.
+    Udp,
+    /// Messages framed with octet counting or delimited by newlines, as described in RFC 6587.
+    Tcp,
+}
+
+impl Default for SyslogProtocol {
+    fn default() -> Self {
+        SyslogProtocol::Udp
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpSourceParams {
+    /// Socket address of the OTLP/gRPC endpoint, for instance `0.0.0.0:4317`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub grpc_listen_address: Option<String>,
+    /// Socket address of the OTLP/HTTP endpoint, for instance `0.0.0.0:4318`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub http_listen_address: Option<String>,
+}
+
+impl OtlpSourceParams {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.grpc_listen_address.is_none() && self.http_listen_address.is_none() {
+            bail!("OTLP source must set `grpc_listen_address`, `http_listen_address`, or both.");
+        }
+        for listen_address in self
+            .grpc_listen_address
+            .iter()
+            .chain(self.http_listen_address.iter())
+        {
+            parse_listen_address(listen_address)?;
+        }
+        if self.grpc_listen_address == self.http_listen_address {
+            bail!("OTLP source gRPC and HTTP endpoints must listen on different addresses.");
+        }
+        Ok(())
+    }
+}
+
+/// Parses the socket address a receiver listens on.
+pub fn parse_listen_address(listen_address: &str) -> anyhow::Result<SocketAddr> {
+    listen_address.parse::<SocketAddr>().with_context(|| {
+        format!(
+            "Listen address `{}` is invalid: expected a socket address such as `0.0.0.0:1514`.",
+            listen_address
+        )
+    })
+}
+
+/// Compression of the data read by a source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// The compression is inferred from the extension: `.gz` for gzip, `.zst` for zstd, and no
+    /// compression otherwise.
+    Auto,
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::Auto
+    }
+}
+
+impl Compression {
+    /// Resolves the compression of the file at `path`, inferring it from the extension of the file
+    /// when it is set to `auto`.
+    pub fn for_path(self, path: &Path) -> Compression {
+        if self != Compression::Auto {
+            return self;
+        }
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") | Some("gzip") => Compression::Gzip,
+            Some("zst") | Some("zstd") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct VecSourceParams {
@@ -294,7 +595,10 @@ mod tests {
 
     use super::*;
     use crate::source_config::RegionOrEndpoint;
-    use crate::{FileSourceParams, IngestApiSourceParams, KinesisSourceParams};
+    use crate::{
+        FileSourceParams, IngestApiSourceParams, KinesisSourceParams, ObjectStorageSourceParams,
+        OtlpSourceParams, PulsarSourceParams, SyslogSourceParams,
+    };
 
     fn get_source_config_filepath(source_config_filename: &str) -> String {
         format!(
@@ -318,11 +622,54 @@ mod tests {
                 topic: "cloudera-cluster-logs".to_string(),
                 client_log_level: None,
                 client_params: json! {{"bootstrap.servers": "host:9092"}},
+                message_format: KafkaMessageFormat::Json,
             }),
         };
         assert_eq!(source_config, expected_source_config);
     }
 
+    #[test]
+    fn test_kafka_source_message_format() {
+        let source_config_yaml = r#"
+            source_id: hdfs-logs-kafka-source
+            source_type: kafka
+            params:
+              topic: cloudera-cluster-logs
+              message_format:
+                type: avro
+                schema_registry_url: http://localhost:8081
+        "#;
+        let source_config = serde_yaml::from_str::<SourceConfig>(source_config_yaml).unwrap();
+        let kafka_params = match &source_config.source_params {
+            SourceParams::Kafka(kafka_params) => kafka_params,
+            _ => panic!("Expected Kafka source params."),
+        };
+        assert_eq!(
+            kafka_params.message_format,
+            KafkaMessageFormat::Avro {
+                schema_registry_url: "http://localhost:8081".to_string()
+            }
+        );
+        source_config.validate().unwrap();
+
+        let invalid_source_config_yaml = r#"
+            source_id: hdfs-logs-kafka-source
+            source_type: kafka
+            params:
+              topic: cloudera-cluster-logs
+              message_format:
+                type: protobuf
+                schema_registry_url: localhost:8081
+        "#;
+        let invalid_source_config =
+            serde_yaml::from_str::<SourceConfig>(invalid_source_config_yaml).unwrap();
+        assert!(invalid_source_config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("must start with `http://` or `https://`"));
+    }
+
     #[tokio::test]
     async fn test_load_kinesis_source_config() {
         let source_config_filepath = get_source_config_filepath("kinesis-source.yaml");
@@ -342,6 +689,181 @@ mod tests {
         assert_eq!(source_config, expected_source_config);
     }
 
+    #[tokio::test]
+    async fn test_load_pulsar_source_config() {
+        let source_config_filepath = get_source_config_filepath("pulsar-source.yaml");
+        let file_content = std::fs::read_to_string(&source_config_filepath).unwrap();
+        let source_config_uri = Uri::try_new(&source_config_filepath).unwrap();
+        let source_config = SourceConfig::from_uri(&source_config_uri, file_content.as_bytes())
+            .await
+            .unwrap();
+        let expected_source_config = SourceConfig {
+            source_id: "hdfs-logs-pulsar-source".to_string(),
+            source_params: SourceParams::Pulsar(PulsarSourceParams {
+                topics: vec!["persistent://public/default/cloudera-cluster-logs".to_string()],
+                address: "pulsar://localhost:6650".to_string(),
+                subscription_name: Some("quickwit-hdfs-logs".to_string()),
+            }),
+        };
+        assert_eq!(source_config, expected_source_config);
+    }
+
+    #[test]
+    fn test_pulsar_source_params_validation() {
+        let mut params = PulsarSourceParams {
+            topics: vec!["my-topic".to_string()],
+            address: "pulsar://localhost:6650".to_string(),
+            subscription_name: None,
+        };
+        params.validate().unwrap();
+
+        params.address = "localhost:6650".to_string();
+        assert!(params
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("must start with `pulsar://` or `pulsar+ssl://`"));
+
+        params.address = "pulsar+ssl://localhost:6651".to_string();
+        params.topics.clear();
+        params.validate().unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_load_object_storage_source_config() {
+        let source_config_filepath = get_source_config_filepath("object-storage-source.yaml");
+        let file_content = std::fs::read_to_string(&source_config_filepath).unwrap();
+        let source_config_uri = Uri::try_new(&source_config_filepath).unwrap();
+        let source_config = SourceConfig::from_uri(&source_config_uri, file_content.as_bytes())
+            .await
+            .unwrap();
+        let expected_source_config = SourceConfig {
+            source_id: "hdfs-logs-object-storage-source".to_string(),
+            source_params: SourceParams::ObjectStorage(ObjectStorageSourceParams {
+                uri: "s3://quickwit-datasets/hdfs-logs/".to_string(),
+                poll_interval_secs: 30,
+                compression: Compression::Gzip,
+                input_format: InputFormat::Csv,
+            }),
+        };
+        assert_eq!(source_config, expected_source_config);
+    }
+
+    #[test]
+    fn test_object_storage_source_params() {
+        let mut params = ObjectStorageSourceParams {
+            uri: "s3://my-bucket/logs/app-".to_string(),
+            poll_interval_secs: 30,
+            compression: Compression::Auto,
+            input_format: InputFormat::Json,
+        };
+        assert_eq!(
+            params.storage_uri_and_key_prefix(),
+            ("s3://my-bucket/logs", "app-")
+        );
+        params.validate().unwrap();
+
+        params.uri = "s3://my-bucket/logs/".to_string();
+        assert_eq!(
+            params.storage_uri_and_key_prefix(),
+            ("s3://my-bucket/logs", "")
+        );
+        params.uri = "s3://my-bucket".to_string();
+        assert_eq!(params.storage_uri_and_key_prefix(), ("s3://my-bucket", ""));
+
+        params.uri = "postgres://localhost/metastore".to_string();
+        params.validate().unwrap_err();
+
+        params.uri = "s3://my-bucket/logs/".to_string();
+        params.poll_interval_secs = 0;
+        params.validate().unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_load_syslog_source_config() {
+        let source_config_filepath = get_source_config_filepath("syslog-source.yaml");
+        let file_content = std::fs::read_to_string(&source_config_filepath).unwrap();
+        let source_config_uri = Uri::try_new(&source_config_filepath).unwrap();
+        let source_config = SourceConfig::from_uri(&source_config_uri, file_content.as_bytes())
+            .await
+            .unwrap();
+        let expected_source_config = SourceConfig {
+            source_id: "rsyslog-source".to_string(),
+            source_params: SourceParams::Syslog(SyslogSourceParams {
+                listen_address: "0.0.0.0:1514".to_string(),
+                protocol: SyslogProtocol::Tcp,
+            }),
+        };
+        assert_eq!(source_config, expected_source_config);
+        assert!(source_config.is_receiver());
+        source_config.validate().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_otlp_source_config() {
+        let source_config_filepath = get_source_config_filepath("otlp-source.yaml");
+        let file_content = std::fs::read_to_string(&source_config_filepath).unwrap();
+        let source_config_uri = Uri::try_new(&source_config_filepath).unwrap();
+        let source_config = SourceConfig::from_uri(&source_config_uri, file_content.as_bytes())
+            .await
+            .unwrap();
+        let expected_source_config = SourceConfig {
+            source_id: "otel-collector-source".to_string(),
+            source_params: SourceParams::Otlp(OtlpSourceParams {
+                grpc_listen_address: Some("0.0.0.0:4317".to_string()),
+                http_listen_address: Some("0.0.0.0:4318".to_string()),
+            }),
+        };
+        assert_eq!(source_config, expected_source_config);
+        assert!(source_config.is_receiver());
+        source_config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_receiver_source_params_validation() {
+        let syslog_params = SyslogSourceParams {
+            listen_address: "localhost".to_string(),
+            protocol: SyslogProtocol::Udp,
+        };
+        assert!(syslog_params
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("Listen address `localhost` is invalid"));
+
+        let mut otlp_params = OtlpSourceParams {
+            grpc_listen_address: None,
+            http_listen_address: None,
+        };
+        otlp_params.validate().unwrap_err();
+
+        otlp_params.http_listen_address = Some("127.0.0.1:4318".to_string());
+        otlp_params.validate().unwrap();
+
+        otlp_params.grpc_listen_address = Some("127.0.0.1:4318".to_string());
+        otlp_params.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_compression_for_path() {
+        assert_eq!(
+            Compression::Auto.for_path(Path::new("logs/app.json.gz")),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::Auto.for_path(Path::new("logs/app.json.zst")),
+            Compression::Zstd
+        );
+        assert_eq!(
+            Compression::Auto.for_path(Path::new("logs/app.json")),
+            Compression::None
+        );
+        assert_eq!(
+            Compression::Gzip.for_path(Path::new("logs/app.json")),
+            Compression::Gzip
+        );
+    }
+
     #[test]
     fn test_file_source_params_serialization() {
         {
@@ -353,7 +875,23 @@ mod tests {
             assert_eq!(
                 file_params.filepath.unwrap().as_path(),
                 uri.filepath().unwrap()
-            )
+            );
+            assert_eq!(file_params.compression, Compression::Auto);
+            assert_eq!(file_params.input_format, InputFormat::Json);
+        }
+        {
+            let yaml = r#"
+                filepath: /data/logs/*.json.gz
+                compression: gzip
+                input_format: logfmt
+            "#;
+            let file_params = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap();
+            assert_eq!(
+                file_params.filepath.unwrap().as_path(),
+                Path::new("/data/logs/*.json.gz")
+            );
+            assert_eq!(file_params.compression, Compression::Gzip);
+            assert_eq!(file_params.input_format, InputFormat::Logfmt);
         }
     }
 
@@ -453,6 +991,6 @@ mod tests {
         "#;
         let ingest_api_params = serde_yaml::from_str::<IngestApiSourceParams>(yaml).unwrap();
         assert_eq!(ingest_api_params.index_id, "wikipedia");
-        assert_eq!(ingest_api_params.batch_num_bytes_threshold, Some(200000))
+        assert_eq!(ingest_api_params.batch_num_bytes_threshold, Some(200000));
     }
 }
@@ -28,17 +28,21 @@ use quickwit_config::IndexConfig;
 use quickwit_indexing::actors::INDEXING_DIR_NAME;
 use quickwit_indexing::models::CACHE;
 use quickwit_indexing::{
-    delete_splits_with_files, run_garbage_collect, FileEntry, IndexingSplitStore,
-    SplitDeletionError,
+    delete_splits_with_files, plan_checkpoint_reset, run_garbage_collect, CheckpointResetTarget,
+    FileEntry, IndexingSplitStore, SplitDeletionError,
 };
+use quickwit_metastore::checkpoint::{CheckpointReset, PartitionId, SourceCheckpoint};
 use quickwit_metastore::{
-    IndexMetadata, Metastore, MetastoreError, Split, SplitMetadata, SplitState,
+    IndexMetadata, IndexStats, ListSplitsQuery, Metastore, MetastoreError, SplitMetadata,
+    SplitState, SplitsPage,
 };
 use quickwit_storage::{StorageResolverError, StorageUriResolver};
 use tantivy::time::OffsetDateTime;
 use thiserror::Error;
 use tracing::{error, info};
 
+use crate::index_check::{check_index, IndexAnomaly};
+
 #[derive(Error, Debug)]
 pub enum IndexServiceError {
     #[error("Failed to resolve the storage `{0}`.")]
@@ -49,6 +53,8 @@ pub enum IndexServiceError {
     SplitDeletionError(#[from] SplitDeletionError),
     #[error("Invalid index config: {0}.")]
     InvalidIndexConfig(String),
+    #[error("Invalid checkpoint reset: {0}")]
+    InvalidCheckpointReset(String),
 }
 
 /// Index service responsible for creating, updating and deleting indexes.
@@ -77,10 +83,83 @@ impl IndexService {
         Ok(index_metadata)
     }
 
-    /// Get all splits from index `index_id`.
-    pub async fn get_all_splits(&self, index_id: &str) -> Result<Vec<Split>, IndexServiceError> {
-        let splits = self.metastore.list_all_splits(index_id).await?;
-        Ok(splits)
+    /// Get a page of the splits from index `index_id` matching `query`.
+    pub async fn list_splits_page(
+        &self,
+        index_id: &str,
+        query: ListSplitsQuery,
+    ) -> Result<SplitsPage, IndexServiceError> {
+        let splits_page = self.metastore.list_splits_page(index_id, query).await?;
+        Ok(splits_page)
+    }
+
+    /// Get the statistics of index `index_id`.
+    pub async fn index_stats(&self, index_id: &str) -> Result<IndexStats, IndexServiceError> {
+        let index_stats = self.metastore.index_stats(index_id).await?;
+        Ok(index_stats)
+    }
+
+    /// Get the checkpoint of source `source_id` of index `index_id`.
+    pub async fn source_checkpoint(
+        &self,
+        index_id: &str,
+        source_id: &str,
+    ) -> Result<SourceCheckpoint, IndexServiceError> {
+        let index_metadata = self.metastore.index_metadata(index_id).await?;
+        let source_checkpoint_opt = index_metadata.checkpoint.source_checkpoint(source_id);
+
+        if !index_metadata.sources.contains_key(source_id) && source_checkpoint_opt.is_none() {
+            return Err(IndexServiceError::MetastoreError(
+                MetastoreError::SourceDoesNotExist {
+                    source_id: source_id.to_string(),
+                },
+            ));
+        }
+        Ok(source_checkpoint_opt.cloned().unwrap_or_default())
+    }
+
+    /// Resets the checkpoint of source `source_id` of index `index_id` to `target`. All the
+    /// partitions of the source are reset if `partition_ids` is empty.
+    ///
+    /// Returns the checkpoint reset, which is only computed and not applied in dry run mode.
+    pub async fn reset_source_checkpoint(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        target: CheckpointResetTarget,
+        partition_ids: &[PartitionId],
+        dry_run: bool,
+    ) -> Result<CheckpointReset, IndexServiceError> {
+        let index_metadata = self.metastore.index_metadata(index_id).await?;
+        let source_config = index_metadata.sources.get(source_id).ok_or_else(|| {
+            MetastoreError::SourceDoesNotExist {
+                source_id: source_id.to_string(),
+            }
+        })?;
+        let source_checkpoint = index_metadata
+            .checkpoint
+            .source_checkpoint(source_id)
+            .cloned()
+            .unwrap_or_default();
+        let checkpoint_reset =
+            plan_checkpoint_reset(source_config, &source_checkpoint, target, partition_ids)
+                .await
+                .map_err(|error| {
+                    IndexServiceError::InvalidCheckpointReset(format!("{:#}", error))
+                })?;
+
+        if !dry_run && !checkpoint_reset.is_empty() {
+            info!(
+                index_id = %index_id,
+                source_id = %source_id,
+                checkpoint_reset = ?checkpoint_reset,
+                "Resetting source checkpoint."
+            );
+            self.metastore
+                .reset_source_checkpoint(index_id, source_id, checkpoint_reset.clone())
+                .await?;
+        }
+        Ok(checkpoint_reset)
     }
 
     /// Get all indexes.
@@ -130,6 +209,7 @@ impl IndexService {
             index_id,
             index_uri: index_uri.into_string(),
             checkpoint: Default::default(),
+            checkpoint_update_timestamps: Default::default(),
             sources: index_config.sources(),
             doc_mapping: index_config.doc_mapping,
             indexing_settings: index_config.indexing_settings,
@@ -244,6 +324,21 @@ impl IndexService {
         Ok(deleted_entries)
     }
 
+    /// Checks that the splits of the index recorded in the metastore match its split files, and
+    /// repairs the index if `repair` is set. See [`check_index`](crate::check_index).
+    ///
+    /// * `index_id` - The target index Id.
+    /// * `repair` - Should the repairable anomalies be fixed.
+    pub async fn check_index(
+        &self,
+        index_id: &str,
+        repair: bool,
+    ) -> anyhow::Result<Vec<IndexAnomaly>> {
+        let index_uri = self.metastore.index_metadata(index_id).await?.index_uri;
+        let storage = self.storage_resolver.resolve(&index_uri)?;
+        check_index(self.metastore.clone(), storage, index_id, repair).await
+    }
+
     /// Clears the index by applying the following actions:
     /// - mark all splits for deletion in the metastore.
     /// - delete the files of all splits marked for deletion using garbage collection.
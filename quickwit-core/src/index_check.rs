@@ -0,0 +1,348 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use quickwit_common::split_file;
+use quickwit_metastore::{Metastore, SplitState};
+use quickwit_storage::Storage;
+use tracing::{info, warn};
+
+const MAX_CONCURRENT_STORAGE_REQUESTS: usize = if cfg!(test) { 2 } else { 10 };
+
+/// An inconsistency between the metastore and the storage of an index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexAnomaly {
+    /// A split file exists in storage but its split is unknown to the metastore, typically
+    /// after a crash between the upload of a split and its staging. Repaired by deleting the
+    /// file.
+    OrphanedFile {
+        file_name: String,
+        file_size_in_bytes: u64,
+    },
+    /// The file of a published split is missing from storage. Repaired by marking the split for
+    /// deletion.
+    MissingFile { split_id: String },
+    /// The size of the file of a published split does not match the end of its footer. Repaired
+    /// by marking the split for deletion.
+    FooterMismatch {
+        split_id: String,
+        expected_num_bytes: u64,
+        num_bytes: u64,
+    },
+    /// The checkpoint of a source that is not registered in the index, typically left behind
+    /// by a source deleted while its pipeline was still publishing.
+    CheckpointWithoutSource { source_id: String },
+}
+
+impl IndexAnomaly {
+    /// Returns true if the `--repair` mode fixes the anomaly.
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            IndexAnomaly::OrphanedFile { .. }
+                | IndexAnomaly::MissingFile { .. }
+                | IndexAnomaly::FooterMismatch { .. }
+        )
+    }
+
+    /// Returns the name of the kind of anomaly.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IndexAnomaly::OrphanedFile { .. } => "orphaned-file",
+            IndexAnomaly::MissingFile { .. } => "missing-file",
+            IndexAnomaly::FooterMismatch { .. } => "footer-mismatch",
+            IndexAnomaly::CheckpointWithoutSource { .. } => "checkpoint-without-source",
+        }
+    }
+}
+
+impl fmt::Display for IndexAnomaly {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexAnomaly::OrphanedFile {
+                file_name,
+                file_size_in_bytes,
+            } => write!(
+                formatter,
+                "File `{file_name}` ({file_size_in_bytes} bytes) does not belong to any split."
+            ),
+            IndexAnomaly::MissingFile { split_id } => write!(
+                formatter,
+                "File of published split `{split_id}` does not exist."
+            ),
+            IndexAnomaly::FooterMismatch {
+                split_id,
+                expected_num_bytes,
+                num_bytes,
+            } => write!(
+                formatter,
+                "File of published split `{split_id}` is {num_bytes} bytes long, expected \
+                 {expected_num_bytes} bytes."
+            ),
+            IndexAnomaly::CheckpointWithoutSource { source_id } => write!(
+                formatter,
+                "Checkpoint of source `{source_id}` does not belong to any source."
+            ),
+        }
+    }
+}
+
+/// Checks that the splits of an index recorded in the metastore match the split files in its
+/// storage, and that its checkpoints belong to its sources.
+///
+/// In repair mode, orphaned files are deleted and published splits whose file is missing or
+/// truncated are marked for deletion. Checkpoint anomalies are only reported.
+pub async fn check_index(
+    metastore: Arc<dyn Metastore>,
+    storage: Arc<dyn Storage>,
+    index_id: &str,
+    repair: bool,
+) -> anyhow::Result<Vec<IndexAnomaly>> {
+    // Splits are staged before their file is uploaded: listing the files first ensures that the
+    // files of splits created during the check are not reported as orphaned.
+    let file_paths = storage.list(Path::new(""), None).await?;
+    let index_metadata = metastore.index_metadata(index_id).await?;
+    let splits = metastore.list_all_splits(index_id).await?;
+
+    let mut anomalies = Vec::new();
+
+    let split_file_names: HashSet<String> = splits
+        .iter()
+        .map(|split| split_file(split.split_id()))
+        .collect();
+    for file_path in file_paths {
+        let is_split_file = file_path.parent() == Some(Path::new(""))
+            && file_path
+                .extension()
+                .map_or(false, |extension| extension == "split");
+        if !is_split_file {
+            continue;
+        }
+        let file_name = file_path.to_string_lossy().to_string();
+        if split_file_names.contains(&file_name) {
+            continue;
+        }
+        let file_size_in_bytes = storage.file_num_bytes(&file_path).await?;
+        anomalies.push(IndexAnomaly::OrphanedFile {
+            file_name,
+            file_size_in_bytes,
+        });
+    }
+
+    let mut file_size_results_stream = futures::stream::iter(
+        splits
+            .iter()
+            .filter(|split| split.split_state == SplitState::Published),
+    )
+    .map(|split| {
+        let storage = storage.clone();
+        async move {
+            let split_file_path = split_file(split.split_id());
+            let exists = storage.exists(Path::new(&split_file_path)).await?;
+            let num_bytes_opt = if exists {
+                Some(storage.file_num_bytes(Path::new(&split_file_path)).await?)
+            } else {
+                None
+            };
+            Ok::<_, anyhow::Error>((split, num_bytes_opt))
+        }
+    })
+    .buffer_unordered(MAX_CONCURRENT_STORAGE_REQUESTS);
+
+    let mut split_anomalies = Vec::new();
+    while let Some(file_size_result) = file_size_results_stream.next().await {
+        let (split, num_bytes_opt) = file_size_result?;
+        let split_id = split.split_id().to_string();
+        let expected_num_bytes = split.split_metadata.footer_offsets.end;
+        match num_bytes_opt {
+            None => {
+                split_anomalies.push((split_id.clone(), IndexAnomaly::MissingFile { split_id }))
+            }
+            // Splits created before footer offsets were recorded have empty offsets.
+            Some(num_bytes) if expected_num_bytes != 0 && num_bytes != expected_num_bytes => {
+                split_anomalies.push((
+                    split_id.clone(),
+                    IndexAnomaly::FooterMismatch {
+                        split_id,
+                        expected_num_bytes,
+                        num_bytes,
+                    },
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+    split_anomalies.sort_by(|left, right| left.0.cmp(&right.0));
+    anomalies.extend(split_anomalies.into_iter().map(|(_, anomaly)| anomaly));
+
+    // Sources whose ID starts with a dot, such as the ingest API source, are never registered.
+    for source_id in index_metadata.checkpoint.source_ids() {
+        if !source_id.starts_with('.') && !index_metadata.sources.contains_key(source_id) {
+            anomalies.push(IndexAnomaly::CheckpointWithoutSource {
+                source_id: source_id.to_string(),
+            });
+        }
+    }
+    if repair {
+        repair_index(&*metastore, &*storage, index_id, &anomalies).await?;
+    }
+    Ok(anomalies)
+}
+
+async fn repair_index(
+    metastore: &dyn Metastore,
+    storage: &dyn Storage,
+    index_id: &str,
+    anomalies: &[IndexAnomaly],
+) -> anyhow::Result<()> {
+    let mut split_ids_to_mark = Vec::new();
+    for anomaly in anomalies {
+        match anomaly {
+            IndexAnomaly::OrphanedFile { file_name, .. } => {
+                info!(index_id = %index_id, file_name = %file_name, "Deleting orphaned file.");
+                storage.delete(Path::new(file_name)).await?;
+            }
+            IndexAnomaly::MissingFile { split_id }
+            | IndexAnomaly::FooterMismatch { split_id, .. } => {
+                split_ids_to_mark.push(split_id.as_str());
+            }
+            IndexAnomaly::CheckpointWithoutSource { .. } => {}
+        }
+    }
+    if !split_ids_to_mark.is_empty() {
+        warn!(index_id = %index_id, split_ids = ?split_ids_to_mark, "Marking broken splits for deletion.");
+        metastore
+            .mark_splits_for_deletion(index_id, &split_ids_to_mark)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{SourceConfig, SourceParams};
+    use quickwit_metastore::checkpoint::CheckpointDelta;
+    use quickwit_metastore::{FileBackedMetastore, IndexMetadata, SplitMetadata};
+    use quickwit_storage::RamStorage;
+
+    use super::*;
+
+    async fn publish_split(metastore: &dyn Metastore, split_id: &str, num_bytes: u64) {
+        let split_metadata = SplitMetadata {
+            split_id: split_id.to_string(),
+            footer_offsets: num_bytes - 10..num_bytes,
+            ..Default::default()
+        };
+        metastore
+            .stage_split("test-index", split_metadata)
+            .await
+            .unwrap();
+        metastore
+            .publish_splits(
+                "test-index",
+                "test-source",
+                &[split_id],
+                CheckpointDelta::default(),
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn put_file(storage: &dyn Storage, file_name: &str, num_bytes: usize) {
+        storage
+            .put(Path::new(file_name), Box::new(vec![0u8; num_bytes]))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_index() {
+        let metastore: Arc<dyn Metastore> = Arc::new(FileBackedMetastore::for_test(Arc::new(
+            RamStorage::default(),
+        )));
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram://test-index");
+        let source_config = SourceConfig {
+            source_id: "registered-source".to_string(),
+            source_params: SourceParams::void(),
+        };
+        index_metadata
+            .checkpoint
+            .add_source(&source_config.source_id);
+        index_metadata
+            .sources
+            .insert(source_config.source_id.clone(), source_config);
+        metastore.create_index(index_metadata).await.unwrap();
+
+        publish_split(&*metastore, "split-ok", 100).await;
+        put_file(&*storage, "split-ok.split", 100).await;
+        publish_split(&*metastore, "split-missing", 100).await;
+        publish_split(&*metastore, "split-truncated", 100).await;
+        put_file(&*storage, "split-truncated.split", 50).await;
+        put_file(&*storage, "split-orphaned.split", 10).await;
+        put_file(&*storage, "unrelated.json", 10).await;
+
+        let anomalies = check_index(metastore.clone(), storage.clone(), "test-index", false)
+            .await
+            .unwrap();
+        assert_eq!(
+            anomalies,
+            [
+                IndexAnomaly::OrphanedFile {
+                    file_name: "split-orphaned.split".to_string(),
+                    file_size_in_bytes: 10
+                },
+                IndexAnomaly::MissingFile {
+                    split_id: "split-missing".to_string()
+                },
+                IndexAnomaly::FooterMismatch {
+                    split_id: "split-truncated".to_string(),
+                    expected_num_bytes: 100,
+                    num_bytes: 50
+                },
+                IndexAnomaly::CheckpointWithoutSource {
+                    source_id: "test-source".to_string()
+                },
+            ]
+        );
+
+        check_index(metastore.clone(), storage.clone(), "test-index", true)
+            .await
+            .unwrap();
+        let anomalies = check_index(metastore.clone(), storage.clone(), "test-index", false)
+            .await
+            .unwrap();
+        assert!(anomalies.iter().all(|anomaly| !anomaly.is_repairable()));
+        assert!(!storage
+            .exists(Path::new("split-orphaned.split"))
+            .await
+            .unwrap());
+        let published_splits = metastore
+            .list_splits("test-index", SplitState::Published, None, None)
+            .await
+            .unwrap();
+        assert_eq!(published_splits.len(), 1);
+        assert_eq!(published_splits[0].split_id(), "split-ok");
+    }
+}
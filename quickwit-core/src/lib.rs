@@ -25,14 +25,17 @@
 //! - `get_indexes` for getting all indexes registered in the metastore;
 //! - `reset_index` for indexing new-line delimited json documents;
 //! - `delete_index` for deleting an index;
-//! - `garbage_collect_index` for garbage collecting dangling files.
+//! - `garbage_collect_index` for garbage collecting dangling files;
+//! - `check_index` for checking the consistency of the metastore and the storage of an index.
 
 mod index;
+mod index_check;
 
 pub use index::{
     clear_cache_directory, get_cache_directory_path, remove_indexing_directory, IndexService,
     IndexServiceError,
 };
+pub use index_check::{check_index, IndexAnomaly};
 
 #[cfg(test)]
 mod tests {
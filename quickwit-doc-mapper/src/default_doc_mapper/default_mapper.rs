@@ -25,6 +25,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use tantivy::query::Query;
 use tantivy::schema::{Cardinality, Field, FieldType, Schema, STORED};
+use tantivy::tokenizer::TokenizerManager;
 use tantivy::Document;
 use tracing::info;
 
@@ -32,8 +33,14 @@ use super::field_mapping_entry::QuickwitTextTokenizer;
 use super::DefaultDocMapperBuilder;
 use crate::default_doc_mapper::mapping_tree::{build_mapping_tree, MappingNode, MappingTree};
 pub use crate::default_doc_mapper::QuickwitJsonOptions;
+use crate::geo_point::is_geo_point_field;
+use crate::ip_address::is_ip_field;
 use crate::query_builder::build_query;
 use crate::sort_by::{validate_sort_by_field_name, SortBy, SortOrder};
+use crate::tokenizers::{create_tokenizer_manager, validate_tokenizer_entries, TokenizerEntry};
+use crate::transforms::{
+    apply_field_transformers, build_field_transformers, FieldTransformEntry, FieldTransformer,
+};
 use crate::{
     DocMapper, DocParsingError, ModeType, QueryParserError, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
 };
@@ -115,6 +122,14 @@ pub struct DefaultDocMapper {
     required_fields: Vec<Field>,
     /// Defines how unmapped fields should be handle.
     mode: Mode,
+    /// Custom tokenizers defined in the doc mapping.
+    tokenizer_entries: Vec<TokenizerEntry>,
+    /// Tokenizer manager holding the built-in and custom tokenizers.
+    tokenizer_manager: TokenizerManager,
+    /// Transforms defined in the doc mapping.
+    transform_entries: Vec<FieldTransformEntry>,
+    /// Compiled transforms, applied to the documents before they are mapped.
+    field_transformers: Vec<FieldTransformer>,
 }
 
 impl DefaultDocMapper {
@@ -158,6 +173,32 @@ fn validate_tag_fields(tag_fields: &[String], schema: &Schema) -> anyhow::Result
     Ok(())
 }
 
+/// Checks that the tokenizers referenced by the text and json fields are registered
+/// in the tokenizer manager.
+fn validate_field_tokenizers(
+    schema: &Schema,
+    tokenizer_manager: &TokenizerManager,
+) -> anyhow::Result<()> {
+    for (_, field_entry) in schema.fields() {
+        let text_indexing_options_opt = match field_entry.field_type() {
+            FieldType::Str(options) => options.get_indexing_options(),
+            FieldType::JsonObject(options) => options.get_text_indexing_options(),
+            _ => None,
+        };
+        if let Some(text_indexing_options) = text_indexing_options_opt {
+            let tokenizer_name = text_indexing_options.tokenizer();
+            if tokenizer_manager.get(tokenizer_name).is_none() {
+                bail!(
+                    "Unknown tokenizer `{}` for field `{}`.",
+                    tokenizer_name,
+                    field_entry.name()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 fn list_required_fields_for_node(node: &MappingNode) -> Vec<Field> {
     node.children().flat_map(list_required_fields).collect()
 }
@@ -276,6 +317,9 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
 
     fn try_from(builder: DefaultDocMapperBuilder) -> Result<DefaultDocMapper, Self::Error> {
         let mode = builder.mode()?;
+        validate_tokenizer_entries(&builder.tokenizers)?;
+        let tokenizer_manager = create_tokenizer_manager(&builder.tokenizers)?;
+        let field_transformers = build_field_transformers(&builder.transforms)?;
         let mut schema_builder = Schema::builder();
         let field_mappings = build_mapping_tree(&builder.field_mappings, &mut schema_builder)?;
         let source_field = if builder.store_source {
@@ -292,6 +336,8 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
 
         let schema = schema_builder.build();
 
+        validate_field_tokenizers(&schema, &tokenizer_manager)?;
+
         // validate fast fields
         validate_tag_fields(&builder.tag_fields, &schema)?;
 
@@ -301,9 +347,22 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             if default_search_field_names.contains(field_name) {
                 bail!("Duplicated default search field: `{}`", field_name)
             }
-            schema
+            let field = schema
                 .get_field(field_name)
                 .with_context(|| format!("Unknown default search field: `{}`", field_name))?;
+            let field_entry = schema.get_field_entry(field);
+            if is_ip_field(field_entry) {
+                bail!(
+                    "Field `{}` of type `ip` cannot be a default search field.",
+                    field_name
+                )
+            }
+            if is_geo_point_field(field_entry) {
+                bail!(
+                    "Field `{}` of type `geo_point` cannot be a default search field.",
+                    field_name
+                )
+            }
             default_search_field_names.push(field_name.clone());
         }
 
@@ -345,6 +404,10 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             required_fields,
             demux_field_name: builder.demux_field,
             mode,
+            tokenizer_entries: builder.tokenizers,
+            tokenizer_manager,
+            transform_entries: builder.transforms,
+            field_transformers,
         })
     }
 }
@@ -374,6 +437,8 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             default_search_fields: default_doc_mapper.default_search_field_names,
             mode,
             dynamic_mapping,
+            tokenizers: default_doc_mapper.tokenizer_entries,
+            transforms: default_doc_mapper.transform_entries,
         }
     }
 }
@@ -425,15 +490,24 @@ impl DocMapper for DefaultDocMapper {
                 let doc_json_sample = doc_json.chars().take(20).collect();
                 DocParsingError::NotJsonObject(doc_json_sample)
             })?;
+        self.doc_from_json_obj(json_obj)
+    }
 
+    fn doc_from_json_obj(
+        &self,
+        mut json_obj: serde_json::Map<String, JsonValue>,
+    ) -> Result<Document, DocParsingError> {
         let mut dynamic_json_obj = serde_json::Map::default();
         let mut field_path = Vec::new();
         let mut document = Document::default();
 
+        // The source field holds the document as it was ingested, before the transforms.
         if let Some(source_field) = self.source_field {
             document.add_json_object(source_field, json_obj.clone());
         }
 
+        apply_field_transformers(&self.field_transformers, &mut json_obj)?;
+
         let mode = self.mode.mode_type();
         self.field_mappings.doc_from_json(
             json_obj,
@@ -453,6 +527,10 @@ impl DocMapper for DefaultDocMapper {
         Ok(document)
     }
 
+    fn coerce_string_values(&self, json_obj: &mut serde_json::Map<String, JsonValue>) {
+        self.field_mappings.coerce_string_values(json_obj);
+    }
+
     fn doc_to_json(
         &self,
         mut named_doc: BTreeMap<String, Vec<serde_json::Value>>,
@@ -484,7 +562,12 @@ impl DocMapper for DefaultDocMapper {
                 tantivy_default_search_field_names.push(DYNAMIC_FIELD_NAME.to_string());
             }
         }
-        build_query(split_schema, request, &tantivy_default_search_field_names)
+        build_query(
+            split_schema,
+            request,
+            &tantivy_default_search_field_names,
+            &self.tokenizer_manager,
+        )
     }
 
     fn schema(&self) -> Schema {
@@ -499,6 +582,10 @@ impl DocMapper for DefaultDocMapper {
         self.demux_field_name.clone()
     }
 
+    fn tokenizer_manager(&self) -> &TokenizerManager {
+        &self.tokenizer_manager
+    }
+
     fn sort_by(&self) -> SortBy {
         self.sort_by.clone()
     }
@@ -510,7 +597,7 @@ impl DocMapper for DefaultDocMapper {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     use quickwit_proto::SearchRequest;
     use serde_json::{self, json, Value as JsonValue};
@@ -1223,6 +1310,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_coerce_string_values() {
+        let doc_mapper = crate::default_doc_mapper_for_tests();
+        let mut json_obj = json!({
+            "timestamp": "1586960586000",
+            "body": "42",
+            "response_time": "2.5",
+            "isImportant": "true",
+            "unmapped": "12",
+            "attributes": {
+                "server": "ABC",
+                "tags": ["22", "23"]
+            }
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+        doc_mapper.coerce_string_values(&mut json_obj);
+        assert_eq!(
+            JsonValue::Object(json_obj),
+            json!({
+                "timestamp": 1586960586000i64,
+                "body": "42",
+                "response_time": 2.5,
+                "isImportant": true,
+                "unmapped": "12",
+                "attributes": {
+                    "server": "ABC",
+                    "tags": [22, 23]
+                }
+            })
+        );
+    }
+
     #[test]
     fn test_lenient_mode_simple() {
         let default_doc_mapper: DefaultDocMapper =
@@ -1441,4 +1562,202 @@ mod tests {
             r#"TermQuery(Term(type=Str, field=1, "toto"))"#
         );
     }
+
+    #[test]
+    fn test_doc_mapper_with_custom_tokenizer() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "tokenizers": [
+                {
+                    "name": "path_lowercase",
+                    "type": "regex",
+                    "pattern": "[/ ]+",
+                    "filters": [{"type": "lowercase"}]
+                }
+            ],
+            "field_mappings": [{"name": "path", "type": "text", "tokenizer": "path_lowercase"}]
+        }"#,
+        )
+        .unwrap();
+        assert!(doc_mapper
+            .tokenizer_manager()
+            .get("path_lowercase")
+            .is_some());
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "path:Var").unwrap(),
+            r#"TermQuery(Term(type=Str, field=0, "var"))"#
+        );
+        let serialized_doc_mapper = serde_json::to_string(&doc_mapper).unwrap();
+        let deserialized_doc_mapper: DefaultDocMapper =
+            serde_json::from_str(&serialized_doc_mapper).unwrap();
+        assert_eq!(
+            deserialized_doc_mapper.tokenizer_entries,
+            doc_mapper.tokenizer_entries
+        );
+        assert!(deserialized_doc_mapper
+            .tokenizer_manager()
+            .get("path_lowercase")
+            .is_some());
+    }
+
+    #[test]
+    fn test_fail_to_build_doc_mapper_with_unknown_tokenizer() {
+        let doc_mapper_result = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [{"name": "body", "type": "text", "tokenizer": "notexist"}]
+        }"#,
+        );
+        assert!(doc_mapper_result
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown tokenizer `notexist` for field `body`."));
+    }
+
+    #[test]
+    fn test_doc_mapper_cjk_query_is_analyzed_into_bigrams() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "body", "type": "text", "tokenizer": "cjk", "record": "position"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "body:検").unwrap(),
+            r#"TermQuery(Term(type=Str, field=0, "検"))"#
+        );
+        let phrase_query = default_doc_mapper_query_aux(&doc_mapper, "body:検索エ").unwrap();
+        assert!(phrase_query.starts_with("PhraseQuery"));
+        assert!(phrase_query.contains(r#""検索""#));
+        assert!(phrase_query.contains(r#""索エ""#));
+    }
+
+    #[test]
+    fn test_doc_mapper_ip_field_round_trip() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "client_ip", "type": "ip"},
+                {"name": "hops", "type": "array<ip>"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        let document = doc_mapper
+            .doc_from_json(
+                r#"{"client_ip": "10.0.0.1", "hops": ["2001:DB8::1", "192.168.0.1"]}"#.to_string(),
+            )
+            .unwrap();
+        let schema = doc_mapper.schema();
+        let client_ip_field = schema.get_field("client_ip").unwrap();
+        assert_eq!(
+            document.get_first(client_ip_field).unwrap().as_text(),
+            Some("00000000000000000000ffff0a000001")
+        );
+        let named_doc: BTreeMap<String, Vec<JsonValue>> = schema
+            .to_named_doc(&document)
+            .0
+            .into_iter()
+            .map(|(field_name, values)| {
+                let json_values = values
+                    .iter()
+                    .map(|value| serde_json::to_value(value).unwrap())
+                    .collect();
+                (field_name, json_values)
+            })
+            .collect();
+        let doc_json = doc_mapper.doc_to_json(named_doc).unwrap();
+        assert_eq!(
+            JsonValue::Object(doc_json),
+            json!({"client_ip": "10.0.0.1", "hops": ["2001:db8::1", "192.168.0.1"]})
+        );
+        let parsing_error = doc_mapper
+            .doc_from_json(r#"{"client_ip": "10.0.0.256"}"#.to_string())
+            .unwrap_err();
+        assert!(matches!(parsing_error, DocParsingError::ValueError(_, _)));
+    }
+
+    #[test]
+    fn test_doc_mapper_transforms() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "store_source": true,
+            "field_mappings": [
+                {"name": "message", "type": "text"},
+                {"name": "level", "type": "text", "tokenizer": "raw"},
+                {"name": "status", "type": "u64"}
+            ],
+            "transforms": [
+                {"field": "message", "type": "grok", "pattern": "%{LOGLEVEL:level} %{INT:status:int}"},
+                {"field": "level", "type": "lowercase"},
+                {"field": "level", "type": "default", "value": "info"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        let schema = doc_mapper.schema();
+        let document = doc_mapper
+            .doc_from_json(r#"{"message": "WARN 404"}"#.to_string())
+            .unwrap();
+        let level_field = schema.get_field("level").unwrap();
+        let status_field = schema.get_field("status").unwrap();
+        assert_eq!(
+            document.get_first(level_field).unwrap().as_text(),
+            Some("warn")
+        );
+        assert_eq!(
+            document.get_first(status_field).unwrap().as_u64(),
+            Some(404)
+        );
+        // The source holds the document before the transforms.
+        let source_field = schema.get_field(SOURCE_FIELD_NAME).unwrap();
+        assert_eq!(
+            document.get_first(source_field).unwrap().as_json(),
+            json!({"message": "WARN 404"}).as_object()
+        );
+        let document = doc_mapper
+            .doc_from_json(r#"{"message": "no match"}"#.to_string())
+            .unwrap();
+        assert_eq!(
+            document.get_first(level_field).unwrap().as_text(),
+            Some("info")
+        );
+        assert!(document.get_first(status_field).is_none());
+
+        let builder = DefaultDocMapperBuilder::from(doc_mapper);
+        assert_eq!(builder.transforms.len(), 3);
+    }
+
+    #[test]
+    fn test_doc_mapper_ip_cidr_query() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "client_ip", "type": "ip"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "client_ip:\"10.12.0.0/16\"").unwrap(),
+            r#"TermQuery(Term(type=Str, field=0, "00000000000000000000ffff0a0c"))"#
+        );
+    }
+
+    #[test]
+    fn test_fail_to_build_doc_mapper_with_ip_default_search_field() {
+        let doc_mapper_result = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "default_search_fields": ["client_ip"],
+            "field_mappings": [
+                {"name": "client_ip", "type": "ip"}
+            ]
+        }"#,
+        );
+        let error_msg = doc_mapper_result.unwrap_err().to_string();
+        assert!(
+            error_msg.contains("Field `client_ip` of type `ip` cannot be a default search field.")
+        );
+    }
 }
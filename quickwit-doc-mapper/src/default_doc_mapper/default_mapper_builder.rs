@@ -23,6 +23,8 @@ use serde::{Deserialize, Serialize};
 use super::FieldMappingEntry;
 use crate::default_doc_mapper::default_mapper::Mode;
 use crate::default_doc_mapper::QuickwitJsonOptions;
+use crate::tokenizers::TokenizerEntry;
+use crate::transforms::FieldTransformEntry;
 use crate::{DefaultDocMapper, SortByConfig};
 
 /// DefaultDocMapperBuilder is here
@@ -65,6 +67,14 @@ pub struct DefaultDocMapperBuilder {
     /// how the unmapped fields should be handled.
     #[serde(default)]
     pub dynamic_mapping: Option<QuickwitJsonOptions>,
+    /// Custom tokenizers that can be referenced by the field mappings.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tokenizers: Vec<TokenizerEntry>,
+    /// Transforms applied, in order, to the documents before they are mapped.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<FieldTransformEntry>,
 }
 
 /// `Mode` describing how the unmapped field should be handled.
@@ -132,6 +142,8 @@ mod tests {
         assert!(default_mapper_builder.sort_by.is_none());
         assert_eq!(default_mapper_builder.store_source, false);
         assert!(default_mapper_builder.timestamp_field.is_none());
+        assert!(default_mapper_builder.tokenizers.is_empty());
+        assert!(default_mapper_builder.transforms.is_empty());
     }
 
     #[test]
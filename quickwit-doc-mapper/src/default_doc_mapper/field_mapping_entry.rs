@@ -28,6 +28,7 @@ use tantivy::schema::{
 use super::{default_as_true, FieldMappingType};
 use crate::default_doc_mapper::field_mapping_type::QuickwitFieldType;
 use crate::default_doc_mapper::validate_field_mapping_name;
+use crate::tokenizers::{GEO_POINT_TOKENIZER_NAME, IP_TOKENIZER_NAME};
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct QuickwitObjectOptions {
@@ -91,14 +92,107 @@ impl Default for QuickwitNumericOptions {
     }
 }
 
+/// Options associated to an IP address field.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct QuickwitIpOptions {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "default_as_true")]
+    pub stored: bool,
+    #[serde(default = "default_as_true")]
+    pub indexed: bool,
+    #[serde(default)]
+    pub fast: bool,
+}
+
+impl Default for QuickwitIpOptions {
+    fn default() -> Self {
+        Self {
+            description: None,
+            stored: true,
+            indexed: true,
+            fast: false,
+        }
+    }
+}
+
+impl From<QuickwitIpOptions> for TextOptions {
+    fn from(quickwit_ip_options: QuickwitIpOptions) -> Self {
+        let mut text_options = TextOptions::default();
+        if quickwit_ip_options.stored {
+            text_options = text_options.set_stored();
+        }
+        if quickwit_ip_options.indexed {
+            let text_field_indexing = TextFieldIndexing::default()
+                .set_tokenizer(IP_TOKENIZER_NAME)
+                .set_index_option(IndexRecordOption::Basic);
+            text_options = text_options.set_indexing_options(text_field_indexing);
+        }
+        text_options
+    }
+}
+
+/// Options associated to a geo point field.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct QuickwitGeoPointOptions {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "default_as_true")]
+    pub stored: bool,
+    #[serde(default = "default_as_true")]
+    pub indexed: bool,
+    /// Required by the `geohash_grid` aggregation.
+    #[serde(default)]
+    pub fast: bool,
+}
+
+impl Default for QuickwitGeoPointOptions {
+    fn default() -> Self {
+        Self {
+            description: None,
+            stored: true,
+            indexed: true,
+            fast: false,
+        }
+    }
+}
+
+impl From<QuickwitGeoPointOptions> for TextOptions {
+    fn from(quickwit_geo_point_options: QuickwitGeoPointOptions) -> Self {
+        let mut text_options = TextOptions::default();
+        if quickwit_geo_point_options.stored {
+            text_options = text_options.set_stored();
+        }
+        if quickwit_geo_point_options.fast {
+            text_options = text_options.set_fast();
+        }
+        if quickwit_geo_point_options.indexed {
+            let text_field_indexing = TextFieldIndexing::default()
+                .set_tokenizer(GEO_POINT_TOKENIZER_NAME)
+                .set_index_option(IndexRecordOption::Basic);
+            text_options = text_options.set_indexing_options(text_field_indexing);
+        }
+        text_options
+    }
+}
+
+/// Tokenizer of a text or json field: either one of the built-in tokenizers or
+/// a custom tokenizer defined in the doc mapping `tokenizers`.
+///
+/// Custom tokenizer names are resolved when the doc mapper is built.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
 pub enum QuickwitTextTokenizer {
-    #[serde(rename = "raw")]
     Raw,
-    #[serde(rename = "default")]
     Default,
-    #[serde(rename = "en_stem")]
     StemEn,
+    Cjk,
+    Bigram,
+    Custom(String),
 }
 
 impl QuickwitTextTokenizer {
@@ -107,10 +201,64 @@ impl QuickwitTextTokenizer {
             QuickwitTextTokenizer::Raw => "raw",
             QuickwitTextTokenizer::Default => "default",
             QuickwitTextTokenizer::StemEn => "en_stem",
+            QuickwitTextTokenizer::Cjk => "cjk",
+            QuickwitTextTokenizer::Bigram => "bigram",
+            QuickwitTextTokenizer::Custom(tokenizer_name) => tokenizer_name,
+        }
+    }
+}
+
+impl From<String> for QuickwitTextTokenizer {
+    fn from(tokenizer_name: String) -> Self {
+        match tokenizer_name.as_str() {
+            "raw" => QuickwitTextTokenizer::Raw,
+            "default" => QuickwitTextTokenizer::Default,
+            "en_stem" => QuickwitTextTokenizer::StemEn,
+            "cjk" => QuickwitTextTokenizer::Cjk,
+            "bigram" => QuickwitTextTokenizer::Bigram,
+            _ => QuickwitTextTokenizer::Custom(tokenizer_name),
         }
     }
 }
 
+impl From<QuickwitTextTokenizer> for String {
+    fn from(tokenizer: QuickwitTextTokenizer) -> Self {
+        match tokenizer {
+            QuickwitTextTokenizer::Custom(tokenizer_name) => tokenizer_name,
+            _ => tokenizer.get_name().to_string(),
+        }
+    }
+}
+
+fn validate_tokenizer_name(tokenizer: &QuickwitTextTokenizer) -> anyhow::Result<()> {
+    if tokenizer.get_name().starts_with('_') {
+        bail!(
+            "Tokenizer `{}` is reserved for internal use.",
+            tokenizer.get_name()
+        );
+    }
+    Ok(())
+}
+
+/// Tokenizers emitting several tokens for a single word require positions to be indexed,
+/// as a multi-token query term is turned into a phrase query over these tokens.
+fn validate_tokenizer_record_option(
+    tokenizer: &QuickwitTextTokenizer,
+    record: IndexRecordOption,
+) -> anyhow::Result<()> {
+    if matches!(
+        tokenizer,
+        QuickwitTextTokenizer::Cjk | QuickwitTextTokenizer::Bigram
+    ) && !record.has_positions()
+    {
+        bail!(
+            "The `{}` tokenizer requires `record` to be set to `position`.",
+            tokenizer.get_name()
+        );
+    }
+    Ok(())
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct QuickwitTextOptions {
@@ -245,6 +393,27 @@ fn deserialize_mapping_type(
             }
             return Ok(FieldMappingType::Object(object_options));
         }
+        QuickwitFieldType::Ip(cardinality) => {
+            let ip_options: QuickwitIpOptions = serde_json::from_value(json)?;
+            // A numeric fast field cannot hold an IPv6 address: fast fields are limited to 64
+            // bits. The term ordinals of a text fast field would not help either: the IP
+            // tokenizer emits every prefix of the address, so the ordinals of a document mix
+            // the address with its 31 prefixes, which neither sorting nor aggregations handle.
+            if ip_options.fast {
+                bail!(
+                    "fast field is not supported for `ip` fields: fast fields are limited to 64 \
+                     bits, and IPv6 addresses take 128 bits."
+                );
+            }
+            return Ok(FieldMappingType::Ip(ip_options, cardinality));
+        }
+        QuickwitFieldType::GeoPoint(cardinality) => {
+            let geo_point_options: QuickwitGeoPointOptions = serde_json::from_value(json)?;
+            if geo_point_options.fast && !geo_point_options.indexed {
+                bail!("fast field requires the `geo_point` field to be indexed.");
+            }
+            return Ok(FieldMappingType::GeoPoint(geo_point_options, cardinality));
+        }
     };
     match typ {
         Type::Str => {
@@ -261,6 +430,10 @@ fn deserialize_mapping_type(
                     );
                 }
             }
+            if let Some(tokenizer) = &text_options.tokenizer {
+                validate_tokenizer_name(tokenizer)?;
+                validate_tokenizer_record_option(tokenizer, text_options.record)?;
+            }
             Ok(FieldMappingType::Text(text_options, cardinality))
         }
         Type::U64 => {
@@ -290,6 +463,10 @@ fn deserialize_mapping_type(
         }
         Type::Json => {
             let json_options: QuickwitJsonOptions = serde_json::from_value(json)?;
+            if json_options.indexed {
+                validate_tokenizer_name(&json_options.tokenizer)?;
+                validate_tokenizer_record_option(&json_options.tokenizer, json_options.record)?;
+            }
             Ok(FieldMappingType::Json(json_options, cardinality))
         }
     }
@@ -340,6 +517,8 @@ fn typed_mapping_to_json_params(
         | FieldMappingType::F64(options, _)
         | FieldMappingType::Bool(options, _) => serialize_to_map(&options),
         FieldMappingType::Json(json_options, _) => serialize_to_map(&json_options),
+        FieldMappingType::Ip(ip_options, _) => serialize_to_map(&ip_options),
+        FieldMappingType::GeoPoint(geo_point_options, _) => serialize_to_map(&geo_point_options),
         FieldMappingType::Object(object_options) => serialize_to_map(&object_options),
     }
     .unwrap()
@@ -383,13 +562,13 @@ mod tests {
         }
     "#;
 
-    const TEXT_MAPPING_ENTRY_VALUE_INVALID_TOKENIZER: &str = r#"
+    const TEXT_MAPPING_ENTRY_VALUE_CUSTOM_TOKENIZER: &str = r#"
         {
             "name": "my_field_name",
             "type": "text",
             "stored": true,
             "record": "basic",
-            "tokenizer": "notexist"
+            "tokenizer": "my_tokenizer"
         }
     "#;
 
@@ -415,16 +594,64 @@ mod tests {
     "#;
 
     #[test]
-    fn test_deserialize_invalid_text_mapping_entry() -> anyhow::Result<()> {
-        let mapping_entry =
-            serde_json::from_str::<FieldMappingEntry>(TEXT_MAPPING_ENTRY_VALUE_INVALID_TOKENIZER);
-        assert!(mapping_entry.is_err());
+    fn test_deserialize_cjk_tokenizer_requires_positions() {
+        let mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "my_field_name",
+                "type": "text",
+                "tokenizer": "cjk"
+            }
+            "#,
+        );
         assert_eq!(
             mapping_entry.unwrap_err().to_string(),
-            "Error while parsing field `my_field_name`: unknown variant `notexist`, expected one \
-             of `raw`, `default`, `en_stem`"
-                .to_string()
+            "Error while parsing field `my_field_name`: The `cjk` tokenizer requires `record` to \
+             be set to `position`."
         );
+        let mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "my_field_name",
+                "type": "json",
+                "tokenizer": "bigram",
+                "record": "position"
+            }
+            "#,
+        )
+        .unwrap();
+        match mapping_entry.mapping_type {
+            FieldMappingType::Json(options, _) => {
+                assert_eq!(options.tokenizer, QuickwitTextTokenizer::Bigram);
+            }
+            _ => panic!("wrong property type"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_custom_tokenizer_text_mapping_entry() -> anyhow::Result<()> {
+        let mapping_entry =
+            serde_json::from_str::<FieldMappingEntry>(TEXT_MAPPING_ENTRY_VALUE_CUSTOM_TOKENIZER)?;
+        match mapping_entry.mapping_type {
+            FieldMappingType::Text(options, _) => {
+                assert_eq!(
+                    options.tokenizer,
+                    Some(QuickwitTextTokenizer::Custom("my_tokenizer".to_string()))
+                );
+            }
+            _ => panic!("wrong property type"),
+        }
+        let mapping_entry_json = serde_json::to_value(&FieldMappingEntry {
+            name: "my_field_name".to_string(),
+            mapping_type: FieldMappingType::Json(
+                QuickwitJsonOptions {
+                    tokenizer: QuickwitTextTokenizer::StemEn,
+                    ..Default::default()
+                },
+                Cardinality::SingleValue,
+            ),
+        })?;
+        assert_eq!(mapping_entry_json["tokenizer"], "en_stem");
         Ok(())
     }
 
@@ -983,6 +1210,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_ip_mapping() {
+        let field_mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "client_ip",
+                "type": "ip"
+            }
+            "#,
+        )
+        .unwrap();
+        match field_mapping_entry.mapping_type {
+            FieldMappingType::Ip(options, cardinality) => {
+                assert!(options.stored);
+                assert!(options.indexed);
+                assert!(!options.fast);
+                assert_eq!(cardinality, Cardinality::SingleValue);
+            }
+            _ => panic!("wrong property type"),
+        }
+        let err = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "client_ip",
+                "type": "ip",
+                "fast": true
+            }
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error while parsing field `client_ip`: fast field is not supported for `ip` fields: \
+             fast fields are limited to 64 bits, and IPv6 addresses take 128 bits.",
+        );
+    }
+
+    #[test]
+    fn test_parse_geo_point_mapping() {
+        let field_mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "location",
+                "type": "array<geo_point>",
+                "fast": true
+            }
+            "#,
+        )
+        .unwrap();
+        match field_mapping_entry.mapping_type {
+            FieldMappingType::GeoPoint(options, cardinality) => {
+                assert!(options.stored);
+                assert!(options.indexed);
+                assert!(options.fast);
+                assert_eq!(cardinality, Cardinality::MultiValues);
+            }
+            _ => panic!("wrong property type"),
+        }
+        let err = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "location",
+                "type": "geo_point",
+                "indexed": false,
+                "fast": true
+            }
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error while parsing field `location`: fast field requires the `geo_point` field to \
+             be indexed.",
+        );
+    }
+
     #[test]
     fn test_parse_json_mapping_singlevalue() {
         let field_mapping_entry = serde_json::from_str::<FieldMappingEntry>(
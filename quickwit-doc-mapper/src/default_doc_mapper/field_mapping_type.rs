@@ -20,7 +20,8 @@
 use tantivy::schema::{Cardinality, Type};
 
 use crate::default_doc_mapper::field_mapping_entry::{
-    QuickwitJsonOptions, QuickwitNumericOptions, QuickwitObjectOptions, QuickwitTextOptions,
+    QuickwitGeoPointOptions, QuickwitIpOptions, QuickwitJsonOptions, QuickwitNumericOptions,
+    QuickwitObjectOptions, QuickwitTextOptions,
 };
 
 /// A `FieldMappingType` defines the type and indexing options
@@ -40,6 +41,10 @@ pub enum FieldMappingType {
     /// Bytes mapping type configuration.
     Bytes(QuickwitNumericOptions, Cardinality),
     Json(QuickwitJsonOptions, Cardinality),
+    /// IP address mapping type configuration.
+    Ip(QuickwitIpOptions, Cardinality),
+    /// Geo point mapping type configuration.
+    GeoPoint(QuickwitGeoPointOptions, Cardinality),
     /// Object mapping type configuration.
     Object(QuickwitObjectOptions),
 }
@@ -54,6 +59,12 @@ impl FieldMappingType {
             FieldMappingType::Bool(_, cardinality) => (Type::Bool, *cardinality),
             FieldMappingType::Bytes(_, cardinality) => (Type::Bytes, *cardinality),
             FieldMappingType::Json(_, cardinality) => (Type::Json, *cardinality),
+            FieldMappingType::Ip(_, cardinality) => {
+                return QuickwitFieldType::Ip(*cardinality);
+            }
+            FieldMappingType::GeoPoint(_, cardinality) => {
+                return QuickwitFieldType::GeoPoint(*cardinality);
+            }
             FieldMappingType::Object(_) => {
                 return QuickwitFieldType::Object;
             }
@@ -70,6 +81,10 @@ pub enum QuickwitFieldType {
     Simple(Type),
     Object,
     Array(Type),
+    /// IP addresses do not have a tantivy type of their own: they are indexed as text.
+    Ip(Cardinality),
+    /// Geo points are indexed as text too.
+    GeoPoint(Cardinality),
 }
 
 impl QuickwitFieldType {
@@ -78,12 +93,23 @@ impl QuickwitFieldType {
             QuickwitFieldType::Simple(typ) => primitive_type_to_str(typ).to_string(),
             QuickwitFieldType::Object => "object".to_string(),
             QuickwitFieldType::Array(typ) => format!("array<{}>", primitive_type_to_str(typ)),
+            QuickwitFieldType::Ip(Cardinality::SingleValue) => "ip".to_string(),
+            QuickwitFieldType::Ip(Cardinality::MultiValues) => "array<ip>".to_string(),
+            QuickwitFieldType::GeoPoint(Cardinality::SingleValue) => "geo_point".to_string(),
+            QuickwitFieldType::GeoPoint(Cardinality::MultiValues) => "array<geo_point>".to_string(),
         }
     }
 
     pub fn parse_type_id(type_str: &str) -> Option<QuickwitFieldType> {
-        if type_str == "object" {
-            return Some(QuickwitFieldType::Object);
+        match type_str {
+            "object" => return Some(QuickwitFieldType::Object),
+            "ip" => return Some(QuickwitFieldType::Ip(Cardinality::SingleValue)),
+            "array<ip>" => return Some(QuickwitFieldType::Ip(Cardinality::MultiValues)),
+            "geo_point" => return Some(QuickwitFieldType::GeoPoint(Cardinality::SingleValue)),
+            "array<geo_point>" => {
+                return Some(QuickwitFieldType::GeoPoint(Cardinality::MultiValues))
+            }
+            _ => {}
         }
         if type_str.starts_with("array<") && type_str.ends_with('>') {
             let parsed_type_str = parse_primitive_type(&type_str[6..type_str.len() - 1])?;
@@ -126,7 +152,7 @@ fn primitive_type_to_str(primitive_type: &Type) -> &'static str {
 
 #[cfg(test)]
 mod tests {
-    use tantivy::schema::Type;
+    use tantivy::schema::{Cardinality, Type};
 
     use super::QuickwitFieldType;
 
@@ -145,5 +171,18 @@ mod tests {
         test_parse_type_aux("object", Some(QuickwitFieldType::Object));
         test_parse_type_aux("object2", None);
         test_parse_type_aux("bool", Some(QuickwitFieldType::Simple(Type::Bool)));
+        test_parse_type_aux("ip", Some(QuickwitFieldType::Ip(Cardinality::SingleValue)));
+        test_parse_type_aux(
+            "array<ip>",
+            Some(QuickwitFieldType::Ip(Cardinality::MultiValues)),
+        );
+        test_parse_type_aux(
+            "geo_point",
+            Some(QuickwitFieldType::GeoPoint(Cardinality::SingleValue)),
+        );
+        test_parse_type_aux(
+            "array<geo_point>",
+            Some(QuickwitFieldType::GeoPoint(Cardinality::MultiValues)),
+        );
     }
 }
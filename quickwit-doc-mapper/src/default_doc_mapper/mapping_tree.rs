@@ -30,9 +30,12 @@ use tantivy::schema::{
 use tantivy::Document;
 
 use crate::default_doc_mapper::field_mapping_entry::{
-    QuickwitNumericOptions, QuickwitObjectOptions, QuickwitTextOptions,
+    QuickwitGeoPointOptions, QuickwitIpOptions, QuickwitNumericOptions, QuickwitObjectOptions,
+    QuickwitTextOptions,
 };
 use crate::default_doc_mapper::{FieldMappingType, QuickwitJsonOptions};
+use crate::geo_point::{geo_point_json_to_term_text, term_text_to_geo_point_json};
+use crate::ip_address::{ip_str_to_term_text, term_text_to_ip_str};
 use crate::{DocParsingError, FieldMappingEntry, ModeType};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -54,12 +57,14 @@ pub enum LeafType {
     Bool(QuickwitNumericOptions),
     Bytes(QuickwitNumericOptions),
     Json(QuickwitJsonOptions),
+    Ip(QuickwitIpOptions),
+    GeoPoint(QuickwitGeoPointOptions),
 }
 
 impl LeafType {
     fn json_type(&self) -> JsonType {
         match self {
-            LeafType::Text(_) => JsonType::String,
+            LeafType::Text(_) | LeafType::Ip(_) | LeafType::GeoPoint(_) => JsonType::String,
             LeafType::I64(_) | LeafType::U64(_) | LeafType::F64(_) => JsonType::Number,
             LeafType::Bool(_) => JsonType::Bool,
             LeafType::Bytes(_) => JsonType::String,
@@ -76,6 +81,57 @@ impl LeafType {
             | LeafType::Bool(opt)
             | LeafType::Bytes(opt) => opt.fast,
             LeafType::Json(_) => false,
+            LeafType::Ip(_) => false,
+            // Geo points are stored in a text fast field, which does not require a value.
+            LeafType::GeoPoint(_) => false,
+        }
+    }
+
+    /// Converts a value read from the doc store into the value returned in the hits.
+    fn json_val_for_display(&self, json_val: JsonValue) -> JsonValue {
+        match (self, json_val) {
+            (LeafType::Ip(_), JsonValue::String(term_text)) => {
+                let ip_str = term_text_to_ip_str(&term_text).unwrap_or(term_text);
+                JsonValue::String(ip_str)
+            }
+            (LeafType::GeoPoint(_), JsonValue::String(term_text)) => {
+                term_text_to_geo_point_json(&term_text).unwrap_or(JsonValue::String(term_text))
+            }
+            (LeafType::Ip(_) | LeafType::GeoPoint(_), JsonValue::Array(json_vals)) => {
+                JsonValue::Array(
+                    json_vals
+                        .into_iter()
+                        .map(|json_val| self.json_val_for_display(json_val))
+                        .collect(),
+                )
+            }
+            (_, json_val) => json_val,
+        }
+    }
+
+    /// Converts a string value into the JSON type expected by the leaf, if possible.
+    ///
+    /// Values that cannot be converted are left untouched so that `value_from_json`
+    /// reports the error.
+    fn coerce_string_value(&self, json_val: &mut JsonValue) {
+        let text = if let JsonValue::String(text) = json_val {
+            text.trim()
+        } else {
+            return;
+        };
+        let coerced_json_val_opt = match self {
+            LeafType::I64(_) => text.parse::<i64>().ok().map(JsonValue::from),
+            LeafType::U64(_) => text.parse::<u64>().ok().map(JsonValue::from),
+            LeafType::F64(_) => text
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(JsonValue::Number),
+            LeafType::Bool(_) => text.parse::<bool>().ok().map(JsonValue::Bool),
+            _ => None,
+        };
+        if let Some(coerced_json_val) = coerced_json_val_opt {
+            *json_val = coerced_json_val;
         }
     }
 
@@ -116,6 +172,19 @@ impl LeafType {
                     Err(format!("Expected JSON object  got '{}'.", json_val))
                 }
             }
+            LeafType::Ip(_) => {
+                if let JsonValue::String(ip_str) = json_val {
+                    let term_text = ip_str_to_term_text(&ip_str).map_err(|err| err.to_string())?;
+                    Ok(Value::Str(term_text))
+                } else {
+                    Err(format!("Expected IP address string, got '{}'.", json_val))
+                }
+            }
+            LeafType::GeoPoint(_) => {
+                let term_text =
+                    geo_point_json_to_term_text(&json_val).map_err(|err| err.to_string())?;
+                Ok(Value::Str(term_text))
+            }
         }
     }
 }
@@ -163,6 +232,16 @@ impl MappingLeaf {
         Ok(())
     }
 
+    fn coerce_string_values(&self, json_val: &mut JsonValue) {
+        if let JsonValue::Array(els) = json_val {
+            for el_json_val in els {
+                self.typ.coerce_string_value(el_json_val);
+            }
+            return;
+        }
+        self.typ.coerce_string_value(json_val);
+    }
+
     fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<JsonValue>>,
@@ -172,7 +251,8 @@ impl MappingLeaf {
         let json_type = self.typ.json_type();
         if let Some(json_val) = extract_json_val(json_type, named_doc, field_path, self.cardinality)
         {
-            insert_json_val(field_path, json_val, doc_json);
+            let displayed_json_val = self.typ.json_val_for_display(json_val);
+            insert_json_val(field_path, displayed_json_val, doc_json);
         }
     }
 
@@ -362,6 +442,17 @@ impl MappingNode {
         Ok(())
     }
 
+    /// Converts the string values of `json_obj` into the types of the mapped fields.
+    ///
+    /// Unmapped fields are left untouched.
+    pub fn coerce_string_values(&self, json_obj: &mut serde_json::Map<String, JsonValue>) {
+        for (field_name, json_val) in json_obj.iter_mut() {
+            if let Some(child_tree) = self.branches.get(field_name) {
+                child_tree.coerce_string_values(json_val);
+            }
+        }
+    }
+
     pub fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<JsonValue>>,
@@ -397,6 +488,8 @@ impl From<MappingLeaf> for FieldMappingType {
             LeafType::Bool(opt) => FieldMappingType::Bool(opt, leaf.cardinality),
             LeafType::Bytes(opt) => FieldMappingType::Bytes(opt, leaf.cardinality),
             LeafType::Json(opt) => FieldMappingType::Json(opt, leaf.cardinality),
+            LeafType::Ip(opt) => FieldMappingType::Ip(opt, leaf.cardinality),
+            LeafType::GeoPoint(opt) => FieldMappingType::GeoPoint(opt, leaf.cardinality),
         }
     }
 }
@@ -439,6 +532,17 @@ impl MappingTree {
         }
     }
 
+    fn coerce_string_values(&self, json_val: &mut JsonValue) {
+        match self {
+            MappingTree::Leaf(mapping_leaf) => mapping_leaf.coerce_string_values(json_val),
+            MappingTree::Node(mapping_node) => {
+                if let JsonValue::Object(json_obj) = json_val {
+                    mapping_node.coerce_string_values(json_obj);
+                }
+            }
+        }
+    }
+
     fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<JsonValue>>,
@@ -610,6 +714,24 @@ fn build_mapping_from_field_type<'a>(
                 cardinality: *cardinality,
             }))
         }
+        FieldMappingType::Ip(options, cardinality) => {
+            let text_options = TextOptions::from(options.clone());
+            let field = schema_builder.add_text_field(&field_name, text_options);
+            Ok(MappingTree::Leaf(MappingLeaf {
+                field,
+                typ: LeafType::Ip(options.clone()),
+                cardinality: *cardinality,
+            }))
+        }
+        FieldMappingType::GeoPoint(options, cardinality) => {
+            let text_options = TextOptions::from(options.clone());
+            let field = schema_builder.add_text_field(&field_name, text_options);
+            Ok(MappingTree::Leaf(MappingLeaf {
+                field,
+                typ: LeafType::GeoPoint(options.clone()),
+                cardinality: *cardinality,
+            }))
+        }
         FieldMappingType::Object(entries) => {
             let mapping_node = build_mapping_tree_from_entries(
                 &entries.field_mappings,
@@ -623,7 +745,7 @@ fn build_mapping_from_field_type<'a>(
 
 #[cfg(test)]
 mod tests {
-    use serde_json::json;
+    use serde_json::{json, Value as JsonValue};
     use tantivy::schema::{Cardinality, Field, Value};
     use tantivy::Document;
 
@@ -797,6 +919,53 @@ mod tests {
         assert_eq!(&values, &[true, false, true])
     }
 
+    #[test]
+    fn test_coerce_string_values() {
+        let assert_coerce = |typ: LeafType, json_val: JsonValue, expected_json_val: JsonValue| {
+            let leaf_entry = MappingLeaf {
+                field: Field::from_field_id(10),
+                typ,
+                cardinality: Cardinality::MultiValues,
+            };
+            let mut coerced_json_val = json_val;
+            leaf_entry.coerce_string_values(&mut coerced_json_val);
+            assert_eq!(coerced_json_val, expected_json_val);
+        };
+        let numeric_opts = QuickwitNumericOptions::default();
+        assert_coerce(
+            LeafType::I64(numeric_opts.clone()),
+            json!("-12"),
+            json!(-12),
+        );
+        assert_coerce(
+            LeafType::U64(numeric_opts.clone()),
+            json!(" 12 "),
+            json!(12),
+        );
+        assert_coerce(
+            LeafType::F64(numeric_opts.clone()),
+            json!("0.5"),
+            json!(0.5),
+        );
+        assert_coerce(
+            LeafType::Bool(numeric_opts.clone()),
+            json!(["true", "false"]),
+            json!([true, false]),
+        );
+        // Values that cannot be converted are left as is.
+        assert_coerce(
+            LeafType::U64(numeric_opts.clone()),
+            json!("-12"),
+            json!("-12"),
+        );
+        assert_coerce(LeafType::I64(numeric_opts), json!(12), json!(12));
+        assert_coerce(
+            LeafType::Text(QuickwitTextOptions::default()),
+            json!("12"),
+            json!("12"),
+        );
+    }
+
     #[test]
     fn test_parse_i64_mutivalued() {
         let typ = LeafType::I64(QuickwitNumericOptions::default());
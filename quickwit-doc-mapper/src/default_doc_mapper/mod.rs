@@ -30,7 +30,8 @@ use regex::Regex;
 pub use self::default_mapper::{DefaultDocMapper, SortByConfig};
 pub use self::default_mapper_builder::{DefaultDocMapperBuilder, ModeType};
 pub use self::field_mapping_entry::{
-    FieldMappingEntry, QuickwitJsonOptions, QuickwitNumericOptions, QuickwitTextOptions,
+    FieldMappingEntry, QuickwitGeoPointOptions, QuickwitIpOptions, QuickwitJsonOptions,
+    QuickwitNumericOptions, QuickwitTextOptions,
 };
 pub use self::field_mapping_type::FieldMappingType;
 
@@ -25,9 +25,10 @@ use quickwit_proto::SearchRequest;
 use serde_json::Value as JsonValue;
 use tantivy::query::Query;
 use tantivy::schema::{Field, Schema};
+use tantivy::tokenizer::TokenizerManager;
 use tantivy::Document;
 
-use crate::{DocParsingError, QueryParserError, SortBy};
+use crate::{DocParsingError, QueryParserError, SortBy, QUICKWIT_TOKENIZER_MANAGER};
 
 /// The `DocMapper` trait defines the way of defining how a (json) document,
 /// and the fields it contains, are stored and indexed.
@@ -44,6 +45,20 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     /// (we pass by value here, as the value can be used as is in the _source field.)
     fn doc_from_json(&self, doc_json: String) -> Result<Document, DocParsingError>;
 
+    /// Returns the document built from a JSON object that has already been parsed, e.g. to
+    /// coerce its values with [`DocMapper::coerce_string_values`].
+    fn doc_from_json_obj(
+        &self,
+        json_obj: serde_json::Map<String, JsonValue>,
+    ) -> Result<Document, DocParsingError>;
+
+    /// Converts the string values of a JSON object into the types of the fields they are
+    /// mapped to, e.g. `"42"` into `42` for an `i64` field.
+    ///
+    /// This is used for documents converted from untyped input formats such as CSV or logfmt.
+    /// Values that cannot be converted are left as is.
+    fn coerce_string_values(&self, _json_obj: &mut serde_json::Map<String, JsonValue>) {}
+
     /// Converts a tantivy named Document to the json format.
     ///
     /// Tantivy does not have any notion of cardinality nor object.
@@ -102,6 +117,12 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     fn demux_field_name(&self) -> Option<String> {
         None
     }
+
+    /// Returns the tokenizer manager holding the text analyzers used to
+    /// index documents and to analyze queries.
+    fn tokenizer_manager(&self) -> &TokenizerManager {
+        &QUICKWIT_TOKENIZER_MANAGER
+    }
 }
 
 clone_trait_object!(DocMapper);
@@ -206,6 +227,7 @@ mod tests {
             sort_order: None,
             sort_by_field: None,
             aggregation_request: None,
+            timeout_millis: None,
         };
         let query = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -242,6 +264,7 @@ mod tests {
             sort_order: None,
             sort_by_field: Some("text_field".to_string()),
             aggregation_request: None,
+            timeout_millis: None,
         };
         let query = doc_mapper.query(schema, &search_request).unwrap_err();
         assert_eq!(
@@ -276,6 +299,7 @@ mod tests {
             sort_order: None,
             sort_by_field: None,
             aggregation_request: None,
+            timeout_millis: None,
         };
         let query = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -310,6 +334,7 @@ mod tests {
             sort_order: None,
             sort_by_field: None,
             aggregation_request: None,
+            timeout_millis: None,
         };
         let query = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -0,0 +1,647 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Geo points are indexed as text, using the `lat,lon` representation and
+//! a dedicated tokenizer.
+//!
+//! The tokenizer emits all the prefixes of the geohash of the point, up to
+//! [`GEOHASH_MAX_PRECISION`] characters. Bounding-box and distance queries are
+//! expressed as the set of geohash cells covering the queried area. Cells crossing
+//! the border of the area are refined until the number of cells gets too large, so
+//! matches are approximated by the size of the smallest cells at the border. On fast
+//! fields, the matches are then filtered exactly at the leaf, see
+//! [`GeoPointFilterQuery`](crate::geo_point_query::GeoPointFilterQuery).
+
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Context};
+use serde_json::Value as JsonValue;
+use tantivy::schema::{FieldEntry, FieldType};
+
+use crate::tokenizers::GEO_POINT_TOKENIZER_NAME;
+
+/// Maximum length of the indexed geohashes. A cell of this precision is about
+/// 37mm x 19mm.
+pub const GEOHASH_MAX_PRECISION: usize = 12;
+
+/// Maximum number of cells examined when refining the cells covering a queried area.
+const MAX_COVERING_CANDIDATE_CELLS: usize = 4096;
+
+const GEOHASH_BASE32_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Mean Earth radius in meters.
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    fn new(lat: f64, lon: f64) -> anyhow::Result<GeoPoint> {
+        if !(-90.0..=90.0).contains(&lat) {
+            bail!("Latitude `{}` is out of the [-90, 90] range.", lat);
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            bail!("Longitude `{}` is out of the [-180, 180] range.", lon);
+        }
+        Ok(GeoPoint { lat, lon })
+    }
+
+    /// Returns the great-circle distance between two points, in meters.
+    fn distance_meters(&self, other: &GeoPoint) -> f64 {
+        let delta_lat = (other.lat - self.lat).to_radians();
+        let delta_lon = (other.lon - self.lon).to_radians();
+        let haversine = (delta_lat / 2.0).sin().powi(2)
+            + self.lat.to_radians().cos()
+                * other.lat.to_radians().cos()
+                * (delta_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_METERS * haversine.sqrt().min(1.0).asin()
+    }
+
+    /// Returns the geohash of the point, `precision` characters long.
+    fn geohash(&self, precision: usize) -> String {
+        let mut lat_range = (-90.0, 90.0);
+        let mut lon_range = (-180.0, 180.0);
+        let mut geohash = String::with_capacity(precision);
+        let mut is_lon_bit = true;
+        for _ in 0..precision {
+            let mut char_idx = 0;
+            for _ in 0..5 {
+                let (value, range) = if is_lon_bit {
+                    (self.lon, &mut lon_range)
+                } else {
+                    (self.lat, &mut lat_range)
+                };
+                let mid = (range.0 + range.1) / 2.0;
+                char_idx <<= 1;
+                if value >= mid {
+                    char_idx |= 1;
+                    range.0 = mid;
+                } else {
+                    range.1 = mid;
+                }
+                is_lon_bit = !is_lon_bit;
+            }
+            geohash.push(GEOHASH_BASE32_ALPHABET[char_idx] as char);
+        }
+        geohash
+    }
+}
+
+/// Tells if the field is a `geo_point` field, i.e. a text field indexed with the geo point
+/// tokenizer.
+pub fn is_geo_point_field(field_entry: &FieldEntry) -> bool {
+    if let FieldType::Str(text_options) = field_entry.field_type() {
+        if let Some(text_indexing_options) = text_options.get_indexing_options() {
+            return text_indexing_options.tokenizer() == GEO_POINT_TOKENIZER_NAME;
+        }
+    }
+    false
+}
+
+/// Parses a `lat,lon` string.
+pub(crate) fn parse_geo_point_str(geo_point_str: &str) -> anyhow::Result<GeoPoint> {
+    let (lat_str, lon_str) = geo_point_str
+        .split_once(',')
+        .with_context(|| format!("Invalid geo point `{}`.", geo_point_str))?;
+    let lat: f64 = lat_str
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid latitude in geo point `{}`.", geo_point_str))?;
+    let lon: f64 = lon_str
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid longitude in geo point `{}`.", geo_point_str))?;
+    GeoPoint::new(lat, lon)
+}
+
+/// Returns the representation of a geo point, as indexed and stored, of a JSON value which
+/// is either an object `{"lat": 40.7, "lon": -74.0}` or a string `"40.7,-74.0"`.
+pub(crate) fn geo_point_json_to_term_text(json_val: &JsonValue) -> anyhow::Result<String> {
+    let geo_point = match json_val {
+        JsonValue::String(geo_point_str) => parse_geo_point_str(geo_point_str)?,
+        JsonValue::Object(json_obj) => {
+            let lat = json_obj
+                .get("lat")
+                .and_then(JsonValue::as_f64)
+                .context("Expected a number for `lat`.")?;
+            let lon = json_obj
+                .get("lon")
+                .and_then(JsonValue::as_f64)
+                .context("Expected a number for `lon`.")?;
+            GeoPoint::new(lat, lon)?
+        }
+        _ => bail!(
+            "Expected geo point object or `lat,lon` string, got `{}`.",
+            json_val
+        ),
+    };
+    Ok(format!("{},{}", geo_point.lat, geo_point.lon))
+}
+
+/// Returns the geo point object `{"lat": .., "lon": ..}` of a stored representation.
+pub(crate) fn term_text_to_geo_point_json(term_text: &str) -> Option<JsonValue> {
+    let geo_point = parse_geo_point_str(term_text).ok()?;
+    Some(serde_json::json!({"lat": geo_point.lat, "lon": geo_point.lon}))
+}
+
+/// Returns all the prefixes of the geohash of a stored representation.
+pub(crate) fn term_text_to_geohashes(term_text: &str) -> Option<Vec<String>> {
+    let geohash = parse_geo_point_str(term_text)
+        .ok()?
+        .geohash(GEOHASH_MAX_PRECISION);
+    let geohashes = (1..=GEOHASH_MAX_PRECISION)
+        .map(|precision| geohash[..precision].to_string())
+        .collect();
+    Some(geohashes)
+}
+
+/// The area described by a geo query, and the geohash cells covering it.
+pub(crate) struct GeoQuery {
+    pub geo_shape: GeoShape,
+    pub covering_geohashes: BTreeSet<String>,
+}
+
+/// Parses a geo query:
+/// - `bbox(top_lat,left_lon,bottom_lat,right_lon)`, a bounding box given by its top left and bottom
+///   right corners;
+/// - `distance(lat,lon,radius)`, the points within `radius` (`500m`, `10km`, `3mi`) of the point.
+pub(crate) fn parse_geo_query(geo_query: &str) -> anyhow::Result<GeoQuery> {
+    let geo_shape = parse_geo_shape(geo_query)?;
+    let covering_geohashes = covering_geohashes(&geo_shape);
+    Ok(GeoQuery {
+        geo_shape,
+        covering_geohashes,
+    })
+}
+
+/// Returns the center of the cell of a geohash of [`GEOHASH_MAX_PRECISION`] characters, which
+/// is the indexed point up to a few centimeters.
+pub(crate) fn geohash_to_geo_point(geohash: &str) -> GeoPoint {
+    let cell = geohash_cell(geohash);
+    GeoPoint {
+        lat: (cell.bottom + cell.top) / 2.0,
+        lon: (cell.left + cell.right) / 2.0,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GeoBoundingBox {
+    top: f64,
+    left: f64,
+    bottom: f64,
+    right: f64,
+}
+
+impl GeoBoundingBox {
+    fn contains(&self, point: &GeoPoint) -> bool {
+        (self.bottom..=self.top).contains(&point.lat)
+            && (self.left..=self.right).contains(&point.lon)
+    }
+
+    fn relation(&self, cell: &GeoBoundingBox) -> CellRelation {
+        // The top and right borders of a cell are excluded from it.
+        if cell.bottom > self.top
+            || cell.top <= self.bottom
+            || cell.left > self.right
+            || cell.right <= self.left
+        {
+            return CellRelation::Disjoint;
+        }
+        if cell.bottom >= self.bottom
+            && cell.top <= self.top
+            && cell.left >= self.left
+            && cell.right <= self.right
+        {
+            return CellRelation::Within;
+        }
+        CellRelation::Intersects
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CellRelation {
+    Disjoint,
+    Intersects,
+    Within,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum GeoShape {
+    /// Bounding boxes crossing the antimeridian are split in two.
+    BoundingBoxes(Vec<GeoBoundingBox>),
+    Circle {
+        center: GeoPoint,
+        radius_meters: f64,
+    },
+}
+
+impl GeoShape {
+    /// Tells if the point is within the shape.
+    pub(crate) fn contains(&self, point: &GeoPoint) -> bool {
+        match self {
+            GeoShape::BoundingBoxes(bboxes) => bboxes.iter().any(|bbox| bbox.contains(point)),
+            GeoShape::Circle {
+                center,
+                radius_meters,
+            } => center.distance_meters(point) <= *radius_meters,
+        }
+    }
+
+    fn relation(&self, cell: &GeoBoundingBox) -> CellRelation {
+        match self {
+            GeoShape::BoundingBoxes(bboxes) => {
+                let mut relation = CellRelation::Disjoint;
+                for bbox in bboxes {
+                    match bbox.relation(cell) {
+                        CellRelation::Within => return CellRelation::Within,
+                        CellRelation::Intersects => relation = CellRelation::Intersects,
+                        CellRelation::Disjoint => {}
+                    }
+                }
+                relation
+            }
+            GeoShape::Circle {
+                center,
+                radius_meters,
+            } => {
+                if closest_distance_meters(center, cell) > *radius_meters {
+                    return CellRelation::Disjoint;
+                }
+                let mid_lat = (cell.bottom + cell.top) / 2.0;
+                let mid_lon = (cell.left + cell.right) / 2.0;
+                let is_within = [cell.bottom, mid_lat, cell.top].iter().all(|&lat| {
+                    [cell.left, mid_lon, cell.right].iter().all(|&lon| {
+                        center.distance_meters(&GeoPoint { lat, lon }) <= *radius_meters
+                    })
+                });
+                if is_within {
+                    CellRelation::Within
+                } else {
+                    CellRelation::Intersects
+                }
+            }
+        }
+    }
+}
+
+/// Returns the distance between a point and the closest point of a cell, in meters.
+fn closest_distance_meters(point: &GeoPoint, cell: &GeoBoundingBox) -> f64 {
+    if (cell.left..=cell.right).contains(&point.lon) {
+        // The closest point of a parallel lies on the meridian of the point.
+        let closest_point = GeoPoint {
+            lat: point.lat.clamp(cell.bottom, cell.top),
+            lon: point.lon,
+        };
+        return point.distance_meters(&closest_point);
+    }
+    [cell.left, cell.right]
+        .iter()
+        .map(|&meridian_lon| {
+            let closest_point = GeoPoint {
+                lat: closest_lat_on_meridian(point, meridian_lon).clamp(cell.bottom, cell.top),
+                lon: meridian_lon,
+            };
+            point.distance_meters(&closest_point)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Returns the latitude of the point of a meridian which is the closest to `point`.
+fn closest_lat_on_meridian(point: &GeoPoint, meridian_lon: f64) -> f64 {
+    let delta_lon_cos = (meridian_lon - point.lon).to_radians().cos();
+    if delta_lon_cos <= 0.0 {
+        // The meridian is on the other side of the globe: the closest point is a pole.
+        return if point.lat >= 0.0 { 90.0 } else { -90.0 };
+    }
+    point
+        .lat
+        .to_radians()
+        .tan()
+        .atan2(delta_lon_cos)
+        .to_degrees()
+}
+
+fn parse_geo_shape(geo_query: &str) -> anyhow::Result<GeoShape> {
+    let geo_query = geo_query.trim();
+    let (function_name, args_str) = geo_query
+        .strip_suffix(')')
+        .and_then(|query| query.split_once('('))
+        .with_context(|| {
+            format!(
+                "Invalid geo query `{}`: expected `bbox(..)` or `distance(..)`.",
+                geo_query
+            )
+        })?;
+    let args: Vec<&str> = args_str.split(',').map(str::trim).collect();
+    match (function_name.trim(), args.as_slice()) {
+        ("bbox", [top_str, left_str, bottom_str, right_str]) => {
+            let top_left = parse_geo_point_str(&format!("{},{}", top_str, left_str))?;
+            let bottom_right = parse_geo_point_str(&format!("{},{}", bottom_str, right_str))?;
+            if top_left.lat < bottom_right.lat {
+                bail!(
+                    "Invalid geo query `{}`: the top latitude must be greater or equal to the \
+                     bottom latitude.",
+                    geo_query
+                );
+            }
+            let bbox = GeoBoundingBox {
+                top: top_left.lat,
+                left: top_left.lon,
+                bottom: bottom_right.lat,
+                right: bottom_right.lon,
+            };
+            let bboxes = if bbox.left <= bbox.right {
+                vec![bbox]
+            } else {
+                vec![
+                    GeoBoundingBox {
+                        right: 180.0,
+                        ..bbox
+                    },
+                    GeoBoundingBox {
+                        left: -180.0,
+                        ..bbox
+                    },
+                ]
+            };
+            Ok(GeoShape::BoundingBoxes(bboxes))
+        }
+        ("distance", [lat_str, lon_str, radius_str]) => {
+            let center = parse_geo_point_str(&format!("{},{}", lat_str, lon_str))?;
+            let radius_meters = parse_distance_meters(radius_str)?;
+            Ok(GeoShape::Circle {
+                center,
+                radius_meters,
+            })
+        }
+        _ => bail!(
+            "Invalid geo query `{}`: expected `bbox(top_lat,left_lon,bottom_lat,right_lon)` or \
+             `distance(lat,lon,radius)`.",
+            geo_query
+        ),
+    }
+}
+
+/// Parses a distance with its unit (`m`, `km` or `mi`), and returns it in meters.
+fn parse_distance_meters(distance_str: &str) -> anyhow::Result<f64> {
+    let (value_str, unit_meters) = if let Some(value_str) = distance_str.strip_suffix("km") {
+        (value_str, 1_000.0)
+    } else if let Some(value_str) = distance_str.strip_suffix("mi") {
+        (value_str, 1_609.344)
+    } else if let Some(value_str) = distance_str.strip_suffix('m') {
+        (value_str, 1.0)
+    } else {
+        bail!(
+            "Invalid distance `{}`: expected a unit among `m`, `km` and `mi`.",
+            distance_str
+        );
+    };
+    let value: f64 = value_str
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid distance `{}`.", distance_str))?;
+    if !value.is_finite() || value < 0.0 {
+        bail!(
+            "Invalid distance `{}`: expected a positive value.",
+            distance_str
+        );
+    }
+    Ok(value * unit_meters)
+}
+
+/// Returns the bounding box of a geohash cell.
+fn geohash_cell(geohash: &str) -> GeoBoundingBox {
+    let mut lat_range = (-90.0, 90.0);
+    let mut lon_range = (-180.0, 180.0);
+    let mut is_lon_bit = true;
+    for geohash_byte in geohash.bytes() {
+        let char_idx = GEOHASH_BASE32_ALPHABET
+            .iter()
+            .position(|&alphabet_byte| alphabet_byte == geohash_byte)
+            .unwrap_or(0);
+        for bit_pos in (0..5).rev() {
+            let range = if is_lon_bit {
+                &mut lon_range
+            } else {
+                &mut lat_range
+            };
+            let mid = (range.0 + range.1) / 2.0;
+            if (char_idx >> bit_pos) & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            is_lon_bit = !is_lon_bit;
+        }
+    }
+    GeoBoundingBox {
+        top: lat_range.1,
+        left: lon_range.0,
+        bottom: lat_range.0,
+        right: lon_range.1,
+    }
+}
+
+/// Returns the geohash cells covering the shape.
+///
+/// The cells crossing the border of the shape are split into their 32 sub-cells, level by
+/// level, as long as the number of sub-cells to examine stays reasonable.
+fn covering_geohashes(geo_shape: &GeoShape) -> BTreeSet<String> {
+    let mut covering_geohashes = BTreeSet::new();
+    let mut candidate_geohashes: Vec<String> = vec![String::new()];
+    for precision in 1..=GEOHASH_MAX_PRECISION {
+        let mut border_geohashes = Vec::new();
+        for candidate_geohash in &candidate_geohashes {
+            for &geohash_byte in GEOHASH_BASE32_ALPHABET {
+                let geohash = format!("{}{}", candidate_geohash, geohash_byte as char);
+                match geo_shape.relation(&geohash_cell(&geohash)) {
+                    CellRelation::Within => {
+                        covering_geohashes.insert(geohash);
+                    }
+                    CellRelation::Intersects => border_geohashes.push(geohash),
+                    CellRelation::Disjoint => {}
+                }
+            }
+        }
+        if precision == GEOHASH_MAX_PRECISION
+            || border_geohashes.len() * GEOHASH_BASE32_ALPHABET.len() > MAX_COVERING_CANDIDATE_CELLS
+        {
+            covering_geohashes.extend(border_geohashes);
+            break;
+        }
+        candidate_geohashes = border_geohashes;
+    }
+    covering_geohashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geohash() {
+        let geo_point = GeoPoint::new(57.64911, 10.40744).unwrap();
+        assert_eq!(geo_point.geohash(11), "u4pruydqqvj");
+        let cell = geohash_cell("u4pruydqqvj");
+        assert!(cell.bottom <= 57.64911 && 57.64911 <= cell.top);
+        assert!(cell.left <= 10.40744 && 10.40744 <= cell.right);
+        assert_eq!(
+            geohash_cell("s"),
+            GeoBoundingBox {
+                top: 45.0,
+                left: 0.0,
+                bottom: 0.0,
+                right: 45.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_geo_point_json_to_term_text() {
+        assert_eq!(
+            geo_point_json_to_term_text(&serde_json::json!({"lat": 40.7, "lon": -74.0})).unwrap(),
+            "40.7,-74"
+        );
+        assert_eq!(
+            geo_point_json_to_term_text(&serde_json::json!(" 40.7 , -74.0")).unwrap(),
+            "40.7,-74"
+        );
+        assert!(geo_point_json_to_term_text(&serde_json::json!("91.0,0.0")).is_err());
+        assert!(geo_point_json_to_term_text(&serde_json::json!({"lat": 40.7})).is_err());
+        assert!(geo_point_json_to_term_text(&serde_json::json!(40.7)).is_err());
+        assert_eq!(
+            term_text_to_geo_point_json("40.7,-74").unwrap(),
+            serde_json::json!({"lat": 40.7, "lon": -74.0})
+        );
+        let geohashes = term_text_to_geohashes("57.64911,10.40744").unwrap();
+        assert_eq!(geohashes.len(), GEOHASH_MAX_PRECISION);
+        assert_eq!(geohashes[0], "u");
+        assert_eq!(geohashes[4], "u4pru");
+    }
+
+    #[test]
+    fn test_distance() {
+        let paris = GeoPoint::new(48.8566, 2.3522).unwrap();
+        let london = GeoPoint::new(51.5074, -0.1278).unwrap();
+        let distance_km = paris.distance_meters(&london) / 1_000.0;
+        assert!((340.0..345.0).contains(&distance_km));
+        assert_eq!(parse_distance_meters("10km").unwrap(), 10_000.0);
+        assert_eq!(parse_distance_meters("250m").unwrap(), 250.0);
+        assert_eq!(parse_distance_meters("1mi").unwrap(), 1_609.344);
+        assert!(parse_distance_meters("10").is_err());
+        assert!(parse_distance_meters("-1km").is_err());
+    }
+
+    #[test]
+    fn test_parse_geo_shape() {
+        assert_eq!(
+            parse_geo_shape("bbox(41.0, -74.5, 40.0, -73.5)").unwrap(),
+            GeoShape::BoundingBoxes(vec![GeoBoundingBox {
+                top: 41.0,
+                left: -74.5,
+                bottom: 40.0,
+                right: -73.5
+            }])
+        );
+        // Crossing the antimeridian.
+        assert!(matches!(
+            parse_geo_shape("bbox(10,170,-10,-170)").unwrap(),
+            GeoShape::BoundingBoxes(bboxes) if bboxes.len() == 2
+        ));
+        assert!(parse_geo_shape("bbox(40.0,-74.5,41.0,-73.5)").is_err());
+        assert!(parse_geo_shape("bbox(41.0,-74.5)").is_err());
+        assert!(parse_geo_shape("distance(40.7,-74.0)").is_err());
+        assert!(parse_geo_shape("circle(40.7,-74.0,1km)").is_err());
+        assert_eq!(
+            parse_geo_shape("distance(40.7,-74.0,1km)").unwrap(),
+            GeoShape::Circle {
+                center: GeoPoint {
+                    lat: 40.7,
+                    lon: -74.0
+                },
+                radius_meters: 1_000.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_geo_query_bbox() {
+        // The bounding box of the `s` cell contains this whole cell, and the border of the
+        // neighboring cells.
+        let term_texts = parse_geo_query("bbox(45, 0, 0, 45)")
+            .unwrap()
+            .covering_geohashes;
+        assert!(term_texts.contains("s"));
+        assert!(term_texts
+            .iter()
+            .all(|term_text| term_text == "s" || term_text.len() > 1));
+        let term_texts = parse_geo_query("bbox(41.0,-74.5,40.0,-73.5)")
+            .unwrap()
+            .covering_geohashes;
+        let inside_geohash = GeoPoint::new(40.7128, -74.006).unwrap().geohash(12);
+        let outside_geohash = GeoPoint::new(42.0, -74.006).unwrap().geohash(12);
+        let is_covered = |geohash: &str| {
+            term_texts
+                .iter()
+                .any(|term_text| geohash.starts_with(term_text.as_str()))
+        };
+        assert!(is_covered(&inside_geohash));
+        assert!(!is_covered(&outside_geohash));
+    }
+
+    #[test]
+    fn test_parse_geo_query_distance() {
+        let term_texts = parse_geo_query("distance(40.7128,-74.006,10km)")
+            .unwrap()
+            .covering_geohashes;
+        let is_covered = |lat: f64, lon: f64| {
+            let geohash = GeoPoint::new(lat, lon).unwrap().geohash(12);
+            term_texts
+                .iter()
+                .any(|term_text| geohash.starts_with(term_text.as_str()))
+        };
+        // Times Square, about 6km away.
+        assert!(is_covered(40.758, -73.9855));
+        // JFK airport, about 20km away.
+        assert!(!is_covered(40.6413, -73.7781));
+    }
+
+    #[test]
+    fn test_geo_shape_contains() {
+        let geo_shape = parse_geo_shape("bbox(10,170,-10,-170)").unwrap();
+        assert!(geo_shape.contains(&GeoPoint::new(0.0, 175.0).unwrap()));
+        assert!(geo_shape.contains(&GeoPoint::new(0.0, -175.0).unwrap()));
+        assert!(!geo_shape.contains(&GeoPoint::new(0.0, 0.0).unwrap()));
+        assert!(!geo_shape.contains(&GeoPoint::new(11.0, 175.0).unwrap()));
+
+        let geo_shape = parse_geo_shape("distance(40.7128,-74.006,10km)").unwrap();
+        assert!(geo_shape.contains(&GeoPoint::new(40.758, -73.9855).unwrap()));
+        assert!(!geo_shape.contains(&GeoPoint::new(40.6413, -73.7781).unwrap()));
+
+        let geohash = GeoPoint::new(57.64911, 10.40744)
+            .unwrap()
+            .geohash(GEOHASH_MAX_PRECISION);
+        let geo_point = geohash_to_geo_point(&geohash);
+        assert!((geo_point.lat - 57.64911).abs() < 1e-6);
+        assert!((geo_point.lon - 10.40744).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use tantivy::fastfield::MultiValuedFastFieldReader;
+use tantivy::query::{Explanation, Query, Scorer, Weight};
+use tantivy::schema::{Field, Term};
+use tantivy::{
+    DocId, DocSet, InvertedIndexReader, Score, Searcher, SegmentReader, TantivyError, TERMINATED,
+};
+
+use crate::geo_point::{geohash_to_geo_point, GeoShape, GEOHASH_MAX_PRECISION};
+
+/// Filters the documents matching the geohash cells covering a geo query, and keeps the ones
+/// with a point within the queried area.
+///
+/// The points of the documents are read from the `geo_point` fast field, which holds the
+/// ordinals of the geohashes of the points, and from the term dictionary of the field. Both must
+/// be warmed up before the search.
+#[derive(Clone, Debug)]
+pub(crate) struct GeoPointFilterQuery {
+    field: Field,
+    geo_shape: Arc<GeoShape>,
+    cells_query: Box<dyn Query>,
+}
+
+impl GeoPointFilterQuery {
+    pub fn new(field: Field, geo_shape: GeoShape, cells_query: Box<dyn Query>) -> Self {
+        GeoPointFilterQuery {
+            field,
+            geo_shape: Arc::new(geo_shape),
+            cells_query,
+        }
+    }
+}
+
+impl Query for GeoPointFilterQuery {
+    fn weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+    ) -> tantivy::Result<Box<dyn Weight>> {
+        let cells_weight = self.cells_query.weight(searcher, scoring_enabled)?;
+        Ok(Box::new(GeoPointFilterWeight {
+            field: self.field,
+            geo_shape: self.geo_shape.clone(),
+            cells_weight,
+        }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        self.cells_query.query_terms(terms);
+    }
+}
+
+struct GeoPointFilterWeight {
+    field: Field,
+    geo_shape: Arc<GeoShape>,
+    cells_weight: Box<dyn Weight>,
+}
+
+impl Weight for GeoPointFilterWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let cells_scorer = self.cells_weight.scorer(reader, boost)?;
+        let mut scorer = GeoPointFilterScorer {
+            cells_scorer,
+            geo_shape: self.geo_shape.clone(),
+            ordinals_reader: reader.fast_fields().u64s_lenient(self.field)?,
+            inverted_index: reader.inverted_index(self.field)?,
+            ordinal_matches: HashMap::new(),
+            ordinals_buffer: Vec::new(),
+        };
+        // Like the other scorers, the scorer starts positioned on its first document.
+        if scorer.doc() != TERMINATED && !scorer.matches(scorer.doc()) {
+            scorer.advance();
+        }
+        Ok(Box::new(scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({}) does not match",
+                doc
+            )));
+        }
+        self.cells_weight.explain(reader, doc)
+    }
+}
+
+struct GeoPointFilterScorer {
+    cells_scorer: Box<dyn Scorer>,
+    geo_shape: Arc<GeoShape>,
+    ordinals_reader: MultiValuedFastFieldReader<u64>,
+    inverted_index: Arc<InvertedIndexReader>,
+    /// Whether the point of the ordinals already seen is within the queried area. The ordinals
+    /// of the geohash prefixes map to `false`.
+    ordinal_matches: HashMap<u64, bool>,
+    ordinals_buffer: Vec<u64>,
+}
+
+impl GeoPointFilterScorer {
+    fn matches(&mut self, doc: DocId) -> bool {
+        self.ordinals_reader
+            .get_vals(doc, &mut self.ordinals_buffer);
+        let geo_shape = &self.geo_shape;
+        let inverted_index = &self.inverted_index;
+        self.ordinals_buffer.iter().any(|ordinal| {
+            *self.ordinal_matches.entry(*ordinal).or_insert_with(|| {
+                let mut geohash_bytes = Vec::new();
+                match inverted_index
+                    .terms()
+                    .ord_to_term(*ordinal, &mut geohash_bytes)
+                {
+                    Ok(true) if geohash_bytes.len() == GEOHASH_MAX_PRECISION => {
+                        std::str::from_utf8(&geohash_bytes)
+                            .map(|geohash| geo_shape.contains(&geohash_to_geo_point(geohash)))
+                            .unwrap_or(false)
+                    }
+                    _ => false,
+                }
+            })
+        })
+    }
+}
+
+impl DocSet for GeoPointFilterScorer {
+    fn advance(&mut self) -> DocId {
+        loop {
+            let doc = self.cells_scorer.advance();
+            if doc == TERMINATED || self.matches(doc) {
+                return doc;
+            }
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.cells_scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.cells_scorer.size_hint()
+    }
+}
+
+impl Scorer for GeoPointFilterScorer {
+    fn score(&mut self) -> Score {
+        self.cells_scorer.score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::Count;
+    use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
+    use tantivy::schema::{IndexRecordOption, Schema, Term, TextOptions};
+    use tantivy::{doc, Index};
+
+    use super::GeoPointFilterQuery;
+    use crate::default_doc_mapper::QuickwitGeoPointOptions;
+    use crate::geo_point::parse_geo_query;
+    use crate::QUICKWIT_TOKENIZER_MANAGER;
+
+    #[test]
+    fn test_geo_point_filter_query() -> tantivy::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let geo_point_options = QuickwitGeoPointOptions {
+            fast: true,
+            ..Default::default()
+        };
+        let field = schema_builder.add_text_field("location", TextOptions::from(geo_point_options));
+        let mut index = Index::create_in_ram(schema_builder.build());
+        index.set_tokenizers(QUICKWIT_TOKENIZER_MANAGER.clone());
+        let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+        index_writer.add_document(doc!(field => "40.5,-74.0"))?;
+        index_writer.add_document(doc!(field => "40.9999,-74.0"))?;
+        // About 50m north of the queried bounding box.
+        index_writer.add_document(doc!(field => "41.0005,-74.0"))?;
+        index_writer.add_document(doc!(field => "48.8566,2.3522"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let geo_query = parse_geo_query("bbox(41.0,-74.5,40.0,-73.5)").unwrap();
+        let cell_queries: Vec<(Occur, Box<dyn Query>)> = geo_query
+            .covering_geohashes
+            .iter()
+            .map(|geohash| {
+                let term = Term::from_field_text(field, geohash);
+                let term_query: Box<dyn Query> =
+                    Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+                (Occur::Should, term_query)
+            })
+            .collect();
+        let cells_query = BooleanQuery::new(cell_queries);
+        let num_cell_matches = searcher.search(&cells_query, &Count)?;
+        assert!((2..=3).contains(&num_cell_matches));
+
+        let geo_point_filter_query =
+            GeoPointFilterQuery::new(field, geo_query.geo_shape, Box::new(cells_query));
+        assert_eq!(searcher.search(&geo_point_filter_query, &Count)?, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tracing::warn;
+
+/// Format of the text records fed to the ingestion paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// One JSON object per line, the default.
+    Json,
+    /// Comma-separated values, starting with a header row naming the fields.
+    Csv,
+    /// `key=value` pairs separated by spaces, one record per line.
+    Logfmt,
+}
+
+impl Default for InputFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+impl InputFormat {
+    /// Returns the name of the input format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputFormat::Json => "json",
+            InputFormat::Csv => "csv",
+            InputFormat::Logfmt => "logfmt",
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(input_format_str: &str) -> anyhow::Result<Self> {
+        match input_format_str {
+            "json" => Ok(InputFormat::Json),
+            "csv" => Ok(InputFormat::Csv),
+            "logfmt" => Ok(InputFormat::Logfmt),
+            _ => bail!(
+                "Unknown input format `{}`. Supported formats are `json`, `csv`, and `logfmt`.",
+                input_format_str
+            ),
+        }
+    }
+}
+
+/// Converts the lines of a stream of text records into JSON documents.
+///
+/// The values of the converted documents are all strings: they are meant to be coerced into
+/// the types of the doc mapping with [`crate::DocMapper::coerce_string_values`]. Keys containing
+/// dots, such as `attributes.server`, are expanded into nested objects.
+///
+/// A converter is stateful: for CSV, the first record fed to the converter is the header row, and
+/// a record whose quoted values contain newlines spans several lines. One converter must therefore
+/// be used per file or per request, fed with the lines of the stream, newlines included, and
+/// finished with [`TextRecordConverter::finish`] at the end of the stream.
+#[derive(Debug)]
+pub struct TextRecordConverter {
+    input_format: InputFormat,
+    /// CSV header row, or the error raised parsing it, in which case the records of the stream
+    /// cannot be converted.
+    csv_header_opt: Option<Result<Vec<String>, String>>,
+    /// First lines of the CSV record being read, which has a quoted value not terminated yet.
+    pending_csv_record: String,
+    num_conversion_errors: u64,
+    first_conversion_error_opt: Option<anyhow::Error>,
+}
+
+impl TextRecordConverter {
+    /// Creates a converter for the given input format.
+    pub fn new(input_format: InputFormat) -> Self {
+        Self {
+            input_format,
+            csv_header_opt: None,
+            pending_csv_record: String::new(),
+            num_conversion_errors: 0,
+            first_conversion_error_opt: None,
+        }
+    }
+
+    /// Returns the input format of the converter.
+    pub fn input_format(&self) -> InputFormat {
+        self.input_format
+    }
+
+    /// Returns whether the converter holds the first lines of a record spanning several lines,
+    /// in which case the stream must not be cut before the next lines are fed.
+    pub fn is_within_record(&self) -> bool {
+        !self.pending_csv_record.is_empty()
+    }
+
+    /// Converts a line into a JSON document.
+    ///
+    /// Returns `None` for lines that do not complete a record, i.e. the CSV header row, blank
+    /// lines and the first lines of a record spanning several lines. Records that cannot be
+    /// converted are returned as is, so that they are reported as parse errors downstream.
+    pub fn convert_line(&mut self, line: String) -> Option<String> {
+        match self.input_format {
+            InputFormat::Json => Some(line),
+            InputFormat::Csv => {
+                if self.pending_csv_record.is_empty() && line.trim().is_empty() {
+                    return None;
+                }
+                self.pending_csv_record.push_str(&line);
+                // Quotes inside quoted values are escaped by doubling them, so a record is
+                // complete once it holds an even number of quotes.
+                if self.pending_csv_record.matches('"').count() % 2 == 1 {
+                    return None;
+                }
+                let record = std::mem::take(&mut self.pending_csv_record);
+                self.convert_record(record)
+            }
+            InputFormat::Logfmt if line.trim().is_empty() => None,
+            InputFormat::Logfmt => self.convert_record(line),
+        }
+    }
+
+    /// Converts the record left incomplete at the end of the stream, if any, i.e. a CSV record
+    /// with an unterminated quoted value, and logs the number of records of the stream that could
+    /// not be converted.
+    pub fn finish(&mut self) -> Option<String> {
+        let doc_opt = if self.pending_csv_record.is_empty() {
+            None
+        } else {
+            let record = std::mem::take(&mut self.pending_csv_record);
+            self.convert_record(record)
+        };
+        if let Some(first_conversion_error) = self.first_conversion_error_opt.take() {
+            warn!(
+                input_format = %self.input_format,
+                num_errors = self.num_conversion_errors,
+                first_error = ?first_conversion_error,
+                "Failed to convert records to JSON."
+            );
+            self.num_conversion_errors = 0;
+        }
+        doc_opt
+    }
+
+    fn convert_record(&mut self, record: String) -> Option<String> {
+        let json_obj_res = match self.input_format {
+            InputFormat::Json => return Some(record),
+            InputFormat::Csv => match &self.csv_header_opt {
+                Some(Ok(csv_header)) => csv_record_to_json(csv_header, &record),
+                Some(Err(header_error)) => Err(anyhow!("Invalid CSV header row: {}", header_error)),
+                None => {
+                    let csv_header_res =
+                        parse_csv_record(&record).map_err(|error| error.to_string());
+                    if let Err(error) = &csv_header_res {
+                        warn!(error = %error, "Failed to parse CSV header row.");
+                    }
+                    self.csv_header_opt = Some(csv_header_res);
+                    return None;
+                }
+            },
+            InputFormat::Logfmt => logfmt_record_to_json(&record),
+        };
+        match json_obj_res {
+            Ok(json_obj) => Some(JsonValue::Object(json_obj).to_string()),
+            Err(error) => {
+                // The errors are logged once per stream rather than once per record.
+                self.num_conversion_errors += 1;
+                if self.first_conversion_error_opt.is_none() {
+                    self.first_conversion_error_opt = Some(error);
+                }
+                Some(record)
+            }
+        }
+    }
+}
+
+fn parse_csv_record(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(line.as_bytes());
+    let mut record = csv::StringRecord::new();
+    csv_reader.read_record(&mut record)?;
+    let fields = record
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect();
+    Ok(fields)
+}
+
+fn csv_record_to_json(
+    csv_header: &[String],
+    line: &str,
+) -> anyhow::Result<serde_json::Map<String, JsonValue>> {
+    let fields = parse_csv_record(line)?;
+    if fields.len() != csv_header.len() {
+        bail!(
+            "Expected {} fields as in the header row, got {}.",
+            csv_header.len(),
+            fields.len()
+        );
+    }
+    let mut json_obj = serde_json::Map::new();
+    for (key, value) in csv_header.iter().zip(fields) {
+        // Empty CSV values are treated as missing values.
+        if !value.is_empty() {
+            insert_string_value(&mut json_obj, key, value)?;
+        }
+    }
+    Ok(json_obj)
+}
+
+fn logfmt_record_to_json(line: &str) -> anyhow::Result<serde_json::Map<String, JsonValue>> {
+    let mut json_obj = serde_json::Map::new();
+    let mut chars = line.trim().chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if key.is_empty() {
+            bail!("Expected a key, got `{}`.", chars.collect::<String>());
+        }
+        let value = if chars.next_if_eq(&'=').is_some() {
+            if chars.next_if_eq(&'"').is_some() {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            let escaped_char = chars
+                                .next()
+                                .with_context(|| format!("Unterminated value for key `{key}`."))?;
+                            value.push(escaped_char);
+                        }
+                        Some(c) => value.push(c),
+                        None => bail!("Unterminated value for key `{}`.", key),
+                    }
+                }
+                value
+            } else {
+                let mut value = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    value.push(c);
+                }
+                value
+            }
+        } else {
+            // A key without a value is a boolean flag.
+            "true".to_string()
+        };
+        insert_string_value(&mut json_obj, &key, value)?;
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+    Ok(json_obj)
+}
+
+/// Inserts a string value at the path described by a dotted key.
+fn insert_string_value(
+    json_obj: &mut serde_json::Map<String, JsonValue>,
+    key: &str,
+    value: String,
+) -> anyhow::Result<()> {
+    let mut key_parts: Vec<&str> = key.split('.').collect();
+    let leaf_key = key_parts
+        .pop()
+        .expect("Split should yield at least one part.");
+    let mut current_obj = json_obj;
+
+    for key_part in key_parts {
+        let child_json_val = current_obj
+            .entry(key_part.to_string())
+            .or_insert_with(|| JsonValue::Object(Default::default()));
+        current_obj = if let JsonValue::Object(child_obj) = child_json_val {
+            child_obj
+        } else {
+            bail!("Key `{}` conflicts with another key of the record.", key);
+        };
+    }
+    if current_obj.contains_key(leaf_key) {
+        bail!("Key `{}` conflicts with another key of the record.", key);
+    }
+    current_obj.insert(leaf_key.to_string(), JsonValue::String(value));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn convert_lines(input_format: InputFormat, lines: &[&str]) -> Vec<JsonValue> {
+        let mut converter = TextRecordConverter::new(input_format);
+        let mut docs: Vec<String> = lines
+            .iter()
+            .flat_map(|line| converter.convert_line(format!("{}\n", line)))
+            .collect();
+        docs.extend(converter.finish());
+        docs.into_iter()
+            .map(|doc_json| {
+                serde_json::from_str(&doc_json)
+                    .unwrap_or_else(|_| JsonValue::String(doc_json.trim_end().to_string()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_input_format_from_str() {
+        assert_eq!("json".parse::<InputFormat>().unwrap(), InputFormat::Json);
+        assert_eq!("csv".parse::<InputFormat>().unwrap(), InputFormat::Csv);
+        assert_eq!(
+            "logfmt".parse::<InputFormat>().unwrap(),
+            InputFormat::Logfmt
+        );
+        assert!("xml".parse::<InputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_convert_json_lines() {
+        let mut converter = TextRecordConverter::new(InputFormat::Json);
+        assert_eq!(
+            converter.convert_line(r#"{"body": "foo"}"#.to_string()),
+            Some(r#"{"body": "foo"}"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_convert_csv_lines() {
+        let docs = convert_lines(
+            InputFormat::Csv,
+            &[
+                "timestamp, body, attributes.server",
+                "1, foo, ABC",
+                "",
+                "2,\"bar, baz\",",
+                "3,\"quoted \"\"value\"\"\",DEF",
+                "4,too,many,fields",
+                "5,\"multi",
+                "",
+                "line\",GHI",
+                "6,\"unterminated",
+            ],
+        );
+        assert_eq!(
+            docs,
+            vec![
+                json!({"timestamp": "1", "body": "foo", "attributes": {"server": "ABC"}}),
+                json!({"timestamp": "2", "body": "bar, baz"}),
+                json!({
+                    "timestamp": "3",
+                    "body": "quoted \"value\"",
+                    "attributes": {"server": "DEF"}
+                }),
+                json!("4,too,many,fields"),
+                json!({
+                    "timestamp": "5",
+                    "body": "multi\n\nline",
+                    "attributes": {"server": "GHI"}
+                }),
+                json!("6,\"unterminated"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_logfmt_lines() {
+        let docs = convert_lines(
+            InputFormat::Logfmt,
+            &[
+                r#"ts=1 level=info msg="hello \"world\"" attributes.server=ABC debug"#,
+                "  ",
+                "ts=2   msg= level=warn",
+                r#"ts=3 msg="unterminated"#,
+                "ts=4 ts.nested=5",
+            ],
+        );
+        assert_eq!(
+            docs,
+            vec![
+                json!({
+                    "ts": "1",
+                    "level": "info",
+                    "msg": "hello \"world\"",
+                    "attributes": {"server": "ABC"},
+                    "debug": "true"
+                }),
+                json!({"ts": "2", "msg": "", "level": "warn"}),
+                json!(r#"ts=3 msg="unterminated"#),
+                json!("ts=4 ts.nested=5"),
+            ]
+        );
+    }
+}
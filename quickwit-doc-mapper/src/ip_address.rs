@@ -0,0 +1,283 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! IP addresses are indexed as text, using a canonical representation and
+//! a dedicated tokenizer.
+//!
+//! IPv4 addresses are mapped to IPv6 addresses (`::ffff:a.b.c.d`), and every address
+//! is represented as the 32 hexadecimal digits of its 128 bits. The tokenizer emits
+//! all the prefixes of this representation, so that exact match, CIDR and range
+//! queries can all be expressed as a set of term queries.
+
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+
+use anyhow::{bail, Context};
+use tantivy::schema::{FieldEntry, FieldType};
+
+use crate::tokenizers::IP_TOKENIZER_NAME;
+
+/// Number of hexadecimal digits (nibbles) of the canonical representation.
+pub(crate) const NUM_IP_NIBBLES: usize = 32;
+
+/// Range of the IPv4-mapped IPv6 addresses.
+const IPV4_MAPPED_RANGE: RangeInclusive<u128> = 0xffff_0000_0000..=0xffff_ffff_ffff;
+
+fn ip_addr_to_u128(ip_addr: IpAddr) -> u128 {
+    match ip_addr {
+        IpAddr::V4(ipv4_addr) => u128::from(ipv4_addr.to_ipv6_mapped()),
+        IpAddr::V6(ipv6_addr) => u128::from(ipv6_addr),
+    }
+}
+
+fn u128_to_ip_addr(ip_u128: u128) -> IpAddr {
+    if IPV4_MAPPED_RANGE.contains(&ip_u128) {
+        return IpAddr::V4(Ipv4Addr::from(ip_u128 as u32));
+    }
+    IpAddr::V6(Ipv6Addr::from(ip_u128))
+}
+
+fn parse_ip_addr(ip_str: &str) -> anyhow::Result<IpAddr> {
+    ip_str
+        .trim()
+        .parse::<IpAddr>()
+        .with_context(|| format!("Invalid IP address `{}`.", ip_str))
+}
+
+/// Tells if the field is an `ip` field, i.e. a text field indexed with the IP tokenizer.
+pub(crate) fn is_ip_field(field_entry: &FieldEntry) -> bool {
+    if let FieldType::Str(text_options) = field_entry.field_type() {
+        if let Some(text_indexing_options) = text_options.get_indexing_options() {
+            return text_indexing_options.tokenizer() == IP_TOKENIZER_NAME;
+        }
+    }
+    false
+}
+
+/// Returns the canonical representation of an IP address, as indexed and stored.
+pub(crate) fn ip_str_to_term_text(ip_str: &str) -> anyhow::Result<String> {
+    let ip_addr = parse_ip_addr(ip_str)?;
+    Ok(format!("{:032x}", ip_addr_to_u128(ip_addr)))
+}
+
+/// Returns the IP address, formatted for display, of a canonical representation.
+pub(crate) fn term_text_to_ip_str(term_text: &str) -> Option<String> {
+    if term_text.len() != NUM_IP_NIBBLES {
+        return None;
+    }
+    let ip_u128 = u128::from_str_radix(term_text, 16).ok()?;
+    Some(u128_to_ip_addr(ip_u128).to_string())
+}
+
+/// Returns the terms matching the IP addresses of a CIDR block (`10.12.0.0/16`) or,
+/// if the query does not contain a `/`, matching a single IP address.
+pub(crate) fn ip_query_to_term_texts(ip_query: &str) -> anyhow::Result<BTreeSet<String>> {
+    let (ip_str, prefix_len_str_opt) = match ip_query.split_once('/') {
+        Some((ip_str, prefix_len_str)) => (ip_str, Some(prefix_len_str)),
+        None => (ip_query, None),
+    };
+    let ip_addr = parse_ip_addr(ip_str)?;
+    let max_prefix_len = if ip_addr.is_ipv4() { 32 } else { 128 };
+    let prefix_len = if let Some(prefix_len_str) = prefix_len_str_opt {
+        let prefix_len: u32 = prefix_len_str
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CIDR block `{}`.", ip_query))?;
+        if prefix_len > max_prefix_len {
+            bail!(
+                "Invalid CIDR block `{}`: prefix length must be lower or equal to {}.",
+                ip_query,
+                max_prefix_len
+            );
+        }
+        prefix_len
+    } else {
+        max_prefix_len
+    };
+    // IPv4-mapped addresses share a 96 bits prefix.
+    let mapped_prefix_len = prefix_len + 128 - max_prefix_len;
+    let network = ip_addr_to_u128(ip_addr) & prefix_mask(mapped_prefix_len);
+    let mut term_texts = BTreeSet::new();
+    add_block_term_texts(network, mapped_prefix_len, &mut term_texts);
+    Ok(term_texts)
+}
+
+/// Returns the terms matching the IP addresses within `[lower, upper]`. Unbounded sides
+/// are `None`.
+///
+/// If one of the bounds is an IPv4 address, the range is restricted to IPv4 addresses.
+pub(crate) fn ip_range_to_term_texts(
+    lower_opt: Option<(&str, bool)>,
+    upper_opt: Option<(&str, bool)>,
+) -> anyhow::Result<BTreeSet<String>> {
+    let lower_ip_opt = lower_opt
+        .map(|(ip_str, inclusive)| parse_ip_addr(ip_str).map(|ip_addr| (ip_addr, inclusive)))
+        .transpose()?;
+    let upper_ip_opt = upper_opt
+        .map(|(ip_str, inclusive)| parse_ip_addr(ip_str).map(|ip_addr| (ip_addr, inclusive)))
+        .transpose()?;
+    let is_ipv4_range = lower_ip_opt
+        .iter()
+        .chain(upper_ip_opt.iter())
+        .any(|(ip_addr, _)| ip_addr.is_ipv4());
+    let (mut start, mut end) = if is_ipv4_range {
+        (*IPV4_MAPPED_RANGE.start(), *IPV4_MAPPED_RANGE.end())
+    } else {
+        (u128::MIN, u128::MAX)
+    };
+    let mut term_texts = BTreeSet::new();
+    if let Some((lower_ip, inclusive)) = lower_ip_opt {
+        start = ip_addr_to_u128(lower_ip);
+        if !inclusive {
+            start = match start.checked_add(1) {
+                Some(start) => start,
+                None => return Ok(term_texts),
+            };
+        }
+    }
+    if let Some((upper_ip, inclusive)) = upper_ip_opt {
+        end = ip_addr_to_u128(upper_ip);
+        if !inclusive {
+            end = match end.checked_sub(1) {
+                Some(end) => end,
+                None => return Ok(term_texts),
+            };
+        }
+    }
+    if start > end {
+        return Ok(term_texts);
+    }
+    // Decomposes the range into the largest aligned CIDR blocks it contains.
+    loop {
+        let mut block_size_log2 = start.trailing_zeros();
+        while start | block_mask(block_size_log2) > end {
+            block_size_log2 -= 1;
+        }
+        add_block_term_texts(start, 128 - block_size_log2, &mut term_texts);
+        let block_last = start | block_mask(block_size_log2);
+        if block_last >= end {
+            break;
+        }
+        start = block_last + 1;
+    }
+    Ok(term_texts)
+}
+
+/// Returns the mask of the `prefix_len` highest bits.
+fn prefix_mask(prefix_len: u32) -> u128 {
+    !block_mask(128 - prefix_len)
+}
+
+/// Returns the mask of the `block_size_log2` lowest bits.
+fn block_mask(block_size_log2: u32) -> u128 {
+    if block_size_log2 == 128 {
+        u128::MAX
+    } else {
+        (1u128 << block_size_log2) - 1
+    }
+}
+
+/// Adds the terms matching the CIDR block `network/prefix_len` to `term_texts`.
+///
+/// Only prefixes made of whole nibbles are indexed. If `prefix_len` is not a multiple of 4,
+/// the block is split into the (at most 8) blocks of the next indexed prefix length.
+fn add_block_term_texts(network: u128, prefix_len: u32, term_texts: &mut BTreeSet<String>) {
+    let indexed_prefix_len = ((prefix_len + 3) / 4 * 4).max(4);
+    let num_sub_blocks = 1u128 << (indexed_prefix_len - prefix_len);
+    let num_prefix_nibbles = indexed_prefix_len as usize / 4;
+    for sub_block_ord in 0..num_sub_blocks {
+        let sub_network = network | (sub_block_ord << (128 - indexed_prefix_len));
+        let mut term_text = format!("{:032x}", sub_network);
+        term_text.truncate(num_prefix_nibbles);
+        term_texts.insert(term_text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_term_text_round_trip() {
+        let term_text = ip_str_to_term_text("10.12.1.2").unwrap();
+        assert_eq!(term_text, "00000000000000000000ffff0a0c0102");
+        assert_eq!(term_text_to_ip_str(&term_text).unwrap(), "10.12.1.2");
+
+        let term_text = ip_str_to_term_text("2001:db8::1").unwrap();
+        assert_eq!(term_text, "20010db8000000000000000000000001");
+        assert_eq!(term_text_to_ip_str(&term_text).unwrap(), "2001:db8::1");
+
+        assert!(ip_str_to_term_text("10.12.1").is_err());
+        assert!(term_text_to_ip_str("0a0c").is_none());
+    }
+
+    #[test]
+    fn test_ip_query_to_term_texts() {
+        assert_eq!(
+            ip_query_to_term_texts("10.12.1.2").unwrap(),
+            BTreeSet::from(["00000000000000000000ffff0a0c0102".to_string()])
+        );
+        assert_eq!(
+            ip_query_to_term_texts("10.12.0.0/16").unwrap(),
+            BTreeSet::from(["00000000000000000000ffff0a0c".to_string()])
+        );
+        // The prefix is not a whole number of nibbles: the block is split in two.
+        assert_eq!(
+            ip_query_to_term_texts("10.12.0.0/15").unwrap(),
+            BTreeSet::from([
+                "00000000000000000000ffff0a0c".to_string(),
+                "00000000000000000000ffff0a0d".to_string()
+            ])
+        );
+        assert_eq!(
+            ip_query_to_term_texts("2001:db8::/32").unwrap(),
+            BTreeSet::from(["20010db8".to_string()])
+        );
+        assert_eq!(ip_query_to_term_texts("::/0").unwrap().len(), 16);
+        assert!(ip_query_to_term_texts("10.12.0.0/33").is_err());
+        assert!(ip_query_to_term_texts("10.12.0.0/a").is_err());
+    }
+
+    #[test]
+    fn test_ip_range_to_term_texts() {
+        assert_eq!(
+            ip_range_to_term_texts(Some(("10.0.0.0", true)), Some(("10.0.0.31", true))).unwrap(),
+            BTreeSet::from([
+                "00000000000000000000ffff0a00000".to_string(),
+                "00000000000000000000ffff0a00001".to_string()
+            ])
+        );
+        assert_eq!(
+            ip_range_to_term_texts(Some(("10.0.0.0", false)), Some(("10.0.0.2", false))).unwrap(),
+            BTreeSet::from(["00000000000000000000ffff0a000001".to_string()])
+        );
+        assert!(
+            ip_range_to_term_texts(Some(("10.0.0.2", true)), Some(("10.0.0.1", true)))
+                .unwrap()
+                .is_empty()
+        );
+        // The range is restricted to IPv4 addresses.
+        assert_eq!(
+            ip_range_to_term_texts(Some(("255.255.255.0", true)), None).unwrap(),
+            BTreeSet::from(["00000000000000000000ffffffffff".to_string()])
+        );
+        assert_eq!(ip_range_to_term_texts(None, None).unwrap().len(), 16);
+    }
+}
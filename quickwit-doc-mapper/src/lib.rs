@@ -27,9 +27,14 @@
 mod default_doc_mapper;
 mod doc_mapper;
 mod error;
+mod geo_point;
+mod geo_point_query;
+mod input_format;
+mod ip_address;
 mod query_builder;
 mod sort_by;
 mod tokenizers;
+mod transforms;
 
 /// Pruning tags manipulation.
 pub mod tag_pruning;
@@ -40,8 +45,14 @@ pub use default_doc_mapper::{
 };
 pub use doc_mapper::DocMapper;
 pub use error::{DocParsingError, QueryParserError};
+pub use geo_point::{is_geo_point_field, GEOHASH_MAX_PRECISION};
+pub use input_format::{InputFormat, TextRecordConverter};
 pub use sort_by::{SortBy, SortByField, SortOrder};
-pub use tokenizers::QUICKWIT_TOKENIZER_MANAGER;
+pub use tokenizers::{
+    NgramOptions, StemmerLanguage, TokenFilterType, TokenizerConfig, TokenizerEntry, TokenizerType,
+    QUICKWIT_TOKENIZER_MANAGER,
+};
+pub use transforms::{FieldTransform, FieldTransformEntry};
 
 /// Field name reserved for storing the source document.
 pub const SOURCE_FIELD_NAME: &str = "_source";
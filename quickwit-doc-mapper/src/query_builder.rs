@@ -17,19 +17,31 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeSet;
+
 use quickwit_proto::SearchRequest;
-use tantivy::query::{Query, QueryParser, QueryParserError as TantivyQueryParserError};
-use tantivy::schema::{Field, Schema};
-use tantivy_query_grammar::{UserInputAst, UserInputLeaf, UserInputLiteral};
+use tantivy::query::{
+    AllQuery, BooleanQuery, BoostQuery, EmptyQuery, Occur, Query, QueryParser,
+    QueryParserError as TantivyQueryParserError, TermQuery,
+};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Term};
+use tantivy::tokenizer::TokenizerManager;
+use tantivy_query_grammar::{
+    Occur as UserInputOccur, UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral,
+};
 
+use crate::geo_point::{is_geo_point_field, parse_geo_query};
+use crate::geo_point_query::GeoPointFilterQuery;
+use crate::ip_address::{ip_query_to_term_texts, ip_range_to_term_texts, is_ip_field};
 use crate::sort_by::validate_sort_by_field_name;
-use crate::{QueryParserError, DYNAMIC_FIELD_NAME, QUICKWIT_TOKENIZER_MANAGER};
+use crate::{QueryParserError, DYNAMIC_FIELD_NAME};
 
 /// Build a `Query` with field resolution & forbidding range clauses.
 pub(crate) fn build_query(
     schema: Schema,
     request: &SearchRequest,
     default_field_names: &[String],
+    tokenizer_manager: &TokenizerManager,
 ) -> Result<Box<dyn Query>, QueryParserError> {
     let user_input_ast = tantivy_query_grammar::parse_query(&request.query)
         .map_err(|_| TantivyQueryParserError::SyntaxError(request.query.to_string()))?;
@@ -38,7 +50,7 @@ pub(crate) fn build_query(
         validate_sort_by_field_name(sort_by_field, &schema)?;
     }
 
-    if has_range_clause(&user_input_ast) {
+    if has_range_clause(&user_input_ast, &schema) {
         return Err(anyhow::anyhow!("Range queries are not currently allowed.").into());
     }
 
@@ -57,25 +69,199 @@ pub(crate) fn build_query(
         resolve_fields(&schema, &request.search_fields)?
     };
 
+    let has_term_set_clause = has_term_set_clause(&user_input_ast, &schema);
     let mut query_parser =
-        QueryParser::new(schema, search_fields, QUICKWIT_TOKENIZER_MANAGER.clone());
+        QueryParser::new(schema.clone(), search_fields, tokenizer_manager.clone());
     query_parser.set_conjunction_by_default();
+    if has_term_set_clause {
+        return build_query_from_ast(&user_input_ast, &schema, &query_parser);
+    }
     let query = query_parser.parse_query(&request.query)?;
     Ok(query)
 }
 
-fn has_range_clause(user_input_ast: &UserInputAst) -> bool {
+/// Tells if the query has a range clause. Range clauses are only allowed on `ip` fields.
+fn has_range_clause(user_input_ast: &UserInputAst, schema: &Schema) -> bool {
     match user_input_ast {
         UserInputAst::Clause(sub_queries) => {
             for (_, sub_ast) in sub_queries {
-                if has_range_clause(sub_ast) {
+                if has_range_clause(sub_ast, schema) {
                     return true;
                 }
             }
             false
         }
-        UserInputAst::Boost(ast, _) => has_range_clause(ast),
-        UserInputAst::Leaf(leaf) => matches!(**leaf, UserInputLeaf::Range { .. }),
+        UserInputAst::Boost(ast, _) => has_range_clause(ast, schema),
+        UserInputAst::Leaf(leaf) => match &**leaf {
+            UserInputLeaf::Range { field, .. } => !matches!(
+                resolve_term_set_field(schema, field.as_ref()),
+                Some(TermSetField::Ip(_))
+            ),
+            _ => false,
+        },
+    }
+}
+
+/// Fields indexed with a dedicated tokenizer, which the tantivy query parser does not know
+/// how to query. Their clauses are built by Quickwit, as sets of term queries.
+enum TermSetField {
+    Ip(Field),
+    GeoPoint(Field),
+}
+
+/// Tells if the query has a Term or Range node targeting an `ip` or `geo_point` field.
+fn has_term_set_clause(user_input_ast: &UserInputAst, schema: &Schema) -> bool {
+    match user_input_ast {
+        UserInputAst::Clause(sub_queries) => sub_queries
+            .iter()
+            .any(|(_, sub_ast)| has_term_set_clause(sub_ast, schema)),
+        UserInputAst::Boost(ast, _) => has_term_set_clause(ast, schema),
+        UserInputAst::Leaf(leaf) => match &**leaf {
+            UserInputLeaf::Literal(UserInputLiteral { field_name, .. }) => {
+                resolve_term_set_field(schema, field_name.as_ref()).is_some()
+            }
+            UserInputLeaf::Range { field, .. } => {
+                resolve_term_set_field(schema, field.as_ref()).is_some()
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Returns the field if `field_name_opt` is the name of an `ip` or `geo_point` field.
+fn resolve_term_set_field(
+    schema: &Schema,
+    field_name_opt: Option<&String>,
+) -> Option<TermSetField> {
+    let field = schema.get_field(field_name_opt?)?;
+    let field_entry = schema.get_field_entry(field);
+    if is_ip_field(field_entry) {
+        Some(TermSetField::Ip(field))
+    } else if is_geo_point_field(field_entry) {
+        Some(TermSetField::GeoPoint(field))
+    } else {
+        None
+    }
+}
+
+/// Builds the query from the AST, node by node. This is only used for queries with
+/// clauses on `ip` or `geo_point` fields, which the tantivy query parser does not know how
+/// to handle.
+/// The other leaves are delegated to the query parser.
+fn build_query_from_ast(
+    user_input_ast: &UserInputAst,
+    schema: &Schema,
+    query_parser: &QueryParser,
+) -> Result<Box<dyn Query>, QueryParserError> {
+    match user_input_ast {
+        UserInputAst::Clause(sub_queries) => {
+            let mut clauses = Vec::with_capacity(sub_queries.len());
+            for (occur_opt, sub_ast) in sub_queries {
+                let occur = match occur_opt {
+                    Some(UserInputOccur::Should) => Occur::Should,
+                    Some(UserInputOccur::Must) | None => Occur::Must,
+                    Some(UserInputOccur::MustNot) => Occur::MustNot,
+                };
+                clauses.push((occur, build_query_from_ast(sub_ast, schema, query_parser)?));
+            }
+            Ok(Box::new(BooleanQuery::new(clauses)))
+        }
+        UserInputAst::Boost(ast, boost) => {
+            let query = build_query_from_ast(ast, schema, query_parser)?;
+            Ok(Box::new(BoostQuery::new(query, *boost as f32)))
+        }
+        UserInputAst::Leaf(leaf) => match &**leaf {
+            UserInputLeaf::Literal(literal) => {
+                match resolve_term_set_field(schema, literal.field_name.as_ref()) {
+                    Some(TermSetField::Ip(field)) => {
+                        let term_texts = ip_query_to_term_texts(&literal.phrase)?;
+                        return Ok(build_term_set_query(field, term_texts));
+                    }
+                    Some(TermSetField::GeoPoint(field)) => {
+                        let geo_query = parse_geo_query(&literal.phrase)?;
+                        let cells_query = build_term_set_query(field, geo_query.covering_geohashes);
+                        // The points of fast fields are checked exactly at the leaf. Otherwise,
+                        // the border of the queried area is approximated by the covering cells.
+                        if !schema.get_field_entry(field).is_fast() {
+                            return Ok(cells_query);
+                        }
+                        let geo_point_filter_query =
+                            GeoPointFilterQuery::new(field, geo_query.geo_shape, cells_query);
+                        return Ok(Box::new(geo_point_filter_query));
+                    }
+                    None => {}
+                }
+                let phrase = escape_phrase(&literal.phrase);
+                let phrase_query_str = match &literal.field_name {
+                    Some(field_name) => format!("{}:\"{}\"", field_name, phrase),
+                    None => format!("\"{}\"", phrase),
+                };
+                let query_str = if literal.slop > 0 {
+                    format!("{}~{}", phrase_query_str, literal.slop)
+                } else {
+                    phrase_query_str
+                };
+                Ok(query_parser.parse_query(&query_str)?)
+            }
+            UserInputLeaf::Range {
+                field,
+                lower,
+                upper,
+            } => {
+                let ip_field = match resolve_term_set_field(schema, field.as_ref()) {
+                    Some(TermSetField::Ip(ip_field)) => ip_field,
+                    _ => {
+                        return Err(
+                            anyhow::anyhow!("Range queries are not currently allowed.").into()
+                        )
+                    }
+                };
+                let term_texts =
+                    ip_range_to_term_texts(user_input_bound(lower), user_input_bound(upper))?;
+                Ok(build_term_set_query(ip_field, term_texts))
+            }
+            UserInputLeaf::All => Ok(Box::new(AllQuery)),
+        },
+    }
+}
+
+/// Escapes the quotes and backslashes of a phrase, so that it can be quoted again and handed
+/// over to the query parser.
+fn escape_phrase(phrase: &str) -> String {
+    let mut escaped_phrase = String::with_capacity(phrase.len());
+    for character in phrase.chars() {
+        if matches!(character, '"' | '\\') {
+            escaped_phrase.push('\\');
+        }
+        escaped_phrase.push(character);
+    }
+    escaped_phrase
+}
+
+/// Returns the bound value and whether it is inclusive, or `None` if unbounded.
+fn user_input_bound(bound: &UserInputBound) -> Option<(&str, bool)> {
+    match bound {
+        UserInputBound::Inclusive(value) => Some((value.as_str(), true)),
+        UserInputBound::Exclusive(value) => Some((value.as_str(), false)),
+        UserInputBound::Unbounded => None,
+    }
+}
+
+/// Builds a query matching the documents containing any of the terms.
+fn build_term_set_query(field: Field, term_texts: BTreeSet<String>) -> Box<dyn Query> {
+    let mut term_queries: Vec<(Occur, Box<dyn Query>)> = term_texts
+        .iter()
+        .map(|term_text| {
+            let term = Term::from_field_text(field, term_text);
+            let term_query: Box<dyn Query> =
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+            (Occur::Should, term_query)
+        })
+        .collect();
+    match term_queries.len() {
+        0 => Box::new(EmptyQuery),
+        1 => term_queries.pop().unwrap().1,
+        _ => Box::new(BooleanQuery::new(term_queries)),
     }
 }
 
@@ -114,10 +300,11 @@ fn resolve_fields(schema: &Schema, field_names: &[String]) -> anyhow::Result<Vec
 #[cfg(test)]
 mod test {
     use quickwit_proto::SearchRequest;
-    use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
+    use tantivy::schema::{Schema, TextOptions, FAST, INDEXED, STORED, TEXT};
 
-    use super::build_query;
-    use crate::{DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME};
+    use super::{build_query, escape_phrase};
+    use crate::default_doc_mapper::{QuickwitGeoPointOptions, QuickwitIpOptions};
+    use crate::{DYNAMIC_FIELD_NAME, QUICKWIT_TOKENIZER_MANAGER, SOURCE_FIELD_NAME};
 
     enum TestExpectation {
         Err(&'static str),
@@ -133,6 +320,11 @@ mod test {
         schema_builder.add_bool_field("server.running", FAST | STORED | INDEXED);
         schema_builder.add_text_field(SOURCE_FIELD_NAME, TEXT);
         schema_builder.add_json_field(DYNAMIC_FIELD_NAME, TEXT);
+        schema_builder.add_text_field("client_ip", TextOptions::from(QuickwitIpOptions::default()));
+        schema_builder.add_text_field(
+            "location",
+            TextOptions::from(QuickwitGeoPointOptions::default()),
+        );
         schema_builder.build()
     }
 
@@ -154,12 +346,18 @@ mod test {
             start_offset: 0,
             sort_order: None,
             sort_by_field: None,
+            timeout_millis: None,
         };
 
         let default_field_names =
             default_search_fields.unwrap_or_else(|| vec!["title".to_string(), "desc".to_string()]);
 
-        let query_result = build_query(make_schema(), &request, &default_field_names);
+        let query_result = build_query(
+            make_schema(),
+            &request,
+            &default_field_names,
+            &QUICKWIT_TOKENIZER_MANAGER,
+        );
         match expected {
             TestExpectation::Err(sub_str) => {
                 assert!(
@@ -298,4 +496,82 @@ mod test {
         )
         .unwrap();
     }
+
+    #[test]
+    fn test_build_query_ip_field() {
+        check_build_query(
+            "client_ip:10.0.0.1",
+            vec![],
+            None,
+            TestExpectation::Ok("00000000000000000000ffff0a000001"),
+        )
+        .unwrap();
+        check_build_query(
+            "client_ip:\"10.0.0.0/8\" AND title:hello",
+            vec![],
+            None,
+            TestExpectation::Ok("00000000000000000000ffff0a"),
+        )
+        .unwrap();
+        check_build_query(
+            "client_ip:[10.0.0.0 TO 10.0.0.255]",
+            vec![],
+            None,
+            TestExpectation::Ok("00000000000000000000ffff0a0000"),
+        )
+        .unwrap();
+        check_build_query(
+            "client_ip:not-an-ip",
+            vec![],
+            None,
+            TestExpectation::Err("not-an-ip"),
+        )
+        .unwrap();
+        check_build_query(
+            "title:[a TO b] AND client_ip:10.0.0.1",
+            vec![],
+            None,
+            TestExpectation::Err("Range queries are not currently allowed."),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_escape_phrase() {
+        assert_eq!(escape_phrase("hello world"), "hello world");
+        assert_eq!(escape_phrase(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(escape_phrase(r"C:\temp"), r"C:\\temp");
+    }
+
+    #[test]
+    fn test_build_query_geo_point_field() {
+        check_build_query(
+            "location:\"bbox(45, 0, 0, 45)\"",
+            vec![],
+            None,
+            TestExpectation::Ok("\"s\""),
+        )
+        .unwrap();
+        check_build_query(
+            "location:\"distance(40.7128,-74.006,1km)\" AND title:hello",
+            vec![],
+            None,
+            TestExpectation::Ok("dr5r"),
+        )
+        .unwrap();
+        check_build_query(
+            "location:\"40.7128,-74.006\"",
+            vec![],
+            None,
+            TestExpectation::Err("Invalid geo query"),
+        )
+        .unwrap();
+        check_build_query(
+            "location:[0 TO 1]",
+            vec![],
+            None,
+            TestExpectation::Err("Range queries are not currently allowed."),
+        )
+        .unwrap();
+    }
 }
@@ -17,14 +17,46 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use anyhow::{bail, Context};
 use once_cell::sync::Lazy;
-use tantivy::tokenizer::{RawTokenizer, RemoveLongFilter, TextAnalyzer, TokenizerManager};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::{
+    AlphaNumOnlyFilter, AsciiFoldingFilter, BoxTokenFilter, BoxTokenStream, Language, LowerCaser,
+    NgramTokenizer, RawTokenizer, RemoveLongFilter, SimpleTokenizer, Stemmer, StopWordFilter,
+    TextAnalyzer, Token, TokenStream, Tokenizer, TokenizerManager, WhitespaceTokenizer,
+};
+
+use crate::geo_point::term_text_to_geohashes;
+use crate::ip_address::NUM_IP_NIBBLES;
+
+/// Name of the tokenizer used by `ip` fields.
+///
+/// Names starting with `_` cannot be used by custom tokenizers or referenced by text fields.
+pub(crate) const IP_TOKENIZER_NAME: &str = "_ip";
+
+/// Name of the tokenizer used by `geo_point` fields.
+pub(crate) const GEO_POINT_TOKENIZER_NAME: &str = "_geo_point";
+
+/// Names of the tokenizers registered by default in every tokenizer manager.
+const BUILT_IN_TOKENIZER_NAMES: [&str; 5] = ["raw", "default", "en_stem", "cjk", "bigram"];
 
 fn get_quickwit_tokenizer_manager() -> TokenizerManager {
     let raw_tokenizer = TextAnalyzer::from(RawTokenizer).filter(RemoveLongFilter::limit(100));
+    let cjk_tokenizer = TextAnalyzer::from(CjkTokenizer)
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser);
+    let bigram_tokenizer = TextAnalyzer::from(BigramTokenizer).filter(LowerCaser);
 
     let tokenizer_manager = TokenizerManager::default();
     tokenizer_manager.register("raw", raw_tokenizer);
+    tokenizer_manager.register("cjk", cjk_tokenizer);
+    tokenizer_manager.register("bigram", bigram_tokenizer);
+    tokenizer_manager.register(IP_TOKENIZER_NAME, TextAnalyzer::from(IpPrefixTokenizer));
+    tokenizer_manager.register(
+        GEO_POINT_TOKENIZER_NAME,
+        TextAnalyzer::from(GeohashPrefixTokenizer),
+    );
     tokenizer_manager
 }
 
@@ -32,19 +64,708 @@ fn get_quickwit_tokenizer_manager() -> TokenizerManager {
 pub static QUICKWIT_TOKENIZER_MANAGER: Lazy<TokenizerManager> =
     Lazy::new(get_quickwit_tokenizer_manager);
 
-#[test]
-fn raw_tokenizer_test() {
-    let my_haiku = r#"
+/// Returns a new tokenizer manager holding the built-in tokenizers and the
+/// custom tokenizers defined in the doc mapping.
+///
+/// A fresh manager is created on every call: `TokenizerManager` clones share
+/// their registry, so registering custom tokenizers on a clone of
+/// [`QUICKWIT_TOKENIZER_MANAGER`] would leak them to other indexes.
+pub(crate) fn create_tokenizer_manager(
+    tokenizer_entries: &[TokenizerEntry],
+) -> anyhow::Result<TokenizerManager> {
+    let tokenizer_manager = get_quickwit_tokenizer_manager();
+    for tokenizer_entry in tokenizer_entries {
+        let text_analyzer = tokenizer_entry
+            .config
+            .text_analyzer()
+            .with_context(|| format!("Invalid tokenizer `{}`.", tokenizer_entry.name))?;
+        tokenizer_manager.register(&tokenizer_entry.name, text_analyzer);
+    }
+    Ok(tokenizer_manager)
+}
+
+/// Validates a list of custom tokenizer entries.
+pub(crate) fn validate_tokenizer_entries(
+    tokenizer_entries: &[TokenizerEntry],
+) -> anyhow::Result<()> {
+    static TOKENIZER_NAME_PTN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"^[a-z][_a-z0-9]{0,254}$"#).unwrap());
+
+    let mut tokenizer_names = Vec::new();
+    for tokenizer_entry in tokenizer_entries {
+        let tokenizer_name = tokenizer_entry.name.as_str();
+        if !TOKENIZER_NAME_PTN.is_match(tokenizer_name) {
+            bail!(
+                "Tokenizer name `{}` is invalid. Tokenizer names must start with a lowercase \
+                 ASCII letter and only contain lowercase ASCII letters, digits, and underscores \
+                 `_`.",
+                tokenizer_name
+            );
+        }
+        if BUILT_IN_TOKENIZER_NAMES.contains(&tokenizer_name) {
+            bail!(
+                "Tokenizer name `{}` is reserved for a built-in tokenizer.",
+                tokenizer_name
+            );
+        }
+        if tokenizer_names.contains(&tokenizer_name) {
+            bail!("Duplicated tokenizer: `{}`", tokenizer_name);
+        }
+        tokenizer_names.push(tokenizer_name);
+    }
+    Ok(())
+}
+
+/// A `TokenizerEntry` defines a custom text analyzer that can be
+/// referenced by name in the field mappings.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TokenizerEntry {
+    /// Tokenizer name, as referenced in the `tokenizer` parameter of text and json fields.
+    pub name: String,
+    /// Tokenizer and filters applied to the text.
+    #[serde(flatten)]
+    pub config: TokenizerConfig,
+}
+
+/// Describes a text analyzer: a tokenizer followed by a chain of token filters.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TokenizerConfig {
+    /// Tokenizer splitting the text into tokens.
+    #[serde(flatten)]
+    pub tokenizer_type: TokenizerType,
+    /// Filters applied, in order, to the emitted tokens.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<TokenFilterType>,
+}
+
+impl TokenizerConfig {
+    /// Builds the text analyzer described by the config.
+    pub fn text_analyzer(&self) -> anyhow::Result<TextAnalyzer> {
+        let mut token_filters: Vec<BoxTokenFilter> = Vec::with_capacity(self.filters.len());
+        for token_filter in &self.filters {
+            token_filters.push(token_filter.box_token_filter()?);
+        }
+        let text_analyzer = match &self.tokenizer_type {
+            TokenizerType::Raw => TextAnalyzer::new(RawTokenizer, token_filters),
+            TokenizerType::Simple => TextAnalyzer::new(SimpleTokenizer, token_filters),
+            TokenizerType::Whitespace => TextAnalyzer::new(WhitespaceTokenizer, token_filters),
+            TokenizerType::Cjk => TextAnalyzer::new(CjkTokenizer, token_filters),
+            TokenizerType::Ngram(options) => {
+                options.validate()?;
+                let ngram_tokenizer = if options.prefix_only {
+                    NgramTokenizer::prefix_only(options.min_gram, options.max_gram)
+                } else {
+                    NgramTokenizer::all_ngrams(options.min_gram, options.max_gram)
+                };
+                TextAnalyzer::new(ngram_tokenizer, token_filters)
+            }
+            TokenizerType::Regex { pattern } => {
+                let regex_split_tokenizer = RegexSplitTokenizer::new(pattern)?;
+                TextAnalyzer::new(regex_split_tokenizer, token_filters)
+            }
+        };
+        Ok(text_analyzer)
+    }
+}
+
+/// Tokenizer splitting the text into tokens.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenizerType {
+    /// Emits the whole text as a single token.
+    Raw,
+    /// Splits on any non alphanumeric character.
+    Simple,
+    /// Splits on whitespaces.
+    Whitespace,
+    /// Splits like `simple`, but emits overlapping bigrams for runs of CJK characters.
+    Cjk,
+    /// Emits the ngrams of the text.
+    Ngram(NgramOptions),
+    /// Splits the text on the matches of a regular expression.
+    Regex {
+        /// Regular expression matching token separators.
+        pattern: String,
+    },
+}
+
+/// Options of the ngram tokenizer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct NgramOptions {
+    /// Minimum number of characters of an ngram.
+    pub min_gram: usize,
+    /// Maximum number of characters of an ngram.
+    pub max_gram: usize,
+    /// If true, only the ngrams starting at the beginning of the text are emitted (edge-ngrams).
+    #[serde(default)]
+    pub prefix_only: bool,
+}
+
+impl NgramOptions {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.min_gram == 0 {
+            bail!("Ngram tokenizer `min_gram` must be strictly positive.");
+        }
+        if self.min_gram > self.max_gram {
+            bail!("Ngram tokenizer `min_gram` must be inferior or equal to `max_gram`.");
+        }
+        Ok(())
+    }
+}
+
+/// Filter applied to the tokens emitted by the tokenizer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenFilterType {
+    /// Lowercases tokens.
+    Lowercase,
+    /// Converts non-ASCII characters to their ASCII equivalent when one exists.
+    AsciiFolding,
+    /// Removes tokens containing non alphanumeric characters.
+    AlphaNumOnly,
+    /// Removes tokens longer than `length_limit` bytes.
+    RemoveLong {
+        /// Maximum length of a token, in bytes.
+        length_limit: usize,
+    },
+    /// Removes the tokens belonging to a list of stop words.
+    StopWords {
+        /// List of stop words.
+        words: Vec<String>,
+    },
+    /// Stems tokens using the Snowball stemmer of the given language.
+    Stemmer {
+        /// Stemmer language.
+        language: StemmerLanguage,
+    },
+}
+
+impl TokenFilterType {
+    fn box_token_filter(&self) -> anyhow::Result<BoxTokenFilter> {
+        let box_token_filter = match self {
+            TokenFilterType::Lowercase => LowerCaser.into(),
+            TokenFilterType::AsciiFolding => AsciiFoldingFilter.into(),
+            TokenFilterType::AlphaNumOnly => AlphaNumOnlyFilter.into(),
+            TokenFilterType::RemoveLong { length_limit } => {
+                if *length_limit == 0 {
+                    bail!("Remove long filter `length_limit` must be strictly positive.");
+                }
+                RemoveLongFilter::limit(*length_limit).into()
+            }
+            TokenFilterType::StopWords { words } => StopWordFilter::remove(words.clone()).into(),
+            TokenFilterType::Stemmer { language } => {
+                Stemmer::new(language.tantivy_language()).into()
+            }
+        };
+        Ok(box_token_filter)
+    }
+}
+
+/// Languages supported by the stemmer filter.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[allow(missing_docs)]
+pub enum StemmerLanguage {
+    Arabic,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Tamil,
+    Turkish,
+}
+
+impl StemmerLanguage {
+    fn tantivy_language(&self) -> Language {
+        match self {
+            StemmerLanguage::Arabic => Language::Arabic,
+            StemmerLanguage::Danish => Language::Danish,
+            StemmerLanguage::Dutch => Language::Dutch,
+            StemmerLanguage::English => Language::English,
+            StemmerLanguage::Finnish => Language::Finnish,
+            StemmerLanguage::French => Language::French,
+            StemmerLanguage::German => Language::German,
+            StemmerLanguage::Greek => Language::Greek,
+            StemmerLanguage::Hungarian => Language::Hungarian,
+            StemmerLanguage::Italian => Language::Italian,
+            StemmerLanguage::Norwegian => Language::Norwegian,
+            StemmerLanguage::Portuguese => Language::Portuguese,
+            StemmerLanguage::Romanian => Language::Romanian,
+            StemmerLanguage::Russian => Language::Russian,
+            StemmerLanguage::Spanish => Language::Spanish,
+            StemmerLanguage::Swedish => Language::Swedish,
+            StemmerLanguage::Tamil => Language::Tamil,
+            StemmerLanguage::Turkish => Language::Turkish,
+        }
+    }
+}
+
+/// Tokenizer splitting the text on the matches of a regular expression.
+/// Empty tokens are skipped.
+#[derive(Clone)]
+struct RegexSplitTokenizer {
+    separator_regex: Regex,
+}
+
+impl RegexSplitTokenizer {
+    fn new(pattern: &str) -> anyhow::Result<Self> {
+        let separator_regex = Regex::new(pattern)
+            .with_context(|| format!("Invalid regex tokenizer pattern `{}`.", pattern))?;
+        Ok(Self { separator_regex })
+    }
+}
+
+impl Tokenizer for RegexSplitTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        let mut token_offsets: Vec<(usize, usize)> = Vec::new();
+        let mut offset_from = 0;
+        for separator_match in self.separator_regex.find_iter(text) {
+            if offset_from < separator_match.start() {
+                token_offsets.push((offset_from, separator_match.start()));
+            }
+            offset_from = separator_match.end();
+        }
+        if offset_from < text.len() {
+            token_offsets.push((offset_from, text.len()));
+        }
+        BoxTokenStream::from(VecTokenStream::from_offsets(text, token_offsets))
+    }
+}
+
+/// Returns true if the character belongs to one of the Chinese, Japanese or Korean scripts.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}' // Hangul Jamo
+        | '\u{3040}'..='\u{309F}' // Hiragana
+        | '\u{30A0}'..='\u{30FF}' // Katakana
+        | '\u{3130}'..='\u{318F}' // Hangul Compatibility Jamo
+        | '\u{31F0}'..='\u{31FF}' // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Halfwidth Katakana
+        | '\u{20000}'..='\u{2A6DF}' // CJK Unified Ideographs Extension B
+    )
+}
+
+/// Tokenizer suited for texts mixing CJK and non-CJK scripts.
+///
+/// Runs of alphanumeric non-CJK characters are emitted as a single token, like the
+/// `simple` tokenizer. CJK scripts do not separate words with whitespaces, so runs of
+/// CJK characters are emitted as overlapping bigrams (`検索エ` yields `検索`, `索エ`).
+/// A CJK character standing alone is emitted as a unigram.
+///
+/// Bigrams of a run are emitted at consecutive positions, so a multi-character query is
+/// analyzed into a phrase query over the same bigrams.
+#[derive(Clone)]
+struct CjkTokenizer;
+
+impl Tokenizer for CjkTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        let mut token_offsets: Vec<(usize, usize)> = Vec::new();
+        // Start offset of the current run of alphanumeric non-CJK characters.
+        let mut word_start_opt: Option<usize> = None;
+        // Offsets of the characters of the current run of CJK characters.
+        let mut cjk_run: Vec<(usize, usize)> = Vec::new();
+        for (offset, c) in text.char_indices() {
+            if is_cjk(c) {
+                if let Some(word_start) = word_start_opt.take() {
+                    token_offsets.push((word_start, offset));
+                }
+                cjk_run.push((offset, offset + c.len_utf8()));
+                continue;
+            }
+            flush_char_run(&mut cjk_run, &mut token_offsets);
+            if c.is_alphanumeric() {
+                word_start_opt.get_or_insert(offset);
+            } else if let Some(word_start) = word_start_opt.take() {
+                token_offsets.push((word_start, offset));
+            }
+        }
+        flush_char_run(&mut cjk_run, &mut token_offsets);
+        if let Some(word_start) = word_start_opt {
+            token_offsets.push((word_start, text.len()));
+        }
+        BoxTokenStream::from(VecTokenStream::from_offsets(text, token_offsets))
+    }
+}
+
+/// Appends the offsets of the bigrams of a run of characters, or of its single character, to
+/// `token_offsets` and clears the run.
+fn flush_char_run(char_run: &mut Vec<(usize, usize)>, token_offsets: &mut Vec<(usize, usize)>) {
+    if char_run.len() == 1 {
+        token_offsets.push(char_run[0]);
+    }
+    for window in char_run.windows(2) {
+        token_offsets.push((window[0].0, window[1].1));
+    }
+    char_run.clear();
+}
+
+/// Tokenizer emitting the overlapping bigrams of each whitespace-separated word (`ab cde` yields
+/// `ab`, `cd`, `de`). A word of a single character is emitted as a unigram.
+///
+/// Unlike tantivy's `NgramTokenizer`, bigrams are emitted at consecutive positions, so a
+/// multi-character query is analyzed into a phrase query over the same bigrams.
+#[derive(Clone)]
+struct BigramTokenizer;
+
+impl Tokenizer for BigramTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        let mut token_offsets: Vec<(usize, usize)> = Vec::new();
+        // Offsets of the characters of the current word.
+        let mut char_run: Vec<(usize, usize)> = Vec::new();
+        for (offset, c) in text.char_indices() {
+            if c.is_whitespace() {
+                flush_char_run(&mut char_run, &mut token_offsets);
+            } else {
+                char_run.push((offset, offset + c.len_utf8()));
+            }
+        }
+        flush_char_run(&mut char_run, &mut token_offsets);
+        BoxTokenStream::from(VecTokenStream::from_offsets(text, token_offsets))
+    }
+}
+
+/// Tokenizer emitting all the prefixes of the canonical representation of an IP address.
+/// See [`crate::ip_address`].
+#[derive(Clone)]
+struct IpPrefixTokenizer;
+
+impl Tokenizer for IpPrefixTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        let mut token_offsets: Vec<(usize, usize)> = Vec::with_capacity(NUM_IP_NIBBLES);
+        if text.len() == NUM_IP_NIBBLES && text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            for prefix_len in 1..=NUM_IP_NIBBLES {
+                token_offsets.push((0, prefix_len));
+            }
+        }
+        BoxTokenStream::from(VecTokenStream::from_offsets(text, token_offsets))
+    }
+}
+
+/// Tokenizer emitting all the prefixes of the geohash of a `lat,lon` geo point.
+/// See [`crate::geo_point`].
+#[derive(Clone)]
+struct GeohashPrefixTokenizer;
+
+impl Tokenizer for GeohashPrefixTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        let geohashes = term_text_to_geohashes(text).unwrap_or_default();
+        let tokens = geohashes
+            .into_iter()
+            .enumerate()
+            .map(|(position, geohash)| Token {
+                offset_from: 0,
+                offset_to: text.len(),
+                position,
+                text: geohash,
+                position_length: 1,
+            })
+            .collect();
+        BoxTokenStream::from(VecTokenStream {
+            tokens,
+            cursor: None,
+        })
+    }
+}
+
+/// Token stream iterating over a precomputed list of tokens.
+struct VecTokenStream {
+    tokens: Vec<Token>,
+    cursor: Option<usize>,
+}
+
+impl VecTokenStream {
+    /// Creates a token stream emitting the slices of `text` delimited by `token_offsets`,
+    /// at consecutive positions.
+    fn from_offsets(text: &str, token_offsets: Vec<(usize, usize)>) -> Self {
+        let tokens = token_offsets
+            .into_iter()
+            .enumerate()
+            .map(|(position, (offset_from, offset_to))| Token {
+                offset_from,
+                offset_to,
+                position,
+                text: text[offset_from..offset_to].to_string(),
+                position_length: 1,
+            })
+            .collect();
+        Self {
+            tokens,
+            cursor: None,
+        }
+    }
+}
+
+impl TokenStream for VecTokenStream {
+    fn advance(&mut self) -> bool {
+        let next_cursor = self.cursor.map(|cursor| cursor + 1).unwrap_or(0);
+        self.cursor = Some(next_cursor);
+        next_cursor < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self
+            .cursor
+            .expect("`advance` must be called before `token`.")]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self
+            .cursor
+            .expect("`advance` must be called before `token_mut`.")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_tokens(text_analyzer: &TextAnalyzer, text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut token_stream = text_analyzer.token_stream(text);
+        while token_stream.advance() {
+            tokens.push(token_stream.token().text.clone());
+        }
+        tokens
+    }
+
+    #[test]
+    fn raw_tokenizer_test() {
+        let my_haiku = r#"
         white sandy beach
         a strong wind is coming 
         sand in my face
         "#;
-    let my_long_text = "a text, that is just too long, no one will type it, no one will like it, \
-                        no one shall find it. I just need some more chars, now you may not pass.";
-
-    let tokenizer = get_quickwit_tokenizer_manager().get("raw").unwrap();
-    let mut haiku_stream = tokenizer.token_stream(my_haiku);
-    assert!(haiku_stream.advance());
-    assert!(!haiku_stream.advance());
-    assert!(!tokenizer.token_stream(my_long_text).advance());
+        let my_long_text = "a text, that is just too long, no one will type it, no one will like \
+                            it, no one shall find it. I just need some more chars, now you may \
+                            not pass.";
+
+        let tokenizer = get_quickwit_tokenizer_manager().get("raw").unwrap();
+        let mut haiku_stream = tokenizer.token_stream(my_haiku);
+        assert!(haiku_stream.advance());
+        assert!(!haiku_stream.advance());
+        assert!(!tokenizer.token_stream(my_long_text).advance());
+    }
+
+    #[test]
+    fn test_cjk_tokenizer() {
+        let tokenizer = get_quickwit_tokenizer_manager().get("cjk").unwrap();
+        assert_eq!(
+            collect_tokens(&tokenizer, "Quickwit検索エンジン, 高速!"),
+            &["quickwit", "検索", "索エ", "エン", "ンジ", "ジン", "高速"]
+        );
+        assert_eq!(collect_tokens(&tokenizer, "한 국어"), &["한", "국어"]);
+        let mut token_stream = tokenizer.token_stream("ab検索");
+        assert!(token_stream.advance());
+        assert_eq!(token_stream.token().offset_to, 2);
+        assert!(token_stream.advance());
+        assert_eq!(token_stream.token().offset_from, 2);
+        assert_eq!(token_stream.token().offset_to, 8);
+        assert_eq!(token_stream.token().position, 1);
+        assert!(!token_stream.advance());
+    }
+
+    #[test]
+    fn test_bigram_tokenizer() {
+        let tokenizer = get_quickwit_tokenizer_manager().get("bigram").unwrap();
+        assert_eq!(collect_tokens(&tokenizer, "中文Ab"), &["中文", "文a", "ab"]);
+        assert_eq!(
+            collect_tokens(&tokenizer, " abc  d\tef "),
+            &["ab", "bc", "d", "ef"]
+        );
+        let mut token_stream = tokenizer.token_stream("ab cde");
+        let mut positions = Vec::new();
+        while token_stream.advance() {
+            positions.push(token_stream.token().position);
+        }
+        assert_eq!(positions, &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_ip_prefix_tokenizer() {
+        let tokenizer = get_quickwit_tokenizer_manager()
+            .get(IP_TOKENIZER_NAME)
+            .unwrap();
+        let tokens = collect_tokens(&tokenizer, "00000000000000000000ffff0a0c0102");
+        assert_eq!(tokens.len(), 32);
+        assert_eq!(tokens[0], "0");
+        assert_eq!(tokens[27], "00000000000000000000ffff0a0c");
+        assert_eq!(tokens[31], "00000000000000000000ffff0a0c0102");
+        assert!(collect_tokens(&tokenizer, "10.12.1.2").is_empty());
+    }
+
+    #[test]
+    fn test_geohash_prefix_tokenizer() {
+        let tokenizer = get_quickwit_tokenizer_manager()
+            .get(GEO_POINT_TOKENIZER_NAME)
+            .unwrap();
+        let tokens = collect_tokens(&tokenizer, "57.64911,10.40744");
+        assert_eq!(tokens.len(), 12);
+        assert_eq!(tokens[0], "u");
+        assert_eq!(tokens[10], "u4pruydqqvj");
+        assert!(collect_tokens(&tokenizer, "not a geo point").is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_tokenizer_entry() {
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{
+                "name": "my_tokenizer",
+                "type": "ngram",
+                "min_gram": 2,
+                "max_gram": 3,
+                "filters": [
+                    {"type": "lowercase"},
+                    {"type": "stemmer", "language": "french"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenizer_entry,
+            TokenizerEntry {
+                name: "my_tokenizer".to_string(),
+                config: TokenizerConfig {
+                    tokenizer_type: TokenizerType::Ngram(NgramOptions {
+                        min_gram: 2,
+                        max_gram: 3,
+                        prefix_only: false,
+                    }),
+                    filters: vec![
+                        TokenFilterType::Lowercase,
+                        TokenFilterType::Stemmer {
+                            language: StemmerLanguage::French
+                        },
+                    ],
+                },
+            }
+        );
+        let serialized_tokenizer_entry = serde_json::to_string(&tokenizer_entry).unwrap();
+        let deserialized_tokenizer_entry: TokenizerEntry =
+            serde_json::from_str(&serialized_tokenizer_entry).unwrap();
+        assert_eq!(deserialized_tokenizer_entry, tokenizer_entry);
+    }
+
+    #[test]
+    fn test_validate_tokenizer_entries() {
+        let tokenizer_entry = |name: &str| TokenizerEntry {
+            name: name.to_string(),
+            config: TokenizerConfig {
+                tokenizer_type: TokenizerType::Whitespace,
+                filters: Vec::new(),
+            },
+        };
+        assert!(validate_tokenizer_entries(&[tokenizer_entry("my_tokenizer")]).is_ok());
+        assert!(validate_tokenizer_entries(&[tokenizer_entry("default")])
+            .unwrap_err()
+            .to_string()
+            .contains("reserved"));
+        assert!(
+            validate_tokenizer_entries(&[tokenizer_entry("My-Tokenizer")])
+                .unwrap_err()
+                .to_string()
+                .contains("is invalid")
+        );
+        assert!(validate_tokenizer_entries(&[
+            tokenizer_entry("my_tokenizer"),
+            tokenizer_entry("my_tokenizer")
+        ])
+        .unwrap_err()
+        .to_string()
+        .contains("Duplicated"));
+    }
+
+    #[test]
+    fn test_whitespace_lowercase_ascii_folding_analyzer() {
+        let tokenizer_config = TokenizerConfig {
+            tokenizer_type: TokenizerType::Whitespace,
+            filters: vec![TokenFilterType::Lowercase, TokenFilterType::AsciiFolding],
+        };
+        let text_analyzer = tokenizer_config.text_analyzer().unwrap();
+        assert_eq!(
+            collect_tokens(&text_analyzer, "Crème BRÛLÉE /var/log"),
+            &["creme", "brulee", "/var/log"]
+        );
+    }
+
+    #[test]
+    fn test_edge_ngram_analyzer() {
+        let tokenizer_config = TokenizerConfig {
+            tokenizer_type: TokenizerType::Ngram(NgramOptions {
+                min_gram: 1,
+                max_gram: 3,
+                prefix_only: true,
+            }),
+            filters: Vec::new(),
+        };
+        let text_analyzer = tokenizer_config.text_analyzer().unwrap();
+        assert_eq!(collect_tokens(&text_analyzer, "hello"), &["h", "he", "hel"]);
+
+        let invalid_tokenizer_config = TokenizerConfig {
+            tokenizer_type: TokenizerType::Ngram(NgramOptions {
+                min_gram: 3,
+                max_gram: 1,
+                prefix_only: false,
+            }),
+            filters: Vec::new(),
+        };
+        assert!(invalid_tokenizer_config.text_analyzer().is_err());
+    }
+
+    #[test]
+    fn test_regex_analyzer() {
+        let tokenizer_config = TokenizerConfig {
+            tokenizer_type: TokenizerType::Regex {
+                pattern: r#"[/\.]+"#.to_string(),
+            },
+            filters: vec![TokenFilterType::StopWords {
+                words: vec!["log".to_string()],
+            }],
+        };
+        let text_analyzer = tokenizer_config.text_analyzer().unwrap();
+        assert_eq!(
+            collect_tokens(&text_analyzer, "/var/log/10.0.0.1.log"),
+            &["var", "10", "0", "0", "1"]
+        );
+        let mut token_stream = text_analyzer.token_stream("a/b");
+        assert!(token_stream.advance());
+        assert_eq!(token_stream.token().offset_from, 0);
+        assert!(token_stream.advance());
+        assert_eq!(token_stream.token().offset_from, 2);
+        assert_eq!(token_stream.token().position, 1);
+        assert!(!token_stream.advance());
+    }
+
+    #[test]
+    fn test_create_tokenizer_manager_does_not_leak_custom_tokenizers() {
+        let tokenizer_entries = vec![TokenizerEntry {
+            name: "my_tokenizer".to_string(),
+            config: TokenizerConfig {
+                tokenizer_type: TokenizerType::Simple,
+                filters: vec![TokenFilterType::Stemmer {
+                    language: StemmerLanguage::German,
+                }],
+            },
+        }];
+        let tokenizer_manager = create_tokenizer_manager(&tokenizer_entries).unwrap();
+        assert!(tokenizer_manager.get("my_tokenizer").is_some());
+        assert!(tokenizer_manager.get("raw").is_some());
+        assert!(QUICKWIT_TOKENIZER_MANAGER.get("my_tokenizer").is_none());
+    }
 }
@@ -0,0 +1,770 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Ingest-time transforms, applied in order to the JSON document before it is mapped.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::DocParsingError;
+
+/// Maximum depth of grok patterns referencing other grok patterns.
+const GROK_MAX_DEPTH: usize = 16;
+
+/// A `FieldTransformEntry` defines a transform applied to a field of the ingested documents.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FieldTransformEntry {
+    /// Path of the transformed field. Keys of nested objects are separated by `.`, and literal
+    /// dots are escaped with `\`.
+    pub field: String,
+    /// Transform applied to the field.
+    #[serde(flatten)]
+    pub transform: FieldTransform,
+}
+
+/// Transform applied to the value of a field.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldTransform {
+    /// Sets the field to `value` when it is missing or `null`.
+    Default {
+        /// Default value of the field.
+        value: JsonValue,
+    },
+    /// Copies the value of the field to the `target` field. If the target field already
+    /// has a value, the values are gathered in an array.
+    CopyTo {
+        /// Path of the target field.
+        target: String,
+    },
+    /// Moves the value of the field to the `target` field.
+    Rename {
+        /// Path of the target field.
+        target: String,
+    },
+    /// Converts strings to lowercase.
+    Lowercase,
+    /// Removes the leading and trailing whitespaces of strings.
+    Trim,
+    /// Converts strings holding a number into numbers.
+    ParseNumber,
+    /// Matches the string against a regular expression, and adds a field to the `target` object,
+    /// or to the root of the document, for every named capture group (`(?P<name>...)`) that
+    /// matched.
+    Regex {
+        /// Regular expression.
+        pattern: String,
+        /// Path of the object receiving the captures.
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
+    },
+    /// Matches the string against a grok pattern, and adds a field to the `target` object, or to
+    /// the root of the document, for every named grok pattern (`%{IP:client}`). A named pattern
+    /// may be converted to a number with the `int` and `float` suffixes (`%{INT:status:int}`).
+    Grok {
+        /// Grok pattern.
+        pattern: String,
+        /// Path of the object receiving the captures.
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
+    },
+}
+
+/// Compiled version of a [`FieldTransformEntry`].
+#[derive(Clone)]
+pub(crate) struct FieldTransformer {
+    field_path: Vec<String>,
+    kind: FieldTransformerKind,
+}
+
+#[derive(Clone)]
+enum FieldTransformerKind {
+    Default(JsonValue),
+    CopyTo(Vec<String>),
+    Rename(Vec<String>),
+    Lowercase,
+    Trim,
+    ParseNumber,
+    Extract {
+        regex: Regex,
+        conversions: HashMap<String, NumberConversion>,
+        /// Path of the object receiving the captures, empty for the root of the document.
+        target_path: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum NumberConversion {
+    Int,
+    Float,
+}
+
+/// Compiles the transforms of the doc mapping.
+pub(crate) fn build_field_transformers(
+    field_transform_entries: &[FieldTransformEntry],
+) -> anyhow::Result<Vec<FieldTransformer>> {
+    let mut field_transformers = Vec::with_capacity(field_transform_entries.len());
+    for field_transform_entry in field_transform_entries {
+        let field_path = parse_field_path(&field_transform_entry.field)?;
+        let kind = match &field_transform_entry.transform {
+            FieldTransform::Default { value } => FieldTransformerKind::Default(value.clone()),
+            FieldTransform::CopyTo { target } => {
+                FieldTransformerKind::CopyTo(parse_target_path(&field_path, target)?)
+            }
+            FieldTransform::Rename { target } => {
+                FieldTransformerKind::Rename(parse_target_path(&field_path, target)?)
+            }
+            FieldTransform::Lowercase => FieldTransformerKind::Lowercase,
+            FieldTransform::Trim => FieldTransformerKind::Trim,
+            FieldTransform::ParseNumber => FieldTransformerKind::ParseNumber,
+            FieldTransform::Regex { pattern, target } => FieldTransformerKind::Extract {
+                regex: compile_extraction_regex(pattern, &field_path, target.is_some())?,
+                conversions: HashMap::new(),
+                target_path: parse_extraction_target_path(&field_path, target.as_deref())?,
+            },
+            FieldTransform::Grok { pattern, target } => {
+                let mut conversions = HashMap::new();
+                let regex_pattern = grok_to_regex(pattern, &mut conversions, 0)
+                    .with_context(|| format!("Invalid grok pattern `{}`.", pattern))?;
+                FieldTransformerKind::Extract {
+                    regex: compile_extraction_regex(&regex_pattern, &field_path, target.is_some())?,
+                    conversions,
+                    target_path: parse_extraction_target_path(&field_path, target.as_deref())?,
+                }
+            }
+        };
+        field_transformers.push(FieldTransformer { field_path, kind });
+    }
+    Ok(field_transformers)
+}
+
+/// Applies the transforms, in order, to a JSON document.
+pub(crate) fn apply_field_transformers(
+    field_transformers: &[FieldTransformer],
+    json_obj: &mut serde_json::Map<String, JsonValue>,
+) -> Result<(), DocParsingError> {
+    for field_transformer in field_transformers {
+        field_transformer.apply(json_obj)?;
+    }
+    Ok(())
+}
+
+impl FieldTransformer {
+    fn apply(
+        &self,
+        json_obj: &mut serde_json::Map<String, JsonValue>,
+    ) -> Result<(), DocParsingError> {
+        match &self.kind {
+            FieldTransformerKind::Default(default_value) => {
+                let is_missing = get_value_mut(json_obj, &self.field_path)
+                    .map(|json_val| json_val.is_null())
+                    .unwrap_or(true);
+                if is_missing {
+                    insert_value(json_obj, &self.field_path, default_value.clone())?;
+                }
+            }
+            FieldTransformerKind::CopyTo(target_path) => {
+                if let Some(json_val) = get_value_mut(json_obj, &self.field_path).cloned() {
+                    let target_json_val = match take_value(json_obj, target_path) {
+                        None | Some(JsonValue::Null) => json_val,
+                        Some(JsonValue::Array(mut target_json_vals)) => {
+                            append_values(&mut target_json_vals, json_val);
+                            JsonValue::Array(target_json_vals)
+                        }
+                        Some(existing_json_val) => {
+                            let mut target_json_vals = vec![existing_json_val];
+                            append_values(&mut target_json_vals, json_val);
+                            JsonValue::Array(target_json_vals)
+                        }
+                    };
+                    insert_value(json_obj, target_path, target_json_val)?;
+                }
+            }
+            FieldTransformerKind::Rename(target_path) => {
+                if let Some(json_val) = take_value(json_obj, &self.field_path) {
+                    insert_value(json_obj, target_path, json_val)?;
+                }
+            }
+            FieldTransformerKind::Lowercase => {
+                self.map_strings(json_obj, |text| Ok(JsonValue::String(text.to_lowercase())))?;
+            }
+            FieldTransformerKind::Trim => {
+                self.map_strings(json_obj, |text| {
+                    Ok(JsonValue::String(text.trim().to_string()))
+                })?;
+            }
+            FieldTransformerKind::ParseNumber => {
+                self.map_strings(json_obj, |text| parse_number(text, NumberConversion::Float))?;
+            }
+            FieldTransformerKind::Extract {
+                regex,
+                conversions,
+                target_path,
+            } => {
+                let text = match get_value_mut(json_obj, &self.field_path) {
+                    Some(JsonValue::String(text)) => text.clone(),
+                    _ => return Ok(()),
+                };
+                // A string that does not match the pattern is left untouched.
+                if let Some(captures) = regex.captures(&text) {
+                    for capture_name in regex.capture_names().flatten() {
+                        if let Some(capture) = captures.name(capture_name) {
+                            let captured_json_val = match conversions.get(capture_name) {
+                                Some(conversion) => parse_number(capture.as_str(), *conversion)
+                                    .map_err(|err_msg| {
+                                        DocParsingError::ValueError(
+                                            capture_name.to_string(),
+                                            err_msg,
+                                        )
+                                    })?,
+                                None => JsonValue::String(capture.as_str().to_string()),
+                            };
+                            let mut capture_path = target_path.clone();
+                            capture_path.push(capture_name.to_string());
+                            // Captures never overwrite the values of the document.
+                            if get_value_mut(json_obj, &capture_path)
+                                .map(|json_val| !json_val.is_null())
+                                .unwrap_or(false)
+                            {
+                                return Err(DocParsingError::ValueError(
+                                    capture_path.join("."),
+                                    "The field already has a value and cannot receive the \
+                                     capture. Set the `target` of the transform to extract the \
+                                     captures into an object."
+                                        .to_string(),
+                                ));
+                            }
+                            insert_value(json_obj, &capture_path, captured_json_val)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the strings of the field, or of the array held by the field.
+    fn map_strings<F>(
+        &self,
+        json_obj: &mut serde_json::Map<String, JsonValue>,
+        map_fn: F,
+    ) -> Result<(), DocParsingError>
+    where
+        F: Fn(&str) -> Result<JsonValue, String>,
+    {
+        let json_val = if let Some(json_val) = get_value_mut(json_obj, &self.field_path) {
+            json_val
+        } else {
+            return Ok(());
+        };
+        let map_json_val = |json_val: &mut JsonValue| -> Result<(), DocParsingError> {
+            if let JsonValue::String(text) = json_val {
+                *json_val = map_fn(text).map_err(|err_msg| {
+                    DocParsingError::ValueError(self.field_path.join("."), err_msg)
+                })?;
+            }
+            Ok(())
+        };
+        if let JsonValue::Array(json_vals) = json_val {
+            for json_val in json_vals {
+                map_json_val(json_val)?;
+            }
+            return Ok(());
+        }
+        map_json_val(json_val)
+    }
+}
+
+fn append_values(json_vals: &mut Vec<JsonValue>, json_val: JsonValue) {
+    if let JsonValue::Array(other_json_vals) = json_val {
+        json_vals.extend(other_json_vals);
+    } else {
+        json_vals.push(json_val);
+    }
+}
+
+/// Parses a number, as an integer if possible unless `conversion` is `Float`.
+fn parse_number(text: &str, conversion: NumberConversion) -> Result<JsonValue, String> {
+    let trimmed_text = text.trim();
+    if conversion == NumberConversion::Int || !trimmed_text.contains(['.', 'e', 'E']) {
+        if let Ok(int_val) = trimmed_text.parse::<i64>() {
+            return Ok(JsonValue::from(int_val));
+        }
+        if let Ok(uint_val) = trimmed_text.parse::<u64>() {
+            return Ok(JsonValue::from(uint_val));
+        }
+        if conversion == NumberConversion::Int {
+            return Err(format!("Expected an integer, got '{}'.", text));
+        }
+    }
+    trimmed_text
+        .parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(JsonValue::Number)
+        .ok_or_else(|| format!("Expected a number, got '{}'.", text))
+}
+
+/// Splits a field path on unescaped dots.
+fn parse_field_path(field_path_str: &str) -> anyhow::Result<Vec<String>> {
+    let mut field_path = Vec::new();
+    let mut key = String::new();
+    let mut chars = field_path_str.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => key.extend(chars.next()),
+            '.' => field_path.push(std::mem::take(&mut key)),
+            _ => key.push(c),
+        }
+    }
+    field_path.push(key);
+    if field_path.iter().any(String::is_empty) {
+        bail!("Invalid field path `{}`.", field_path_str);
+    }
+    Ok(field_path)
+}
+
+fn parse_target_path(field_path: &[String], target: &str) -> anyhow::Result<Vec<String>> {
+    let target_path = parse_field_path(target)?;
+    if target_path == field_path {
+        bail!(
+            "The target field `{}` must be different from the transformed field.",
+            target
+        );
+    }
+    Ok(target_path)
+}
+
+fn parse_extraction_target_path(
+    field_path: &[String],
+    target_opt: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    match target_opt {
+        Some(target) => parse_target_path(field_path, target),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Compiles the regular expression of a `regex` or `grok` transform. Captures extracted to the
+/// root of the document must not overwrite the transformed field.
+fn compile_extraction_regex(
+    pattern: &str,
+    field_path: &[String],
+    has_target: bool,
+) -> anyhow::Result<Regex> {
+    let regex = Regex::new(pattern)
+        .with_context(|| format!("Invalid regular expression `{}`.", pattern))?;
+    if regex.capture_names().flatten().next().is_none() {
+        bail!(
+            "The pattern `{}` does not have any named capture group.",
+            pattern
+        );
+    }
+    if !has_target {
+        if let Some(capture_name) = regex
+            .capture_names()
+            .flatten()
+            .find(|capture_name| *capture_name == field_path[0])
+        {
+            bail!(
+                "The capture `{}` would overwrite the transformed field. Set the `target` of the \
+                 transform to extract the captures into an object.",
+                capture_name
+            );
+        }
+    }
+    Ok(regex)
+}
+
+fn get_value_mut<'a>(
+    json_obj: &'a mut serde_json::Map<String, JsonValue>,
+    field_path: &[String],
+) -> Option<&'a mut JsonValue> {
+    let (last_key, parent_keys) = field_path.split_last()?;
+    let mut current_obj = json_obj;
+    for key in parent_keys {
+        current_obj = current_obj.get_mut(key)?.as_object_mut()?;
+    }
+    current_obj.get_mut(last_key)
+}
+
+fn take_value(
+    json_obj: &mut serde_json::Map<String, JsonValue>,
+    field_path: &[String],
+) -> Option<JsonValue> {
+    let (last_key, parent_keys) = field_path.split_last()?;
+    let mut current_obj = json_obj;
+    for key in parent_keys {
+        current_obj = current_obj.get_mut(key)?.as_object_mut()?;
+    }
+    current_obj.remove(last_key)
+}
+
+/// Inserts a value, creating the missing parent objects.
+fn insert_value(
+    json_obj: &mut serde_json::Map<String, JsonValue>,
+    field_path: &[String],
+    json_val: JsonValue,
+) -> Result<(), DocParsingError> {
+    let (last_key, parent_keys) = field_path
+        .split_last()
+        .expect("Field paths should not be empty.");
+    let mut current_obj = json_obj;
+    for key in parent_keys {
+        current_obj = current_obj
+            .entry(key.clone())
+            .or_insert_with(|| JsonValue::Object(Default::default()))
+            .as_object_mut()
+            .ok_or_else(|| {
+                DocParsingError::ValueError(
+                    field_path.join("."),
+                    format!("Expected JSON object for '{}'.", key),
+                )
+            })?;
+    }
+    current_obj.insert(last_key.clone(), json_val);
+    Ok(())
+}
+
+/// Built-in grok patterns.
+static GROK_PATTERNS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    HashMap::from([
+        ("USERNAME", r"[a-zA-Z0-9._-]+"),
+        ("USER", r"%{USERNAME}"),
+        ("INT", r"(?:[+-]?[0-9]+)"),
+        ("POSINT", r"\b[1-9][0-9]*\b"),
+        ("NONNEGINT", r"\b[0-9]+\b"),
+        (
+            "NUMBER",
+            r"(?:[+-]?(?:[0-9]+(?:\.[0-9]*)?|\.[0-9]+)(?:[eE][+-]?[0-9]+)?)",
+        ),
+        ("WORD", r"\b\w+\b"),
+        ("NOTSPACE", r"\S+"),
+        ("SPACE", r"\s*"),
+        ("DATA", r".*?"),
+        ("GREEDYDATA", r".*"),
+        ("QUOTEDSTRING", r#""(?:[^"\\]|\\.)*""#),
+        ("QS", r"%{QUOTEDSTRING}"),
+        (
+            "UUID",
+            r"[A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}",
+        ),
+        (
+            "IPV4",
+            r"(?:(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.){3}(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)",
+        ),
+        (
+            "IPV6",
+            r"(?:[0-9A-Fa-f]{0,4}:){2,7}(?:[0-9A-Fa-f]{1,4}|%{IPV4})?",
+        ),
+        ("IP", r"(?:%{IPV6}|%{IPV4})"),
+        (
+            "HOSTNAME",
+            r"\b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*\.?\b",
+        ),
+        ("IPORHOST", r"(?:%{IP}|%{HOSTNAME})"),
+        ("URIPATH", r"(?:/[^\s?#]*)+"),
+        ("URIPARAM", r"\?\S*"),
+        ("URIPATHPARAM", r"%{URIPATH}(?:%{URIPARAM})?"),
+        (
+            "LOGLEVEL",
+            r"(?:[Tt]race|TRACE|[Dd]ebug|DEBUG|[Nn]otice|NOTICE|[Ii]nfo|INFO|[Ww]arn(?:ing)?|WARN(?:ING)?|[Ee]rr(?:or)?|ERR(?:OR)?|[Cc]rit(?:ical)?|CRIT(?:ICAL)?|[Ff]atal|FATAL|[Ee]merg(?:ency)?|EMERG(?:ENCY)?)",
+        ),
+        (
+            "TIMESTAMP_ISO8601",
+            r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?",
+        ),
+        ("HTTPDATE", r"\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4}"),
+    ])
+});
+
+/// Expands the `%{PATTERN}`, `%{PATTERN:name}` and `%{PATTERN:name:int|float}` references of
+/// a grok pattern into a regular expression.
+fn grok_to_regex(
+    grok_pattern: &str,
+    conversions: &mut HashMap<String, NumberConversion>,
+    depth: usize,
+) -> anyhow::Result<String> {
+    static GROK_REFERENCE_PTN: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"%\{(?P<pattern>[A-Z0-9_]+)(?::(?P<name>[a-zA-Z_][a-zA-Z0-9_]*))?(?::(?P<conversion>int|float))?\}").unwrap()
+    });
+    if depth > GROK_MAX_DEPTH {
+        bail!("Grok patterns are nested too deeply.");
+    }
+    let mut regex_pattern = String::with_capacity(grok_pattern.len());
+    let mut last_match_end = 0;
+    for captures in GROK_REFERENCE_PTN.captures_iter(grok_pattern) {
+        let reference = captures
+            .get(0)
+            .expect("Capture group 0 should always be set.");
+        regex_pattern.push_str(&grok_pattern[last_match_end..reference.start()]);
+        last_match_end = reference.end();
+        let pattern_name = &captures["pattern"];
+        let pattern = GROK_PATTERNS
+            .get(pattern_name)
+            .with_context(|| format!("Unknown grok pattern `{}`.", pattern_name))?;
+        let expanded_pattern = grok_to_regex(pattern, conversions, depth + 1)?;
+        if let Some(name) = captures.name("name") {
+            regex_pattern.push_str(&format!("(?P<{}>{})", name.as_str(), expanded_pattern));
+            match captures
+                .name("conversion")
+                .map(|conversion| conversion.as_str())
+            {
+                Some("int") => {
+                    conversions.insert(name.as_str().to_string(), NumberConversion::Int);
+                }
+                Some("float") => {
+                    conversions.insert(name.as_str().to_string(), NumberConversion::Float);
+                }
+                _ => {}
+            }
+        } else {
+            regex_pattern.push_str(&expanded_pattern);
+        }
+    }
+    regex_pattern.push_str(&grok_pattern[last_match_end..]);
+    Ok(regex_pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn apply_transforms(
+        transforms_json: JsonValue,
+        doc_json: JsonValue,
+    ) -> Result<JsonValue, DocParsingError> {
+        let field_transform_entries: Vec<FieldTransformEntry> =
+            serde_json::from_value(transforms_json).unwrap();
+        let field_transformers = build_field_transformers(&field_transform_entries).unwrap();
+        let mut json_obj = doc_json.as_object().unwrap().clone();
+        apply_field_transformers(&field_transformers, &mut json_obj)?;
+        Ok(JsonValue::Object(json_obj))
+    }
+
+    #[test]
+    fn test_deserialize_field_transform_entry() {
+        let field_transform_entry: FieldTransformEntry =
+            serde_json::from_str(r#"{"field": "level", "type": "default", "value": "info"}"#)
+                .unwrap();
+        assert_eq!(
+            field_transform_entry,
+            FieldTransformEntry {
+                field: "level".to_string(),
+                transform: FieldTransform::Default {
+                    value: json!("info")
+                },
+            }
+        );
+        let field_transform_entry: FieldTransformEntry =
+            serde_json::from_str(r#"{"field": "level", "type": "lowercase"}"#).unwrap();
+        assert_eq!(field_transform_entry.transform, FieldTransform::Lowercase);
+    }
+
+    #[test]
+    fn test_default_transform() {
+        let transforms = json!([
+            {"field": "level", "type": "default", "value": "info"},
+            {"field": "http.status", "type": "default", "value": 200}
+        ]);
+        assert_eq!(
+            apply_transforms(transforms.clone(), json!({"level": null})).unwrap(),
+            json!({"level": "info", "http": {"status": 200}})
+        );
+        assert_eq!(
+            apply_transforms(
+                transforms,
+                json!({"level": "warn", "http": {"status": 404}})
+            )
+            .unwrap(),
+            json!({"level": "warn", "http": {"status": 404}})
+        );
+    }
+
+    #[test]
+    fn test_copy_to_and_rename_transforms() {
+        let transforms = json!([
+            {"field": "title", "type": "copy_to", "target": "all"},
+            {"field": "body", "type": "copy_to", "target": "all"},
+            {"field": "msg", "type": "rename", "target": "message.text"}
+        ]);
+        assert_eq!(
+            apply_transforms(
+                transforms,
+                json!({"title": "hello", "body": ["happy", "world"], "msg": "hi"})
+            )
+            .unwrap(),
+            json!({
+                "title": "hello",
+                "body": ["happy", "world"],
+                "all": ["hello", "happy", "world"],
+                "message": {"text": "hi"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_string_transforms() {
+        let transforms = json!([
+            {"field": "level", "type": "trim"},
+            {"field": "level", "type": "lowercase"},
+            {"field": "sizes", "type": "parse_number"},
+            {"field": "ratio", "type": "parse_number"}
+        ]);
+        assert_eq!(
+            apply_transforms(
+                transforms.clone(),
+                json!({"level": " WARN ", "sizes": ["12", "-3", 4], "ratio": "0.5"})
+            )
+            .unwrap(),
+            json!({"level": "warn", "sizes": [12, -3, 4], "ratio": 0.5})
+        );
+        assert_eq!(
+            apply_transforms(transforms, json!({"ratio": "half"})).unwrap_err(),
+            DocParsingError::ValueError(
+                "ratio".to_string(),
+                "Expected a number, got 'half'.".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_regex_transform() {
+        let transforms = json!([
+            {"field": "line", "type": "regex", "pattern": r"^(?P<method>[A-Z]+) (?P<path>\S+)"}
+        ]);
+        assert_eq!(
+            apply_transforms(
+                transforms.clone(),
+                json!({"line": "GET /index.html HTTP/1.1"})
+            )
+            .unwrap(),
+            json!({"line": "GET /index.html HTTP/1.1", "method": "GET", "path": "/index.html"})
+        );
+        assert_eq!(
+            apply_transforms(transforms.clone(), json!({"line": "not a request"})).unwrap(),
+            json!({"line": "not a request"})
+        );
+        assert_eq!(
+            apply_transforms(
+                transforms,
+                json!({"line": "GET /index.html HTTP/1.1", "method": "POST"})
+            )
+            .unwrap_err(),
+            DocParsingError::ValueError(
+                "method".to_string(),
+                "The field already has a value and cannot receive the capture. Set the `target` \
+                 of the transform to extract the captures into an object."
+                    .to_string()
+            )
+        );
+        let transforms = json!([{
+            "field": "line",
+            "type": "regex",
+            "pattern": r"^(?P<method>[A-Z]+) (?P<path>\S+)",
+            "target": "request"
+        }]);
+        assert_eq!(
+            apply_transforms(
+                transforms,
+                json!({"line": "GET /index.html HTTP/1.1", "method": "POST"})
+            )
+            .unwrap(),
+            json!({
+                "line": "GET /index.html HTTP/1.1",
+                "method": "POST",
+                "request": {"method": "GET", "path": "/index.html"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_grok_transform() {
+        let transforms = json!([{
+            "field": "message",
+            "type": "grok",
+            "pattern": "%{IP:client} %{WORD:method} %{URIPATHPARAM:request} %{INT:status:int} %{NUMBER:duration:float}"
+        }]);
+        assert_eq!(
+            apply_transforms(
+                transforms,
+                json!({"message": "55.3.244.1 GET /index.html?q=1 200 0.043"})
+            )
+            .unwrap(),
+            json!({
+                "message": "55.3.244.1 GET /index.html?q=1 200 0.043",
+                "client": "55.3.244.1",
+                "method": "GET",
+                "request": "/index.html?q=1",
+                "status": 200,
+                "duration": 0.043
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_field_transformers_errors() {
+        let build_aux = |transforms_json: JsonValue| {
+            let field_transform_entries: Vec<FieldTransformEntry> =
+                serde_json::from_value(transforms_json).unwrap();
+            build_field_transformers(&field_transform_entries)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            build_aux(json!([{"field": "line", "type": "grok", "pattern": "%{FOO:bar}"}])),
+            "Invalid grok pattern `%{FOO:bar}`."
+        );
+        assert_eq!(
+            build_aux(json!([{"field": "line", "type": "regex", "pattern": "[a-z]+"}])),
+            "The pattern `[a-z]+` does not have any named capture group."
+        );
+        assert_eq!(
+            build_aux(json!([{"field": "a..b", "type": "trim"}])),
+            "Invalid field path `a..b`."
+        );
+        assert_eq!(
+            build_aux(json!([{"field": "a", "type": "rename", "target": "a"}])),
+            "The target field `a` must be different from the transformed field."
+        );
+        assert_eq!(
+            build_aux(json!([{"field": "line", "type": "grok", "pattern": "%{WORD:line}"}])),
+            "The capture `line` would overwrite the transformed field. Set the `target` of the \
+             transform to extract the captures into an object."
+        );
+    }
+
+    #[test]
+    fn test_parse_field_path() {
+        assert_eq!(
+            parse_field_path(r"http.request\.id").unwrap(),
+            vec!["http".to_string(), "request.id".to_string()]
+        );
+    }
+}
@@ -26,22 +26,29 @@ use fail::fail_point;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_config::IndexingSettings;
-use quickwit_doc_mapper::{DocMapper, DocParsingError, SortBy, QUICKWIT_TOKENIZER_MANAGER};
+use quickwit_doc_mapper::{DocMapper, DocParsingError, InputFormat, SortBy};
+use quickwit_metastore::checkpoint::{PartitionId, Position};
 use quickwit_metastore::Metastore;
 use tantivy::schema::{Field, Value};
 use tantivy::store::{Compressor, ZstdCompressor};
 use tantivy::{Document, IndexBuilder, IndexSettings, IndexSortByField};
+use time::OffsetDateTime;
 use tokio::runtime::Handle;
 use tracing::{info, warn};
 
 use crate::actors::Packager;
 use crate::models::{IndexedSplit, IndexedSplitBatch, IndexingDirectory, RawDocBatch};
+use crate::{DeadLetterQueue, DeadLetterRecord};
 
 #[derive(Debug)]
 struct CommitTimeout {
     split_id: String,
 }
 
+/// Commits the current split without waiting for the commit timeout.
+#[derive(Debug)]
+pub(crate) struct ForceCommit;
+
 #[derive(Clone, Default, Debug, Eq, PartialEq)]
 pub struct IndexerCounters {
     /// Overall number of documents received, partitioned
@@ -84,16 +91,19 @@ impl IndexerCounters {
 
 struct IndexerState {
     index_id: String,
+    source_id: String,
+    input_format: InputFormat,
     doc_mapper: Arc<dyn DocMapper>,
     indexing_directory: IndexingDirectory,
     indexing_settings: IndexingSettings,
     timestamp_field_opt: Option<Field>,
     sort_by_field_opt: Option<IndexSortByField>,
+    dead_letter_queue_opt: Option<DeadLetterQueue>,
 }
 
 enum PrepareDocumentOutcome {
-    ParsingError,
-    MissingField,
+    ParsingError(DocParsingError),
+    MissingField(DocParsingError),
     Document {
         document: Document,
         timestamp_opt: Option<i64>,
@@ -113,7 +123,7 @@ impl IndexerState {
         let index_builder = IndexBuilder::new()
             .settings(index_settings)
             .schema(schema)
-            .tokenizers(QUICKWIT_TOKENIZER_MANAGER.clone());
+            .tokenizers(self.doc_mapper.tokenizer_manager().clone());
         let indexed_split = IndexedSplit::new_in_dir(
             self.index_id.clone(),
             self.indexing_directory.scratch_directory.clone(),
@@ -153,16 +163,34 @@ impl IndexerState {
         Ok(current_index_split)
     }
 
+    /// Parses a document. The string values of documents converted from untyped input formats,
+    /// such as CSV, are coerced into the types of the doc mapping. Documents that are not valid
+    /// JSON objects are reported as parse errors by the doc mapper.
+    fn parse_document(&self, doc_json: String) -> Result<Document, DocParsingError> {
+        if self.input_format == InputFormat::Json {
+            return self.doc_mapper.doc_from_json(doc_json);
+        }
+        match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&doc_json) {
+            Ok(mut json_obj) => {
+                self.doc_mapper.coerce_string_values(&mut json_obj);
+                self.doc_mapper.doc_from_json_obj(json_obj)
+            }
+            Err(_) => self.doc_mapper.doc_from_json(doc_json),
+        }
+    }
+
     fn prepare_document(&self, doc_json: String) -> PrepareDocumentOutcome {
         // Parse the document
-        let doc_parsing_result = self.doc_mapper.doc_from_json(doc_json);
+        let doc_parsing_result = self.parse_document(doc_json);
         let document = match doc_parsing_result {
             Ok(doc) => doc,
             Err(doc_parsing_error) => {
                 warn!(err=?doc_parsing_error);
                 return match doc_parsing_error {
-                    DocParsingError::RequiredFastField(_) => PrepareDocumentOutcome::MissingField,
-                    _ => PrepareDocumentOutcome::ParsingError,
+                    DocParsingError::RequiredFastField(_) => {
+                        PrepareDocumentOutcome::MissingField(doc_parsing_error)
+                    }
+                    _ => PrepareDocumentOutcome::ParsingError(doc_parsing_error),
                 };
             }
         };
@@ -188,6 +216,26 @@ impl IndexerState {
         }
     }
 
+    /// Returns the dead-letter record of a rejected document, or `None` if the index has no
+    /// dead-letter queue.
+    fn make_dead_letter_record(
+        &self,
+        raw_doc_opt: Option<String>,
+        doc_position_opt: Option<&(PartitionId, Position)>,
+        doc_parsing_error: DocParsingError,
+    ) -> Option<DeadLetterRecord> {
+        let raw_doc = raw_doc_opt?;
+        Some(DeadLetterRecord {
+            index_id: self.index_id.clone(),
+            source_id: self.source_id.clone(),
+            partition_id: doc_position_opt.map(|(partition_id, _)| partition_id.0.to_string()),
+            position: doc_position_opt.map(|(_, position)| position.as_str().to_string()),
+            error: doc_parsing_error.to_string(),
+            doc: raw_doc,
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        })
+    }
+
     async fn process_batch(
         &self,
         batch: RawDocBatch,
@@ -202,19 +250,34 @@ impl IndexerState {
             .checkpoint_delta
             .extend(batch.checkpoint_delta)
             .with_context(|| "Batch delta does not follow indexer checkpoint")?;
-        for doc_json in batch.docs {
+        let mut dead_letter_records = Vec::new();
+        for (doc_idx, doc_json) in batch.docs.into_iter().enumerate() {
             counters.overall_num_bytes += doc_json.len() as u64;
             indexed_split.docs_size_in_bytes += doc_json.len() as u64;
+            let raw_doc_opt = self
+                .dead_letter_queue_opt
+                .as_ref()
+                .map(|_| doc_json.clone());
             let prepared_doc = {
                 let _protect_zone = ctx.protect_zone();
                 self.prepare_document(doc_json)
             };
             match prepared_doc {
-                PrepareDocumentOutcome::ParsingError => {
+                PrepareDocumentOutcome::ParsingError(doc_parsing_error) => {
                     counters.num_parse_errors += 1;
+                    dead_letter_records.extend(self.make_dead_letter_record(
+                        raw_doc_opt,
+                        batch.doc_positions.get(doc_idx),
+                        doc_parsing_error,
+                    ));
                 }
-                PrepareDocumentOutcome::MissingField => {
+                PrepareDocumentOutcome::MissingField(doc_parsing_error) => {
                     counters.num_missing_fields += 1;
+                    dead_letter_records.extend(self.make_dead_letter_record(
+                        raw_doc_opt,
+                        batch.doc_positions.get(doc_idx),
+                        doc_parsing_error,
+                    ));
                 }
                 PrepareDocumentOutcome::Document {
                     document,
@@ -235,6 +298,15 @@ impl IndexerState {
             }
             ctx.record_progress();
         }
+        if let Some(dead_letter_queue) = &self.dead_letter_queue_opt {
+            let _protect_guard = ctx.protect_zone();
+            dead_letter_queue
+                .push(&dead_letter_records)
+                .await
+                .with_context(|| {
+                    "Failed to send the rejected documents to the dead-letter queue."
+                })?;
+        }
         Ok(())
     }
 }
@@ -312,6 +384,20 @@ impl Handler<CommitTimeout> for Indexer {
     }
 }
 
+#[async_trait]
+impl Handler<ForceCommit> for Indexer {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _force_commit: ForceCommit,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.send_to_packager(CommitTrigger::Forced, ctx).await?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<RawDocBatch> for Indexer {
     type Reply = ();
@@ -330,16 +416,20 @@ enum CommitTrigger {
     Timeout,
     NoMoreDocs,
     NumDocsLimit,
+    Forced,
 }
 
 impl Indexer {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         index_id: String,
         doc_mapper: Arc<dyn DocMapper>,
         source_id: String,
+        input_format: InputFormat,
         metastore: Arc<dyn Metastore>,
         indexing_directory: IndexingDirectory,
         indexing_settings: IndexingSettings,
+        dead_letter_queue_opt: Option<DeadLetterQueue>,
         packager_mailbox: Mailbox<Packager>,
     ) -> Self {
         let schema = doc_mapper.schema();
@@ -354,11 +444,14 @@ impl Indexer {
         Self {
             indexer_state: IndexerState {
                 index_id,
+                source_id: source_id.clone(),
+                input_format,
                 doc_mapper,
                 indexing_directory,
                 indexing_settings,
                 timestamp_field_opt,
                 sort_by_field_opt,
+                dead_letter_queue_opt,
             },
             packager_mailbox,
             current_split_opt: None,
@@ -498,9 +591,11 @@ mod tests {
             "test-index".to_string(),
             doc_mapper,
             "source-id".to_string(),
+            InputFormat::Json,
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
         );
         let universe = Universe::new();
@@ -513,6 +608,7 @@ mod tests {
                         r#"{"body": "happy2", "timestamp": 1628837062, "response_date": 1652866573232, "response_time": 13, "response_payload": "YWJj"}"#.to_string(), // ok
                         "{".to_string(),                    // invalid json
                     ],
+                doc_positions: Vec::new(),
                 checkpoint_delta: CheckpointDelta::from(0..4),
             })
             .await?;
@@ -532,6 +628,7 @@ mod tests {
             .send_message(
                 RawDocBatch {
                     docs: vec![r#"{"body": "happy3", "timestamp": 1628837062, "response_date": 1652866573227, "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
+                    doc_positions: Vec::new(),
                     checkpoint_delta: CheckpointDelta::from(4..5),
                 }
             )
@@ -580,9 +677,11 @@ mod tests {
             "test-index".to_string(),
             doc_mapper,
             "source-id".to_string(),
+            InputFormat::Json,
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
         );
         let universe = Universe::new();
@@ -591,6 +690,7 @@ mod tests {
             .send_message(
                 RawDocBatch {
                     docs: vec![r#"{"body": "happy", "timestamp": 1628837062, "response_date": 1652866573228, "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
+                    doc_positions: Vec::new(),
                     checkpoint_delta: CheckpointDelta::from(0..1),
                 }
             )
@@ -647,9 +747,11 @@ mod tests {
             "test-index".to_string(),
             doc_mapper,
             "source-id".to_string(),
+            InputFormat::Json,
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
         );
         let universe = Universe::new();
@@ -658,6 +760,7 @@ mod tests {
             .send_message(
                 RawDocBatch {
                     docs: vec![r#"{"body": "happy", "timestamp": 1628837062, "response_date": 1652866573227, "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
+                    doc_positions: Vec::new(),
                     checkpoint_delta: CheckpointDelta::from(0..1),
                 }
             )
@@ -688,4 +791,101 @@ mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_indexer_sends_rejected_docs_to_dead_letter_queue() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.timestamp_field = Some("timestamp".to_string());
+        let temp_dir = tempfile::tempdir()?;
+        let dead_letter_queue = DeadLetterQueue::create(
+            &quickwit_config::DeadLetterQueueConfig::File,
+            "test-index",
+            temp_dir.path(),
+            None,
+        )
+        .await?;
+        let (mailbox, _inbox) = create_test_mailbox();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            InputFormat::Json,
+            Arc::new(MockMetastore::default()),
+            indexing_directory,
+            indexing_settings,
+            Some(dead_letter_queue.clone()),
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec![
+                        r#"{"body": "happy", "response_date": 1652866573228, "response_time": 12, "response_payload": "YWJj"}"#.to_string(), // missing timestamp
+                        r#"{"body": "happy", "timestamp": 1628837062, "response_date": 1652866573229, "response_time": 2, "response_payload": "YWJj"}"#.to_string(), // ok
+                        "{".to_string(),                    // invalid json
+                    ],
+                doc_positions: (0..3u64)
+                    .map(|offset| (PartitionId::default(), Position::from(offset)))
+                    .collect(),
+                checkpoint_delta: CheckpointDelta::from(0..3),
+            })
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_invalid_docs(), 2);
+        let records = dead_letter_queue.read_records().await?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].index_id, "test-index");
+        assert_eq!(records[0].source_id, "source-id");
+        assert_eq!(records[0].partition_id.as_deref(), Some(""));
+        assert_eq!(records[0].position.as_deref(), Some("00000000000000000000"));
+        assert!(records[0]
+            .error
+            .contains("must contain field \"timestamp\""));
+        assert_eq!(records[1].doc, "{");
+        assert_eq!(records[1].position.as_deref(), Some("00000000000000000002"));
+        assert!(records[1].error.contains("not a valid JSON object"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_coerces_converted_documents() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.timestamp_field = Some("timestamp".to_string());
+        let (mailbox, _inbox) = create_test_mailbox();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            InputFormat::Csv,
+            Arc::new(MockMetastore::default()),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec![
+                        r#"{"body": "happy", "timestamp": "1628837062", "response_date": "1652866573229", "response_time": "2.5", "response_payload": "YWJj"}"#.to_string(), // ok
+                        r#"{"body": "happy", "timestamp": "yesterday", "response_date": "1652866573229", "response_time": "2.5", "response_payload": "YWJj"}"#.to_string(), // invalid timestamp
+                        "1628837062,happy".to_string(), // failed conversion
+                    ],
+                doc_positions: Vec::new(),
+                checkpoint_delta: CheckpointDelta::from(0..3),
+            })
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_valid_docs, 1);
+        assert_eq!(indexer_counters.num_parse_errors, 2);
+        Ok(())
+    }
 }
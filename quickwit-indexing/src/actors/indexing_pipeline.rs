@@ -35,6 +35,7 @@ use quickwit_storage::Storage;
 use tokio::join;
 use tracing::{debug, error, info, info_span, instrument, Span};
 
+use crate::actors::indexer::ForceCommit;
 use crate::actors::merge_split_downloader::MergeSplitDownloader;
 use crate::actors::publisher::PublisherType;
 use crate::actors::sequencer::Sequencer;
@@ -42,10 +43,13 @@ use crate::actors::{
     GarbageCollector, Indexer, MergeExecutor, MergePlanner, NamedField, Packager, Publisher,
     Uploader,
 };
-use crate::models::{IndexingDirectory, IndexingStatistics, Observe};
+use crate::models::{
+    CommitPipeline, IndexingDirectory, IndexingStatistics, Observe, PartitionAssignment,
+    PausePipeline, RestartPipeline, ResumePipeline,
+};
 use crate::source::{quickwit_supported_sources, SourceActor};
 use crate::split_store::{IndexingSplitStore, IndexingSplitStoreParams};
-use crate::{MergePolicy, StableMultitenantWithTimestampMergePolicy};
+use crate::{DeadLetterQueue, MergePolicy, StableMultitenantWithTimestampMergePolicy};
 
 const MAX_RETRY_DELAY: Duration = Duration::from_secs(600); // 10 min.
 
@@ -86,6 +90,8 @@ pub struct IndexingPipeline {
     handlers: Option<IndexingPipelineHandler>,
     // Killswitch used for the actors in the pipeline. This is not the supervisor killswitch.
     kill_switch: KillSwitch,
+    // Whether the source is paused. A paused pipeline stays paused when it is respawned.
+    is_paused: bool,
 }
 
 #[async_trait]
@@ -120,6 +126,7 @@ impl IndexingPipeline {
             handlers: None,
             kill_switch: KillSwitch::default(),
             statistics: IndexingStatistics::default(),
+            is_paused: false,
         }
     }
 
@@ -383,9 +390,11 @@ impl IndexingPipeline {
             self.params.index_id.clone(),
             self.params.doc_mapper.clone(),
             self.params.source.source_id.clone(),
+            self.params.source.input_format(),
             self.params.metastore.clone(),
             self.params.indexing_directory.clone(),
             self.params.indexing_settings.clone(),
+            self.params.dead_letter_queue_opt.clone(),
             packager_mailbox,
         );
         let (indexer_mailbox, indexer_handler) = ctx
@@ -405,7 +414,11 @@ impl IndexingPipeline {
             .cloned()
             .unwrap_or_default(); // TODO Have a stricter check.
         let source = quickwit_supported_sources()
-            .load_source(self.params.source.clone(), source_checkpoint)
+            .load_source(
+                self.params.source.clone(),
+                source_checkpoint,
+                self.params.partition_assignment.clone(),
+            )
             .await?;
         let actor_source = SourceActor {
             source,
@@ -416,6 +429,9 @@ impl IndexingPipeline {
             .set_mailboxes(source_mailbox, source_inbox)
             .set_kill_switch(self.kill_switch.clone())
             .spawn();
+        if self.is_paused {
+            source_handler.pause().await;
+        }
 
         // Increment generation once we are sure there will be no spawning error.
         self.previous_generations_statistics = self.statistics.clone();
@@ -566,12 +582,85 @@ impl Handler<Spawn> for IndexingPipeline {
     }
 }
 
+#[async_trait]
+impl Handler<PausePipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: PausePipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        info!(index=%self.params.index_id, source=%self.params.source.source_id, "pause-indexing-pipeline");
+        self.is_paused = true;
+        if let Some(handlers) = self.handlers.as_ref() {
+            handlers.source.pause().await;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<ResumePipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: ResumePipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        info!(index=%self.params.index_id, source=%self.params.source.source_id, "resume-indexing-pipeline");
+        self.is_paused = false;
+        if let Some(handlers) = self.handlers.as_ref() {
+            handlers.source.resume().await;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<RestartPipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: RestartPipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        info!(index=%self.params.index_id, source=%self.params.source.source_id, "restart-indexing-pipeline");
+        self.terminate().await;
+        self.handle(Spawn::default(), ctx).await
+    }
+}
+
+#[async_trait]
+impl Handler<CommitPipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: CommitPipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if let Some(handlers) = self.handlers.as_ref() {
+            // The indexer may have exited in the meantime, in which case the pipeline is about to
+            // be respawned and there is nothing to commit.
+            let _ = ctx
+                .send_message(handlers.indexer.mailbox(), ForceCommit)
+                .await;
+        }
+        Ok(())
+    }
+}
+
 pub struct IndexingPipelineParams {
     pub index_id: String,
     pub doc_mapper: Arc<dyn DocMapper>,
     pub indexing_directory: IndexingDirectory,
     pub indexing_settings: IndexingSettings,
     pub source: SourceConfig,
+    pub partition_assignment: PartitionAssignment,
+    pub dead_letter_queue_opt: Option<DeadLetterQueue>,
     pub split_store_max_num_bytes: usize,
     pub split_store_max_num_splits: usize,
     pub metastore: Arc<dyn Metastore>,
@@ -579,9 +668,12 @@ pub struct IndexingPipelineParams {
 }
 
 impl IndexingPipelineParams {
+    #[allow(clippy::too_many_arguments)]
     pub async fn try_new(
         index_metadata: IndexMetadata,
         source: SourceConfig,
+        partition_assignment: PartitionAssignment,
+        dead_letter_queue_opt: Option<DeadLetterQueue>,
         indexing_dir_path: PathBuf,
         split_store_max_num_bytes: usize,
         split_store_max_num_splits: usize,
@@ -603,6 +695,8 @@ impl IndexingPipelineParams {
             indexing_directory,
             indexing_settings: index_metadata.indexing_settings,
             source,
+            partition_assignment,
+            dead_letter_queue_opt,
             split_store_max_num_bytes,
             split_store_max_num_splits,
             metastore,
@@ -710,6 +804,8 @@ mod tests {
             split_store_max_num_bytes: 10_000_000,
             split_store_max_num_splits: 100,
             source: source_config,
+            partition_assignment: PartitionAssignment::default(),
+            dead_letter_queue_opt: None,
             metastore: Arc::new(metastore),
             storage: Arc::new(RamStorage::default()),
         };
@@ -791,6 +887,8 @@ mod tests {
             split_store_max_num_bytes: 10_000_000,
             split_store_max_num_splits: 100,
             source,
+            partition_assignment: PartitionAssignment::default(),
+            dead_letter_queue_opt: None,
             metastore: Arc::new(metastore),
             storage: Arc::new(RamStorage::default()),
         };
@@ -21,6 +21,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use quickwit_actors::{
     Actor, ActorContext, ActorExitStatus, ActorHandle, Handler, Health, Mailbox, Observation,
@@ -29,19 +30,27 @@ use quickwit_actors::{
 use quickwit_config::{
     IndexerConfig, IngestApiSourceParams, SourceConfig, SourceParams, VecSourceParams,
 };
-use quickwit_ingest_api::IngestApiService;
+use quickwit_ingest_api::{add_doc, IngestApiService};
 use quickwit_metastore::{IndexMetadata, Metastore, MetastoreError};
-use quickwit_proto::ingest_api::CreateQueueIfNotExistsRequest;
+use quickwit_proto::ingest_api::{
+    CreateQueueIfNotExistsRequest, DocBatch, IngestRequest, IngestResponse,
+};
 use quickwit_storage::{StorageResolverError, StorageUriResolver};
 use serde::Serialize;
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::models::{
-    DetachPipeline, IndexingPipelineId, Observe, ObservePipeline, ShutdownPipeline,
-    SpawnMergePipeline, SpawnPipeline, SpawnPipelinesForIndex,
+    CommitPipeline, DetachPipeline, IndexerNodes, IndexingPipelineId, InspectDeadLetterQueue,
+    Observe, ObservePipeline, PartitionAssignment, PausePipeline, ReplayDeadLetterQueue,
+    RestartPipeline, ResumePipeline, ShutdownPipeline, SpawnMergePipeline, SpawnPipeline,
+    SpawnPipelinesForIndex, UpdateIndexerNodes,
+};
+use crate::source::has_local_files;
+use crate::{
+    DeadLetterQueue, DeadLetterRecord, IndexingPipeline, IndexingPipelineParams,
+    IndexingStatistics, DEAD_LETTER_DIR_NAME,
 };
-use crate::{IndexingPipeline, IndexingPipelineParams, IndexingStatistics};
 
 pub const INDEXING_DIR_NAME: &str = "indexing";
 
@@ -60,6 +69,10 @@ pub enum IndexingServiceError {
     MetastoreError(#[from] MetastoreError),
     #[error("Invalid params `{0}`.")]
     InvalidParams(anyhow::Error),
+    #[error("Index `{index_id}` has no dead-letter queue.")]
+    MissingDeadLetterQueue { index_id: String },
+    #[error("Dead-letter queue error `{0}`.")]
+    DeadLetterQueueError(anyhow::Error),
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize)]
@@ -71,6 +84,7 @@ pub struct IndexingServiceState {
 
 pub struct IndexingService {
     indexing_dir_path: PathBuf,
+    dead_letter_dir_path: PathBuf,
     split_store_max_num_bytes: usize,
     split_store_max_num_splits: usize,
     metastore: Arc<dyn Metastore>,
@@ -78,6 +92,10 @@ pub struct IndexingService {
     pipeline_handles: HashMap<IndexingPipelineId, ActorHandle<IndexingPipeline>>,
     state: IndexingServiceState,
     ingest_api_service: Option<Mailbox<IngestApiService>>,
+    /// Indexer nodes of the cluster, used to assign the sources and partitions to the current
+    /// node. When `None`, the node runs standalone and indexes every source. The pipelines
+    /// consuming shared partitions hold a reference to the nodes, which are updated in place.
+    indexer_nodes: Option<Arc<ArcSwap<IndexerNodes>>>,
 }
 
 impl IndexingService {
@@ -95,6 +113,7 @@ impl IndexingService {
     ) -> IndexingService {
         Self {
             indexing_dir_path: data_dir_path.join(INDEXING_DIR_NAME),
+            dead_letter_dir_path: data_dir_path.join(DEAD_LETTER_DIR_NAME),
             split_store_max_num_bytes: indexer_config.split_store_max_num_bytes.get_bytes()
                 as usize,
             split_store_max_num_splits: indexer_config.split_store_max_num_splits,
@@ -103,6 +122,54 @@ impl IndexingService {
             pipeline_handles: Default::default(),
             state: Default::default(),
             ingest_api_service,
+            indexer_nodes: None,
+        }
+    }
+
+    /// Returns the partitions of the source that the current node should index, or `None` if the
+    /// source is assigned to another node.
+    ///
+    /// Kafka partitions are spread over the indexer nodes, the ingest API queues are local to each
+    /// node, file sources are indexed by the nodes on which the files exist, and any other
+    /// source is indexed by a single node. Receivers have no pipeline of their own: they are run
+    /// by the receiver service of each node and write to the ingest API queue of the index.
+    async fn partition_assignment(
+        &self,
+        index_id: &str,
+        source: &SourceConfig,
+    ) -> Option<PartitionAssignment> {
+        if source.is_receiver() {
+            return None;
+        }
+        let indexer_nodes = if let Some(indexer_nodes) = &self.indexer_nodes {
+            indexer_nodes
+        } else {
+            return Some(PartitionAssignment::All);
+        };
+        match &source.source_params {
+            SourceParams::Kafka(_) => Some(PartitionAssignment::Shared {
+                index_id: index_id.to_string(),
+                source_id: source.source_id.clone(),
+                indexer_nodes: indexer_nodes.clone(),
+            }),
+            SourceParams::IngestApi(_) => Some(PartitionAssignment::All),
+            // The other nodes cannot tell whether the files exist on the current node. If the
+            // files are on a volume shared by several nodes, the checkpoint stored in the
+            // metastore prevents them from being indexed twice.
+            SourceParams::File(params) => {
+                if has_local_files(params).await {
+                    Some(PartitionAssignment::All)
+                } else {
+                    None
+                }
+            }
+            _ if indexer_nodes
+                .load()
+                .owns_source(index_id, &source.source_id) =>
+            {
+                Some(PartitionAssignment::All)
+            }
+            _ => None,
         }
     }
 
@@ -120,6 +187,39 @@ impl IndexingService {
         Ok(pipeline_handle)
     }
 
+    async fn shutdown_pipeline(&mut self, pipeline_id: &IndexingPipelineId) {
+        if let Some(pipeline_handle) = self.pipeline_handles.remove(pipeline_id) {
+            self.state.num_running_pipelines -= 1;
+            pipeline_handle.quit().await;
+        }
+    }
+
+    /// Forwards a message to a running pipeline.
+    async fn forward_to_pipeline<M>(
+        &self,
+        pipeline_id: &IndexingPipelineId,
+        message: M,
+    ) -> Result<(), IndexingServiceError>
+    where
+        IndexingPipeline: Handler<M, Reply = ()>,
+        M: 'static + Send + Sync + std::fmt::Debug,
+    {
+        let pipeline_handle = self.pipeline_handles.get(pipeline_id).ok_or_else(|| {
+            IndexingServiceError::MissingPipeline {
+                index_id: pipeline_id.index_id.clone(),
+                source_id: pipeline_id.source_id.clone(),
+            }
+        })?;
+        // The pipeline may exit before processing the message, in which case it is reported as
+        // missing.
+        pipeline_handle.mailbox().ask(message).await.map_err(|_| {
+            IndexingServiceError::MissingPipeline {
+                index_id: pipeline_id.index_id.clone(),
+                source_id: pipeline_id.source_id.clone(),
+            }
+        })
+    }
+
     async fn observe_pipeline(
         &mut self,
         pipeline_id: &IndexingPipelineId,
@@ -140,13 +240,37 @@ impl IndexingService {
         source: SourceConfig,
         ctx: &ActorContext<Self>,
     ) -> Result<IndexingPipelineId, IndexingServiceError> {
+        if source.is_receiver() {
+            return Err(IndexingServiceError::InvalidParams(anyhow::anyhow!(
+                "Source `{}` of type `{}` is a receiver: it is run by the receiver service of the \
+                 indexer nodes.",
+                source.source_id,
+                source.source_type()
+            )));
+        }
         let pipeline_id = IndexingPipelineId {
             index_id,
             source_id: source.source_id.clone(),
         };
+        let partition_assignment = self
+            .partition_assignment(&pipeline_id.index_id, &source)
+            .await
+            .ok_or_else(|| {
+                IndexingServiceError::InvalidParams(anyhow::anyhow!(
+                    "Source `{}` is not assigned to the current node: it is indexed by another \
+                     indexer node, or its files do not exist on this node.",
+                    source.source_id
+                ))
+            })?;
         let index_metadata = self.index_metadata(&pipeline_id.index_id, ctx).await?;
-        self.spawn_pipeline_inner(pipeline_id.clone(), index_metadata, source, ctx)
-            .await?;
+        self.spawn_pipeline_inner(
+            pipeline_id.clone(),
+            index_metadata,
+            source,
+            partition_assignment,
+            ctx,
+        )
+        .await?;
         Ok(pipeline_id)
     }
 
@@ -167,10 +291,18 @@ impl IndexingService {
             if self.pipeline_handles.contains_key(&pipeline_id) {
                 continue;
             }
+            let partition_assignment = if let Some(partition_assignment) =
+                self.partition_assignment(&index_id, source).await
+            {
+                partition_assignment
+            } else {
+                continue;
+            };
             self.spawn_pipeline_inner(
                 pipeline_id.clone(),
                 index_metadata.clone(),
                 source.clone(),
+                partition_assignment,
                 ctx,
             )
             .await?;
@@ -189,10 +321,26 @@ impl IndexingService {
                 .map_err(|err| IndexingServiceError::InvalidParams(err.into()))?;
 
             let source_id = INGEST_API_SOURCE_ID.to_string();
+            let ingest_api_pipeline_id = IndexingPipelineId {
+                index_id: index_id.clone(),
+                source_id: source_id.clone(),
+            };
+            if self.pipeline_handles.contains_key(&ingest_api_pipeline_id) {
+                return Ok(pipeline_ids);
+            }
             let ingest_api_pipeline_id = self
                 .spawn_ingest_api_pipeline(index_id, source_id, index_metadata, ctx)
                 .await?;
             pipeline_ids.push(ingest_api_pipeline_id);
+        } else if index_metadata
+            .sources
+            .values()
+            .any(SourceConfig::is_receiver)
+        {
+            warn!(
+                index_id = %index_id,
+                "The receivers of the index are not started because the ingest API is disabled."
+            );
         }
         Ok(pipeline_ids)
     }
@@ -202,6 +350,7 @@ impl IndexingService {
         pipeline_id: IndexingPipelineId,
         index_metadata: IndexMetadata,
         source: SourceConfig,
+        partition_assignment: PartitionAssignment,
         ctx: &ActorContext<Self>,
     ) -> Result<(), IndexingServiceError> {
         if self.pipeline_handles.contains_key(&pipeline_id) {
@@ -211,9 +360,15 @@ impl IndexingService {
             });
         }
         let storage = self.storage_resolver.resolve(&index_metadata.index_uri)?;
+        let dead_letter_queue_opt = self
+            .dead_letter_queue(&index_metadata)
+            .await
+            .map_err(IndexingServiceError::InvalidParams)?;
         let pipeline_params = IndexingPipelineParams::try_new(
             index_metadata,
             source,
+            partition_assignment,
+            dead_letter_queue_opt,
             self.indexing_dir_path.clone(),
             self.split_store_max_num_bytes,
             self.split_store_max_num_splits,
@@ -253,6 +408,7 @@ impl IndexingService {
             ingest_api_pipeline_id.clone(),
             index_metadata.clone(),
             ingest_api_source,
+            PartitionAssignment::All,
             ctx,
         )
         .await?;
@@ -279,11 +435,193 @@ impl IndexingService {
             source_id: pipeline_id.source_id.clone(),
             source_params: SourceParams::Vec(VecSourceParams::default()),
         };
-        self.spawn_pipeline_inner(pipeline_id.clone(), index_metadata, source, ctx)
-            .await?;
+        self.spawn_pipeline_inner(
+            pipeline_id.clone(),
+            index_metadata,
+            source,
+            PartitionAssignment::All,
+            ctx,
+        )
+        .await?;
         Ok(pipeline_id)
     }
 
+    /// Reassigns the sources and partitions after a change of the indexer nodes of the cluster.
+    ///
+    /// The pipelines of the sources assigned to other nodes are shut down, and the pipelines of
+    /// the sources newly assigned to the current node are spawned. The pipelines consuming shared
+    /// partitions keep running: they see the new indexer nodes and pick up or drop partitions on
+    /// their own, the Kafka source restarting only if its set of partitions changed. A partition
+    /// taken over by the current node is consumed from the checkpoint stored in the metastore: if
+    /// its former owner publishes a split for it in the meantime, the conflicting checkpoint delta
+    /// is rejected by the metastore and the pipeline restarts from the updated checkpoint, so
+    /// documents are never indexed twice.
+    async fn update_indexer_nodes(
+        &mut self,
+        indexer_nodes: IndexerNodes,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), IndexingServiceError> {
+        if let Some(current_indexer_nodes) = &self.indexer_nodes {
+            if **current_indexer_nodes.load() == indexer_nodes {
+                return Ok(());
+            }
+        }
+        info!(
+            node_id = %indexer_nodes.node_id(),
+            indexer_node_ids = %indexer_nodes.indexer_node_ids().join(", "),
+            "Reassigning sources to indexer nodes."
+        );
+        // The pipelines spawned while the node was standalone consume all the partitions of
+        // their source and must be restarted to share them.
+        let was_standalone = self.indexer_nodes.is_none();
+        if let Some(current_indexer_nodes) = &self.indexer_nodes {
+            current_indexer_nodes.store(Arc::new(indexer_nodes));
+        } else {
+            self.indexer_nodes = Some(Arc::new(ArcSwap::from_pointee(indexer_nodes)));
+        }
+        let index_metadatas = {
+            let _protect_guard = ctx.protect_zone();
+            self.metastore.list_indexes_metadatas().await?
+        };
+        for index_metadata in &index_metadatas {
+            for source in index_metadata.sources.values() {
+                let pipeline_id = IndexingPipelineId {
+                    index_id: index_metadata.index_id.clone(),
+                    source_id: source.source_id.clone(),
+                };
+                if !self.pipeline_handles.contains_key(&pipeline_id) {
+                    continue;
+                }
+                match self
+                    .partition_assignment(&index_metadata.index_id, source)
+                    .await
+                {
+                    Some(PartitionAssignment::All) => {}
+                    Some(PartitionAssignment::Shared { .. }) if !was_standalone => {}
+                    _ => {
+                        info!(index_id = %pipeline_id.index_id, source_id = %pipeline_id.source_id, "Shutting down reassigned indexing pipeline.");
+                        self.shutdown_pipeline(&pipeline_id).await;
+                    }
+                }
+            }
+        }
+        for index_metadata in index_metadatas {
+            self.spawn_pipelines(index_metadata.index_id, ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the dead-letter queue of the index, or `None` if the index does not configure one.
+    async fn dead_letter_queue(
+        &self,
+        index_metadata: &IndexMetadata,
+    ) -> anyhow::Result<Option<DeadLetterQueue>> {
+        let dead_letter_queue_config = if let Some(dead_letter_queue_config) =
+            &index_metadata.indexing_settings.dead_letter_queue
+        {
+            dead_letter_queue_config
+        } else {
+            return Ok(None);
+        };
+        let dead_letter_queue = DeadLetterQueue::create(
+            dead_letter_queue_config,
+            &index_metadata.index_id,
+            &self.dead_letter_dir_path,
+            self.ingest_api_service.clone(),
+        )
+        .await?;
+        Ok(Some(dead_letter_queue))
+    }
+
+    async fn inspect_dead_letter_queue(
+        &self,
+        index_id: &str,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Vec<DeadLetterRecord>, IndexingServiceError> {
+        let dead_letter_queue = self.require_dead_letter_queue(index_id, ctx).await?;
+        let records = dead_letter_queue
+            .read_records()
+            .await
+            .map_err(IndexingServiceError::DeadLetterQueueError)?;
+        Ok(records
+            .into_iter()
+            .filter(|record| record.index_id == index_id)
+            .collect())
+    }
+
+    /// Ingests the rejected documents of the index again through its ingest API queue. The
+    /// records of other indexes sharing the same dead-letter queue are put back in the queue.
+    async fn replay_dead_letter_queue(
+        &self,
+        index_id: &str,
+        ctx: &ActorContext<Self>,
+    ) -> Result<IngestResponse, IndexingServiceError> {
+        let ingest_api_service = self.ingest_api_service.clone().ok_or_else(|| {
+            IndexingServiceError::DeadLetterQueueError(anyhow::anyhow!(
+                "The ingest API service must be enabled to replay the dead-letter queue."
+            ))
+        })?;
+        let dead_letter_queue = self.require_dead_letter_queue(index_id, ctx).await?;
+        // The taken records stay in the dead-letter queue until the batch is committed, once the
+        // records of the index are ingested and the records of other indexes are put back. If
+        // any of these steps fails, nothing is lost: the records are replayed again next time.
+        let mut dead_letter_batch = dead_letter_queue
+            .take_records()
+            .await
+            .map_err(IndexingServiceError::DeadLetterQueueError)?;
+        let (records, other_records): (Vec<DeadLetterRecord>, Vec<DeadLetterRecord>) =
+            std::mem::take(&mut dead_letter_batch.records)
+                .into_iter()
+                .partition(|record| record.index_id == index_id);
+        let mut ingest_response = IngestResponse::default();
+        if !records.is_empty() {
+            info!(index_id = %index_id, num_docs = records.len(), "replay-dead-letter-queue");
+            let mut doc_batch = DocBatch {
+                index_id: index_id.to_string(),
+                ..Default::default()
+            };
+            for record in &records {
+                add_doc(record.doc.as_bytes(), &mut doc_batch);
+            }
+            ingest_api_service
+                .ask_for_res(CreateQueueIfNotExistsRequest {
+                    queue_id: index_id.to_string(),
+                })
+                .await
+                .map_err(|error| IndexingServiceError::DeadLetterQueueError(error.into()))?;
+            ingest_response = ingest_api_service
+                .ask_for_res(IngestRequest {
+                    doc_batches: vec![doc_batch],
+                    ..Default::default()
+                })
+                .await
+                .map_err(|error| IndexingServiceError::DeadLetterQueueError(error.into()))?;
+        }
+        dead_letter_queue
+            .push(&other_records)
+            .await
+            .map_err(IndexingServiceError::DeadLetterQueueError)?;
+        dead_letter_batch
+            .commit()
+            .await
+            .map_err(IndexingServiceError::DeadLetterQueueError)?;
+        Ok(ingest_response)
+    }
+
+    async fn require_dead_letter_queue(
+        &self,
+        index_id: &str,
+        ctx: &ActorContext<Self>,
+    ) -> Result<DeadLetterQueue, IndexingServiceError> {
+        let index_metadata = self.index_metadata(index_id, ctx).await?;
+        self.dead_letter_queue(&index_metadata)
+            .await
+            .map_err(IndexingServiceError::DeadLetterQueueError)?
+            .ok_or_else(|| IndexingServiceError::MissingDeadLetterQueue {
+                index_id: index_id.to_string(),
+            })
+    }
+
     async fn index_metadata(
         &self,
         index_id: &str,
@@ -441,22 +779,111 @@ impl Handler<ShutdownPipeline> for IndexingService {
             index_id: message.index_id,
             source_id: message.source_id,
         };
-        let pipeline_handle_opt = self.pipeline_handles.remove(&pipeline_id);
-        if let Some(pipeline_handle) = pipeline_handle_opt {
-            pipeline_handle.quit().await;
-        }
+        self.shutdown_pipeline(&pipeline_id).await;
         Ok(Ok(()))
     }
 }
 
+#[async_trait]
+impl Handler<UpdateIndexerNodes> for IndexingService {
+    type Reply = Result<(), IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: UpdateIndexerNodes,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.update_indexer_nodes(message.indexer_nodes, ctx).await)
+    }
+}
+
+#[async_trait]
+impl Handler<PausePipeline> for IndexingService {
+    type Reply = Result<(), IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: PausePipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_id = message.pipeline_id.clone();
+        Ok(self.forward_to_pipeline(&pipeline_id, message).await)
+    }
+}
+
+#[async_trait]
+impl Handler<ResumePipeline> for IndexingService {
+    type Reply = Result<(), IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: ResumePipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_id = message.pipeline_id.clone();
+        Ok(self.forward_to_pipeline(&pipeline_id, message).await)
+    }
+}
+
+#[async_trait]
+impl Handler<RestartPipeline> for IndexingService {
+    type Reply = Result<(), IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: RestartPipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_id = message.pipeline_id.clone();
+        Ok(self.forward_to_pipeline(&pipeline_id, message).await)
+    }
+}
+
+#[async_trait]
+impl Handler<CommitPipeline> for IndexingService {
+    type Reply = Result<(), IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: CommitPipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_id = message.pipeline_id.clone();
+        Ok(self.forward_to_pipeline(&pipeline_id, message).await)
+    }
+}
+
+#[async_trait]
+impl Handler<InspectDeadLetterQueue> for IndexingService {
+    type Reply = Result<Vec<DeadLetterRecord>, IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: InspectDeadLetterQueue,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.inspect_dead_letter_queue(&message.index_id, ctx).await)
+    }
+}
+
+#[async_trait]
+impl Handler<ReplayDeadLetterQueue> for IndexingService {
+    type Reply = Result<IngestResponse, IndexingServiceError>;
+    async fn handle(
+        &mut self,
+        message: ReplayDeadLetterQueue,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.replay_dead_letter_queue(&message.index_id, ctx).await)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
 
-    use quickwit_actors::{ObservationType, Universe};
+    use quickwit_actors::{AskError, ObservationType, Universe};
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::VecSourceParams;
+    use quickwit_config::{
+        DeadLetterQueueConfig, SyslogProtocol, SyslogSourceParams, VecSourceParams,
+    };
+    use quickwit_ingest_api::spawn_ingest_api_actor;
     use quickwit_metastore::quickwit_metastore_uri_resolver;
+    use quickwit_proto::ingest_api::FetchRequest;
 
     use super::*;
 
@@ -569,6 +996,36 @@ mod tests {
             2
         );
 
+        // Receivers are run by the receiver service, and the ingest API is disabled here.
+        let receiver_source = SourceConfig {
+            source_id: "test-indexing-service--receiver".to_string(),
+            source_params: SourceParams::Syslog(SyslogSourceParams {
+                listen_address: "127.0.0.1:0".to_string(),
+                protocol: SyslogProtocol::Udp,
+            }),
+        };
+        indexing_server_mailbox
+            .ask_for_res(SpawnPipeline {
+                index_id: index_id.clone(),
+                source: receiver_source.clone(),
+            })
+            .await
+            .unwrap_err();
+        metastore
+            .add_source(&index_id, receiver_source)
+            .await
+            .unwrap();
+        indexing_server_mailbox
+            .ask_for_res(SpawnPipelinesForIndex {
+                index_id: index_id.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            indexing_server_handle.observe().await.num_running_pipelines,
+            2
+        );
+
         // Test `spawn_merge_pipeline`.
         let merge_pipeline_id = indexing_server_mailbox
             .ask_for_res(SpawnMergePipeline {
@@ -616,4 +1073,252 @@ mod tests {
         }
         panic!("Sleep");
     }
+
+    #[tokio::test]
+    async fn test_indexing_service_pipeline_actions() {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = append_random_suffix("test-indexing-service-pipeline-actions");
+        let index_uri = format!("{}/{}", METASTORE_URI, index_id);
+        let index_metadata = IndexMetadata::for_test(&index_id, &index_uri);
+
+        let metastore = quickwit_metastore_uri_resolver()
+            .resolve(METASTORE_URI)
+            .await
+            .unwrap();
+        metastore.create_index(index_metadata).await.unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let indexing_server = IndexingService::new(
+            temp_dir.path().to_path_buf(),
+            IndexerConfig::for_test().unwrap(),
+            metastore,
+            StorageUriResolver::for_test(),
+            None,
+        );
+        let universe = Universe::new();
+        let (indexing_server_mailbox, _indexing_server_handle) =
+            universe.spawn_actor(indexing_server).spawn();
+        let pipeline_id = indexing_server_mailbox
+            .ask_for_res(SpawnPipeline {
+                index_id: index_id.clone(),
+                source: SourceConfig {
+                    source_id: "test-indexing-service--source".to_string(),
+                    source_params: SourceParams::void(),
+                },
+            })
+            .await
+            .unwrap();
+        indexing_server_mailbox
+            .ask_for_res(PausePipeline {
+                pipeline_id: pipeline_id.clone(),
+            })
+            .await
+            .unwrap();
+        indexing_server_mailbox
+            .ask_for_res(ResumePipeline {
+                pipeline_id: pipeline_id.clone(),
+            })
+            .await
+            .unwrap();
+        indexing_server_mailbox
+            .ask_for_res(CommitPipeline {
+                pipeline_id: pipeline_id.clone(),
+            })
+            .await
+            .unwrap();
+        indexing_server_mailbox
+            .ask_for_res(RestartPipeline {
+                pipeline_id: pipeline_id.clone(),
+            })
+            .await
+            .unwrap();
+        let pipeline_observation = indexing_server_mailbox
+            .ask_for_res(ObservePipeline { pipeline_id })
+            .await
+            .unwrap();
+        assert_eq!(pipeline_observation.generation, 2);
+
+        let missing_pipeline_id = IndexingPipelineId {
+            index_id,
+            source_id: "test-indexing-service--missing-source".to_string(),
+        };
+        let error = indexing_server_mailbox
+            .ask_for_res(PausePipeline {
+                pipeline_id: missing_pipeline_id,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AskError::ErrorReply(IndexingServiceError::MissingPipeline { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_dead_letter_queue() {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = append_random_suffix("test-indexing-service-dead-letter-queue");
+        let index_uri = format!("{}/{}", METASTORE_URI, index_id);
+        let mut index_metadata = IndexMetadata::for_test(&index_id, &index_uri);
+        index_metadata.indexing_settings.dead_letter_queue = Some(DeadLetterQueueConfig::File);
+
+        let metastore = quickwit_metastore_uri_resolver()
+            .resolve(METASTORE_URI)
+            .await
+            .unwrap();
+        metastore.create_index(index_metadata).await.unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let universe = Universe::new();
+        let ingest_api_service =
+            spawn_ingest_api_actor(&universe, &temp_dir.path().join("queues"), false).unwrap();
+        let indexing_server = IndexingService::new(
+            temp_dir.path().to_path_buf(),
+            IndexerConfig::for_test().unwrap(),
+            metastore,
+            StorageUriResolver::for_test(),
+            Some(ingest_api_service.clone()),
+        );
+        let (indexing_server_mailbox, _indexing_server_handle) =
+            universe.spawn_actor(indexing_server).spawn();
+
+        let dead_letter_queue = DeadLetterQueue::create(
+            &DeadLetterQueueConfig::File,
+            &index_id,
+            &temp_dir.path().join(DEAD_LETTER_DIR_NAME),
+            None,
+        )
+        .await
+        .unwrap();
+        let records: Vec<DeadLetterRecord> = ["{", "}"]
+            .iter()
+            .map(|doc| DeadLetterRecord {
+                index_id: index_id.clone(),
+                source_id: "test-source".to_string(),
+                partition_id: None,
+                position: None,
+                error: "The provided string is not a valid JSON object.".to_string(),
+                doc: doc.to_string(),
+                timestamp: 1,
+            })
+            .collect();
+        dead_letter_queue.push(&records).await.unwrap();
+
+        let inspected_records = indexing_server_mailbox
+            .ask_for_res(InspectDeadLetterQueue {
+                index_id: index_id.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(inspected_records, records);
+
+        let ingest_response = indexing_server_mailbox
+            .ask_for_res(ReplayDeadLetterQueue {
+                index_id: index_id.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+        assert!(dead_letter_queue.read_records().await.unwrap().is_empty());
+        let fetch_response = ingest_api_service
+            .ask_for_res(FetchRequest {
+                index_id: index_id.clone(),
+                start_after: None,
+                num_bytes_limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_response.doc_batch.unwrap().concat_docs, b"{}");
+
+        let error = indexing_server_mailbox
+            .ask_for_res(InspectDeadLetterQueue {
+                index_id: "test-indexing-service-missing-index".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AskError::ErrorReply(IndexingServiceError::MetastoreError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_update_indexer_nodes() {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = append_random_suffix("test-indexing-service-indexer-nodes");
+        let index_uri = format!("{}/{}", METASTORE_URI, index_id);
+        let index_metadata = IndexMetadata::for_test(&index_id, &index_uri);
+
+        let metastore = quickwit_metastore_uri_resolver()
+            .resolve(METASTORE_URI)
+            .await
+            .unwrap();
+        metastore.create_index(index_metadata).await.unwrap();
+        let source_ids: Vec<String> = (0..8)
+            .map(|source_ord| format!("test-indexing-service--source-{}", source_ord))
+            .collect();
+        for source_id in &source_ids {
+            let source = SourceConfig {
+                source_id: source_id.clone(),
+                source_params: SourceParams::void(),
+            };
+            metastore.add_source(&index_id, source).await.unwrap();
+        }
+        let temp_dir = tempfile::tempdir().unwrap();
+        let indexing_server = IndexingService::new(
+            temp_dir.path().to_path_buf(),
+            IndexerConfig::for_test().unwrap(),
+            metastore,
+            StorageUriResolver::for_test(),
+            None,
+        );
+        let universe = Universe::new();
+        let (indexing_server_mailbox, indexing_server_handle) =
+            universe.spawn_actor(indexing_server).spawn();
+
+        // Two indexers share the sources.
+        let indexer_nodes = IndexerNodes::new("node-1".to_string(), vec!["node-2".to_string()]);
+        let num_owned_sources = source_ids
+            .iter()
+            .filter(|source_id| indexer_nodes.owns_source(&index_id, source_id))
+            .count();
+        indexing_server_mailbox
+            .ask_for_res(UpdateIndexerNodes { indexer_nodes })
+            .await
+            .unwrap();
+        assert_eq!(
+            indexing_server_handle.observe().await.num_running_pipelines,
+            num_owned_sources
+        );
+
+        // The sources owned by `node-2` cannot be spawned on `node-1`.
+        let indexer_nodes = IndexerNodes::new("node-1".to_string(), vec!["node-2".to_string()]);
+        let source_id = source_ids
+            .iter()
+            .find(|source_id| !indexer_nodes.owns_source(&index_id, source_id))
+            .unwrap();
+        let error = indexing_server_mailbox
+            .ask_for_res(SpawnPipeline {
+                index_id: index_id.clone(),
+                source: SourceConfig {
+                    source_id: source_id.clone(),
+                    source_params: SourceParams::void(),
+                },
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AskError::ErrorReply(IndexingServiceError::InvalidParams(_))
+        ));
+
+        // `node-2` leaves the cluster: `node-1` takes over its sources.
+        let indexer_nodes = IndexerNodes::new("node-1".to_string(), Vec::new());
+        indexing_server_mailbox
+            .ask_for_res(UpdateIndexerNodes { indexer_nodes })
+            .await
+            .unwrap();
+        assert_eq!(
+            indexing_server_handle.observe().await.num_running_pipelines,
+            source_ids.len()
+        );
+    }
 }
@@ -108,17 +108,27 @@ impl IngestApiGarbageCollector {
             .collect();
         debug!(queues=?queues, "list-queues");
 
-        let index_ids: HashSet<String> = self
+        // The queues to keep are the queues of the indexes and their dead-letter queues.
+        let mut live_queue_ids: HashSet<String> = HashSet::new();
+        for index_metadata in self
             .metastore
             .list_indexes_metadatas()
             .await
             .context("Failed to list queues")?
-            .into_iter()
-            .map(|index_metadata| index_metadata.index_id)
-            .collect();
-        debug!(index_ids=?index_ids, "list-index-ids");
+        {
+            if let Some(dead_letter_queue_id) = index_metadata
+                .indexing_settings
+                .dead_letter_queue
+                .as_ref()
+                .and_then(|config| config.ingest_api_queue_id(&index_metadata.index_id))
+            {
+                live_queue_ids.insert(dead_letter_queue_id);
+            }
+            live_queue_ids.insert(index_metadata.index_id);
+        }
+        debug!(live_queue_ids=?live_queue_ids, "list-live-queue-ids");
 
-        let queue_ids_to_delete = queues.difference(&index_ids);
+        let queue_ids_to_delete = queues.difference(&live_queue_ids);
         for queue_id in queue_ids_to_delete {
             if let Err(delete_queue_error) = self.delete_queue(queue_id).await {
                 error!(error=?delete_queue_error, queue_id=%queue_id, "queue-delete-failure");
@@ -176,7 +186,7 @@ mod tests {
     use std::time::Duration;
 
     use quickwit_actors::Universe;
-    use quickwit_config::IndexerConfig;
+    use quickwit_config::{DeadLetterQueueConfig, IndexerConfig};
     use quickwit_ingest_api::spawn_ingest_api_actor;
     use quickwit_metastore::{quickwit_metastore_uri_resolver, IndexMetadata};
     use quickwit_proto::ingest_api::CreateQueueIfNotExistsRequest;
@@ -203,7 +213,7 @@ mod tests {
 
         // Setup ingest api objects
         let ingest_api_mailbox =
-            spawn_ingest_api_actor(&universe, temp_dir.path().join("queues").as_path())?;
+            spawn_ingest_api_actor(&universe, temp_dir.path().join("queues").as_path(), false)?;
         let create_queue_req = CreateQueueIfNotExistsRequest {
             queue_id: index_id.clone(),
         };
@@ -253,4 +263,79 @@ mod tests {
 
         Ok(())
     }
+    #[tokio::test]
+    async fn test_ingest_api_garbage_collector_keeps_dead_letter_queues() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let index_id = "index-with-dead-letter-queue".to_string();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let index_uri = format!("{}/{}", METASTORE_URI, index_id);
+        let mut index_metadata = IndexMetadata::for_test(&index_id, &index_uri);
+        index_metadata.indexing_settings.dead_letter_queue =
+            Some(DeadLetterQueueConfig::IngestApi { queue_id: None });
+        let metastore = quickwit_metastore_uri_resolver()
+            .resolve(METASTORE_URI)
+            .await
+            .unwrap();
+        metastore.create_index(index_metadata).await.unwrap();
+
+        let ingest_api_mailbox =
+            spawn_ingest_api_actor(&universe, temp_dir.path().join("queues").as_path(), false)?;
+        for queue_id in [
+            index_id.as_str(),
+            "index-with-dead-letter-queue-dead-letter",
+            "orphan-queue",
+        ] {
+            ingest_api_mailbox
+                .ask_for_res(CreateQueueIfNotExistsRequest {
+                    queue_id: queue_id.to_string(),
+                })
+                .await
+                .map_err(|error| anyhow::anyhow!(error))?;
+        }
+
+        let indexing_server = IndexingService::new(
+            temp_dir.path().to_path_buf(),
+            IndexerConfig::for_test().unwrap(),
+            metastore.clone(),
+            StorageUriResolver::for_test(),
+            Some(ingest_api_mailbox.clone()),
+        );
+        let (indexing_server_mailbox, _indexing_server_handle) =
+            universe.spawn_actor(indexing_server).spawn();
+
+        let ingest_api_garbage_collector = IngestApiGarbageCollector::new(
+            metastore.clone(),
+            ingest_api_mailbox.clone(),
+            indexing_server_mailbox,
+        );
+        let (_mailbox, handler) = universe.spawn_actor(ingest_api_garbage_collector).spawn();
+
+        let state_after_initialization = handler.process_pending_and_observe().await.state;
+        assert_eq!(state_after_initialization.num_passes, 1);
+        assert_eq!(state_after_initialization.num_deleted_queues, 1);
+
+        let mut queues = ingest_api_mailbox
+            .ask_for_res(ListQueuesRequest {})
+            .await
+            .map_err(|error| anyhow::anyhow!(error))?
+            .queues;
+        queues.sort();
+        assert_eq!(
+            queues,
+            vec![
+                "index-with-dead-letter-queue",
+                "index-with-dead-letter-queue-dead-letter"
+            ]
+        );
+
+        // The dead-letter queue goes away with its index.
+        metastore.delete_index(&index_id).await.unwrap();
+        universe.simulate_time_shift(RUN_INTERVAL).await;
+        let state_after_deletion = handler.process_pending_and_observe().await.state;
+        assert_eq!(state_after_deletion.num_passes, 2);
+        assert_eq!(state_after_deletion.num_deleted_queues, 3);
+        Ok(())
+    }
 }
@@ -25,6 +25,7 @@ mod indexing_service;
 mod ingest_api_garbage_collector;
 mod packager;
 mod publisher;
+mod receiver_service;
 mod sequencer;
 mod uploader;
 
@@ -45,6 +46,7 @@ pub use self::merge_planner::MergePlanner;
 pub use self::merge_split_downloader::MergeSplitDownloader;
 pub use self::packager::Packager;
 pub use self::publisher::{Publisher, PublisherCounters};
+pub use self::receiver_service::{ReceiverService, ReceiverServiceState};
 pub use self::uploader::{Uploader, UploaderCounters};
 
 /// A struct to wrap a tantivy field with its name.
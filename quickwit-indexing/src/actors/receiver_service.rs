@@ -0,0 +1,298 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
+use quickwit_config::SourceConfig;
+use quickwit_ingest_api::IngestApiService;
+use quickwit_metastore::Metastore;
+use quickwit_proto::ingest_api::CreateQueueIfNotExistsRequest;
+use tracing::{error, info, warn};
+
+use crate::receivers::{start_receiver, Receiver};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default)]
+pub struct ReceiverServiceState {
+    /// The number of passes over the receiver sources of the indexes.
+    pub num_passes: usize,
+    /// The receivers running on the node.
+    pub receivers: Vec<serde_json::Value>,
+    /// The number of receivers that could not be started during the last pass, for instance
+    /// because their address is already in use or is not an address of the node.
+    pub num_failed_receivers: usize,
+}
+
+type ReceiverKey = (String, String);
+
+#[derive(Debug)]
+struct Loop;
+
+/// An actor running the receivers of all the indexes on the node.
+///
+/// Receivers are run independently of the indexing pipelines: they are started as soon as the
+/// node starts, or as soon as they are added to an index, and keep listening while the ingest API
+/// pipelines of their indexes are restarted. Each receiver listens on its address on every
+/// indexer node. Receivers that cannot be started on the node are retried at the next pass.
+pub struct ReceiverService {
+    metastore: Arc<dyn Metastore>,
+    ingest_api_service: Mailbox<IngestApiService>,
+    receivers: HashMap<ReceiverKey, Receiver>,
+    /// The last error of each receiver that could not be started, to log it only once.
+    failed_receivers: HashMap<ReceiverKey, String>,
+    num_passes: usize,
+}
+
+impl ReceiverService {
+    pub fn new(
+        metastore: Arc<dyn Metastore>,
+        ingest_api_service: Mailbox<IngestApiService>,
+    ) -> Self {
+        Self {
+            metastore,
+            ingest_api_service,
+            receivers: HashMap::new(),
+            failed_receivers: HashMap::new(),
+            num_passes: 0,
+        }
+    }
+
+    /// Starts the receivers added to the indexes and stops the ones removed from them. Receivers
+    /// whose configuration changed are restarted.
+    async fn sync_receivers(&mut self) -> anyhow::Result<()> {
+        let mut receiver_configs: HashMap<ReceiverKey, SourceConfig> = HashMap::new();
+        for index_metadata in self
+            .metastore
+            .list_indexes_metadatas()
+            .await
+            .context("Failed to list indexes.")?
+        {
+            for source_config in index_metadata.sources.into_values() {
+                if source_config.is_receiver() {
+                    let receiver_key = (
+                        index_metadata.index_id.clone(),
+                        source_config.source_id.clone(),
+                    );
+                    receiver_configs.insert(receiver_key, source_config);
+                }
+            }
+        }
+        // The outdated receivers are stopped first so that their addresses can be reused.
+        let outdated_receiver_keys: Vec<ReceiverKey> = self
+            .receivers
+            .iter()
+            .filter(|(receiver_key, receiver)| {
+                receiver_configs.get(*receiver_key) != Some(receiver.source_config())
+            })
+            .map(|(receiver_key, _)| receiver_key.clone())
+            .collect();
+        for receiver_key in outdated_receiver_keys {
+            if let Some(receiver) = self.receivers.remove(&receiver_key) {
+                receiver.stop().await;
+                info!(
+                    index_id = %receiver_key.0,
+                    source_id = %receiver_key.1,
+                    "Stopped receiver."
+                );
+            }
+        }
+        self.failed_receivers
+            .retain(|receiver_key, _| receiver_configs.contains_key(receiver_key));
+
+        for (receiver_key, source_config) in receiver_configs {
+            if self.receivers.contains_key(&receiver_key) {
+                continue;
+            }
+            match self.start_receiver(&receiver_key.0, &source_config).await {
+                Ok(receiver) => {
+                    self.failed_receivers.remove(&receiver_key);
+                    self.receivers.insert(receiver_key, receiver);
+                }
+                Err(error) => {
+                    let error_message = format!("{:#}", error);
+                    if self.failed_receivers.get(&receiver_key) != Some(&error_message) {
+                        warn!(
+                            index_id = %receiver_key.0,
+                            source_id = %receiver_key.1,
+                            error = %error_message,
+                            "Failed to start receiver."
+                        );
+                    }
+                    self.failed_receivers.insert(receiver_key, error_message);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn start_receiver(
+        &self,
+        index_id: &str,
+        source_config: &SourceConfig,
+    ) -> anyhow::Result<Receiver> {
+        self.ingest_api_service
+            .ask_for_res(CreateQueueIfNotExistsRequest {
+                queue_id: index_id.to_string(),
+            })
+            .await
+            .with_context(|| format!("Failed to create queue `{}`.", index_id))?;
+        start_receiver(index_id, source_config, self.ingest_api_service.clone()).await
+    }
+}
+
+#[async_trait]
+impl Actor for ReceiverService {
+    type ObservableState = ReceiverServiceState;
+
+    fn observable_state(&self) -> Self::ObservableState {
+        ReceiverServiceState {
+            num_passes: self.num_passes,
+            receivers: self
+                .receivers
+                .values()
+                .map(Receiver::observable_state)
+                .collect(),
+            num_failed_receivers: self.failed_receivers.len(),
+        }
+    }
+
+    fn name(&self) -> String {
+        "ReceiverService".to_string()
+    }
+
+    async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
+        self.handle(Loop, ctx).await
+    }
+}
+
+#[async_trait]
+impl Handler<Loop> for ReceiverService {
+    type Reply = ();
+
+    async fn handle(&mut self, _: Loop, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
+        self.num_passes += 1;
+        if let Err(error) = self.sync_receivers().await {
+            // We do not stop the actor here: the receivers already running keep running.
+            error!(error = ?error, "Failed to sync receivers.");
+        }
+        ctx.schedule_self_msg(SYNC_INTERVAL, Loop).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+    use quickwit_config::{SourceParams, SyslogProtocol, SyslogSourceParams};
+    use quickwit_ingest_api::spawn_ingest_api_actor;
+    use quickwit_metastore::{quickwit_metastore_uri_resolver, IndexMetadata};
+
+    use super::*;
+
+    fn make_syslog_source_config(listen_address: &str) -> SourceConfig {
+        SourceConfig {
+            source_id: "my-syslog-source".to_string(),
+            source_params: SourceParams::Syslog(SyslogSourceParams {
+                listen_address: listen_address.to_string(),
+                protocol: SyslogProtocol::Udp,
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_receiver_service() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let index_id = "test-receiver-service";
+        let temp_dir = tempfile::tempdir()?;
+
+        let metastore_uri = "ram:///test-receiver-service/indexes";
+        let metastore = quickwit_metastore_uri_resolver()
+            .resolve(metastore_uri)
+            .await
+            .unwrap();
+        let index_uri = format!("{}/{}", metastore_uri, index_id);
+        metastore
+            .create_index(IndexMetadata::for_test(index_id, &index_uri))
+            .await
+            .unwrap();
+        // The address of the receiver is taken, so the receiver cannot start.
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0")?;
+        let listen_address = socket.local_addr()?.to_string();
+        metastore
+            .add_source(index_id, make_syslog_source_config(&listen_address))
+            .await
+            .unwrap();
+
+        let ingest_api_service =
+            spawn_ingest_api_actor(&universe, temp_dir.path().join("queues").as_path(), false)?;
+        let receiver_service = ReceiverService::new(metastore.clone(), ingest_api_service);
+        let (_receiver_service_mailbox, receiver_service_handle) =
+            universe.spawn_actor(receiver_service).spawn();
+
+        let state = receiver_service_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(state.num_passes, 1);
+        assert!(state.receivers.is_empty());
+        assert_eq!(state.num_failed_receivers, 1);
+
+        // The receiver starts once its address is released.
+        drop(socket);
+        universe.simulate_time_shift(SYNC_INTERVAL).await;
+        let state = receiver_service_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(state.num_passes, 2);
+        assert_eq!(state.receivers.len(), 1);
+        assert_eq!(state.receivers[0]["index_id"], index_id);
+        assert_eq!(state.num_failed_receivers, 0);
+
+        // The receiver keeps running across passes and releases its address once removed.
+        universe.simulate_time_shift(SYNC_INTERVAL).await;
+        let state = receiver_service_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(state.num_passes, 3);
+        assert_eq!(state.receivers.len(), 1);
+
+        metastore
+            .delete_source(index_id, "my-syslog-source")
+            .await
+            .unwrap();
+        universe.simulate_time_shift(SYNC_INTERVAL).await;
+        let state = receiver_service_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(state.num_passes, 4);
+        assert!(state.receivers.is_empty());
+        std::net::UdpSocket::bind(&listen_address)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,508 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use quickwit_actors::Mailbox;
+use quickwit_config::DeadLetterQueueConfig;
+use quickwit_ingest_api::{add_doc, iter_doc_payloads, IngestApiService};
+use quickwit_proto::ingest_api::{
+    CreateQueueIfNotExistsRequest, DocBatch, FetchRequest, IngestRequest, SuggestTruncateRequest,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+/// Name of the directory, relative to the data directory, holding the file dead-letter queues.
+pub const DEAD_LETTER_DIR_NAME: &str = "dead-letter";
+
+/// A document rejected by the doc mapper, along with the reason and the place it came from.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterRecord {
+    pub index_id: String,
+    pub source_id: String,
+    /// Source partition the document was read from, if the source tracks it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_id: Option<String>,
+    /// Position of the document in its partition, if the source tracks it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+    pub error: String,
+    /// Raw document, as read from the source.
+    pub doc: String,
+    /// Unix timestamp, in seconds, of the moment the document was rejected.
+    pub timestamp: i64,
+}
+
+/// Sink receiving the documents rejected by the indexer.
+#[derive(Clone)]
+pub enum DeadLetterQueue {
+    /// NDJSON file in the data directory of the indexer.
+    File { path: PathBuf },
+    /// Local ingest API queue.
+    IngestApi {
+        queue_id: String,
+        ingest_api_service: Mailbox<IngestApiService>,
+    },
+    /// Ingest API queue of another index. The records are indexed by the ingest API pipeline of
+    /// that index and can be searched like any other document.
+    Index {
+        index_id: String,
+        ingest_api_service: Mailbox<IngestApiService>,
+    },
+}
+
+impl DeadLetterQueue {
+    /// Creates the dead-letter queue of the index `index_id` from its config.
+    pub async fn create(
+        config: &DeadLetterQueueConfig,
+        index_id: &str,
+        dead_letter_dir_path: &Path,
+        ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
+    ) -> anyhow::Result<Self> {
+        let dead_letter_queue = match config {
+            DeadLetterQueueConfig::File => {
+                tokio::fs::create_dir_all(dead_letter_dir_path)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed to create dead-letter directory `{}`.",
+                            dead_letter_dir_path.display()
+                        )
+                    })?;
+                DeadLetterQueue::File {
+                    path: dead_letter_dir_path.join(format!("{index_id}.ndjson")),
+                }
+            }
+            DeadLetterQueueConfig::IngestApi { .. } => DeadLetterQueue::IngestApi {
+                queue_id: config
+                    .ingest_api_queue_id(index_id)
+                    .expect("An `ingest_api` dead-letter queue should have a queue ID."),
+                ingest_api_service: require_ingest_api_service(ingest_api_service_opt)?,
+            },
+            DeadLetterQueueConfig::Index {
+                index_id: dead_letter_index_id,
+            } => DeadLetterQueue::Index {
+                index_id: dead_letter_index_id.clone(),
+                ingest_api_service: require_ingest_api_service(ingest_api_service_opt)?,
+            },
+        };
+        dead_letter_queue.create_queue_if_not_exists().await?;
+        Ok(dead_letter_queue)
+    }
+
+    async fn create_queue_if_not_exists(&self) -> anyhow::Result<()> {
+        let (queue_id, ingest_api_service) = match self {
+            DeadLetterQueue::File { .. } => return Ok(()),
+            DeadLetterQueue::IngestApi {
+                queue_id,
+                ingest_api_service,
+            } => (queue_id, ingest_api_service),
+            DeadLetterQueue::Index {
+                index_id,
+                ingest_api_service,
+            } => (index_id, ingest_api_service),
+        };
+        ingest_api_service
+            .ask_for_res(CreateQueueIfNotExistsRequest {
+                queue_id: queue_id.clone(),
+            })
+            .await
+            .map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+
+    /// Appends records to the dead-letter queue.
+    pub async fn push(&self, records: &[DeadLetterRecord]) -> anyhow::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        match self {
+            DeadLetterQueue::File { path } => {
+                let mut buffer = Vec::new();
+                for record in records {
+                    serde_json::to_writer(&mut buffer, record)?;
+                    buffer.push(b'\n');
+                }
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to open dead-letter file `{}`.", path.display())
+                    })?;
+                file.write_all(&buffer).await?;
+                file.flush().await?;
+            }
+            DeadLetterQueue::IngestApi {
+                queue_id: target_queue_id,
+                ingest_api_service,
+            }
+            | DeadLetterQueue::Index {
+                index_id: target_queue_id,
+                ingest_api_service,
+            } => {
+                let mut doc_batch = DocBatch {
+                    index_id: target_queue_id.clone(),
+                    ..Default::default()
+                };
+                for record in records {
+                    add_doc(&serde_json::to_vec(record)?, &mut doc_batch);
+                }
+                ingest_api_service
+                    .ask_for_res(IngestRequest {
+                        doc_batches: vec![doc_batch],
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the records currently held by the dead-letter queue.
+    pub async fn read_records(&self) -> anyhow::Result<Vec<DeadLetterRecord>> {
+        match self {
+            DeadLetterQueue::File { path } => {
+                // Records taken out by a replay that was not committed are still in the queue.
+                let mut records = read_records_from_file(&replay_file_path(path)).await?;
+                records.extend(read_records_from_file(&incoming_file_path(path)).await?);
+                records.extend(read_records_from_file(path).await?);
+                Ok(records)
+            }
+            DeadLetterQueue::IngestApi {
+                queue_id,
+                ingest_api_service,
+            } => {
+                let (records, _) = fetch_records(queue_id, ingest_api_service).await?;
+                Ok(records)
+            }
+            DeadLetterQueue::Index { index_id, .. } => {
+                anyhow::bail!(
+                    "The rejected documents are sent to the index `{index_id}`, search this index \
+                     to inspect them."
+                )
+            }
+        }
+    }
+
+    /// Takes the records out of the dead-letter queue. The records stay in the queue until the
+    /// returned batch is committed, so that they are not lost if their replay fails. Records
+    /// appended to the queue in the meantime are not part of the batch and are kept.
+    pub async fn take_records(&self) -> anyhow::Result<DeadLetterBatch> {
+        match self {
+            DeadLetterQueue::File { path } => {
+                let replay_path = replay_file_path(path);
+                let incoming_path = incoming_file_path(path);
+                // Records of an interrupted take are appended to the replay file first.
+                append_file_to(&incoming_path, &replay_path).await?;
+                // The indexer keeps appending to a new file while the renamed one is moved to the
+                // replay file, which holds the records of the previous uncommitted replays.
+                match tokio::fs::rename(path, &incoming_path).await {
+                    Ok(()) => {}
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(error) => return Err(error.into()),
+                }
+                append_file_to(&incoming_path, &replay_path).await?;
+                let records = read_records_from_file(&replay_path).await?;
+                Ok(DeadLetterBatch {
+                    records,
+                    commit_point: CommitPoint::File { replay_path },
+                })
+            }
+            DeadLetterQueue::IngestApi {
+                queue_id,
+                ingest_api_service,
+            } => {
+                let (records, last_position_opt) =
+                    fetch_records(queue_id, ingest_api_service).await?;
+                let commit_point = match last_position_opt {
+                    Some(last_position) => CommitPoint::IngestApi {
+                        queue_id: queue_id.clone(),
+                        ingest_api_service: ingest_api_service.clone(),
+                        last_position,
+                    },
+                    None => CommitPoint::None,
+                };
+                Ok(DeadLetterBatch {
+                    records,
+                    commit_point,
+                })
+            }
+            DeadLetterQueue::Index { .. } => Ok(DeadLetterBatch {
+                records: self.read_records().await?,
+                commit_point: CommitPoint::None,
+            }),
+        }
+    }
+}
+
+/// Records taken out of a dead-letter queue by [`DeadLetterQueue::take_records`].
+pub struct DeadLetterBatch {
+    pub records: Vec<DeadLetterRecord>,
+    commit_point: CommitPoint,
+}
+
+enum CommitPoint {
+    File {
+        replay_path: PathBuf,
+    },
+    IngestApi {
+        queue_id: String,
+        ingest_api_service: Mailbox<IngestApiService>,
+        last_position: u64,
+    },
+    None,
+}
+
+impl DeadLetterBatch {
+    /// Removes the records of the batch from the dead-letter queue.
+    pub async fn commit(self) -> anyhow::Result<()> {
+        match self.commit_point {
+            CommitPoint::File { replay_path } => match tokio::fs::remove_file(&replay_path).await {
+                Ok(()) => Ok(()),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(error) => Err(error.into()),
+            },
+            CommitPoint::IngestApi {
+                queue_id,
+                ingest_api_service,
+                last_position,
+            } => {
+                // Only the fetched records are truncated: the queue is not dropped, so the
+                // records appended since then are kept.
+                ingest_api_service
+                    .ask_for_res(SuggestTruncateRequest {
+                        index_id: queue_id,
+                        up_to_position_included: last_position,
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)?;
+                Ok(())
+            }
+            CommitPoint::None => Ok(()),
+        }
+    }
+}
+
+/// Returns the records of an ingest API queue, along with the position of the last one.
+async fn fetch_records(
+    queue_id: &str,
+    ingest_api_service: &Mailbox<IngestApiService>,
+) -> anyhow::Result<(Vec<DeadLetterRecord>, Option<u64>)> {
+    let mut records = Vec::new();
+    let mut start_after = None;
+    loop {
+        let fetch_resp = ingest_api_service
+            .ask_for_res(FetchRequest {
+                index_id: queue_id.to_string(),
+                start_after,
+                num_bytes_limit: None,
+            })
+            .await
+            .map_err(anyhow::Error::from)?;
+        let (first_position, doc_batch) = match (fetch_resp.first_position, fetch_resp.doc_batch) {
+            (Some(first_position), Some(doc_batch)) if !doc_batch.doc_lens.is_empty() => {
+                (first_position, doc_batch)
+            }
+            _ => break,
+        };
+        for doc_payload in iter_doc_payloads(&doc_batch) {
+            records.push(serde_json::from_slice(doc_payload)?);
+        }
+        start_after = Some(first_position + doc_batch.doc_lens.len() as u64 - 1);
+    }
+    Ok((records, start_after))
+}
+
+/// File holding the records taken out of a file dead-letter queue until they are committed.
+fn replay_file_path(path: &Path) -> PathBuf {
+    path.with_extension("ndjson.replay")
+}
+
+/// File the records of a file dead-letter queue are moved to before being appended to the replay
+/// file.
+fn incoming_file_path(path: &Path) -> PathBuf {
+    path.with_extension("ndjson.incoming")
+}
+
+/// Appends the content of the file at `src_path`, if any, to the file at `dst_path` and removes
+/// it.
+async fn append_file_to(src_path: &Path, dst_path: &Path) -> anyhow::Result<()> {
+    let content = match tokio::fs::read(src_path).await {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dst_path)
+        .await
+        .with_context(|| format!("Failed to open dead-letter file `{}`.", dst_path.display()))?;
+    file.write_all(&content).await?;
+    file.sync_all().await?;
+    tokio::fs::remove_file(src_path).await?;
+    Ok(())
+}
+
+fn require_ingest_api_service(
+    ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
+) -> anyhow::Result<Mailbox<IngestApiService>> {
+    ingest_api_service_opt.context(
+        "The ingest API service must be enabled to send the rejected documents to an ingest API \
+         queue.",
+    )
+}
+
+async fn read_records_from_file(path: &Path) -> anyhow::Result<Vec<DeadLetterRecord>> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Failed to read dead-letter file `{}`.", path.display()))
+        }
+    };
+    content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            serde_json::from_str(line).with_context(|| {
+                format!("Invalid record in dead-letter file `{}`.", path.display())
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+    use quickwit_ingest_api::spawn_ingest_api_actor;
+
+    use super::*;
+
+    fn make_record(doc: &str) -> DeadLetterRecord {
+        DeadLetterRecord {
+            index_id: "test-index".to_string(),
+            source_id: "test-source".to_string(),
+            partition_id: Some("".to_string()),
+            position: Some("00000000000000000002".to_string()),
+            error: "The provided string is not a valid JSON object.".to_string(),
+            doc: doc.to_string(),
+            timestamp: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_dead_letter_queue() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dead_letter_dir_path = temp_dir.path().join(DEAD_LETTER_DIR_NAME);
+        let dead_letter_queue = DeadLetterQueue::create(
+            &DeadLetterQueueConfig::File,
+            "test-index",
+            &dead_letter_dir_path,
+            None,
+        )
+        .await?;
+        assert!(dead_letter_queue.read_records().await?.is_empty());
+
+        dead_letter_queue
+            .push(&[make_record("{"), make_record("}")])
+            .await?;
+        dead_letter_queue.push(&[make_record("[")]).await?;
+        assert!(dead_letter_dir_path.join("test-index.ndjson").exists());
+        let records = dead_letter_queue.read_records().await?;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2], make_record("["));
+
+        // The records stay in the queue until the batch is committed.
+        let batch = dead_letter_queue.take_records().await?;
+        assert_eq!(batch.records.len(), 3);
+        dead_letter_queue.push(&[make_record("]")]).await?;
+        assert_eq!(dead_letter_queue.read_records().await?.len(), 4);
+        drop(batch);
+
+        // The records of the uncommitted batch are taken again, along with the new ones.
+        let batch = dead_letter_queue.take_records().await?;
+        assert_eq!(batch.records.len(), 4);
+        assert_eq!(batch.records[3], make_record("]"));
+        dead_letter_queue.push(&[make_record("{}")]).await?;
+        batch.commit().await?;
+        assert_eq!(
+            dead_letter_queue.read_records().await?,
+            vec![make_record("{}")]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_dead_letter_queue() -> anyhow::Result<()> {
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir()?;
+        let ingest_api_service = spawn_ingest_api_actor(&universe, temp_dir.path(), false)?;
+        DeadLetterQueue::create(
+            &DeadLetterQueueConfig::IngestApi { queue_id: None },
+            "test-index",
+            temp_dir.path(),
+            None,
+        )
+        .await
+        .err()
+        .unwrap();
+        let dead_letter_queue = DeadLetterQueue::create(
+            &DeadLetterQueueConfig::IngestApi { queue_id: None },
+            "test-index",
+            temp_dir.path(),
+            Some(ingest_api_service),
+        )
+        .await?;
+        assert!(
+            matches!(&dead_letter_queue, DeadLetterQueue::IngestApi { queue_id, .. } if queue_id == "test-index-dead-letter")
+        );
+        dead_letter_queue
+            .push(&[make_record("{"), make_record("}")])
+            .await?;
+        assert_eq!(
+            dead_letter_queue.read_records().await?,
+            vec![make_record("{"), make_record("}")]
+        );
+        let batch = dead_letter_queue.take_records().await?;
+        assert_eq!(batch.records.len(), 2);
+        // Records pushed while the batch is replayed are kept.
+        dead_letter_queue.push(&[make_record("]")]).await?;
+        batch.commit().await?;
+        assert_eq!(
+            dead_letter_queue.read_records().await?,
+            vec![make_record("]")]
+        );
+        assert_eq!(dead_letter_queue.take_records().await?.records.len(), 1);
+        dead_letter_queue.take_records().await?.commit().await?;
+        assert!(dead_letter_queue.read_records().await?.is_empty());
+
+        dead_letter_queue.push(&[make_record("[")]).await?;
+        assert_eq!(
+            dead_letter_queue.read_records().await?,
+            vec![make_record("[")]
+        );
+        Ok(())
+    }
+}
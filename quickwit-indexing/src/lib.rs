@@ -30,8 +30,9 @@ use tracing::info;
 pub use crate::actors::IndexingServiceError;
 use crate::actors::{
     IndexingPipeline, IndexingPipelineParams, IndexingService, IngestApiGarbageCollector,
+    ReceiverService,
 };
-use crate::models::{IndexingStatistics, SpawnPipelinesForIndex};
+use crate::models::{IndexerNodes, IndexingStatistics, UpdateIndexerNodes};
 pub use crate::split_store::{
     get_tantivy_directory_from_split_bundle, IndexingSplitStore, IndexingSplitStoreParams,
     SplitFolder,
@@ -39,31 +40,39 @@ pub use crate::split_store::{
 
 pub mod actors;
 mod controlled_directory;
+mod dead_letter_queue;
 mod garbage_collection;
 pub mod merge_policy;
 pub mod models;
+mod receivers;
 pub mod source;
 mod split_store;
 mod test_utils;
 
 pub use test_utils::{mock_split, mock_split_meta, TestSandbox};
 
+pub use self::dead_letter_queue::{
+    DeadLetterBatch, DeadLetterQueue, DeadLetterRecord, DEAD_LETTER_DIR_NAME,
+};
 pub use self::garbage_collection::{
     delete_splits_with_files, run_garbage_collect, FileEntry, SplitDeletionError,
 };
 use self::merge_policy::{MergePolicy, StableMultitenantWithTimestampMergePolicy};
-pub use self::source::check_source_connectivity;
+pub use self::source::{check_source_connectivity, plan_checkpoint_reset, CheckpointResetTarget};
 
 pub fn new_split_id() -> String {
     ulid::Ulid::new().to_string()
 }
 
+/// Starts the indexing service and spawns the pipelines of the sources assigned to the current
+/// node among `indexer_node_ids`, the indexer nodes of the cluster known at startup.
 pub async fn start_indexer_service(
     universe: &Universe,
     config: &QuickwitConfig,
     metastore: Arc<dyn Metastore>,
     storage_uri_resolver: StorageUriResolver,
     ingest_api_service: Option<Mailbox<IngestApiService>>,
+    indexer_node_ids: Vec<String>,
 ) -> anyhow::Result<Mailbox<IndexingService>> {
     info!("Starting indexer service.");
     let indexing_server = IndexingService::new(
@@ -78,22 +87,26 @@ pub async fn start_indexer_service(
     let index_metadatas = metastore.list_indexes_metadatas().await?;
     info!(index_ids = %index_metadatas.iter().map(|im| &im.index_id).join(", "), "Spawning indexing pipeline(s).");
 
-    for index_metadata in index_metadatas {
-        indexer_service_mailbox
-            .ask_for_res(SpawnPipelinesForIndex {
-                index_id: index_metadata.index_id,
-            })
-            .await?;
-    }
+    // The node only spawns the pipelines of the sources assigned to it among the indexers known at
+    // startup, so that it does not start pipelines owned by other nodes. The sources are
+    // reassigned as indexers join or leave the cluster (see `UpdateIndexerNodes`).
+    let indexer_nodes = IndexerNodes::new(config.node_id.clone(), indexer_node_ids);
+    indexer_service_mailbox
+        .ask_for_res(UpdateIndexerNodes { indexer_nodes })
+        .await?;
 
     // IngestApi garbage collector
     if let Some(ingest_api_service_mailbox) = ingest_api_service {
         let ingest_api_garbage_collector = IngestApiGarbageCollector::new(
-            metastore,
-            ingest_api_service_mailbox,
+            metastore.clone(),
+            ingest_api_service_mailbox.clone(),
             indexer_service_mailbox.clone(),
         );
         universe.spawn_actor(ingest_api_garbage_collector).spawn();
+
+        // Receivers write to the ingest API queues, and run independently of the pipelines.
+        let receiver_service = ReceiverService::new(metastore, ingest_api_service_mailbox);
+        universe.spawn_actor(receiver_service).spawn();
     }
 
     Ok(indexer_service_mailbox)
@@ -19,6 +19,8 @@
 
 use quickwit_config::SourceConfig;
 
+use crate::models::IndexerNodes;
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct IndexingPipelineId {
     pub index_id: String,
@@ -56,8 +58,54 @@ pub struct SpawnPipeline {
     pub source: SourceConfig,
 }
 
+/// Pauses the source of a pipeline. The documents already read from the source are still indexed
+/// and published.
+#[derive(Debug, Clone)]
+pub struct PausePipeline {
+    pub pipeline_id: IndexingPipelineId,
+}
+
+/// Resumes a paused pipeline.
+#[derive(Debug, Clone)]
+pub struct ResumePipeline {
+    pub pipeline_id: IndexingPipelineId,
+}
+
+/// Kills the actors of a pipeline and respawns them. The pipeline resumes from the last published
+/// checkpoint.
+#[derive(Debug, Clone)]
+pub struct RestartPipeline {
+    pub pipeline_id: IndexingPipelineId,
+}
+
+/// Commits the split being indexed by a pipeline without waiting for the commit timeout.
+#[derive(Debug, Clone)]
+pub struct CommitPipeline {
+    pub pipeline_id: IndexingPipelineId,
+}
+
+/// Lists the documents of an index held by its dead-letter queue.
+#[derive(Debug, Clone)]
+pub struct InspectDeadLetterQueue {
+    pub index_id: String,
+}
+
+/// Takes the documents of an index out of its dead-letter queue and ingests them again through
+/// the ingest API.
+#[derive(Debug, Clone)]
+pub struct ReplayDeadLetterQueue {
+    pub index_id: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ShutdownPipeline {
     pub index_id: String,
     pub source_id: String,
 }
+
+/// Notifies the indexing service of a change of the indexer nodes of the cluster. The sources and
+/// partitions are reassigned accordingly.
+#[derive(Debug, Clone)]
+pub struct UpdateIndexerNodes {
+    pub indexer_nodes: IndexerNodes,
+}
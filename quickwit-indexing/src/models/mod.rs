@@ -24,6 +24,7 @@ mod indexing_statistics;
 mod merge_planner_message;
 mod merge_scratch;
 mod packaged_split;
+mod partition_assignment;
 mod publisher_message;
 mod raw_doc_batch;
 mod scratch_directory;
@@ -31,13 +32,15 @@ mod scratch_directory;
 pub use indexed_split::{IndexedSplit, IndexedSplitBatch};
 pub use indexing_directory::{IndexingDirectory, CACHE};
 pub use indexing_service_message::{
-    DetachPipeline, IndexingPipelineId, ObservePipeline, ShutdownPipeline, SpawnMergePipeline,
-    SpawnPipeline, SpawnPipelinesForIndex,
+    CommitPipeline, DetachPipeline, IndexingPipelineId, InspectDeadLetterQueue, ObservePipeline,
+    PausePipeline, ReplayDeadLetterQueue, RestartPipeline, ResumePipeline, ShutdownPipeline,
+    SpawnMergePipeline, SpawnPipeline, SpawnPipelinesForIndex, UpdateIndexerNodes,
 };
 pub use indexing_statistics::IndexingStatistics;
 pub use merge_planner_message::NewSplits;
 pub use merge_scratch::MergeScratch;
 pub use packaged_split::{PackagedSplit, PackagedSplitBatch};
+pub use partition_assignment::{IndexerNodes, PartitionAssignment};
 pub use publisher_message::{PublishNewSplit, PublisherMessage, ReplaceSplits};
 pub use raw_doc_batch::RawDocBatch;
 pub use scratch_directory::ScratchDirectory;
@@ -0,0 +1,272 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use quickwit_metastore::checkpoint::PartitionId;
+use siphasher::sip::SipHasher;
+
+/// Lists the indexer nodes of the cluster, and identifies the current node among them.
+///
+/// Sources and partitions are assigned to the indexers by rendezvous hashing: every indexer
+/// computes the same assignment from the same cluster membership, and only the sources and
+/// partitions owned by the nodes joining or leaving the cluster are reassigned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexerNodes {
+    node_id: String,
+    indexer_node_ids: Vec<String>,
+}
+
+impl IndexerNodes {
+    /// Creates the list of indexer nodes seen from `node_id`. The current node is always part of
+    /// the list.
+    pub fn new(node_id: String, indexer_node_ids: impl IntoIterator<Item = String>) -> Self {
+        let mut indexer_node_ids: Vec<String> = indexer_node_ids.into_iter().collect();
+        indexer_node_ids.push(node_id.clone());
+        indexer_node_ids.sort();
+        indexer_node_ids.dedup();
+        Self {
+            node_id,
+            indexer_node_ids,
+        }
+    }
+
+    /// Returns the ID of the current node.
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// Returns the sorted IDs of the indexer nodes.
+    pub fn indexer_node_ids(&self) -> &[String] {
+        &self.indexer_node_ids
+    }
+
+    /// Returns whether the current node is in charge of the (non-partitioned) source.
+    pub fn owns_source(&self, index_id: &str, source_id: &str) -> bool {
+        self.owner_node_id(&[index_id, source_id]) == self.node_id
+    }
+
+    /// Returns whether the current node is in charge of the partition of a source.
+    pub fn owns_partition(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        partition_id: &PartitionId,
+    ) -> bool {
+        self.owner_node_id(&[index_id, source_id, partition_id.0.as_str()]) == self.node_id
+    }
+
+    fn owner_node_id(&self, key: &[&str]) -> &str {
+        self.indexer_node_ids
+            .iter()
+            .max_by_key(|node_id| node_affinity(node_id, key))
+            .expect("The list of indexer nodes should contain at least the current node.")
+    }
+}
+
+/// Computes the affinity of a node with a given key.
+///
+/// We rely on rendez-vous hashing here. All the indexers must compute the same affinities,
+/// whatever their version of Quickwit or of the Rust compiler, so we hash the raw bytes of the
+/// key parts with a fixed-key SipHash rather than with the `Hash` trait and `DefaultHasher`,
+/// whose output is not guaranteed to be stable.
+fn node_affinity(node_id: &str, key: &[&str]) -> u64 {
+    let mut state = SipHasher::new();
+    for key_part in key {
+        state.write(key_part.as_bytes());
+        // Separates the parts so that `("ab", "c")` and `("a", "bc")` hash differently.
+        state.write_u8(0xff);
+    }
+    state.write(node_id.as_bytes());
+    state.finish()
+}
+
+/// Describes the partitions of a source consumed by an indexing pipeline.
+#[derive(Clone, Debug)]
+pub enum PartitionAssignment {
+    /// The pipeline consumes all the partitions of the source.
+    All,
+    /// The partitions of the source are shared among the indexer nodes: the pipeline consumes the
+    /// partitions assigned to the current node.
+    ///
+    /// The indexer nodes are updated in place by the indexing service when the cluster membership
+    /// changes, so the assignment always reflects the current membership and the pipeline does not
+    /// need to be restarted.
+    Shared {
+        index_id: String,
+        source_id: String,
+        indexer_nodes: Arc<ArcSwap<IndexerNodes>>,
+    },
+}
+
+impl Default for PartitionAssignment {
+    fn default() -> Self {
+        PartitionAssignment::All
+    }
+}
+
+impl PartitionAssignment {
+    /// Returns whether the pipeline should consume the partition.
+    pub fn is_assigned(&self, partition_id: &PartitionId) -> bool {
+        match self {
+            PartitionAssignment::All => true,
+            PartitionAssignment::Shared {
+                index_id,
+                source_id,
+                indexer_nodes,
+            } => indexer_nodes
+                .load()
+                .owns_partition(index_id, source_id, partition_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indexer_nodes(node_id: &str, indexer_node_ids: &[&str]) -> IndexerNodes {
+        IndexerNodes::new(
+            node_id.to_string(),
+            indexer_node_ids
+                .iter()
+                .map(|indexer_node_id| indexer_node_id.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_indexer_nodes_includes_current_node() {
+        let nodes = indexer_nodes("node-2", &["node-3", "node-1", "node-3"]);
+        assert_eq!(nodes.node_id(), "node-2");
+        assert_eq!(nodes.indexer_node_ids(), &["node-1", "node-2", "node-3"]);
+        let single_node = indexer_nodes("node-1", &[]);
+        assert!(single_node.owns_source("my-index", "my-source"));
+        assert!(single_node.owns_partition("my-index", "my-source", &PartitionId::from(0u64)));
+    }
+
+    #[test]
+    fn test_indexer_nodes_partitions_are_assigned_once() {
+        let node_ids = ["node-1", "node-2", "node-3"];
+        let mut num_partitions_per_node = [0; 3];
+        for partition in 0..300u64 {
+            let partition_id = PartitionId::from(partition);
+            let owners: Vec<usize> = node_ids
+                .iter()
+                .enumerate()
+                .filter(|(_, node_id)| {
+                    indexer_nodes(node_id, &node_ids).owns_partition(
+                        "my-index",
+                        "my-source",
+                        &partition_id,
+                    )
+                })
+                .map(|(node_ord, _)| node_ord)
+                .collect();
+            assert_eq!(owners.len(), 1);
+            num_partitions_per_node[owners[0]] += 1;
+        }
+        assert!(num_partitions_per_node
+            .iter()
+            .all(|&num_partitions| num_partitions > 50));
+        let owners: Vec<&&str> = node_ids
+            .iter()
+            .filter(|node_id| {
+                indexer_nodes(node_id, &node_ids).owns_source("my-index", "my-source")
+            })
+            .collect();
+        assert_eq!(owners.len(), 1);
+    }
+
+    #[test]
+    fn test_indexer_nodes_minimal_reassignment() {
+        // When `node-3` leaves, only the partitions it owned are reassigned.
+        for partition in 0..100u64 {
+            let partition_id = PartitionId::from(partition);
+            for node_id in ["node-1", "node-2"] {
+                let before = indexer_nodes(node_id, &["node-1", "node-2", "node-3"]);
+                let after = indexer_nodes(node_id, &["node-1", "node-2"]);
+                if before.owns_partition("my-index", "my-source", &partition_id) {
+                    assert!(after.owns_partition("my-index", "my-source", &partition_id));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_affinity_is_stable() {
+        // The affinities must not change across releases, otherwise indexers running different
+        // versions would disagree on the assignment.
+        assert_eq!(
+            node_affinity("node-1", &["my-index", "my-source"]),
+            node_affinity("node-1", &["my-index", "my-source"])
+        );
+        assert_ne!(
+            node_affinity("node-1", &["my-index", "my-source"]),
+            node_affinity("node-1", &["my-inde", "xmy-source"])
+        );
+        assert_eq!(
+            node_affinity("node-1", &["my-index", "my-source"]),
+            6434147286634511124
+        );
+    }
+
+    #[test]
+    fn test_partition_assignment() {
+        let partition_id = PartitionId::from(1u64);
+        assert!(PartitionAssignment::default().is_assigned(&partition_id));
+        let node_ids = ["node-1", "node-2"];
+        let num_assigned = node_ids
+            .iter()
+            .filter(|node_id| {
+                PartitionAssignment::Shared {
+                    index_id: "my-index".to_string(),
+                    source_id: "my-source".to_string(),
+                    indexer_nodes: Arc::new(ArcSwap::from_pointee(indexer_nodes(
+                        node_id, &node_ids,
+                    ))),
+                }
+                .is_assigned(&partition_id)
+            })
+            .count();
+        assert_eq!(num_assigned, 1);
+    }
+
+    #[test]
+    fn test_partition_assignment_follows_membership_changes() {
+        let shared_indexer_nodes = Arc::new(ArcSwap::from_pointee(indexer_nodes("node-1", &[])));
+        let partition_assignment = PartitionAssignment::Shared {
+            index_id: "my-index".to_string(),
+            source_id: "my-source".to_string(),
+            indexer_nodes: shared_indexer_nodes.clone(),
+        };
+        let partition_ids: Vec<PartitionId> = (0..100u64).map(PartitionId::from).collect();
+        assert!(partition_ids
+            .iter()
+            .all(|partition_id| partition_assignment.is_assigned(partition_id)));
+
+        shared_indexer_nodes.store(Arc::new(indexer_nodes("node-1", &["node-2"])));
+        let num_assigned = partition_ids
+            .iter()
+            .filter(|partition_id| partition_assignment.is_assigned(partition_id))
+            .count();
+        assert!(num_assigned > 0 && num_assigned < 100);
+    }
+}
@@ -19,11 +19,14 @@
 
 use std::fmt;
 
-use quickwit_metastore::checkpoint::CheckpointDelta;
+use quickwit_metastore::checkpoint::{CheckpointDelta, PartitionId, Position};
 
 #[derive(Default, Clone)]
 pub struct RawDocBatch {
     pub docs: Vec<String>,
+    /// Partition and position of each document of `docs`, for the sources that track them. Either
+    /// empty or of the same length as `docs`.
+    pub doc_positions: Vec<(PartitionId, Position)>,
     pub checkpoint_delta: CheckpointDelta,
 }
 
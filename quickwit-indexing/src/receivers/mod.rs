@@ -0,0 +1,228 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Receivers are listeners to which log shippers push records, for instance rsyslog or an
+//! OpenTelemetry collector.
+//!
+//! Receivers do not have their own indexing pipeline: they are run by the
+//! [`ReceiverService`](crate::actors::ReceiverService) of each indexer node and write the
+//! documents they receive to the ingest API queue of their index before acknowledging them, so
+//! that the documents are persisted until the ingest API source of the index indexes them.
+
+mod otlp;
+mod otlp_json;
+mod syslog;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::bail;
+use quickwit_actors::Mailbox;
+use quickwit_config::{SourceConfig, SourceParams};
+use quickwit_ingest_api::{add_doc, IngestApiService};
+use quickwit_proto::ingest_api::{DocBatch, IngestRequest};
+use serde_json::json;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+/// Maximum number of documents buffered by a receiver before writing them to the queue.
+const MAX_NUM_DOCS_PER_WRITE: usize = 1_000;
+
+#[derive(Debug, Default)]
+struct ReceiverCounters {
+    num_docs_written: AtomicU64,
+    num_write_errors: AtomicU64,
+}
+
+/// Writes the documents received by a receiver to the ingest API queue of its index.
+#[derive(Clone)]
+struct QueueWriter {
+    queue_id: String,
+    ingest_api_mailbox: Mailbox<IngestApiService>,
+    counters: Arc<ReceiverCounters>,
+}
+
+impl QueueWriter {
+    /// Writes `docs` to the queue. Returns once the documents are persisted, so that the receiver
+    /// can acknowledge them.
+    async fn write(&self, docs: Vec<String>) -> anyhow::Result<()> {
+        if docs.is_empty() {
+            return Ok(());
+        }
+        let mut doc_batch = DocBatch {
+            index_id: self.queue_id.clone(),
+            ..Default::default()
+        };
+        for doc in &docs {
+            add_doc(doc.as_bytes(), &mut doc_batch);
+        }
+        let ingest_req = IngestRequest {
+            doc_batches: vec![doc_batch],
+            ..Default::default()
+        };
+        if let Err(error) = self.ingest_api_mailbox.ask_for_res(ingest_req).await {
+            self.counters
+                .num_write_errors
+                .fetch_add(1, Ordering::Relaxed);
+            bail!(
+                "Failed to write documents to queue `{}`: {}",
+                self.queue_id,
+                error
+            );
+        }
+        self.counters
+            .num_docs_written
+            .fetch_add(docs.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// A running receiver. The listeners of the receiver are stopped when it is dropped.
+pub(crate) struct Receiver {
+    index_id: String,
+    source_config: SourceConfig,
+    counters: Arc<ReceiverCounters>,
+    listener_handles: Vec<JoinHandle<()>>,
+}
+
+impl Receiver {
+    pub fn source_config(&self) -> &SourceConfig {
+        &self.source_config
+    }
+
+    pub fn observable_state(&self) -> serde_json::Value {
+        json!({
+            "index_id": self.index_id,
+            "source_id": self.source_config.source_id,
+            "num_docs_written": self.counters.num_docs_written.load(Ordering::Relaxed),
+            "num_write_errors": self.counters.num_write_errors.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Stops the listeners of the receiver and waits for them to release their sockets, so that
+    /// their addresses can be bound again right away.
+    pub async fn stop(mut self) {
+        let listener_handles = std::mem::take(&mut self.listener_handles);
+        for listener_handle in &listener_handles {
+            listener_handle.abort();
+        }
+        for listener_handle in listener_handles {
+            let _ = listener_handle.await;
+        }
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        for listener_handle in &self.listener_handles {
+            listener_handle.abort();
+        }
+    }
+}
+
+/// Starts the receiver configured by `source_config`, which writes the documents it receives to
+/// the ingest API queue of the index `index_id`.
+pub(crate) async fn start_receiver(
+    index_id: &str,
+    source_config: &SourceConfig,
+    ingest_api_mailbox: Mailbox<IngestApiService>,
+) -> anyhow::Result<Receiver> {
+    let counters = Arc::new(ReceiverCounters::default());
+    let queue_writer = QueueWriter {
+        queue_id: index_id.to_string(),
+        ingest_api_mailbox,
+        counters: counters.clone(),
+    };
+    let listener_handles = match &source_config.source_params {
+        SourceParams::Syslog(params) => vec![syslog::start_listener(params, queue_writer).await?],
+        SourceParams::Otlp(params) => otlp::start_listeners(params, queue_writer).await?,
+        _ => bail!(
+            "Source `{}` of type `{}` is not a receiver.",
+            source_config.source_id,
+            source_config.source_type()
+        ),
+    };
+    info!(
+        index_id = index_id,
+        source_id = %source_config.source_id,
+        source_type = source_config.source_type(),
+        "Started receiver."
+    );
+    Ok(Receiver {
+        index_id: index_id.to_string(),
+        source_config: source_config.clone(),
+        counters,
+        listener_handles,
+    })
+}
+
+#[cfg(test)]
+async fn fetch_docs_for_test(
+    queue_writer: &QueueWriter,
+    num_docs: u64,
+) -> anyhow::Result<Vec<serde_json::Value>> {
+    for _ in 0..100 {
+        if queue_writer
+            .counters
+            .num_docs_written
+            .load(Ordering::Relaxed)
+            >= num_docs
+        {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let fetch_req = quickwit_proto::ingest_api::FetchRequest {
+        index_id: queue_writer.queue_id.clone(),
+        start_after: None,
+        num_bytes_limit: None,
+    };
+    let fetch_resp = queue_writer
+        .ingest_api_mailbox
+        .ask_for_res(fetch_req)
+        .await
+        .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+    let docs = fetch_resp
+        .doc_batch
+        .map(|doc_batch| {
+            quickwit_ingest_api::iter_doc_payloads(&doc_batch)
+                .map(|doc_payload| serde_json::from_slice(doc_payload).unwrap())
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(docs)
+}
+
+#[cfg(test)]
+async fn make_queue_writer_for_test(
+    universe: &quickwit_actors::Universe,
+    queue_path: &std::path::Path,
+) -> anyhow::Result<QueueWriter> {
+    let queue_id = "my-index".to_string();
+    let mut queues = quickwit_ingest_api::Queues::open(queue_path)?;
+    queues.create_queue(&queue_id)?;
+    drop(queues);
+    let ingest_api_mailbox =
+        quickwit_ingest_api::spawn_ingest_api_actor(universe, queue_path, false)?;
+    Ok(QueueWriter {
+        queue_id,
+        ingest_api_mailbox,
+        counters: Arc::default(),
+    })
+}
@@ -0,0 +1,696 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! OpenTelemetry logs receiver, implementing the OTLP/gRPC and OTLP/HTTP (binary protobuf and
+//! JSON encodings) logs endpoints.
+
+use std::convert::Infallible;
+use std::io::Cursor;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use prost::Message;
+use quickwit_config::{parse_listen_address, Compression, OtlpSourceParams};
+use quickwit_proto::opentelemetry::proto::collector::logs::v1::logs_service_server::{
+    LogsService, LogsServiceServer,
+};
+use quickwit_proto::opentelemetry::proto::collector::logs::v1::{
+    ExportLogsServiceRequest, ExportLogsServiceResponse,
+};
+use quickwit_proto::opentelemetry::proto::common::v1::any_value::Value as OtlpValue;
+use quickwit_proto::opentelemetry::proto::common::v1::{AnyValue, InstrumentationScope, KeyValue};
+use quickwit_proto::opentelemetry::proto::logs::v1::LogRecord;
+use quickwit_proto::tonic;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use time::OffsetDateTime;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tracing::error;
+use warp::http::StatusCode;
+use warp::hyper::Body;
+use warp::{Filter, Rejection};
+
+use super::otlp_json::export_logs_request_from_json;
+use super::QueueWriter;
+use crate::source::decompressing_reader;
+
+/// Maximum size of an OTLP/HTTP request body.
+const MAX_HTTP_BODY_NUM_BYTES: u64 = 10 * 1024 * 1024;
+
+pub(super) async fn start_listeners(
+    params: &OtlpSourceParams,
+    queue_writer: QueueWriter,
+) -> anyhow::Result<Vec<JoinHandle<()>>> {
+    // Both endpoints are bound before any of them is spawned, so that no listener is left running
+    // when the other one fails to bind.
+    let grpc_listener_opt = if let Some(grpc_listen_address) = &params.grpc_listen_address {
+        let listen_address = parse_listen_address(grpc_listen_address)?;
+        let listener = TcpListener::bind(listen_address).await.with_context(|| {
+            format!("Failed to bind OTLP/gRPC receiver to address `{listen_address}`.")
+        })?;
+        Some(listener)
+    } else {
+        None
+    };
+    let http_server_opt = if let Some(http_listen_address) = &params.http_listen_address {
+        let listen_address = parse_listen_address(http_listen_address)?;
+        let (_, server) = warp::serve(export_logs_filter(queue_writer.clone()))
+            .try_bind_ephemeral(listen_address)
+            .with_context(|| {
+                format!("Failed to bind OTLP/HTTP receiver to address `{listen_address}`.")
+            })?;
+        Some(server)
+    } else {
+        None
+    };
+    let mut listener_handles = Vec::new();
+    if let Some(grpc_listener) = grpc_listener_opt {
+        let logs_service = LogsServiceServer::new(OtlpLogsService { queue_writer });
+        let server = tonic::transport::Server::builder()
+            .add_service(logs_service)
+            .serve_with_incoming(TcpListenerStream::new(grpc_listener));
+        listener_handles.push(tokio::spawn(async move {
+            if let Err(error) = server.await {
+                error!(error = ?error, "OTLP/gRPC receiver failed.");
+            }
+        }));
+    }
+    if let Some(http_server) = http_server_opt {
+        listener_handles.push(tokio::spawn(http_server));
+    }
+    Ok(listener_handles)
+}
+
+struct OtlpLogsService {
+    queue_writer: QueueWriter,
+}
+
+#[async_trait]
+impl LogsService for OtlpLogsService {
+    async fn export(
+        &self,
+        request: tonic::Request<ExportLogsServiceRequest>,
+    ) -> Result<tonic::Response<ExportLogsServiceResponse>, tonic::Status> {
+        let docs = export_logs_request_to_docs(request.into_inner(), OffsetDateTime::now_utc());
+        self.queue_writer
+            .write(docs)
+            .await
+            .map_err(|error| tonic::Status::unavailable(error.to_string()))?;
+        Ok(tonic::Response::new(ExportLogsServiceResponse::default()))
+    }
+}
+
+fn export_logs_filter(
+    queue_writer: QueueWriter,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone {
+    warp::path!("v1" / "logs")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::header::optional::<String>("content-encoding"))
+        .and(warp::body::content_length_limit(MAX_HTTP_BODY_NUM_BYTES))
+        .and(warp::body::bytes())
+        .and(warp::any().map(move || queue_writer.clone()))
+        .and_then(export_logs_endpoint)
+}
+
+/// Encoding of the bodies of OTLP/HTTP requests and responses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HttpEncoding {
+    Protobuf,
+    Json,
+}
+
+impl HttpEncoding {
+    /// Returns the encoding of a request from its content type. The binary protobuf encoding is
+    /// the default.
+    fn from_content_type(content_type_opt: Option<&str>) -> Result<Self, (StatusCode, String)> {
+        let content_type = if let Some(content_type) = content_type_opt {
+            content_type
+        } else {
+            return Ok(HttpEncoding::Protobuf);
+        };
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        match media_type {
+            "application/x-protobuf" => Ok(HttpEncoding::Protobuf),
+            "application/json" => Ok(HttpEncoding::Json),
+            _ => Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!(
+                    "Content type `{content_type}` is not supported: the OTLP/HTTP receiver \
+                     accepts the binary protobuf (`application/x-protobuf`) and JSON \
+                     (`application/json`) encodings."
+                ),
+            )),
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            HttpEncoding::Protobuf => "application/x-protobuf",
+            HttpEncoding::Json => "application/json",
+        }
+    }
+}
+
+async fn export_logs_endpoint(
+    content_type_opt: Option<String>,
+    content_encoding_opt: Option<String>,
+    body: Bytes,
+    queue_writer: QueueWriter,
+) -> Result<warp::reply::Response, Infallible> {
+    let http_encoding = match HttpEncoding::from_content_type(content_type_opt.as_deref()) {
+        Ok(http_encoding) => http_encoding,
+        Err((status_code, message)) => return Ok(text_response(status_code, message)),
+    };
+    let export_logs_request = match decode_export_logs_request(
+        http_encoding,
+        content_encoding_opt.as_deref(),
+        body,
+    )
+    .await
+    {
+        Ok(export_logs_request) => export_logs_request,
+        Err((status_code, message)) => return Ok(text_response(status_code, message)),
+    };
+    let docs = export_logs_request_to_docs(export_logs_request, OffsetDateTime::now_utc());
+    if let Err(error) = queue_writer.write(docs).await {
+        return Ok(text_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            error.to_string(),
+        ));
+    }
+    // The export logs response carries no field when all the records are accepted.
+    let response_body = match http_encoding {
+        HttpEncoding::Protobuf => ExportLogsServiceResponse::default().encode_to_vec(),
+        HttpEncoding::Json => b"{}".to_vec(),
+    };
+    let response = warp::http::Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", http_encoding.content_type())
+        .body(Body::from(response_body))
+        .expect("The response should be valid.");
+    Ok(response)
+}
+
+async fn decode_export_logs_request(
+    http_encoding: HttpEncoding,
+    content_encoding_opt: Option<&str>,
+    body: Bytes,
+) -> Result<ExportLogsServiceRequest, (StatusCode, String)> {
+    let body = match content_encoding_opt {
+        None | Some("identity") => body,
+        Some("gzip") => {
+            let mut decompressed_body = Vec::new();
+            decompressing_reader(Compression::Gzip, Cursor::new(body))
+                .read_to_end(&mut decompressed_body)
+                .await
+                .map_err(|error| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Failed to decompress request body: {error}."),
+                    )
+                })?;
+            Bytes::from(decompressed_body)
+        }
+        Some(content_encoding) => {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Content encoding `{content_encoding}` is not supported."),
+            ))
+        }
+    };
+    let decode_res = match http_encoding {
+        HttpEncoding::Protobuf => {
+            ExportLogsServiceRequest::decode(body).map_err(anyhow::Error::from)
+        }
+        HttpEncoding::Json => export_logs_request_from_json(&body),
+    };
+    decode_res.map_err(|error| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Failed to decode export logs request: {error}"),
+        )
+    })
+}
+
+fn text_response(status_code: StatusCode, message: String) -> warp::reply::Response {
+    warp::http::Response::builder()
+        .status(status_code)
+        .header("content-type", "text/plain")
+        .body(Body::from(message))
+        .expect("The response should be valid.")
+}
+
+fn export_logs_request_to_docs(
+    export_logs_request: ExportLogsServiceRequest,
+    received_at: OffsetDateTime,
+) -> Vec<String> {
+    let mut docs = Vec::new();
+    for resource_logs in export_logs_request.resource_logs {
+        let resource_attributes = resource_logs
+            .resource
+            .map(|resource| key_values_to_json(resource.attributes))
+            .unwrap_or_default();
+        for scope_logs in resource_logs.scope_logs {
+            for log_record in scope_logs.log_records {
+                let doc = log_record_to_json(
+                    log_record,
+                    &resource_attributes,
+                    scope_logs.scope.as_ref(),
+                    received_at,
+                );
+                docs.push(doc.to_string());
+            }
+        }
+    }
+    docs
+}
+
+/// Converts a log record into a JSON document. Resource attributes are stored in the `resource`
+/// field and non-string bodies are serialized as JSON strings.
+fn log_record_to_json(
+    log_record: LogRecord,
+    resource_attributes: &JsonMap<String, JsonValue>,
+    scope_opt: Option<&InstrumentationScope>,
+    received_at: OffsetDateTime,
+) -> JsonValue {
+    let mut doc = JsonMap::new();
+    // Falls back to the observed time, as recommended by the log data model, then to the time of
+    // reception.
+    let timestamp_nanos = [
+        log_record.time_unix_nano,
+        log_record.observed_time_unix_nano,
+    ]
+    .into_iter()
+    .find(|timestamp_nanos| *timestamp_nanos != 0)
+    .unwrap_or(received_at.unix_timestamp_nanos() as u64);
+    doc.insert(
+        "timestamp".to_string(),
+        JsonValue::from(timestamp_nanos / 1_000_000_000),
+    );
+    doc.insert(
+        "timestamp_nanos".to_string(),
+        JsonValue::from(timestamp_nanos),
+    );
+    let severity_text = if log_record.severity_text.is_empty() {
+        severity_text_from_number(log_record.severity_number).map(str::to_string)
+    } else {
+        Some(log_record.severity_text)
+    };
+    if let Some(severity_text) = severity_text {
+        doc.insert("severity_text".to_string(), JsonValue::from(severity_text));
+    }
+    if log_record.severity_number != 0 {
+        doc.insert(
+            "severity_number".to_string(),
+            JsonValue::from(log_record.severity_number),
+        );
+    }
+    if let Some(body) = log_record.body.and_then(any_value_to_json) {
+        let body = match body {
+            JsonValue::String(body) => body,
+            body => body.to_string(),
+        };
+        doc.insert("body".to_string(), JsonValue::from(body));
+    }
+    if !log_record.attributes.is_empty() {
+        doc.insert(
+            "attributes".to_string(),
+            JsonValue::Object(key_values_to_json(log_record.attributes)),
+        );
+    }
+    if log_record.dropped_attributes_count != 0 {
+        doc.insert(
+            "dropped_attributes_count".to_string(),
+            JsonValue::from(log_record.dropped_attributes_count),
+        );
+    }
+    if !resource_attributes.is_empty() {
+        doc.insert(
+            "resource".to_string(),
+            JsonValue::Object(resource_attributes.clone()),
+        );
+    }
+    if let Some(scope) = scope_opt {
+        if !scope.name.is_empty() {
+            doc.insert(
+                "scope_name".to_string(),
+                JsonValue::from(scope.name.clone()),
+            );
+        }
+        if !scope.version.is_empty() {
+            doc.insert(
+                "scope_version".to_string(),
+                JsonValue::from(scope.version.clone()),
+            );
+        }
+    }
+    if !log_record.trace_id.is_empty() {
+        doc.insert(
+            "trace_id".to_string(),
+            JsonValue::from(hex_encode(&log_record.trace_id)),
+        );
+    }
+    if !log_record.span_id.is_empty() {
+        doc.insert(
+            "span_id".to_string(),
+            JsonValue::from(hex_encode(&log_record.span_id)),
+        );
+    }
+    let trace_flags = log_record.flags & 0xff;
+    if trace_flags != 0 {
+        doc.insert("trace_flags".to_string(), JsonValue::from(trace_flags));
+    }
+    JsonValue::Object(doc)
+}
+
+/// Returns the short name of a severity number, as defined by the log data model.
+fn severity_text_from_number(severity_number: i32) -> Option<&'static str> {
+    match severity_number {
+        1..=4 => Some("TRACE"),
+        5..=8 => Some("DEBUG"),
+        9..=12 => Some("INFO"),
+        13..=16 => Some("WARN"),
+        17..=20 => Some("ERROR"),
+        21..=24 => Some("FATAL"),
+        _ => None,
+    }
+}
+
+fn key_values_to_json(key_values: Vec<KeyValue>) -> JsonMap<String, JsonValue> {
+    key_values
+        .into_iter()
+        .filter_map(|key_value| {
+            let value = key_value.value.and_then(any_value_to_json)?;
+            Some((key_value.key, value))
+        })
+        .collect()
+}
+
+/// Converts an OTLP value into a JSON value. Bytes are hex-encoded. Returns `None` for empty
+/// values.
+fn any_value_to_json(any_value: AnyValue) -> Option<JsonValue> {
+    let json_value = match any_value.value? {
+        OtlpValue::StringValue(value) => JsonValue::String(value),
+        OtlpValue::BoolValue(value) => JsonValue::Bool(value),
+        OtlpValue::IntValue(value) => JsonValue::from(value),
+        OtlpValue::DoubleValue(value) => {
+            serde_json::Number::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+        }
+        OtlpValue::ArrayValue(array_value) => JsonValue::Array(
+            array_value
+                .values
+                .into_iter()
+                .filter_map(any_value_to_json)
+                .collect(),
+        ),
+        OtlpValue::KvlistValue(kvlist_value) => {
+            JsonValue::Object(key_values_to_json(kvlist_value.values))
+        }
+        OtlpValue::BytesValue(value) => JsonValue::String(hex_encode(&value)),
+    };
+    Some(json_value)
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use quickwit_actors::Universe;
+    use quickwit_proto::opentelemetry::proto::common::v1::{ArrayValue, KeyValueList};
+    use quickwit_proto::opentelemetry::proto::logs::v1::{ResourceLogs, ScopeLogs};
+    use quickwit_proto::opentelemetry::proto::resource::v1::Resource;
+    use serde_json::json;
+
+    use super::*;
+    use crate::receivers::{fetch_docs_for_test, make_queue_writer_for_test};
+
+    fn string_value(value: &str) -> Option<AnyValue> {
+        Some(AnyValue {
+            value: Some(OtlpValue::StringValue(value.to_string())),
+        })
+    }
+
+    fn key_value(key: &str, value: Option<AnyValue>) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    fn make_export_logs_request() -> ExportLogsServiceRequest {
+        let log_records = vec![
+            LogRecord {
+                time_unix_nano: 1_656_000_000_123_456_789,
+                severity_number: 17,
+                body: string_value("Connection refused"),
+                attributes: vec![
+                    key_value("http.method", string_value("GET")),
+                    key_value(
+                        "http.status_code",
+                        Some(AnyValue {
+                            value: Some(OtlpValue::IntValue(503)),
+                        }),
+                    ),
+                    key_value("empty", None),
+                ],
+                flags: 1,
+                trace_id: vec![0x4b; 16],
+                span_id: vec![0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7],
+                ..Default::default()
+            },
+            LogRecord {
+                observed_time_unix_nano: 1_656_000_001_000_000_000,
+                severity_text: "Information".to_string(),
+                severity_number: 9,
+                body: Some(AnyValue {
+                    value: Some(OtlpValue::KvlistValue(KeyValueList {
+                        values: vec![
+                            key_value(
+                                "ratio",
+                                Some(AnyValue {
+                                    value: Some(OtlpValue::DoubleValue(0.5)),
+                                }),
+                            ),
+                            key_value(
+                                "tags",
+                                Some(AnyValue {
+                                    value: Some(OtlpValue::ArrayValue(ArrayValue {
+                                        values: vec![
+                                            string_value("a").unwrap(),
+                                            AnyValue {
+                                                value: Some(OtlpValue::BytesValue(vec![1, 255])),
+                                            },
+                                        ],
+                                    })),
+                                }),
+                            ),
+                        ],
+                    })),
+                }),
+                ..Default::default()
+            },
+        ];
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: vec![key_value("service.name", string_value("frontend"))],
+                    dropped_attributes_count: 0,
+                }),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope {
+                        name: "my-library".to_string(),
+                        version: "1.0.0".to_string(),
+                    }),
+                    log_records,
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_export_logs_request_to_docs() {
+        let received_at = OffsetDateTime::from_unix_timestamp(1_656_000_002).unwrap();
+        let docs = export_logs_request_to_docs(make_export_logs_request(), received_at)
+            .into_iter()
+            .map(|doc| serde_json::from_str::<JsonValue>(&doc).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            docs,
+            vec![
+                json!({
+                    "timestamp": 1_656_000_000u64,
+                    "timestamp_nanos": 1_656_000_000_123_456_789u64,
+                    "severity_text": "ERROR",
+                    "severity_number": 17,
+                    "body": "Connection refused",
+                    "attributes": {
+                        "http.method": "GET",
+                        "http.status_code": 503,
+                    },
+                    "resource": {
+                        "service.name": "frontend",
+                    },
+                    "scope_name": "my-library",
+                    "scope_version": "1.0.0",
+                    "trace_id": "4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b",
+                    "span_id": "00f067aa0ba902b7",
+                    "trace_flags": 1,
+                }),
+                json!({
+                    "timestamp": 1_656_000_001u64,
+                    "timestamp_nanos": 1_656_000_001_000_000_000u64,
+                    "severity_text": "Information",
+                    "severity_number": 9,
+                    "body": r#"{"ratio":0.5,"tags":["a","01ff"]}"#,
+                    "resource": {
+                        "service.name": "frontend",
+                    },
+                    "scope_name": "my-library",
+                    "scope_version": "1.0.0",
+                }),
+            ]
+        );
+        let export_logs_request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord::default()],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let docs = export_logs_request_to_docs(export_logs_request, received_at);
+        assert_eq!(
+            docs,
+            vec![r#"{"timestamp":1656000002,"timestamp_nanos":1656000002000000000}"#]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_otlp_grpc_receiver() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let queue_path = tempfile::tempdir()?;
+        let queue_writer = make_queue_writer_for_test(&universe, queue_path.path()).await?;
+        let logs_service = OtlpLogsService {
+            queue_writer: queue_writer.clone(),
+        };
+        logs_service
+            .export(tonic::Request::new(make_export_logs_request()))
+            .await?;
+        let docs = fetch_docs_for_test(&queue_writer, 2).await?;
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["body"], "Connection refused");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_otlp_http_receiver() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let queue_path = tempfile::tempdir()?;
+        let queue_writer = make_queue_writer_for_test(&universe, queue_path.path()).await?;
+        let export_logs_filter = export_logs_filter(queue_writer.clone());
+
+        let body = make_export_logs_request().encode_to_vec();
+        let response = warp::test::request()
+            .method("POST")
+            .path("/v1/logs")
+            .header("content-type", "application/x-protobuf")
+            .body(body.clone())
+            .reply(&export_logs_filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        ExportLogsServiceResponse::decode(response.body().clone())?;
+
+        let mut gzip_encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip_encoder.write_all(&body)?;
+        let response = warp::test::request()
+            .method("POST")
+            .path("/v1/logs")
+            .header("content-type", "application/x-protobuf")
+            .header("content-encoding", "gzip")
+            .body(gzip_encoder.finish()?)
+            .reply(&export_logs_filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let docs = fetch_docs_for_test(&queue_writer, 4).await?;
+        assert_eq!(docs.len(), 4);
+
+        let json_body = r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{
+            "timeUnixNano": "1656000000123456789",
+            "body": {"stringValue": "Connection refused"}
+        }]}]}]}"#;
+        let response = warp::test::request()
+            .method("POST")
+            .path("/v1/logs")
+            .header("content-type", "application/json")
+            .body(json_body)
+            .reply(&export_logs_filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert_eq!(response.body().as_ref(), b"{}");
+
+        let docs = fetch_docs_for_test(&queue_writer, 5).await?;
+        assert_eq!(docs.len(), 5);
+        assert_eq!(docs[4]["timestamp_nanos"], 1_656_000_000_123_456_789u64);
+        assert_eq!(docs[4]["body"], "Connection refused");
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/v1/logs")
+            .header("content-type", "text/plain")
+            .body("Connection refused")
+            .reply(&export_logs_filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/v1/logs")
+            .header("content-type", "application/json")
+            .body(r#"{"resourceLogs": {}}"#)
+            .reply(&export_logs_filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/v1/logs")
+            .header("content-type", "application/x-protobuf")
+            .body("not a protobuf message")
+            .reply(&export_logs_filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of the JSON encoding of OTLP/HTTP export logs requests.
+//!
+//! The encoding follows the JSON mapping of protobuf messages, with the exceptions specified by
+//! OTLP: trace and span IDs are hex-encoded rather than base64-encoded, and field names are in
+//! lowerCamelCase. As with any protobuf JSON parser, the original field names are accepted too,
+//! 64-bit integers may be encoded as numbers or strings, and unknown fields are ignored.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use quickwit_proto::opentelemetry::proto::collector::logs::v1::ExportLogsServiceRequest;
+use quickwit_proto::opentelemetry::proto::common::v1::any_value::Value as OtlpValue;
+use quickwit_proto::opentelemetry::proto::common::v1::{
+    AnyValue, ArrayValue, InstrumentationScope, KeyValue, KeyValueList,
+};
+use quickwit_proto::opentelemetry::proto::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+use quickwit_proto::opentelemetry::proto::resource::v1::Resource;
+use serde_json::Value as JsonValue;
+
+pub(super) fn export_logs_request_from_json(
+    body: &[u8],
+) -> anyhow::Result<ExportLogsServiceRequest> {
+    let json_value: JsonValue = serde_json::from_slice(body).context("Invalid JSON.")?;
+    let resource_logs = json_array(&json_value, "resourceLogs", "resource_logs")?
+        .iter()
+        .map(resource_logs_from_json)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ExportLogsServiceRequest { resource_logs })
+}
+
+fn resource_logs_from_json(json_value: &JsonValue) -> anyhow::Result<ResourceLogs> {
+    let resource = json_field(json_value, "resource", "resource")
+        .map(|resource_json| -> anyhow::Result<Resource> {
+            Ok(Resource {
+                attributes: json_key_values(resource_json, "attributes", "attributes")?,
+                dropped_attributes_count: json_integer(
+                    resource_json,
+                    "droppedAttributesCount",
+                    "dropped_attributes_count",
+                )?,
+            })
+        })
+        .transpose()?;
+    let scope_logs = json_array(json_value, "scopeLogs", "scope_logs")?
+        .iter()
+        .map(scope_logs_from_json)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ResourceLogs {
+        resource,
+        scope_logs,
+        schema_url: json_string(json_value, "schemaUrl", "schema_url")?,
+    })
+}
+
+fn scope_logs_from_json(json_value: &JsonValue) -> anyhow::Result<ScopeLogs> {
+    let scope = json_field(json_value, "scope", "scope")
+        .map(|scope_json| -> anyhow::Result<InstrumentationScope> {
+            Ok(InstrumentationScope {
+                name: json_string(scope_json, "name", "name")?,
+                version: json_string(scope_json, "version", "version")?,
+            })
+        })
+        .transpose()?;
+    let log_records = json_array(json_value, "logRecords", "log_records")?
+        .iter()
+        .map(log_record_from_json)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ScopeLogs {
+        scope,
+        log_records,
+        schema_url: json_string(json_value, "schemaUrl", "schema_url")?,
+    })
+}
+
+fn log_record_from_json(json_value: &JsonValue) -> anyhow::Result<LogRecord> {
+    let body = json_field(json_value, "body", "body")
+        .map(any_value_from_json)
+        .transpose()?;
+    Ok(LogRecord {
+        time_unix_nano: json_integer(json_value, "timeUnixNano", "time_unix_nano")?,
+        observed_time_unix_nano: json_integer(
+            json_value,
+            "observedTimeUnixNano",
+            "observed_time_unix_nano",
+        )?,
+        severity_number: json_integer(json_value, "severityNumber", "severity_number")?,
+        severity_text: json_string(json_value, "severityText", "severity_text")?,
+        body,
+        attributes: json_key_values(json_value, "attributes", "attributes")?,
+        dropped_attributes_count: json_integer(
+            json_value,
+            "droppedAttributesCount",
+            "dropped_attributes_count",
+        )?,
+        flags: json_integer(json_value, "flags", "flags")?,
+        trace_id: json_hex_bytes(json_value, "traceId", "trace_id")?,
+        span_id: json_hex_bytes(json_value, "spanId", "span_id")?,
+    })
+}
+
+fn any_value_from_json(json_value: &JsonValue) -> anyhow::Result<AnyValue> {
+    let value = if let Some(value) = json_field(json_value, "stringValue", "string_value") {
+        let value = value
+            .as_str()
+            .context("Field `stringValue` must be a string.")?;
+        OtlpValue::StringValue(value.to_string())
+    } else if let Some(value) = json_field(json_value, "boolValue", "bool_value") {
+        let value = value
+            .as_bool()
+            .context("Field `boolValue` must be a boolean.")?;
+        OtlpValue::BoolValue(value)
+    } else if json_field(json_value, "intValue", "int_value").is_some() {
+        OtlpValue::IntValue(json_integer(json_value, "intValue", "int_value")?)
+    } else if let Some(value) = json_field(json_value, "doubleValue", "double_value") {
+        let value = value
+            .as_f64()
+            .context("Field `doubleValue` must be a number.")?;
+        OtlpValue::DoubleValue(value)
+    } else if let Some(value) = json_field(json_value, "arrayValue", "array_value") {
+        let values = json_array(value, "values", "values")?
+            .iter()
+            .map(any_value_from_json)
+            .collect::<anyhow::Result<_>>()?;
+        OtlpValue::ArrayValue(ArrayValue { values })
+    } else if let Some(value) = json_field(json_value, "kvlistValue", "kvlist_value") {
+        let values = json_key_values(value, "values", "values")?;
+        OtlpValue::KvlistValue(KeyValueList { values })
+    } else if let Some(value) = json_field(json_value, "bytesValue", "bytes_value") {
+        let value = value
+            .as_str()
+            .and_then(|value| base64::decode(value).ok())
+            .context("Field `bytesValue` must be a base64-encoded string.")?;
+        OtlpValue::BytesValue(value)
+    } else {
+        return Ok(AnyValue { value: None });
+    };
+    Ok(AnyValue { value: Some(value) })
+}
+
+fn json_key_values(
+    json_value: &JsonValue,
+    camel_case_name: &str,
+    name: &str,
+) -> anyhow::Result<Vec<KeyValue>> {
+    json_array(json_value, camel_case_name, name)?
+        .iter()
+        .map(|key_value_json| {
+            let value = json_field(key_value_json, "value", "value")
+                .map(any_value_from_json)
+                .transpose()?;
+            Ok(KeyValue {
+                key: json_string(key_value_json, "key", "key")?,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Returns the field `camel_case_name`, or `name`, of an object. Null fields are ignored.
+fn json_field<'a>(
+    json_value: &'a JsonValue,
+    camel_case_name: &str,
+    name: &str,
+) -> Option<&'a JsonValue> {
+    json_value
+        .get(camel_case_name)
+        .or_else(|| json_value.get(name))
+        .filter(|field_value| !field_value.is_null())
+}
+
+fn json_array<'a>(
+    json_value: &'a JsonValue,
+    camel_case_name: &str,
+    name: &str,
+) -> anyhow::Result<&'a [JsonValue]> {
+    match json_field(json_value, camel_case_name, name) {
+        None => Ok(&[]),
+        Some(JsonValue::Array(values)) => Ok(values),
+        Some(_) => bail!("Field `{camel_case_name}` must be an array."),
+    }
+}
+
+fn json_string(
+    json_value: &JsonValue,
+    camel_case_name: &str,
+    name: &str,
+) -> anyhow::Result<String> {
+    match json_field(json_value, camel_case_name, name) {
+        None => Ok(String::new()),
+        Some(JsonValue::String(value)) => Ok(value.clone()),
+        Some(_) => bail!("Field `{camel_case_name}` must be a string."),
+    }
+}
+
+/// Parses an integer field, encoded as a number or, for 64-bit integers, as a string.
+fn json_integer<T>(json_value: &JsonValue, camel_case_name: &str, name: &str) -> anyhow::Result<T>
+where T: FromStr + Default {
+    let integer_opt = match json_field(json_value, camel_case_name, name) {
+        None => return Ok(T::default()),
+        Some(JsonValue::Number(value)) => value.to_string().parse::<T>().ok(),
+        Some(JsonValue::String(value)) => value.parse::<T>().ok(),
+        Some(_) => None,
+    };
+    integer_opt.with_context(|| format!("Field `{camel_case_name}` must be an integer."))
+}
+
+fn json_hex_bytes(
+    json_value: &JsonValue,
+    camel_case_name: &str,
+    name: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let hex_str = json_string(json_value, camel_case_name, name)?;
+    if hex_str.len() % 2 != 0 || !hex_str.is_ascii() {
+        bail!("Field `{camel_case_name}` must be a hex-encoded string.");
+    }
+    (0..hex_str.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&hex_str[idx..idx + 2], 16)
+                .with_context(|| format!("Field `{camel_case_name}` must be a hex-encoded string."))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_logs_request_from_json() {
+        let body = r#"{
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": [{"key": "service.name", "value": {"stringValue": "frontend"}}]
+                },
+                "scopeLogs": [{
+                    "scope": {"name": "my-library", "version": "1.0.0"},
+                    "logRecords": [{
+                        "timeUnixNano": "1656000000123456789",
+                        "severityNumber": 17,
+                        "severityText": "ERROR",
+                        "body": {"stringValue": "Connection refused"},
+                        "attributes": [
+                            {"key": "http.status_code", "value": {"intValue": "503"}},
+                            {"key": "retry", "value": {"boolValue": true}},
+                            {"key": "ratio", "value": {"doubleValue": 0.5}},
+                            {"key": "tags", "value": {"arrayValue": {"values": [
+                                {"stringValue": "a"},
+                                {"bytesValue": "Af8="}
+                            ]}}},
+                            {"key": "empty", "value": {}}
+                        ],
+                        "flags": 1,
+                        "traceId": "4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b",
+                        "spanId": "00f067aa0ba902b7",
+                        "unknownField": 42
+                    }, {
+                        "observed_time_unix_nano": 1656000001000000000,
+                        "body": {"kvlistValue": {"values": [
+                            {"key": "user", "value": {"stringValue": "alice"}}
+                        ]}}
+                    }]
+                }]
+            }]
+        }"#;
+        let export_logs_request = export_logs_request_from_json(body.as_bytes()).unwrap();
+        let resource_logs = &export_logs_request.resource_logs[0];
+        assert_eq!(
+            resource_logs.resource.as_ref().unwrap().attributes[0].key,
+            "service.name"
+        );
+        let scope_logs = &resource_logs.scope_logs[0];
+        assert_eq!(scope_logs.scope.as_ref().unwrap().name, "my-library");
+        assert_eq!(scope_logs.log_records.len(), 2);
+
+        let log_record = &scope_logs.log_records[0];
+        assert_eq!(log_record.time_unix_nano, 1_656_000_000_123_456_789);
+        assert_eq!(log_record.severity_number, 17);
+        assert_eq!(log_record.severity_text, "ERROR");
+        assert_eq!(
+            log_record.body.as_ref().unwrap().value,
+            Some(OtlpValue::StringValue("Connection refused".to_string()))
+        );
+        let attribute_values: Vec<Option<OtlpValue>> = log_record
+            .attributes
+            .iter()
+            .map(|key_value| key_value.value.clone().unwrap().value)
+            .collect();
+        assert_eq!(
+            attribute_values,
+            vec![
+                Some(OtlpValue::IntValue(503)),
+                Some(OtlpValue::BoolValue(true)),
+                Some(OtlpValue::DoubleValue(0.5)),
+                Some(OtlpValue::ArrayValue(ArrayValue {
+                    values: vec![
+                        AnyValue {
+                            value: Some(OtlpValue::StringValue("a".to_string()))
+                        },
+                        AnyValue {
+                            value: Some(OtlpValue::BytesValue(vec![1, 255]))
+                        },
+                    ]
+                })),
+                None,
+            ]
+        );
+        assert_eq!(log_record.flags, 1);
+        assert_eq!(log_record.trace_id, vec![0x4b; 16]);
+        assert_eq!(
+            log_record.span_id,
+            vec![0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7]
+        );
+
+        let log_record = &scope_logs.log_records[1];
+        assert_eq!(
+            log_record.observed_time_unix_nano,
+            1_656_000_001_000_000_000
+        );
+        assert!(matches!(
+            log_record.body.as_ref().unwrap().value,
+            Some(OtlpValue::KvlistValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_logs_request_from_invalid_json() {
+        for body in [
+            "not json",
+            r#"{"resourceLogs": {}}"#,
+            r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{"timeUnixNano": "now"}]}]}]}"#,
+            r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{"traceId": "4b4"}]}]}]}"#,
+            r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{"severityNumber": -1.5}]}]}]}"#,
+        ] {
+            export_logs_request_from_json(body.as_bytes()).unwrap_err();
+        }
+    }
+}
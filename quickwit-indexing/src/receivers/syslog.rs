@@ -0,0 +1,665 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Syslog receiver, accepting RFC 5424 and RFC 3164 (BSD) messages over UDP or TCP.
+
+use std::io;
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use quickwit_config::{parse_listen_address, SyslogProtocol, SyslogSourceParams};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use super::{QueueWriter, MAX_NUM_DOCS_PER_WRITE};
+
+/// Maximum payload size of a UDP datagram.
+const MAX_DATAGRAM_NUM_BYTES: usize = 65_507;
+
+/// Maximum size of a message received over TCP.
+const MAX_FRAME_NUM_BYTES: usize = 1 << 20;
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+pub(super) async fn start_listener(
+    params: &SyslogSourceParams,
+    queue_writer: QueueWriter,
+) -> anyhow::Result<JoinHandle<()>> {
+    let listen_address = parse_listen_address(&params.listen_address)?;
+    let listener_handle = match params.protocol {
+        SyslogProtocol::Udp => {
+            let socket = UdpSocket::bind(listen_address).await.with_context(|| {
+                format!("Failed to bind syslog receiver to UDP address `{listen_address}`.")
+            })?;
+            tokio::spawn(receive_datagrams(socket, queue_writer))
+        }
+        SyslogProtocol::Tcp => {
+            let listener = TcpListener::bind(listen_address).await.with_context(|| {
+                format!("Failed to bind syslog receiver to TCP address `{listen_address}`.")
+            })?;
+            tokio::spawn(accept_connections(listener, queue_writer))
+        }
+    };
+    Ok(listener_handle)
+}
+
+async fn receive_datagrams(socket: UdpSocket, queue_writer: QueueWriter) {
+    let mut buffer = vec![0u8; MAX_DATAGRAM_NUM_BYTES];
+    loop {
+        let mut docs = Vec::new();
+        match socket.recv_from(&mut buffer).await {
+            Ok((num_bytes, _)) => docs.extend(parse_message(&buffer[..num_bytes])),
+            Err(error) => {
+                warn!(error = ?error, "Failed to receive syslog datagram.");
+                continue;
+            }
+        }
+        // Drains the datagrams received in the meantime to write them in a single batch.
+        while docs.len() < MAX_NUM_DOCS_PER_WRITE {
+            match socket.try_recv_from(&mut buffer) {
+                Ok((num_bytes, _)) => docs.extend(parse_message(&buffer[..num_bytes])),
+                Err(_) => break,
+            }
+        }
+        // Syslog over UDP offers no delivery guarantee: the messages are dropped.
+        if let Err(error) = queue_writer.write(docs).await {
+            warn!(error = ?error, "Failed to write syslog messages.");
+        }
+    }
+}
+
+async fn accept_connections(listener: TcpListener, queue_writer: QueueWriter) {
+    // The connections are polled by this task so that they are closed when the listener stops.
+    let mut connections = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            accept_res = listener.accept() => match accept_res {
+                Ok((stream, peer_address)) => {
+                    connections.push(receive_frames(stream, peer_address, queue_writer.clone()));
+                }
+                Err(error) => warn!(error = ?error, "Failed to accept syslog connection."),
+            },
+            Some(()) = connections.next(), if !connections.is_empty() => {}
+        }
+    }
+}
+
+async fn receive_frames(stream: TcpStream, peer_address: SocketAddr, queue_writer: QueueWriter) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut docs = Vec::new();
+        let mut is_eof = false;
+        // Reads the frames already buffered to write them in a single batch.
+        loop {
+            match read_frame(&mut reader).await {
+                Ok(Some(frame)) => docs.extend(parse_message(&frame)),
+                Ok(None) => {
+                    is_eof = true;
+                    break;
+                }
+                Err(error) => {
+                    warn!(
+                        peer_address = %peer_address,
+                        error = ?error,
+                        "Failed to read syslog frame."
+                    );
+                    is_eof = true;
+                    break;
+                }
+            }
+            if reader.buffer().is_empty() || docs.len() >= MAX_NUM_DOCS_PER_WRITE {
+                break;
+            }
+        }
+        if let Err(error) = queue_writer.write(docs).await {
+            // Closing the connection lets the sender know that the messages were not delivered.
+            warn!(
+                peer_address = %peer_address,
+                error = ?error,
+                "Failed to write syslog messages."
+            );
+            return;
+        }
+        if is_eof {
+            return;
+        }
+    }
+}
+
+/// Reads a frame either framed with octet counting, i.e. prefixed with its length, or terminated
+/// by a newline, as described in RFC 6587. Returns `None` at the end of the stream.
+async fn read_frame<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where R: AsyncBufRead + Unpin {
+    let buffer = reader.fill_buf().await?;
+    if buffer.is_empty() {
+        return Ok(None);
+    }
+    if buffer[0].is_ascii_digit() {
+        let mut frame_len_bytes = Vec::new();
+        (&mut *reader)
+            .take(8)
+            .read_until(b' ', &mut frame_len_bytes)
+            .await?;
+        let frame_len = std::str::from_utf8(&frame_len_bytes)
+            .ok()
+            .and_then(|frame_len_str| frame_len_str.trim_end().parse::<usize>().ok())
+            .filter(|frame_len| *frame_len <= MAX_FRAME_NUM_BYTES)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid frame length."))?;
+        let mut frame = vec![0u8; frame_len];
+        reader.read_exact(&mut frame).await?;
+        return Ok(Some(frame));
+    }
+    let mut frame = Vec::new();
+    (&mut *reader)
+        .take(MAX_FRAME_NUM_BYTES as u64)
+        .read_until(b'\n', &mut frame)
+        .await?;
+    Ok(Some(frame))
+}
+
+fn parse_message(message: &[u8]) -> Option<String> {
+    let doc = syslog_message_to_json(message, OffsetDateTime::now_utc())?;
+    Some(doc.to_string())
+}
+
+/// Converts a syslog message into a JSON document. Messages that follow neither RFC 5424 nor RFC
+/// 3164 are indexed as is in the `message` field. Returns `None` for blank messages.
+fn syslog_message_to_json(message: &[u8], received_at: OffsetDateTime) -> Option<JsonValue> {
+    let message = String::from_utf8_lossy(message);
+    let message = message.trim_end_matches(&['\r', '\n', '\0'][..]);
+    if message.trim().is_empty() {
+        return None;
+    }
+    let mut doc = JsonMap::new();
+    let message = if let Some((priority, message)) = parse_priority(message) {
+        doc.insert(
+            "facility".to_string(),
+            JsonValue::from(FACILITIES[priority / 8]),
+        );
+        doc.insert(
+            "severity".to_string(),
+            JsonValue::from(SEVERITIES[priority % 8]),
+        );
+        message
+    } else {
+        message
+    };
+    if let Some(fields) = parse_rfc5424(message).or_else(|| parse_rfc3164(message, received_at)) {
+        doc.extend(fields);
+    } else {
+        doc.insert("message".to_string(), JsonValue::from(message));
+    }
+    if !doc.contains_key("timestamp") {
+        insert_timestamp(&mut doc, received_at);
+    }
+    Some(JsonValue::Object(doc))
+}
+
+/// Inserts the time of a message as the number of seconds since the Unix epoch, in the
+/// `timestamp` field, and as the number of nanoseconds, in the `timestamp_nanos` field, so that
+/// sub-second precision is not lost.
+fn insert_timestamp(fields: &mut JsonMap<String, JsonValue>, datetime: OffsetDateTime) {
+    fields.insert(
+        "timestamp".to_string(),
+        JsonValue::from(datetime.unix_timestamp()),
+    );
+    fields.insert(
+        "timestamp_nanos".to_string(),
+        JsonValue::from(datetime.unix_timestamp_nanos() as i64),
+    );
+}
+
+/// Parses the `<PRI>` header of a message.
+fn parse_priority(message: &str) -> Option<(usize, &str)> {
+    let (priority_str, message) = message.strip_prefix('<')?.split_once('>')?;
+    if priority_str.is_empty()
+        || priority_str.len() > 3
+        || !priority_str.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let priority = priority_str
+        .parse::<usize>()
+        .ok()
+        .filter(|priority| *priority < 192)?;
+    Some((priority, message))
+}
+
+/// Parses the fields following the priority of an RFC 5424 message:
+/// `VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]`.
+fn parse_rfc5424(message: &str) -> Option<JsonMap<String, JsonValue>> {
+    let (version, mut message) = message.split_once(' ')?;
+    if version.is_empty() || version.len() > 2 || !version.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut fields = JsonMap::new();
+    let (timestamp, rest) = message.split_once(' ')?;
+    if timestamp != "-" {
+        let timestamp = OffsetDateTime::parse(timestamp, &Rfc3339).ok()?;
+        insert_timestamp(&mut fields, timestamp);
+    }
+    message = rest;
+    for field_name in ["hostname", "app_name", "proc_id", "msg_id"] {
+        let (value, rest) = message.split_once(' ')?;
+        if value != "-" {
+            fields.insert(field_name.to_string(), JsonValue::from(value));
+        }
+        message = rest;
+    }
+    let (structured_data, message) = parse_structured_data(message)?;
+    if !structured_data.is_empty() {
+        fields.insert(
+            "structured_data".to_string(),
+            JsonValue::Object(structured_data),
+        );
+    }
+    let message = message.strip_prefix(' ').unwrap_or(message);
+    let message = message.strip_prefix('\u{feff}').unwrap_or(message);
+    if !message.is_empty() {
+        fields.insert("message".to_string(), JsonValue::from(message));
+    }
+    Some(fields)
+}
+
+/// Parses the structured data of an RFC 5424 message, i.e. `-` or a sequence of
+/// `[SD-ID PARAM-NAME="PARAM-VALUE" ...]` elements, into an object mapping the ID of each element
+/// to its parameters.
+fn parse_structured_data(message: &str) -> Option<(JsonMap<String, JsonValue>, &str)> {
+    if let Some(rest) = message.strip_prefix('-') {
+        return Some((JsonMap::new(), rest));
+    }
+    let mut elements = JsonMap::new();
+    let mut rest = message;
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find(|c| c == ' ' || c == ']')?;
+        let (id, mut element) = element.split_at(id_end);
+        let mut params = JsonMap::new();
+        loop {
+            element = element.trim_start_matches(' ');
+            if let Some(after_element) = element.strip_prefix(']') {
+                rest = after_element;
+                break;
+            }
+            let (name, value_start) = element.split_once("=\"")?;
+            let (value, after_value) = parse_param_value(value_start)?;
+            params.insert(name.to_string(), JsonValue::from(value));
+            element = after_value;
+        }
+        elements.insert(id.to_string(), JsonValue::Object(params));
+    }
+    if elements.is_empty() {
+        return None;
+    }
+    Some((elements, rest))
+}
+
+/// Parses a structured data parameter value up to its closing quote, unescaping `\"`, `\\`, and
+/// `\]`.
+fn parse_param_value(value_start: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = value_start.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &value_start[idx + 1..])),
+            '\\' => match chars.next()? {
+                (_, escaped @ ('"' | '\\' | ']')) => value.push(escaped),
+                (_, other) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+            },
+            _ => value.push(c),
+        }
+    }
+    None
+}
+
+/// Parses the fields following the priority of an RFC 3164 message:
+/// `Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG`.
+fn parse_rfc3164(message: &str, received_at: OffsetDateTime) -> Option<JsonMap<String, JsonValue>> {
+    let timestamp = message.get(..15)?;
+    let timestamp = parse_rfc3164_timestamp(timestamp, received_at)?;
+    let message = message[15..].strip_prefix(' ')?;
+    let mut fields = JsonMap::new();
+    insert_timestamp(&mut fields, timestamp);
+    let (hostname, message) = message.split_once(' ').unwrap_or((message, ""));
+    if !hostname.is_empty() {
+        fields.insert("hostname".to_string(), JsonValue::from(hostname));
+    }
+    let message = if let Some((app_name, proc_id_opt, message)) = parse_tag(message) {
+        fields.insert("app_name".to_string(), JsonValue::from(app_name));
+        if let Some(proc_id) = proc_id_opt {
+            fields.insert("proc_id".to_string(), JsonValue::from(proc_id));
+        }
+        message
+    } else {
+        message
+    };
+    if !message.is_empty() {
+        fields.insert("message".to_string(), JsonValue::from(message));
+    }
+    Some(fields)
+}
+
+/// Parses an RFC 3164 timestamp, which carries neither the year nor the time zone. The timestamp
+/// is assumed to be in UTC and in the year of reception, unless it would then be more than a day
+/// ahead of the time of reception.
+fn parse_rfc3164_timestamp(timestamp: &str, received_at: OffsetDateTime) -> Option<OffsetDateTime> {
+    let timestamp_bytes = timestamp.as_bytes();
+    if !timestamp.is_ascii() || timestamp_bytes[3] != b' ' || timestamp_bytes[6] != b' ' {
+        return None;
+    }
+    let month = match &timestamp[..3] {
+        "Jan" => Month::January,
+        "Feb" => Month::February,
+        "Mar" => Month::March,
+        "Apr" => Month::April,
+        "May" => Month::May,
+        "Jun" => Month::June,
+        "Jul" => Month::July,
+        "Aug" => Month::August,
+        "Sep" => Month::September,
+        "Oct" => Month::October,
+        "Nov" => Month::November,
+        "Dec" => Month::December,
+        _ => return None,
+    };
+    let day = timestamp[4..6].trim_start().parse::<u8>().ok()?;
+    let mut time_parts = timestamp[7..].split(':');
+    let mut next_time_part = || time_parts.next()?.parse::<u8>().ok();
+    let time = Time::from_hms(next_time_part()?, next_time_part()?, next_time_part()?).ok()?;
+    let year = received_at.year();
+    let datetime =
+        PrimitiveDateTime::new(Date::from_calendar_date(year, month, day).ok()?, time).assume_utc();
+    if datetime <= received_at + time::Duration::days(1) {
+        return Some(datetime);
+    }
+    let datetime =
+        PrimitiveDateTime::new(Date::from_calendar_date(year - 1, month, day).ok()?, time)
+            .assume_utc();
+    Some(datetime)
+}
+
+/// Parses the tag of an RFC 3164 message, i.e. the name of the program that emitted the message,
+/// optionally followed by its process ID in brackets, and a colon.
+fn parse_tag(message: &str) -> Option<(&str, Option<&str>, &str)> {
+    let tag_end = message.find(|c: char| c == ':' || c == '[' || c.is_whitespace())?;
+    if tag_end == 0 {
+        return None;
+    }
+    let (app_name, mut rest) = message.split_at(tag_end);
+    let mut proc_id_opt = None;
+    if let Some(after_bracket) = rest.strip_prefix('[') {
+        let (proc_id, after_proc_id) = after_bracket.split_once(']')?;
+        proc_id_opt = Some(proc_id);
+        rest = after_proc_id;
+    }
+    let message = rest.strip_prefix(':')?;
+    Some((app_name, proc_id_opt, message.trim_start()))
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+    use serde_json::json;
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::receivers::{fetch_docs_for_test, make_queue_writer_for_test};
+
+    #[test]
+    fn test_syslog_rfc5424_message_to_json() {
+        let received_at = OffsetDateTime::from_unix_timestamp(1_066_000_000).unwrap();
+        let message = "<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - \
+                       \u{feff}'su root' failed for lonvick on /dev/pts/8\n";
+        assert_eq!(
+            syslog_message_to_json(message.as_bytes(), received_at).unwrap(),
+            json!({
+                "facility": "auth",
+                "severity": "crit",
+                "timestamp": 1_065_910_455,
+                "timestamp_nanos": 1_065_910_455_003_000_000i64,
+                "hostname": "mymachine.example.com",
+                "app_name": "su",
+                "msg_id": "ID47",
+                "message": "'su root' failed for lonvick on /dev/pts/8",
+            })
+        );
+        let message = "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+                       [exampleSDID@32473 iut=\"3\" \
+                       eventSource=\"Application\"][examplePriority@32473 class=\"high\\]\"] An \
+                       application event log entry";
+        assert_eq!(
+            syslog_message_to_json(message.as_bytes(), received_at).unwrap(),
+            json!({
+                "facility": "local4",
+                "severity": "notice",
+                "timestamp": 1_065_910_455,
+                "timestamp_nanos": 1_065_910_455_003_000_000i64,
+                "hostname": "mymachine.example.com",
+                "app_name": "evntslog",
+                "msg_id": "ID47",
+                "structured_data": {
+                    "exampleSDID@32473": {
+                        "iut": "3",
+                        "eventSource": "Application",
+                    },
+                    "examplePriority@32473": {
+                        "class": "high]",
+                    },
+                },
+                "message": "An application event log entry",
+            })
+        );
+        assert_eq!(
+            syslog_message_to_json(b"<13>1 - - - - - -", received_at).unwrap(),
+            json!({
+                "facility": "user",
+                "severity": "notice",
+                "timestamp": 1_066_000_000,
+                "timestamp_nanos": 1_066_000_000_000_000_000i64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_syslog_rfc3164_message_to_json() {
+        let received_at = OffsetDateTime::from_unix_timestamp(1_065_914_055).unwrap();
+        let message = b"<34>Oct 11 22:14:15 mymachine su: 'su root' failed for lonvick";
+        assert_eq!(
+            syslog_message_to_json(message, received_at).unwrap(),
+            json!({
+                "facility": "auth",
+                "severity": "crit",
+                "timestamp": 1_065_910_455,
+                "timestamp_nanos": 1_065_910_455_000_000_000i64,
+                "hostname": "mymachine",
+                "app_name": "su",
+                "message": "'su root' failed for lonvick",
+            })
+        );
+        let message = b"<38>Oct  1 02:03:04 host sshd[1234]: Accepted publickey for root";
+        assert_eq!(
+            syslog_message_to_json(message, received_at).unwrap(),
+            json!({
+                "facility": "auth",
+                "severity": "info",
+                "timestamp": 1_064_973_784,
+                "timestamp_nanos": 1_064_973_784_000_000_000i64,
+                "hostname": "host",
+                "app_name": "sshd",
+                "proc_id": "1234",
+                "message": "Accepted publickey for root",
+            })
+        );
+        // Messages received early January may have been sent late December of the previous year.
+        let received_at = OffsetDateTime::from_unix_timestamp(1_072_915_200).unwrap();
+        let message = b"<13>Dec 31 23:59:59 host kernel message without tag";
+        assert_eq!(
+            syslog_message_to_json(message, received_at).unwrap(),
+            json!({
+                "facility": "user",
+                "severity": "notice",
+                "timestamp": 1_072_915_199,
+                "timestamp_nanos": 1_072_915_199_000_000_000i64,
+                "hostname": "host",
+                "message": "kernel message without tag",
+            })
+        );
+    }
+
+    #[test]
+    fn test_syslog_invalid_message_to_json() {
+        let received_at = OffsetDateTime::from_unix_timestamp(1_066_000_000).unwrap();
+        assert_eq!(
+            syslog_message_to_json(b"hello world", received_at).unwrap(),
+            json!({
+                "timestamp": 1_066_000_000,
+                "timestamp_nanos": 1_066_000_000_000_000_000i64,
+                "message": "hello world",
+            })
+        );
+        assert_eq!(
+            syslog_message_to_json(b"<999>hello", received_at).unwrap(),
+            json!({
+                "timestamp": 1_066_000_000,
+                "timestamp_nanos": 1_066_000_000_000_000_000i64,
+                "message": "<999>hello",
+            })
+        );
+        assert_eq!(
+            syslog_message_to_json(b"<14>1 2003-13-45T00:00:00Z - - - - -", received_at).unwrap(),
+            json!({
+                "facility": "user",
+                "severity": "info",
+                "timestamp": 1_066_000_000,
+                "timestamp_nanos": 1_066_000_000_000_000_000i64,
+                "message": "1 2003-13-45T00:00:00Z - - - - -",
+            })
+        );
+        assert!(syslog_message_to_json(b" \r\n", received_at).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_frame() {
+        let mut reader: &[u8] = b"17 <13>1 - - - - - -<13>Oct 11 22:14:15 host hello\n<13>last";
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap().unwrap(),
+            b"<13>1 - - - - - -"
+        );
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap().unwrap(),
+            b"<13>Oct 11 22:14:15 host hello\n"
+        );
+        assert_eq!(read_frame(&mut reader).await.unwrap().unwrap(), b"<13>last");
+        assert!(read_frame(&mut reader).await.unwrap().is_none());
+
+        let mut reader: &[u8] = b"99999999999 <13>1 - - - - - -";
+        read_frame(&mut reader).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_syslog_udp_receiver() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let queue_path = tempfile::tempdir()?;
+        let queue_writer = make_queue_writer_for_test(&universe, queue_path.path()).await?;
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        let listen_address = socket.local_addr()?;
+        let listener_handle = tokio::spawn(receive_datagrams(socket, queue_writer.clone()));
+
+        let client = UdpSocket::bind("127.0.0.1:0").await?;
+        client
+            .send_to(b"<34>Oct 11 22:14:15 mymachine su: failed", listen_address)
+            .await?;
+        client
+            .send_to(b"<13>1 - - - - - - hello", listen_address)
+            .await?;
+
+        let docs = fetch_docs_for_test(&queue_writer, 2).await?;
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["app_name"], "su");
+        assert_eq!(docs[1]["message"], "hello");
+        listener_handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_syslog_tcp_receiver() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let queue_path = tempfile::tempdir()?;
+        let queue_writer = make_queue_writer_for_test(&universe, queue_path.path()).await?;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let listen_address = listener.local_addr()?;
+        let listener_handle = tokio::spawn(accept_connections(listener, queue_writer.clone()));
+
+        let mut client = TcpStream::connect(listen_address).await?;
+        client
+            .write_all(b"23 <13>1 - - - - - - hello<13>1 - - - - - - world\n")
+            .await?;
+        client.shutdown().await?;
+
+        let docs = fetch_docs_for_test(&queue_writer, 2).await?;
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["message"], "hello");
+        assert_eq!(docs[1]["message"], "world");
+        listener_handle.abort();
+        Ok(())
+    }
+}
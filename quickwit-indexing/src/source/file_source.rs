@@ -17,22 +17,23 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::SeekFrom;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{fmt, io};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
 use quickwit_actors::{ActorExitStatus, Mailbox};
-use quickwit_config::FileSourceParams;
-use quickwit_metastore::checkpoint::{CheckpointDelta, PartitionId, Position};
+use quickwit_config::{Compression, FileSourceParams};
+use quickwit_doc_mapper::{InputFormat, TextRecordConverter};
+use quickwit_metastore::checkpoint::{CheckpointDelta, PartitionId, Position, SourceCheckpoint};
 use serde::Serialize;
-use tokio::fs::File;
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncSeekExt, BufReader};
 use tracing::info;
 
 use crate::actors::Indexer;
-use crate::models::RawDocBatch;
+use crate::models::{PartitionAssignment, RawDocBatch};
+use crate::source::text_file_reader::{new_record_converter, TextFilePosition, TextFileReader};
 use crate::source::{Source, SourceContext, TypedSourceFactory};
 
 /// Cut a new batch as soon as we have read BATCH_NUM_BYTES_THRESHOLD.
@@ -43,13 +44,52 @@ pub struct FileSourceCounters {
     pub previous_offset: u64,
     pub current_offset: u64,
     pub num_lines_processed: u64,
+    /// Number of files read entirely.
+    pub num_files_processed: u64,
+    /// Progress of each file of the source, keyed by path.
+    pub files: BTreeMap<String, FileCounters>,
 }
 
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct FileCounters {
+    /// Offset in the decompressed content of the file up to which it has been read.
+    pub offset: u64,
+    pub num_lines_processed: u64,
+    pub is_complete: bool,
+}
+
+/// The file being read, or stdin.
+struct CurrentFile {
+    /// Path of the file, `None` for stdin.
+    filepath_opt: Option<String>,
+    is_compressed: bool,
+    reader: TextFileReader,
+    converter: TextRecordConverter,
+}
+
+/// A `FileSource` reads the lines of a file, of the files of a directory or matching a glob
+/// pattern, or of stdin. Compressed files are decompressed on the fly.
+///
+/// Each file is a partition of the source: its position in the checkpoint is the offset in its
+/// decompressed content of the last line indexed (see [`TextFilePosition`]). Compressed files are
+/// assumed to be immutable: their position is suffixed with `:eof` once they have been read
+/// entirely, so that they are skipped rather than decompressed again upon restart.
+///
+/// Once the files listed have been read, the files are listed again and the files that appeared
+/// in the meantime are read in turn. The source exits once a listing yields no new file.
 pub struct FileSource {
     source_id: String,
-    params: FileSourceParams,
+    /// Path, directory or glob pattern designating the files to read, `None` for stdin.
+    filepath_opt: Option<PathBuf>,
+    compression: Compression,
+    input_format: InputFormat,
+    checkpoint: SourceCheckpoint,
     counters: FileSourceCounters,
-    reader: BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>,
+    /// Files listed but not read yet.
+    pending_filepaths: VecDeque<PathBuf>,
+    current_file_opt: Option<CurrentFile>,
+    /// Position of the current file up to which documents have been sent to the indexer.
+    previous_position: Position,
 }
 
 impl fmt::Debug for FileSource {
@@ -58,6 +98,91 @@ impl fmt::Debug for FileSource {
     }
 }
 
+impl FileSource {
+    /// Queues the files that have not been listed before.
+    fn queue_new_filepaths(&mut self, filepaths: Vec<PathBuf>) -> anyhow::Result<()> {
+        for filepath in filepaths {
+            let filepath_str = filepath.to_string_lossy().to_string();
+            if self.counters.files.contains_key(&filepath_str) {
+                continue;
+            }
+            let partition_id = PartitionId::from(filepath_str.as_str());
+            let file_counters =
+                if let Some(position) = self.checkpoint.position_for_partition(&partition_id) {
+                    let file_position = TextFilePosition::parse(position)?;
+                    FileCounters {
+                        offset: file_position.offset,
+                        num_lines_processed: 0,
+                        is_complete: file_position.is_complete,
+                    }
+                } else {
+                    FileCounters::default()
+                };
+            self.counters.files.insert(filepath_str, file_counters);
+            self.pending_filepaths.push_back(filepath);
+        }
+        Ok(())
+    }
+
+    /// Opens the next file that has not been read entirely, and seeks to its position in the
+    /// checkpoint. The files are listed again once the files listed before have been read.
+    async fn open_next_file(&mut self) -> anyhow::Result<Option<CurrentFile>> {
+        loop {
+            if self.pending_filepaths.is_empty() {
+                if let Some(filepath) = self.filepath_opt.clone() {
+                    self.queue_new_filepaths(find_filepaths(&filepath)?)?;
+                }
+            }
+            let filepath = if let Some(filepath) = self.pending_filepaths.pop_front() {
+                filepath
+            } else {
+                return Ok(None);
+            };
+            let filepath_str = filepath.to_string_lossy().to_string();
+            let partition_id = PartitionId::from(filepath_str.as_str());
+            let position = self
+                .checkpoint
+                .position_for_partition(&partition_id)
+                .cloned()
+                .unwrap_or(Position::Beginning);
+            let file_position = TextFilePosition::parse(&position)?;
+            if file_position.is_complete {
+                continue;
+            }
+            let compression = self.compression.for_path(&filepath);
+            let converter = new_record_converter(
+                &filepath,
+                compression,
+                self.input_format,
+                file_position.offset,
+            )
+            .await?;
+            let reader = if let Some(reader) =
+                TextFileReader::open(&filepath, compression, file_position).await?
+            {
+                reader
+            } else {
+                bail!(
+                    "Source file `{}` is shorter than its position `{}` in the checkpoint.",
+                    filepath.display(),
+                    position.as_str()
+                );
+            };
+            info!(filepath = %filepath.display(), offset = file_position.offset, "Reading file.");
+            self.counters.previous_offset = file_position.offset;
+            self.counters.current_offset = file_position.offset;
+            self.previous_position = reader.position().to_position();
+            let current_file = CurrentFile {
+                filepath_opt: Some(filepath_str),
+                is_compressed: compression != Compression::None,
+                reader,
+                converter,
+            };
+            return Ok(Some(current_file));
+        }
+    }
+}
+
 #[async_trait]
 impl Source for FileSource {
     async fn emit_batches(
@@ -65,53 +190,98 @@ impl Source for FileSource {
         batch_sink: &Mailbox<Indexer>,
         ctx: &SourceContext,
     ) -> Result<Duration, ActorExitStatus> {
+        if self.current_file_opt.is_none() {
+            self.current_file_opt = self.open_next_file().await?;
+        }
+        let current_file = if let Some(current_file) = self.current_file_opt.as_mut() {
+            current_file
+        } else {
+            info!("EOF");
+            ctx.send_exit_with_success(batch_sink).await?;
+            return Err(ActorExitStatus::Success);
+        };
         // We collect batches of documents before sending them to the indexer.
         let limit_num_bytes = self.counters.previous_offset + BATCH_NUM_BYTES_THRESHOLD;
         let mut reached_eof = false;
+        let mut num_lines = 0;
         let mut docs = Vec::new();
-        while self.counters.current_offset < limit_num_bytes {
+        let mut doc_positions = Vec::new();
+        // A batch is not cut in the middle of a record spanning several lines, so that the
+        // checkpoint never points inside a record.
+        while self.counters.current_offset < limit_num_bytes
+            || current_file.converter.is_within_record()
+        {
             let mut doc_line = String::new();
-            let num_bytes = self
+            let num_bytes = current_file
                 .reader
                 .read_line(&mut doc_line)
                 .await
                 .map_err(|io_err: io::Error| anyhow::anyhow!(io_err))?;
             if num_bytes == 0 {
                 reached_eof = true;
+                if let Some(doc) = current_file.converter.finish() {
+                    docs.push(doc);
+                    if let Some(filepath) = &current_file.filepath_opt {
+                        doc_positions.push((
+                            PartitionId::from(filepath.as_str()),
+                            current_file.reader.position().to_position(),
+                        ));
+                    }
+                }
                 break;
             }
-            docs.push(doc_line);
             self.counters.current_offset += num_bytes as u64;
+            if let Some(doc) = current_file.converter.convert_line(doc_line) {
+                docs.push(doc);
+                if let Some(filepath) = &current_file.filepath_opt {
+                    doc_positions.push((
+                        PartitionId::from(filepath.as_str()),
+                        current_file.reader.position().to_position(),
+                    ));
+                }
+            }
             self.counters.num_lines_processed += 1;
+            num_lines += 1;
         }
-        if !docs.is_empty() {
-            let mut checkpoint_delta = CheckpointDelta::default();
-            if let Some(filepath) = &self.params.filepath {
-                let filepath_str = filepath
-                    .to_str()
-                    .context("Path is invalid utf-8")?
-                    .to_string();
-                let partition_id = PartitionId::from(filepath_str);
+        let mut checkpoint_delta = CheckpointDelta::default();
+        let mut to_position_opt = None;
+        if let Some(filepath) = &current_file.filepath_opt {
+            let mut file_position = current_file.reader.position();
+            file_position.is_complete = reached_eof && current_file.is_compressed;
+            let to_position = file_position.to_position();
+            if self.counters.current_offset > self.counters.previous_offset
+                || file_position.is_complete
+            {
                 checkpoint_delta
                     .record_partition_delta(
-                        partition_id,
-                        Position::from(self.counters.previous_offset),
-                        Position::from(self.counters.current_offset),
+                        PartitionId::from(filepath.as_str()),
+                        self.previous_position.clone(),
+                        to_position.clone(),
                     )
                     .unwrap();
             }
+            to_position_opt = Some(to_position);
+            let file_counters = self.counters.files.entry(filepath.clone()).or_default();
+            file_counters.offset = self.counters.current_offset;
+            file_counters.num_lines_processed += num_lines;
+            file_counters.is_complete = reached_eof;
+        }
+        if reached_eof {
+            self.current_file_opt = None;
+            self.counters.num_files_processed += 1;
+        }
+        if !docs.is_empty() || !checkpoint_delta.is_empty() {
             let raw_doc_batch = RawDocBatch {
                 docs,
+                doc_positions,
                 checkpoint_delta,
             };
             self.counters.previous_offset = self.counters.current_offset;
+            if let Some(to_position) = to_position_opt {
+                self.previous_position = to_position;
+            }
             ctx.send_message(batch_sink, raw_doc_batch).await?;
         }
-        if reached_eof {
-            info!("EOF");
-            ctx.send_exit_with_success(batch_sink).await?;
-            return Err(ActorExitStatus::Success);
-        }
         Ok(Duration::default())
     }
 
@@ -131,44 +301,91 @@ impl TypedSourceFactory for FileSourceFactory {
     type Source = FileSource;
     type Params = FileSourceParams;
 
-    // TODO handle checkpoint for files.
     async fn typed_create_source(
         source_id: String,
         params: FileSourceParams,
-        checkpoint: quickwit_metastore::checkpoint::SourceCheckpoint,
+        checkpoint: SourceCheckpoint,
+        _partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<FileSource> {
-        let mut offset = 0;
-        let reader: Box<dyn AsyncRead + Send + Sync + Unpin> =
-            if let Some(filepath) = &params.filepath {
-                let mut file = File::open(&filepath).await.with_context(|| {
-                    format!("Failed to open source file `{}`.", filepath.display())
-                })?;
-                let partition_id = PartitionId::from(filepath.to_string_lossy().to_string());
-                if let Some(Position::Offset(offset_str)) =
-                    checkpoint.position_for_partition(&partition_id).cloned()
-                {
-                    offset = offset_str.parse::<u64>()?;
-                    file.seek(SeekFrom::Start(offset)).await?;
-                }
-                Box::new(file)
-            } else {
-                // We cannot use the checkpoint.
-                Box::new(tokio::io::stdin())
-            };
-        let file_source = FileSource {
+        let mut current_file_opt = None;
+        if params.filepath.is_none() {
+            // We cannot use the checkpoint.
+            let compression = params.compression.for_path(Path::new(""));
+            current_file_opt = Some(CurrentFile {
+                filepath_opt: None,
+                is_compressed: compression != Compression::None,
+                reader: TextFileReader::from_reader(compression, Box::new(tokio::io::stdin())),
+                converter: TextRecordConverter::new(params.input_format),
+            });
+        }
+        let mut file_source = FileSource {
             source_id,
-            counters: FileSourceCounters {
-                previous_offset: offset,
-                current_offset: offset,
-                num_lines_processed: 0,
-            },
-            reader: BufReader::new(reader),
-            params,
+            filepath_opt: params.filepath.clone(),
+            compression: params.compression,
+            input_format: params.input_format,
+            checkpoint,
+            counters: FileSourceCounters::default(),
+            pending_filepaths: VecDeque::new(),
+            current_file_opt,
+            previous_position: Position::Beginning,
         };
+        if let Some(filepath) = &params.filepath {
+            file_source.queue_new_filepaths(list_filepaths(filepath)?)?;
+        }
         Ok(file_source)
     }
 }
 
+/// Lists the files designated by `filepath`, which is either the path of a file, the path of a
+/// directory whose files are listed recursively, or a glob pattern. The files are sorted by path.
+/// Fails if no file matches.
+pub(crate) fn list_filepaths(filepath: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let filepaths = find_filepaths(filepath)?;
+    if filepaths.is_empty() {
+        bail!("No file matches `{}`.", filepath.display());
+    }
+    Ok(filepaths)
+}
+
+/// Same as [`list_filepaths`], but returns an empty list if no file matches.
+fn find_filepaths(filepath: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let filepath_str = filepath.to_string_lossy();
+    let pattern = if filepath_str.contains(&['*', '?', '['][..]) {
+        filepath_str.to_string()
+    } else if filepath.is_dir() {
+        filepath.join("**").join("*").to_string_lossy().to_string()
+    } else if filepath.is_file() {
+        return Ok(vec![filepath.to_path_buf()]);
+    } else {
+        return Ok(Vec::new());
+    };
+    let mut filepaths = Vec::new();
+    for glob_entry in glob::glob(&pattern)
+        .with_context(|| format!("Failed to parse glob pattern `{}`.", pattern))?
+    {
+        let filepath = glob_entry?;
+        if filepath.is_file() {
+            filepaths.push(filepath);
+        }
+    }
+    filepaths.sort();
+    Ok(filepaths)
+}
+
+/// Returns whether the files read by the source exist on the current node. A source reading stdin
+/// is always local. The files are listed on the blocking thread pool, so that walking a large
+/// directory or glob pattern does not stall the calling actor.
+pub(crate) async fn has_local_files(params: &FileSourceParams) -> bool {
+    let filepath = if let Some(filepath) = &params.filepath {
+        filepath.clone()
+    } else {
+        return true;
+    };
+    tokio::task::spawn_blocking(move || list_filepaths(&filepath).is_ok())
+        .await
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -189,6 +406,7 @@ mod tests {
             "my-file-source".to_string(),
             params,
             SourceCheckpoint::default(),
+            PartitionAssignment::default(),
         )
         .await?;
         let file_source_actor = SourceActor {
@@ -204,7 +422,15 @@ mod tests {
             serde_json::json!({
                 "previous_offset": 1030u64,
                 "current_offset": 1030u64,
-                "num_lines_processed": 4u32
+                "num_lines_processed": 4u32,
+                "num_files_processed": 1u32,
+                "files": {
+                    "data/test_corpus.json": {
+                        "offset": 1030u64,
+                        "num_lines_processed": 4u32,
+                        "is_complete": true,
+                    }
+                }
             })
         );
         let batch = inbox.drain_for_test();
@@ -240,6 +466,7 @@ mod tests {
             "my-file-source".to_string(),
             params,
             SourceCheckpoint::default(),
+            PartitionAssignment::default(),
         )
         .await?;
         let file_source_actor = SourceActor {
@@ -255,7 +482,15 @@ mod tests {
             serde_json::json!({
                 "previous_offset": 700_000u64,
                 "current_offset": 700_000u64,
-                "num_lines_processed": 20_000u64
+                "num_lines_processed": 20_000u64,
+                "num_files_processed": 1u64,
+                "files": {
+                    filepath.clone(): {
+                        "offset": 700_000u64,
+                        "num_lines_processed": 20_000u64,
+                        "is_complete": true,
+                    }
+                }
             })
         );
         let indexer_msgs = inbox.drain_for_test();
@@ -314,6 +549,7 @@ mod tests {
             "my-file-source".to_string(),
             params,
             checkpoint,
+            PartitionAssignment::default(),
         )
         .await?;
         let file_source_actor = SourceActor {
@@ -329,7 +565,15 @@ mod tests {
             serde_json::json!({
                 "previous_offset": 290u64,
                 "current_offset": 290u64,
-                "num_lines_processed": 98u64
+                "num_lines_processed": 98u64,
+                "num_files_processed": 1u64,
+                "files": {
+                    temp_file_path.to_string_lossy(): {
+                        "offset": 290u64,
+                        "num_lines_processed": 98u64,
+                        "is_complete": true,
+                    }
+                }
             })
         );
         let indexer_msgs = inbox.drain_for_test();
@@ -337,4 +581,196 @@ mod tests {
         assert!(received_batch.docs[0].starts_with("2\n"));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_file_source_csv_resume_from_checkpoint() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        temp_file.write_all(b"ts,body\n1,foo\n2,\"bar, baz\"\n3,\n4,\"multi\nline\"\n")?;
+        temp_file.flush()?;
+        let temp_file_path = temp_file.path().canonicalize()?;
+        let mut params = FileSourceParams::file(&temp_file_path);
+        params.input_format = InputFormat::Csv;
+        let mut checkpoint = SourceCheckpoint::default();
+        let partition_id = PartitionId::from(temp_file_path.to_string_lossy().to_string());
+        checkpoint.try_apply_delta(CheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::Beginning,
+            Position::from(14u64),
+        ))?;
+        let source = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            params,
+            checkpoint,
+            PartitionAssignment::default(),
+        )
+        .await?;
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_actor(file_source_actor).spawn();
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(counters["num_lines_processed"], 4);
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        let docs: Vec<serde_json::Value> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter())
+            .map(|doc| serde_json::from_str(doc).unwrap())
+            .collect();
+        assert_eq!(
+            docs,
+            vec![
+                serde_json::json!({"ts": "2", "body": "bar, baz"}),
+                serde_json::json!({"ts": "3"}),
+                serde_json::json!({"ts": "4", "body": "multi\nline"}),
+            ]
+        );
+        Ok(())
+    }
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_file_source_multiple_compressed_files() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path().canonicalize()?;
+        std::fs::create_dir(dir_path.join("2022"))?;
+        std::fs::write(dir_path.join("2022/01.json"), "{\"body\": \"a\"}\n")?;
+        std::fs::write(
+            dir_path.join("2022/02.json.gz"),
+            gzip(b"{\"body\": \"b\"}\n{\"body\": \"c\"}\n"),
+        )?;
+        std::fs::write(
+            dir_path.join("2022/03.json.zst"),
+            zstd::encode_all(&b"{\"body\": \"d\"}\n"[..], 0)?,
+        )?;
+        std::fs::write(dir_path.join("2022/README.md"), "Not JSON.\n")?;
+
+        let glob_pattern = dir_path.join("2022/*.json*");
+        let mut checkpoint = SourceCheckpoint::default();
+        for (filepath, position) in [
+            ("2022/02.json.gz", Position::from(14u64)),
+            (
+                "2022/03.json.zst",
+                TextFilePosition {
+                    offset: 14,
+                    is_complete: true,
+                    ..Default::default()
+                }
+                .to_position(),
+            ),
+        ] {
+            let partition_id =
+                PartitionId::from(dir_path.join(filepath).to_string_lossy().to_string());
+            checkpoint.try_apply_delta(CheckpointDelta::from_partition_delta(
+                partition_id,
+                Position::Beginning,
+                position,
+            ))?;
+        }
+        let source = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            FileSourceParams::file(&glob_pattern),
+            checkpoint,
+            PartitionAssignment::default(),
+        )
+        .await?;
+        let (mailbox, inbox) = create_test_mailbox();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_actor(file_source_actor).spawn();
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(counters["num_files_processed"], 2);
+        assert_eq!(counters["num_lines_processed"], 4);
+        assert_eq!(counters["files"].as_object().unwrap().len(), 3);
+        let filepath_02 = dir_path
+            .join("2022/02.json.gz")
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(
+            counters["files"][&filepath_02],
+            serde_json::json!({
+                "offset": 28u64,
+                "num_lines_processed": 1u64,
+                "is_complete": true,
+            })
+        );
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        let docs: Vec<&str> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter().map(|doc| doc.trim_end()))
+            .collect();
+        assert_eq!(docs, vec![r#"{"body": "a"}"#, r#"{"body": "c"}"#]);
+        assert_eq!(
+            format!("{:?}", batches[1].checkpoint_delta),
+            format!(
+                "∆({}:(00000000000000000014..00000000000000000028:eof])",
+                filepath_02
+            )
+        );
+
+        // Directories are read recursively.
+        assert_eq!(
+            list_filepaths(&dir_path)?,
+            vec![
+                dir_path.join("2022/01.json"),
+                dir_path.join("2022/02.json.gz"),
+                dir_path.join("2022/03.json.zst"),
+                dir_path.join("2022/README.md"),
+            ]
+        );
+        assert!(list_filepaths(&dir_path.join("2023/*.json")).is_err());
+        assert!(list_filepaths(&dir_path.join("2023")).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_source_lists_new_files() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path().canonicalize()?;
+        std::fs::write(dir_path.join("01.json"), "{\"body\": \"a\"}\n")?;
+        let source = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            FileSourceParams::file(&dir_path),
+            SourceCheckpoint::default(),
+            PartitionAssignment::default(),
+        )
+        .await?;
+        // The file appears after the files have been listed.
+        std::fs::write(dir_path.join("02.json"), "{\"body\": \"b\"}\n")?;
+        let (mailbox, inbox) = create_test_mailbox();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_actor(file_source_actor).spawn();
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(counters["num_files_processed"], 2);
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        let docs: Vec<&str> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter().map(|doc| doc.trim_end()))
+            .collect();
+        assert_eq!(docs, vec![r#"{"body": "a"}"#, r#"{"body": "b"}"#]);
+        Ok(())
+    }
 }
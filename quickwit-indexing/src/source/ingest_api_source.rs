@@ -30,8 +30,9 @@ use serde::Serialize;
 
 use super::file_source::BATCH_NUM_BYTES_THRESHOLD;
 use super::{Source, SourceActor, SourceContext, TypedSourceFactory};
+use crate::actors::indexer::ForceCommit;
 use crate::actors::Indexer;
-use crate::models::RawDocBatch;
+use crate::models::{PartitionAssignment, RawDocBatch};
 
 /// Wait time for SourceActor before pooling for new documents.
 /// TODO: Think of better way, maybe increment this (i.e wait longer) as time
@@ -118,6 +119,7 @@ impl Source for IngestApiSource {
         let FetchResponse {
             first_position: first_position_opt,
             doc_batch: doc_batch_opt,
+            force_commit,
         } = self
             .ingest_api_mailbox
             .ask_for_res(fetch_req)
@@ -140,6 +142,9 @@ impl Source for IngestApiSource {
         let current_offset = first_position + docs.len() as u64 - 1;
         let mut checkpoint_delta = CheckpointDelta::default();
         let partition_id = PartitionId::from(self.params.index_id.as_str());
+        let doc_positions = (first_position..=current_offset)
+            .map(|offset| (partition_id.clone(), Position::from(offset)))
+            .collect();
         checkpoint_delta
             .record_partition_delta(
                 partition_id,
@@ -149,11 +154,17 @@ impl Source for IngestApiSource {
             .unwrap();
         let raw_doc_batch = RawDocBatch {
             docs,
+            doc_positions,
             checkpoint_delta,
         };
 
         self.update_counters(current_offset, raw_doc_batch.docs.len() as u64);
         ctx.send_message(batch_sink, raw_doc_batch).await?;
+        // An ingest request with the `force` commit type is waiting for this batch: the indexer
+        // commits the split right after indexing it.
+        if force_commit {
+            ctx.send_message(batch_sink, ForceCommit).await?;
+        }
         Ok(Duration::default())
     }
 
@@ -199,6 +210,7 @@ impl TypedSourceFactory for IngestApiSourceFactory {
         source_id: String,
         params: IngestApiSourceParams,
         checkpoint: SourceCheckpoint,
+        _partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Self::Source> {
         let ingest_api_mailbox = get_ingest_api_service().ok_or_else(|| {
             anyhow::anyhow!(
@@ -217,7 +229,7 @@ mod tests {
     use quickwit_actors::{create_test_mailbox, Universe};
     use quickwit_ingest_api::{add_doc, spawn_ingest_api_actor, Queues};
     use quickwit_metastore::checkpoint::SourceCheckpoint;
-    use quickwit_proto::ingest_api::{DocBatch, IngestRequest};
+    use quickwit_proto::ingest_api::{CommitType, DocBatch, IngestRequest};
 
     use super::*;
     use crate::source::SourceActor;
@@ -236,7 +248,10 @@ mod tests {
             }
             doc_batches.push(doc_batch);
         }
-        IngestRequest { doc_batches }
+        IngestRequest {
+            doc_batches,
+            ..Default::default()
+        }
     }
 
     #[tokio::test]
@@ -251,7 +266,7 @@ mod tests {
         queues.create_queue(&index_id)?;
         drop(queues);
 
-        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path())?;
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path(), false)?;
 
         let ingest_req = make_ingest_request(index_id.clone(), 2, 1000);
 
@@ -299,13 +314,61 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_source_force_commit() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let index_id = "my-index".to_string();
+        let queue_path = tempfile::tempdir()?;
+
+        let mut queues = Queues::open(queue_path.path())?;
+        queues.create_queue(&index_id)?;
+        drop(queues);
+
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path(), false)?;
+        let mut ingest_req = make_ingest_request(index_id.clone(), 1, 10);
+        ingest_req.set_commit(CommitType::Force);
+        ingest_api_mailbox
+            .ask_for_res(ingest_req)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+
+        let (mailbox, inbox) = create_test_mailbox();
+        let params = IngestApiSourceParams {
+            index_id,
+            batch_num_bytes_threshold: None,
+        };
+        let ingest_api_source = IngestApiSource::make(
+            "my-source".to_string(),
+            params,
+            ingest_api_mailbox,
+            SourceCheckpoint::default(),
+        )
+        .await?;
+        let ingest_api_source_actor = SourceActor {
+            source: Box::new(ingest_api_source),
+            batch_sink: mailbox,
+        };
+        let (_ingest_api_source_mailbox, ingest_api_source_handle) =
+            universe.spawn_actor(ingest_api_source_actor).spawn();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        ingest_api_source_handle.process_pending_and_observe().await;
+
+        let indexer_msgs = inbox.drain_for_test();
+        assert_eq!(indexer_msgs.len(), 2);
+        let received_batch = indexer_msgs[0].downcast_ref::<RawDocBatch>().unwrap();
+        assert_eq!(received_batch.docs.len(), 10);
+        assert!(indexer_msgs[1].downcast_ref::<ForceCommit>().is_some());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_ingest_api_source_without_existing_queue() -> anyhow::Result<()> {
         quickwit_common::setup_logging_for_tests();
         let universe = Universe::new();
         let index_id = "my-index".to_string();
         let queue_path = tempfile::tempdir()?;
-        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path())?;
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path(), false)?;
 
         let ingest_req = make_ingest_request(index_id.clone(), 2, 1000);
         assert!(ingest_api_mailbox.ask_for_res(ingest_req).await.is_err());
@@ -345,7 +408,7 @@ mod tests {
         queues.create_queue(&index_id)?;
         drop(queues);
 
-        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path())?;
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path(), false)?;
 
         let ingest_req = make_ingest_request(index_id.clone(), 4, 1000);
         ingest_api_mailbox
@@ -413,7 +476,7 @@ mod tests {
         queues.create_queue(&index_id)?;
         drop(queues);
 
-        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path())?;
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, queue_path.path(), false)?;
 
         let ingest_req = make_ingest_request(index_id.clone(), 1, 1);
 
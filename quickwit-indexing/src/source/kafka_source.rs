@@ -45,8 +45,9 @@ use tokio::task::spawn_blocking;
 use tracing::{debug, info, warn};
 
 use crate::actors::Indexer;
-use crate::models::RawDocBatch;
-use crate::source::{Source, SourceContext, TypedSourceFactory};
+use crate::models::{PartitionAssignment, RawDocBatch};
+use crate::source::schema_registry::{DecodeError, MessageDecoder};
+use crate::source::{CheckpointResetTarget, Source, SourceContext, TypedSourceFactory};
 
 /// We try to emit chewable batches for the indexer.
 /// One batch = one message to the indexer actor.
@@ -59,6 +60,12 @@ use crate::source::{Source, SourceContext, TypedSourceFactory};
 /// 5MB seems like a good one size fits all value.
 const TARGET_BATCH_NUM_BYTES: u64 = 5_000_000;
 
+/// Number of attempts at fetching the schema of a message before failing the source.
+const SCHEMA_REGISTRY_MAX_NUM_ATTEMPTS: usize = 3;
+
+/// Delay before the first retry. It grows linearly with the number of attempts.
+const SCHEMA_REGISTRY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 /// Factory for instantiating a `KafkaSource`.
 pub struct KafkaSourceFactory;
 
@@ -71,8 +78,9 @@ impl TypedSourceFactory for KafkaSourceFactory {
         source_id: String,
         params: KafkaSourceParams,
         checkpoint: SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Self::Source> {
-        KafkaSource::try_new(source_id, params, checkpoint).await
+        KafkaSource::try_new(source_id, params, checkpoint, partition_assignment).await
     }
 }
 
@@ -116,7 +124,11 @@ pub struct KafkaSourceState {
 pub struct KafkaSource {
     source_id: String,
     topic: String,
+    /// Partitions of the topic when the source was created.
+    topic_partition_ids: Vec<i32>,
+    partition_assignment: PartitionAssignment,
     consumer: Arc<RdKafkaConsumer>,
+    message_decoder: MessageDecoder,
     state: KafkaSourceState,
 }
 
@@ -131,15 +143,23 @@ impl fmt::Debug for KafkaSource {
 }
 
 impl KafkaSource {
-    /// Instantiates a new `KafkaSource`.
+    /// Instantiates a new `KafkaSource` consuming the partitions of the topic assigned to the
+    /// pipeline.
     pub async fn try_new(
         source_id: String,
         params: KafkaSourceParams,
         checkpoint: SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Self> {
         let topic = params.topic;
+        let message_decoder = MessageDecoder::new(&params.message_format)?;
         let consumer = create_consumer(&source_id, params.client_log_level, params.client_params)?;
-        let partition_ids = fetch_partition_ids(consumer.clone(), &topic).await?;
+        let topic_partition_ids = fetch_partition_ids(consumer.clone(), &topic).await?;
+        let partition_ids: Vec<i32> =
+            assigned_partition_ids(&topic_partition_ids, &partition_assignment);
+        if partition_ids.is_empty() {
+            info!(topic = %topic, "No partitions of the topic are assigned to this indexer.");
+        }
         let assigned_partition_ids = partition_ids
             .iter()
             .map(|&partition_id| (partition_id, PartitionId::from(partition_id as i64)))
@@ -168,7 +188,10 @@ impl KafkaSource {
         Ok(KafkaSource {
             source_id,
             topic,
+            topic_partition_ids,
+            partition_assignment,
             consumer,
+            message_decoder,
             state,
         })
     }
@@ -181,7 +204,24 @@ impl Source for KafkaSource {
         batch_sink: &Mailbox<Indexer>,
         ctx: &SourceContext,
     ) -> Result<Duration, ActorExitStatus> {
+        // The indexer nodes sharing the partitions may have changed since the source was created.
+        // Failing restarts the pipeline, which then consumes the partitions of the new assignment
+        // from the checkpoint.
+        let partition_ids =
+            assigned_partition_ids(&self.topic_partition_ids, &self.partition_assignment);
+        if partition_ids.len() != self.state.assigned_partition_ids.len()
+            || partition_ids
+                .iter()
+                .any(|partition_id| !self.state.assigned_partition_ids.contains_key(partition_id))
+        {
+            return Err(ActorExitStatus::from(anyhow::anyhow!(
+                "The partitions of topic `{}` assigned to the source changed to `{{{}}}`.",
+                self.topic,
+                partition_ids.iter().join(", ")
+            )));
+        }
         let mut docs = Vec::new();
+        let mut doc_positions = Vec::new();
         let mut checkpoint_delta = CheckpointDelta::default();
 
         let deadline = tokio::time::sleep(quickwit_actors::HEARTBEAT / 2);
@@ -206,15 +246,6 @@ impl Source for KafkaSource {
                 // case.
                 Err(err) => return Err(ActorExitStatus::from(anyhow::anyhow!(err))),
             };
-            if let Some(doc) = parse_message_payload(&message) {
-                docs.push(doc);
-            } else {
-                self.state.num_invalid_messages += 1;
-            }
-            batch_num_bytes += message.payload_len() as u64;
-            self.state.num_bytes_processed += message.payload_len() as u64;
-            self.state.num_messages_processed += 1;
-
             let partition_id = self
                 .state
                 .assigned_partition_ids
@@ -229,6 +260,18 @@ impl Source for KafkaSource {
                 })?
                 .clone();
             let current_position = Position::from(message.offset());
+            if let Some(doc) =
+                parse_message_payload(&message, &mut self.message_decoder, ctx).await?
+            {
+                docs.push(doc);
+                doc_positions.push((partition_id.clone(), current_position.clone()));
+            } else {
+                self.state.num_invalid_messages += 1;
+            }
+            batch_num_bytes += message.payload_len() as u64;
+            self.state.num_bytes_processed += message.payload_len() as u64;
+            self.state.num_messages_processed += 1;
+
             let previous_position = self
                 .state
                 .current_positions
@@ -246,11 +289,13 @@ impl Source for KafkaSource {
         if !checkpoint_delta.is_empty() {
             let batch = RawDocBatch {
                 docs,
+                doc_positions,
                 checkpoint_delta,
             };
             ctx.send_message(batch_sink, batch).await?;
         }
-        if self.state.num_active_partitions == 0 {
+        // A source with no partitions assigned keeps running: it may be assigned some later.
+        if self.state.num_active_partitions == 0 && !self.state.assigned_partition_ids.is_empty() {
             info!(topic = %self.topic, "Reached end of topic.");
             ctx.send_exit_with_success(batch_sink).await?;
             return Err(ActorExitStatus::Success);
@@ -290,6 +335,20 @@ impl Source for KafkaSource {
     }
 }
 
+/// Returns the partitions of the topic assigned to the source.
+fn assigned_partition_ids(
+    topic_partition_ids: &[i32],
+    partition_assignment: &PartitionAssignment,
+) -> Vec<i32> {
+    topic_partition_ids
+        .iter()
+        .copied()
+        .filter(|&partition_id| {
+            partition_assignment.is_assigned(&PartitionId::from(partition_id as i64))
+        })
+        .collect()
+}
+
 /// Returns the preceding `Position` for the offset.
 fn previous_position_for_offset(offset: i64) -> Position {
     if offset == 0 {
@@ -307,6 +366,150 @@ pub(super) async fn check_connectivity(params: KafkaSourceParams) -> anyhow::Res
     Ok(())
 }
 
+/// Computes the positions to reset the given partitions of a topic to. All the partitions of the
+/// topic are reset if `partition_ids` is empty.
+pub(super) async fn compute_reset_positions(
+    params: KafkaSourceParams,
+    target: CheckpointResetTarget,
+    partition_ids: &[PartitionId],
+) -> anyhow::Result<Vec<(PartitionId, Position)>> {
+    let source_id = "quickwit-checkpoint-reset";
+    let consumer = create_consumer(source_id, params.client_log_level, params.client_params)?;
+    let topic_partition_ids = fetch_partition_ids(consumer.clone(), &params.topic).await?;
+    let kafka_partition_ids = if partition_ids.is_empty() {
+        topic_partition_ids
+    } else {
+        let mut kafka_partition_ids = Vec::with_capacity(partition_ids.len());
+        for partition_id in partition_ids {
+            let partition_i32 = partition_id.0.parse::<i32>().with_context(|| {
+                format!("Failed to parse partition ID `{}` to i32.", partition_id.0)
+            })?;
+            if !topic_partition_ids.contains(&partition_i32) {
+                bail!(
+                    "Partition `{}` does not exist in topic `{}`.",
+                    partition_i32,
+                    params.topic
+                );
+            }
+            kafka_partition_ids.push(partition_i32);
+        }
+        kafka_partition_ids
+    };
+    let timeout = Duration::from_secs(30);
+    let watermarks = fetch_watermarks(
+        consumer.clone(),
+        &params.topic,
+        &kafka_partition_ids,
+        timeout,
+    )
+    .await?;
+    let timestamp_offsets = if let CheckpointResetTarget::Timestamp(timestamp_millis) = target {
+        fetch_offsets_for_timestamp(
+            consumer,
+            &params.topic,
+            &kafka_partition_ids,
+            timestamp_millis,
+            timeout,
+        )
+        .await?
+    } else {
+        HashMap::new()
+    };
+    let mut positions = Vec::with_capacity(kafka_partition_ids.len());
+    for partition_id in kafka_partition_ids {
+        let partition_watermarks = watermarks.get(&partition_id).cloned().with_context(|| {
+            format!(
+                "Failed to fetch watermarks for topic `{}` and partition `{}`.",
+                params.topic, partition_id
+            )
+        })?;
+        let timestamp_offset = timestamp_offsets.get(&partition_id).cloned();
+        let position = match compute_reset_offset(
+            target,
+            partition_id,
+            partition_watermarks,
+            timestamp_offset,
+        )? {
+            Some(next_offset) => previous_position_for_offset(next_offset),
+            None => Position::Beginning,
+        };
+        positions.push((PartitionId::from(partition_id as i64), position));
+    }
+    Ok(positions)
+}
+
+/// Computes the offset of the next message to read in a partition after a reset. Returns `None`
+/// if the partition must be read from the beginning.
+fn compute_reset_offset(
+    target: CheckpointResetTarget,
+    partition_id: i32,
+    watermarks: (i64, i64),
+    timestamp_offset: Option<Offset>,
+) -> anyhow::Result<Option<i64>> {
+    let (low_watermark, high_watermark) = watermarks;
+    let next_offset = match target {
+        CheckpointResetTarget::Beginning => return Ok(None),
+        CheckpointResetTarget::End => high_watermark,
+        CheckpointResetTarget::Offset(offset) => {
+            if offset < low_watermark || offset > high_watermark {
+                bail!(
+                    "Offset `{}` is out of the range `[{}, {}]` of partition `{}`.",
+                    offset,
+                    low_watermark,
+                    high_watermark,
+                    partition_id
+                );
+            }
+            offset
+        }
+        CheckpointResetTarget::Timestamp(timestamp_millis) => match timestamp_offset {
+            Some(Offset::Offset(offset)) => offset,
+            // No message was written to the partition at or after the timestamp.
+            Some(Offset::End) | None => high_watermark,
+            Some(offset) => bail!(
+                "Failed to find the offset of timestamp `{}` in partition `{}`: got `{:?}`.",
+                timestamp_millis,
+                partition_id,
+                offset
+            ),
+        },
+    };
+    Ok(Some(next_offset))
+}
+
+/// Fetches, for the given topic and partition IDs, the offset of the earliest message whose
+/// timestamp is greater than or equal to the given timestamp in milliseconds.
+async fn fetch_offsets_for_timestamp(
+    consumer: Arc<RdKafkaConsumer>,
+    topic: &str,
+    partition_ids: &[i32],
+    timestamp_millis: i64,
+    timeout: Duration,
+) -> anyhow::Result<HashMap<i32, Offset>> {
+    let mut timestamps = TopicPartitionList::with_capacity(partition_ids.len());
+    for &partition_id in partition_ids {
+        timestamps.add_partition_offset(topic, partition_id, Offset::Offset(timestamp_millis))?;
+    }
+    let topic_clone = topic.to_string();
+    let offsets = spawn_blocking(move || {
+        consumer
+            .offsets_for_times(timestamps, timeout)
+            .with_context(|| {
+                format!(
+                    "Failed to fetch offsets for timestamp `{}` in topic `{}`.",
+                    timestamp_millis, topic_clone
+                )
+            })
+    })
+    .await??;
+    let offsets = offsets
+        .elements()
+        .iter()
+        .map(|element| (element.partition(), element.offset()))
+        .collect();
+    Ok(offsets)
+}
+
 /// Creates a new `KafkaSourceConsumer`.
 fn create_consumer(
     source_id: &str,
@@ -547,46 +750,90 @@ fn compute_next_offset(
     );
 }
 
-/// Converts the raw bytes of the message payload to a `String` skipping corrupted or empty
+/// Decodes the raw bytes of the message payload into a JSON document, skipping corrupted or empty
 /// messages.
-fn parse_message_payload(message: &BorrowedMessage) -> Option<String> {
-    match message.payload_view::<str>() {
-        Some(Ok(payload)) if !payload.is_empty() => {
-            let doc = payload.to_string();
+///
+/// Fetching the schema of the message from the schema registry is retried a few times. If the
+/// registry remains unavailable, an error is returned and the pipeline restarts from its last
+/// published checkpoint, so the message is not lost.
+async fn parse_message_payload(
+    message: &BorrowedMessage<'_>,
+    message_decoder: &mut MessageDecoder,
+    ctx: &SourceContext,
+) -> anyhow::Result<Option<String>> {
+    let payload = match message.payload() {
+        Some(payload) if !payload.is_empty() => payload,
+        Some(_) => {
             debug!(
                 topic = ?message.topic(),
-                partition_id = ?message.partition(),
+                partition = ?message.partition(),
                 offset = ?message.offset(),
                 timestamp = ?message.timestamp(),
-                num_bytes = ?message.payload_len(),
-                "Message received.",
+                "Document is empty."
             );
-            return Some(doc);
+            return Ok(None);
+        }
+        None => {
+            debug!(
+                topic = ?message.topic(),
+                partition = ?message.partition(),
+                offset = ?message.offset(),
+                timestamp = ?message.timestamp(),
+                "Message payload is empty."
+            );
+            return Ok(None);
+        }
+    };
+    let mut num_attempts = 0;
+    loop {
+        num_attempts += 1;
+        // Fetching a schema may take a while: the actor should not be considered stuck.
+        match ctx.protect_future(message_decoder.decode(payload)).await {
+            Ok(doc) => {
+                debug!(
+                    topic = ?message.topic(),
+                    partition_id = ?message.partition(),
+                    offset = ?message.offset(),
+                    timestamp = ?message.timestamp(),
+                    num_bytes = ?message.payload_len(),
+                    "Message received.",
+                );
+                return Ok(Some(doc));
+            }
+            Err(DecodeError::InvalidPayload(error)) => {
+                warn!(
+                    topic = ?message.topic(),
+                    partition = ?message.partition(),
+                    offset = ?message.offset(),
+                    timestamp = ?message.timestamp(),
+                    error = ?error,
+                    "Failed to deserialize message payload."
+                );
+                return Ok(None);
+            }
+            Err(error @ DecodeError::SchemaRegistryUnavailable(_)) => {
+                if num_attempts >= SCHEMA_REGISTRY_MAX_NUM_ATTEMPTS {
+                    return Err(anyhow::Error::from(error).context(format!(
+                        "Failed to decode message at offset `{}` of partition `{}`.",
+                        message.offset(),
+                        message.partition()
+                    )));
+                }
+                warn!(
+                    topic = ?message.topic(),
+                    partition = ?message.partition(),
+                    offset = ?message.offset(),
+                    error = ?error,
+                    num_attempts = num_attempts,
+                    "Schema registry is unavailable, retrying."
+                );
+                ctx.protect_future(tokio::time::sleep(
+                    SCHEMA_REGISTRY_RETRY_DELAY * num_attempts as u32,
+                ))
+                .await;
+            }
         }
-        Some(Ok(_)) => debug!(
-            topic = ?message.topic(),
-            partition = ?message.partition(),
-            offset = ?message.offset(),
-            timestamp = ?message.timestamp(),
-            "Document is empty."
-        ),
-        Some(Err(error)) => warn!(
-            topic = ?message.topic(),
-            partition = ?message.partition(),
-            offset = ?message.offset(),
-            timestamp = ?message.timestamp(),
-            error = ?error,
-            "Failed to deserialize message payload."
-        ),
-        None => debug!(
-            topic = ?message.topic(),
-            partition = ?message.partition(),
-            offset = ?message.offset(),
-            timestamp = ?message.timestamp(),
-            "Message payload is empty."
-        ),
     }
-    None
 }
 
 #[cfg(test)]
@@ -674,13 +921,56 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_compute_reset_offset() {
+        let watermarks = (5, 10);
+        assert_eq!(
+            compute_reset_offset(CheckpointResetTarget::Beginning, 0, watermarks, None).unwrap(),
+            None
+        );
+        assert_eq!(
+            compute_reset_offset(CheckpointResetTarget::End, 0, watermarks, None).unwrap(),
+            Some(10)
+        );
+        assert_eq!(
+            compute_reset_offset(CheckpointResetTarget::Offset(7), 0, watermarks, None).unwrap(),
+            Some(7)
+        );
+        assert!(
+            compute_reset_offset(CheckpointResetTarget::Offset(4), 0, watermarks, None).is_err()
+        );
+        assert!(
+            compute_reset_offset(CheckpointResetTarget::Offset(11), 0, watermarks, None).is_err()
+        );
+        assert_eq!(
+            compute_reset_offset(
+                CheckpointResetTarget::Timestamp(1_000),
+                0,
+                watermarks,
+                Some(Offset::Offset(8))
+            )
+            .unwrap(),
+            Some(8)
+        );
+        assert_eq!(
+            compute_reset_offset(
+                CheckpointResetTarget::Timestamp(1_000),
+                0,
+                watermarks,
+                Some(Offset::End)
+            )
+            .unwrap(),
+            Some(10)
+        );
+    }
 }
 
 #[cfg(all(test, feature = "kafka-broker-tests"))]
 mod kafka_broker_tests {
     use quickwit_actors::{create_test_mailbox, Universe};
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{SourceConfig, SourceParams};
+    use quickwit_config::{KafkaMessageFormat, SourceConfig, SourceParams};
     use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
     use rdkafka::client::DefaultClientContext;
     use rdkafka::message::ToBytes;
@@ -823,6 +1113,7 @@ mod kafka_broker_tests {
                     "bootstrap.servers": bootstrap_servers,
                     "enable.partition.eof": true,
                 }),
+                message_format: KafkaMessageFormat::Json,
             }),
         };
 
@@ -831,7 +1122,11 @@ mod kafka_broker_tests {
             let (sink, inbox) = create_test_mailbox();
             let checkpoint = SourceCheckpoint::default();
             let source = source_loader
-                .load_source(source_config.clone(), checkpoint)
+                .load_source(
+                    source_config.clone(),
+                    checkpoint,
+                    PartitionAssignment::default(),
+                )
                 .await?;
             let actor = SourceActor {
                 source,
@@ -884,7 +1179,11 @@ mod kafka_broker_tests {
             let (sink, inbox) = create_test_mailbox();
             let checkpoint = SourceCheckpoint::default();
             let source = source_loader
-                .load_source(source_config.clone(), checkpoint)
+                .load_source(
+                    source_config.clone(),
+                    checkpoint,
+                    PartitionAssignment::default(),
+                )
                 .await?;
             let actor = SourceActor {
                 source,
@@ -943,7 +1242,11 @@ mod kafka_broker_tests {
                 })
                 .collect();
             let source = source_loader
-                .load_source(source_config.clone(), checkpoint)
+                .load_source(
+                    source_config.clone(),
+                    checkpoint,
+                    PartitionAssignment::default(),
+                )
                 .await?;
             let actor = SourceActor {
                 source,
@@ -37,7 +37,7 @@ use tracing::{info, warn};
 
 use super::api::list_shards;
 use super::shard_consumer::{ShardConsumer, ShardConsumerHandle, ShardConsumerMessage};
-use crate::models::RawDocBatch;
+use crate::models::{PartitionAssignment, RawDocBatch};
 use crate::source::kinesis::helpers::get_kinesis_client;
 use crate::source::{Indexer, Source, SourceContext, TypedSourceFactory};
 
@@ -57,6 +57,7 @@ impl TypedSourceFactory for KinesisSourceFactory {
         source_id: String,
         params: KinesisSourceParams,
         checkpoint: SourceCheckpoint,
+        _partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Self::Source> {
         KinesisSource::try_new(source_id, params, checkpoint).await
     }
@@ -200,6 +201,7 @@ impl Source for KinesisSource {
     ) -> Result<Duration, ActorExitStatus> {
         let mut batch_num_bytes = 0;
         let mut docs = Vec::new();
+        let mut doc_positions = Vec::new();
         let mut checkpoint_delta = CheckpointDelta::default();
 
         let deadline = time::sleep(quickwit_actors::HEARTBEAT / 2);
@@ -217,10 +219,23 @@ impl Source for KinesisSource {
                         }
                         ShardConsumerMessage::Records { shard_id, records, lag_millis } => {
                             let num_records = records.len();
+                            let shard_partition_id_opt = self
+                                .state
+                                .shard_consumers
+                                .get(&shard_id)
+                                .map(|shard_consumer_state| {
+                                    shard_consumer_state.partition_id.clone()
+                                });
 
                             for (i, record) in records.into_iter().enumerate() {
                                 match String::from_utf8(record.data.to_vec()) {
-                                    Ok(doc) if !doc.is_empty() => docs.push(doc),
+                                    Ok(doc) if !doc.is_empty() => {
+                                        docs.push(doc);
+                                        if let Some(partition_id) = &shard_partition_id_opt {
+                                            let position = Position::from(record.sequence_number.clone());
+                                            doc_positions.push((partition_id.clone(), position));
+                                        }
+                                    }
                                     Ok(_) => {
                                         warn!(
                                             stream_name = %self.stream_name,
@@ -301,6 +316,7 @@ impl Source for KinesisSource {
         if !checkpoint_delta.is_empty() {
             let batch = RawDocBatch {
                 docs,
+                doc_positions,
                 checkpoint_delta,
             };
             ctx.send_message(batch_sink, batch).await?;
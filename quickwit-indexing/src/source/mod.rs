@@ -57,32 +57,47 @@
 //!   that file.
 //! - the kafka source: the partition id is a kafka topic partition id, and the position is a kafka
 //!   offset.
+//! - the pulsar source: the partition id is the name of a pulsar topic partition, and the position
+//!   is a pulsar message id.
 mod file_source;
 mod ingest_api_source;
 #[cfg(feature = "kafka")]
 mod kafka_source;
 #[cfg(feature = "kinesis")]
 mod kinesis;
+mod object_storage_source;
+#[cfg(feature = "pulsar")]
+mod pulsar_source;
+#[cfg(feature = "kafka")]
+mod schema_registry;
 mod source_factory;
+mod text_file_reader;
 mod vec_source;
 mod void_source;
 
-use std::path::Path;
+use std::fmt;
+use std::str::FromStr;
 use std::time::Duration;
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
+pub(crate) use file_source::has_local_files;
 pub use file_source::{FileSource, FileSourceFactory};
 #[cfg(feature = "kafka")]
 pub use kafka_source::{KafkaSource, KafkaSourceFactory};
 #[cfg(feature = "kinesis")]
 pub use kinesis::kinesis_source::{KinesisSource, KinesisSourceFactory};
+pub use object_storage_source::{ObjectStorageSource, ObjectStorageSourceFactory};
 use once_cell::sync::OnceCell;
+#[cfg(feature = "pulsar")]
+pub use pulsar_source::{PulsarSource, PulsarSourceFactory};
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_config::{SourceConfig, SourceParams};
-use quickwit_metastore::checkpoint::SourceCheckpoint;
+use quickwit_metastore::checkpoint::{CheckpointReset, PartitionId, Position, SourceCheckpoint};
+use serde::{Deserialize, Serialize};
 pub use source_factory::{SourceFactory, SourceLoader, TypedSourceFactory};
+pub(crate) use text_file_reader::decompressing_reader;
 use tokio::runtime::Handle;
 use tracing::error;
 pub use vec_source::{VecSource, VecSourceFactory};
@@ -244,6 +259,9 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
         source_factory.add_source("kafka", KafkaSourceFactory);
         #[cfg(feature = "kinesis")]
         source_factory.add_source("kinesis", KinesisSourceFactory);
+        #[cfg(feature = "pulsar")]
+        source_factory.add_source("pulsar", PulsarSourceFactory);
+        source_factory.add_source("object-storage", ObjectStorageSourceFactory);
         source_factory.add_source("vec", VecSourceFactory);
         source_factory.add_source("void", VoidSourceFactory);
         source_factory.add_source("ingest-api", IngestApiSourceFactory);
@@ -255,9 +273,7 @@ pub async fn check_source_connectivity(source_config: &SourceConfig) -> anyhow::
     match &source_config.source_params {
         SourceParams::File(params) => {
             if let Some(filepath) = &params.filepath {
-                if !Path::new(filepath).exists() {
-                    bail!("File `{}` does not exist.", filepath.display())
-                }
+                file_source::list_filepaths(filepath)?;
             }
             Ok(())
         }
@@ -283,10 +299,120 @@ pub async fn check_source_connectivity(source_config: &SourceConfig) -> anyhow::
                 Ok(())
             }
         }
+        #[allow(unused_variables)]
+        SourceParams::Pulsar(params) => {
+            #[cfg(not(feature = "pulsar"))]
+            bail!("Quickwit binary was not compiled with the `pulsar` feature.");
+
+            #[cfg(feature = "pulsar")]
+            {
+                pulsar_source::check_connectivity(params.clone()).await?;
+                Ok(())
+            }
+        }
+        SourceParams::ObjectStorage(params) => {
+            object_storage_source::check_connectivity(params).await
+        }
         _ => Ok(()),
     }
 }
 
+/// Position to reset the checkpoint of a source to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointResetTarget {
+    /// Reprocesses the partitions from the beginning.
+    Beginning,
+    /// Skips all the messages written to the partitions so far.
+    End,
+    /// Resumes from the message with the given offset.
+    Offset(i64),
+    /// Resumes from the first message written at or after the given timestamp in milliseconds.
+    Timestamp(i64),
+}
+
+impl fmt::Display for CheckpointResetTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Beginning => write!(f, "beginning"),
+            Self::End => write!(f, "end"),
+            Self::Offset(offset) => write!(f, "offset:{}", offset),
+            Self::Timestamp(timestamp_millis) => write!(f, "timestamp:{}", timestamp_millis),
+        }
+    }
+}
+
+impl FromStr for CheckpointResetTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(target_str: &str) -> anyhow::Result<Self> {
+        let target = match target_str.split_once(':') {
+            None if target_str == "beginning" => Self::Beginning,
+            None if target_str == "end" => Self::End,
+            Some(("offset", offset_str)) => offset_str
+                .parse()
+                .map(Self::Offset)
+                .with_context(|| format!("Failed to parse offset `{}`.", offset_str))?,
+            Some(("timestamp", timestamp_str)) => timestamp_str
+                .parse()
+                .map(Self::Timestamp)
+                .with_context(|| format!("Failed to parse timestamp `{}`.", timestamp_str))?,
+            _ => bail!(
+                "Failed to parse checkpoint reset target `{}`. Expected `beginning`, `end`, \
+                 `offset:<offset>`, or `timestamp:<timestamp in milliseconds>`.",
+                target_str
+            ),
+        };
+        Ok(target)
+    }
+}
+
+/// Computes the reset moving the given partitions of a source checkpoint to `target`. All the
+/// partitions of the source are reset if `partition_ids` is empty.
+///
+/// Resetting to the beginning is supported by all sources, whereas resetting to the end, an offset,
+/// or a timestamp requires querying the source and is only supported by the Kafka source.
+pub async fn plan_checkpoint_reset(
+    source_config: &SourceConfig,
+    checkpoint: &SourceCheckpoint,
+    target: CheckpointResetTarget,
+    partition_ids: &[PartitionId],
+) -> anyhow::Result<CheckpointReset> {
+    let new_positions: Vec<(PartitionId, Position)> = match &source_config.source_params {
+        #[cfg(feature = "kafka")]
+        SourceParams::Kafka(params) => {
+            kafka_source::compute_reset_positions(params.clone(), target, partition_ids).await?
+        }
+        _ if target == CheckpointResetTarget::Beginning => {
+            if partition_ids.is_empty() {
+                checkpoint
+                    .iter()
+                    .map(|(partition_id, _)| (partition_id, Position::Beginning))
+                    .collect()
+            } else {
+                partition_ids
+                    .iter()
+                    .map(|partition_id| (partition_id.clone(), Position::Beginning))
+                    .collect()
+            }
+        }
+        _ => bail!(
+            "Resetting the checkpoint of a `{}` source to `{}` is not supported.",
+            source_config.source_type(),
+            target
+        ),
+    };
+    let mut checkpoint_reset = CheckpointReset::default();
+    for (partition_id, new_position) in new_positions {
+        let current_position = checkpoint
+            .position_for_partition(&partition_id)
+            .cloned()
+            .unwrap_or(Position::Beginning);
+        checkpoint_reset.record_partition_reset(partition_id, current_position, new_position);
+    }
+    Ok(checkpoint_reset)
+}
+
 #[derive(Debug)]
 pub struct SuggestTruncate(pub SourceCheckpoint);
 
@@ -347,4 +473,77 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_checkpoint_reset_target_from_str() {
+        for target in [
+            CheckpointResetTarget::Beginning,
+            CheckpointResetTarget::End,
+            CheckpointResetTarget::Offset(42),
+            CheckpointResetTarget::Timestamp(1_640_577_000_000),
+        ] {
+            assert_eq!(
+                target.to_string().parse::<CheckpointResetTarget>().unwrap(),
+                target
+            );
+        }
+        assert!("start".parse::<CheckpointResetTarget>().is_err());
+        assert!("offset:".parse::<CheckpointResetTarget>().is_err());
+        assert!("timestamp:yesterday"
+            .parse::<CheckpointResetTarget>()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plan_checkpoint_reset() {
+        let source_config = SourceConfig {
+            source_id: "file".to_string(),
+            source_params: SourceParams::file("data/test_corpus.json"),
+        };
+        let checkpoint: SourceCheckpoint = vec![("shard-000", 10u64), ("shard-001", 20u64)]
+            .into_iter()
+            .map(|(partition_id, offset)| (PartitionId::from(partition_id), Position::from(offset)))
+            .collect();
+        {
+            let checkpoint_reset = plan_checkpoint_reset(
+                &source_config,
+                &checkpoint,
+                CheckpointResetTarget::Beginning,
+                &[],
+            )
+            .await
+            .unwrap();
+            assert_eq!(checkpoint_reset.num_partitions(), 2);
+            let mut new_checkpoint = checkpoint.clone();
+            new_checkpoint.try_apply_reset(checkpoint_reset).unwrap();
+            assert!(new_checkpoint.is_empty());
+        }
+        {
+            let checkpoint_reset = plan_checkpoint_reset(
+                &source_config,
+                &checkpoint,
+                CheckpointResetTarget::Beginning,
+                &[PartitionId::from("shard-001")],
+            )
+            .await
+            .unwrap();
+            let partition_resets: Vec<_> = checkpoint_reset.iter().collect();
+            assert_eq!(
+                partition_resets,
+                vec![(
+                    &PartitionId::from("shard-001"),
+                    &Position::from(20u64),
+                    &Position::Beginning
+                )]
+            );
+        }
+        assert!(plan_checkpoint_reset(
+            &source_config,
+            &checkpoint,
+            CheckpointResetTarget::End,
+            &[],
+        )
+        .await
+        .is_err());
+    }
 }
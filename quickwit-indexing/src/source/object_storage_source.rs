@@ -0,0 +1,613 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::uri::Uri;
+use quickwit_config::{Compression, ObjectStorageSourceParams};
+use quickwit_doc_mapper::{InputFormat, TextRecordConverter};
+use quickwit_metastore::checkpoint::{CheckpointDelta, PartitionId, Position, SourceCheckpoint};
+use quickwit_storage::{quickwit_storage_uri_resolver, Storage};
+use serde::Serialize;
+use tempfile::NamedTempFile;
+use tracing::{info, warn};
+
+use crate::actors::Indexer;
+use crate::models::{PartitionAssignment, RawDocBatch};
+use crate::source::file_source::BATCH_NUM_BYTES_THRESHOLD;
+use crate::source::text_file_reader::{new_record_converter, TextFilePosition, TextFileReader};
+use crate::source::{Source, SourceContext, TypedSourceFactory};
+
+/// Factory for instantiating an `ObjectStorageSource`.
+pub struct ObjectStorageSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for ObjectStorageSourceFactory {
+    type Source = ObjectStorageSource;
+    type Params = ObjectStorageSourceParams;
+
+    async fn typed_create_source(
+        source_id: String,
+        params: ObjectStorageSourceParams,
+        checkpoint: SourceCheckpoint,
+        _partition_assignment: PartitionAssignment,
+    ) -> anyhow::Result<Self::Source> {
+        let (storage_uri, key_prefix) = params.storage_uri_and_key_prefix();
+        let storage = resolve_storage(storage_uri)?;
+        let key_prefix = PathBuf::from(key_prefix);
+        Ok(ObjectStorageSource::new(
+            source_id, storage, key_prefix, &params, checkpoint,
+        ))
+    }
+}
+
+fn resolve_storage(storage_uri: &str) -> anyhow::Result<Arc<dyn Storage>> {
+    let storage_uri = Uri::try_new(storage_uri)?;
+    let storage = quickwit_storage_uri_resolver()
+        .resolve(storage_uri.as_str())
+        .with_context(|| format!("Failed to resolve storage `{}`.", storage_uri))?;
+    Ok(storage)
+}
+
+fn partition_id_from_path(object_path: &Path) -> PartitionId {
+    PartitionId::from(object_path.to_string_lossy().to_string())
+}
+
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ObjectStorageSourceCounters {
+    /// Number of objects read entirely by the source.
+    pub num_objects_processed: u64,
+    /// Number of decompressed bytes read by the source.
+    pub num_bytes_processed: u64,
+    /// Number of lines read by the source.
+    pub num_lines_processed: u64,
+    /// Number of objects pruned from the checkpoint.
+    pub num_objects_pruned: u64,
+    /// Key of the object being read, if any.
+    pub current_object: Option<String>,
+}
+
+/// The object being read. It is downloaded to a temporary file and decompressed on the fly.
+struct CurrentObject {
+    object_path: PathBuf,
+    partition_id: PartitionId,
+    /// Position of the last line sent to the indexer.
+    position: Position,
+    reader: TextFileReader,
+    converter: TextRecordConverter,
+    /// Deletes the downloaded object once read.
+    _temp_file: NamedTempFile,
+}
+
+/// An `ObjectStorageSource` tails a key prefix of an object storage, such as an S3 bucket, and
+/// ingests the objects appearing under that prefix. The objects must consist of documents
+/// separated by a newline, optionally compressed with gzip or zstd.
+///
+/// Each object is a partition of the source: its position in the checkpoint is the position of the
+/// last line indexed (see [`TextFilePosition`]), suffixed with `:eof` once the object has been read
+/// entirely. Objects are assumed to be immutable once written. Every listing covers the whole
+/// prefix, so that objects are read whatever the order in which their keys appear. To keep the
+/// checkpoint bounded, the partitions of the objects whose key sorts before the first key listed,
+/// i.e. objects deleted from the storage, for instance by a lifecycle rule, are pruned from the
+/// checkpoint.
+pub struct ObjectStorageSource {
+    source_id: String,
+    storage: Arc<dyn Storage>,
+    key_prefix: PathBuf,
+    compression: Compression,
+    input_format: InputFormat,
+    poll_interval: Duration,
+    /// Positions of the objects read by the source and by its previous runs.
+    positions: BTreeMap<PartitionId, Position>,
+    /// Objects listed but not read entirely yet.
+    pending_objects: VecDeque<PathBuf>,
+    /// Checkpoint delta of the objects pruned or skipped, sent along with the next batch.
+    checkpoint_delta: CheckpointDelta,
+    current_object_opt: Option<CurrentObject>,
+    counters: ObjectStorageSourceCounters,
+}
+
+impl fmt::Debug for ObjectStorageSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ObjectStorageSource {{ source_id: {}, storage: {}, key_prefix: {} }}",
+            self.source_id,
+            self.storage.uri(),
+            self.key_prefix.display()
+        )
+    }
+}
+
+impl ObjectStorageSource {
+    /// Instantiates a new `ObjectStorageSource` reading the objects of `storage` whose key starts
+    /// with `key_prefix`.
+    pub fn new(
+        source_id: String,
+        storage: Arc<dyn Storage>,
+        key_prefix: PathBuf,
+        params: &ObjectStorageSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> Self {
+        ObjectStorageSource {
+            source_id,
+            storage,
+            key_prefix,
+            compression: params.compression,
+            input_format: params.input_format,
+            poll_interval: Duration::from_secs(params.poll_interval_secs),
+            positions: checkpoint.iter().collect(),
+            pending_objects: VecDeque::new(),
+            checkpoint_delta: CheckpointDelta::default(),
+            current_object_opt: None,
+            counters: ObjectStorageSourceCounters::default(),
+        }
+    }
+
+    /// Lists the objects that have not been read entirely yet, and prunes the objects whose key
+    /// sorts before the first key listed from the checkpoint.
+    async fn list_pending_objects(&mut self) -> anyhow::Result<()> {
+        let object_paths = self
+            .storage
+            .list(&self.key_prefix, None)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to list objects with prefix `{}` in storage `{}`.",
+                    self.key_prefix.display(),
+                    self.storage.uri()
+                )
+            })?;
+        if let Some(first_object_path) = object_paths.first() {
+            let watermark = partition_id_from_path(first_object_path);
+            let pruned_partition_ids: Vec<PartitionId> = self
+                .positions
+                .range(..watermark)
+                .map(|(partition_id, _)| partition_id.clone())
+                .collect();
+            for partition_id in pruned_partition_ids {
+                let position = self
+                    .positions
+                    .remove(&partition_id)
+                    .expect("The partition should be in the positions.");
+                self.checkpoint_delta
+                    .record_partition_pruning(partition_id, position)
+                    .context("Failed to record the pruning of the object.")?;
+                self.counters.num_objects_pruned += 1;
+            }
+        }
+        for object_path in object_paths {
+            if let Some(position) = self.positions.get(&partition_id_from_path(&object_path)) {
+                if TextFilePosition::parse(position)?.is_complete {
+                    continue;
+                }
+            }
+            self.pending_objects.push_back(object_path);
+        }
+        Ok(())
+    }
+
+    /// Downloads an object to a temporary file and opens it at its position in the checkpoint.
+    /// Returns `None` if the object is shorter than its position, in which case the object is
+    /// marked as read entirely.
+    async fn open_object(
+        &mut self,
+        object_path: PathBuf,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<Option<CurrentObject>> {
+        let partition_id = partition_id_from_path(&object_path);
+        let position = self
+            .positions
+            .get(&partition_id)
+            .cloned()
+            .unwrap_or(Position::Beginning);
+        let file_position = TextFilePosition::parse(&position)?;
+        let temp_file = NamedTempFile::new()?;
+        {
+            let _protect_guard = ctx.protect_zone();
+            self.storage
+                .copy_to_file(&object_path, temp_file.path())
+                .await
+                .with_context(|| {
+                    format!("Failed to download object `{}`.", object_path.display())
+                })?;
+        }
+        let compression = self.compression.for_path(&object_path);
+        let converter = new_record_converter(
+            temp_file.path(),
+            compression,
+            self.input_format,
+            file_position.offset,
+        )
+        .await?;
+        let reader = if let Some(reader) =
+            TextFileReader::open(temp_file.path(), compression, file_position).await?
+        {
+            reader
+        } else {
+            warn!(
+                object = %object_path.display(),
+                offset = file_position.offset,
+                "The object is shorter than its position in the checkpoint. Skipping."
+            );
+            let complete_position = TextFilePosition {
+                is_complete: true,
+                ..file_position
+            }
+            .to_position();
+            self.checkpoint_delta
+                .record_partition_delta(partition_id.clone(), position, complete_position.clone())
+                .context("Failed to record the checkpoint delta of the object.")?;
+            self.positions.insert(partition_id, complete_position);
+            return Ok(None);
+        };
+        info!(object = %object_path.display(), offset = file_position.offset, "Reading object.");
+        let current_object = CurrentObject {
+            object_path,
+            partition_id,
+            position,
+            reader,
+            converter,
+            _temp_file: temp_file,
+        };
+        Ok(Some(current_object))
+    }
+}
+
+#[async_trait]
+impl Source for ObjectStorageSource {
+    async fn emit_batches(
+        &mut self,
+        batch_sink: &Mailbox<Indexer>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        if self.current_object_opt.is_none() {
+            if self.pending_objects.is_empty() {
+                self.list_pending_objects().await?;
+            }
+            if let Some(object_path) = self.pending_objects.pop_front() {
+                self.counters.current_object = Some(object_path.to_string_lossy().to_string());
+                self.current_object_opt = self.open_object(object_path, ctx).await?;
+            }
+        }
+        let mut checkpoint_delta = std::mem::take(&mut self.checkpoint_delta);
+        let current_object = if let Some(current_object) = self.current_object_opt.as_mut() {
+            current_object
+        } else {
+            self.counters.current_object = None;
+            if !checkpoint_delta.is_empty() {
+                let raw_doc_batch = RawDocBatch {
+                    checkpoint_delta,
+                    ..Default::default()
+                };
+                ctx.send_message(batch_sink, raw_doc_batch).await?;
+            }
+            if self.pending_objects.is_empty() {
+                return Ok(self.poll_interval);
+            }
+            return Ok(Duration::default());
+        };
+        let start_offset = current_object.reader.position().offset;
+        let mut reached_eof = false;
+        let mut docs = Vec::new();
+        let mut doc_positions = Vec::new();
+        // A batch is not cut in the middle of a record spanning several lines, so that the
+        // checkpoint never points inside a record.
+        while current_object.reader.position().offset - start_offset < BATCH_NUM_BYTES_THRESHOLD
+            || current_object.converter.is_within_record()
+        {
+            let mut line = String::new();
+            let num_bytes = current_object
+                .reader
+                .read_line(&mut line)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to read object `{}`.",
+                        current_object.object_path.display()
+                    )
+                })?;
+            let doc_opt = if num_bytes == 0 {
+                reached_eof = true;
+                current_object.converter.finish()
+            } else {
+                self.counters.num_lines_processed += 1;
+                current_object.converter.convert_line(line)
+            };
+            if let Some(mut doc) = doc_opt {
+                // The lines are read with their newline character.
+                doc.truncate(doc.trim_end().len());
+                if !doc.is_empty() {
+                    docs.push(doc);
+                    doc_positions.push((
+                        current_object.partition_id.clone(),
+                        current_object.reader.position().to_position(),
+                    ));
+                }
+            }
+            if reached_eof {
+                break;
+            }
+        }
+        let mut file_position = current_object.reader.position();
+        file_position.is_complete = reached_eof;
+        let to_position = file_position.to_position();
+        checkpoint_delta
+            .record_partition_delta(
+                current_object.partition_id.clone(),
+                current_object.position.clone(),
+                to_position.clone(),
+            )
+            .context("Failed to record the checkpoint delta of the object.")?;
+        current_object.position = to_position.clone();
+        self.positions
+            .insert(current_object.partition_id.clone(), to_position);
+        self.counters.num_bytes_processed += file_position.offset - start_offset;
+        if reached_eof {
+            self.current_object_opt = None;
+            self.counters.current_object = None;
+            self.counters.num_objects_processed += 1;
+        }
+        let raw_doc_batch = RawDocBatch {
+            docs,
+            doc_positions,
+            checkpoint_delta,
+        };
+        ctx.send_message(batch_sink, raw_doc_batch).await?;
+        Ok(Duration::default())
+    }
+
+    fn name(&self) -> String {
+        format!("ObjectStorageSource{{source_id={}}}", self.source_id)
+    }
+
+    fn observable_state(&self) -> serde_json::Value {
+        serde_json::to_value(&self.counters).unwrap()
+    }
+}
+
+pub(super) async fn check_connectivity(params: &ObjectStorageSourceParams) -> anyhow::Result<()> {
+    let (storage_uri, _) = params.storage_uri_and_key_prefix();
+    let storage = resolve_storage(storage_uri)?;
+    storage.check().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+
+    use quickwit_actors::{create_test_mailbox, ActorHandle, Universe};
+    use quickwit_storage::RamStorage;
+
+    use super::*;
+    use crate::source::SourceActor;
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn object_storage_params() -> ObjectStorageSourceParams {
+        ObjectStorageSourceParams {
+            uri: "ram:///logs/app-".to_string(),
+            poll_interval_secs: 1,
+            compression: Compression::Auto,
+            input_format: InputFormat::Json,
+        }
+    }
+
+    async fn wait_for_num_objects_processed(
+        handle: &ActorHandle<SourceActor>,
+        expected_num_objects_processed: u64,
+    ) {
+        let mut num_objects_processed = 0;
+        for _ in 0..100 {
+            let state = handle.observe().await.state;
+            num_objects_processed = state["num_objects_processed"].as_u64().unwrap();
+            if num_objects_processed == expected_num_objects_processed {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(num_objects_processed, expected_num_objects_processed);
+    }
+
+    fn merge_doc_batches(batches: Vec<RawDocBatch>) -> anyhow::Result<RawDocBatch> {
+        let mut merged_batch = RawDocBatch::default();
+        for batch in batches {
+            merged_batch.docs.extend(batch.docs);
+            merged_batch
+                .checkpoint_delta
+                .extend(batch.checkpoint_delta)?;
+        }
+        Ok(merged_batch)
+    }
+
+    #[tokio::test]
+    async fn test_object_storage_source() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let storage = RamStorage::builder()
+            .put("logs/app-1.json", b"{\"body\": \"a\"}\n{\"body\": \"b\"}\n")
+            .put(
+                "logs/app-2.json.gz",
+                &gzip(b"{\"body\": \"c\"}\n\n{\"body\": \"d\"}"),
+            )
+            .put(
+                "logs/app-3.json.zst",
+                &zstd::encode_all(&b"{\"body\": \"e\"}\n"[..], 0)?,
+            )
+            .put("logs/other.json", b"{\"body\": \"f\"}\n")
+            .build();
+        let storage: Arc<dyn Storage> = Arc::new(storage);
+        let source = ObjectStorageSource::new(
+            "my-object-storage-source".to_string(),
+            storage.clone(),
+            PathBuf::from("logs/app-"),
+            &object_storage_params(),
+            SourceCheckpoint::default(),
+        );
+        let (sink, inbox) = create_test_mailbox();
+        let actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: sink,
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        wait_for_num_objects_processed(&handle, 3).await;
+        let batch = merge_doc_batches(inbox.drain_for_test_typed())?;
+        assert_eq!(
+            batch.docs,
+            vec![
+                r#"{"body": "a"}"#,
+                r#"{"body": "b"}"#,
+                r#"{"body": "c"}"#,
+                r#"{"body": "d"}"#,
+                r#"{"body": "e"}"#,
+            ]
+        );
+        let checkpoint = batch.checkpoint_delta.get_source_checkpoint();
+        assert_eq!(
+            format!("{:?}", checkpoint),
+            "Ckpt(logs/app-1.json:00000000000000000028:eof \
+             logs/app-2.json.gz:00000000000000000028:eof \
+             logs/app-3.json.zst:00000000000000000014:eof)"
+        );
+
+        // New objects are ingested as they appear, whatever the order of their keys.
+        storage
+            .put(
+                Path::new("logs/app-0.json"),
+                Box::new(b"{\"body\": \"z\"}\n".to_vec()),
+            )
+            .await?;
+        storage
+            .put(
+                Path::new("logs/app-4.json"),
+                Box::new(b"{\"body\": \"g\"}\n".to_vec()),
+            )
+            .await?;
+        wait_for_num_objects_processed(&handle, 5).await;
+        let batch = merge_doc_batches(inbox.drain_for_test_typed())?;
+        assert_eq!(batch.docs, vec![r#"{"body": "z"}"#, r#"{"body": "g"}"#]);
+        handle.quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_object_storage_source_resume_from_checkpoint() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let storage = RamStorage::builder()
+            .put("app-1.json", b"{\"body\": \"a\"}\n")
+            .put("app-2.json", b"{\"body\": \"b\"}\n{\"body\": \"c\"}\n")
+            .put("app-3.json", b"{\"body\": \"d\"}\n")
+            .build();
+        let checkpoint: SourceCheckpoint = vec![
+            (
+                PartitionId::from("app-1.json"),
+                Position::from("00000000000000000014:eof"),
+            ),
+            (PartitionId::from("app-2.json"), Position::from(14u64)),
+        ]
+        .into_iter()
+        .collect();
+        let source = ObjectStorageSource::new(
+            "my-object-storage-source".to_string(),
+            Arc::new(storage),
+            PathBuf::new(),
+            &object_storage_params(),
+            checkpoint,
+        );
+        let (sink, inbox) = create_test_mailbox();
+        let actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: sink,
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        wait_for_num_objects_processed(&handle, 2).await;
+        let batch = merge_doc_batches(inbox.drain_for_test_typed())?;
+        assert_eq!(batch.docs, vec![r#"{"body": "c"}"#, r#"{"body": "d"}"#]);
+        assert_eq!(
+            format!("{:?}", batch.checkpoint_delta),
+            "∆(app-2.json:(00000000000000000014..00000000000000000028:eof] \
+             app-3.json:(..00000000000000000014:eof])"
+        );
+        handle.quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_object_storage_source_prunes_deleted_objects() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let storage = RamStorage::builder()
+            .put("app-2.json", b"{\"body\": \"b\"}\n")
+            .put("app-3.json", b"{\"body\": \"c\"}\n")
+            .build();
+        // `app-0.json` and `app-1.json` were deleted after being read. `app-3.json` was read
+        // entirely before `app-2.json` appeared.
+        let mut checkpoint: SourceCheckpoint = vec![
+            (
+                PartitionId::from("app-0.json"),
+                Position::from("00000000000000000014:eof"),
+            ),
+            (PartitionId::from("app-1.json"), Position::from(14u64)),
+            (
+                PartitionId::from("app-3.json"),
+                Position::from("00000000000000000014:eof"),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let source = ObjectStorageSource::new(
+            "my-object-storage-source".to_string(),
+            Arc::new(storage),
+            PathBuf::new(),
+            &object_storage_params(),
+            checkpoint.clone(),
+        );
+        let (sink, inbox) = create_test_mailbox();
+        let actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: sink,
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        wait_for_num_objects_processed(&handle, 1).await;
+        let state = handle.observe().await.state;
+        assert_eq!(state["num_objects_pruned"].as_u64(), Some(2));
+        let batch = merge_doc_batches(inbox.drain_for_test_typed())?;
+        assert_eq!(batch.docs, vec![r#"{"body": "b"}"#]);
+        checkpoint.try_apply_delta(batch.checkpoint_delta)?;
+        assert_eq!(
+            format!("{:?}", checkpoint),
+            "Ckpt(app-2.json:00000000000000000014:eof app-3.json:00000000000000000014:eof)"
+        );
+        handle.quit().await;
+        Ok(())
+    }
+}
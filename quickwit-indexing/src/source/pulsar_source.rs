@@ -0,0 +1,573 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use futures::StreamExt;
+use itertools::Itertools;
+use pulsar::consumer::InitialPosition;
+use pulsar::message::proto::MessageIdData;
+use pulsar::{Consumer, ConsumerOptions, Pulsar, SubType, TokioExecutor};
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_config::PulsarSourceParams;
+use quickwit_metastore::checkpoint::{CheckpointDelta, PartitionId, Position, SourceCheckpoint};
+use serde_json::json;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::actors::Indexer;
+use crate::models::{PartitionAssignment, RawDocBatch};
+use crate::source::{Source, SourceContext, TypedSourceFactory};
+
+/// We try to emit chewable batches for the indexer.
+/// One batch = one message to the indexer actor.
+///
+/// See the Kafka source for the rationale behind this value.
+const TARGET_BATCH_NUM_BYTES: u64 = 5_000_000;
+
+type PulsarConsumer = Consumer<Vec<u8>, TokioExecutor>;
+
+/// Factory for instantiating a `PulsarSource`.
+pub struct PulsarSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for PulsarSourceFactory {
+    type Source = PulsarSource;
+    type Params = PulsarSourceParams;
+
+    async fn typed_create_source(
+        source_id: String,
+        params: PulsarSourceParams,
+        checkpoint: SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
+    ) -> anyhow::Result<Self::Source> {
+        PulsarSource::try_new(source_id, params, checkpoint, partition_assignment).await
+    }
+}
+
+#[derive(Default)]
+pub struct PulsarSourceState {
+    /// Partitions assigned to the source, i.e. the partitions of the topics, or the topics
+    /// themselves for non-partitioned topics.
+    pub assigned_partitions: Vec<String>,
+    /// Position of the last message received for each partition.
+    pub current_positions: HashMap<String, Position>,
+    /// Number of bytes processed by the source.
+    pub num_bytes_processed: u64,
+    /// Number of messages processed by the source (including invalid messages).
+    pub num_messages_processed: u64,
+    // Number of invalid messages, i.e., that were empty or could not be parsed.
+    pub num_invalid_messages: u64,
+}
+
+/// A `PulsarSource` consumes a set of topics and forwards their messages to an `Indexer`.
+///
+/// The positions of the messages are tracked per partition in the checkpoint. Messages are
+/// acknowledged cumulatively to the broker once the splits containing them are published, so
+/// that the broker redelivers the unpublished messages upon restart. Redelivered messages that
+/// are already covered by the checkpoint are skipped.
+pub struct PulsarSource {
+    source_id: String,
+    /// The consumer is `None` when no partitions are assigned to the source. The mutex is never
+    /// contended: it only makes the consumer accessible from `suggest_truncate`.
+    consumer_opt: Option<Mutex<PulsarConsumer>>,
+    /// Positions of the messages indexed by previous runs of the source.
+    checkpoint: SourceCheckpoint,
+    state: PulsarSourceState,
+}
+
+impl fmt::Debug for PulsarSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PulsarSource {{ source_id: {}, partitions: {:?} }}",
+            self.source_id, self.state.assigned_partitions
+        )
+    }
+}
+
+impl PulsarSource {
+    /// Instantiates a new `PulsarSource` consuming the partitions of the topics assigned to the
+    /// pipeline.
+    pub async fn try_new(
+        source_id: String,
+        params: PulsarSourceParams,
+        checkpoint: SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
+    ) -> anyhow::Result<Self> {
+        let pulsar = connect(&params.address).await?;
+        let assigned_partitions: Vec<String> = list_partitions(&pulsar, &params.topics)
+            .await?
+            .into_iter()
+            .filter(|partition| {
+                partition_assignment.is_assigned(&PartitionId::from(partition.as_str()))
+            })
+            .collect();
+        let current_positions = checkpoint
+            .iter()
+            .map(|(partition_id, position)| (partition_id.0.to_string(), position))
+            .collect();
+        if assigned_partitions.is_empty() {
+            info!(
+                topics = ?params.topics,
+                "No partitions of the topics are assigned to this indexer."
+            );
+            return Ok(PulsarSource {
+                source_id,
+                consumer_opt: None,
+                checkpoint,
+                state: PulsarSourceState {
+                    current_positions,
+                    ..Default::default()
+                },
+            });
+        }
+        let subscription_name = params
+            .subscription_name
+            .unwrap_or_else(|| format!("quickwit-{source_id}"));
+        // The broker redelivers the messages that were not acknowledged yet, i.e. the messages
+        // that may not be covered by the checkpoint, starting from the earliest ones for new
+        // subscriptions.
+        let consumer: PulsarConsumer = pulsar
+            .consumer()
+            .with_topics(&assigned_partitions)
+            .with_consumer_name(format!("quickwit-{source_id}"))
+            .with_subscription(&subscription_name)
+            .with_subscription_type(SubType::Failover)
+            .with_options(
+                ConsumerOptions::default().with_initial_position(InitialPosition::Earliest),
+            )
+            .build()
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to subscribe to Pulsar topics with subscription `{subscription_name}`."
+                )
+            })?;
+
+        info!(
+            partitions = ?assigned_partitions,
+            subscription = %subscription_name,
+            "Starting Pulsar source."
+        );
+        let state = PulsarSourceState {
+            assigned_partitions,
+            current_positions,
+            ..Default::default()
+        };
+        Ok(PulsarSource {
+            source_id,
+            consumer_opt: Some(Mutex::new(consumer)),
+            checkpoint,
+            state,
+        })
+    }
+}
+
+#[async_trait]
+impl Source for PulsarSource {
+    async fn emit_batches(
+        &mut self,
+        batch_sink: &Mailbox<Indexer>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let mut consumer = if let Some(consumer) = &self.consumer_opt {
+            consumer.lock().await
+        } else {
+            info!("No partitions to consume.");
+            ctx.send_exit_with_success(batch_sink).await?;
+            return Err(ActorExitStatus::Success);
+        };
+        let mut docs = Vec::new();
+        let mut doc_positions = Vec::new();
+        let mut checkpoint_delta = CheckpointDelta::default();
+
+        let deadline = tokio::time::sleep(quickwit_actors::HEARTBEAT / 2);
+        let mut message_stream = Box::pin(consumer.by_ref().take_until(deadline));
+
+        let mut batch_num_bytes = 0;
+
+        while let Some(message_res) = message_stream.next().await {
+            let message = message_res
+                .map_err(|error| anyhow::anyhow!(error).context("Failed to consume message."))?;
+            let partition = message.topic.clone();
+            let current_position = position_from_message_id(message.message_id());
+            let partition_id = PartitionId::from(partition.as_str());
+            if let Some(checkpoint_position) = self.checkpoint.position_for_partition(&partition_id)
+            {
+                // The message was indexed before the source restarted but not acknowledged.
+                if current_position <= *checkpoint_position {
+                    continue;
+                }
+            }
+            let num_bytes = message.payload.data.len() as u64;
+            match String::from_utf8(message.payload.data) {
+                Ok(doc) if !doc.is_empty() => {
+                    debug!(
+                        partition = %partition,
+                        position = ?current_position,
+                        num_bytes = num_bytes,
+                        "Message received."
+                    );
+                    docs.push(doc);
+                    doc_positions.push((partition_id.clone(), current_position.clone()));
+                }
+                Ok(_) => {
+                    debug!(
+                        partition = %partition,
+                        position = ?current_position,
+                        "Document is empty."
+                    );
+                    self.state.num_invalid_messages += 1;
+                }
+                Err(error) => {
+                    warn!(
+                        partition = %partition,
+                        position = ?current_position,
+                        error = ?error,
+                        "Failed to deserialize message payload."
+                    );
+                    self.state.num_invalid_messages += 1;
+                }
+            }
+            batch_num_bytes += num_bytes;
+            self.state.num_bytes_processed += num_bytes;
+            self.state.num_messages_processed += 1;
+
+            let previous_position = self
+                .state
+                .current_positions
+                .insert(partition, current_position.clone())
+                .unwrap_or(Position::Beginning);
+            checkpoint_delta
+                .record_partition_delta(partition_id, previous_position, current_position)
+                .context("Failed to record partition delta.")?;
+
+            if batch_num_bytes >= TARGET_BATCH_NUM_BYTES {
+                break;
+            }
+            ctx.record_progress();
+        }
+        if !checkpoint_delta.is_empty() {
+            let batch = RawDocBatch {
+                docs,
+                doc_positions,
+                checkpoint_delta,
+            };
+            ctx.send_message(batch_sink, batch).await?;
+        }
+        Ok(Duration::default())
+    }
+
+    async fn suggest_truncate(
+        &self,
+        checkpoint: SourceCheckpoint,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let mut consumer = if let Some(consumer) = &self.consumer_opt {
+            consumer.lock().await
+        } else {
+            return Ok(());
+        };
+        // Acknowledging cumulatively the last message published for a partition lets the broker
+        // discard the messages anterior to it for the subscription.
+        for (partition_id, position) in checkpoint.iter() {
+            let partition = partition_id.0.as_str();
+            if !self
+                .state
+                .assigned_partitions
+                .iter()
+                .any(|assigned_partition| assigned_partition == partition)
+            {
+                continue;
+            }
+            if let Some(message_id) = message_id_from_position(&position)? {
+                consumer
+                    .cumulative_ack_with_id(partition, message_id)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to acknowledge messages of partition `{partition}`.")
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        format!("PulsarSource{{source_id={}}}", self.source_id)
+    }
+
+    fn observable_state(&self) -> serde_json::Value {
+        let current_positions: BTreeMap<&String, &str> = self
+            .state
+            .current_positions
+            .iter()
+            .map(|(partition, position)| (partition, position.as_str()))
+            .collect();
+        json!({
+            "assigned_partitions": self.state.assigned_partitions,
+            "current_positions": current_positions,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+        })
+    }
+}
+
+/// Checks whether we can establish a connection to the Pulsar service and look up the topics.
+pub(super) async fn check_connectivity(params: PulsarSourceParams) -> anyhow::Result<()> {
+    let pulsar = connect(&params.address).await?;
+    list_partitions(&pulsar, &params.topics).await?;
+    Ok(())
+}
+
+async fn connect(address: &str) -> anyhow::Result<Pulsar<TokioExecutor>> {
+    Pulsar::builder(address, TokioExecutor)
+        .build()
+        .await
+        .with_context(|| format!("Failed to connect to Pulsar service `{address}`."))
+}
+
+/// Returns the partitions of the topics. Non-partitioned topics are their own single partition.
+async fn list_partitions(
+    pulsar: &Pulsar<TokioExecutor>,
+    topics: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let mut partitions = Vec::new();
+    for topic in topics {
+        let topic_partitions = pulsar
+            .lookup_partitioned_topic(topic)
+            .await
+            .with_context(|| format!("Failed to look up Pulsar topic `{topic}`."))?;
+        partitions.extend(topic_partitions.into_iter().map(|(partition, _)| partition));
+    }
+    Ok(partitions.into_iter().sorted().dedup().collect())
+}
+
+/// Encodes a message ID as a position. Positions are zero-padded so that their lexicographic
+/// order matches the order of the messages within a partition.
+fn position_from_message_id(message_id: &MessageIdData) -> Position {
+    let batch_index = message_id.batch_index.unwrap_or(0).max(0);
+    Position::from(format!(
+        "{:0>20}:{:0>20}:{:0>10}",
+        message_id.ledger_id, message_id.entry_id, batch_index
+    ))
+}
+
+/// Decodes a position encoded by [`position_from_message_id`]. Returns `None` for the beginning
+/// of the partition.
+fn message_id_from_position(position: &Position) -> anyhow::Result<Option<MessageIdData>> {
+    let position_str = match position {
+        Position::Beginning => return Ok(None),
+        Position::Offset(position_str) => position_str.as_str(),
+    };
+    let parts: Vec<&str> = position_str.split(':').collect();
+    if parts.len() != 3 {
+        bail!("Invalid Pulsar position `{position_str}`.");
+    }
+    let parse_error = || format!("Invalid Pulsar position `{position_str}`.");
+    let message_id = MessageIdData {
+        ledger_id: parts[0].parse().with_context(parse_error)?,
+        entry_id: parts[1].parse().with_context(parse_error)?,
+        batch_index: Some(parts[2].parse().with_context(parse_error)?),
+        ..Default::default()
+    };
+    Ok(Some(message_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_from_message_id() {
+        let message_id = MessageIdData {
+            ledger_id: 42,
+            entry_id: 7,
+            batch_index: Some(3),
+            ..Default::default()
+        };
+        let position = position_from_message_id(&message_id);
+        assert_eq!(
+            position.as_str(),
+            "00000000000000000042:00000000000000000007:0000000003"
+        );
+        let decoded_message_id = message_id_from_position(&position).unwrap().unwrap();
+        assert_eq!(decoded_message_id.ledger_id, 42);
+        assert_eq!(decoded_message_id.entry_id, 7);
+        assert_eq!(decoded_message_id.batch_index, Some(3));
+
+        let unbatched_message_id = MessageIdData {
+            ledger_id: 42,
+            entry_id: 8,
+            batch_index: Some(-1),
+            ..Default::default()
+        };
+        assert!(position_from_message_id(&unbatched_message_id) > position);
+
+        assert!(message_id_from_position(&Position::Beginning)
+            .unwrap()
+            .is_none());
+        message_id_from_position(&Position::from(42u64)).unwrap_err();
+    }
+}
+
+#[cfg(all(test, feature = "pulsar-broker-tests"))]
+mod pulsar_broker_tests {
+    use pulsar::producer::ProducerOptions;
+    use quickwit_actors::{create_test_mailbox, Universe};
+    use quickwit_common::rand::append_random_suffix;
+    use quickwit_config::{SourceConfig, SourceParams};
+
+    use super::*;
+    use crate::source::{quickwit_supported_sources, SourceActor, SuggestTruncate};
+
+    const PULSAR_ADDRESS: &str = "pulsar://localhost:6650";
+
+    async fn populate_topic(topic: &str, messages: &[&str]) -> anyhow::Result<()> {
+        let pulsar = connect(PULSAR_ADDRESS).await?;
+        let mut producer = pulsar
+            .producer()
+            .with_topic(topic)
+            .with_options(ProducerOptions::default())
+            .build()
+            .await?;
+        for message in messages {
+            producer.send(message.as_bytes().to_vec()).await?.await?;
+        }
+        Ok(())
+    }
+
+    fn merge_doc_batches(batches: Vec<RawDocBatch>) -> anyhow::Result<RawDocBatch> {
+        let mut merged_batch = RawDocBatch::default();
+        for batch in batches {
+            merged_batch.docs.extend(batch.docs);
+            merged_batch
+                .checkpoint_delta
+                .extend(batch.checkpoint_delta)?;
+        }
+        Ok(merged_batch)
+    }
+
+    #[tokio::test]
+    async fn test_pulsar_source() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+
+        let universe = Universe::new();
+        let topic = append_random_suffix("persistent://public/default/test-pulsar-source-topic");
+        let source_config = SourceConfig {
+            source_id: append_random_suffix("test-pulsar-source"),
+            source_params: SourceParams::Pulsar(PulsarSourceParams {
+                topics: vec![topic.clone()],
+                address: PULSAR_ADDRESS.to_string(),
+                subscription_name: None,
+            }),
+        };
+        populate_topic(&topic, &["Message #000", "", "Message #002"]).await?;
+
+        let source_loader = quickwit_supported_sources();
+        let (sink, inbox) = create_test_mailbox();
+        let source = source_loader
+            .load_source(
+                source_config.clone(),
+                SourceCheckpoint::default(),
+                PartitionAssignment::default(),
+            )
+            .await?;
+        let actor = SourceActor {
+            source,
+            batch_sink: sink.clone(),
+        };
+        let (mailbox, handle) = universe.spawn_actor(actor).spawn();
+        let mut num_messages_processed = 0;
+        for _ in 0..100 {
+            let state = handle.observe().await.state;
+            num_messages_processed = state["num_messages_processed"].as_u64().unwrap();
+            if num_messages_processed == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(num_messages_processed, 3);
+        let batches: Vec<RawDocBatch> = inbox
+            .drain_for_test()
+            .into_iter()
+            .flat_map(|msg_any| msg_any.downcast::<RawDocBatch>().ok())
+            .map(|boxed_msg| *boxed_msg)
+            .collect();
+        let batch = merge_doc_batches(batches)?;
+        assert_eq!(batch.docs, vec!["Message #000", "Message #002"]);
+
+        // Acknowledge the first two messages only.
+        let checkpoint = batch.checkpoint_delta.get_source_checkpoint();
+        let (_, last_position) = checkpoint.iter().next().unwrap();
+        let last_message_id = message_id_from_position(&last_position)?.unwrap();
+        let truncate_position = position_from_message_id(&MessageIdData {
+            entry_id: last_message_id.entry_id - 1,
+            ..last_message_id
+        });
+        let truncate_checkpoint: SourceCheckpoint =
+            vec![(PartitionId::from(topic.as_str()), truncate_position)]
+                .into_iter()
+                .collect();
+        mailbox
+            .send_message(SuggestTruncate(truncate_checkpoint))
+            .await?;
+        tokio::time::sleep(quickwit_actors::HEARTBEAT).await;
+        handle.quit().await;
+
+        // The unacknowledged message is redelivered, but skipped since it is covered by the
+        // checkpoint.
+        let (sink, inbox) = create_test_mailbox();
+        let source = source_loader
+            .load_source(
+                source_config.clone(),
+                checkpoint,
+                PartitionAssignment::default(),
+            )
+            .await?;
+        let actor = SourceActor {
+            source,
+            batch_sink: sink.clone(),
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        populate_topic(&topic, &["Message #003"]).await?;
+        for _ in 0..100 {
+            let state = handle.observe().await.state;
+            if state["num_messages_processed"].as_u64().unwrap() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let batches: Vec<RawDocBatch> = inbox
+            .drain_for_test()
+            .into_iter()
+            .flat_map(|msg_any| msg_any.downcast::<RawDocBatch>().ok())
+            .map(|boxed_msg| *boxed_msg)
+            .collect();
+        let batch = merge_doc_batches(batches)?;
+        assert_eq!(batch.docs, vec!["Message #003"]);
+        handle.quit().await;
+        Ok(())
+    }
+}
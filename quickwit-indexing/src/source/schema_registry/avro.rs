@@ -0,0 +1,172 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of Avro binary encoded data into JSON documents.
+
+use anyhow::Context;
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Schema;
+use serde_json::{Map, Number, Value as JsonValue};
+
+/// Parsed Avro schema.
+#[derive(Debug, Clone)]
+pub(crate) struct AvroSchema {
+    schema: Schema,
+}
+
+impl AvroSchema {
+    pub fn parse(schema_str: &str) -> anyhow::Result<Self> {
+        let schema = Schema::parse_str(schema_str).context("Failed to parse Avro schema.")?;
+        Ok(AvroSchema { schema })
+    }
+
+    /// Decodes an Avro datum written with this schema into a JSON value.
+    pub fn decode(&self, mut payload: &[u8]) -> anyhow::Result<JsonValue> {
+        let avro_value = apache_avro::from_avro_datum(&self.schema, &mut payload, None)
+            .context("Failed to decode Avro datum.")?;
+        avro_value_to_json(avro_value)
+    }
+}
+
+/// Converts an Avro value into a JSON value. Unions are flattened, enums are represented by their
+/// symbol, and bytes are base64 encoded, as expected by the doc mapper.
+fn avro_value_to_json(avro_value: AvroValue) -> anyhow::Result<JsonValue> {
+    let json_value = match avro_value {
+        AvroValue::Null => JsonValue::Null,
+        AvroValue::Boolean(value) => JsonValue::Bool(value),
+        AvroValue::Int(value) | AvroValue::Date(value) | AvroValue::TimeMillis(value) => {
+            JsonValue::from(value)
+        }
+        AvroValue::Long(value)
+        | AvroValue::TimeMicros(value)
+        | AvroValue::TimestampMillis(value)
+        | AvroValue::TimestampMicros(value) => JsonValue::from(value),
+        AvroValue::Float(value) => float_to_json(value as f64),
+        AvroValue::Double(value) => float_to_json(value),
+        AvroValue::Bytes(bytes) | AvroValue::Fixed(_, bytes) => {
+            JsonValue::String(base64::encode(bytes))
+        }
+        AvroValue::String(value) | AvroValue::Enum(_, value) => JsonValue::String(value),
+        AvroValue::Union(_, value) => avro_value_to_json(*value)?,
+        AvroValue::Array(values) => JsonValue::Array(
+            values
+                .into_iter()
+                .map(avro_value_to_json)
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        AvroValue::Map(entries) => JsonValue::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((key, avro_value_to_json(value)?)))
+                .collect::<anyhow::Result<Map<_, _>>>()?,
+        ),
+        AvroValue::Record(fields) => JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| Ok((name, avro_value_to_json(value)?)))
+                .collect::<anyhow::Result<Map<_, _>>>()?,
+        ),
+        other => JsonValue::try_from(other).context("Failed to convert Avro value to JSON.")?,
+    };
+    Ok(json_value)
+}
+
+/// Non-finite floats have no JSON representation and are mapped to `null`.
+fn float_to_json(float: f64) -> JsonValue {
+    Number::from_f64(float)
+        .map(JsonValue::Number)
+        .unwrap_or(JsonValue::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::types::Record;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_avro_record() {
+        let schema = AvroSchema::parse(
+            r#"{
+                "type": "record",
+                "name": "LogEvent",
+                "namespace": "io.quickwit",
+                "fields": [
+                    {"name": "severity", "type": {"type": "enum", "name": "Severity", "symbols": ["INFO", "WARN"]}},
+                    {"name": "body", "type": "string"},
+                    {"name": "timestamp", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+                    {"name": "latency", "type": ["null", "double"]},
+                    {"name": "tags", "type": {"type": "array", "items": "string"}},
+                    {"name": "attributes", "type": {"type": "map", "values": "int"}},
+                    {"name": "payload", "type": "bytes"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut record = Record::new(&schema.schema).unwrap();
+        record.put("severity", AvroValue::Enum(1, "WARN".to_string()));
+        record.put("body", "disk is full");
+        record.put("timestamp", AvroValue::TimestampMillis(1_660_000_000_000));
+        record.put(
+            "latency",
+            AvroValue::Union(1, Box::new(AvroValue::Double(1.5))),
+        );
+        record.put(
+            "tags",
+            AvroValue::Array(vec![
+                AvroValue::String("ssd".to_string()),
+                AvroValue::String("eu".to_string()),
+            ]),
+        );
+        record.put(
+            "attributes",
+            AvroValue::Map(
+                [("disk".to_string(), AvroValue::Int(3))]
+                    .into_iter()
+                    .collect(),
+            ),
+        );
+        record.put("payload", AvroValue::Bytes(b"abc".to_vec()));
+        let payload = apache_avro::to_avro_datum(&schema.schema, record).unwrap();
+        assert_eq!(
+            schema.decode(&payload).unwrap(),
+            json!({
+                "severity": "WARN",
+                "body": "disk is full",
+                "timestamp": 1_660_000_000_000i64,
+                "latency": 1.5,
+                "tags": ["ssd", "eu"],
+                "attributes": {"disk": 3},
+                "payload": "YWJj",
+            })
+        );
+        schema.decode(&payload[..payload.len() - 1]).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_invalid_avro_schema() {
+        AvroSchema::parse("{").unwrap_err();
+        AvroSchema::parse(r#"{"type": "record", "name": "Event"}"#).unwrap_err();
+        AvroSchema::parse(
+            r#"{"type": "record", "name": "Event", "fields": [{"name": "kind", "type": "Kind"}]}"#,
+        )
+        .unwrap_err();
+    }
+}
@@ -0,0 +1,525 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of Kafka message payloads serialized with a Confluent-compatible schema registry.
+//!
+//! Such payloads are framed as follows: a magic byte `0`, the ID of the schema as a 4-byte
+//! big-endian integer, and, for Protobuf only, the indexes of the message type within the schema.
+//! The datum follows.
+
+mod avro;
+mod protobuf;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use prost::Message;
+use prost_types::FileDescriptorProto;
+use quickwit_config::KafkaMessageFormat;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+use self::avro::AvroSchema;
+use self::protobuf::ProtobufSchema;
+
+const MAGIC_BYTE: u8 = 0;
+
+/// Maximum number of schemas making up a Protobuf schema, including the schemas it imports.
+const MAX_NUM_PROTOBUF_SCHEMAS: usize = 128;
+
+/// Error returned by [`MessageDecoder::decode`].
+#[derive(Debug, Error)]
+pub(crate) enum DecodeError {
+    /// The payload cannot be decoded: the message is invalid.
+    #[error("Invalid message payload: {0:#}")]
+    InvalidPayload(#[from] anyhow::Error),
+    /// The schema of the payload could not be fetched from the schema registry. Decoding the
+    /// payload may succeed once the registry is reachable again.
+    #[error("Schema registry is unavailable: {0:#}")]
+    SchemaRegistryUnavailable(anyhow::Error),
+}
+
+/// Decodes the payload of Kafka messages into JSON documents.
+pub(crate) enum MessageDecoder {
+    Json,
+    Avro(SchemaRegistryClient),
+    Protobuf(SchemaRegistryClient),
+}
+
+impl MessageDecoder {
+    pub fn new(message_format: &KafkaMessageFormat) -> anyhow::Result<Self> {
+        let message_decoder = match message_format {
+            KafkaMessageFormat::Json => MessageDecoder::Json,
+            KafkaMessageFormat::Avro {
+                schema_registry_url,
+            } => MessageDecoder::Avro(SchemaRegistryClient::try_new(schema_registry_url)?),
+            KafkaMessageFormat::Protobuf {
+                schema_registry_url,
+            } => MessageDecoder::Protobuf(SchemaRegistryClient::try_new(schema_registry_url)?),
+        };
+        Ok(message_decoder)
+    }
+
+    /// Decodes a message payload into a JSON document.
+    pub async fn decode(&mut self, payload: &[u8]) -> Result<String, DecodeError> {
+        let (schema_registry_client, schema_type) = match self {
+            MessageDecoder::Json => {
+                let doc =
+                    std::str::from_utf8(payload).context("Message payload is not valid UTF-8.")?;
+                return Ok(doc.to_string());
+            }
+            MessageDecoder::Avro(schema_registry_client) => {
+                (schema_registry_client, SchemaType::Avro)
+            }
+            MessageDecoder::Protobuf(schema_registry_client) => {
+                (schema_registry_client, SchemaType::Protobuf)
+            }
+        };
+        let (schema_id, mut datum) = parse_header(payload)?;
+        let schema = schema_registry_client
+            .fetch_schema(schema_id, schema_type)
+            .await?;
+        let doc_json = match &*schema {
+            Schema::Avro(avro_schema) => avro_schema.decode(datum)?,
+            Schema::Protobuf(protobuf_schema) => {
+                let message_indexes = parse_message_indexes(&mut datum)?;
+                let message_descriptor = protobuf_schema.message_descriptor(&message_indexes)?;
+                protobuf_schema.decode(message_descriptor, datum)?
+            }
+        };
+        Ok(doc_json.to_string())
+    }
+}
+
+/// Parses the magic byte and the schema ID heading the payload and returns the schema ID along
+/// with the remaining bytes.
+fn parse_header(payload: &[u8]) -> anyhow::Result<(u32, &[u8])> {
+    if payload.len() < 5 || payload[0] != MAGIC_BYTE {
+        bail!("Message payload is not framed with a schema registry header.");
+    }
+    let schema_id = u32::from_be_bytes(payload[1..5].try_into().expect("slice has 4 bytes"));
+    Ok((schema_id, &payload[5..]))
+}
+
+/// Parses the message indexes of a Protobuf payload: a zigzag encoded count followed by as many
+/// zigzag encoded indexes. A single `0` byte stands for `[0]`, i.e. the first message of the
+/// schema.
+fn parse_message_indexes(datum: &mut &[u8]) -> anyhow::Result<Vec<usize>> {
+    let read_zigzag = |datum: &mut &[u8]| -> anyhow::Result<i64> {
+        let varint = prost::encoding::decode_varint(datum)
+            .context("Failed to read Protobuf message index.")?;
+        Ok((varint >> 1) as i64 ^ -((varint & 1) as i64))
+    };
+    let num_indexes = read_zigzag(datum)?;
+    if num_indexes == 0 {
+        return Ok(vec![0]);
+    }
+    if !(0..=128).contains(&num_indexes) {
+        bail!("Invalid number of Protobuf message indexes `{num_indexes}`.");
+    }
+    (0..num_indexes)
+        .map(|_| {
+            let index = read_zigzag(datum)?;
+            usize::try_from(index)
+                .with_context(|| format!("Invalid Protobuf message index `{index}`."))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SchemaType {
+    Avro,
+    Protobuf,
+}
+
+enum Schema {
+    Avro(AvroSchema),
+    Protobuf(ProtobufSchema),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaResponse {
+    schema: String,
+    #[serde(default)]
+    schema_type: Option<String>,
+    #[serde(default)]
+    references: Vec<SchemaReference>,
+}
+
+impl SchemaResponse {
+    fn schema_type(&self) -> anyhow::Result<SchemaType> {
+        // The schema type is omitted for Avro schemas.
+        match self.schema_type.as_deref().unwrap_or("AVRO") {
+            "AVRO" => Ok(SchemaType::Avro),
+            "PROTOBUF" => Ok(SchemaType::Protobuf),
+            schema_type => bail!("Schema type `{schema_type}` is not supported."),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SchemaReference {
+    /// Name under which the referenced schema is imported.
+    name: String,
+    subject: String,
+    version: i32,
+}
+
+/// Fetches schemas by ID from a schema registry and caches them.
+pub(crate) struct SchemaRegistryClient {
+    http_client: reqwest::Client,
+    schema_registry_url: String,
+    schemas: HashMap<u32, Arc<Schema>>,
+}
+
+impl SchemaRegistryClient {
+    fn try_new(schema_registry_url: &str) -> anyhow::Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create schema registry HTTP client.")?;
+        Ok(SchemaRegistryClient {
+            http_client,
+            schema_registry_url: schema_registry_url.trim_end_matches('/').to_string(),
+            schemas: HashMap::new(),
+        })
+    }
+
+    async fn fetch_schema(
+        &mut self,
+        schema_id: u32,
+        expected_schema_type: SchemaType,
+    ) -> Result<Arc<Schema>, DecodeError> {
+        if let Some(schema) = self.schemas.get(&schema_id) {
+            return Ok(schema.clone());
+        }
+        // Protobuf schemas are requested as serialized file descriptors rather than `.proto`
+        // files.
+        let format_param = match expected_schema_type {
+            SchemaType::Avro => "",
+            SchemaType::Protobuf => "?format=serialized",
+        };
+        let path = format!("schemas/ids/{schema_id}{format_param}");
+        let response = self.get(&path).await?;
+        let schema_type = response.schema_type()?;
+        if schema_type != expected_schema_type {
+            return Err(anyhow!(
+                "Schema `{schema_id}` is a {schema_type:?} schema, expected a \
+                 {expected_schema_type:?} schema."
+            )
+            .into());
+        }
+        let schema = match schema_type {
+            SchemaType::Avro => Schema::Avro(AvroSchema::parse(&response.schema)?),
+            SchemaType::Protobuf => {
+                let root_name = format!("schema-{schema_id}.proto");
+                let file_descriptor_protos = self
+                    .fetch_file_descriptor_protos(root_name, response)
+                    .await?;
+                Schema::Protobuf(ProtobufSchema::from_file_descriptor_protos(
+                    file_descriptor_protos,
+                )?)
+            }
+        };
+        let schema = Arc::new(schema);
+        self.schemas.insert(schema_id, schema.clone());
+        Ok(schema)
+    }
+
+    /// Fetches the schemas referenced by a Protobuf schema, transitively, and returns their file
+    /// descriptors, each preceded by the file descriptors of the schemas it references.
+    async fn fetch_file_descriptor_protos(
+        &self,
+        name: String,
+        response: SchemaResponse,
+    ) -> Result<Vec<FileDescriptorProto>, DecodeError> {
+        let mut file_descriptor_protos = Vec::new();
+        let mut visited_names = HashSet::from([name.clone()]);
+        // Schemas whose references are being fetched, depth first.
+        let mut stack = vec![(name, response)];
+        loop {
+            let reference_opt = match stack.last_mut() {
+                Some((_, response)) => response.references.pop(),
+                None => break,
+            };
+            if let Some(reference) = reference_opt {
+                if !visited_names.insert(reference.name.clone()) {
+                    continue;
+                }
+                if visited_names.len() > MAX_NUM_PROTOBUF_SCHEMAS {
+                    return Err(anyhow!("Protobuf schema has too many references.").into());
+                }
+                let path = format!(
+                    "subjects/{}/versions/{}?format=serialized",
+                    reference.subject, reference.version
+                );
+                let reference_response = self.get(&path).await?;
+                stack.push((reference.name, reference_response));
+                continue;
+            }
+            let (name, response) = stack.pop().expect("The stack should not be empty.");
+            let file_descriptor_bytes = base64::decode(&response.schema)
+                .with_context(|| format!("Protobuf schema `{name}` is not base64 encoded."))?;
+            let mut file_descriptor_proto = FileDescriptorProto::decode(&file_descriptor_bytes[..])
+                .with_context(|| format!("Failed to decode Protobuf schema `{name}`."))?;
+            // Imports refer to the referenced schemas by their reference name.
+            file_descriptor_proto.name = Some(name);
+            file_descriptor_protos.push(file_descriptor_proto);
+        }
+        Ok(file_descriptor_protos)
+    }
+
+    /// Sends a GET request to the schema registry. Network errors, server errors, and
+    /// throttling are reported as [`DecodeError::SchemaRegistryUnavailable`].
+    async fn get(&self, path: &str) -> Result<SchemaResponse, DecodeError> {
+        let url = format!("{}/{path}", self.schema_registry_url);
+        let response = self.http_client.get(&url).send().await.map_err(|error| {
+            DecodeError::SchemaRegistryUnavailable(
+                anyhow!(error).context(format!("Failed to send request to `{url}`.")),
+            )
+        })?;
+        let status = response.status();
+        if status.is_server_error()
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS
+        {
+            return Err(DecodeError::SchemaRegistryUnavailable(anyhow!(
+                "Request to `{url}` failed with status `{status}`."
+            )));
+        }
+        if !status.is_success() {
+            return Err(anyhow!("Request to `{url}` failed with status `{status}`.").into());
+        }
+        response.json::<SchemaResponse>().await.map_err(|error| {
+            DecodeError::SchemaRegistryUnavailable(
+                anyhow!(error).context(format!("Failed to parse response from `{url}`.")),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use apache_avro::types::Record;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{DescriptorProto, FieldDescriptorProto};
+    use serde_json::json;
+    use warp::Filter;
+
+    use super::*;
+    use crate::source::schema_registry::protobuf::tests::log_event_file_descriptor_proto;
+
+    const AVRO_SCHEMA: &str =
+        r#"{"type": "record", "name": "Event", "fields": [{"name": "body", "type": "string"}]}"#;
+
+    /// Returns the file descriptor of a schema importing `log_event.proto`:
+    ///
+    /// ```protobuf
+    /// syntax = "proto3";
+    /// import "log_event.proto";
+    ///
+    /// message Envelope {
+    ///     quickwit.logs.LogEvent event = 1;
+    /// }
+    /// ```
+    fn envelope_file_descriptor_proto() -> FileDescriptorProto {
+        let envelope = DescriptorProto {
+            name: Some("Envelope".to_string()),
+            field: vec![FieldDescriptorProto {
+                name: Some("event".to_string()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::Message as i32),
+                type_name: Some(".quickwit.logs.LogEvent".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        FileDescriptorProto {
+            name: Some("envelope.proto".to_string()),
+            syntax: Some("proto3".to_string()),
+            dependency: vec!["log_event.proto".to_string()],
+            message_type: vec![envelope],
+            ..Default::default()
+        }
+    }
+
+    fn start_schema_registry(num_requests: Arc<AtomicUsize>) -> String {
+        let log_event_schema = base64::encode(log_event_file_descriptor_proto().encode_to_vec());
+        let envelope_schema = base64::encode(envelope_file_descriptor_proto().encode_to_vec());
+        let log_event_subject_schema = log_event_schema.clone();
+        let schemas = warp::path!("schemas" / "ids" / u32).map(move |schema_id| {
+            num_requests.fetch_add(1, Ordering::SeqCst);
+            let response = match schema_id {
+                1 => json!({ "schema": AVRO_SCHEMA }),
+                2 => json!({
+                    "schema": log_event_schema,
+                    "schemaType": "PROTOBUF"
+                }),
+                4 => json!({
+                    "schema": envelope_schema,
+                    "schemaType": "PROTOBUF",
+                    "references": [
+                        {"name": "log_event.proto", "subject": "log-event", "version": 1}
+                    ]
+                }),
+                5 => {
+                    return warp::reply::with_status(
+                        warp::reply::json(&json!({"error_code": 50001})),
+                        warp::http::StatusCode::SERVICE_UNAVAILABLE,
+                    )
+                }
+                _ => {
+                    return warp::reply::with_status(
+                        warp::reply::json(&json!({"error_code": 40403})),
+                        warp::http::StatusCode::NOT_FOUND,
+                    )
+                }
+            };
+            warp::reply::with_status(warp::reply::json(&response), warp::http::StatusCode::OK)
+        });
+        let subjects = warp::path!("subjects" / "log-event" / "versions" / "1").map(move || {
+            warp::reply::json(&json!({
+                "schema": log_event_subject_schema,
+                "schemaType": "PROTOBUF"
+            }))
+        });
+        let (socket_addr, server) =
+            warp::serve(schemas.or(subjects)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{socket_addr}/")
+    }
+
+    #[test]
+    fn test_parse_header() {
+        parse_header(b"{}").unwrap_err();
+        parse_header(&[1, 0, 0, 0, 1, 42]).unwrap_err();
+        assert_eq!(
+            parse_header(&[0, 0, 0, 1, 2, 42]).unwrap(),
+            (258, [42].as_slice())
+        );
+    }
+
+    #[test]
+    fn test_parse_message_indexes() {
+        let mut datum = [0, 42].as_slice();
+        assert_eq!(parse_message_indexes(&mut datum).unwrap(), vec![0]);
+        assert_eq!(datum, &[42]);
+
+        let mut datum = [4, 2, 0, 42].as_slice();
+        assert_eq!(parse_message_indexes(&mut datum).unwrap(), vec![1, 0]);
+        assert_eq!(datum, &[42]);
+
+        parse_message_indexes(&mut [1, 1].as_slice()).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_message_decoder_json() {
+        let mut message_decoder = MessageDecoder::new(&KafkaMessageFormat::Json).unwrap();
+        assert_eq!(
+            message_decoder.decode(br#"{"body": "foo"}"#).await.unwrap(),
+            r#"{"body": "foo"}"#
+        );
+        assert!(matches!(
+            message_decoder.decode(&[0xFF]).await.unwrap_err(),
+            DecodeError::InvalidPayload(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_message_decoder_with_schema_registry() {
+        let num_requests = Arc::new(AtomicUsize::new(0));
+        let schema_registry_url = start_schema_registry(num_requests.clone());
+
+        let mut avro_decoder = MessageDecoder::new(&KafkaMessageFormat::Avro {
+            schema_registry_url: schema_registry_url.clone(),
+        })
+        .unwrap();
+        let avro_schema = apache_avro::Schema::parse_str(AVRO_SCHEMA).unwrap();
+        let mut record = Record::new(&avro_schema).unwrap();
+        record.put("body", "foo");
+        let mut avro_payload = vec![0, 0, 0, 0, 1];
+        avro_payload.extend(apache_avro::to_avro_datum(&avro_schema, record).unwrap());
+        for _ in 0..2 {
+            assert_eq!(
+                avro_decoder.decode(&avro_payload).await.unwrap(),
+                r#"{"body":"foo"}"#
+            );
+        }
+        // The schema is fetched once.
+        assert_eq!(num_requests.load(Ordering::SeqCst), 1);
+
+        // An unknown schema makes the message invalid.
+        let unknown_schema_payload = [0, 0, 0, 0, 3, 0];
+        assert!(matches!(
+            avro_decoder
+                .decode(&unknown_schema_payload)
+                .await
+                .unwrap_err(),
+            DecodeError::InvalidPayload(_)
+        ));
+        // A failing schema registry does not.
+        let unavailable_schema_payload = [0, 0, 0, 0, 5, 0];
+        assert!(matches!(
+            avro_decoder
+                .decode(&unavailable_schema_payload)
+                .await
+                .unwrap_err(),
+            DecodeError::SchemaRegistryUnavailable(_)
+        ));
+
+        let mut protobuf_decoder = MessageDecoder::new(&KafkaMessageFormat::Protobuf {
+            schema_registry_url: schema_registry_url.clone(),
+        })
+        .unwrap();
+        // Message indexes `[0]` followed by `LogEvent { body: "bar" }`.
+        let protobuf_payload = [0, 0, 0, 0, 2, 2, 0, 0x0A, 3, b'b', b'a', b'r'];
+        assert_eq!(
+            protobuf_decoder.decode(&protobuf_payload).await.unwrap(),
+            r#"{"body":"bar"}"#
+        );
+        let error = protobuf_decoder.decode(&avro_payload).await.unwrap_err();
+        assert!(error.to_string().contains("expected a Protobuf schema"));
+
+        // `Envelope { event: LogEvent { body: "bar" } }`, the schema importing `log_event.proto`.
+        let protobuf_payload = [0, 0, 0, 0, 4, 0, 0x0A, 5, 0x0A, 3, b'b', b'a', b'r'];
+        assert_eq!(
+            protobuf_decoder.decode(&protobuf_payload).await.unwrap(),
+            r#"{"event":{"body":"bar"}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schema_registry_unreachable() {
+        let mut avro_decoder = MessageDecoder::new(&KafkaMessageFormat::Avro {
+            schema_registry_url: "http://127.0.0.1:1".to_string(),
+        })
+        .unwrap();
+        assert!(matches!(
+            avro_decoder.decode(&[0, 0, 0, 0, 1, 0]).await.unwrap_err(),
+            DecodeError::SchemaRegistryUnavailable(_)
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of Protobuf binary encoded messages into JSON documents.
+//!
+//! The schema registry serves Protobuf schemas as serialized `FileDescriptorProto`s. They are
+//! loaded, along with the schemas they import, into a `prost-reflect` descriptor pool.
+
+use anyhow::Context;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FileDescriptor, MessageDescriptor, SerializeOptions,
+};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use serde_json::Value as JsonValue;
+
+/// Parsed Protobuf schema.
+#[derive(Debug, Clone)]
+pub(crate) struct ProtobufSchema {
+    file_descriptor: FileDescriptor,
+}
+
+impl ProtobufSchema {
+    /// Builds the schema from its file descriptor, preceded by the file descriptors of the schemas
+    /// it imports.
+    pub fn from_file_descriptor_protos(
+        file_descriptor_protos: Vec<FileDescriptorProto>,
+    ) -> anyhow::Result<Self> {
+        let file_name = file_descriptor_protos
+            .last()
+            .context("Protobuf schema is empty.")?
+            .name()
+            .to_string();
+        let descriptor_pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+            file: file_descriptor_protos,
+        })
+        .context("Failed to parse Protobuf schema.")?;
+        let file_descriptor = descriptor_pool
+            .files()
+            .find(|file_descriptor| file_descriptor.name() == file_name)
+            .with_context(|| format!("Protobuf schema `{file_name}` is missing."))?;
+        Ok(ProtobufSchema { file_descriptor })
+    }
+
+    /// Returns the descriptor of the message designated by `message_indexes`: the index of a
+    /// top-level message of the schema, followed by the indexes of nested messages.
+    pub fn message_descriptor(
+        &self,
+        message_indexes: &[usize],
+    ) -> anyhow::Result<MessageDescriptor> {
+        let (top_level_index, nested_indexes) = message_indexes.split_first().unwrap_or((&0, &[]));
+        let mut message_descriptor = self
+            .file_descriptor
+            .messages()
+            .nth(*top_level_index)
+            .with_context(|| format!("Protobuf schema has no message `{message_indexes:?}`."))?;
+        for nested_index in nested_indexes {
+            message_descriptor = message_descriptor
+                .child_messages()
+                .nth(*nested_index)
+                .with_context(|| {
+                    format!("Protobuf schema has no message `{message_indexes:?}`.")
+                })?;
+        }
+        Ok(message_descriptor)
+    }
+
+    /// Decodes a message into a JSON value. Fields are named after their declaration in the
+    /// schema, and fields holding their default value are omitted.
+    pub fn decode(
+        &self,
+        message_descriptor: MessageDescriptor,
+        payload: &[u8],
+    ) -> anyhow::Result<JsonValue> {
+        let message_name = message_descriptor.full_name().to_string();
+        let message = DynamicMessage::decode(message_descriptor, payload)
+            .with_context(|| format!("Failed to decode Protobuf message `{message_name}`."))?;
+        let serialize_options = SerializeOptions::new()
+            .use_proto_field_name(true)
+            .stringify_64_bit_integers(false);
+        let json_value = message
+            .serialize_with_options(serde_json::value::Serializer, &serialize_options)
+            .with_context(|| format!("Failed to convert Protobuf message `{message_name}`."))?;
+        Ok(json_value)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use prost::Message;
+    use prost_reflect::Value;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{DescriptorProto, FieldDescriptorProto};
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(name: &str, number: i32, label: Label, field_type: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(field_type as i32),
+            ..Default::default()
+        }
+    }
+
+    fn message_field(name: &str, number: i32, type_name: &str) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            type_name: Some(type_name.to_string()),
+            ..field(name, number, Label::Optional, Type::Message)
+        }
+    }
+
+    /// Returns the file descriptor of the schema:
+    ///
+    /// ```protobuf
+    /// syntax = "proto3";
+    /// package quickwit.logs;
+    ///
+    /// message LogEvent {
+    ///     message Resource {
+    ///         string service = 1;
+    ///     }
+    ///     string body = 1;
+    ///     repeated uint32 codes = 2;
+    ///     Resource resource = 3;
+    ///     bytes payload = 4;
+    /// }
+    ///
+    /// message Heartbeat {
+    ///     fixed64 sequence = 1;
+    ///     bool up = 2;
+    /// }
+    /// ```
+    pub(crate) fn log_event_file_descriptor_proto() -> FileDescriptorProto {
+        let resource = DescriptorProto {
+            name: Some("Resource".to_string()),
+            field: vec![field("service", 1, Label::Optional, Type::String)],
+            ..Default::default()
+        };
+        let log_event = DescriptorProto {
+            name: Some("LogEvent".to_string()),
+            field: vec![
+                field("body", 1, Label::Optional, Type::String),
+                field("codes", 2, Label::Repeated, Type::Uint32),
+                message_field("resource", 3, ".quickwit.logs.LogEvent.Resource"),
+                field("payload", 4, Label::Optional, Type::Bytes),
+            ],
+            nested_type: vec![resource],
+            ..Default::default()
+        };
+        let heartbeat = DescriptorProto {
+            name: Some("Heartbeat".to_string()),
+            field: vec![
+                field("sequence", 1, Label::Optional, Type::Fixed64),
+                field("up", 2, Label::Optional, Type::Bool),
+            ],
+            ..Default::default()
+        };
+        FileDescriptorProto {
+            name: Some("log_event.proto".to_string()),
+            package: Some("quickwit.logs".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![log_event, heartbeat],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_protobuf_message_descriptor() {
+        let schema =
+            ProtobufSchema::from_file_descriptor_protos(vec![log_event_file_descriptor_proto()])
+                .unwrap();
+        assert_eq!(
+            schema.message_descriptor(&[]).unwrap().full_name(),
+            "quickwit.logs.LogEvent"
+        );
+        assert_eq!(
+            schema.message_descriptor(&[1]).unwrap().full_name(),
+            "quickwit.logs.Heartbeat"
+        );
+        assert_eq!(
+            schema.message_descriptor(&[0, 0]).unwrap().full_name(),
+            "quickwit.logs.LogEvent.Resource"
+        );
+        schema.message_descriptor(&[2]).unwrap_err();
+        schema.message_descriptor(&[1, 0]).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_invalid_protobuf_schema() {
+        ProtobufSchema::from_file_descriptor_protos(Vec::new()).unwrap_err();
+        let mut file_descriptor_proto = log_event_file_descriptor_proto();
+        file_descriptor_proto.message_type[0].field[2].type_name = Some(".Kind".to_string());
+        ProtobufSchema::from_file_descriptor_protos(vec![file_descriptor_proto]).unwrap_err();
+    }
+
+    #[test]
+    fn test_decode_protobuf_message() {
+        let schema =
+            ProtobufSchema::from_file_descriptor_protos(vec![log_event_file_descriptor_proto()])
+                .unwrap();
+        let log_event_descriptor = schema.message_descriptor(&[0]).unwrap();
+        let resource_descriptor = schema.message_descriptor(&[0, 0]).unwrap();
+
+        let mut resource = DynamicMessage::new(resource_descriptor);
+        resource.set_field_by_name("service", Value::String("storage".to_string()));
+        let mut log_event = DynamicMessage::new(log_event_descriptor.clone());
+        log_event.set_field_by_name("body", Value::String("disk is full".to_string()));
+        log_event.set_field_by_name("codes", Value::List(vec![Value::U32(404), Value::U32(500)]));
+        log_event.set_field_by_name("resource", Value::Message(resource));
+        log_event.set_field_by_name("payload", Value::Bytes(b"abc".to_vec().into()));
+        let payload = log_event.encode_to_vec();
+
+        assert_eq!(
+            schema
+                .decode(log_event_descriptor.clone(), &payload)
+                .unwrap(),
+            json!({
+                "body": "disk is full",
+                "codes": [404, 500],
+                "resource": {"service": "storage"},
+                "payload": "YWJj",
+            })
+        );
+        schema
+            .decode(log_event_descriptor, &payload[..payload.len() - 1])
+            .unwrap_err();
+
+        let heartbeat_descriptor = schema.message_descriptor(&[1]).unwrap();
+        let mut heartbeat = DynamicMessage::new(heartbeat_descriptor.clone());
+        heartbeat.set_field_by_name("sequence", Value::U64(7));
+        heartbeat.set_field_by_name("up", Value::Bool(true));
+        assert_eq!(
+            schema
+                .decode(heartbeat_descriptor, &heartbeat.encode_to_vec())
+                .unwrap(),
+            json!({"sequence": 7, "up": true})
+        );
+    }
+}
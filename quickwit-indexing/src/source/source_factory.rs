@@ -25,6 +25,7 @@ use quickwit_metastore::checkpoint::SourceCheckpoint;
 use thiserror::Error;
 
 use super::Source;
+use crate::models::PartitionAssignment;
 use crate::source::SourceConfig;
 
 #[async_trait]
@@ -34,6 +35,7 @@ pub trait SourceFactory: 'static + Send + Sync {
         source_id: String,
         params: serde_json::Value,
         checkpoint: SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Box<dyn Source>>;
 }
 
@@ -45,6 +47,7 @@ pub trait TypedSourceFactory: Send + Sync + 'static {
         source_id: String,
         params: Self::Params,
         checkpoint: quickwit_metastore::checkpoint::SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Self::Source>;
 }
 
@@ -55,9 +58,12 @@ impl<T: TypedSourceFactory> SourceFactory for T {
         source_id: String,
         params: serde_json::Value,
         checkpoint: quickwit_metastore::checkpoint::SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Box<dyn Source>> {
         let typed_params: T::Params = serde_json::from_value(params)?;
-        let file_source = Self::typed_create_source(source_id, typed_params, checkpoint).await?;
+        let file_source =
+            Self::typed_create_source(source_id, typed_params, checkpoint, partition_assignment)
+                .await?;
         Ok(Box::new(file_source))
     }
 }
@@ -96,6 +102,7 @@ impl SourceLoader {
         &self,
         source_config: SourceConfig,
         checkpoint: SourceCheckpoint,
+        partition_assignment: PartitionAssignment,
     ) -> Result<Box<dyn Source>, SourceLoaderError> {
         let source_factory = self
             .type_to_factory
@@ -109,6 +116,7 @@ impl SourceLoader {
                 source_config.source_id.clone(),
                 source_config.params(),
                 checkpoint,
+                partition_assignment,
             )
             .await
             .map_err(|error| SourceLoaderError::FailedToCreateSource {
@@ -135,7 +143,11 @@ mod tests {
             source_params: SourceParams::void(),
         };
         source_loader
-            .load_source(source_config, SourceCheckpoint::default())
+            .load_source(
+                source_config,
+                SourceCheckpoint::default(),
+                PartitionAssignment::default(),
+            )
             .await?;
         Ok(())
     }
@@ -0,0 +1,584 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Reading of the lines of text files compressed with gzip or zstd or not compressed at all,
+//! shared by the file source and the object storage source.
+
+use std::collections::VecDeque;
+use std::io::{self, SeekFrom};
+use std::mem;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+
+use anyhow::{bail, Context};
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use quickwit_config::Compression;
+use quickwit_doc_mapper::{InputFormat, TextRecordConverter};
+use quickwit_metastore::checkpoint::Position;
+use tokio::fs::File;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, BufReader, ReadBuf,
+};
+
+/// Suffix appended to the position of a file once it has been read entirely.
+const COMPLETE_POSITION_SUFFIX: &str = ":eof";
+
+/// Separates the offset of a position from the gzip member or zstd frame decompression restarts
+/// from. It sorts before `:`, so that a position sorts before the complete position with the same
+/// offset.
+const MEMBER_SEPARATOR: char = '+';
+
+/// Position in a text file, optionally compressed.
+///
+/// Positions are serialized as the offset padded with zeros, followed, for a compressed file made
+/// of several gzip members or zstd frames, by the offsets of the member containing the next line
+/// (`{offset}+{member_compressed_offset}+{member_offset}`), or suffixed with `:eof` once the file
+/// has been read entirely. Serialized positions sort like the offsets they refer to.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub(crate) struct TextFilePosition {
+    /// Offset in the decompressed content of the next line to read.
+    pub offset: u64,
+    /// Offset in the compressed content of the gzip member or zstd frame containing the next line
+    /// to read. Always 0 for an uncompressed file.
+    pub member_compressed_offset: u64,
+    /// Offset in the decompressed content of the start of that member or frame.
+    pub member_offset: u64,
+    pub is_complete: bool,
+}
+
+impl TextFilePosition {
+    pub fn parse(position: &Position) -> anyhow::Result<TextFilePosition> {
+        let position_str = match position {
+            Position::Beginning => return Ok(TextFilePosition::default()),
+            Position::Offset(position_str) => position_str.as_str(),
+        };
+        let parse_u64 = |offset_str: &str| {
+            offset_str
+                .parse::<u64>()
+                .with_context(|| format!("Failed to parse position `{}`.", position_str))
+        };
+        if let Some(offset_str) = position_str.strip_suffix(COMPLETE_POSITION_SUFFIX) {
+            return Ok(TextFilePosition {
+                offset: parse_u64(offset_str)?,
+                is_complete: true,
+                ..Default::default()
+            });
+        }
+        let mut offset_strs = position_str.split(MEMBER_SEPARATOR);
+        let offset = parse_u64(offset_strs.next().unwrap_or_default())?;
+        let (member_compressed_offset, member_offset) =
+            match (offset_strs.next(), offset_strs.next()) {
+                (None, None) => (0, 0),
+                (Some(member_compressed_offset_str), Some(member_offset_str)) => (
+                    parse_u64(member_compressed_offset_str)?,
+                    parse_u64(member_offset_str)?,
+                ),
+                _ => bail!("Failed to parse position `{}`.", position_str),
+            };
+        if offset_strs.next().is_some() || member_offset > offset {
+            bail!("Failed to parse position `{}`.", position_str);
+        }
+        Ok(TextFilePosition {
+            offset,
+            member_compressed_offset,
+            member_offset,
+            is_complete: false,
+        })
+    }
+
+    pub fn to_position(self) -> Position {
+        if self.is_complete {
+            Position::from(format!("{:0>20}{}", self.offset, COMPLETE_POSITION_SUFFIX))
+        } else if self.member_compressed_offset == 0 && self.member_offset == 0 {
+            Position::from(self.offset)
+        } else {
+            Position::from(format!(
+                "{:0>20}{sep}{:0>20}{sep}{:0>20}",
+                self.offset,
+                self.member_compressed_offset,
+                self.member_offset,
+                sep = MEMBER_SEPARATOR
+            ))
+        }
+    }
+}
+
+fn is_compressed(compression: Compression) -> bool {
+    matches!(compression, Compression::Gzip | Compression::Zstd)
+}
+
+/// Reads the lines of a text file, decompressing it on the fly, and keeps track of the position of
+/// the next line.
+///
+/// Compressed files are decompressed one gzip member or zstd frame at a time, so that reading can
+/// resume from the member containing a position rather than from the start of the file. A file
+/// made of a single member is still decompressed again from its start up to the position.
+pub(crate) struct TextFileReader {
+    reader: BufReader<MemberDecoder>,
+    position: TextFilePosition,
+}
+
+impl TextFileReader {
+    /// Opens the file at `filepath` at `position`. Returns `None` if the decompressed content of
+    /// the file is shorter than the offset of the position.
+    pub async fn open(
+        filepath: &Path,
+        compression: Compression,
+        position: TextFilePosition,
+    ) -> anyhow::Result<Option<TextFileReader>> {
+        let mut file = File::open(filepath)
+            .await
+            .with_context(|| format!("Failed to open source file `{}`.", filepath.display()))?;
+        if !is_compressed(compression) {
+            file.seek(SeekFrom::Start(position.offset)).await?;
+            let mut reader = TextFileReader::from_reader(compression, Box::new(file));
+            reader.position.offset = position.offset;
+            return Ok(Some(reader));
+        }
+        file.seek(SeekFrom::Start(position.member_compressed_offset))
+            .await?;
+        let mut reader = TextFileReader::from_reader(compression, Box::new(file));
+        reader.position.member_compressed_offset = position.member_compressed_offset;
+        reader.position.member_offset = position.member_offset;
+        reader.position.offset = position.member_offset;
+        let decoder = reader.reader.get_mut();
+        decoder.reader_num_bytes_consumed_start = position.member_compressed_offset;
+        decoder.offset = position.member_offset;
+
+        let num_bytes_to_skip = position.offset - position.member_offset;
+        let num_bytes_skipped = tokio::io::copy(
+            &mut (&mut reader.reader).take(num_bytes_to_skip),
+            &mut tokio::io::sink(),
+        )
+        .await
+        .with_context(|| format!("Failed to decompress source file `{}`.", filepath.display()))?;
+        if num_bytes_skipped < num_bytes_to_skip {
+            return Ok(None);
+        }
+        reader.advance(num_bytes_skipped);
+        Ok(Some(reader))
+    }
+
+    /// Reads the lines of `reader`, e.g. stdin, from its start.
+    pub fn from_reader(
+        compression: Compression,
+        reader: Box<dyn AsyncRead + Send + Sync + Unpin>,
+    ) -> TextFileReader {
+        let counting_reader = CountingBufReader {
+            inner: BufReader::new(reader),
+            num_bytes_consumed: 0,
+        };
+        let decoder = MemberDecoder {
+            compression,
+            state: DecoderState::Decoding(Decoder::new(compression, counting_reader)),
+            reader_num_bytes_consumed_start: 0,
+            offset: 0,
+            member_starts: VecDeque::new(),
+        };
+        TextFileReader {
+            reader: BufReader::new(decoder),
+            position: TextFilePosition::default(),
+        }
+    }
+
+    /// Position of the next line to read.
+    pub fn position(&self) -> TextFilePosition {
+        self.position
+    }
+
+    /// Reads the next line, including its newline character, into `line` and returns the number
+    /// of bytes read, 0 at the end of the file.
+    pub async fn read_line(&mut self, line: &mut String) -> io::Result<usize> {
+        let num_bytes = self.reader.read_line(line).await?;
+        self.advance(num_bytes as u64);
+        Ok(num_bytes)
+    }
+
+    fn advance(&mut self, num_bytes: u64) {
+        self.position.offset += num_bytes;
+        // The decoder reads ahead of the lines returned, so it may have entered members that start
+        // after the next line.
+        let member_starts = &mut self.reader.get_mut().member_starts;
+        while let Some(&(member_compressed_offset, member_offset)) = member_starts.front() {
+            if member_offset > self.position.offset {
+                break;
+            }
+            self.position.member_compressed_offset = member_compressed_offset;
+            self.position.member_offset = member_offset;
+            member_starts.pop_front();
+        }
+    }
+}
+
+/// Creates the converter of the lines of the file at `filepath` read from `offset`. The header row
+/// of a CSV file read from the middle is read again, since it is needed to convert the next rows.
+/// It may span several lines if its quoted values contain newlines.
+pub(crate) async fn new_record_converter(
+    filepath: &Path,
+    compression: Compression,
+    input_format: InputFormat,
+    offset: u64,
+) -> anyhow::Result<TextRecordConverter> {
+    let mut converter = TextRecordConverter::new(input_format);
+    if input_format == InputFormat::Csv && offset > 0 {
+        if let Some(mut reader) =
+            TextFileReader::open(filepath, compression, TextFilePosition::default()).await?
+        {
+            loop {
+                let mut header_line = String::new();
+                if reader.read_line(&mut header_line).await? == 0 {
+                    break;
+                }
+                converter.convert_line(header_line);
+                if !converter.is_within_record() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(converter)
+}
+
+/// Wraps `reader` into a reader decompressing its content on the fly.
+pub(crate) fn decompressing_reader<R>(
+    compression: Compression,
+    reader: R,
+) -> Box<dyn AsyncRead + Send + Sync + Unpin>
+where
+    R: AsyncRead + Send + Sync + Unpin + 'static,
+{
+    match compression {
+        Compression::Auto | Compression::None => Box::new(reader),
+        Compression::Gzip => {
+            let mut decoder = GzipDecoder::new(BufReader::new(reader));
+            decoder.multiple_members(true);
+            Box::new(decoder)
+        }
+        Compression::Zstd => {
+            let mut decoder = ZstdDecoder::new(BufReader::new(reader));
+            decoder.multiple_members(true);
+            Box::new(decoder)
+        }
+    }
+}
+
+/// Buffered reader counting the bytes consumed from it.
+struct CountingBufReader {
+    inner: BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>,
+    num_bytes_consumed: u64,
+}
+
+impl AsyncRead for CountingBufReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let num_bytes_before = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        this.num_bytes_consumed += (buf.filled().len() - num_bytes_before) as u64;
+        poll
+    }
+}
+
+impl AsyncBufRead for CountingBufReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.num_bytes_consumed += amt as u64;
+        Pin::new(&mut this.inner).consume(amt);
+    }
+}
+
+/// Decoder of a single gzip member or zstd frame, which leaves the bytes following it unread.
+enum Decoder {
+    None(CountingBufReader),
+    Gzip(GzipDecoder<CountingBufReader>),
+    Zstd(ZstdDecoder<CountingBufReader>),
+}
+
+impl Decoder {
+    fn new(compression: Compression, reader: CountingBufReader) -> Decoder {
+        match compression {
+            Compression::Auto | Compression::None => Decoder::None(reader),
+            Compression::Gzip => Decoder::Gzip(GzipDecoder::new(reader)),
+            Compression::Zstd => Decoder::Zstd(ZstdDecoder::new(reader)),
+        }
+    }
+
+    fn into_inner(self) -> CountingBufReader {
+        match self {
+            Decoder::None(reader) => reader,
+            Decoder::Gzip(decoder) => decoder.into_inner(),
+            Decoder::Zstd(decoder) => decoder.into_inner(),
+        }
+    }
+}
+
+impl AsyncRead for Decoder {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Decoder::None(reader) => Pin::new(reader).poll_read(cx, buf),
+            Decoder::Gzip(decoder) => Pin::new(decoder).poll_read(cx, buf),
+            Decoder::Zstd(decoder) => Pin::new(decoder).poll_read(cx, buf),
+        }
+    }
+}
+
+enum DecoderState {
+    Decoding(Decoder),
+    /// The last member has been decompressed entirely; the next one, if any, starts at the
+    /// current position of the reader.
+    BetweenMembers(CountingBufReader),
+    Done,
+}
+
+/// Reader decompressing the members of a file one after the other, recording where each of them
+/// starts.
+struct MemberDecoder {
+    compression: Compression,
+    state: DecoderState,
+    /// Offset in the compressed content at which the reader was positioned when created.
+    reader_num_bytes_consumed_start: u64,
+    /// Offset in the decompressed content of the next byte to read.
+    offset: u64,
+    /// Offsets in the compressed and decompressed content of the members entered but not yet
+    /// reached by the lines read.
+    member_starts: VecDeque<(u64, u64)>,
+}
+
+impl AsyncRead for MemberDecoder {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                DecoderState::Decoding(decoder) => {
+                    if buf.remaining() == 0 {
+                        return Poll::Ready(Ok(()));
+                    }
+                    let num_bytes_before = buf.filled().len();
+                    match Pin::new(decoder).poll_read(cx, buf) {
+                        Poll::Ready(Ok(())) => {}
+                        poll => return poll,
+                    }
+                    let num_bytes_read = buf.filled().len() - num_bytes_before;
+                    if num_bytes_read > 0 {
+                        this.offset += num_bytes_read as u64;
+                        return Poll::Ready(Ok(()));
+                    }
+                    if let DecoderState::Decoding(decoder) =
+                        mem::replace(&mut this.state, DecoderState::Done)
+                    {
+                        this.state = DecoderState::BetweenMembers(decoder.into_inner());
+                    }
+                }
+                DecoderState::BetweenMembers(reader) => {
+                    let is_eof = match Pin::new(reader).poll_fill_buf(cx) {
+                        Poll::Ready(Ok(remaining_bytes)) => remaining_bytes.is_empty(),
+                        Poll::Ready(Err(io_error)) => return Poll::Ready(Err(io_error)),
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if let DecoderState::BetweenMembers(reader) =
+                        mem::replace(&mut this.state, DecoderState::Done)
+                    {
+                        if !is_eof && is_compressed(this.compression) {
+                            let member_compressed_offset =
+                                this.reader_num_bytes_consumed_start + reader.num_bytes_consumed;
+                            this.member_starts
+                                .push_back((member_compressed_offset, this.offset));
+                            this.state =
+                                DecoderState::Decoding(Decoder::new(this.compression, reader));
+                        }
+                    }
+                }
+                DecoderState::Done => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_decompressing_reader() {
+        let content = b"{\"body\": \"a\"}\n";
+        for (compression, compressed_content) in [
+            (Compression::None, content.to_vec()),
+            (Compression::Gzip, gzip(content)),
+            (
+                Compression::Zstd,
+                zstd::encode_all(&content[..], 0).unwrap(),
+            ),
+        ] {
+            let mut decompressed_content = Vec::new();
+            decompressing_reader(compression, io::Cursor::new(compressed_content))
+                .read_to_end(&mut decompressed_content)
+                .await
+                .unwrap();
+            assert_eq!(decompressed_content, content);
+        }
+        let mut decompressed_content = Vec::new();
+        decompressing_reader(Compression::Gzip, io::Cursor::new(content.to_vec()))
+            .read_to_end(&mut decompressed_content)
+            .await
+            .unwrap_err();
+    }
+
+    async fn read_lines(reader: &mut TextFileReader) -> Vec<(String, TextFilePosition)> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                return lines;
+            }
+            lines.push((line, reader.position()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_text_file_reader_resumes_from_member() -> anyhow::Result<()> {
+        // The second line spans the two members.
+        let first_member = gzip(b"a\nb");
+        let second_member = gzip(b"b\nc\n");
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        temp_file.write_all(&first_member)?;
+        temp_file.write_all(&second_member)?;
+        temp_file.flush()?;
+
+        let mut reader =
+            TextFileReader::open(temp_file.path(), Compression::Gzip, Default::default())
+                .await?
+                .unwrap();
+        let lines = read_lines(&mut reader).await;
+        let second_member_start = (first_member.len() as u64, 3);
+        assert_eq!(
+            lines
+                .iter()
+                .map(|(line, position)| (
+                    line.as_str(),
+                    position.offset,
+                    (position.member_compressed_offset, position.member_offset)
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a\n", 2, (0, 0)),
+                ("bb\n", 5, second_member_start),
+                ("c\n", 7, second_member_start),
+            ]
+        );
+        let position = lines[1].1;
+        assert_eq!(
+            position.to_position(),
+            Position::from(format!(
+                "00000000000000000005+{:0>20}+00000000000000000003",
+                first_member.len()
+            ))
+        );
+        assert_eq!(TextFilePosition::parse(&position.to_position())?, position);
+
+        // Decompression resumes from the second member.
+        let mut second_member_file = tempfile::NamedTempFile::new()?;
+        second_member_file.write_all(&vec![0u8; first_member.len()])?;
+        second_member_file.write_all(&second_member)?;
+        second_member_file.flush()?;
+        let mut reader =
+            TextFileReader::open(second_member_file.path(), Compression::Gzip, position)
+                .await?
+                .unwrap();
+        let lines = read_lines(&mut reader).await;
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].0, "c\n");
+        assert_eq!(lines[0].1.offset, 7);
+
+        let past_end_position = TextFilePosition {
+            offset: 10,
+            ..position
+        };
+        assert!(
+            TextFileReader::open(temp_file.path(), Compression::Gzip, past_end_position)
+                .await?
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_text_file_position() -> anyhow::Result<()> {
+        assert_eq!(
+            TextFilePosition::parse(&Position::Beginning)?,
+            TextFilePosition::default()
+        );
+        let complete_position = TextFilePosition {
+            offset: 14,
+            is_complete: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            complete_position.to_position(),
+            Position::from("00000000000000000014:eof")
+        );
+        assert_eq!(
+            TextFilePosition::parse(&complete_position.to_position())?,
+            complete_position
+        );
+        assert_eq!(
+            TextFilePosition::parse(&Position::from(14u64))?,
+            TextFilePosition {
+                offset: 14,
+                ..Default::default()
+            }
+        );
+        assert!(TextFilePosition::parse(&Position::from("14+1")).is_err());
+        assert!(TextFilePosition::parse(&Position::from("14+1+15")).is_err());
+
+        let member_position = TextFilePosition {
+            offset: 14,
+            member_compressed_offset: 100,
+            member_offset: 10,
+            is_complete: false,
+        };
+        assert!(Position::from(14u64) < member_position.to_position());
+        assert!(member_position.to_position() < complete_position.to_position());
+        assert!(member_position.to_position() < Position::from(15u64));
+        Ok(())
+    }
+}
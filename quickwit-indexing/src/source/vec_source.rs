@@ -27,7 +27,7 @@ use quickwit_metastore::checkpoint::{CheckpointDelta, PartitionId, Position, Sou
 use tracing::info;
 
 use crate::actors::Indexer;
-use crate::models::RawDocBatch;
+use crate::models::{PartitionAssignment, RawDocBatch};
 use crate::source::{Source, SourceContext, TypedSourceFactory};
 
 pub struct VecSource {
@@ -53,6 +53,7 @@ impl TypedSourceFactory for VecSourceFactory {
         source_id: String,
         params: VecSourceParams,
         checkpoint: SourceCheckpoint,
+        _partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<Self::Source> {
         let partition = PartitionId::from(params.partition.as_str());
         let next_item_idx = match checkpoint.position_for_partition(&partition) {
@@ -100,8 +101,12 @@ impl Source for VecSource {
             position_from_offset(from_item_idx),
             position_from_offset(to_item_idx),
         );
+        let doc_positions = (from_item_idx..to_item_idx)
+            .map(|item_idx| (self.partition.clone(), position_from_offset(item_idx + 1)))
+            .collect();
         let batch = RawDocBatch {
             docs: line_docs,
+            doc_positions,
             checkpoint_delta,
         };
         ctx.send_message(batch_sink, batch).await?;
@@ -144,6 +149,7 @@ mod tests {
             "my-vec-source".to_string(),
             params,
             SourceCheckpoint::default(),
+            PartitionAssignment::default(),
         )
         .await?;
         let vec_source_actor = SourceActor {
@@ -187,9 +193,13 @@ mod tests {
         let mut checkpoint = SourceCheckpoint::default();
         checkpoint.try_apply_delta(CheckpointDelta::from(0u64..2u64))?;
 
-        let vec_source =
-            VecSourceFactory::typed_create_source("my-vec-source".to_string(), params, checkpoint)
-                .await?;
+        let vec_source = VecSourceFactory::typed_create_source(
+            "my-vec-source".to_string(),
+            params,
+            checkpoint,
+            PartitionAssignment::default(),
+        )
+        .await?;
         let vec_source_actor = SourceActor {
             source: Box::new(vec_source),
             batch_sink: mailbox,
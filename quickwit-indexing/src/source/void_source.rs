@@ -24,6 +24,7 @@ use quickwit_actors::{ActorExitStatus, Mailbox, HEARTBEAT};
 use quickwit_config::VoidSourceParams;
 
 use crate::actors::Indexer;
+use crate::models::PartitionAssignment;
 use crate::source::{Source, SourceContext, TypedSourceFactory};
 
 pub struct VoidSource;
@@ -60,6 +61,7 @@ impl TypedSourceFactory for VoidSourceFactory {
         _source_id: String,
         _params: VoidSourceParams,
         _checkpoint: quickwit_metastore::checkpoint::SourceCheckpoint,
+        _partition_assignment: PartitionAssignment,
     ) -> anyhow::Result<VoidSource> {
         Ok(VoidSource)
     }
@@ -83,7 +85,11 @@ mod tests {
         };
         let source_loader = quickwit_supported_sources();
         let _ = source_loader
-            .load_source(source_config.clone(), SourceCheckpoint::default())
+            .load_source(
+                source_config.clone(),
+                SourceCheckpoint::default(),
+                PartitionAssignment::default(),
+            )
             .await?;
         Ok(())
     }
@@ -97,6 +103,7 @@ mod tests {
             "my-void-source".to_string(),
             VoidSourceParams {},
             SourceCheckpoint::default(),
+            PartitionAssignment::default(),
         )
         .await?;
         let void_source_actor = SourceActor {
@@ -31,6 +31,8 @@ pub enum IngestApiError {
     IndexAlreadyExists { index_id: String },
     #[error("Ingest API service is down")]
     IngestAPIServiceDown,
+    #[error("Timed out waiting for the documents to be committed.")]
+    CommitTimeout,
 }
 
 #[derive(Error, Debug)]
@@ -60,6 +62,7 @@ impl From<IngestApiError> for tonic::Status {
             IngestApiError::IndexDoesNotExist { .. } => tonic::Code::NotFound,
             IngestApiError::IndexAlreadyExists { .. } => tonic::Code::AlreadyExists,
             IngestApiError::IngestAPIServiceDown => tonic::Code::Internal,
+            IngestApiError::CommitTimeout => tonic::Code::DeadlineExceeded,
         };
         let message = error.to_string();
         tonic::Status::new(code, message)
@@ -17,31 +17,68 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, QueueCapacity};
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_proto::ingest_api::{
-    CreateQueueIfNotExistsRequest, CreateQueueRequest, DropQueueRequest, FetchRequest,
+    CommitType, CreateQueueIfNotExistsRequest, CreateQueueRequest, DropQueueRequest, FetchRequest,
     FetchResponse, IngestRequest, IngestResponse, ListQueuesRequest, ListQueuesResponse,
     QueueExistsRequest, SuggestTruncateRequest, TailRequest,
 };
+use tokio::sync::oneshot;
 
 use crate::{iter_doc_payloads, IngestApiError, Position, Queues};
 
 pub struct IngestApiService {
     queues: Queues,
+    /// Position of the last record of each queue that must be committed as soon as it is
+    /// indexed, following an ingest request with the `force` commit type. Positions are kept
+    /// until they are published, so that a pipeline restarting from its checkpoint and fetching
+    /// these records again also forces their commit.
+    force_commit_position_per_queue: HashMap<String, Position>,
+    /// Ingest requests waiting for their documents to be published.
+    pending_commits: Vec<PendingCommit>,
 }
 
+/// Notifies an ingest request once the records it appended are published.
+struct PendingCommit {
+    /// Position of the last record appended by the request to each queue, for the queues that
+    /// are not yet published up to that position.
+    positions: HashMap<String, Position>,
+    notify_tx: oneshot::Sender<crate::Result<()>>,
+}
+
+/// Ingests documents like an [`IngestRequest`] and, unless the commit type of the request is
+/// `auto`, replies with a receiver notified once the documents are published.
+///
+/// Use [`crate::ingest`] rather than sending this message directly.
+#[derive(Debug)]
+pub(crate) struct IngestAndNotifyCommit(pub IngestRequest);
+
+pub(crate) type CommitReceiver = oneshot::Receiver<crate::Result<()>>;
+
 impl IngestApiService {
-    pub fn with_queue_path(queue_path: &Path) -> crate::Result<Self> {
-        let queues = Queues::open(queue_path)?;
-        Ok(IngestApiService { queues })
+    /// Opens the queues stored in `queue_path`. If `fsync` is true, each ingested batch is
+    /// flushed to disk before the ingest request returns.
+    pub fn with_queue_path(queue_path: &Path, fsync: bool) -> crate::Result<Self> {
+        let mut queues = Queues::open(queue_path)?;
+        queues.set_fsync(fsync);
+        Ok(IngestApiService {
+            queues,
+            force_commit_position_per_queue: HashMap::new(),
+            pending_commits: Vec::new(),
+        })
     }
 
-    async fn ingest(&mut self, request: IngestRequest) -> crate::Result<IngestResponse> {
+    /// Appends the documents of the request to the queues and returns the position of the last
+    /// appended record of each queue.
+    async fn ingest(
+        &mut self,
+        request: IngestRequest,
+    ) -> crate::Result<(IngestResponse, HashMap<String, Position>)> {
         // Check all indexes exist assuming existing queues always have a corresponding index.
         let first_non_existing_queue_opt = request
             .doc_batches
@@ -56,16 +93,50 @@ impl IngestApiService {
         }
 
         let mut num_docs = 0usize;
+        let mut last_positions = HashMap::new();
         for doc_batch in &request.doc_batches {
+            if doc_batch.doc_lens.is_empty() {
+                continue;
+            }
             // TODO better error handling.
             // If there is an error, we probably want a transactional behavior.
             let records_it = iter_doc_payloads(doc_batch);
             self.queues.append_batch(&doc_batch.index_id, records_it)?;
             num_docs += doc_batch.doc_lens.len();
+            if let Some(last_position) = self.queues.last_position(&doc_batch.index_id) {
+                last_positions.insert(doc_batch.index_id.clone(), last_position);
+            }
         }
-        Ok(IngestResponse {
+        if request.commit() == CommitType::Force {
+            self.force_commit_position_per_queue
+                .extend(last_positions.clone());
+        }
+        let ingest_resp = IngestResponse {
             num_docs_for_processing: num_docs as u64,
-        })
+        };
+        Ok((ingest_resp, last_positions))
+    }
+
+    async fn ingest_and_notify_commit(
+        &mut self,
+        request: IngestRequest,
+    ) -> crate::Result<(IngestResponse, Option<CommitReceiver>)> {
+        let commit_type = request.commit();
+        let (ingest_resp, last_positions) = self.ingest(request).await?;
+        if commit_type == CommitType::Auto {
+            return Ok((ingest_resp, None));
+        }
+        let (notify_tx, notify_rx) = oneshot::channel();
+        if last_positions.is_empty() {
+            // Nothing was appended, so there is nothing to wait for.
+            let _ = notify_tx.send(Ok(()));
+        } else {
+            self.pending_commits.push(PendingCommit {
+                positions: last_positions,
+                notify_tx,
+            });
+        }
+        Ok((ingest_resp, Some(notify_rx)))
     }
 
     fn fetch(&mut self, fetch_req: FetchRequest) -> crate::Result<FetchResponse> {
@@ -73,15 +144,85 @@ impl IngestApiService {
         let num_bytes_limit_opt: Option<usize> = fetch_req
             .num_bytes_limit
             .map(|num_bytes_limit| num_bytes_limit as usize);
-        self.queues
-            .fetch(&fetch_req.index_id, start_from_opt, num_bytes_limit_opt)
+        let mut fetch_resp =
+            self.queues
+                .fetch(&fetch_req.index_id, start_from_opt, num_bytes_limit_opt)?;
+        if let Some(first_position) = fetch_resp.first_position {
+            let num_docs = fetch_resp
+                .doc_batch
+                .as_ref()
+                .map(|doc_batch| doc_batch.doc_lens.len())
+                .unwrap_or(1) as u64;
+            let last_position = Position::from(first_position + num_docs - 1);
+            // The commit is only forced for the batch containing the record, so that the
+            // following batches are committed as usual.
+            fetch_resp.force_commit = self
+                .force_commit_position_per_queue
+                .get(&fetch_req.index_id)
+                .map(|force_commit_position| {
+                    Position::from(first_position) <= *force_commit_position
+                        && *force_commit_position <= last_position
+                })
+                .unwrap_or(false);
+        }
+        Ok(fetch_resp)
     }
 
     fn suggest_truncate(&mut self, request: SuggestTruncateRequest) -> crate::Result<()> {
-        self.queues.suggest_truncate(
-            &request.index_id,
-            Position::from(request.up_to_position_included),
-        )?;
+        let published_position = Position::from(request.up_to_position_included);
+        // The queue is truncated once the records are published, which is when the ingest
+        // requests waiting for these records can be notified.
+        self.notify_published(&request.index_id, published_position);
+        let is_force_commit_published = self
+            .force_commit_position_per_queue
+            .get(&request.index_id)
+            .map(|force_commit_position| *force_commit_position <= published_position)
+            .unwrap_or(false);
+        if is_force_commit_published {
+            self.force_commit_position_per_queue
+                .remove(&request.index_id);
+        }
+        self.queues
+            .suggest_truncate(&request.index_id, published_position)?;
+        Ok(())
+    }
+
+    fn notify_published(&mut self, queue_id: &str, published_position: Position) {
+        for pending_commit in &mut self.pending_commits {
+            let is_published = pending_commit
+                .positions
+                .get(queue_id)
+                .map(|position| *position <= published_position)
+                .unwrap_or(false);
+            if is_published {
+                pending_commit.positions.remove(queue_id);
+            }
+        }
+        let (published_commits, pending_commits): (Vec<PendingCommit>, Vec<PendingCommit>) =
+            self.pending_commits.drain(..).partition(|pending_commit| {
+                pending_commit.positions.is_empty() || pending_commit.notify_tx.is_closed()
+            });
+        self.pending_commits = pending_commits;
+        for published_commit in published_commits {
+            let _ = published_commit.notify_tx.send(Ok(()));
+        }
+    }
+
+    fn drop_queue(&mut self, queue_id: &str) -> crate::Result<()> {
+        self.queues.drop_queue(queue_id)?;
+        self.force_commit_position_per_queue.remove(queue_id);
+        let (aborted_commits, pending_commits): (Vec<PendingCommit>, Vec<PendingCommit>) = self
+            .pending_commits
+            .drain(..)
+            .partition(|pending_commit| pending_commit.positions.contains_key(queue_id));
+        self.pending_commits = pending_commits;
+        for aborted_commit in aborted_commits {
+            let _ = aborted_commit
+                .notify_tx
+                .send(Err(IngestApiError::IndexDoesNotExist {
+                    index_id: queue_id.to_string(),
+                }));
+        }
         Ok(())
     }
 }
@@ -149,10 +290,12 @@ impl Handler<DropQueueRequest> for IngestApiService {
         drop_queue_req: DropQueueRequest,
         _ctx: &ActorContext<Self>,
     ) -> Result<Self::Reply, ActorExitStatus> {
-        Ok(self.queues.drop_queue(&drop_queue_req.queue_id))
+        Ok(self.drop_queue(&drop_queue_req.queue_id))
     }
 }
 
+// This handler does not wait for the documents to be published: requests with the `wait_for` or
+// `force` commit types must go through `crate::ingest`.
 #[async_trait]
 impl Handler<IngestRequest> for IngestApiService {
     type Reply = crate::Result<IngestResponse>;
@@ -161,7 +304,23 @@ impl Handler<IngestRequest> for IngestApiService {
         ingest_req: IngestRequest,
         _ctx: &ActorContext<Self>,
     ) -> Result<Self::Reply, ActorExitStatus> {
-        Ok(self.ingest(ingest_req).await)
+        let ingest_res = self
+            .ingest(ingest_req)
+            .await
+            .map(|(ingest_resp, _)| ingest_resp);
+        Ok(ingest_res)
+    }
+}
+
+#[async_trait]
+impl Handler<IngestAndNotifyCommit> for IngestApiService {
+    type Reply = crate::Result<(IngestResponse, Option<CommitReceiver>)>;
+    async fn handle(
+        &mut self,
+        message: IngestAndNotifyCommit,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.ingest_and_notify_commit(message.0).await)
     }
 }
 
@@ -212,3 +371,235 @@ impl Handler<ListQueuesRequest> for IngestApiService {
         Ok(self.queues.list_queues())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_actors::Universe;
+    use quickwit_proto::ingest_api::{
+        CommitType, CreateQueueRequest, DocBatch, DropQueueRequest, FetchRequest, IngestRequest,
+        SuggestTruncateRequest,
+    };
+
+    use crate::{add_doc, ingest, spawn_ingest_api_actor, IngestApiError};
+
+    fn make_ingest_request(queue_id: &str, num_docs: usize, commit: CommitType) -> IngestRequest {
+        let mut doc_batch = DocBatch {
+            index_id: queue_id.to_string(),
+            ..Default::default()
+        };
+        for doc_id in 0..num_docs {
+            add_doc(format!("{{\"id\": {doc_id}}}").as_bytes(), &mut doc_batch);
+        }
+        IngestRequest {
+            doc_batches: vec![doc_batch],
+            commit: commit as i32,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ingest_wait_for_commit() {
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, temp_dir.path(), true).unwrap();
+        ingest_api_mailbox
+            .ask_for_res(CreateQueueRequest {
+                queue_id: "my-queue".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let ingest_resp = ingest(
+            &ingest_api_mailbox,
+            make_ingest_request("my-queue", 2, CommitType::Auto),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ingest_resp.num_docs_for_processing, 2);
+
+        let ingest_api_mailbox_clone = ingest_api_mailbox.clone();
+        let ingest_handle = tokio::spawn(async move {
+            ingest(
+                &ingest_api_mailbox_clone,
+                make_ingest_request("my-queue", 3, CommitType::WaitFor),
+                Duration::from_secs(60),
+            )
+            .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!ingest_handle.is_finished());
+
+        // The first request is published, the second one is not.
+        ingest_api_mailbox
+            .ask_for_res(SuggestTruncateRequest {
+                index_id: "my-queue".to_string(),
+                up_to_position_included: 1,
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!ingest_handle.is_finished());
+
+        ingest_api_mailbox
+            .ask_for_res(SuggestTruncateRequest {
+                index_id: "my-queue".to_string(),
+                up_to_position_included: 4,
+            })
+            .await
+            .unwrap();
+        let ingest_resp = ingest_handle.await.unwrap().unwrap();
+        assert_eq!(ingest_resp.num_docs_for_processing, 3);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_force_commit() {
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, temp_dir.path(), false).unwrap();
+        ingest_api_mailbox
+            .ask_for_res(CreateQueueRequest {
+                queue_id: "my-queue".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let ingest_api_mailbox_clone = ingest_api_mailbox.clone();
+        let ingest_handle = tokio::spawn(async move {
+            ingest(
+                &ingest_api_mailbox_clone,
+                make_ingest_request("my-queue", 3, CommitType::Force),
+                Duration::from_secs(60),
+            )
+            .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let fetch_resp = ingest_api_mailbox
+            .ask_for_res(FetchRequest {
+                index_id: "my-queue".to_string(),
+                start_after: None,
+                num_bytes_limit: Some(1),
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_resp.first_position, Some(0));
+        assert!(!fetch_resp.force_commit);
+
+        let fetch_resp = ingest_api_mailbox
+            .ask_for_res(FetchRequest {
+                index_id: "my-queue".to_string(),
+                start_after: Some(0),
+                num_bytes_limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_resp.first_position, Some(1));
+        assert!(fetch_resp.force_commit);
+
+        // A pipeline restarting from its checkpoint fetches the records again and must still
+        // force the commit.
+        let fetch_resp = ingest_api_mailbox
+            .ask_for_res(FetchRequest {
+                index_id: "my-queue".to_string(),
+                start_after: Some(0),
+                num_bytes_limit: None,
+            })
+            .await
+            .unwrap();
+        assert!(fetch_resp.force_commit);
+
+        ingest_api_mailbox
+            .ask_for_res(SuggestTruncateRequest {
+                index_id: "my-queue".to_string(),
+                up_to_position_included: 2,
+            })
+            .await
+            .unwrap();
+        let ingest_resp = ingest_handle.await.unwrap().unwrap();
+        assert_eq!(ingest_resp.num_docs_for_processing, 3);
+
+        // The following records are committed as usual.
+        let ingest_resp = ingest(
+            &ingest_api_mailbox,
+            make_ingest_request("my-queue", 1, CommitType::Auto),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ingest_resp.num_docs_for_processing, 1);
+        let fetch_resp = ingest_api_mailbox
+            .ask_for_res(FetchRequest {
+                index_id: "my-queue".to_string(),
+                start_after: Some(2),
+                num_bytes_limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_resp.first_position, Some(3));
+        assert!(!fetch_resp.force_commit);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_wait_for_commit_timeout() {
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, temp_dir.path(), false).unwrap();
+        ingest_api_mailbox
+            .ask_for_res(CreateQueueRequest {
+                queue_id: "my-queue".to_string(),
+            })
+            .await
+            .unwrap();
+
+        tokio::time::pause();
+        let ingest_error = ingest(
+            &ingest_api_mailbox,
+            make_ingest_request("my-queue", 1, CommitType::WaitFor),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            ingest_error,
+            quickwit_actors::AskError::ErrorReply(IngestApiError::CommitTimeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_wait_for_commit_aborted_by_drop_queue() {
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ingest_api_mailbox = spawn_ingest_api_actor(&universe, temp_dir.path(), false).unwrap();
+        ingest_api_mailbox
+            .ask_for_res(CreateQueueRequest {
+                queue_id: "my-queue".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let ingest_api_mailbox_clone = ingest_api_mailbox.clone();
+        let ingest_handle = tokio::spawn(async move {
+            ingest(
+                &ingest_api_mailbox_clone,
+                make_ingest_request("my-queue", 1, CommitType::WaitFor),
+                Duration::from_secs(60),
+            )
+            .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        ingest_api_mailbox
+            .ask_for_res(DropQueueRequest {
+                queue_id: "my-queue".to_string(),
+            })
+            .await
+            .unwrap();
+        let ingest_error = ingest_handle.await.unwrap().unwrap_err();
+        assert!(matches!(
+            ingest_error,
+            quickwit_actors::AskError::ErrorReply(IngestApiError::IndexDoesNotExist { .. })
+        ));
+    }
+}
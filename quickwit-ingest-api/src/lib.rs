@@ -23,16 +23,18 @@ mod position;
 mod queue;
 
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::Context;
 pub use errors::IngestApiError;
 use errors::Result;
+use ingest_api_service::IngestAndNotifyCommit;
 pub use ingest_api_service::IngestApiService;
 use once_cell::sync::OnceCell;
 pub use position::Position;
 pub use queue::Queues;
-use quickwit_actors::{Mailbox, Universe};
-use quickwit_proto::ingest_api::DocBatch;
+use quickwit_actors::{AskError, Mailbox, Universe};
+use quickwit_proto::ingest_api::{DocBatch, IngestRequest, IngestResponse};
 use tracing::info;
 
 pub static INGEST_API_SERVICE_INSTANCE: OnceCell<Mailbox<IngestApiService>> = OnceCell::new();
@@ -41,9 +43,10 @@ pub static INGEST_API_SERVICE_INSTANCE: OnceCell<Mailbox<IngestApiService>> = On
 pub fn init_ingest_api(
     universe: &Universe,
     queue_path: &Path,
+    fsync: bool,
 ) -> anyhow::Result<Mailbox<IngestApiService>> {
     let ingest_api_service = INGEST_API_SERVICE_INSTANCE
-        .get_or_try_init(|| spawn_ingest_api_actor(universe, queue_path))
+        .get_or_try_init(|| spawn_ingest_api_actor(universe, queue_path, fsync))
         .context("Failed to initialize the ingest API service.")?;
     Ok(ingest_api_service.clone())
 }
@@ -57,13 +60,39 @@ pub fn get_ingest_api_service() -> Option<Mailbox<IngestApiService>> {
 pub fn spawn_ingest_api_actor(
     universe: &Universe,
     queue_path: &Path,
+    fsync: bool,
 ) -> anyhow::Result<Mailbox<IngestApiService>> {
-    info!(queue_path=?queue_path, "Spawning ingest API actor");
-    let ingest_api_actor = IngestApiService::with_queue_path(queue_path)?;
+    info!(queue_path=?queue_path, fsync=fsync, "Spawning ingest API actor");
+    let ingest_api_actor = IngestApiService::with_queue_path(queue_path, fsync)?;
     let (ingest_api_mailbox, _ingest_api_handle) = universe.spawn_actor(ingest_api_actor).spawn();
     Ok(ingest_api_mailbox)
 }
 
+/// Time granted to the indexing pipeline, on top of the commit timeout of the index, to index
+/// and publish the documents of an ingest request waiting for their commit.
+const COMMIT_WAIT_MARGIN: Duration = Duration::from_secs(30);
+
+/// Ingests the documents of an [`IngestRequest`]. If the commit type of the request is `wait_for`
+/// or `force`, waits until the documents are published, for at most `commit_timeout`, the largest
+/// commit timeout of the targeted indexes, plus a margin.
+pub async fn ingest(
+    ingest_api_mailbox: &Mailbox<IngestApiService>,
+    ingest_req: IngestRequest,
+    commit_timeout: Duration,
+) -> std::result::Result<IngestResponse, AskError<IngestApiError>> {
+    let (ingest_resp, commit_rx_opt) = ingest_api_mailbox
+        .ask_for_res(IngestAndNotifyCommit(ingest_req))
+        .await?;
+    if let Some(commit_rx) = commit_rx_opt {
+        // Dropping the receiver on timeout lets the service discard the pending commit.
+        tokio::time::timeout(commit_timeout + COMMIT_WAIT_MARGIN, commit_rx)
+            .await
+            .map_err(|_| IngestApiError::CommitTimeout)?
+            .map_err(|_| IngestApiError::IngestAPIServiceDown)??;
+    }
+    Ok(ingest_resp)
+}
+
 /// Adds a document raw bytes to a [`DocBatch`]
 pub fn add_doc(payload: &[u8], fetch_resp: &mut DocBatch) -> usize {
     fetch_resp.concat_docs.extend_from_slice(payload);
@@ -33,6 +33,8 @@ const QUICKWIT_CF_PREFIX: &str = ".queue_";
 pub struct Queues {
     db: DB,
     last_position_per_queue: HashMap<String, Option<Position>>,
+    /// If true, each appended batch is flushed to disk before `append_batch` returns.
+    fsync: bool,
 }
 
 fn default_rocks_db_options() -> rocksdb::Options {
@@ -42,9 +44,11 @@ fn default_rocks_db_options() -> rocksdb::Options {
     options
 }
 
-fn default_rocks_db_write_options() -> rocksdb::WriteOptions {
+fn default_rocks_db_write_options(fsync: bool) -> rocksdb::WriteOptions {
     let mut write_options = WriteOptions::default();
-    write_options.set_sync(false);
+    // Without fsync, the write-ahead log is only written to the page cache: appended batches
+    // survive a crash of the process but may be lost if the machine crashes.
+    write_options.set_sync(fsync);
     write_options.disable_wal(false);
     write_options
 }
@@ -82,9 +86,24 @@ impl Queues {
         Ok(Queues {
             db,
             last_position_per_queue: next_position_per_queue,
+            fsync: false,
         })
     }
 
+    /// Sets whether each appended batch is flushed to disk (fsync) before being acknowledged.
+    pub fn set_fsync(&mut self, fsync: bool) {
+        self.fsync = fsync;
+    }
+
+    /// Returns the position of the last record appended to a queue, if any.
+    pub fn last_position(&self, queue_id: &str) -> Option<Position> {
+        let real_queue_id = format!("{}{}", QUICKWIT_CF_PREFIX, queue_id);
+        self.last_position_per_queue
+            .get(&real_queue_id)
+            .copied()
+            .flatten()
+    }
+
     pub fn queue_exists(&self, queue_id: &str) -> bool {
         let real_queue_id = format!("{}{}", QUICKWIT_CF_PREFIX, queue_id);
         self.db.cf_handle(&real_queue_id).is_some()
@@ -198,7 +217,7 @@ impl Queues {
             next_position = next_position.inc();
         }
 
-        let write_options = default_rocks_db_write_options();
+        let write_options = default_rocks_db_write_options(self.fsync);
         self.db.write_opt(batch, &write_options)?;
 
         Ok(())
@@ -244,6 +263,7 @@ impl Queues {
         Ok(FetchResponse {
             first_position: first_key_opt,
             doc_batch: Some(doc_batch),
+            force_commit: false,
         })
     }
 
@@ -272,6 +292,7 @@ impl Queues {
         Ok(FetchResponse {
             first_position: first_key_opt,
             doc_batch: Some(doc_batch),
+            force_commit: false,
         })
     }
 
@@ -431,6 +452,32 @@ mod tests {
         queues.fetch_test(TEST_QUEUE_ID2, None, Some(0), &[&b"hello2"[..]]);
     }
 
+    #[test]
+    fn test_last_position_with_fsync() {
+        let mut queues = QueuesForTest::default();
+        queues.set_fsync(true);
+        queues.create_queue(TEST_QUEUE_ID).unwrap();
+        assert!(queues.last_position(TEST_QUEUE_ID).is_none());
+
+        queues
+            .append_batch(
+                TEST_QUEUE_ID,
+                [b"hello", b"happy"].iter().map(|bytes| bytes.as_slice()),
+            )
+            .unwrap();
+        assert_eq!(
+            queues.last_position(TEST_QUEUE_ID),
+            Some(Position::from(1u64))
+        );
+        assert!(queues.last_position(TEST_QUEUE_ID2).is_none());
+
+        queues.reload();
+        assert_eq!(
+            queues.last_position(TEST_QUEUE_ID),
+            Some(Position::from(1u64))
+        );
+    }
+
     #[test]
     fn test_create_reopen() {
         let mut queues = QueuesForTest::default();
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS splits_index_id_split_id_idx;
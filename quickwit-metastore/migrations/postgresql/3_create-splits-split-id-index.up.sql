@@ -0,0 +1,3 @@
+-- Split listing pages through the splits of an index in the byte-wise order of their IDs, with
+-- the "C" collation, which the primary key index cannot serve.
+CREATE INDEX IF NOT EXISTS splits_index_id_split_id_idx ON splits (index_id, split_id COLLATE "C");
@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS quickwit_set_index_update_timestamp;
+DROP TABLE IF EXISTS indexes;
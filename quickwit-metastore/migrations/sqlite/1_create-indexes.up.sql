@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS indexes (
+    index_id VARCHAR(50) PRIMARY KEY,
+    index_metadata_json TEXT NOT NULL,
+    -- Timestamps are stored as Unix timestamps in seconds.
+    create_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    update_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER))
+);
+
+-- Refresh the `update_timestamp` field of an index whenever it is modified, unless the
+-- statement sets it explicitly. Recursive triggers are disabled by default, so the nested update
+-- does not fire this trigger again.
+CREATE TRIGGER IF NOT EXISTS quickwit_set_index_update_timestamp
+    AFTER UPDATE ON indexes
+    FOR EACH ROW
+    WHEN NEW.update_timestamp IS OLD.update_timestamp
+BEGIN
+    UPDATE indexes SET update_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE index_id = NEW.index_id;
+END;
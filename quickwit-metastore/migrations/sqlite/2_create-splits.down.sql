@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS quickwit_set_index_update_timestamp_on_split_delete;
+DROP TRIGGER IF EXISTS quickwit_set_index_update_timestamp_on_split_update;
+DROP TRIGGER IF EXISTS quickwit_set_index_update_timestamp_on_split_insert;
+DROP TRIGGER IF EXISTS quickwit_set_split_update_timestamp;
+DROP TABLE IF EXISTS splits;
@@ -0,0 +1,52 @@
+CREATE TABLE IF NOT EXISTS splits (
+    split_id VARCHAR(50) PRIMARY KEY,
+    split_state VARCHAR(30) NOT NULL,
+    time_range_start BIGINT,
+    time_range_end BIGINT,
+    -- SQLite has no array type: the tags are stored as a JSON array of strings.
+    tags TEXT NOT NULL,
+    split_metadata_json TEXT NOT NULL,
+    index_id VARCHAR(50) NOT NULL,
+    create_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    update_timestamp INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+
+    FOREIGN KEY(index_id) REFERENCES indexes(index_id)
+);
+
+CREATE INDEX IF NOT EXISTS splits_index_id_idx ON splits(index_id);
+
+-- Refresh the `update_timestamp` field of a split whenever it is modified.
+CREATE TRIGGER IF NOT EXISTS quickwit_set_split_update_timestamp
+    AFTER UPDATE ON splits
+    FOR EACH ROW
+    WHEN NEW.update_timestamp IS OLD.update_timestamp
+BEGIN
+    UPDATE splits SET update_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE split_id = NEW.split_id;
+END;
+
+-- We also want to update an index `update_timestamp` field whenever a related split
+-- is modified.
+CREATE TRIGGER IF NOT EXISTS quickwit_set_index_update_timestamp_on_split_insert
+    AFTER INSERT ON splits
+    FOR EACH ROW
+BEGIN
+    UPDATE indexes SET update_timestamp = NEW.update_timestamp
+    WHERE index_id = NEW.index_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS quickwit_set_index_update_timestamp_on_split_update
+    AFTER UPDATE ON splits
+    FOR EACH ROW
+BEGIN
+    UPDATE indexes SET update_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE index_id = NEW.index_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS quickwit_set_index_update_timestamp_on_split_delete
+    AFTER DELETE ON splits
+    FOR EACH ROW
+BEGIN
+    UPDATE indexes SET update_timestamp = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE index_id = OLD.index_id;
+END;
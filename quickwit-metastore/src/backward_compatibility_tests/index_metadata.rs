@@ -21,8 +21,8 @@ use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use byte_unit::Byte;
 use quickwit_config::{
-    DocMapping, IndexingResources, IndexingSettings, KafkaSourceParams, MergePolicy,
-    SearchSettings, SourceConfig, SourceParams,
+    DocMapping, IndexingResources, IndexingSettings, KafkaMessageFormat, KafkaSourceParams,
+    MergePolicy, SearchSettings, SourceConfig, SourceParams,
 };
 use quickwit_doc_mapper::{ModeType, SortOrder};
 
@@ -141,6 +141,8 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
         store_source: true,
         mode: ModeType::Dynamic,
         dynamic_mapping: None,
+        tokenizers: Vec::new(),
+        transforms: Vec::new(),
     };
     let merge_policy = MergePolicy {
         demux_factor: 7,
@@ -164,6 +166,7 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
         resources: indexing_resources,
         docstore_blocksize: IndexingSettings::default_docstore_blocksize(),
         docstore_compression_level: IndexingSettings::default_docstore_compression_level(),
+        dead_letter_queue: None,
     };
     let search_settings = SearchSettings {
         default_search_fields: vec!["message".to_string()],
@@ -174,6 +177,7 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
             topic: "kafka-topic".to_string(),
             client_log_level: None,
             client_params: serde_json::json!({}),
+            message_format: KafkaMessageFormat::Json,
         }),
     };
     let mut sources = HashMap::default();
@@ -183,6 +187,7 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
         index_id: "my-index".to_string(),
         index_uri: "s3://quickwit-indexes/my-index".to_string(),
         checkpoint,
+        checkpoint_update_timestamps: Default::default(),
         doc_mapping,
         indexing_settings,
         search_settings,
@@ -19,13 +19,12 @@
 
 use std::cmp::Ordering;
 use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::iter::FromIterator;
 use std::ops::Range;
 use std::sync::Arc;
 
-use serde::ser::SerializeMap;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{info, warn};
@@ -162,6 +161,21 @@ impl IndexCheckpoint {
         Ok(())
     }
 
+    /// Resets a given source checkpoint.
+    ///
+    /// See [`SourceCheckpoint::try_apply_reset`] for more details.
+    pub fn try_apply_reset(
+        &mut self,
+        source_id: &str,
+        reset: CheckpointReset,
+    ) -> Result<(), IncompatibleCheckpointDelta> {
+        self.per_source
+            .entry(source_id.to_string())
+            .or_default()
+            .try_apply_reset(reset)?;
+        Ok(())
+    }
+
     /// Returns the checkpoint associated to a given source.
     ///
     /// All registered source have an associated checkpoint (that is possibly empty).
@@ -184,6 +198,11 @@ impl IndexCheckpoint {
     pub fn remove_source(&mut self, source_id: &str) {
         self.per_source.remove(source_id);
     }
+
+    /// Returns the IDs of the sources that have a checkpoint.
+    pub fn source_ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.per_source.keys().map(String::as_str)
+    }
 }
 
 /// A source checkpoint is a map of the last processed position for every partition.
@@ -193,6 +212,10 @@ impl IndexCheckpoint {
 #[derive(Default, Clone, PartialEq)]
 pub struct SourceCheckpoint {
     per_partition: BTreeMap<PartitionId, Position>,
+    /// Partitions moved by a reset. Deltas over these partitions must start exactly at the
+    /// current position, so that a pipeline still reading from the positions preceding the
+    /// reset cannot publish over it.
+    reset_partitions: BTreeSet<PartitionId>,
 }
 
 impl SourceCheckpoint {
@@ -222,32 +245,72 @@ impl FromIterator<(PartitionId, Position)> for SourceCheckpoint {
     where I: IntoIterator<Item = (PartitionId, Position)> {
         SourceCheckpoint {
             per_partition: iter.into_iter().collect(),
+            reset_partitions: BTreeSet::new(),
         }
     }
 }
 
+/// Serialized form of a [`SourceCheckpoint`]. A checkpoint without reset partitions is
+/// serialized as a plain map of positions, as before resets were introduced.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SerializedSourceCheckpoint {
+    Positions(BTreeMap<String, String>),
+    PositionsWithResetPartitions {
+        positions: BTreeMap<String, String>,
+        reset_partitions: Vec<String>,
+    },
+}
+
 impl Serialize for SourceCheckpoint {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: serde::Serializer {
-        let mut map = serializer.serialize_map(Some(self.per_partition.len()))?;
-        for (partition, position) in &self.per_partition {
-            map.serialize_entry(&*partition.0, &*position.as_str())?;
-        }
-        map.end()
+        let positions: BTreeMap<String, String> = self
+            .per_partition
+            .iter()
+            .map(|(partition, position)| (partition.0.to_string(), position.as_str().to_string()))
+            .collect();
+        let serialized_checkpoint = if self.reset_partitions.is_empty() {
+            SerializedSourceCheckpoint::Positions(positions)
+        } else {
+            SerializedSourceCheckpoint::PositionsWithResetPartitions {
+                positions,
+                reset_partitions: self
+                    .reset_partitions
+                    .iter()
+                    .map(|partition_id| partition_id.0.to_string())
+                    .collect(),
+            }
+        };
+        serialized_checkpoint.serialize(serializer)
     }
 }
 
 impl<'de> Deserialize<'de> for SourceCheckpoint {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: serde::Deserializer<'de> {
-        let string_to_string_map: BTreeMap<String, String> = BTreeMap::deserialize(deserializer)?;
-        let per_partition: BTreeMap<PartitionId, Position> = string_to_string_map
+        let (positions, reset_partitions) =
+            match SerializedSourceCheckpoint::deserialize(deserializer)? {
+                SerializedSourceCheckpoint::Positions(positions) => (positions, Vec::new()),
+                SerializedSourceCheckpoint::PositionsWithResetPartitions {
+                    positions,
+                    reset_partitions,
+                } => (positions, reset_partitions),
+            };
+        let per_partition: BTreeMap<PartitionId, Position> = positions
             .into_iter()
             .map(|(partition_id, position)| {
                 (PartitionId::from(partition_id), Position::from(position))
             })
             .collect();
-        Ok(SourceCheckpoint { per_partition })
+        let reset_partitions: BTreeSet<PartitionId> = reset_partitions
+            .into_iter()
+            .map(PartitionId::from)
+            .collect();
+        Ok(SourceCheckpoint {
+            per_partition,
+            reset_partitions,
+        })
     }
 }
 
@@ -287,17 +350,18 @@ impl SourceCheckpoint {
     ) -> Result<(), IncompatibleCheckpointDelta> {
         info!(delta=?delta, checkpoint=?self);
         for (delta_partition, delta_position) in &delta.per_partition {
-            let position = if let Some(position) = self.per_partition.get(delta_partition) {
-                position
-            } else {
-                continue;
+            let is_reset_partition = self.reset_partitions.contains(delta_partition);
+            let position = match self.per_partition.get(delta_partition) {
+                Some(position) => position,
+                None if is_reset_partition => &Position::Beginning,
+                None => continue,
             };
             match position.cmp(&delta_position.from) {
                 Ordering::Equal => {}
-                Ordering::Less => {
+                Ordering::Less if !is_reset_partition => {
                     warn!(cur_pos=?position, delta_pos_from=?delta_position.from,partition=?delta_partition, "Some positions were skipped.");
                 }
-                Ordering::Greater => {
+                Ordering::Less | Ordering::Greater => {
                     return Err(IncompatibleCheckpointDelta {
                         partition_id: delta_partition.clone(),
                         current_position: position.clone(),
@@ -306,6 +370,19 @@ impl SourceCheckpoint {
                 }
             }
         }
+        for (pruned_partition, pruned_position) in &delta.pruned_partitions {
+            let position = match self.per_partition.get(pruned_partition) {
+                Some(position) => position,
+                None => continue,
+            };
+            if position != pruned_position {
+                return Err(IncompatibleCheckpointDelta {
+                    partition_id: pruned_partition.clone(),
+                    current_position: position.clone(),
+                    delta_position_from: pruned_position.clone(),
+                });
+            }
+        }
         Ok(())
     }
 
@@ -324,6 +401,11 @@ impl SourceCheckpoint {
     ///   |  (..a] (b..c] with b > a     | Compatible                  |
     ///   |  (..a] (b..c] with b < a     | Incompatible                |
     ///
+    /// Over a partition moved by a reset, only the first case is compatible.
+    ///
+    /// The partitions pruned by the delta are removed from the checkpoint, provided they are at
+    /// the position the delta expects.
+    ///
     /// If the delta is incompatible, returns an error without modifying the original checkpoint.
     pub fn try_apply_delta(
         &mut self,
@@ -334,6 +416,48 @@ impl SourceCheckpoint {
             self.per_partition
                 .insert(partition_id, partition_position.to);
         }
+        for partition_id in delta.pruned_partitions.keys() {
+            self.per_partition.remove(partition_id);
+        }
+        Ok(())
+    }
+
+    /// Try and apply a reset.
+    ///
+    /// Contrary to deltas, resets may move positions backward. However, every partition of the
+    /// reset must be exactly at the position the reset expects, a missing partition being at
+    /// [`Position::Beginning`].
+    ///
+    /// The partitions of the reset only accept the deltas starting exactly at their position from
+    /// then on: a pipeline still reading from the positions preceding the reset fails to publish
+    /// and restarts from the new positions.
+    ///
+    /// If the reset is incompatible, returns an error without modifying the original checkpoint.
+    pub fn try_apply_reset(
+        &mut self,
+        reset: CheckpointReset,
+    ) -> Result<(), IncompatibleCheckpointDelta> {
+        for (partition_id, partition_reset) in &reset.per_partition {
+            let position = self
+                .per_partition
+                .get(partition_id)
+                .unwrap_or(&Position::Beginning);
+            if position.cmp(&partition_reset.from) != Ordering::Equal {
+                return Err(IncompatibleCheckpointDelta {
+                    partition_id: partition_id.clone(),
+                    current_position: position.clone(),
+                    delta_position_from: partition_reset.from.clone(),
+                });
+            }
+        }
+        for (partition_id, partition_reset) in reset.per_partition {
+            self.reset_partitions.insert(partition_id.clone());
+            if partition_reset.to == Position::Beginning {
+                self.per_partition.remove(&partition_id);
+            } else {
+                self.per_partition.insert(partition_id, partition_reset.to);
+            }
+        }
         Ok(())
     }
 }
@@ -372,9 +496,14 @@ struct PartitionDelta {
 /// partition not only a new position, but also an expected
 /// `from` position. This makes it possible to defensively check that
 /// we are not trying to add documents to the index that were already indexed.
+///
+/// A checkpoint delta can also prune partitions that a source will never read again, for instance
+/// the objects deleted from an object storage, to keep the checkpoint bounded.
 #[derive(Default, Clone, Eq, PartialEq)]
 pub struct CheckpointDelta {
     per_partition: BTreeMap<PartitionId, PartitionDelta>,
+    /// Partitions to remove from the checkpoint, with the position they are expected to be at.
+    pruned_partitions: BTreeMap<PartitionId, Position>,
 }
 
 impl fmt::Debug for CheckpointDelta {
@@ -392,6 +521,12 @@ impl fmt::Debug for CheckpointDelta {
                 f.write_str(" ")?;
             }
         }
+        for (i, (partition_id, position)) in self.pruned_partitions.iter().enumerate() {
+            if i != 0 || !self.per_partition.is_empty() {
+                f.write_str(" ")?;
+            }
+            write!(f, "-{}:{}", partition_id.0, position.as_str())?;
+        }
         f.write_str(")")?;
         Ok(())
     }
@@ -441,6 +576,13 @@ impl CheckpointDelta {
         from_position: Position,
         to_position: Position,
     ) -> Result<(), IncompatibleCheckpointDelta> {
+        if let Some(pruned_position) = self.pruned_partitions.get(&partition_id) {
+            return Err(IncompatibleCheckpointDelta {
+                partition_id,
+                current_position: pruned_position.clone(),
+                delta_position_from: from_position,
+            });
+        }
         let entry = self.per_partition.entry(partition_id);
         match entry {
             Entry::Occupied(mut occupied_entry) => {
@@ -473,6 +615,49 @@ impl CheckpointDelta {
         for (partition_id, partition_delta) in delta.per_partition {
             self.record_partition_delta(partition_id, partition_delta.from, partition_delta.to)?;
         }
+        for (partition_id, position) in delta.pruned_partitions {
+            self.record_partition_pruning(partition_id, position)?;
+        }
+        Ok(())
+    }
+
+    /// Records the pruning of a partition expected to be at `position`: the partition is removed
+    /// from the checkpoint when the delta is applied, and cannot be moved by the delta anymore.
+    ///
+    /// Pruning a partition moved by the delta cancels the move, the partition being expected at
+    /// the position the move started from.
+    pub fn record_partition_pruning(
+        &mut self,
+        partition_id: PartitionId,
+        position: Position,
+    ) -> Result<(), IncompatibleCheckpointDelta> {
+        let expected_position = if let Some(partition_delta) = self.per_partition.get(&partition_id)
+        {
+            if partition_delta.to != position {
+                return Err(IncompatibleCheckpointDelta {
+                    partition_id,
+                    current_position: partition_delta.to.clone(),
+                    delta_position_from: position,
+                });
+            }
+            self.per_partition.remove(&partition_id).unwrap().from
+        } else {
+            position
+        };
+        match self.pruned_partitions.entry(partition_id) {
+            Entry::Occupied(occupied_entry) => {
+                if *occupied_entry.get() != expected_position {
+                    return Err(IncompatibleCheckpointDelta {
+                        partition_id: occupied_entry.key().clone(),
+                        current_position: occupied_entry.get().clone(),
+                        delta_position_from: expected_position,
+                    });
+                }
+            }
+            Entry::Vacant(vacant_entry) => {
+                vacant_entry.insert(expected_position);
+            }
+        }
         Ok(())
     }
 
@@ -482,6 +667,74 @@ impl CheckpointDelta {
     }
 
     /// Returns `true` if the checkpoint delta is empty.
+    pub fn is_empty(&self) -> bool {
+        self.per_partition.is_empty() && self.pruned_partitions.is_empty()
+    }
+}
+
+/// A checkpoint reset moves the positions of some partitions of a source, possibly backward.
+///
+/// For each partition, the reset records the position the partition is expected to be at, and the
+/// position to move it to. The reset is rejected if the checkpoint moved in the meantime, for
+/// instance because an indexing pipeline published new splits.
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct CheckpointReset {
+    per_partition: BTreeMap<PartitionId, PartitionDelta>,
+}
+
+impl fmt::Debug for CheckpointReset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Reset(")?;
+        for (i, (partition_id, partition_reset)) in self.per_partition.iter().enumerate() {
+            write!(
+                f,
+                "{}:{}->{}",
+                partition_id.0,
+                partition_reset.from.as_str(),
+                partition_reset.to.as_str()
+            )?;
+            if i != self.per_partition.len() - 1 {
+                f.write_str(" ")?;
+            }
+        }
+        f.write_str(")")?;
+        Ok(())
+    }
+}
+
+impl CheckpointReset {
+    /// Records the reset of a partition from its current position to a new position, which may
+    /// precede the current one. Resetting a partition to [`Position::Beginning`] removes it from
+    /// the checkpoint.
+    pub fn record_partition_reset(
+        &mut self,
+        partition_id: PartitionId,
+        current_position: Position,
+        new_position: Position,
+    ) {
+        let partition_reset = PartitionDelta {
+            from: current_position,
+            to: new_position,
+        };
+        self.per_partition.insert(partition_id, partition_reset);
+    }
+
+    /// Returns an iterator over the `(partition ID, current position, new position)` tuples of the
+    /// reset.
+    pub fn iter(&self) -> impl Iterator<Item = (&PartitionId, &Position, &Position)> + '_ {
+        self.per_partition
+            .iter()
+            .map(|(partition_id, partition_reset)| {
+                (partition_id, &partition_reset.from, &partition_reset.to)
+            })
+    }
+
+    /// Returns the number of partitions covered by the checkpoint reset.
+    pub fn num_partitions(&self) -> usize {
+        self.per_partition.len()
+    }
+
+    /// Returns `true` if the checkpoint reset is empty.
     pub fn is_empty(&self) -> bool {
         self.per_partition.is_empty()
     }
@@ -670,6 +923,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_prune_partitions() -> anyhow::Result<()> {
+        let mut checkpoint: SourceCheckpoint = vec![
+            (PartitionId::from("a"), Position::from("00128")),
+            (PartitionId::from("b"), Position::from("60187")),
+        ]
+        .into_iter()
+        .collect();
+        let mut delta = CheckpointDelta::from_partition_delta(
+            PartitionId::from("b"),
+            Position::from("60187"),
+            Position::from("60190"),
+        );
+        delta.record_partition_pruning(PartitionId::from("a"), Position::from("00128"))?;
+        // Pruning a partition missing from the checkpoint is a no-op.
+        delta.record_partition_pruning(PartitionId::from("c"), Position::from("20008"))?;
+        assert_eq!(
+            format!("{:?}", delta),
+            "∆(b:(60187..60190] -a:00128 -c:20008)"
+        );
+        // A pruned partition cannot move anymore.
+        assert!(delta
+            .record_partition_delta(
+                PartitionId::from("a"),
+                Position::from("00128"),
+                Position::from("00130")
+            )
+            .is_err());
+        checkpoint.try_apply_delta(delta)?;
+        assert_eq!(format!("{:?}", checkpoint), "Ckpt(b:60190)");
+
+        // The partition must be at the position the pruning expects.
+        let mut delta = CheckpointDelta::default();
+        delta.record_partition_pruning(PartitionId::from("b"), Position::from("60187"))?;
+        assert_eq!(
+            checkpoint.try_apply_delta(delta),
+            Err(IncompatibleCheckpointDelta {
+                partition_id: PartitionId::from("b"),
+                current_position: Position::from("60190"),
+                delta_position_from: Position::from("60187")
+            })
+        );
+
+        // Pruning a partition moved by the delta cancels the move.
+        let mut delta = CheckpointDelta::from_partition_delta(
+            PartitionId::from("b"),
+            Position::from("60190"),
+            Position::from("60200"),
+        );
+        assert!(delta
+            .record_partition_pruning(PartitionId::from("b"), Position::from("60190"))
+            .is_err());
+        delta.record_partition_pruning(PartitionId::from("b"), Position::from("60200"))?;
+        assert_eq!(format!("{:?}", delta), "∆(-b:60190)");
+        checkpoint.try_apply_delta(delta)?;
+        assert!(checkpoint.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_position_u64() {
         let pos = Position::from(4u64);
@@ -713,4 +1025,106 @@ mod tests {
             &Position::from(43u64)
         );
     }
+
+    #[test]
+    fn test_checkpoint_reset() {
+        let mut checkpoint: SourceCheckpoint = vec![(0u64, 100u64), (1u64, 200u64)]
+            .into_iter()
+            .map(|(partition_id, offset)| (PartitionId::from(partition_id), Position::from(offset)))
+            .collect();
+        {
+            // Partition 0 is not at the expected position.
+            let mut reset = CheckpointReset::default();
+            reset.record_partition_reset(
+                PartitionId::from(1u64),
+                Position::from(200u64),
+                Position::from(50u64),
+            );
+            reset.record_partition_reset(
+                PartitionId::from(0u64),
+                Position::from(99u64),
+                Position::from(50u64),
+            );
+            let error = checkpoint.clone().try_apply_reset(reset).unwrap_err();
+            assert_eq!(
+                error,
+                IncompatibleCheckpointDelta {
+                    partition_id: PartitionId::from(0u64),
+                    current_position: Position::from(100u64),
+                    delta_position_from: Position::from(99u64),
+                }
+            );
+        }
+        let mut reset = CheckpointReset::default();
+        reset.record_partition_reset(
+            PartitionId::from(0u64),
+            Position::from(100u64),
+            Position::Beginning,
+        );
+        reset.record_partition_reset(
+            PartitionId::from(1u64),
+            Position::from(200u64),
+            Position::from(50u64),
+        );
+        reset.record_partition_reset(
+            PartitionId::from(2u64),
+            Position::Beginning,
+            Position::from(300u64),
+        );
+        assert_eq!(reset.num_partitions(), 3);
+        checkpoint.try_apply_reset(reset).unwrap();
+        assert_eq!(
+            format!("{:?}", checkpoint),
+            "Ckpt(00000000000000000001:00000000000000000050 \
+             00000000000000000002:00000000000000000300)"
+        );
+        // Pipelines still reading from the positions preceding the reset cannot publish.
+        for (partition_id, from_position) in [
+            (0u64, Position::from(100u64)),
+            (1u64, Position::from(200u64)),
+            (1u64, Position::from(20u64)),
+        ] {
+            let stale_delta = CheckpointDelta::from_partition_delta(
+                PartitionId::from(partition_id),
+                from_position,
+                Position::from(1_000u64),
+            );
+            assert!(checkpoint.clone().try_apply_delta(stale_delta).is_err());
+        }
+        let delta = CheckpointDelta::from_partition_delta(
+            PartitionId::from(0u64),
+            Position::Beginning,
+            Position::from(10u64),
+        );
+        checkpoint.try_apply_delta(delta).unwrap();
+        let delta = CheckpointDelta::from_partition_delta(
+            PartitionId::from(1u64),
+            Position::from(50u64),
+            Position::from(60u64),
+        );
+        checkpoint.try_apply_delta(delta).unwrap();
+
+        // The reset partitions survive serialization, and a checkpoint without resets keeps the
+        // serialization format of plain positions.
+        let checkpoint_json = serde_json::to_value(&checkpoint).unwrap();
+        assert_eq!(
+            checkpoint_json["reset_partitions"],
+            serde_json::json!([
+                "00000000000000000000",
+                "00000000000000000001",
+                "00000000000000000002"
+            ])
+        );
+        let deserialized_checkpoint: SourceCheckpoint =
+            serde_json::from_value(checkpoint_json).unwrap();
+        assert_eq!(deserialized_checkpoint, checkpoint);
+        let checkpoint_without_reset: SourceCheckpoint =
+            vec![(PartitionId::from(0u64), Position::from(1u64))]
+                .into_iter()
+                .collect();
+        assert_eq!(
+            serde_json::to_value(&checkpoint_without_reset).unwrap(),
+            serde_json::json!({"00000000000000000000": "00000000000000000001"})
+        );
+    }
 }
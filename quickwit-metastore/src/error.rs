@@ -75,7 +75,7 @@ pub enum MetastoreError {
     #[error("Source `{source_id}` does not exist.")]
     SourceDoesNotExist { source_id: String },
 
-    #[cfg(feature = "postgres")]
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
     #[error("Database error: {0:?}.")]
     DbError(#[from] sqlx::Error),
 }
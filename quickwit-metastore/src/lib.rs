@@ -34,15 +34,25 @@ mod split_metadata_version;
 pub mod checkpoint;
 mod error;
 mod metastore;
+mod metastore_archive;
 mod metastore_resolver;
 
 pub use error::{MetastoreError, MetastoreResolverError, MetastoreResult};
 pub use metastore::file_backed_metastore::FileBackedMetastore;
 #[cfg(feature = "postgres")]
 pub use metastore::postgresql_metastore::PostgresqlMetastore;
+#[cfg(feature = "sqlite")]
+pub use metastore::sqlite_metastore::SqliteMetastore;
 #[cfg(feature = "testsuite")]
 pub use metastore::MockMetastore;
-pub use metastore::{file_backed_metastore, IndexMetadata, Metastore};
+pub use metastore::{
+    file_backed_metastore, IndexMetadata, IndexStats, ListSplitsQuery, Metastore, SourceStats,
+    SplitCounts, SplitsPage,
+};
+pub use metastore_archive::{
+    check_split_files, export_metastore, import_metastore, IndexArchive, MetastoreArchive,
+    MissingSplitFile,
+};
 pub use metastore_resolver::{
     quickwit_metastore_uri_resolver, MetastoreFactory, MetastoreUriResolver,
 };
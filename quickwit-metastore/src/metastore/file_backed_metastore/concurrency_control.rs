@@ -0,0 +1,357 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use quickwit_storage::{Storage, StorageErrorKind};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::warn;
+
+use crate::{MetastoreError, MetastoreResult};
+
+/// Default duration of the lease on a file of the metastore.
+const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(30);
+
+/// Default delay between writing a lease and reading it back to check that another writer did
+/// not overwrite it.
+const DEFAULT_LEASE_SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Interval between two attempts to acquire a lease held by another writer.
+const LEASE_POLLING_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Protocol used by the [`FileBackedMetastore`](crate::FileBackedMetastore) to prevent
+/// concurrent writers of the metadata file of an index, or of the indexes states file, from
+/// overwriting each other's changes.
+#[derive(Clone, Debug)]
+pub(crate) enum ConcurrencyControl {
+    /// No protection: the metastore must be the only writer of the storage.
+    Disabled,
+    /// Writes are conditioned on the version of the file observed before the mutation. On
+    /// conflict, the file is fetched again and the mutation is retried.
+    ConditionalPut,
+    /// Writers take a lease on the file, then fetch it, mutate it, and release the lease. This is
+    /// the fallback for storages that do not support conditional writes.
+    Lease {
+        lease_duration: Duration,
+        settle_delay: Duration,
+    },
+}
+
+impl ConcurrencyControl {
+    /// Returns the protocol to use for `storage`: conditional writes if the storage supports
+    /// them, and the lease otherwise.
+    pub fn for_storage(storage: &dyn Storage) -> Self {
+        if storage.supports_conditional_put() {
+            ConcurrencyControl::ConditionalPut
+        } else {
+            ConcurrencyControl::Lease {
+                lease_duration: DEFAULT_LEASE_DURATION,
+                settle_delay: DEFAULT_LEASE_SETTLE_DELAY,
+            }
+        }
+    }
+}
+
+/// Content of a lease file.
+#[derive(Serialize, Deserialize)]
+struct LeaseRecord {
+    owner: String,
+    expiration_timestamp_millis: i128,
+}
+
+impl LeaseRecord {
+    fn is_expired(&self) -> bool {
+        self.expiration_timestamp_millis <= now_millis()
+    }
+}
+
+fn now_millis() -> i128 {
+    OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000
+}
+
+/// Returns a lease owner ID unique across processes and across the leases of a process.
+fn new_lease_owner() -> String {
+    static LEASE_COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        OffsetDateTime::now_utc().unix_timestamp_nanos(),
+        LEASE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Lease on a file of the metastore, such as the metadata file of an index, materialized by a
+/// lease file next to it.
+///
+/// Storages without conditional writes cannot create the lease file atomically, so the lease is
+/// written, then read back after a settle delay: the writer whose lease survives holds it. This
+/// is best effort, and it relies on the clocks of the writers being roughly synchronized.
+///
+/// The writes of the protected file do not carry the lease, so writers must call
+/// [`FileLease::check_held`] right before writing: a writer whose lease expired, or is about to
+/// expire, while it was paused gives up instead of overwriting the changes of the next holder.
+pub(crate) struct FileLease {
+    storage: Arc<dyn Storage>,
+    path: PathBuf,
+    owner: String,
+    /// Instant before which the protected file may be written.
+    write_deadline: Instant,
+}
+
+impl FileLease {
+    /// Acquires the lease materialized by the lease file at `path`, waiting for the lease of
+    /// another writer to be released or to expire.
+    pub async fn acquire(
+        storage: Arc<dyn Storage>,
+        path: PathBuf,
+        lease_duration: Duration,
+        settle_delay: Duration,
+    ) -> MetastoreResult<Self> {
+        let owner = new_lease_owner();
+        let deadline = Instant::now() + 2 * lease_duration;
+        loop {
+            if let Some(lease_record) = fetch_lease_record(&*storage, &path).await? {
+                if lease_record.owner != owner && !lease_record.is_expired() {
+                    if Instant::now() >= deadline {
+                        return Err(MetastoreError::InternalError {
+                            message: format!("Failed to acquire the lease `{}`.", path.display()),
+                            cause: anyhow::anyhow!(
+                                "The lease is held by `{}`.",
+                                lease_record.owner
+                            ),
+                        });
+                    }
+                    tokio::time::sleep(LEASE_POLLING_INTERVAL).await;
+                    continue;
+                }
+            }
+            // The protected file must be written well before the lease expires, so that the
+            // write completes before another writer can take the lease over.
+            let write_deadline = Instant::now() + lease_duration / 2;
+            let lease_record = LeaseRecord {
+                owner: owner.clone(),
+                expiration_timestamp_millis: now_millis() + lease_duration.as_millis() as i128,
+            };
+            put_lease_record(&*storage, &path, &lease_record).await?;
+            tokio::time::sleep(settle_delay).await;
+
+            if let Some(lease_record) = fetch_lease_record(&*storage, &path).await? {
+                if lease_record.owner == owner {
+                    return Ok(Self {
+                        storage,
+                        path,
+                        owner,
+                        write_deadline,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Checks that the lease is still held and far enough from its expiration for the protected
+    /// file to be written.
+    pub async fn check_held(&self) -> MetastoreResult<()> {
+        let lease_lost_error = |cause: anyhow::Error| MetastoreError::InternalError {
+            message: format!("Lost the lease `{}`.", self.path.display()),
+            cause,
+        };
+        if Instant::now() >= self.write_deadline {
+            return Err(lease_lost_error(anyhow::anyhow!(
+                "The lease is about to expire."
+            )));
+        }
+        match fetch_lease_record(&*self.storage, &self.path).await? {
+            Some(lease_record) if lease_record.owner == self.owner => Ok(()),
+            Some(lease_record) => Err(lease_lost_error(anyhow::anyhow!(
+                "The lease is held by `{}`.",
+                lease_record.owner
+            ))),
+            None => Err(lease_lost_error(anyhow::anyhow!("The lease was released."))),
+        }
+    }
+
+    /// Releases the lease, unless it expired and was taken over by another writer.
+    pub async fn release(self) {
+        let release_res = async {
+            if let Some(lease_record) = fetch_lease_record(&*self.storage, &self.path).await? {
+                if lease_record.owner == self.owner {
+                    self.storage
+                        .delete(&self.path)
+                        .await
+                        .map_err(|storage_err| MetastoreError::InternalError {
+                            message: format!("Failed to delete lease `{}`.", self.path.display()),
+                            cause: anyhow::anyhow!(storage_err),
+                        })?;
+                }
+            }
+            Ok::<_, MetastoreError>(())
+        }
+        .await;
+        // The lease expires anyway, so failing to release it only delays the other writers.
+        if let Err(error) = release_res {
+            warn!(error = ?error, lease = %self.path.display(), "Failed to release lease.");
+        }
+    }
+}
+
+async fn fetch_lease_record(
+    storage: &dyn Storage,
+    path: &Path,
+) -> MetastoreResult<Option<LeaseRecord>> {
+    let content = match storage.get_all(path).await {
+        Ok(content) => content,
+        Err(storage_err) if storage_err.kind() == StorageErrorKind::DoesNotExist => {
+            return Ok(None);
+        }
+        Err(storage_err) => {
+            return Err(MetastoreError::InternalError {
+                message: format!("Failed to get lease `{}`.", path.display()),
+                cause: anyhow::anyhow!(storage_err),
+            });
+        }
+    };
+    let lease_record = serde_json::from_slice(&content[..])
+        .map_err(|serde_err| MetastoreError::InvalidManifest { cause: serde_err })?;
+    Ok(Some(lease_record))
+}
+
+async fn put_lease_record(
+    storage: &dyn Storage,
+    path: &Path,
+    lease_record: &LeaseRecord,
+) -> MetastoreResult<()> {
+    let content =
+        serde_json::to_vec(lease_record).map_err(|serde_err| MetastoreError::InternalError {
+            message: "Failed to serialize lease".to_string(),
+            cause: anyhow::anyhow!(serde_err),
+        })?;
+    storage
+        .put(path, Box::new(content))
+        .await
+        .map_err(|storage_err| MetastoreError::InternalError {
+            message: format!("Failed to put lease `{}`.", path.display()),
+            cause: anyhow::anyhow!(storage_err),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_storage::RamStorage;
+
+    use super::*;
+    use crate::metastore::file_backed_metastore::store_operations::lease_path;
+
+    #[tokio::test]
+    async fn test_index_lease() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let lease_duration = Duration::from_millis(300);
+        let settle_delay = Duration::from_millis(10);
+        let lease = FileLease::acquire(
+            storage.clone(),
+            lease_path("my-index"),
+            lease_duration,
+            settle_delay,
+        )
+        .await
+        .unwrap();
+        assert!(storage.exists(&lease_path("my-index")).await.unwrap());
+        lease.check_held().await.unwrap();
+
+        // A lease on another index is independent.
+        let other_lease = FileLease::acquire(
+            storage.clone(),
+            lease_path("other-index"),
+            lease_duration,
+            settle_delay,
+        )
+        .await
+        .unwrap();
+        other_lease.release().await;
+
+        // The second writer waits for the release of the lease.
+        let start = Instant::now();
+        let release_handle = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            lease.release().await;
+        });
+        let second_lease = FileLease::acquire(
+            storage.clone(),
+            lease_path("my-index"),
+            lease_duration,
+            settle_delay,
+        )
+        .await
+        .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        release_handle.await.unwrap();
+        second_lease.release().await;
+        assert!(!storage.exists(&lease_path("my-index")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_index_lease_expires() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let lease_duration = Duration::from_millis(100);
+        let settle_delay = Duration::from_millis(10);
+        let paused_writer_lease = FileLease::acquire(
+            storage.clone(),
+            lease_path("my-index"),
+            lease_duration,
+            settle_delay,
+        )
+        .await
+        .unwrap();
+        let start = Instant::now();
+        let lease = FileLease::acquire(
+            storage.clone(),
+            lease_path("my-index"),
+            lease_duration,
+            settle_delay,
+        )
+        .await
+        .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // The writer whose lease expired while it was paused must not write anymore.
+        let error = paused_writer_lease.check_held().await.unwrap_err();
+        assert!(matches!(error, MetastoreError::InternalError { .. }));
+        lease.release().await;
+    }
+
+    #[tokio::test]
+    async fn test_file_lease_must_be_written_before_half_duration() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let lease = FileLease::acquire(
+            storage.clone(),
+            lease_path("my-index"),
+            Duration::from_millis(200),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        lease.check_held().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(lease.check_held().await.is_err());
+        lease.release().await;
+    }
+}
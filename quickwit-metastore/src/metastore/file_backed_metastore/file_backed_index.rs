@@ -30,8 +30,12 @@ use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-use crate::checkpoint::CheckpointDelta;
-use crate::{IndexMetadata, MetastoreError, MetastoreResult, Split, SplitMetadata, SplitState};
+use crate::checkpoint::{CheckpointDelta, CheckpointReset};
+use crate::split_metadata::utc_now_timestamp;
+use crate::{
+    IndexMetadata, IndexStats, ListSplitsQuery, MetastoreError, MetastoreResult, Split,
+    SplitMetadata, SplitState, SplitsPage,
+};
 
 /// A `FileBackedIndex` object carries an index metadata and its split metadata.
 // This struct is meant to be used only within the [`FileBackedMetastore`]. The public visibility is
@@ -47,6 +51,9 @@ pub struct FileBackedIndex {
     /// it possible to discard this entry if there is an error
     /// while mutating the Index.
     pub discarded: bool,
+    /// Version of the metadata file this index was read from or written to, when the storage
+    /// supports conditional writes.
+    pub(crate) storage_version: Option<String>,
 }
 
 impl From<IndexMetadata> for FileBackedIndex {
@@ -55,6 +62,7 @@ impl From<IndexMetadata> for FileBackedIndex {
             metadata: index_metadata,
             splits: Default::default(),
             discarded: false,
+            storage_version: None,
         }
     }
 }
@@ -127,6 +135,7 @@ impl FileBackedIndex {
                 .map(|split| (split.split_id().to_string(), split))
                 .collect(),
             discarded: false,
+            storage_version: None,
         }
     }
 
@@ -290,8 +299,7 @@ impl FileBackedIndex {
         checkpoint_delta: CheckpointDelta,
     ) -> MetastoreResult<()> {
         self.metadata
-            .checkpoint
-            .try_apply_delta(source_id, checkpoint_delta)?;
+            .try_apply_checkpoint_delta(source_id, checkpoint_delta)?;
         self.mark_splits_as_published_helper(split_ids)?;
         Ok(())
     }
@@ -336,6 +344,14 @@ impl FileBackedIndex {
         Ok(splits)
     }
 
+    pub(crate) fn list_splits_page(&self, query: &ListSplitsQuery) -> MetastoreResult<SplitsPage> {
+        Ok(query.paginate(self.splits.values()))
+    }
+
+    pub(crate) fn index_stats(&self) -> IndexStats {
+        IndexStats::compute(&self.metadata, self.splits.values(), utc_now_timestamp())
+    }
+
     fn delete_split(&mut self, split_id: &str) -> DeleteSplitOutcome {
         let metadata = match self.splits.get_mut(split_id) {
             Some(metadata) => metadata,
@@ -394,4 +410,14 @@ impl FileBackedIndex {
         self.metadata.delete_source(source_id)?;
         Ok(true)
     }
+
+    pub(crate) fn reset_source_checkpoint(
+        &mut self,
+        source_id: &str,
+        checkpoint_reset: CheckpointReset,
+    ) -> MetastoreResult<bool> {
+        self.metadata
+            .reset_source_checkpoint(source_id, checkpoint_reset)?;
+        Ok(true)
+    }
 }
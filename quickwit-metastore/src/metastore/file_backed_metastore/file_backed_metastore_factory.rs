@@ -77,6 +77,24 @@ fn extract_polling_interval_from_uri(uri: &str) -> (String, Option<Duration>) {
     }
 }
 
+/// Extracts the `#concurrency_control=disabled` fragment, which disables the protection of the
+/// file-backed metastore against concurrent writers, from the URI.
+fn extract_concurrency_control_disabled_flag_from_uri(uri: &str) -> (String, bool) {
+    static URI_FRAGMENT_PATTERN: OnceCell<Regex> = OnceCell::new();
+    if let Some(captures) = URI_FRAGMENT_PATTERN
+        .get_or_init(|| Regex::new("^(.*)#concurrency_control=disabled(#.*)?$").unwrap())
+        .captures(uri)
+    {
+        let mut uri_without_fragment = captures.get(1).unwrap().as_str().to_string();
+        if let Some(other_fragments) = captures.get(2) {
+            uri_without_fragment.push_str(other_fragments.as_str());
+        }
+        (uri_without_fragment, true)
+    } else {
+        (uri.to_string(), false)
+    }
+}
+
 impl FileBackedMetastoreFactory {
     async fn get_from_cache(&self, uri: &Uri) -> Option<Arc<dyn Metastore>> {
         let cache_lock = self.cache.lock().await;
@@ -103,7 +121,10 @@ impl FileBackedMetastoreFactory {
 #[async_trait]
 impl MetastoreFactory for FileBackedMetastoreFactory {
     async fn resolve(&self, uri: &str) -> Result<Arc<dyn Metastore>, MetastoreResolverError> {
-        let (uri_stripped, polling_interval_opt) = extract_polling_interval_from_uri(uri);
+        let (uri_without_concurrency_control_flag, concurrency_control_disabled) =
+            extract_concurrency_control_disabled_flag_from_uri(uri);
+        let (uri_stripped, polling_interval_opt) =
+            extract_polling_interval_from_uri(&uri_without_concurrency_control_flag);
         // The Uri has the benefit of canonicalizing our path.
         let uri = quickwit_common::uri::Uri::try_new(&uri_stripped).map_err(|parse_uri_err| {
             MetastoreResolverError::InvalidUri(format!("Invalid uri: {}. {:?}", uri, parse_uri_err))
@@ -130,9 +151,12 @@ impl MetastoreFactory for FileBackedMetastoreFactory {
                     })
                 }
             })?;
-        let file_backed_metastore = FileBackedMetastore::try_new(storage, polling_interval_opt)
+        let mut file_backed_metastore = FileBackedMetastore::try_new(storage, polling_interval_opt)
             .await
             .map_err(MetastoreResolverError::FailedToOpenMetastore)?;
+        if concurrency_control_disabled {
+            file_backed_metastore.disable_concurrency_control();
+        }
         let unique_metastore_for_uri = self
             .cache_metastore(uri, Arc::new(file_backed_metastore))
             .await;
@@ -144,7 +168,9 @@ impl MetastoreFactory for FileBackedMetastoreFactory {
 mod tests {
     use std::time::Duration;
 
-    use crate::metastore::file_backed_metastore::file_backed_metastore_factory::extract_polling_interval_from_uri;
+    use crate::metastore::file_backed_metastore::file_backed_metastore_factory::{
+        extract_concurrency_control_disabled_flag_from_uri, extract_polling_interval_from_uri,
+    };
 
     #[test]
     fn test_extract_polling_interval_from_uri() {
@@ -173,4 +199,36 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_extract_concurrency_control_disabled_flag_from_uri() {
+        assert_eq!(
+            extract_concurrency_control_disabled_flag_from_uri("s3://some-uri"),
+            ("s3://some-uri".to_string(), false)
+        );
+        assert_eq!(
+            extract_concurrency_control_disabled_flag_from_uri(
+                "s3://some-uri#concurrency_control=disabled"
+            ),
+            ("s3://some-uri".to_string(), true)
+        );
+        assert_eq!(
+            extract_concurrency_control_disabled_flag_from_uri(
+                "s3://some-uri#concurrency_control=disabled#polling_interval=10s"
+            ),
+            ("s3://some-uri#polling_interval=10s".to_string(), true)
+        );
+        assert_eq!(
+            extract_concurrency_control_disabled_flag_from_uri(
+                "s3://some-uri#polling_interval=10s#concurrency_control=disabled"
+            ),
+            ("s3://some-uri#polling_interval=10s".to_string(), true)
+        );
+        assert_eq!(
+            extract_concurrency_control_disabled_flag_from_uri(
+                "s3://some-uri#concurrency_control=lease"
+            ),
+            ("s3://some-uri#concurrency_control=lease".to_string(), false)
+        );
+    }
 }
@@ -21,6 +21,7 @@
 //! can import [`FileBackedIndex`] and run backward-compatibility tests. You should not have to
 //! import anything from here directly.
 
+mod concurrency_control;
 pub mod file_backed_index;
 mod file_backed_metastore_factory;
 mod lazy_file_backed_index;
@@ -37,17 +38,21 @@ use quickwit_config::SourceConfig;
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_storage::Storage;
 use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
+use tracing::info;
 
+use self::concurrency_control::{ConcurrencyControl, FileLease};
 use self::file_backed_index::FileBackedIndex;
 pub use self::file_backed_metastore_factory::FileBackedMetastoreFactory;
 use self::lazy_file_backed_index::LazyFileBackedIndex;
 use self::store_operations::{
-    delete_index, fetch_and_build_indexes_states, fetch_index, index_exists, put_index,
-    put_indexes_states,
+    delete_index, fetch_and_build_indexes_states, fetch_index, fetch_index_with_version,
+    index_exists, indexes_states_lease_path, lease_path, put_index, put_index_if_version,
+    put_indexes_states, update_index_state,
 };
-use crate::checkpoint::CheckpointDelta;
+use crate::checkpoint::{CheckpointDelta, CheckpointReset};
 use crate::{
-    IndexMetadata, Metastore, MetastoreError, MetastoreResult, Split, SplitMetadata, SplitState,
+    IndexMetadata, IndexStats, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult, Split,
+    SplitMetadata, SplitState, SplitsPage,
 };
 
 /// State of an index tracked by the metastore.
@@ -87,18 +92,29 @@ pub(crate) enum IndexState {
 /// - on deletion, same story, the metastore deletes an index metadata file present on the storage
 ///   even if the index is not in the map.
 ///
-/// !!! Important note 2: it is strongly advised to restrict the `FileBackedMetastore`
-/// usage to the following use cases:
-/// - testing;
-/// - single-node environment;
-/// - multiple-nodes environment with only one writer and readers. In this case, you must be very
-///   cautious and ensure that your readers are really readers.
+/// !!! Important note 2: if the storage supports conditional writes, writers on different nodes
+/// are protected from overwriting each other's changes to the metadata file of an index: each
+/// write is conditioned on the version of the file the mutation was applied to. On conflict, the
+/// file is fetched again and the mutation is retried.
+/// Otherwise, writers take a lease on the file (`{index_id}/metastore.lease`), fetch it, mutate
+/// it, and release the lease. This fallback is best effort and slows down each write. It can be
+/// disabled with [`FileBackedMetastore::disable_concurrency_control`] when the metastore is the
+/// only writer of the storage.
+///
+/// The `indexes_states.json` file is protected the same way (`indexes_states.lease`), and the
+/// metastore only updates the state of the index it creates or deletes, leaving the states of the
+/// other indexes as they are on the storage.
 pub struct FileBackedMetastore {
     storage: Arc<dyn Storage>,
     per_index_metastores: Arc<RwLock<HashMap<String, IndexState>>>,
     polling_interval_opt: Option<Duration>,
+    concurrency_control: ConcurrencyControl,
 }
 
+/// Maximum number of attempts to write the metadata file of an index, or the indexes states file,
+/// in the presence of concurrent writers.
+const MAX_CONDITIONAL_PUT_ATTEMPTS: usize = 10;
+
 impl FileBackedMetastore {
     /// Creates a [`FileBackedMetastore`] for tests.
     #[doc(hidden)]
@@ -107,6 +123,20 @@ impl FileBackedMetastore {
             storage,
             per_index_metastores: Default::default(),
             polling_interval_opt: None,
+            concurrency_control: ConcurrencyControl::Disabled,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_concurrency_control(
+        storage: Arc<dyn Storage>,
+        concurrency_control: ConcurrencyControl,
+    ) -> Self {
+        Self {
+            storage,
+            per_index_metastores: Default::default(),
+            polling_interval_opt: None,
+            concurrency_control,
         }
     }
 
@@ -117,6 +147,12 @@ impl FileBackedMetastore {
         self.polling_interval_opt = polling_interval_opt;
     }
 
+    /// Disables the protection against concurrent writers, which is only safe if the metastore
+    /// is the only writer of the storage.
+    pub fn disable_concurrency_control(&mut self) {
+        self.concurrency_control = ConcurrencyControl::Disabled;
+    }
+
     #[cfg(test)]
     pub fn storage(&self) -> Arc<dyn Storage> {
         self.storage.clone()
@@ -131,24 +167,73 @@ impl FileBackedMetastore {
         let indexes_map =
             fetch_and_build_indexes_states(storage.clone(), polling_interval_opt).await?;
         let per_index_metastores = Arc::new(RwLock::new(indexes_map));
+        let concurrency_control = ConcurrencyControl::for_storage(&*storage);
         Ok(Self {
             storage,
             per_index_metastores,
             polling_interval_opt,
+            concurrency_control,
         })
     }
 
+    /// Applies `mutation` to the index and writes it to the storage if it has changed.
+    ///
+    /// The mutation may be applied several times, to the latest version of the index, when other
+    /// writers modify the index concurrently.
     async fn mutate(
         &self,
         index_id: &str,
-        mutation: impl FnOnce(&mut FileBackedIndex) -> crate::MetastoreResult<bool>,
+        mutation: impl Fn(&mut FileBackedIndex) -> crate::MetastoreResult<bool>,
     ) -> MetastoreResult<()> {
         let mut locked_index = self.get_locked_index(index_id).await?;
+        match &self.concurrency_control {
+            ConcurrencyControl::Disabled => {
+                self.mutate_and_put(index_id, &mut locked_index, mutation, None)
+                    .await
+            }
+            ConcurrencyControl::ConditionalPut => {
+                self.mutate_and_put_if_version(index_id, &mut locked_index, mutation)
+                    .await
+            }
+            ConcurrencyControl::Lease {
+                lease_duration,
+                settle_delay,
+            } => {
+                let lease = FileLease::acquire(
+                    self.storage.clone(),
+                    lease_path(index_id),
+                    *lease_duration,
+                    *settle_delay,
+                )
+                .await?;
+                let mutation_res = async {
+                    // Other writers may have modified the index before we took the lease.
+                    *locked_index = fetch_index(&*self.storage, index_id).await?;
+                    self.mutate_and_put(index_id, &mut locked_index, mutation, Some(&lease))
+                        .await
+                }
+                .await;
+                lease.release().await;
+                mutation_res
+            }
+        }
+    }
+
+    async fn mutate_and_put(
+        &self,
+        index_id: &str,
+        locked_index: &mut FileBackedIndex,
+        mutation: impl Fn(&mut FileBackedIndex) -> crate::MetastoreResult<bool>,
+        lease_opt: Option<&FileLease>,
+    ) -> MetastoreResult<()> {
         let mut index = locked_index.clone();
         let has_changed = mutation(&mut index)?;
         if !has_changed {
             return Ok(());
         }
+        if let Some(lease) = lease_opt {
+            lease.check_held().await?;
+        }
 
         let put_result = put_index(&*self.storage, &index).await;
         match put_result {
@@ -157,25 +242,111 @@ impl FileBackedMetastore {
                 Ok(())
             }
             err @ Err(_) => {
-                // For some of the error type here, we cannot know for sure
-                // whether the content was written or not.
-                //
-                // Just to be sure, let's discard the cache.
-                let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
+                self.discard_index(index_id, locked_index).await;
+                err
+            }
+        }
+    }
 
-                // At this point, we hold both locks.
-                per_index_metastores_wlock.insert(
-                    index_id.to_string(),
-                    IndexState::Alive(LazyFileBackedIndex::new(
-                        self.storage.clone(),
-                        index_id.to_string(),
-                        self.polling_interval_opt,
-                        None,
-                    )),
-                );
-                locked_index.discarded = true;
+    async fn mutate_and_put_if_version(
+        &self,
+        index_id: &str,
+        locked_index: &mut FileBackedIndex,
+        mutation: impl Fn(&mut FileBackedIndex) -> crate::MetastoreResult<bool>,
+    ) -> MetastoreResult<()> {
+        for _ in 0..MAX_CONDITIONAL_PUT_ATTEMPTS {
+            // The version is unknown for indexes loaded or refreshed with a plain fetch, and
+            // reset after a conflict.
+            if locked_index.storage_version.is_none() {
+                *locked_index = fetch_index_with_version(&*self.storage, index_id).await?;
+            }
+            let mut index = locked_index.clone();
+            let has_changed = mutation(&mut index)?;
+            if !has_changed {
+                return Ok(());
+            }
+            let expected_version = locked_index.storage_version.as_deref();
+            let put_result = put_index_if_version(&*self.storage, &index, expected_version).await;
+            match put_result {
+                Ok(Some(version)) => {
+                    index.storage_version = Some(version);
+                    *locked_index = index;
+                    return Ok(());
+                }
+                Ok(None) => {
+                    info!(index_id = %index_id, "Index modified concurrently, retrying mutation.");
+                    locked_index.storage_version = None;
+                }
+                Err(error) => {
+                    self.discard_index(index_id, locked_index).await;
+                    return Err(error);
+                }
+            }
+        }
+        Err(MetastoreError::InternalError {
+            message: format!("Failed to update index `{}`.", index_id),
+            cause: anyhow::anyhow!(
+                "The index was modified concurrently {} times in a row.",
+                MAX_CONDITIONAL_PUT_ATTEMPTS
+            ),
+        })
+    }
 
-                err
+    /// Discards the cached index after a failed write.
+    async fn discard_index(&self, index_id: &str, locked_index: &mut FileBackedIndex) {
+        // For some of the error type here, we cannot know for sure
+        // whether the content was written or not.
+        //
+        // Just to be sure, let's discard the cache.
+        let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
+
+        // At this point, we hold both locks.
+        per_index_metastores_wlock.insert(
+            index_id.to_string(),
+            IndexState::Alive(LazyFileBackedIndex::new(
+                self.storage.clone(),
+                index_id.to_string(),
+                self.polling_interval_opt,
+                None,
+            )),
+        );
+        locked_index.discarded = true;
+    }
+
+    /// Writes the state of an index, as found in `indexes_states`, to the indexes states file.
+    ///
+    /// Without concurrency control, the metastore is the only writer of the storage and writes
+    /// its whole map of states. Otherwise, it only updates the state of the index, protected by
+    /// conditional writes or by the lease on the file.
+    async fn put_index_state(
+        &self,
+        indexes_states: &HashMap<String, IndexState>,
+        index_id: &str,
+    ) -> MetastoreResult<()> {
+        let index_state_opt = indexes_states.get(index_id);
+        match &self.concurrency_control {
+            ConcurrencyControl::Disabled => {
+                put_indexes_states(&*self.storage, indexes_states).await
+            }
+            ConcurrencyControl::ConditionalPut => {
+                update_index_state(&*self.storage, index_id, index_state_opt, None).await
+            }
+            ConcurrencyControl::Lease {
+                lease_duration,
+                settle_delay,
+            } => {
+                let lease = FileLease::acquire(
+                    self.storage.clone(),
+                    indexes_states_lease_path(),
+                    *lease_duration,
+                    *settle_delay,
+                )
+                .await?;
+                let update_res =
+                    update_index_state(&*self.storage, index_id, index_state_opt, Some(&lease))
+                        .await;
+                lease.release().await;
+                update_res
             }
         }
     }
@@ -299,14 +470,39 @@ impl Metastore for FileBackedMetastore {
 
         // Set state to Creating` and rollback on metastore error.
         per_index_metastores_wlock.insert(index_id.clone(), IndexState::Creating);
-        if let Err(error) = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await {
+        if let Err(error) = self
+            .put_index_state(&per_index_metastores_wlock, &index_id)
+            .await
+        {
             per_index_metastores_wlock.remove(&index_id);
             return Err(error);
         }
 
         // Put index metadata on storage.
-        let index = FileBackedIndex::from(index_metadata);
-        put_index(&*self.storage, &index).await?;
+        let mut index = FileBackedIndex::from(index_metadata);
+        if let ConcurrencyControl::ConditionalPut = self.concurrency_control {
+            // Fails if another node created the index concurrently.
+            index.storage_version = put_index_if_version(&*self.storage, &index, None).await?;
+            if index.storage_version.is_none() {
+                // The index created by the other node is alive: replace the `Creating` state,
+                // which would make the index unavailable, with the state of the index loaded
+                // from the storage.
+                per_index_metastores_wlock.insert(
+                    index_id.clone(),
+                    IndexState::Alive(LazyFileBackedIndex::new(
+                        self.storage.clone(),
+                        index_id.clone(),
+                        self.polling_interval_opt,
+                        None,
+                    )),
+                );
+                self.put_index_state(&per_index_metastores_wlock, &index_id)
+                    .await?;
+                return Err(MetastoreError::IndexAlreadyExists { index_id });
+            }
+        } else {
+            put_index(&*self.storage, &index).await?;
+        }
 
         per_index_metastores_wlock.insert(
             index_id.clone(),
@@ -319,7 +515,9 @@ impl Metastore for FileBackedMetastore {
         );
 
         // Set state to `Alive` and rollback on metastore error.
-        let put_res = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await;
+        let put_res = self
+            .put_index_state(&per_index_metastores_wlock, &index_id)
+            .await;
         if put_res.is_err() {
             per_index_metastores_wlock.insert(index_id.clone(), IndexState::Creating);
         }
@@ -345,7 +543,10 @@ impl Metastore for FileBackedMetastore {
         let index_state_opt =
             per_index_metastores_wlock.insert(index_id.to_string(), IndexState::Deleting);
         // On a put error, reinsert the previous state if any.
-        if let Err(error) = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await {
+        if let Err(error) = self
+            .put_index_state(&per_index_metastores_wlock, index_id)
+            .await
+        {
             if let Some(index_state) = index_state_opt {
                 per_index_metastores_wlock.insert(index_id.to_string(), index_state);
             } else {
@@ -362,7 +563,7 @@ impl Metastore for FileBackedMetastore {
             // but it makes sense to ensure that the index state is removed.
             Err(MetastoreError::IndexDoesNotExist { .. }) => {
                 per_index_metastores_wlock.remove(index_id);
-                if let Err(error) = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await {
+                if let Err(error) = self.put_index_state(&per_index_metastores_wlock, index_id).await {
                     per_index_metastores_wlock.insert(index_id.to_string(), IndexState::Deleting);
                     return Err(error);
                 }
@@ -382,7 +583,21 @@ impl Metastore for FileBackedMetastore {
         split_metadata: SplitMetadata,
     ) -> MetastoreResult<()> {
         self.mutate(index_id, |index| {
-            index.stage_split(split_metadata)?;
+            index.stage_split(split_metadata.clone())?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn stage_splits(
+        &self,
+        index_id: &str,
+        split_metadatas: Vec<SplitMetadata>,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_id, |index| {
+            for split_metadata in &split_metadatas {
+                index.stage_split(split_metadata.clone())?;
+            }
             Ok(true)
         })
         .await
@@ -396,7 +611,7 @@ impl Metastore for FileBackedMetastore {
         checkpoint_delta: CheckpointDelta,
     ) -> MetastoreResult<()> {
         self.mutate(index_id, |index| {
-            index.publish_splits(source_id, split_ids, checkpoint_delta)?;
+            index.publish_splits(source_id, split_ids, checkpoint_delta.clone())?;
             Ok(true)
         })
         .await
@@ -437,7 +652,7 @@ impl Metastore for FileBackedMetastore {
     }
 
     async fn add_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()> {
-        self.mutate(index_id, |index| index.add_source(source))
+        self.mutate(index_id, |index| index.add_source(source.clone()))
             .await
     }
 
@@ -446,6 +661,18 @@ impl Metastore for FileBackedMetastore {
             .await
     }
 
+    async fn reset_source_checkpoint(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        checkpoint_reset: CheckpointReset,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_id, |index| {
+            index.reset_source_checkpoint(source_id, checkpoint_reset.clone())
+        })
+        .await
+    }
+
     /// -------------------------------------------------------------------------------
     /// Read-only accessors
 
@@ -466,6 +693,19 @@ impl Metastore for FileBackedMetastore {
         self.read(index_id, |index| index.list_all_splits()).await
     }
 
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        query: ListSplitsQuery,
+    ) -> MetastoreResult<SplitsPage> {
+        self.read(index_id, |index| index.list_splits_page(&query))
+            .await
+    }
+
+    async fn index_stats(&self, index_id: &str) -> MetastoreResult<IndexStats> {
+        self.read(index_id, |index| Ok(index.index_stats())).await
+    }
+
     async fn index_metadata(&self, index_id: &str) -> MetastoreResult<IndexMetadata> {
         self.read(index_id, |index| Ok(index.metadata().clone()))
             .await
@@ -544,9 +784,11 @@ mod tests {
     use time::OffsetDateTime;
     use tokio::time::Duration;
 
+    use super::concurrency_control::ConcurrencyControl;
     use super::lazy_file_backed_index::LazyFileBackedIndex;
     use super::store_operations::{
-        fetch_and_build_indexes_states, meta_path, put_index_given_index_id, put_indexes_states,
+        fetch_and_build_indexes_states, indexes_states_lease_path, lease_path, meta_path,
+        put_index_given_index_id, put_indexes_states,
     };
     use super::{FileBackedIndex, FileBackedMetastore, IndexState};
     use crate::checkpoint::CheckpointDelta;
@@ -792,6 +1034,209 @@ mod tests {
         panic!("The metastore should have been updated.");
     }
 
+    async fn test_file_backed_metastore_concurrent_writers_aux(
+        storage: Arc<dyn Storage>,
+        metastore_1: FileBackedMetastore,
+        metastore_2: FileBackedMetastore,
+    ) {
+        let index_id = "my-index";
+        let index_metadata = IndexMetadata::for_test(index_id, "ram://indexes/my-index");
+        metastore_1.create_index(index_metadata).await.unwrap();
+
+        // Both metastores cache the index, so each write is based on a stale version of it.
+        assert!(metastore_2
+            .list_all_splits(index_id)
+            .await
+            .unwrap()
+            .is_empty());
+        let metastores = Arc::new([metastore_1, metastore_2]);
+        let handles = (0..6).map(|i| {
+            let metastores = metastores.clone();
+            tokio::spawn(async move {
+                let split_metadata = SplitMetadata {
+                    split_id: format!("split-{}", i),
+                    ..Default::default()
+                };
+                metastores[i % 2]
+                    .stage_split(index_id, split_metadata)
+                    .await
+                    .unwrap();
+            })
+        });
+        futures::future::try_join_all(handles).await.unwrap();
+
+        let metastore = FileBackedMetastore::try_new(storage, None).await.unwrap();
+        let splits = metastore.list_all_splits(index_id).await.unwrap();
+        assert_eq!(splits.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_concurrent_writers_with_conditional_put() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let metastore_1 = FileBackedMetastore::try_new(storage.clone(), None)
+            .await
+            .unwrap();
+        let metastore_2 = FileBackedMetastore::try_new(storage.clone(), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            metastore_1.concurrency_control,
+            ConcurrencyControl::ConditionalPut
+        ));
+        test_file_backed_metastore_concurrent_writers_aux(storage, metastore_1, metastore_2).await;
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_concurrent_writers_with_lease() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let concurrency_control = ConcurrencyControl::Lease {
+            lease_duration: Duration::from_secs(5),
+            settle_delay: Duration::from_millis(10),
+        };
+        let metastore_1 = FileBackedMetastore::with_concurrency_control(
+            storage.clone(),
+            concurrency_control.clone(),
+        );
+        let metastore_2 =
+            FileBackedMetastore::with_concurrency_control(storage.clone(), concurrency_control);
+        test_file_backed_metastore_concurrent_writers_aux(
+            storage.clone(),
+            metastore_1,
+            metastore_2,
+        )
+        .await;
+        assert!(!storage.exists(&lease_path("my-index")).await.unwrap());
+    }
+
+    #[test]
+    fn test_file_backed_metastore_lease_without_conditional_put() {
+        let mut mock_storage = MockStorage::default();
+        mock_storage
+            .expect_supports_conditional_put()
+            .returning(|| false);
+        let mut metastore = FileBackedMetastore::for_test(Arc::new(mock_storage));
+        metastore.concurrency_control = ConcurrencyControl::for_storage(&*metastore.storage);
+        assert!(matches!(
+            metastore.concurrency_control,
+            ConcurrencyControl::Lease { .. }
+        ));
+        metastore.disable_concurrency_control();
+        assert!(matches!(
+            metastore.concurrency_control,
+            ConcurrencyControl::Disabled
+        ));
+
+        // Conditional writes prevail over the lease.
+        let storage = RamStorage::default();
+        assert!(matches!(
+            ConcurrencyControl::for_storage(&storage),
+            ConcurrencyControl::ConditionalPut
+        ));
+    }
+
+    async fn test_file_backed_metastore_concurrent_index_creations_aux(
+        storage: Arc<dyn Storage>,
+        metastore_1: FileBackedMetastore,
+        metastore_2: FileBackedMetastore,
+    ) {
+        let index_metadata_1 = IndexMetadata::for_test("my-index-1", "ram://indexes/my-index-1");
+        let index_metadata_2 = IndexMetadata::for_test("my-index-2", "ram://indexes/my-index-2");
+        let (create_res_1, create_res_2) = tokio::join!(
+            metastore_1.create_index(index_metadata_1),
+            metastore_2.create_index(index_metadata_2)
+        );
+        create_res_1.unwrap();
+        create_res_2.unwrap();
+
+        // Each metastore only knows about its own index, but the states of both are kept.
+        let indexes_states = fetch_and_build_indexes_states(storage.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(indexes_states.len(), 2);
+        for index_id in ["my-index-1", "my-index-2"] {
+            assert!(matches!(
+                indexes_states.get(index_id),
+                Some(IndexState::Alive(_))
+            ));
+        }
+        let metastore = FileBackedMetastore::try_new(storage, None).await.unwrap();
+        assert_eq!(metastore.list_indexes_metadatas().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_concurrent_index_creations_with_conditional_put() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let metastore_1 = FileBackedMetastore::try_new(storage.clone(), None)
+            .await
+            .unwrap();
+        let metastore_2 = FileBackedMetastore::try_new(storage.clone(), None)
+            .await
+            .unwrap();
+        test_file_backed_metastore_concurrent_index_creations_aux(
+            storage,
+            metastore_1,
+            metastore_2,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_concurrent_index_creations_with_lease() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let concurrency_control = ConcurrencyControl::Lease {
+            lease_duration: Duration::from_secs(5),
+            settle_delay: Duration::from_millis(10),
+        };
+        let metastore_1 = FileBackedMetastore::with_concurrency_control(
+            storage.clone(),
+            concurrency_control.clone(),
+        );
+        let metastore_2 =
+            FileBackedMetastore::with_concurrency_control(storage.clone(), concurrency_control);
+        test_file_backed_metastore_concurrent_index_creations_aux(
+            storage.clone(),
+            metastore_1,
+            metastore_2,
+        )
+        .await;
+        assert!(!storage.exists(&indexes_states_lease_path()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_create_index_with_conditional_put() {
+        let storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let metastore_1 = FileBackedMetastore::try_new(storage.clone(), None)
+            .await
+            .unwrap();
+        let metastore_2 = FileBackedMetastore::try_new(storage.clone(), None)
+            .await
+            .unwrap();
+        let index_id = "my-index";
+        let index_metadata = IndexMetadata::for_test(index_id, "ram://indexes/my-index");
+        metastore_1
+            .create_index(index_metadata.clone())
+            .await
+            .unwrap();
+        // The second metastore does not know about the index, but must not overwrite it.
+        {
+            let mut per_index_metastores_wlock = metastore_2.per_index_metastores.write().await;
+            per_index_metastores_wlock.insert(index_id.to_string(), IndexState::Creating);
+        }
+        let metastore_error = metastore_2.create_index(index_metadata).await.unwrap_err();
+        assert!(matches!(
+            metastore_error,
+            MetastoreError::IndexAlreadyExists { .. }
+        ));
+        // The index created by the first metastore is neither left in the `Creating` state in
+        // the second metastore nor in the indexes states file.
+        metastore_2.get_index(index_id).await.unwrap();
+        let indexes_states = fetch_and_build_indexes_states(storage, None).await.unwrap();
+        assert!(matches!(
+            indexes_states.get(index_id),
+            Some(IndexState::Alive(_))
+        ));
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 3)]
     async fn test_file_backed_metastore_race_condition() {
         let metastore = Arc::new(FileBackedMetastore::default_for_test().await);
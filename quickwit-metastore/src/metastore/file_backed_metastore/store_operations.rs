@@ -24,8 +24,10 @@ use std::time::Duration;
 
 use quickwit_storage::{Storage, StorageError, StorageErrorKind};
 use serde::{Deserialize, Serialize};
+use tracing::info;
 
-use super::{IndexState, LazyFileBackedIndex};
+use super::concurrency_control::FileLease;
+use super::{IndexState, LazyFileBackedIndex, MAX_CONDITIONAL_PUT_ATTEMPTS};
 use crate::metastore::file_backed_metastore::file_backed_index::FileBackedIndex;
 use crate::{MetastoreError, MetastoreResult};
 
@@ -35,6 +37,12 @@ const INDEXES_STATES_FILENAME: &str = "indexes_states.json";
 /// Index metadata file managed by [`FileBackedMetastore`](crate::FileBackedMetastore).
 const META_FILENAME: &str = "metastore.json";
 
+/// Lease file on the index metadata file, see [`FileLease`].
+const LEASE_FILENAME: &str = "metastore.lease";
+
+/// Lease file on the indexes states file, see [`FileLease`].
+const INDEXES_STATES_LEASE_FILENAME: &str = "indexes_states.lease";
+
 /// Index state used for serialization/deserialization only.
 #[derive(Serialize, Deserialize)]
 enum IndexStateValue {
@@ -58,6 +66,16 @@ pub(crate) fn meta_path(index_id: &str) -> PathBuf {
     Path::new(index_id).join(META_FILENAME)
 }
 
+/// Path to the lease file from the given index ID.
+pub(crate) fn lease_path(index_id: &str) -> PathBuf {
+    Path::new(index_id).join(LEASE_FILENAME)
+}
+
+/// Path to the lease file on the indexes states file.
+pub(crate) fn indexes_states_lease_path() -> PathBuf {
+    PathBuf::from(INDEXES_STATES_LEASE_FILENAME)
+}
+
 fn convert_error(index_id: &str, storage_err: StorageError) -> MetastoreError {
     match storage_err.kind() {
         StorageErrorKind::DoesNotExist => MetastoreError::IndexDoesNotExist {
@@ -141,6 +159,100 @@ pub(crate) async fn put_indexes_states(
     Ok(())
 }
 
+/// Fetches the indexes states file, along with its version if the storage supports conditional
+/// writes. Returns an empty map if the file does not exist.
+async fn fetch_indexes_states_values(
+    storage: &dyn Storage,
+) -> MetastoreResult<(HashMap<String, IndexStateValue>, Option<String>)> {
+    let indexes_list_path = Path::new(INDEXES_STATES_FILENAME);
+    let fetch_res = if storage.supports_conditional_put() {
+        storage
+            .get_all_with_version(indexes_list_path)
+            .await
+            .map(|(content, version)| (content, Some(version)))
+    } else {
+        storage
+            .get_all(indexes_list_path)
+            .await
+            .map(|content| (content, None))
+    };
+    let (content, version_opt) = match fetch_res {
+        Ok(content_and_version) => content_and_version,
+        Err(storage_err) if storage_err.kind() == StorageErrorKind::DoesNotExist => {
+            return Ok((HashMap::new(), None));
+        }
+        Err(storage_err) => {
+            return Err(MetastoreError::InternalError {
+                message: format!("Failed to get {INDEXES_STATES_FILENAME} file."),
+                cause: anyhow::anyhow!(storage_err),
+            });
+        }
+    };
+    let indexes_states_values = serde_json::from_slice(&content[..])
+        .map_err(|serde_err| MetastoreError::InvalidManifest { cause: serde_err })?;
+    Ok((indexes_states_values, version_opt))
+}
+
+/// Sets the state of an index in the indexes states file, or removes it if `index_state_opt` is
+/// `None`, leaving the states of the other indexes as they are on the storage.
+///
+/// If the storage supports conditional writes, the file is written only if it has not been
+/// modified since it was fetched, and the update is retried otherwise. Other storages must be
+/// protected by `lease_opt`, which is checked right before the write.
+pub(crate) async fn update_index_state(
+    storage: &dyn Storage,
+    index_id: &str,
+    index_state_opt: Option<&IndexState>,
+    lease_opt: Option<&FileLease>,
+) -> MetastoreResult<()> {
+    let indexes_list_path = Path::new(INDEXES_STATES_FILENAME);
+    for _ in 0..MAX_CONDITIONAL_PUT_ATTEMPTS {
+        let (mut indexes_states_values, version_opt) = fetch_indexes_states_values(storage).await?;
+        if let Some(index_state) = index_state_opt {
+            indexes_states_values.insert(index_id.to_string(), IndexStateValue::from(index_state));
+        } else {
+            indexes_states_values.remove(index_id);
+        }
+        let content: Vec<u8> =
+            serde_json::to_vec_pretty(&indexes_states_values).map_err(|serde_err| {
+                MetastoreError::InternalError {
+                    message: "Failed to serialize indexes map".to_string(),
+                    cause: anyhow::anyhow!(serde_err),
+                }
+            })?;
+        if let Some(lease) = lease_opt {
+            lease.check_held().await?;
+        }
+        let put_res = if storage.supports_conditional_put() {
+            storage
+                .put_if_version(indexes_list_path, Box::new(content), version_opt.as_deref())
+                .await
+                .map(|_| ())
+        } else {
+            storage.put(indexes_list_path, Box::new(content)).await
+        };
+        match put_res {
+            Ok(()) => return Ok(()),
+            Err(storage_err) if storage_err.kind() == StorageErrorKind::PreconditionFailed => {
+                info!(index_id = %index_id, "Indexes states modified concurrently, retrying update.");
+            }
+            Err(storage_err) => {
+                return Err(MetastoreError::InternalError {
+                    message: format!("Failed to put {INDEXES_STATES_FILENAME} file."),
+                    cause: anyhow::anyhow!(storage_err),
+                });
+            }
+        }
+    }
+    Err(MetastoreError::InternalError {
+        message: format!("Failed to update the state of index `{}`.", index_id),
+        cause: anyhow::anyhow!(
+            "The indexes states were modified concurrently {} times in a row.",
+            MAX_CONDITIONAL_PUT_ATTEMPTS
+        ),
+    })
+}
+
 pub(crate) async fn fetch_index(
     storage: &dyn Storage,
     index_id: &str,
@@ -150,8 +262,27 @@ pub(crate) async fn fetch_index(
         .get_all(&metadata_path)
         .await
         .map_err(|storage_err| convert_error(index_id, storage_err))?;
+    deserialize_index(&content[..], index_id)
+}
 
-    let index: FileBackedIndex = serde_json::from_slice(&content[..])
+/// Fetches the index along with the version of its metadata file. The storage must support
+/// conditional writes.
+pub(crate) async fn fetch_index_with_version(
+    storage: &dyn Storage,
+    index_id: &str,
+) -> MetastoreResult<FileBackedIndex> {
+    let metadata_path = meta_path(index_id);
+    let (content, version) = storage
+        .get_all_with_version(&metadata_path)
+        .await
+        .map_err(|storage_err| convert_error(index_id, storage_err))?;
+    let mut index = deserialize_index(&content[..], index_id)?;
+    index.storage_version = Some(version);
+    Ok(index)
+}
+
+fn deserialize_index(content: &[u8], index_id: &str) -> MetastoreResult<FileBackedIndex> {
+    let index: FileBackedIndex = serde_json::from_slice(content)
         .map_err(|serde_err| MetastoreError::InvalidManifest { cause: serde_err })?;
 
     if index.index_id() != index_id {
@@ -209,6 +340,32 @@ pub(crate) async fn put_index(
     put_index_given_index_id(storage, index, index.index_id()).await
 }
 
+/// Serializes the `Index` object and stores the data on the storage if the version of the
+/// metadata file is still `expected_version`, `None` meaning that the file must not exist.
+///
+/// Returns the version of the new metadata file, or `None` if the condition does not hold.
+pub(crate) async fn put_index_if_version(
+    storage: &dyn Storage,
+    index: &FileBackedIndex,
+    expected_version: Option<&str>,
+) -> MetastoreResult<Option<String>> {
+    let index_id = index.index_id();
+    let content: Vec<u8> =
+        serde_json::to_vec_pretty(&index).map_err(|serde_err| MetastoreError::InternalError {
+            message: "Failed to serialize Metadata set".to_string(),
+            cause: anyhow::anyhow!(serde_err),
+        })?;
+    let metadata_path = meta_path(index_id);
+    match storage
+        .put_if_version(&metadata_path, Box::new(content), expected_version)
+        .await
+    {
+        Ok(version) => Ok(Some(version)),
+        Err(storage_err) if storage_err.kind() == StorageErrorKind::PreconditionFailed => Ok(None),
+        Err(storage_err) => Err(convert_error(index_id, storage_err)),
+    }
+}
+
 /// Serializes the Index and stores the data on the storage.
 pub(crate) async fn delete_index(storage: &dyn Storage, index_id: &str) -> MetastoreResult<()> {
     let metadata_path = meta_path(index_id);
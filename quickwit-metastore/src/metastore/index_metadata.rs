@@ -18,7 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use itertools::Itertools;
 use quickwit_config::{
@@ -27,7 +27,9 @@ use quickwit_config::{
 use quickwit_doc_mapper::SortOrder;
 use serde::{Deserialize, Serialize};
 
-use crate::checkpoint::IndexCheckpoint;
+use crate::checkpoint::{
+    CheckpointDelta, CheckpointReset, IncompatibleCheckpointDelta, IndexCheckpoint,
+};
 use crate::split_metadata::utc_now_timestamp;
 use crate::{MetastoreError, MetastoreResult};
 
@@ -43,6 +45,8 @@ pub struct IndexMetadata {
     /// Checkpoint relative to a source or a set of sources. It expresses up to which point
     /// documents have been indexed.
     pub checkpoint: IndexCheckpoint,
+    /// Time at which the checkpoint of each source last advanced, keyed by `source_id`.
+    pub checkpoint_update_timestamps: BTreeMap<String, i64>,
     /// Describes how ingested JSON documents are indexed.
     pub doc_mapping: DocMapping,
     /// Configures various indexing settings such as commit timeout, max split size, indexing
@@ -140,6 +144,7 @@ impl IndexMetadata {
             index_id: index_id.to_string(),
             index_uri: index_uri.to_string(),
             checkpoint: Default::default(),
+            checkpoint_update_timestamps: Default::default(),
             doc_mapping,
             indexing_settings,
             search_settings,
@@ -170,6 +175,43 @@ impl IndexMetadata {
                 source_id: source_id.to_string(),
             })?;
         self.checkpoint.remove_source(source_id);
+        self.checkpoint_update_timestamps.remove(source_id);
+        Ok(())
+    }
+
+    /// Resets the checkpoint of a source. Fails if the source is unknown, or if the reset is not
+    /// compatible with the current checkpoint.
+    pub(crate) fn reset_source_checkpoint(
+        &mut self,
+        source_id: &str,
+        checkpoint_reset: CheckpointReset,
+    ) -> MetastoreResult<()> {
+        if !self.sources.contains_key(source_id)
+            && self.checkpoint.source_checkpoint(source_id).is_none()
+        {
+            return Err(MetastoreError::SourceDoesNotExist {
+                source_id: source_id.to_string(),
+            });
+        }
+        self.checkpoint
+            .try_apply_reset(source_id, checkpoint_reset)?;
+        Ok(())
+    }
+
+    /// Applies a checkpoint delta to the checkpoint of a source, and records the time at which
+    /// the checkpoint advanced.
+    pub(crate) fn try_apply_checkpoint_delta(
+        &mut self,
+        source_id: &str,
+        checkpoint_delta: CheckpointDelta,
+    ) -> Result<(), IncompatibleCheckpointDelta> {
+        let is_empty_delta = checkpoint_delta.is_empty();
+        self.checkpoint
+            .try_apply_delta(source_id, checkpoint_delta)?;
+        if !is_empty_delta {
+            self.checkpoint_update_timestamps
+                .insert(source_id.to_string(), utc_now_timestamp());
+        }
         Ok(())
     }
 }
@@ -200,6 +242,9 @@ pub(crate) struct IndexMetadataV1 {
     pub index_id: String,
     pub index_uri: String,
     pub checkpoint: IndexCheckpoint,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checkpoint_update_timestamps: BTreeMap<String, i64>,
     pub doc_mapping: DocMapping,
     #[serde(default)]
     pub indexing_settings: IndexingSettings,
@@ -224,6 +269,7 @@ impl From<IndexMetadata> for IndexMetadataV1 {
             index_id: index_metadata.index_id,
             index_uri: index_metadata.index_uri,
             checkpoint: index_metadata.checkpoint,
+            checkpoint_update_timestamps: index_metadata.checkpoint_update_timestamps,
             doc_mapping: index_metadata.doc_mapping,
             indexing_settings: index_metadata.indexing_settings,
             search_settings: index_metadata.search_settings,
@@ -245,6 +291,7 @@ impl From<IndexMetadataV1> for IndexMetadata {
             index_id: v1.index_id,
             index_uri: v1.index_uri,
             checkpoint: v1.checkpoint,
+            checkpoint_update_timestamps: v1.checkpoint_update_timestamps,
             doc_mapping: v1.doc_mapping,
             indexing_settings: v1.indexing_settings,
             search_settings: v1.search_settings,
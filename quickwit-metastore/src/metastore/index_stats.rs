@@ -0,0 +1,303 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{IndexMetadata, Split, SplitMetadata, SplitState};
+
+/// Statistics of an index returned by [`Metastore::index_stats`](crate::Metastore::index_stats).
+///
+/// Document counts, sizes, time range and tag cardinalities only account for published splits.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexStats {
+    /// Index ID.
+    pub index_id: String,
+    /// Number of splits in each state.
+    pub num_splits: SplitCounts,
+    /// Number of documents.
+    pub num_docs: u64,
+    /// Sum of the sizes of the raw documents.
+    pub uncompressed_docs_size_in_bytes: u64,
+    /// Sum of the sizes of the split files on the storage.
+    pub split_size_in_bytes: u64,
+    /// Smallest range of timestamps covering the time ranges of the splits, if the index has a
+    /// timestamp field.
+    pub time_range: Option<RangeInclusive<i64>>,
+    /// Statistics of the checkpoint of each source, sorted by source ID.
+    pub sources: Vec<SourceStats>,
+    /// Number of distinct values recorded in the tags of the splits, keyed by tag field. The
+    /// demux field is a tag field.
+    ///
+    /// Splits do not record the values of the fields exceeding the maximum number of values per
+    /// tag field, so this is a lower bound.
+    pub tag_cardinalities: BTreeMap<String, usize>,
+}
+
+/// Number of splits in each state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitCounts {
+    /// Number of [`SplitState::Staged`] splits.
+    pub staged: usize,
+    /// Number of [`SplitState::Published`] splits.
+    pub published: usize,
+    /// Number of [`SplitState::MarkedForDeletion`] splits.
+    pub marked_for_deletion: usize,
+}
+
+impl SplitCounts {
+    pub(crate) fn add(&mut self, split_state: SplitState, num_splits: usize) {
+        match split_state {
+            SplitState::Staged => self.staged += num_splits,
+            SplitState::Published => self.published += num_splits,
+            SplitState::MarkedForDeletion => self.marked_for_deletion += num_splits,
+        }
+    }
+}
+
+/// Statistics of the checkpoint of a source.
+///
+/// The lag of the source is not reported: the metastore does not know the end positions of the
+/// partitions, such as the high watermarks of Kafka partitions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStats {
+    /// Source ID.
+    pub source_id: String,
+    /// Number of partitions recorded in the checkpoint.
+    pub num_partitions: usize,
+    /// Time at which the checkpoint last advanced, or `None` if it never did.
+    pub checkpoint_update_timestamp: Option<i64>,
+    /// Number of seconds elapsed since the checkpoint last advanced. This is not the lag of the
+    /// source: an idle source does not advance its checkpoint.
+    pub secs_since_checkpoint_update: Option<i64>,
+}
+
+impl IndexStats {
+    /// Creates the statistics of an index holding no splits.
+    ///
+    /// `now_timestamp` is the time against which the ages of the checkpoints are measured.
+    pub(crate) fn new(index_metadata: &IndexMetadata, now_timestamp: i64) -> Self {
+        let source_ids: BTreeSet<&str> = index_metadata
+            .sources
+            .keys()
+            .map(String::as_str)
+            .chain(index_metadata.checkpoint.source_ids())
+            .collect();
+        let sources = source_ids
+            .into_iter()
+            .map(|source_id| {
+                let num_partitions = index_metadata
+                    .checkpoint
+                    .source_checkpoint(source_id)
+                    .map(|source_checkpoint| source_checkpoint.num_partitions())
+                    .unwrap_or(0);
+                let checkpoint_update_timestamp = index_metadata
+                    .checkpoint_update_timestamps
+                    .get(source_id)
+                    .copied();
+                SourceStats {
+                    source_id: source_id.to_string(),
+                    num_partitions,
+                    checkpoint_update_timestamp,
+                    secs_since_checkpoint_update: checkpoint_update_timestamp
+                        .map(|timestamp| (now_timestamp - timestamp).max(0)),
+                }
+            })
+            .collect();
+        IndexStats {
+            index_id: index_metadata.index_id.clone(),
+            sources,
+            ..Default::default()
+        }
+    }
+
+    /// Computes the statistics of an index out of its splits.
+    ///
+    /// This is meant for metastores holding the splits of an index in memory.
+    pub(crate) fn compute<'a>(
+        index_metadata: &IndexMetadata,
+        splits: impl Iterator<Item = &'a Split>,
+        now_timestamp: i64,
+    ) -> Self {
+        let mut index_stats = IndexStats::new(index_metadata, now_timestamp);
+        let mut tag_values = TagValues::default();
+        for split in splits {
+            index_stats.num_splits.add(split.split_state, 1);
+            if split.split_state == SplitState::Published {
+                index_stats.add_published_split(&split.split_metadata, &mut tag_values);
+            }
+        }
+        index_stats.tag_cardinalities = tag_values.cardinalities();
+        index_stats
+    }
+
+    /// Accounts for the metadata of a published split. The split itself must be counted
+    /// separately, in `num_splits`.
+    pub(crate) fn add_published_split(
+        &mut self,
+        split_metadata: &SplitMetadata,
+        tag_values: &mut TagValues,
+    ) {
+        self.num_docs += split_metadata.num_docs as u64;
+        self.uncompressed_docs_size_in_bytes += split_metadata.uncompressed_docs_size_in_bytes;
+        self.split_size_in_bytes += split_metadata.footer_offsets.end;
+        if let Some(split_time_range) = &split_metadata.time_range {
+            self.time_range = Some(match self.time_range.take() {
+                Some(time_range) => {
+                    *time_range.start().min(split_time_range.start())
+                        ..=*time_range.end().max(split_time_range.end())
+                }
+                None => split_time_range.clone(),
+            });
+        }
+        tag_values.add_tags(&split_metadata.tags);
+    }
+}
+
+/// Distinct tag values, keyed by tag field.
+#[derive(Default)]
+pub(crate) struct TagValues {
+    per_field: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl TagValues {
+    /// Records tags of the form `{field_name}:{field_value}` and `{field_name}!`.
+    pub(crate) fn add_tags<'a>(&mut self, tags: impl IntoIterator<Item = &'a String>) {
+        for tag in tags {
+            if let Some(field_name) = tag.strip_suffix('!') {
+                self.per_field.entry(field_name.to_string()).or_default();
+            } else if let Some((field_name, field_value)) = tag.split_once(':') {
+                self.per_field
+                    .entry(field_name.to_string())
+                    .or_default()
+                    .insert(field_value.to_string());
+            }
+        }
+    }
+
+    pub(crate) fn cardinalities(self) -> BTreeMap<String, usize> {
+        self.per_field
+            .into_iter()
+            .map(|(field_name, field_values)| (field_name, field_values.len()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{IndexStats, SourceStats, SplitCounts};
+    use crate::checkpoint::CheckpointDelta;
+    use crate::{IndexMetadata, Split, SplitMetadata, SplitState};
+
+    fn make_split(
+        split_id: &str,
+        split_state: SplitState,
+        time_range_start: i64,
+        tags: &[&str],
+    ) -> Split {
+        Split {
+            split_state,
+            update_timestamp: 0,
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                num_docs: 10,
+                uncompressed_docs_size_in_bytes: 1_000,
+                time_range: Some(time_range_start..=time_range_start + 100),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                footer_offsets: 400..500,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_index_stats_compute() {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        index_metadata
+            .try_apply_checkpoint_delta("source-1", CheckpointDelta::from(0..10))
+            .unwrap();
+        index_metadata
+            .checkpoint_update_timestamps
+            .insert("source-1".to_string(), 1_000);
+        index_metadata.checkpoint.add_source("source-2");
+
+        let splits = vec![
+            make_split(
+                "split-1",
+                SplitState::Published,
+                100,
+                &["owner!", "owner:a"],
+            ),
+            make_split(
+                "split-2",
+                SplitState::Published,
+                0,
+                &["owner!", "owner:a", "owner:b", "tenant!"],
+            ),
+            make_split("split-3", SplitState::Staged, -1_000, &["owner:c"]),
+            make_split("split-4", SplitState::MarkedForDeletion, 1_000, &[]),
+        ];
+        let index_stats = IndexStats::compute(&index_metadata, splits.iter(), 1_042);
+        let expected_index_stats = IndexStats {
+            index_id: "test-index".to_string(),
+            num_splits: SplitCounts {
+                staged: 1,
+                published: 2,
+                marked_for_deletion: 1,
+            },
+            num_docs: 20,
+            uncompressed_docs_size_in_bytes: 2_000,
+            split_size_in_bytes: 1_000,
+            time_range: Some(0..=200),
+            sources: vec![
+                SourceStats {
+                    source_id: "source-1".to_string(),
+                    num_partitions: 1,
+                    checkpoint_update_timestamp: Some(1_000),
+                    secs_since_checkpoint_update: Some(42),
+                },
+                SourceStats {
+                    source_id: "source-2".to_string(),
+                    num_partitions: 0,
+                    checkpoint_update_timestamp: None,
+                    secs_since_checkpoint_update: None,
+                },
+            ],
+            tag_cardinalities: BTreeMap::from_iter([
+                ("owner".to_string(), 2),
+                ("tenant".to_string(), 0),
+            ]),
+        };
+        assert_eq!(index_stats, expected_index_stats);
+    }
+
+    #[test]
+    fn test_index_stats_compute_empty_index() {
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        let index_stats = IndexStats::compute(&index_metadata, std::iter::empty(), 0);
+        assert_eq!(index_stats.num_splits, SplitCounts::default());
+        assert!(index_stats.time_range.is_none());
+        assert!(index_stats.sources.is_empty());
+        assert!(index_stats.tag_cardinalities.is_empty());
+    }
+}
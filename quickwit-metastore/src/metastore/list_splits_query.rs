@@ -0,0 +1,239 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use serde::{Deserialize, Serialize};
+
+use crate::{Split, SplitState};
+
+/// Filters and paginates the splits listed by
+/// [`Metastore::list_splits_page`](crate::Metastore::list_splits_page).
+///
+/// Splits are listed in the order of their IDs. A page starts after the split ID `cursor` and
+/// holds at most `limit` splits.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListSplitsQuery {
+    /// Keeps the splits in one of these states. All the states are kept if empty.
+    pub split_states: Vec<SplitState>,
+    /// Keeps the splits whose time range intersects this range. Splits without time range are
+    /// always kept.
+    pub time_range: Option<Range<i64>>,
+    /// Keeps the splits whose tags match this filter.
+    pub tags: Option<TagFilterAst>,
+    /// Keeps the splits created within this range of Unix timestamps (in seconds).
+    pub create_timestamp: Option<Range<i64>>,
+    /// Keeps the splits last updated within this range of Unix timestamps (in seconds).
+    pub update_timestamp: Option<Range<i64>>,
+    /// Keeps the splits whose ID starts with this prefix.
+    pub split_id_prefix: Option<String>,
+    /// Returns the splits whose ID is greater than this cursor, usually the `next_cursor` of the
+    /// previous page.
+    pub cursor: Option<String>,
+    /// Maximum number of splits returned. All the matching splits are returned if `None`.
+    pub limit: Option<usize>,
+}
+
+impl ListSplitsQuery {
+    /// Returns true if the split matches the filters of the query, regardless of the cursor.
+    pub fn matches(&self, split: &Split) -> bool {
+        if !self.split_states.is_empty() && !self.split_states.contains(&split.split_state) {
+            return false;
+        }
+        if let (Some(time_range), Some(split_time_range)) =
+            (&self.time_range, &split.split_metadata.time_range)
+        {
+            if *split_time_range.end() < time_range.start
+                || *split_time_range.start() >= time_range.end
+            {
+                return false;
+            }
+        }
+        if let Some(tags) = &self.tags {
+            if !tags.evaluate(&split.split_metadata.tags) {
+                return false;
+            }
+        }
+        if let Some(create_timestamp) = &self.create_timestamp {
+            if !create_timestamp.contains(&split.split_metadata.create_timestamp) {
+                return false;
+            }
+        }
+        if let Some(update_timestamp) = &self.update_timestamp {
+            if !update_timestamp.contains(&split.update_timestamp) {
+                return false;
+            }
+        }
+        if let Some(split_id_prefix) = &self.split_id_prefix {
+            if !split.split_id().starts_with(split_id_prefix.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Builds the page of the query out of the splits of an index, in any order.
+    ///
+    /// This is meant for metastores holding the splits of an index in memory.
+    pub(crate) fn paginate<'a>(&self, splits: impl Iterator<Item = &'a Split>) -> SplitsPage {
+        let mut matching_splits: Vec<&Split> = splits
+            .filter(|split| {
+                self.cursor
+                    .as_ref()
+                    .map(|cursor| split.split_id() > cursor.as_str())
+                    .unwrap_or(true)
+            })
+            .filter(|split| self.matches(split))
+            .collect();
+        matching_splits.sort_unstable_by(|left, right| left.split_id().cmp(right.split_id()));
+        let splits = matching_splits
+            .into_iter()
+            .take(self.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        SplitsPage::new(splits, self.limit)
+    }
+}
+
+/// A page of splits returned by
+/// [`Metastore::list_splits_page`](crate::Metastore::list_splits_page).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SplitsPage {
+    /// Splits of the page, sorted by ID.
+    pub splits: Vec<Split>,
+    /// Cursor of the next page, or `None` if this is the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl SplitsPage {
+    /// Creates the page of a query given its splits, sorted by ID.
+    ///
+    /// The page is assumed to be the last one if it holds fewer splits than the limit. In
+    /// the rare case where the last page is full, the next page is empty.
+    pub(crate) fn new(splits: Vec<Split>, limit_opt: Option<usize>) -> SplitsPage {
+        let next_cursor = match (limit_opt, splits.last()) {
+            (Some(limit), Some(last_split)) if splits.len() >= limit => {
+                Some(last_split.split_id().to_string())
+            }
+            _ => None,
+        };
+        SplitsPage {
+            splits,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ListSplitsQuery, SplitsPage};
+    use crate::{Split, SplitMetadata, SplitState};
+
+    fn make_split(split_id: &str, split_state: SplitState, create_timestamp: i64) -> Split {
+        Split {
+            split_state,
+            update_timestamp: create_timestamp + 10,
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                time_range: Some(create_timestamp - 100..=create_timestamp),
+                create_timestamp,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_list_splits_query_matches() {
+        let split = make_split("split-1", SplitState::Published, 1000);
+        assert!(ListSplitsQuery::default().matches(&split));
+
+        let query = ListSplitsQuery {
+            split_states: vec![SplitState::Staged, SplitState::Published],
+            time_range: Some(1000..2000),
+            create_timestamp: Some(1000..1001),
+            update_timestamp: Some(0..1011),
+            split_id_prefix: Some("split-".to_string()),
+            ..Default::default()
+        };
+        assert!(query.matches(&split));
+
+        let mismatching_queries = [
+            ListSplitsQuery {
+                split_states: vec![SplitState::Staged],
+                ..Default::default()
+            },
+            ListSplitsQuery {
+                time_range: Some(1001..2000),
+                ..Default::default()
+            },
+            ListSplitsQuery {
+                create_timestamp: Some(0..1000),
+                ..Default::default()
+            },
+            ListSplitsQuery {
+                update_timestamp: Some(1011..2000),
+                ..Default::default()
+            },
+            ListSplitsQuery {
+                split_id_prefix: Some("split-2".to_string()),
+                ..Default::default()
+            },
+        ];
+        for query in mismatching_queries {
+            assert!(!query.matches(&split), "{query:?}");
+        }
+    }
+
+    #[test]
+    fn test_list_splits_query_paginate() {
+        let splits = vec![
+            make_split("split-3", SplitState::Published, 1000),
+            make_split("split-1", SplitState::Published, 1000),
+            make_split("split-4", SplitState::Staged, 1000),
+            make_split("split-2", SplitState::Published, 1000),
+        ];
+        let split_ids = |page: &SplitsPage| {
+            page.splits
+                .iter()
+                .map(|split| split.split_id().to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut query = ListSplitsQuery {
+            split_states: vec![SplitState::Published],
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = query.paginate(splits.iter());
+        assert_eq!(split_ids(&page), ["split-1", "split-2"]);
+        assert_eq!(page.next_cursor.as_deref(), Some("split-2"));
+
+        query.cursor = page.next_cursor;
+        let page = query.paginate(splits.iter());
+        assert_eq!(split_ids(&page), ["split-3"]);
+        assert!(page.next_cursor.is_none());
+
+        let page = ListSplitsQuery::default().paginate(splits.iter());
+        assert_eq!(
+            split_ids(&page),
+            ["split-1", "split-2", "split-3", "split-4"]
+        );
+        assert!(page.next_cursor.is_none());
+    }
+}
@@ -19,19 +19,29 @@
 
 pub mod file_backed_metastore;
 mod index_metadata;
+mod index_stats;
+mod list_splits_query;
 #[cfg(feature = "postgres")]
 pub mod postgresql_metastore;
 #[cfg(feature = "postgres")]
 mod postgresql_model;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_metastore;
+#[cfg(feature = "sqlite")]
+mod sqlite_model;
 
 use std::ops::Range;
 
 use async_trait::async_trait;
 pub use index_metadata::IndexMetadata;
+pub(crate) use index_stats::TagValues;
+pub use index_stats::{IndexStats, SourceStats, SplitCounts};
+pub use list_splits_query::{ListSplitsQuery, SplitsPage};
 use quickwit_config::SourceConfig;
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 
-use crate::checkpoint::CheckpointDelta;
+use crate::checkpoint::{CheckpointDelta, CheckpointReset};
+use crate::split_metadata::utc_now_timestamp;
 use crate::{MetastoreResult, Split, SplitMetadata, SplitState};
 
 /// Metastore meant to manage Quickwit's indexes and their splits.
@@ -107,6 +117,16 @@ pub trait Metastore: Send + Sync + 'static {
         split_metadata: SplitMetadata,
     ) -> MetastoreResult<()>;
 
+    /// Stages a list of splits.
+    ///
+    /// Either all the splits are staged, or none of them. Staging many splits at once is much
+    /// cheaper than staging them one by one.
+    async fn stage_splits(
+        &self,
+        index_id: &str,
+        split_metadatas: Vec<SplitMetadata>,
+    ) -> MetastoreResult<()>;
+
     /// Publishes a list of splits.
     ///
     /// This API only updates the state of the split from [`SplitState::Staged`] to
@@ -153,6 +173,28 @@ pub trait Metastore: Send + Sync + 'static {
     /// Returns a list of all splits currently known to the metastore regardless of their state.
     async fn list_all_splits(&self, index_id: &str) -> MetastoreResult<Vec<Split>>;
 
+    /// Lists a page of the splits matching a [`ListSplitsQuery`].
+    ///
+    /// Splits are sorted by ID. Pass the `next_cursor` of the returned [`SplitsPage`] as the
+    /// `cursor` of the query to fetch the next page.
+    /// An error will occur if an index that does not exist in the storage is specified.
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        query: ListSplitsQuery,
+    ) -> MetastoreResult<SplitsPage>;
+
+    /// Returns the statistics of an index.
+    ///
+    /// The default implementation computes them out of all the splits of the index. Metastores
+    /// backed by a database should aggregate the splits in the database instead.
+    async fn index_stats(&self, index_id: &str) -> MetastoreResult<IndexStats> {
+        let index_metadata = self.index_metadata(index_id).await?;
+        let splits = self.list_all_splits(index_id).await?;
+        let index_stats = IndexStats::compute(&index_metadata, splits.iter(), utc_now_timestamp());
+        Ok(index_stats)
+    }
+
     /// Marks a list of splits for deletion.
     ///
     /// This API will change the state to [`SplitState::MarkedForDeletion`] so that it is not
@@ -188,6 +230,21 @@ pub trait Metastore: Send + Sync + 'static {
     /// If the checkpoint is missing, this does not trigger an error.
     async fn delete_source(&self, index_id: &str, source_id: &str) -> MetastoreResult<()>;
 
+    /// Resets the checkpoint of a source. Fails with
+    /// [`SourceDoesNotExist`](crate::MetastoreError::SourceDoesNotExist) if the specified source
+    /// does not exist.
+    ///
+    /// Contrary to [`Metastore::publish_splits`], the reset may move positions backward. It fails
+    /// with [`IncompatibleCheckpointDelta`](crate::MetastoreError::IncompatibleCheckpointDelta) if
+    /// a partition is not at the position the reset expects, which happens when an indexing
+    /// pipeline publishes splits in the meantime.
+    async fn reset_source_checkpoint(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        checkpoint_reset: CheckpointReset,
+    ) -> MetastoreResult<()>;
+
     /// Returns the metastore uri.
     fn uri(&self) -> String;
 }
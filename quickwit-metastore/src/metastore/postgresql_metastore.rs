@@ -19,6 +19,7 @@
 
 use std::collections::{HashMap, HashSet};
 use std::ops::Range;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -34,11 +35,13 @@ use tokio::sync::Mutex;
 use tracing::log::LevelFilter;
 use tracing::{debug, error, instrument, warn};
 
+use crate::checkpoint::CheckpointReset;
 use crate::metastore::postgresql_model::{Index, IndexIdSplitIdRow};
-use crate::metastore::{postgresql_model, CheckpointDelta};
+use crate::metastore::{postgresql_model, CheckpointDelta, TagValues};
+use crate::split_metadata::utc_now_timestamp;
 use crate::{
-    IndexMetadata, Metastore, MetastoreError, MetastoreFactory, MetastoreResolverError,
-    MetastoreResult, Split, SplitMetadata, SplitState,
+    IndexMetadata, IndexStats, ListSplitsQuery, Metastore, MetastoreError, MetastoreFactory,
+    MetastoreResolverError, MetastoreResult, Split, SplitMetadata, SplitState, SplitsPage,
 };
 
 static MIGRATOR: Migrator = sqlx::migrate!("migrations/postgresql");
@@ -168,6 +171,47 @@ async fn mark_splits_as_published_helper(
     Ok(published_split_ids)
 }
 
+/// Inserts a new split in the `Staged` state.
+async fn insert_staged_split(
+    tx: &mut Transaction<'_, Postgres>,
+    index_id: &str,
+    metadata: SplitMetadata,
+) -> MetastoreResult<()> {
+    // Fit the time_range to the database model.
+    let time_range_start = metadata.time_range.clone().map(|range| *range.start());
+    let time_range_end = metadata.time_range.clone().map(|range| *range.end());
+
+    // Serialize the split metadata and footer offsets to fit the database model.
+    let split_metadata_json =
+        serde_json::to_string(&metadata).map_err(|err| MetastoreError::InternalError {
+            message: "Failed to serialize split metadata and footer offsets".to_string(),
+            cause: anyhow::anyhow!(err),
+        })?;
+
+    let tags: Vec<String> = metadata.tags.into_iter().collect();
+    sqlx::query(
+        r#"
+        INSERT INTO splits
+            (split_id, split_state, time_range_start, time_range_end, tags, split_metadata_json, index_id)
+        VALUES
+            ($1, $2, $3, $4, $5, $6, $7)
+    "#,
+    )
+    .bind(&metadata.split_id)
+    .bind(&SplitState::Staged.as_str())
+    .bind(time_range_start)
+    .bind(time_range_end)
+    .bind(tags)
+    .bind(split_metadata_json)
+    .bind(index_id)
+    .execute(tx)
+    .await
+    .map_err(|err| convert_sqlx_err(index_id, err))?;
+
+    debug!(index_id=?index_id, split_id=?metadata.split_id, "The split has been staged");
+    Ok(())
+}
+
 /// Mark splits for deletion.
 /// Returns the IDs of the splits that were successfully marked for deletion.
 #[instrument(skip(tx))]
@@ -247,6 +291,200 @@ async fn list_splits_helper(
     splits.into_iter().map(|split| split.try_into()).collect()
 }
 
+/// Returns the `LIKE` pattern matching the split IDs starting with `split_id_prefix`.
+fn split_id_prefix_pattern(split_id_prefix: &str) -> String {
+    let mut pattern = String::with_capacity(split_id_prefix.len() + 1);
+    for character in split_id_prefix.chars() {
+        if matches!(character, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(character);
+    }
+    pattern.push('%');
+    pattern
+}
+
+async fn list_splits_page_helper(
+    tx: &mut Transaction<'_, Postgres>,
+    index_id: &str,
+    query: ListSplitsQuery,
+) -> MetastoreResult<SplitsPage> {
+    let mut sql = r#"
+        SELECT *
+        FROM splits
+        WHERE index_id = $1
+    "#
+    .to_string();
+    if !query.split_states.is_empty() {
+        let split_states = query
+            .split_states
+            .iter()
+            .map(|split_state| format!("'{}'", split_state.as_str()))
+            .join(", ");
+        sql.push_str(&format!(" AND split_state IN ({split_states}) "));
+    }
+    if let Some(time_range) = &query.time_range {
+        sql.push_str(&format!(
+            " AND (time_range_end >= {} OR time_range_end IS NULL) ",
+            time_range.start
+        ));
+        sql.push_str(&format!(
+            " AND (time_range_start < {} OR time_range_start IS NULL) ",
+            time_range.end
+        ));
+    }
+    if let Some(tags) = query.tags.clone() {
+        sql.push_str(" AND (");
+        sql.push_str(&tags_filter_expression_helper(tags));
+        sql.push_str(") ");
+    }
+    for (column, timestamp_range) in [
+        ("create_timestamp", &query.create_timestamp),
+        ("update_timestamp", &query.update_timestamp),
+    ] {
+        // Unbounded sides are skipped: `to_timestamp` fails on `i64::MIN` and `i64::MAX`.
+        if let Some(timestamp_range) = timestamp_range {
+            if timestamp_range.start != i64::MIN {
+                sql.push_str(&format!(
+                    " AND {column} >= (to_timestamp({}) AT TIME ZONE 'UTC') ",
+                    timestamp_range.start
+                ));
+            }
+            if timestamp_range.end != i64::MAX {
+                sql.push_str(&format!(
+                    " AND {column} < (to_timestamp({}) AT TIME ZONE 'UTC') ",
+                    timestamp_range.end
+                ));
+            }
+        }
+    }
+    // The split ID prefix and the cursor are user inputs: they are bound rather than inlined.
+    let mut split_id_params = Vec::new();
+    if let Some(split_id_prefix) = &query.split_id_prefix {
+        split_id_params.push(split_id_prefix_pattern(split_id_prefix));
+        sql.push_str(&format!(
+            " AND split_id COLLATE \"C\" LIKE ${} ",
+            split_id_params.len() + 1
+        ));
+    }
+    if let Some(cursor) = &query.cursor {
+        split_id_params.push(cursor.clone());
+        sql.push_str(&format!(
+            " AND split_id COLLATE \"C\" > ${} ",
+            split_id_params.len() + 1
+        ));
+    }
+    // The "C" collation orders the split IDs byte-wise, like the file-backed metastore. The
+    // `splits_index_id_split_id_idx` index uses the same collation to serve the prefix filter,
+    // the cursor, and the order.
+    sql.push_str(r#" ORDER BY split_id COLLATE "C" ASC "#);
+    if let Some(limit) = query.limit {
+        sql.push_str(&format!(" LIMIT {limit} "));
+    }
+
+    let mut sqlx_query = sqlx::query_as::<_, postgresql_model::Split>(&sql).bind(index_id);
+    for split_id_param in &split_id_params {
+        sqlx_query = sqlx_query.bind(split_id_param.as_str());
+    }
+    let splits = sqlx_query.fetch_all(&mut *tx).await?;
+
+    if splits.is_empty() && index_opt(&mut *tx, index_id).await?.is_none() {
+        return Err(MetastoreError::IndexDoesNotExist {
+            index_id: index_id.to_string(),
+        });
+    }
+    let splits = splits
+        .into_iter()
+        .map(|split| split.try_into())
+        .collect::<MetastoreResult<Vec<Split>>>()?;
+    Ok(SplitsPage::new(splits, query.limit))
+}
+
+/// Computes the statistics of an index. The splits are counted and their metadata is aggregated
+/// in the database: only the distinct tags of the published splits are fetched.
+async fn index_stats_helper(
+    tx: &mut Transaction<'_, Postgres>,
+    index_id: &str,
+) -> MetastoreResult<IndexStats> {
+    let index_metadata = index_metadata(&mut *tx, index_id).await?;
+    let mut index_stats = IndexStats::new(&index_metadata, utc_now_timestamp());
+
+    let split_counts: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT split_state, COUNT(*)
+        FROM splits
+        WHERE index_id = $1
+        GROUP BY split_state
+    "#,
+    )
+    .bind(index_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    for (split_state_str, num_splits) in split_counts {
+        let split_state = SplitState::from_str(&split_state_str).map_err(|err| {
+            MetastoreError::InternalError {
+                message: "Failed to deserialize split state.".to_string(),
+                cause: anyhow::anyhow!(err),
+            }
+        })?;
+        index_stats.num_splits.add(split_state, num_splits as usize);
+    }
+
+    let (
+        num_docs,
+        uncompressed_docs_size_in_bytes,
+        split_size_in_bytes,
+        time_range_start_opt,
+        time_range_end_opt,
+    ): (i64, i64, i64, Option<i64>, Option<i64>) = sqlx::query_as(
+        r#"
+        SELECT
+            COALESCE(SUM((split_metadata->>'num_docs')::BIGINT), 0)::BIGINT,
+            COALESCE(SUM(COALESCE(
+                split_metadata->>'uncompressed_docs_size_in_bytes',
+                split_metadata->>'size_in_bytes'
+            )::BIGINT), 0)::BIGINT,
+            COALESCE(SUM((split_metadata->'footer_offsets'->>'end')::BIGINT), 0)::BIGINT,
+            MIN(time_range_start),
+            MAX(time_range_end)
+        FROM (
+            SELECT split_metadata_json::JSONB AS split_metadata, time_range_start, time_range_end
+            FROM splits
+            WHERE index_id = $1 AND split_state = $2
+        ) AS published_splits
+    "#,
+    )
+    .bind(index_id)
+    .bind(SplitState::Published.as_str())
+    .fetch_one(&mut *tx)
+    .await?;
+    index_stats.num_docs = num_docs as u64;
+    index_stats.uncompressed_docs_size_in_bytes = uncompressed_docs_size_in_bytes as u64;
+    index_stats.split_size_in_bytes = split_size_in_bytes as u64;
+    if let (Some(time_range_start), Some(time_range_end)) =
+        (time_range_start_opt, time_range_end_opt)
+    {
+        index_stats.time_range = Some(time_range_start..=time_range_end);
+    }
+
+    let tags: Vec<String> = sqlx::query(
+        r#"
+        SELECT DISTINCT UNNEST(tags)
+        FROM splits
+        WHERE index_id = $1 AND split_state = $2
+    "#,
+    )
+    .bind(index_id)
+    .bind(SplitState::Published.as_str())
+    .map(|row| row.get(0))
+    .fetch_all(tx)
+    .await?;
+    let mut tag_values = TagValues::default();
+    tag_values.add_tags(&tags);
+    index_stats.tag_cardinalities = tag_values.cardinalities();
+    Ok(index_stats)
+}
+
 /// Query the database to find out if:
 /// - index exists?
 /// - splits exist?
@@ -478,38 +716,20 @@ impl Metastore for PostgresqlMetastore {
     #[instrument(skip(self, metadata),fields(split_id=metadata.split_id.as_str()))]
     async fn stage_split(&self, index_id: &str, metadata: SplitMetadata) -> MetastoreResult<()> {
         run_with_tx!(self.connection_pool, tx, {
-            // Fit the time_range to the database model.
-            let time_range_start = metadata.time_range.clone().map(|range| *range.start());
-            let time_range_end = metadata.time_range.clone().map(|range| *range.end());
-
-            // Serialize the split metadata and footer offsets to fit the database model.
-            let split_metadata_json =
-                serde_json::to_string(&metadata).map_err(|err| MetastoreError::InternalError {
-                    message: "Failed to serialize split metadata and footer offsets".to_string(),
-                    cause: anyhow::anyhow!(err),
-                })?;
-
-            let tags: Vec<String> = metadata.tags.into_iter().collect();
-            // Insert a new split metadata as `Staged` state.
-            let split_id = metadata.split_id.clone();
-            sqlx::query(r#"
-                INSERT INTO splits
-                    (split_id, split_state, time_range_start, time_range_end, tags, split_metadata_json, index_id)
-                VALUES
-                    ($1, $2, $3, $4, $5, $6, $7)
-            "#)
-            .bind(&metadata.split_id)
-            .bind(&SplitState::Staged.as_str())
-            .bind(time_range_start)
-            .bind(time_range_end)
-            .bind(tags)
-            .bind(split_metadata_json)
-            .bind(index_id)
-            .execute(tx)
-            .await
-                .map_err(|err| convert_sqlx_err(index_id, err))?;
+            insert_staged_split(tx, index_id, metadata).await
+        })
+    }
 
-            debug!(index_id=?index_id, split_id=?split_id, "The split has been staged");
+    #[instrument(skip(self, split_metadatas), fields(num_splits = split_metadatas.len()))]
+    async fn stage_splits(
+        &self,
+        index_id: &str,
+        split_metadatas: Vec<SplitMetadata>,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            for metadata in split_metadatas {
+                insert_staged_split(tx, index_id, metadata).await?;
+            }
             Ok(())
         })
     }
@@ -525,9 +745,7 @@ impl Metastore for PostgresqlMetastore {
         run_with_tx!(self.connection_pool, tx, {
             // Update the index checkpoint.
             mutate_index_metadata(tx, index_id, |index_metadata| {
-                index_metadata
-                    .checkpoint
-                    .try_apply_delta(source_id, checkpoint_delta)
+                index_metadata.try_apply_checkpoint_delta(source_id, checkpoint_delta)
             })
             .await?;
 
@@ -622,6 +840,24 @@ impl Metastore for PostgresqlMetastore {
         })
     }
 
+    #[instrument(skip(self))]
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        query: ListSplitsQuery,
+    ) -> MetastoreResult<SplitsPage> {
+        run_with_tx!(self.connection_pool, tx, {
+            list_splits_page_helper(tx, index_id, query).await
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn index_stats(&self, index_id: &str) -> MetastoreResult<IndexStats> {
+        run_with_tx!(self.connection_pool, tx, {
+            index_stats_helper(tx, index_id).await
+        })
+    }
+
     #[instrument(skip(self))]
     async fn mark_splits_for_deletion<'a>(
         &self,
@@ -715,6 +951,21 @@ impl Metastore for PostgresqlMetastore {
         })
     }
 
+    #[instrument(skip(self, checkpoint_reset))]
+    async fn reset_source_checkpoint(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        checkpoint_reset: CheckpointReset,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_id, |index_metadata| {
+                index_metadata.reset_source_checkpoint(source_id, checkpoint_reset)
+            })
+            .await
+        })
+    }
+
     fn uri(&self) -> String {
         // TODO: This is dangerous because it may leak the db credentials. We must generalize the
         // use of the `Uri` struct eventually.
@@ -858,7 +1109,7 @@ metastore_test_suite!(crate::PostgresqlMetastore);
 mod tests {
     use quickwit_doc_mapper::tag_pruning::{no_tag, tag, TagFilterAst};
 
-    use super::tags_filter_expression_helper;
+    use super::{split_id_prefix_pattern, tags_filter_expression_helper};
 
     fn test_tags_filter_expression_helper(tags_ast: TagFilterAst, expected: &str) {
         assert_eq!(tags_filter_expression_helper(tags_ast), expected);
@@ -919,4 +1170,12 @@ mod tests {
             "$Quickwit!$tag:$$;DELETE FROM something_evil$Quickwit!$ = ANY(tags)",
         );
     }
+
+    #[test]
+    fn test_split_id_prefix_pattern() {
+        assert_eq!(split_id_prefix_pattern(""), "%");
+        assert_eq!(split_id_prefix_pattern("split-01"), "split-01%");
+        assert_eq!(split_id_prefix_pattern("my_split%"), r"my\_split\%%");
+        assert_eq!(split_id_prefix_pattern(r"split\"), r"split\\%");
+    }
 }
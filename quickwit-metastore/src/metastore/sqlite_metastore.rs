@@ -0,0 +1,1102 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use quickwit_common::uri::Uri;
+use quickwit_config::SourceConfig;
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{ConnectOptions, Pool, Row, Sqlite, Transaction};
+use tokio::sync::Mutex;
+use tracing::log::LevelFilter;
+use tracing::{debug, error, instrument, warn};
+
+use crate::checkpoint::CheckpointReset;
+use crate::metastore::sqlite_model::{Index, IndexIdSplitIdRow};
+use crate::metastore::{sqlite_model, CheckpointDelta, TagValues};
+use crate::split_metadata::utc_now_timestamp;
+use crate::{
+    IndexMetadata, IndexStats, ListSplitsQuery, Metastore, MetastoreError, MetastoreFactory,
+    MetastoreResolverError, MetastoreResult, Split, SplitMetadata, SplitState, SplitsPage,
+};
+
+static MIGRATOR: Migrator = sqlx::migrate!("migrations/sqlite");
+
+// https://www.sqlite.org/rescode.html
+mod sqlite_error_code {
+    pub const CONSTRAINT_FOREIGNKEY: &str = "787";
+    pub const CONSTRAINT_PRIMARYKEY: &str = "1555";
+    pub const CONSTRAINT_UNIQUE: &str = "2067";
+}
+
+/// Opens the database at the given URI, creating it if it does not exist.
+///
+/// The pool holds a single connection: SQLite serializes writes anyway, and a single connection
+/// prevents concurrent transactions from failing with `SQLITE_BUSY`. It also keeps in-memory
+/// databases (`sqlite://:memory:`) alive.
+async fn establish_connection(connection_uri: &Uri) -> MetastoreResult<Pool<Sqlite>> {
+    let pool_options = SqlitePoolOptions::new()
+        .max_connections(1)
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None);
+    let mut sqlite_connect_options = SqliteConnectOptions::from_str(connection_uri.as_str())
+        .map_err(|err| MetastoreError::ConnectionError {
+            message: err.to_string(),
+        })?
+        .create_if_missing(true)
+        .foreign_keys(true);
+    sqlite_connect_options.log_statements(LevelFilter::Info);
+    pool_options
+        .connect_with(sqlite_connect_options)
+        .await
+        .map_err(|err| {
+            error!(connection_uri=%connection_uri, err=?err, "Failed to open database.");
+            MetastoreError::ConnectionError {
+                message: err.to_string(),
+            }
+        })
+}
+
+/// Initialize the database.
+/// The sql used for the initialization is stored in quickwit-metastore/migrations directory.
+#[instrument(skip_all)]
+async fn run_sqlite_migrations(pool: &Pool<Sqlite>) -> MetastoreResult<()> {
+    // The migrator runs each migration in its own transaction. We cannot wrap them in another
+    // one, because the pool holds a single connection.
+    MIGRATOR.run(pool).await.map_err(|migration_err| {
+        error!(err=?migration_err, "Database migrations failed");
+        MetastoreError::InternalError {
+            message: "Failed to run migrator on SQLite database.".to_string(),
+            cause: anyhow::anyhow!(migration_err),
+        }
+    })
+}
+
+/// SQLite metastore implementation, meant for single-node deployments.
+#[derive(Clone)]
+pub struct SqliteMetastore {
+    uri: Uri,
+    connection_pool: Pool<Sqlite>,
+}
+
+impl SqliteMetastore {
+    /// Creates a meta store given a database URI.
+    pub async fn new(connection_uri: Uri) -> MetastoreResult<Self> {
+        let connection_pool = establish_connection(&connection_uri).await?;
+        run_sqlite_migrations(&connection_pool).await?;
+        Ok(SqliteMetastore {
+            uri: connection_uri,
+            connection_pool,
+        })
+    }
+}
+
+/// Returns a JSON array of the given strings, to be bound as a parameter and expanded with
+/// `json_each`: SQLite has no array type.
+fn json_array<S: AsRef<str>>(values: &[S]) -> String {
+    let values: Vec<&str> = values.iter().map(AsRef::as_ref).collect();
+    serde_json::to_string(&values).expect("Serializing strings to JSON should never fail.")
+}
+
+/// Returns an Index object given an index_id or None if it does not exists.
+async fn index_opt<'a>(
+    tx: &mut Transaction<'a, Sqlite>,
+    index_id: &str,
+) -> MetastoreResult<Option<Index>> {
+    let index_opt: Option<Index> = sqlx::query_as::<_, Index>(
+        r#"
+        SELECT *
+        FROM indexes
+        WHERE index_id = $1
+    "#,
+    )
+    .bind(index_id)
+    .fetch_optional(tx)
+    .await
+    .map_err(MetastoreError::DbError)?;
+    Ok(index_opt)
+}
+
+async fn index_metadata(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+) -> MetastoreResult<IndexMetadata> {
+    index_opt(tx, index_id)
+        .await?
+        .ok_or_else(|| MetastoreError::IndexDoesNotExist {
+            index_id: index_id.to_string(),
+        })?
+        .index_metadata()
+}
+
+/// Publish splits.
+/// Returns the successful split IDs.
+#[instrument(skip(tx))]
+async fn mark_splits_as_published_helper(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    split_ids: &[&str],
+) -> MetastoreResult<Vec<String>> {
+    let publishable_states = [SplitState::Staged.as_str(), SplitState::Published.as_str()];
+    let published_split_ids: Vec<String> = sqlx::query(
+        r#"
+        UPDATE splits
+        SET split_state = $1
+        WHERE
+                index_id = $2
+            AND split_id IN (SELECT value FROM json_each($3))
+            AND split_state IN (SELECT value FROM json_each($4))
+        RETURNING split_id
+    "#,
+    )
+    .bind(SplitState::Published.as_str())
+    .bind(index_id)
+    .bind(json_array(split_ids))
+    .bind(json_array(&publishable_states))
+    .map(|row| row.get(0))
+    .fetch_all(tx)
+    .await?;
+    Ok(published_split_ids)
+}
+
+/// Inserts a new split in the `Staged` state.
+async fn insert_staged_split(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    metadata: SplitMetadata,
+) -> MetastoreResult<()> {
+    // Fit the time_range to the database model.
+    let time_range_start = metadata.time_range.clone().map(|range| *range.start());
+    let time_range_end = metadata.time_range.clone().map(|range| *range.end());
+
+    // Serialize the split metadata and footer offsets to fit the database model.
+    let split_metadata_json =
+        serde_json::to_string(&metadata).map_err(|err| MetastoreError::InternalError {
+            message: "Failed to serialize split metadata and footer offsets".to_string(),
+            cause: anyhow::anyhow!(err),
+        })?;
+
+    let tags: Vec<String> = metadata.tags.into_iter().collect();
+    sqlx::query(
+        r#"
+        INSERT INTO splits
+            (split_id, split_state, time_range_start, time_range_end, tags, split_metadata_json, index_id)
+        VALUES
+            ($1, $2, $3, $4, $5, $6, $7)
+    "#,
+    )
+    .bind(&metadata.split_id)
+    .bind(&SplitState::Staged.as_str())
+    .bind(time_range_start)
+    .bind(time_range_end)
+    .bind(json_array(&tags))
+    .bind(split_metadata_json)
+    .bind(index_id)
+    .execute(tx)
+    .await
+    .map_err(|err| convert_sqlx_err(index_id, err))?;
+
+    debug!(index_id=?index_id, split_id=?metadata.split_id, "The split has been staged");
+    Ok(())
+}
+
+/// Mark splits for deletion.
+/// Returns the IDs of the splits that were successfully marked for deletion.
+#[instrument(skip(tx))]
+async fn mark_splits_for_deletion(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    split_ids: &[&str],
+) -> MetastoreResult<Vec<String>> {
+    if split_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let marked_split_ids: Vec<String> = sqlx::query(
+        r#"
+        UPDATE splits
+        SET split_state = $1
+        WHERE
+                index_id = $2
+            AND split_id IN (SELECT value FROM json_each($3))
+        RETURNING split_id
+    "#,
+    )
+    .bind(SplitState::MarkedForDeletion.as_str())
+    .bind(index_id)
+    .bind(json_array(split_ids))
+    .map(|row| row.get(0))
+    .fetch_all(tx)
+    .await?;
+    Ok(marked_split_ids)
+}
+
+async fn list_splits_helper(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    state_opt: Option<SplitState>,
+    time_range_opt: Option<Range<i64>>,
+    tags_opt: Option<TagFilterAst>,
+) -> MetastoreResult<Vec<Split>> {
+    let query = ListSplitsQuery {
+        split_states: state_opt.into_iter().collect(),
+        time_range: time_range_opt,
+        tags: tags_opt,
+        ..Default::default()
+    };
+    list_splits_page_helper(tx, index_id, query)
+        .await
+        .map(|splits_page| splits_page.splits)
+}
+
+async fn list_splits_page_helper(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    query: ListSplitsQuery,
+) -> MetastoreResult<SplitsPage> {
+    let mut sql = r#"
+        SELECT *
+        FROM splits
+        WHERE index_id = $1
+    "#
+    .to_string();
+    if !query.split_states.is_empty() {
+        let split_states = query
+            .split_states
+            .iter()
+            .map(|split_state| format!("'{}'", split_state.as_str()))
+            .join(", ");
+        sql.push_str(&format!(" AND split_state IN ({split_states}) "));
+    }
+    if let Some(time_range) = &query.time_range {
+        sql.push_str(&format!(
+            " AND (time_range_end >= {} OR time_range_end IS NULL) ",
+            time_range.start
+        ));
+        sql.push_str(&format!(
+            " AND (time_range_start < {} OR time_range_start IS NULL) ",
+            time_range.end
+        ));
+    }
+    if let Some(tags) = query.tags.clone() {
+        sql.push_str(" AND (");
+        sql.push_str(&tags_filter_expression_helper(tags));
+        sql.push_str(") ");
+    }
+    for (column, timestamp_range) in [
+        ("create_timestamp", &query.create_timestamp),
+        ("update_timestamp", &query.update_timestamp),
+    ] {
+        if let Some(timestamp_range) = timestamp_range {
+            sql.push_str(&format!(
+                " AND {column} >= {} AND {column} < {} ",
+                timestamp_range.start, timestamp_range.end
+            ));
+        }
+    }
+    // The split ID prefix and the cursor are user inputs: they are bound rather than inlined.
+    // `LIKE` is case-insensitive in SQLite, so the prefix is matched with `substr`.
+    let mut split_id_params = Vec::new();
+    if let Some(split_id_prefix) = &query.split_id_prefix {
+        split_id_params.push(split_id_prefix.clone());
+        let param_idx = split_id_params.len() + 1;
+        sql.push_str(&format!(
+            " AND substr(split_id, 1, length(${param_idx})) = ${param_idx} "
+        ));
+    }
+    if let Some(cursor) = &query.cursor {
+        split_id_params.push(cursor.clone());
+        sql.push_str(&format!(" AND split_id > ${} ", split_id_params.len() + 1));
+    }
+    // The default `BINARY` collation orders the split IDs byte-wise, like the file-backed
+    // metastore.
+    sql.push_str(" ORDER BY split_id ASC ");
+    if let Some(limit) = query.limit {
+        sql.push_str(&format!(" LIMIT {limit} "));
+    }
+
+    let mut sqlx_query = sqlx::query_as::<_, sqlite_model::Split>(&sql).bind(index_id);
+    for split_id_param in &split_id_params {
+        sqlx_query = sqlx_query.bind(split_id_param.as_str());
+    }
+    let splits = sqlx_query.fetch_all(&mut *tx).await?;
+
+    // If no splits was returned, maybe the index itself does not exist
+    // in the first place?
+    if splits.is_empty() && index_opt(&mut *tx, index_id).await?.is_none() {
+        return Err(MetastoreError::IndexDoesNotExist {
+            index_id: index_id.to_string(),
+        });
+    }
+    let splits = splits
+        .into_iter()
+        .map(|split| split.try_into())
+        .collect::<MetastoreResult<Vec<Split>>>()?;
+    Ok(SplitsPage::new(splits, query.limit))
+}
+
+/// Computes the statistics of an index. The splits are counted and their metadata is aggregated
+/// in the database: only the distinct tags of the published splits are fetched.
+async fn index_stats_helper(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+) -> MetastoreResult<IndexStats> {
+    let index_metadata = index_metadata(&mut *tx, index_id).await?;
+    let mut index_stats = IndexStats::new(&index_metadata, utc_now_timestamp());
+
+    let split_counts: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT split_state, COUNT(*)
+        FROM splits
+        WHERE index_id = $1
+        GROUP BY split_state
+    "#,
+    )
+    .bind(index_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    for (split_state_str, num_splits) in split_counts {
+        let split_state = SplitState::from_str(&split_state_str).map_err(|err| {
+            MetastoreError::InternalError {
+                message: "Failed to deserialize split state.".to_string(),
+                cause: anyhow::anyhow!(err),
+            }
+        })?;
+        index_stats.num_splits.add(split_state, num_splits as usize);
+    }
+
+    let (
+        num_docs,
+        uncompressed_docs_size_in_bytes,
+        split_size_in_bytes,
+        time_range_start_opt,
+        time_range_end_opt,
+    ): (i64, i64, i64, Option<i64>, Option<i64>) = sqlx::query_as(
+        r#"
+        SELECT
+            COALESCE(SUM(json_extract(split_metadata_json, '$.num_docs')), 0),
+            COALESCE(SUM(COALESCE(
+                json_extract(split_metadata_json, '$.uncompressed_docs_size_in_bytes'),
+                json_extract(split_metadata_json, '$.size_in_bytes')
+            )), 0),
+            COALESCE(SUM(json_extract(split_metadata_json, '$.footer_offsets.end')), 0),
+            MIN(time_range_start),
+            MAX(time_range_end)
+        FROM splits
+        WHERE index_id = $1 AND split_state = $2
+    "#,
+    )
+    .bind(index_id)
+    .bind(SplitState::Published.as_str())
+    .fetch_one(&mut *tx)
+    .await?;
+    index_stats.num_docs = num_docs as u64;
+    index_stats.uncompressed_docs_size_in_bytes = uncompressed_docs_size_in_bytes as u64;
+    index_stats.split_size_in_bytes = split_size_in_bytes as u64;
+    if let (Some(time_range_start), Some(time_range_end)) =
+        (time_range_start_opt, time_range_end_opt)
+    {
+        index_stats.time_range = Some(time_range_start..=time_range_end);
+    }
+
+    let tags: Vec<String> = sqlx::query(
+        r#"
+        SELECT DISTINCT json_each.value
+        FROM splits, json_each(splits.tags)
+        WHERE splits.index_id = $1 AND splits.split_state = $2
+    "#,
+    )
+    .bind(index_id)
+    .bind(SplitState::Published.as_str())
+    .map(|row| row.get(0))
+    .fetch_all(tx)
+    .await?;
+    let mut tag_values = TagValues::default();
+    tag_values.add_tags(&tags);
+    index_stats.tag_cardinalities = tag_values.cardinalities();
+    Ok(index_stats)
+}
+
+/// Query the database to find out if:
+/// - index exists?
+/// - splits exist?
+/// Returns split that are not in valid state.
+async fn get_splits_with_invalid_state<'a>(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    split_ids: &[&'a str],
+    affected_split_ids: &[String],
+) -> MetastoreResult<Vec<String>> {
+    let affected_ids_set: HashSet<&str> = affected_split_ids
+        .iter()
+        .map(|split_id| split_id.as_str())
+        .collect();
+    let unaffected_ids_set: HashSet<&str> = split_ids
+        .iter()
+        .copied()
+        .filter(|&split_id| !affected_ids_set.contains(split_id))
+        .collect();
+
+    // SQL query that helps figure out if index exist, non-existent
+    // splits and not deletable splits.
+    const SELECT_SPLITS_FOR_INDEX: &str = r#"
+        SELECT i.index_id, s.split_id
+        FROM indexes AS i
+        LEFT JOIN (
+            SELECT index_id, split_id
+            FROM splits
+            WHERE split_id IN (SELECT value FROM json_each($1))
+        ) AS s
+        ON i.index_id = s.index_id
+        WHERE i.index_id = $2"#;
+
+    let index_split_rows: Vec<IndexIdSplitIdRow> =
+        sqlx::query_as::<_, IndexIdSplitIdRow>(SELECT_SPLITS_FOR_INDEX)
+            .bind(json_array(&unaffected_ids_set.iter().collect::<Vec<_>>()))
+            .bind(index_id)
+            .fetch_all(tx)
+            .await?;
+
+    // Index does not exist if empty.
+    if index_split_rows.is_empty() {
+        return Err(MetastoreError::IndexDoesNotExist {
+            index_id: index_id.to_string(),
+        });
+    }
+
+    // None of the unaffected splits exist if we have a single row
+    // with the split_id being `null`
+    if index_split_rows.len() == 1 && index_split_rows[0].split_id.is_none() {
+        error!("none of the unaffected split exists");
+        return Err(MetastoreError::SplitsDoNotExist {
+            split_ids: unaffected_ids_set
+                .iter()
+                .map(|split_id| split_id.to_string())
+                .collect(),
+        });
+    }
+
+    // The unaffected splits might be a mix of non-existant splits and splits in non valid
+    // state.
+    let not_in_correct_state_ids_set: HashSet<&str> = index_split_rows
+        .iter()
+        .flat_map(|item| item.split_id.as_deref())
+        .collect();
+    let not_found_ids_set: HashSet<&str> = &unaffected_ids_set - &not_in_correct_state_ids_set;
+
+    if !not_found_ids_set.is_empty() {
+        return Err(MetastoreError::SplitsDoNotExist {
+            split_ids: not_found_ids_set
+                .iter()
+                .map(|split_id| split_id.to_string())
+                .collect(),
+        });
+    }
+
+    Ok(not_in_correct_state_ids_set
+        .iter()
+        .map(|split_id| split_id.to_string())
+        .collect())
+}
+
+fn convert_sqlx_err(index_id: &str, sqlx_err: sqlx::Error) -> MetastoreError {
+    match &sqlx_err {
+        sqlx::Error::Database(boxed_db_err) => {
+            error!(sqlite_db_err=?boxed_db_err, "sqlite-error");
+            match boxed_db_err.code().as_deref() {
+                Some(sqlite_error_code::CONSTRAINT_FOREIGNKEY) => {
+                    MetastoreError::IndexDoesNotExist {
+                        index_id: index_id.to_string(),
+                    }
+                }
+                Some(
+                    sqlite_error_code::CONSTRAINT_PRIMARYKEY | sqlite_error_code::CONSTRAINT_UNIQUE,
+                ) => MetastoreError::InternalError {
+                    message: "Unique key violation.".to_string(),
+                    cause: anyhow::anyhow!("DB error {:?}", boxed_db_err),
+                },
+                _ => MetastoreError::DbError(sqlx_err),
+            }
+        }
+        _ => {
+            error!(err=?sqlx_err, "An error has occurred in the database operation.");
+            MetastoreError::DbError(sqlx_err)
+        }
+    }
+}
+
+/// This macro is used to systematically wrap the metastore
+/// into transaction, commit them on Result::Ok and rollback on Error.
+macro_rules! run_with_tx {
+    ($connection_pool:expr, $tx_refmut:ident, $x:block) => {{
+        let mut tx: Transaction<'_, Sqlite> = $connection_pool.begin().await?;
+        let $tx_refmut = &mut tx;
+        let op_fut = move || async move { $x };
+        let op_result: MetastoreResult<_> = op_fut().await;
+        if op_result.is_ok() {
+            debug!("commit");
+            tx.commit().await?;
+        } else {
+            warn!("rollback");
+            tx.rollback().await?;
+        }
+        op_result
+    }};
+}
+
+async fn mutate_index_metadata<E, M: FnOnce(&mut IndexMetadata) -> Result<(), E>>(
+    tx: &mut Transaction<'_, Sqlite>,
+    index_id: &str,
+    mutation: M,
+) -> MetastoreResult<()>
+where
+    MetastoreError: From<E>,
+{
+    let mut index_metadata = index_metadata(tx, index_id).await?;
+    mutation(&mut index_metadata)?;
+    let index_metadata_json =
+        serde_json::to_string(&index_metadata).map_err(|err| MetastoreError::InternalError {
+            message: "Failed to serialize index metadata.".to_string(),
+            cause: anyhow::anyhow!(err),
+        })?;
+    let update_index_res = sqlx::query(
+        r#"
+        UPDATE indexes
+        SET index_metadata_json = $1
+        WHERE index_id = $2
+    "#,
+    )
+    .bind(index_metadata_json)
+    .bind(&index_id)
+    .execute(tx)
+    .await?;
+    if update_index_res.rows_affected() == 0 {
+        return Err(MetastoreError::IndexDoesNotExist {
+            index_id: index_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Metastore for SqliteMetastore {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.connection_pool.acquire().await?;
+        Ok(())
+    }
+
+    async fn list_indexes_metadatas(&self) -> MetastoreResult<Vec<IndexMetadata>> {
+        run_with_tx!(self.connection_pool, tx, {
+            let indexes: Vec<Index> = sqlx::query_as::<_, Index>("SELECT * FROM indexes")
+                .fetch_all(tx)
+                .await?;
+            let index_metadata: MetastoreResult<Vec<IndexMetadata>> = indexes
+                .into_iter()
+                .map(|index| index.index_metadata())
+                .collect::<MetastoreResult<_>>();
+            index_metadata
+        })
+    }
+
+    #[instrument(skip(self),fields(index_id=index_metadata.index_id.as_str()))]
+    async fn create_index(&self, index_metadata: IndexMetadata) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            // Serialize the index metadata to fit the database model.
+            let index_metadata_json = serde_json::to_string(&index_metadata).map_err(|err| {
+                MetastoreError::InternalError {
+                    message: "Failed to serialize index metadata.".to_string(),
+                    cause: anyhow::anyhow!(err),
+                }
+            })?;
+            // Create index.
+            let create_index_statement_res =
+                sqlx::query("INSERT INTO indexes (index_id, index_metadata_json) VALUES ($1, $2)")
+                    .bind(&index_metadata.index_id)
+                    .bind(&index_metadata_json)
+                    .execute(tx)
+                    .await;
+            create_index_statement_res
+                .map_err(|err| convert_sqlx_err(&index_metadata.index_id, err))?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_index(&self, index_id: &str) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            let query_res = sqlx::query("DELETE FROM indexes WHERE index_id = $1")
+                .bind(index_id)
+                .execute(tx)
+                .await?;
+            if query_res.rows_affected() == 0 {
+                return Err(MetastoreError::IndexDoesNotExist {
+                    index_id: index_id.to_string(),
+                });
+            }
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self, metadata),fields(split_id=metadata.split_id.as_str()))]
+    async fn stage_split(&self, index_id: &str, metadata: SplitMetadata) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            insert_staged_split(tx, index_id, metadata).await
+        })
+    }
+
+    #[instrument(skip(self, split_metadatas), fields(num_splits = split_metadatas.len()))]
+    async fn stage_splits(
+        &self,
+        index_id: &str,
+        split_metadatas: Vec<SplitMetadata>,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            for metadata in split_metadatas {
+                insert_staged_split(tx, index_id, metadata).await?;
+            }
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self, checkpoint_delta))]
+    async fn publish_splits<'a>(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        split_ids: &[&'a str],
+        checkpoint_delta: CheckpointDelta,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            // Update the index checkpoint.
+            mutate_index_metadata(tx, index_id, |index_metadata| {
+                index_metadata.try_apply_checkpoint_delta(source_id, checkpoint_delta)
+            })
+            .await?;
+
+            if split_ids.is_empty() {
+                return Ok(());
+            }
+
+            let published_split_ids =
+                mark_splits_as_published_helper(tx, index_id, split_ids).await?;
+
+            if published_split_ids.len() == split_ids.len() {
+                return Ok(());
+            }
+
+            error!(
+                num_split_ids = split_ids.len(),
+                published = published_split_ids.len(),
+                "published_splits_ids_not_match"
+            );
+
+            // Investigate and report the error.
+            let not_staged_ids =
+                get_splits_with_invalid_state(tx, index_id, split_ids, &published_split_ids)
+                    .await?;
+
+            Err(MetastoreError::SplitsNotStaged {
+                split_ids: not_staged_ids,
+            })
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn replace_splits<'a>(
+        &self,
+        index_id: &str,
+        new_split_ids: &[&'a str],
+        replaced_split_ids: &[&'a str],
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            // Publish splits.
+            let published_split_ids =
+                mark_splits_as_published_helper(tx, index_id, new_split_ids).await?;
+
+            // Mark splits for deletion
+            let marked_split_ids =
+                mark_splits_for_deletion(tx, index_id, replaced_split_ids).await?;
+
+            // returning `Ok` means `commit` the transaction.
+            if published_split_ids.len() == new_split_ids.len()
+                && marked_split_ids.len() == replaced_split_ids.len()
+            {
+                return Ok(());
+            }
+
+            let affected_split_ids: Vec<String> = published_split_ids
+                .into_iter()
+                .chain(marked_split_ids.into_iter())
+                .collect();
+            let split_ids: Vec<&str> = new_split_ids
+                .iter()
+                .chain(replaced_split_ids.iter())
+                .copied()
+                .collect();
+
+            let not_staged_ids =
+                get_splits_with_invalid_state(tx, index_id, &split_ids, &affected_split_ids)
+                    .await?;
+
+            Err(MetastoreError::SplitsNotStaged {
+                split_ids: not_staged_ids,
+            })
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn list_splits(
+        &self,
+        index_id: &str,
+        state: SplitState,
+        time_range_opt: Option<Range<i64>>,
+        tags: Option<TagFilterAst>,
+    ) -> MetastoreResult<Vec<Split>> {
+        run_with_tx!(self.connection_pool, tx, {
+            list_splits_helper(tx, index_id, Some(state), time_range_opt, tags).await
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn list_all_splits(&self, index_id: &str) -> MetastoreResult<Vec<Split>> {
+        run_with_tx!(self.connection_pool, tx, {
+            list_splits_helper(tx, index_id, None, None, None).await
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        query: ListSplitsQuery,
+    ) -> MetastoreResult<SplitsPage> {
+        run_with_tx!(self.connection_pool, tx, {
+            list_splits_page_helper(tx, index_id, query).await
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn index_stats(&self, index_id: &str) -> MetastoreResult<IndexStats> {
+        run_with_tx!(self.connection_pool, tx, {
+            index_stats_helper(tx, index_id).await
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn mark_splits_for_deletion<'a>(
+        &self,
+        index_id: &str,
+        split_ids: &[&'a str],
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            let marked_split_ids: Vec<String> =
+                mark_splits_for_deletion(tx, index_id, split_ids).await?;
+
+            if marked_split_ids.len() == split_ids.len() {
+                return Ok(());
+            }
+
+            get_splits_with_invalid_state(tx, index_id, split_ids, &marked_split_ids).await?;
+
+            let err_msg = format!("Failed to mark splits for deletion for index {index_id}.");
+            let cause = anyhow::anyhow!(err_msg.clone());
+            Err(MetastoreError::InternalError {
+                message: err_msg,
+                cause,
+            })
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_splits<'a>(
+        &self,
+        index_id: &str,
+        split_ids: &[&'a str],
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            let deletable_states = [
+                SplitState::Staged.as_str(),
+                SplitState::MarkedForDeletion.as_str(),
+            ];
+            let deleted_split_ids: Vec<String> = sqlx::query(
+                r#"
+                DELETE FROM splits
+                WHERE
+                        split_id IN (SELECT value FROM json_each($1))
+                    AND split_state IN (SELECT value FROM json_each($2))
+                RETURNING split_id
+            "#,
+            )
+            .bind(json_array(split_ids))
+            .bind(json_array(&deletable_states))
+            .map(|sqlite_row| sqlite_row.get(0))
+            .fetch_all(&mut *tx)
+            .await?;
+
+            if deleted_split_ids.len() == split_ids.len() {
+                return Ok(());
+            }
+
+            // There is an error, but we want to investigate and return a meaningful error.
+            // From this point, we always have to return `Err` to abort the transaction.
+            let not_deletable_ids =
+                get_splits_with_invalid_state(tx, index_id, split_ids, &deleted_split_ids).await?;
+
+            Err(MetastoreError::SplitsNotDeletable {
+                split_ids: not_deletable_ids,
+            })
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn index_metadata(&self, index_id: &str) -> MetastoreResult<IndexMetadata> {
+        run_with_tx!(self.connection_pool, tx, {
+            index_metadata(tx, index_id).await
+        })
+    }
+
+    #[instrument(skip(self, source), fields(source_id=source.source_id.as_str()))]
+    async fn add_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_id, |index_metadata| {
+                index_metadata.add_source(source)
+            })
+            .await
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_source(&self, index_id: &str, source_id: &str) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_id, |index_metadata| {
+                index_metadata.delete_source(source_id)
+            })
+            .await
+        })
+    }
+
+    #[instrument(skip(self, checkpoint_reset))]
+    async fn reset_source_checkpoint(
+        &self,
+        index_id: &str,
+        source_id: &str,
+        checkpoint_reset: CheckpointReset,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_id, |index_metadata| {
+                index_metadata.reset_source_checkpoint(source_id, checkpoint_reset)
+            })
+            .await
+        })
+    }
+
+    fn uri(&self) -> String {
+        self.uri.to_string()
+    }
+}
+
+/// Returns the SQL string literal of `value`. SQLite string literals have no escape character
+/// other than the doubled single quote.
+fn sql_string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Takes a tag filters AST and returns a sql expression that can be used as
+/// a filter.
+fn tags_filter_expression_helper(tags: TagFilterAst) -> String {
+    match tags {
+        TagFilterAst::And(child_asts) => {
+            if child_asts.is_empty() {
+                return "TRUE".to_string();
+            }
+            let expr_without_parenthesis = child_asts
+                .into_iter()
+                .map(tags_filter_expression_helper)
+                .join(" AND ");
+            format!("({expr_without_parenthesis})")
+        }
+        TagFilterAst::Or(child_asts) => {
+            if child_asts.is_empty() {
+                return "TRUE".to_string();
+            }
+            let expr_without_parenthesis = child_asts
+                .into_iter()
+                .map(tags_filter_expression_helper)
+                .join(" OR ");
+            format!("({expr_without_parenthesis})")
+        }
+        TagFilterAst::Tag { is_present, tag } => {
+            let tag_literal = sql_string_literal(&tag);
+            let tag_expr =
+                format!("EXISTS (SELECT 1 FROM json_each(tags) WHERE value = {tag_literal})");
+            if is_present {
+                tag_expr
+            } else {
+                format!("NOT {tag_expr}")
+            }
+        }
+    }
+}
+
+/// A SQLite metastore factory
+#[derive(Clone, Default)]
+pub struct SqliteMetastoreFactory {
+    // In a normal run, this cache will contain a single Metastore.
+    //
+    // Like the PostgreSQL metastore factory, we use a strong pointer here, so that the Metastore
+    // and its connection pool are not dropped. Dropping the pool of an in-memory database would
+    // also drop the database.
+    cache: Arc<Mutex<HashMap<Uri, Arc<dyn Metastore>>>>,
+}
+
+impl SqliteMetastoreFactory {
+    async fn get_from_cache(&self, uri: &Uri) -> Option<Arc<dyn Metastore>> {
+        let cache_lock = self.cache.lock().await;
+        cache_lock.get(uri).map(Arc::clone)
+    }
+
+    /// If there is a valid entry in the cache to begin with, we trash the new
+    /// one and return the old one.
+    ///
+    /// This way we make sure that we keep only one instance associated
+    /// to the key `uri` outside of this struct.
+    async fn cache_metastore(&self, uri: Uri, metastore: Arc<dyn Metastore>) -> Arc<dyn Metastore> {
+        let mut cache_lock = self.cache.lock().await;
+        if let Some(metastore) = cache_lock.get(&uri) {
+            return metastore.clone();
+        }
+        cache_lock.insert(uri, metastore.clone());
+        metastore
+    }
+}
+
+#[async_trait]
+impl MetastoreFactory for SqliteMetastoreFactory {
+    async fn resolve(&self, uri: &str) -> Result<Arc<dyn Metastore>, MetastoreResolverError> {
+        let uri = Uri::new(uri.to_string());
+        if let Some(metastore) = self.get_from_cache(&uri).await {
+            debug!("using metastore from cache");
+            return Ok(metastore);
+        }
+        debug!("metastore not found in cache");
+        let metastore = SqliteMetastore::new(uri.clone())
+            .await
+            .map_err(MetastoreResolverError::FailedToOpenMetastore)?;
+        let metastore = self.cache_metastore(uri, Arc::new(metastore)).await;
+        Ok(metastore)
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl crate::tests::test_suite::DefaultForTest for SqliteMetastore {
+    async fn default_for_test() -> Self {
+        // Each test gets its own in-memory database.
+        let uri = Uri::new("sqlite://:memory:".to_string());
+        SqliteMetastore::new(uri)
+            .await
+            .expect("Failed to initialize test SQLite metastore.")
+    }
+}
+
+metastore_test_suite!(crate::SqliteMetastore);
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::uri::Uri;
+    use quickwit_doc_mapper::tag_pruning::{no_tag, tag, TagFilterAst};
+
+    use super::{json_array, tags_filter_expression_helper, SqliteMetastore};
+    use crate::{IndexMetadata, Metastore};
+
+    fn test_tags_filter_expression_helper(tags_ast: TagFilterAst, expected: &str) {
+        assert_eq!(tags_filter_expression_helper(tags_ast), expected);
+    }
+
+    #[test]
+    fn test_tags_filter_expression_single_tag() {
+        test_tags_filter_expression_helper(
+            tag("my_field:titi"),
+            "EXISTS (SELECT 1 FROM json_each(tags) WHERE value = 'my_field:titi')",
+        );
+    }
+
+    #[test]
+    fn test_tags_filter_expression_not_tag() {
+        test_tags_filter_expression_helper(
+            no_tag("my_field:titi"),
+            "NOT EXISTS (SELECT 1 FROM json_each(tags) WHERE value = 'my_field:titi')",
+        );
+    }
+
+    #[test]
+    fn test_tags_filter_expression_and_or() {
+        let tags_ast = TagFilterAst::Or(vec![
+            TagFilterAst::And(vec![tag("tag:val1"), tag("tag:val2")]),
+            tag("tag:val3"),
+        ]);
+        test_tags_filter_expression_helper(
+            tags_ast,
+            "((EXISTS (SELECT 1 FROM json_each(tags) WHERE value = 'tag:val1') AND EXISTS (SELECT \
+             1 FROM json_each(tags) WHERE value = 'tag:val2')) OR EXISTS (SELECT 1 FROM \
+             json_each(tags) WHERE value = 'tag:val3'))",
+        );
+    }
+
+    #[test]
+    fn test_tags_sql_injection_attempt() {
+        test_tags_filter_expression_helper(
+            tag("tag:');DELETE FROM something_evil"),
+            "EXISTS (SELECT 1 FROM json_each(tags) WHERE value = 'tag:'');DELETE FROM \
+             something_evil')",
+        );
+    }
+
+    #[test]
+    fn test_json_array() {
+        assert_eq!(json_array::<&str>(&[]), "[]");
+        assert_eq!(
+            json_array(&["split-1", "split\"2"]),
+            r#"["split-1","split\"2"]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_metastore_persists_to_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_path = temp_dir.path().join("metastore.db");
+        let uri = Uri::new(format!("sqlite://{}", database_path.display()));
+        {
+            let metastore = SqliteMetastore::new(uri.clone()).await.unwrap();
+            let index_metadata = IndexMetadata::for_test("my-index", "ram://indexes/my-index");
+            metastore.create_index(index_metadata).await.unwrap();
+        }
+        let metastore = SqliteMetastore::new(uri).await.unwrap();
+        let indexes = metastore.list_indexes_metadatas().await.unwrap();
+        assert_eq!(indexes.len(), 1);
+        assert_eq!(indexes[0].index_id, "my-index");
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+use futures::StreamExt;
+use itertools::Itertools;
+use quickwit_common::split_file;
+use quickwit_storage::StorageUriResolver;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{IndexMetadata, Metastore, MetastoreError, MetastoreResult, Split, SplitState};
+
+const MAX_CONCURRENT_STORAGE_REQUESTS: usize = if cfg!(test) { 2 } else { 10 };
+
+/// A portable dump of the content of a metastore: the metadata of its indexes, including their
+/// sources and checkpoints, and their splits.
+///
+/// Archives are created by [`export_metastore`] and restored by [`import_metastore`], possibly
+/// into a metastore of a different kind.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "VersionedMetastoreArchive")]
+#[serde(from = "VersionedMetastoreArchive")]
+pub struct MetastoreArchive {
+    /// Archived indexes.
+    pub indexes: Vec<IndexArchive>,
+}
+
+/// The metadata and the splits of an archived index.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexArchive {
+    /// Index metadata, including its sources and checkpoints.
+    #[serde(rename = "index")]
+    pub index_metadata: IndexMetadata,
+    /// Splits of the index, in any state.
+    pub splits: Vec<Split>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "version")]
+enum VersionedMetastoreArchive {
+    #[serde(rename = "0")]
+    V0(MetastoreArchiveV0),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct MetastoreArchiveV0 {
+    indexes: Vec<IndexArchive>,
+}
+
+impl From<MetastoreArchive> for VersionedMetastoreArchive {
+    fn from(archive: MetastoreArchive) -> Self {
+        VersionedMetastoreArchive::V0(MetastoreArchiveV0 {
+            indexes: archive.indexes,
+        })
+    }
+}
+
+impl From<VersionedMetastoreArchive> for MetastoreArchive {
+    fn from(archive: VersionedMetastoreArchive) -> Self {
+        match archive {
+            VersionedMetastoreArchive::V0(archive) => MetastoreArchive {
+                indexes: archive.indexes,
+            },
+        }
+    }
+}
+
+/// Dumps all the indexes of a metastore into an archive.
+pub async fn export_metastore(metastore: &dyn Metastore) -> MetastoreResult<MetastoreArchive> {
+    let mut indexes = Vec::new();
+    for index_metadata in metastore.list_indexes_metadatas().await? {
+        let splits = metastore
+            .list_all_splits(&index_metadata.index_id)
+            .await?
+            .into_iter()
+            .sorted_by(|left, right| left.split_id().cmp(right.split_id()))
+            .collect();
+        indexes.push(IndexArchive {
+            index_metadata,
+            splits,
+        });
+    }
+    indexes.sort_by(|left, right| {
+        left.index_metadata
+            .index_id
+            .cmp(&right.index_metadata.index_id)
+    });
+    Ok(MetastoreArchive { indexes })
+}
+
+/// Restores the indexes of an archive into a metastore.
+///
+/// The metastore must not hold any of the archived indexes: this is checked before anything is
+/// written. Splits keep their state, but their update timestamp is reset.
+///
+/// The import is all or nothing: if an index cannot be restored, the indexes already restored by
+/// the import are deleted from the metastore before the error is returned, so that the import can
+/// be run again once the error is fixed.
+pub async fn import_metastore(
+    metastore: &dyn Metastore,
+    archive: MetastoreArchive,
+) -> MetastoreResult<()> {
+    for index in &archive.indexes {
+        let index_id = &index.index_metadata.index_id;
+        match metastore.index_metadata(index_id).await {
+            Ok(_) => {
+                return Err(MetastoreError::IndexAlreadyExists {
+                    index_id: index_id.clone(),
+                })
+            }
+            Err(MetastoreError::IndexDoesNotExist { .. }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    let mut created_index_ids = Vec::new();
+    for index in archive.indexes {
+        if let Err(error) = import_index(metastore, index, &mut created_index_ids).await {
+            for index_id in &created_index_ids {
+                if let Err(rollback_error) = delete_index_and_splits(metastore, index_id).await {
+                    error!(
+                        index_id = %index_id,
+                        error = ?rollback_error,
+                        "Failed to delete the partially imported index."
+                    );
+                }
+            }
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Restores an archived index, and appends its ID to `created_index_ids` as soon as it is created.
+async fn import_index(
+    metastore: &dyn Metastore,
+    index: IndexArchive,
+    created_index_ids: &mut Vec<String>,
+) -> MetastoreResult<()> {
+    let index_id = index.index_metadata.index_id.clone();
+    metastore.create_index(index.index_metadata).await?;
+    created_index_ids.push(index_id.clone());
+
+    if !index.splits.is_empty() {
+        let split_metadatas = index
+            .splits
+            .iter()
+            .map(|split| split.split_metadata.clone())
+            .collect();
+        metastore.stage_splits(&index_id, split_metadatas).await?;
+    }
+    let split_ids_in_state = |split_state: SplitState| -> Vec<&str> {
+        index
+            .splits
+            .iter()
+            .filter(|split| split.split_state == split_state)
+            .map(|split| split.split_id())
+            .collect()
+    };
+    // Unlike `publish_splits`, `replace_splits` leaves the restored checkpoints untouched.
+    let published_split_ids = split_ids_in_state(SplitState::Published);
+    if !published_split_ids.is_empty() {
+        metastore
+            .replace_splits(&index_id, &published_split_ids, &[])
+            .await?;
+    }
+    let marked_split_ids = split_ids_in_state(SplitState::MarkedForDeletion);
+    if !marked_split_ids.is_empty() {
+        metastore
+            .mark_splits_for_deletion(&index_id, &marked_split_ids)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Deletes an index along with all its splits.
+async fn delete_index_and_splits(metastore: &dyn Metastore, index_id: &str) -> MetastoreResult<()> {
+    let splits = metastore.list_all_splits(index_id).await?;
+    let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id()).collect();
+    if !split_ids.is_empty() {
+        metastore
+            .mark_splits_for_deletion(index_id, &split_ids)
+            .await?;
+        metastore.delete_splits(index_id, &split_ids).await?;
+    }
+    metastore.delete_index(index_id).await
+}
+
+/// A published split of an archive whose file could not be found in the storage of its index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingSplitFile {
+    /// ID of the index of the split.
+    pub index_id: String,
+    /// ID of the split.
+    pub split_id: String,
+    /// Reason why the split file is missing or could not be checked.
+    pub reason: String,
+}
+
+/// Checks that the files of the published splits of an archive exist in the storage of their
+/// index, and returns the missing ones.
+///
+/// Staged splits may not have been uploaded yet and splits marked for deletion may already be
+/// deleted, so their files are not checked.
+pub async fn check_split_files(
+    archive: &MetastoreArchive,
+    storage_resolver: &StorageUriResolver,
+) -> Vec<MissingSplitFile> {
+    let mut missing_split_files = Vec::new();
+    for index in &archive.indexes {
+        let index_id = &index.index_metadata.index_id;
+        let storage = match storage_resolver.resolve(&index.index_metadata.index_uri) {
+            Ok(storage) => storage,
+            Err(error) => {
+                missing_split_files.extend(
+                    index
+                        .splits
+                        .iter()
+                        .filter(|split| split.split_state == SplitState::Published)
+                        .map(|split| MissingSplitFile {
+                            index_id: index_id.clone(),
+                            split_id: split.split_id().to_string(),
+                            reason: error.to_string(),
+                        }),
+                );
+                continue;
+            }
+        };
+        let mut check_results_stream = futures::stream::iter(
+            index
+                .splits
+                .iter()
+                .filter(|split| split.split_state == SplitState::Published),
+        )
+        .map(|split| {
+            let storage = storage.clone();
+            async move {
+                let split_file_path = split_file(split.split_id());
+                let exists_res = storage.exists(Path::new(&split_file_path)).await;
+                (split.split_id(), exists_res)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_STORAGE_REQUESTS);
+
+        while let Some((split_id, exists_res)) = check_results_stream.next().await {
+            let reason = match exists_res {
+                Ok(true) => continue,
+                Ok(false) => "Split file does not exist.".to_string(),
+                Err(error) => error.to_string(),
+            };
+            missing_split_files.push(MissingSplitFile {
+                index_id: index_id.clone(),
+                split_id: split_id.to_string(),
+                reason,
+            });
+        }
+    }
+    missing_split_files.sort_by(|left, right| {
+        (&left.index_id, &left.split_id).cmp(&(&right.index_id, &right.split_id))
+    });
+    missing_split_files
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_storage::RamStorage;
+
+    use super::*;
+    use crate::checkpoint::CheckpointDelta;
+    use crate::{FileBackedMetastore, SplitMetadata};
+
+    async fn stage_and_publish_split(
+        metastore: &dyn Metastore,
+        index_id: &str,
+        split_id: &str,
+        checkpoint_delta: CheckpointDelta,
+    ) {
+        let split_metadata = SplitMetadata {
+            split_id: split_id.to_string(),
+            ..Default::default()
+        };
+        metastore
+            .stage_split(index_id, split_metadata)
+            .await
+            .unwrap();
+        metastore
+            .publish_splits(index_id, "my-source", &[split_id], checkpoint_delta)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_metastore_archive_export_import() {
+        let source_metastore = FileBackedMetastore::for_test(Arc::new(RamStorage::default()));
+        let index_metadata = IndexMetadata::for_test("my-index", "ram://indexes/my-index");
+        source_metastore
+            .create_index(index_metadata.clone())
+            .await
+            .unwrap();
+        stage_and_publish_split(&source_metastore, "my-index", "split-1", (0..10).into()).await;
+        stage_and_publish_split(&source_metastore, "my-index", "split-2", (10..20).into()).await;
+        source_metastore
+            .mark_splits_for_deletion("my-index", &["split-2"])
+            .await
+            .unwrap();
+        source_metastore
+            .stage_split(
+                "my-index",
+                SplitMetadata {
+                    split_id: "split-3".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let archive = export_metastore(&source_metastore).await.unwrap();
+        let archive_json = serde_json::to_string(&archive).unwrap();
+        let archive: MetastoreArchive = serde_json::from_str(&archive_json).unwrap();
+        assert_eq!(archive.indexes.len(), 1);
+        assert_eq!(archive.indexes[0].splits.len(), 3);
+
+        let target_metastore = FileBackedMetastore::for_test(Arc::new(RamStorage::default()));
+        import_metastore(&target_metastore, archive.clone())
+            .await
+            .unwrap();
+
+        let imported_index_metadata = target_metastore.index_metadata("my-index").await.unwrap();
+        assert_eq!(
+            imported_index_metadata.checkpoint,
+            source_metastore
+                .index_metadata("my-index")
+                .await
+                .unwrap()
+                .checkpoint
+        );
+        let split_states: Vec<(String, SplitState)> = target_metastore
+            .list_all_splits("my-index")
+            .await
+            .unwrap()
+            .into_iter()
+            .sorted_by(|left, right| left.split_id().cmp(right.split_id()))
+            .map(|split| (split.split_id().to_string(), split.split_state))
+            .collect();
+        assert_eq!(
+            split_states,
+            [
+                ("split-1".to_string(), SplitState::Published),
+                ("split-2".to_string(), SplitState::MarkedForDeletion),
+                ("split-3".to_string(), SplitState::Staged),
+            ]
+        );
+
+        let error = import_metastore(&target_metastore, archive)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexAlreadyExists { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_metastore_archive_import_rollback() {
+        let split_fn = |split_id: &str| Split {
+            split_state: SplitState::Published,
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                ..Default::default()
+            },
+            update_timestamp: 0,
+        };
+        let archive = MetastoreArchive {
+            indexes: vec![
+                IndexArchive {
+                    index_metadata: IndexMetadata::for_test("index-1", "ram://indexes/index-1"),
+                    splits: vec![split_fn("split-1")],
+                },
+                // The duplicate split cannot be staged.
+                IndexArchive {
+                    index_metadata: IndexMetadata::for_test("index-2", "ram://indexes/index-2"),
+                    splits: vec![split_fn("split-2"), split_fn("split-2")],
+                },
+            ],
+        };
+        let metastore = FileBackedMetastore::for_test(Arc::new(RamStorage::default()));
+        import_metastore(&metastore, archive.clone())
+            .await
+            .unwrap_err();
+        assert!(metastore.list_indexes_metadatas().await.unwrap().is_empty());
+
+        // Once the archive is fixed, the import can be run again.
+        let mut archive = archive;
+        archive.indexes[1].splits.pop();
+        import_metastore(&metastore, archive).await.unwrap();
+        assert_eq!(metastore.list_indexes_metadatas().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_metastore_archive_check_split_files() {
+        let metastore = FileBackedMetastore::for_test(Arc::new(RamStorage::default()));
+        let index_metadata = IndexMetadata::for_test("my-index", "ram://indexes/my-index");
+        metastore.create_index(index_metadata).await.unwrap();
+        stage_and_publish_split(&metastore, "my-index", "split-1", (0..10).into()).await;
+        stage_and_publish_split(&metastore, "my-index", "split-2", (10..20).into()).await;
+
+        let storage_resolver = StorageUriResolver::for_test();
+        storage_resolver
+            .resolve("ram://indexes/my-index")
+            .unwrap()
+            .put(Path::new("split-1.split"), Box::new(b"split".to_vec()))
+            .await
+            .unwrap();
+
+        let archive = export_metastore(&metastore).await.unwrap();
+        let missing_split_files = check_split_files(&archive, &storage_resolver).await;
+        assert_eq!(missing_split_files.len(), 1);
+        assert_eq!(missing_split_files[0].index_id, "my-index");
+        assert_eq!(missing_split_files[0].split_id, "split-2");
+    }
+}
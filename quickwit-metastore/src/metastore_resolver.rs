@@ -26,6 +26,8 @@ use once_cell::sync::OnceCell;
 use crate::metastore::file_backed_metastore::FileBackedMetastoreFactory;
 #[cfg(feature = "postgres")]
 use crate::metastore::postgresql_metastore::PostgresqlMetastoreFactory;
+#[cfg(feature = "sqlite")]
+use crate::metastore::sqlite_metastore::SqliteMetastoreFactory;
 use crate::{Metastore, MetastoreResolverError};
 
 /// A metastore factory builds a [`Metastore`] object from an URI.
@@ -100,6 +102,23 @@ pub fn quickwit_metastore_uri_resolver() -> &'static MetastoreUriResolver {
                 )
         }
 
+        #[cfg(feature = "sqlite")]
+        {
+            builder = builder.register("sqlite", SqliteMetastoreFactory::default());
+        }
+
+        #[cfg(not(feature = "sqlite"))]
+        {
+            builder = builder.register(
+                "sqlite",
+                UnsuportedMetastore {
+                    message: "sqlite unsupported, quickwit was compiled without the 'sqlite' \
+                              feature flag"
+                        .to_string(),
+                },
+            );
+        }
+
         builder.build()
     })
 }
@@ -168,6 +187,17 @@ mod tests {
             .unwrap();
     }
 
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_protocol_accepted() {
+        let metastore_resolver = quickwit_metastore_uri_resolver();
+        let metastore = metastore_resolver
+            .resolve("sqlite://:memory:")
+            .await
+            .unwrap();
+        metastore.check_connectivity().await.unwrap();
+    }
+
     #[cfg(feature = "postgres")]
     #[tokio::test]
     async fn test_postgres_and_postgresql_protocol_accepted() {
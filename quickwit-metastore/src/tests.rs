@@ -29,8 +29,13 @@ pub mod test_suite {
     use tokio::time::{sleep, Duration};
     use tracing::{error, info};
 
-    use crate::checkpoint::{CheckpointDelta, PartitionId, Position, SourceCheckpoint};
-    use crate::{IndexMetadata, Metastore, MetastoreError, SplitMetadata, SplitState};
+    use crate::checkpoint::{
+        CheckpointDelta, CheckpointReset, PartitionId, Position, SourceCheckpoint,
+    };
+    use crate::{
+        IndexMetadata, ListSplitsQuery, Metastore, MetastoreError, SplitCounts, SplitMetadata,
+        SplitState, SplitsPage,
+    };
 
     #[async_trait]
     pub trait DefaultForTest {
@@ -179,6 +184,169 @@ pub mod test_suite {
         cleanup_index(&metastore, &index_metadata.index_id).await;
     }
 
+    pub async fn test_metastore_reset_source_checkpoint<
+        MetastoreToTest: Metastore + DefaultForTest,
+    >() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = "reset-source-checkpoint-index";
+        let index_uri = "ram://indexes/reset-source-checkpoint-index";
+        let source_id = "reset-source-checkpoint-source";
+
+        let mut checkpoint_reset = CheckpointReset::default();
+        checkpoint_reset.record_partition_reset(
+            PartitionId::from(0u64),
+            Position::from(42u64),
+            Position::from(10u64),
+        );
+
+        // Reset the checkpoint of a source of a non-existent index
+        {
+            let result = metastore
+                .reset_source_checkpoint(index_id, source_id, checkpoint_reset.clone())
+                .await
+                .unwrap_err();
+            assert!(matches!(result, MetastoreError::IndexDoesNotExist { .. }));
+        }
+
+        let mut index_metadata = IndexMetadata::for_test(index_id, index_uri);
+        index_metadata.sources.insert(
+            source_id.to_string(),
+            SourceConfig {
+                source_id: source_id.to_string(),
+                source_params: SourceParams::void(),
+            },
+        );
+        metastore.create_index(index_metadata).await.unwrap();
+
+        // Reset the checkpoint of a non-existent source
+        {
+            let result = metastore
+                .reset_source_checkpoint(
+                    index_id,
+                    "source-does-not-exist",
+                    checkpoint_reset.clone(),
+                )
+                .await
+                .unwrap_err();
+            assert!(matches!(result, MetastoreError::SourceDoesNotExist { .. }));
+        }
+
+        let split_id = "reset-source-checkpoint-split";
+        let split_metadata = SplitMetadata {
+            split_id: split_id.to_string(),
+            ..Default::default()
+        };
+        metastore
+            .stage_split(index_id, split_metadata)
+            .await
+            .unwrap();
+        let checkpoint_delta = CheckpointDelta::from_partition_delta(
+            PartitionId::from(0u64),
+            Position::Beginning,
+            Position::from(42u64),
+        );
+        metastore
+            .publish_splits(index_id, source_id, &[split_id], checkpoint_delta)
+            .await
+            .unwrap();
+
+        // Reset the checkpoint from a stale position
+        {
+            let mut stale_checkpoint_reset = CheckpointReset::default();
+            stale_checkpoint_reset.record_partition_reset(
+                PartitionId::from(0u64),
+                Position::from(41u64),
+                Position::from(10u64),
+            );
+            let result = metastore
+                .reset_source_checkpoint(index_id, source_id, stale_checkpoint_reset)
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                result,
+                MetastoreError::IncompatibleCheckpointDelta(_)
+            ));
+            let index_metadata = metastore.index_metadata(index_id).await.unwrap();
+            let source_checkpoint = index_metadata
+                .checkpoint
+                .source_checkpoint(source_id)
+                .unwrap();
+            assert_eq!(
+                source_checkpoint.position_for_partition(&PartitionId::from(0u64)),
+                Some(&Position::from(42u64))
+            );
+        }
+
+        // Rewind the checkpoint
+        {
+            metastore
+                .reset_source_checkpoint(index_id, source_id, checkpoint_reset)
+                .await
+                .unwrap();
+            let index_metadata = metastore.index_metadata(index_id).await.unwrap();
+            let source_checkpoint = index_metadata
+                .checkpoint
+                .source_checkpoint(source_id)
+                .unwrap();
+            assert_eq!(
+                source_checkpoint.position_for_partition(&PartitionId::from(0u64)),
+                Some(&Position::from(10u64))
+            );
+
+            // A pipeline still reading from the position preceding the reset cannot publish.
+            let stale_split_id = "reset-source-checkpoint-stale-split";
+            let split_metadata = SplitMetadata {
+                split_id: stale_split_id.to_string(),
+                ..Default::default()
+            };
+            metastore
+                .stage_split(index_id, split_metadata)
+                .await
+                .unwrap();
+            let stale_checkpoint_delta = CheckpointDelta::from_partition_delta(
+                PartitionId::from(0u64),
+                Position::from(42u64),
+                Position::from(50u64),
+            );
+            let result = metastore
+                .publish_splits(
+                    index_id,
+                    source_id,
+                    &[stale_split_id],
+                    stale_checkpoint_delta,
+                )
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                result,
+                MetastoreError::IncompatibleCheckpointDelta(_)
+            ));
+        }
+
+        // Reset the checkpoint to the beginning
+        {
+            let mut checkpoint_reset = CheckpointReset::default();
+            checkpoint_reset.record_partition_reset(
+                PartitionId::from(0u64),
+                Position::from(10u64),
+                Position::Beginning,
+            );
+            metastore
+                .reset_source_checkpoint(index_id, source_id, checkpoint_reset)
+                .await
+                .unwrap();
+            let index_metadata = metastore.index_metadata(index_id).await.unwrap();
+            let source_checkpoint = index_metadata
+                .checkpoint
+                .source_checkpoint(source_id)
+                .unwrap();
+            assert!(source_checkpoint.is_empty());
+        }
+
+        cleanup_index(&metastore, index_id).await;
+    }
+
     pub async fn test_metastore_create_index<MetastoreToTest: Metastore + DefaultForTest>() {
         let metastore = MetastoreToTest::default_for_test().await;
 
@@ -289,6 +457,55 @@ pub mod test_suite {
         cleanup_index(&metastore, index_id).await;
     }
 
+    pub async fn test_metastore_stage_splits<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = "stage-splits-index";
+        let index_metadata = IndexMetadata::for_test(index_id, "ram://indexes/my-index");
+        metastore.create_index(index_metadata).await.unwrap();
+
+        let split_metadata_fn = |split_id: &str| SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range: Some(0..=99),
+            ..Default::default()
+        };
+        metastore
+            .stage_splits(
+                index_id,
+                vec![
+                    split_metadata_fn("stage-splits-one"),
+                    split_metadata_fn("stage-splits-two"),
+                ],
+            )
+            .await
+            .unwrap();
+
+        // Staging an existing split fails, and none of the other splits are staged.
+        let result = metastore
+            .stage_splits(
+                index_id,
+                vec![
+                    split_metadata_fn("stage-splits-three"),
+                    split_metadata_fn("stage-splits-one"),
+                ],
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(result, MetastoreError::InternalError { .. }));
+
+        let split_ids: Vec<String> = metastore
+            .list_all_splits(index_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|split| split.split_id().to_string())
+            .sorted()
+            .collect();
+        assert_eq!(split_ids, ["stage-splits-one", "stage-splits-two"]);
+
+        cleanup_index(&metastore, index_id).await;
+    }
+
     pub async fn test_metastore_publish_splits_empty_splits_array_is_allowed<
         MetastoreToTest: Metastore + DefaultForTest,
     >() {
@@ -1267,6 +1484,273 @@ pub mod test_suite {
         }
     }
 
+    pub async fn test_metastore_list_splits_page<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+        let index_id = "list-splits-page-index";
+        let index_metadata = IndexMetadata::for_test(index_id, "ram://indexes/my-index");
+        let source_id = "list-splits-page-source";
+
+        let split_metadatas: Vec<SplitMetadata> = [
+            ("split-a-1", Some(0..=99), &["tag:foo"][..]),
+            ("split-a-2", Some(100..=199), &["tag:bar"][..]),
+            ("split-a_3", Some(200..=299), &["tag:foo"][..]),
+            ("split-b-1", None, &[][..]),
+            ("split-b-2", Some(300..=399), &["tag:foo", "tag:bar"][..]),
+        ]
+        .into_iter()
+        .map(|(split_id, time_range, tags)| SplitMetadata {
+            footer_offsets: 1000..2000,
+            split_id: split_id.to_string(),
+            num_docs: 1,
+            uncompressed_docs_size_in_bytes: 2,
+            time_range,
+            create_timestamp: current_timestamp,
+            tags: to_set(tags),
+            ..Default::default()
+        })
+        .collect();
+
+        let split_ids = |splits_page: &SplitsPage| -> Vec<String> {
+            splits_page
+                .splits
+                .iter()
+                .map(|split| split.split_id().to_string())
+                .collect()
+        };
+
+        // List splits on a non-existent index
+        {
+            let result = metastore
+                .list_splits_page("non-existent-index", ListSplitsQuery::default())
+                .await
+                .unwrap_err();
+            assert!(matches!(result, MetastoreError::IndexDoesNotExist { .. }));
+        }
+
+        metastore.create_index(index_metadata).await.unwrap();
+        for split_metadata in &split_metadatas {
+            metastore
+                .stage_split(index_id, split_metadata.clone())
+                .await
+                .unwrap();
+        }
+        metastore
+            .publish_splits(
+                index_id,
+                source_id,
+                &["split-a-1", "split-b-1"],
+                CheckpointDelta::default(),
+            )
+            .await
+            .unwrap();
+
+        // List all splits
+        {
+            let splits_page = metastore
+                .list_splits_page(index_id, ListSplitsQuery::default())
+                .await
+                .unwrap();
+            assert_eq!(
+                split_ids(&splits_page),
+                [
+                    "split-a-1",
+                    "split-a-2",
+                    "split-a_3",
+                    "split-b-1",
+                    "split-b-2"
+                ]
+            );
+            assert!(splits_page.next_cursor.is_none());
+        }
+
+        // List splits with filters
+        {
+            let query = ListSplitsQuery {
+                split_states: vec![SplitState::Staged],
+                ..Default::default()
+            };
+            let splits_page = metastore.list_splits_page(index_id, query).await.unwrap();
+            assert_eq!(
+                split_ids(&splits_page),
+                ["split-a-2", "split-a_3", "split-b-2"]
+            );
+
+            let query = ListSplitsQuery {
+                time_range: Some(150..300),
+                ..Default::default()
+            };
+            let splits_page = metastore.list_splits_page(index_id, query).await.unwrap();
+            assert_eq!(
+                split_ids(&splits_page),
+                ["split-a-2", "split-a_3", "split-b-1"]
+            );
+
+            let query = ListSplitsQuery {
+                tags: Some(tag("tag:foo")),
+                ..Default::default()
+            };
+            let splits_page = metastore.list_splits_page(index_id, query).await.unwrap();
+            assert_eq!(
+                split_ids(&splits_page),
+                ["split-a-1", "split-a_3", "split-b-2"]
+            );
+
+            let query = ListSplitsQuery {
+                create_timestamp: Some(current_timestamp + 1..current_timestamp + 10),
+                ..Default::default()
+            };
+            let splits_page = metastore.list_splits_page(index_id, query).await.unwrap();
+            assert!(splits_page.splits.is_empty());
+
+            let query = ListSplitsQuery {
+                split_id_prefix: Some("split-a_".to_string()),
+                ..Default::default()
+            };
+            let splits_page = metastore.list_splits_page(index_id, query).await.unwrap();
+            assert_eq!(split_ids(&splits_page), ["split-a_3"]);
+
+            let query = ListSplitsQuery {
+                split_states: vec![SplitState::Staged],
+                split_id_prefix: Some("split-a".to_string()),
+                tags: Some(tag("tag:foo")),
+                ..Default::default()
+            };
+            let splits_page = metastore.list_splits_page(index_id, query).await.unwrap();
+            assert_eq!(split_ids(&splits_page), ["split-a_3"]);
+        }
+
+        // List splits page by page
+        {
+            let mut query = ListSplitsQuery {
+                limit: Some(2),
+                ..Default::default()
+            };
+            let mut pages = Vec::new();
+            loop {
+                let splits_page = metastore
+                    .list_splits_page(index_id, query.clone())
+                    .await
+                    .unwrap();
+                pages.push(split_ids(&splits_page));
+                match splits_page.next_cursor {
+                    Some(next_cursor) => query.cursor = Some(next_cursor),
+                    None => break,
+                }
+            }
+            assert_eq!(
+                pages,
+                [
+                    vec!["split-a-1", "split-a-2"],
+                    vec!["split-a_3", "split-b-1"],
+                    vec!["split-b-2"],
+                ]
+            );
+        }
+
+        cleanup_index(&metastore, index_id).await;
+    }
+
+    pub async fn test_metastore_index_stats<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+        let index_id = "index-stats-index";
+        let index_metadata = IndexMetadata::for_test(index_id, "ram://indexes/my-index");
+        let source_id = "index-stats-source";
+
+        // Index stats on a non-existent index
+        {
+            let result = metastore.index_stats(index_id).await.unwrap_err();
+            assert!(matches!(result, MetastoreError::IndexDoesNotExist { .. }));
+        }
+
+        metastore.create_index(index_metadata).await.unwrap();
+
+        // Index stats on an empty index
+        {
+            let index_stats = metastore.index_stats(index_id).await.unwrap();
+            assert_eq!(index_stats.index_id, index_id);
+            assert_eq!(index_stats.num_splits, SplitCounts::default());
+            assert_eq!(index_stats.num_docs, 0);
+            assert!(index_stats.time_range.is_none());
+            assert!(index_stats.sources.is_empty());
+        }
+
+        let split_metadatas: Vec<SplitMetadata> = [
+            ("index-stats-one", Some(0..=99), &["tag!", "tag:foo"][..]),
+            ("index-stats-two", Some(100..=199), &["tag!", "tag:bar"][..]),
+            ("index-stats-three", None, &["tag!", "tag:foo"][..]),
+            (
+                "index-stats-four",
+                Some(1000..=1099),
+                &["tag!", "tag:baz"][..],
+            ),
+            ("index-stats-five", Some(-100..=0), &[][..]),
+        ]
+        .into_iter()
+        .map(|(split_id, time_range, tags)| SplitMetadata {
+            footer_offsets: 1000..2000,
+            split_id: split_id.to_string(),
+            num_docs: 1,
+            uncompressed_docs_size_in_bytes: 2,
+            time_range,
+            create_timestamp: current_timestamp,
+            tags: to_set(tags),
+            ..Default::default()
+        })
+        .collect();
+        for split_metadata in &split_metadatas {
+            metastore
+                .stage_split(index_id, split_metadata.clone())
+                .await
+                .unwrap();
+        }
+        metastore
+            .publish_splits(
+                index_id,
+                source_id,
+                &["index-stats-one", "index-stats-two", "index-stats-three"],
+                CheckpointDelta::from(0..3),
+            )
+            .await
+            .unwrap();
+        metastore
+            .mark_splits_for_deletion(index_id, &["index-stats-four"])
+            .await
+            .unwrap();
+
+        // Index stats on an index with splits
+        {
+            let index_stats = metastore.index_stats(index_id).await.unwrap();
+            assert_eq!(
+                index_stats.num_splits,
+                SplitCounts {
+                    staged: 1,
+                    published: 3,
+                    marked_for_deletion: 1,
+                }
+            );
+            assert_eq!(index_stats.num_docs, 3);
+            assert_eq!(index_stats.uncompressed_docs_size_in_bytes, 6);
+            assert_eq!(index_stats.split_size_in_bytes, 6000);
+            assert_eq!(index_stats.time_range, Some(0..=199));
+            assert_eq!(index_stats.sources.len(), 1);
+            assert_eq!(index_stats.sources[0].source_id, source_id);
+            assert_eq!(index_stats.sources[0].num_partitions, 1);
+            assert!(index_stats.sources[0].checkpoint_update_timestamp.is_some());
+            assert!(index_stats.sources[0]
+                .secs_since_checkpoint_update
+                .is_some());
+            assert_eq!(index_stats.tag_cardinalities.get("tag"), Some(&2));
+        }
+
+        cleanup_index(&metastore, index_id).await;
+    }
+
     pub async fn test_metastore_list_splits<MetastoreToTest: Metastore + DefaultForTest>() {
         let _ = tracing_subscriber::fmt::try_init();
         let metastore = MetastoreToTest::default_for_test().await;
@@ -1968,6 +2452,12 @@ macro_rules! metastore_test_suite {
                 crate::tests::test_suite::test_metastore_stage_split::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_stage_splits() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_stage_splits::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_publish_splits() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -2004,6 +2494,28 @@ macro_rules! metastore_test_suite {
                 crate::tests::test_suite::test_metastore_list_all_splits::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_list_splits_page() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_list_splits_page::<$metastore_type>()
+                    .await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_reset_source_checkpoint() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_reset_source_checkpoint::<
+                    $metastore_type,
+                >()
+                .await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_index_stats() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_index_stats::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_list_splits() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -20,18 +20,31 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=proto/search_api.proto");
     println!("cargo:rerun-if-changed=proto/ingest_api.proto");
+    println!("cargo:rerun-if-changed=proto/opentelemetry");
 
     let mut prost_config = prost_build::Config::default();
     // prost_config.type_attribute("LeafSearchResponse", "#[derive(Default)]");
     prost_config.protoc_arg("--experimental_allow_proto3_optional");
     tonic_build::configure()
         .type_attribute(".", "#[derive(Serialize, Deserialize)]")
+        .type_attribute("SearchRequest", "#[derive(Eq, Hash)]")
         .type_attribute("OutputFormat", "#[serde(rename_all = \"snake_case\")]")
+        .type_attribute("CommitType", "#[serde(rename_all = \"snake_case\")]")
         .out_dir("src/")
         .compile_with_config(
             prost_config,
             &["./proto/search_api.proto", "./proto/ingest_api.proto"],
             &["./proto"],
         )?;
+
+    // The OTLP logs receiver only implements the server side of the logs service.
+    tonic_build::configure()
+        .build_client(false)
+        .type_attribute(".", "#[derive(Serialize, Deserialize)]")
+        .out_dir("src/")
+        .compile(
+            &["./proto/opentelemetry/proto/collector/logs/v1/logs_service.proto"],
+            &["./proto"],
+        )?;
     Ok(())
 }
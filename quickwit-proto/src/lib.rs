@@ -24,12 +24,41 @@ pub mod ingest_api {
     pub use crate::quickwit_ingest_api::*;
 }
 
+/// Subset of the OpenTelemetry protocol used by the OTLP logs receiver.
+pub mod opentelemetry {
+    pub mod proto {
+        pub mod collector {
+            pub mod logs {
+                pub mod v1 {
+                    include!("opentelemetry.proto.collector.logs.v1.rs");
+                }
+            }
+        }
+        pub mod common {
+            pub mod v1 {
+                include!("opentelemetry.proto.common.v1.rs");
+            }
+        }
+        pub mod logs {
+            pub mod v1 {
+                include!("opentelemetry.proto.logs.v1.rs");
+            }
+        }
+        pub mod resource {
+            pub mod v1 {
+                include!("opentelemetry.proto.resource.v1.rs");
+            }
+        }
+    }
+}
+
 #[macro_use]
 extern crate serde;
 
 use std::fmt;
 
 pub use quickwit::*;
+pub use prost;
 pub use tonic;
 
 impl From<SearchStreamRequest> for SearchRequest {
@@ -1,6 +1,7 @@
 // -- Search -------------------
 
 #[derive(Serialize, Deserialize)]
+#[derive(Eq, Hash)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SearchRequest {
     /// Index ID
@@ -36,6 +37,11 @@ pub struct SearchRequest {
     /// json serialized aggregation_request
     #[prost(string, optional, tag="11")]
     pub aggregation_request: ::core::option::Option<::prost::alloc::string::String>,
+    /// Timeout in milliseconds after which the splits that have not been searched yet
+    /// are given up and a partial response is returned. If unset, the searcher
+    /// `search_timeout_millis` setting applies.
+    #[prost(uint64, optional, tag="12")]
+    pub timeout_millis: ::core::option::Option<u64>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -56,6 +62,13 @@ pub struct SearchResponse {
     /// Serialized aggregation response
     #[prost(string, optional, tag="5")]
     pub aggregation: ::core::option::Option<::prost::alloc::string::String>,
+    /// Set when some splits could not be searched, for instance before the
+    /// timeout. The response then only covers the other splits.
+    #[prost(bool, tag="6")]
+    pub is_partial: bool,
+    /// The splits that could not be searched and why.
+    #[prost(message, repeated, tag="7")]
+    pub failed_splits: ::prost::alloc::vec::Vec<SplitSearchError>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -88,6 +101,11 @@ pub struct LeafSearchRequest {
     /// split files.
     #[prost(string, tag="6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// Time left to the leaf, in milliseconds, to search the splits. The splits
+    /// that have not been searched by then are reported as failed, and the leaf
+    /// returns the results of the others.
+    #[prost(uint64, optional, tag="7")]
+    pub timeout_millis: ::core::option::Option<u64>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -102,6 +120,12 @@ pub struct SplitIdAndFooterOffsets {
     /// The offset of the end of the footer in split bundle. The footer contains the file bundle metada and the hotcache.
     #[prost(uint64, tag="3")]
     pub split_footer_end: u64,
+    /// The lowest timestamp appearing in the split, in seconds since epoch.
+    #[prost(int64, optional, tag="4")]
+    pub timestamp_start: ::core::option::Option<i64>,
+    /// The highest timestamp appearing in the split, in seconds since epoch.
+    #[prost(int64, optional, tag="5")]
+    pub timestamp_end: ::core::option::Option<i64>,
 }
 //// Hits returned by a FetchDocRequest.
 ////
@@ -27,6 +27,8 @@ pub struct DropQueueRequest {
 pub struct IngestRequest {
     #[prost(message, repeated, tag="1")]
     pub doc_batches: ::prost::alloc::vec::Vec<DocBatch>,
+    #[prost(enumeration="CommitType", tag="2")]
+    pub commit: i32,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -51,6 +53,10 @@ pub struct FetchResponse {
     pub first_position: ::core::option::Option<u64>,
     #[prost(message, optional, tag="2")]
     pub doc_batch: ::core::option::Option<DocBatch>,
+    /// Set when an ingest request with the `FORCE` commit type requires the fetched records to be
+    /// committed right after they are indexed.
+    #[prost(bool, tag="3")]
+    pub force_commit: bool,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -99,6 +105,20 @@ pub struct ListQueuesResponse {
     #[prost(string, repeated, tag="1")]
     pub queues: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
+/// Specifies when an ingest request returns with respect to the commit of its documents.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CommitType {
+    /// Returns as soon as the documents are written to the queues. They are committed according
+    /// to the `commit_timeout_secs` indexing setting.
+    Auto = 0,
+    /// Waits until the documents are published in a split and searchable.
+    WaitFor = 1,
+    /// Forces a commit right after the documents are indexed and waits until they are published.
+    Force = 2,
+}
 /// Generated client implementations.
 pub mod ingest_api_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
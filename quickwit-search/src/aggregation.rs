@@ -0,0 +1,448 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Aggregations computed by Quickwit on top of the tantivy aggregations.
+//!
+//! An aggregation request may mix tantivy aggregations and `geohash_grid`
+//! aggregations, which group the hits by the geohash cell of a `geo_point` field:
+//!
+//! ```json
+//! {
+//!     "cells": {
+//!         "geohash_grid": { "field": "location", "precision": 5, "size": 1000 }
+//!     }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use quickwit_doc_mapper::{is_geo_point_field, GEOHASH_MAX_PRECISION};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tantivy::aggregation::agg_req::{
+    get_fast_field_names, get_term_dict_field_names, Aggregations,
+};
+use tantivy::aggregation::agg_result::AggregationResults;
+use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
+use tantivy::aggregation::AggregationSegmentCollector;
+use tantivy::fastfield::MultiValuedFastFieldReader;
+use tantivy::{DocId, InvertedIndexReader, Score, SegmentReader, TantivyError};
+
+const GEOHASH_GRID_AGGREGATION_KEY: &str = "geohash_grid";
+
+/// Groups the hits by the geohash cell of a `geo_point` field.
+///
+/// The field has to be a fast field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeohashGridAggregation {
+    /// Name of the `geo_point` field.
+    pub field: String,
+    /// Length of the geohashes of the cells, from 1 to 12.
+    #[serde(default = "default_geohash_grid_precision")]
+    pub precision: usize,
+    /// Maximum number of cells returned, by decreasing number of hits.
+    #[serde(default = "default_geohash_grid_size")]
+    pub size: usize,
+}
+
+fn default_geohash_grid_precision() -> usize {
+    5
+}
+
+fn default_geohash_grid_size() -> usize {
+    10_000
+}
+
+/// Aggregations of a search request.
+#[derive(Clone, Debug, Default)]
+pub struct QuickwitAggregations {
+    /// Aggregations handled by tantivy.
+    pub tantivy_aggregations: Option<Aggregations>,
+    /// `geohash_grid` aggregations, by name.
+    pub geohash_grids: BTreeMap<String, GeohashGridAggregation>,
+}
+
+impl QuickwitAggregations {
+    /// Parses an aggregation request.
+    pub fn parse(aggregation_request: &str) -> anyhow::Result<Self> {
+        let aggregations_json: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(aggregation_request)?;
+        let mut tantivy_aggregations_json = serde_json::Map::new();
+        let mut geohash_grids = BTreeMap::new();
+        for (name, aggregation_json) in aggregations_json {
+            let geohash_grid_json_opt = aggregation_json
+                .as_object()
+                .and_then(|aggregation_obj| aggregation_obj.get(GEOHASH_GRID_AGGREGATION_KEY));
+            if let Some(geohash_grid_json) = geohash_grid_json_opt {
+                if aggregation_json.as_object().map(|obj| obj.len()) != Some(1) {
+                    anyhow::bail!(
+                        "Sub-aggregations are not supported by the `geohash_grid` aggregation \
+                         `{}`.",
+                        name
+                    );
+                }
+                let geohash_grid: GeohashGridAggregation =
+                    serde_json::from_value(geohash_grid_json.clone())?;
+                if !(1..=GEOHASH_MAX_PRECISION).contains(&geohash_grid.precision) {
+                    anyhow::bail!(
+                        "The precision of the `geohash_grid` aggregation `{}` must be between 1 \
+                         and {}.",
+                        name,
+                        GEOHASH_MAX_PRECISION
+                    );
+                }
+                geohash_grids.insert(name, geohash_grid);
+            } else {
+                tantivy_aggregations_json.insert(name, aggregation_json);
+            }
+        }
+        let tantivy_aggregations = if geohash_grids.is_empty() {
+            // Parsing the original request keeps the error positions.
+            Some(serde_json::from_str(aggregation_request)?)
+        } else if tantivy_aggregations_json.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_value(JsonValue::Object(
+                tantivy_aggregations_json,
+            ))?)
+        };
+        Ok(QuickwitAggregations {
+            tantivy_aggregations,
+            geohash_grids,
+        })
+    }
+
+    /// Returns the names of the fast fields required by the aggregations.
+    pub fn fast_field_names(&self) -> HashSet<String> {
+        let mut fast_field_names = HashSet::default();
+        if let Some(tantivy_aggregations) = self.tantivy_aggregations.as_ref() {
+            fast_field_names.extend(get_fast_field_names(tantivy_aggregations));
+        }
+        for geohash_grid in self.geohash_grids.values() {
+            fast_field_names.insert(geohash_grid.field.clone());
+        }
+        fast_field_names
+    }
+
+    /// Returns the names of the fields whose whole term dictionary is required by the
+    /// aggregations.
+    pub fn term_dict_field_names(&self) -> HashSet<String> {
+        let mut term_dict_field_names = HashSet::default();
+        if let Some(tantivy_aggregations) = self.tantivy_aggregations.as_ref() {
+            term_dict_field_names.extend(get_term_dict_field_names(tantivy_aggregations));
+        }
+        // The geohashes are fetched from the term dictionary, given their ordinals.
+        for geohash_grid in self.geohash_grids.values() {
+            term_dict_field_names.insert(geohash_grid.field.clone());
+        }
+        term_dict_field_names
+    }
+}
+
+/// Intermediate results of the aggregations, computed by the leaves and merged by the root.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuickwitIntermediateAggregationResults {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tantivy: Option<IntermediateAggregationResults>,
+    /// Number of hits per geohash cell, by aggregation name.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    geohash_grids: BTreeMap<String, HashMap<String, u64>>,
+}
+
+impl QuickwitIntermediateAggregationResults {
+    /// Merges the results of another leaf or segment into `self`.
+    pub fn merge_fruits(&mut self, other: QuickwitIntermediateAggregationResults) {
+        self.tantivy = match (self.tantivy.take(), other.tantivy) {
+            (Some(mut tantivy_results), Some(other_tantivy_results)) => {
+                tantivy_results.merge_fruits(other_tantivy_results);
+                Some(tantivy_results)
+            }
+            (tantivy_results_opt, other_tantivy_results_opt) => {
+                tantivy_results_opt.or(other_tantivy_results_opt)
+            }
+        };
+        for (name, other_cell_counts) in other.geohash_grids {
+            let cell_counts = self.geohash_grids.entry(name).or_default();
+            for (geohash, count) in other_cell_counts {
+                *cell_counts.entry(geohash).or_default() += count;
+            }
+        }
+    }
+
+    /// Computes the final results of the aggregations, as a JSON object.
+    pub fn into_final_result(
+        self,
+        aggregations: QuickwitAggregations,
+    ) -> crate::Result<serde_json::Map<String, JsonValue>> {
+        let mut final_result = serde_json::Map::new();
+        if let (Some(tantivy_results), Some(tantivy_aggregations)) =
+            (self.tantivy, aggregations.tantivy_aggregations)
+        {
+            let tantivy_final_result: AggregationResults =
+                tantivy_results.into_final_bucket_result(tantivy_aggregations)?;
+            if let JsonValue::Object(tantivy_final_result_json) =
+                serde_json::to_value(tantivy_final_result)?
+            {
+                final_result = tantivy_final_result_json;
+            }
+        }
+        let mut geohash_grid_results = self.geohash_grids;
+        for (name, geohash_grid) in aggregations.geohash_grids {
+            let cell_counts = geohash_grid_results.remove(&name).unwrap_or_default();
+            let mut buckets: Vec<(String, u64)> = cell_counts.into_iter().collect();
+            buckets.sort_by(|(left_geohash, left_count), (right_geohash, right_count)| {
+                right_count
+                    .cmp(left_count)
+                    .then_with(|| left_geohash.cmp(right_geohash))
+            });
+            buckets.truncate(geohash_grid.size);
+            let buckets_json: Vec<JsonValue> = buckets
+                .into_iter()
+                .map(|(geohash, count)| serde_json::json!({"key": geohash, "doc_count": count}))
+                .collect();
+            final_result.insert(name, serde_json::json!({ "buckets": buckets_json }));
+        }
+        Ok(final_result)
+    }
+}
+
+/// Computes the aggregations at the scale of a segment.
+pub struct QuickwitAggregationSegmentCollector {
+    tantivy_collector_opt: Option<AggregationSegmentCollector>,
+    geohash_grid_collectors: Vec<(String, GeohashGridSegmentCollector)>,
+}
+
+impl QuickwitAggregationSegmentCollector {
+    pub fn from_aggregations_and_reader(
+        aggregations: &QuickwitAggregations,
+        segment_reader: &SegmentReader,
+        bucket_limit: u32,
+    ) -> tantivy::Result<Self> {
+        let tantivy_collector_opt = aggregations
+            .tantivy_aggregations
+            .as_ref()
+            .map(|tantivy_aggregations| {
+                AggregationSegmentCollector::from_agg_req_and_reader(
+                    tantivy_aggregations,
+                    segment_reader,
+                    bucket_limit,
+                )
+            })
+            .transpose()?;
+        let geohash_grid_collectors = aggregations
+            .geohash_grids
+            .iter()
+            .map(|(name, geohash_grid)| {
+                let geohash_grid_collector =
+                    GeohashGridSegmentCollector::new(geohash_grid, segment_reader)?;
+                Ok((name.clone(), geohash_grid_collector))
+            })
+            .collect::<tantivy::Result<_>>()?;
+        Ok(QuickwitAggregationSegmentCollector {
+            tantivy_collector_opt,
+            geohash_grid_collectors,
+        })
+    }
+
+    pub fn collect(&mut self, doc_id: DocId, score: Score) {
+        if let Some(tantivy_collector) = self.tantivy_collector_opt.as_mut() {
+            tantivy_collector.collect(doc_id, score);
+        }
+        for (_, geohash_grid_collector) in self.geohash_grid_collectors.iter_mut() {
+            geohash_grid_collector.collect(doc_id);
+        }
+    }
+
+    pub fn harvest(self) -> tantivy::Result<QuickwitIntermediateAggregationResults> {
+        let tantivy = self
+            .tantivy_collector_opt
+            .map(|tantivy_collector| tantivy_collector.harvest())
+            .transpose()?;
+        let geohash_grids = self
+            .geohash_grid_collectors
+            .into_iter()
+            .map(|(name, geohash_grid_collector)| (name, geohash_grid_collector.cell_counts))
+            .collect();
+        Ok(QuickwitIntermediateAggregationResults {
+            tantivy,
+            geohash_grids,
+        })
+    }
+}
+
+/// Counts the hits per geohash cell.
+///
+/// The `geo_point` fast field holds, for every document, the ordinals of all the
+/// prefixes of the geohashes of its points. The ordinals of the geohashes of the requested
+/// precision are resolved through the term dictionary.
+struct GeohashGridSegmentCollector {
+    precision: usize,
+    ordinals_reader: MultiValuedFastFieldReader<u64>,
+    inverted_index: Arc<InvertedIndexReader>,
+    /// Geohash of the requested precision of the ordinals already seen, if any.
+    ordinal_geohashes: HashMap<u64, Option<String>>,
+    cell_counts: HashMap<String, u64>,
+    ordinals_buffer: Vec<u64>,
+}
+
+impl GeohashGridSegmentCollector {
+    fn new(
+        geohash_grid: &GeohashGridAggregation,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self> {
+        let schema = segment_reader.schema();
+        let field = schema
+            .get_field(&geohash_grid.field)
+            .filter(|&field| {
+                let field_entry = schema.get_field_entry(field);
+                is_geo_point_field(field_entry) && field_entry.is_fast()
+            })
+            .ok_or_else(|| {
+                TantivyError::InvalidArgument(format!(
+                    "Field `{}` of the `geohash_grid` aggregation must be a fast `geo_point` \
+                     field.",
+                    geohash_grid.field
+                ))
+            })?;
+        Ok(GeohashGridSegmentCollector {
+            precision: geohash_grid.precision,
+            ordinals_reader: segment_reader.fast_fields().u64s_lenient(field)?,
+            inverted_index: segment_reader.inverted_index(field)?,
+            ordinal_geohashes: HashMap::new(),
+            cell_counts: HashMap::new(),
+            ordinals_buffer: Vec::new(),
+        })
+    }
+
+    fn collect(&mut self, doc_id: DocId) {
+        self.ordinals_reader
+            .get_vals(doc_id, &mut self.ordinals_buffer);
+        let precision = self.precision;
+        let inverted_index = &self.inverted_index;
+        let mut doc_geohashes: Vec<String> = Vec::new();
+        for ordinal in &self.ordinals_buffer {
+            let geohash_opt = self.ordinal_geohashes.entry(*ordinal).or_insert_with(|| {
+                let mut geohash_bytes = Vec::new();
+                match inverted_index
+                    .terms()
+                    .ord_to_term(*ordinal, &mut geohash_bytes)
+                {
+                    Ok(true) if geohash_bytes.len() == precision => {
+                        String::from_utf8(geohash_bytes).ok()
+                    }
+                    _ => None,
+                }
+            });
+            if let Some(geohash) = geohash_opt {
+                doc_geohashes.push(geohash.clone());
+            }
+        }
+        // A document with several points in the same cell is counted once.
+        doc_geohashes.sort();
+        doc_geohashes.dedup();
+        for geohash in doc_geohashes {
+            *self.cell_counts.entry(geohash).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quickwit_aggregations() {
+        let aggregations = QuickwitAggregations::parse(
+            r#"{
+                "cells": {"geohash_grid": {"field": "location", "precision": 3}},
+                "range_rollover": {"range": {"field": "score", "ranges": [{"to": 3.0}]}}
+            }"#,
+        )
+        .unwrap();
+        assert!(aggregations.tantivy_aggregations.is_some());
+        assert_eq!(
+            aggregations.geohash_grids.get("cells").unwrap(),
+            &GeohashGridAggregation {
+                field: "location".to_string(),
+                precision: 3,
+                size: 10_000,
+            }
+        );
+        assert_eq!(
+            aggregations.fast_field_names(),
+            HashSet::from(["location".to_string(), "score".to_string()])
+        );
+
+        let aggregations =
+            QuickwitAggregations::parse(r#"{"cells": {"geohash_grid": {"field": "location"}}}"#)
+                .unwrap();
+        assert!(aggregations.tantivy_aggregations.is_none());
+        assert_eq!(
+            aggregations.geohash_grids.get("cells").unwrap().precision,
+            5
+        );
+
+        assert!(QuickwitAggregations::parse(
+            r#"{"cells": {"geohash_grid": {"field": "location", "precision": 13}}}"#
+        )
+        .is_err());
+        assert!(QuickwitAggregations::parse(
+            r#"{"cells": {"geohash_grid": {"field": "location"}, "aggs": {}}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_geohash_grid_final_result() {
+        let aggregations = QuickwitAggregations::parse(
+            r#"{"cells": {"geohash_grid": {"field": "location", "size": 2}}}"#,
+        )
+        .unwrap();
+        let mut results = QuickwitIntermediateAggregationResults {
+            tantivy: None,
+            geohash_grids: BTreeMap::from([(
+                "cells".to_string(),
+                HashMap::from([("u09tv".to_string(), 3), ("u09tu".to_string(), 1)]),
+            )]),
+        };
+        results.merge_fruits(QuickwitIntermediateAggregationResults {
+            tantivy: None,
+            geohash_grids: BTreeMap::from([(
+                "cells".to_string(),
+                HashMap::from([("u09tu".to_string(), 3), ("dr5re".to_string(), 1)]),
+            )]),
+        });
+        let final_result = results.into_final_result(aggregations).unwrap();
+        assert_eq!(
+            JsonValue::Object(final_result),
+            serde_json::json!({
+                "cells": {
+                    "buckets": [
+                        {"key": "u09tu", "doc_count": 4},
+                        {"key": "u09tv", "doc_count": 3}
+                    ]
+                }
+            })
+        );
+    }
+}
@@ -19,6 +19,7 @@
 
 use std::fmt;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use futures::{StreamExt, TryStreamExt};
@@ -54,6 +55,26 @@ enum SearchServiceClientImpl {
     Grpc(quickwit_proto::search_service_client::SearchServiceClient<Channel>),
 }
 
+/// Cost of the jobs assigned to a node by an in-flight request. The cost is added to the load of
+/// the node on creation and removed on drop.
+struct InFlightLoad {
+    node_load: Arc<AtomicU64>,
+    cost: u64,
+}
+
+impl InFlightLoad {
+    fn new(node_load: Arc<AtomicU64>, cost: u64) -> Self {
+        node_load.fetch_add(cost, Ordering::Relaxed);
+        Self { node_load, cost }
+    }
+}
+
+impl Drop for InFlightLoad {
+    fn drop(&mut self) {
+        self.node_load.fetch_sub(self.cost, Ordering::Relaxed);
+    }
+}
+
 /// A search service client.
 /// It contains the client implementation and the gRPC address of the node to which the client
 /// connects.
@@ -61,6 +82,11 @@ enum SearchServiceClientImpl {
 pub struct SearchServiceClient {
     client_impl: SearchServiceClientImpl,
     grpc_addr: SocketAddr,
+    /// Sum of the costs of the in-flight jobs assigned to the node, shared by the clones of the
+    /// client.
+    node_load: Arc<AtomicU64>,
+    /// Load held by this client and its clones, released when the last of them is dropped.
+    in_flight_load_opt: Option<Arc<InFlightLoad>>,
 }
 
 impl fmt::Debug for SearchServiceClient {
@@ -85,6 +111,8 @@ impl SearchServiceClient {
         SearchServiceClient {
             client_impl: SearchServiceClientImpl::Grpc(client),
             grpc_addr,
+            node_load: Default::default(),
+            in_flight_load_opt: None,
         }
     }
 
@@ -93,6 +121,8 @@ impl SearchServiceClient {
         SearchServiceClient {
             client_impl: SearchServiceClientImpl::Local(service),
             grpc_addr,
+            node_load: Default::default(),
+            in_flight_load_opt: None,
         }
     }
 
@@ -101,6 +131,18 @@ impl SearchServiceClient {
         self.grpc_addr
     }
 
+    /// Returns the sum of the costs of the in-flight jobs assigned to the node.
+    pub(crate) fn node_load(&self) -> u64 {
+        self.node_load.load(Ordering::Relaxed)
+    }
+
+    /// Returns a client that adds `cost` to the load of the node until it and its clones are
+    /// dropped.
+    pub(crate) fn with_in_flight_load(mut self, cost: u64) -> Self {
+        self.in_flight_load_opt = Some(Arc::new(InFlightLoad::new(self.node_load.clone(), cost)));
+        self
+    }
+
     /// Perform root search.
     pub async fn root_search(
         &mut self,
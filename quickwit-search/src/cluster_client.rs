@@ -22,12 +22,12 @@ use quickwit_proto::{
     FetchDocsRequest, FetchDocsResponse, LeafSearchRequest, LeafSearchResponse,
     LeafSearchStreamRequest, LeafSearchStreamResponse,
 };
-use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::debug;
 
+use crate::aggregation::QuickwitIntermediateAggregationResults;
 use crate::retry::search::LeafSearchRetryPolicy;
 use crate::retry::search_stream::{LeafSearchStreamRetryPolicy, SuccessfullSplitIds};
 use crate::retry::{retry_client, DefaultRetryPolicy, RetryPolicy};
@@ -161,9 +161,10 @@ fn merge_leaf_search_results(
                 .map(|res1_str| {
                     if let Some(res2_str) = retry_response.intermediate_aggregation_result.as_ref()
                     {
-                        let mut res1: IntermediateAggregationResults =
+                        let mut res1: QuickwitIntermediateAggregationResults =
                             serde_json::from_str(&res1_str)?;
-                        let res2: IntermediateAggregationResults = serde_json::from_str(res2_str)?;
+                        let res2: QuickwitIntermediateAggregationResults =
+                            serde_json::from_str(res2_str)?;
                         res1.merge_fruits(res2);
                         serde_json::to_string(&res1)
                     } else {
@@ -177,7 +178,13 @@ fn merge_leaf_search_results(
                 num_hits: initial_response.num_hits + retry_response.num_hits,
                 num_attempted_splits: initial_response.num_attempted_splits
                     + retry_response.num_attempted_splits,
-                failed_splits: retry_response.failed_splits,
+                // The splits that failed with a non-retryable error were not retried.
+                failed_splits: initial_response
+                    .failed_splits
+                    .into_iter()
+                    .filter(|failed_split| !failed_split.retryable_error)
+                    .chain(retry_response.failed_splits)
+                    .collect(),
                 partial_hits: initial_response.partial_hits,
             };
             Ok(merged_response)
@@ -246,6 +253,7 @@ mod tests {
                 split_id: split_id.to_string(),
                 split_footer_end: 100,
                 split_footer_start: 0,
+                ..Default::default()
             }],
         }
     }
@@ -265,16 +273,19 @@ mod tests {
             search_request: Some(search_request),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            timeout_millis: None,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    ..Default::default()
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    ..Default::default()
                 },
             ],
         }
@@ -300,11 +311,13 @@ mod tests {
                     split_id: "split_1".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    ..Default::default()
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    ..Default::default()
                 },
             ],
         }
@@ -475,6 +488,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_merge_leaf_search_retry_keeps_non_retryable_failed_splits() -> anyhow::Result<()> {
+        let leaf_response = LeafSearchResponse {
+            num_hits: 1,
+            partial_hits: vec![mock_partial_hit("split_1", 3, 1)],
+            failed_splits: vec![
+                SplitSearchError {
+                    error: "error".to_string(),
+                    split_id: "split_2".to_string(),
+                    retryable_error: true,
+                },
+                SplitSearchError {
+                    error: "Split search timed out.".to_string(),
+                    split_id: "split_3".to_string(),
+                    retryable_error: false,
+                },
+            ],
+            num_attempted_splits: 3,
+            ..Default::default()
+        };
+        let leaf_response_retry = LeafSearchResponse {
+            num_hits: 1,
+            partial_hits: vec![mock_partial_hit("split_2", 3, 1)],
+            failed_splits: vec![],
+            num_attempted_splits: 1,
+            ..Default::default()
+        };
+        let merged_leaf_search_response =
+            merge_leaf_search_results(Ok(leaf_response), Ok(leaf_response_retry)).unwrap();
+        assert_eq!(merged_leaf_search_response.num_hits, 2);
+        assert_eq!(merged_leaf_search_response.failed_splits.len(), 1);
+        assert_eq!(
+            merged_leaf_search_response.failed_splits[0].split_id,
+            "split_3"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_merge_leaf_search_retry_on_error() -> anyhow::Result<()> {
         let split_error = SplitSearchError {
@@ -23,16 +23,15 @@ use std::collections::{BinaryHeap, HashSet};
 use itertools::Itertools;
 use quickwit_doc_mapper::{DocMapper, SortBy, SortOrder};
 use quickwit_proto::{LeafSearchResponse, PartialHit, SearchRequest};
-use tantivy::aggregation::agg_req::{
-    get_fast_field_names, get_term_dict_field_names, Aggregations,
-};
-use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
-use tantivy::aggregation::AggregationSegmentCollector;
 use tantivy::collector::{Collector, SegmentCollector};
 use tantivy::fastfield::{DynamicFastFieldReader, FastFieldReader};
 use tantivy::schema::Schema;
 use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader};
 
+use crate::aggregation::{
+    QuickwitAggregationSegmentCollector, QuickwitAggregations,
+    QuickwitIntermediateAggregationResults,
+};
 use crate::filters::{TimestampFilter, TimestampFilterBuilder};
 use crate::partial_hit_sorting_key;
 
@@ -141,7 +140,7 @@ pub struct QuickwitSegmentCollector {
     max_hits: usize,
     segment_ord: u32,
     timestamp_filter_opt: Option<TimestampFilter>,
-    aggregation: Option<AggregationSegmentCollector>,
+    aggregation: Option<QuickwitAggregationSegmentCollector>,
 }
 
 impl QuickwitSegmentCollector {
@@ -241,7 +240,7 @@ pub struct QuickwitCollector {
     pub max_hits: usize,
     pub sort_by: SortBy,
     timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
-    pub aggregation: Option<Aggregations>,
+    pub aggregation: Option<QuickwitAggregations>,
 }
 
 impl QuickwitCollector {
@@ -254,7 +253,7 @@ impl QuickwitCollector {
             }
         }
         if let Some(aggregate) = self.aggregation.as_ref() {
-            fast_field_names.extend(get_fast_field_names(aggregate));
+            fast_field_names.extend(aggregate.fast_field_names());
         }
         if let Some(timestamp_filter_builder) = &self.timestamp_filter_builder_opt {
             fast_field_names.insert(timestamp_filter_builder.timestamp_field_name.clone());
@@ -264,7 +263,7 @@ impl QuickwitCollector {
     pub fn term_dict_field_names(&self) -> HashSet<String> {
         let mut term_dict_field_names = HashSet::default();
         if let Some(aggregate) = self.aggregation.as_ref() {
-            term_dict_field_names.extend(get_term_dict_field_names(aggregate));
+            term_dict_field_names.extend(aggregate.term_dict_field_names());
         }
         term_dict_field_names
     }
@@ -305,7 +304,7 @@ impl Collector for QuickwitCollector {
                 .aggregation
                 .as_ref()
                 .map(|aggs| {
-                    AggregationSegmentCollector::from_agg_req_and_reader(
+                    QuickwitAggregationSegmentCollector::from_aggregations_and_reader(
                         aggs,
                         segment_reader,
                         AGGREGATION_BUCKET_LIMIT,
@@ -363,7 +362,7 @@ fn merge_leaf_responses(
                 .as_ref()
                 .map(|res| serde_json::from_str(res))
         })
-        .collect::<Result<Vec<IntermediateAggregationResults>, _>>()?;
+        .collect::<Result<Vec<QuickwitIntermediateAggregationResults>, _>>()?;
 
     let intermediate_aggregation_result =
         intermediate_aggregation_results
@@ -426,7 +425,7 @@ pub fn make_collector_for_split(
     split_schema: &Schema,
 ) -> crate::Result<QuickwitCollector> {
     let aggregation = if let Some(agg) = search_request.aggregation_request.as_ref() {
-        Some(serde_json::from_str(agg)?)
+        Some(QuickwitAggregations::parse(agg)?)
     } else {
         None
     };
@@ -455,7 +454,7 @@ pub fn make_collector_for_split(
 /// can be set to default.
 pub fn make_merge_collector(search_request: &SearchRequest) -> crate::Result<QuickwitCollector> {
     let aggregation = if let Some(agg) = search_request.aggregation_request.as_ref() {
-        Some(serde_json::from_str(agg)?)
+        Some(QuickwitAggregations::parse(agg)?)
     } else {
         None
     };
@@ -21,6 +21,7 @@ use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use futures::future::try_join_all;
@@ -29,7 +30,7 @@ use itertools::{Either, Itertools};
 use once_cell::sync::OnceCell;
 use quickwit_config::get_searcher_config_instance;
 use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
-use quickwit_doc_mapper::{DocMapper, QUICKWIT_TOKENIZER_MANAGER};
+use quickwit_doc_mapper::{is_geo_point_field, DocMapper, QUICKWIT_TOKENIZER_MANAGER};
 use quickwit_proto::{
     LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets, SplitSearchError,
 };
@@ -44,9 +45,11 @@ use tantivy::schema::{Cardinality, FieldType};
 use tantivy::{Index, ReloadPolicy, Searcher, Term};
 use tokio::sync::{Semaphore, SemaphorePermit};
 use tokio::task::spawn_blocking;
+use tokio::time::Instant;
 use tracing::*;
 
 use crate::collector::{make_collector_for_split, make_merge_collector};
+use crate::leaf_cache::LeafSearchCache;
 use crate::SearchError;
 
 async fn get_leaf_search_split_semaphore() -> SemaphorePermit<'static> {
@@ -72,6 +75,14 @@ fn global_split_footer_cache() -> &'static MemorySizedCache<String> {
     })
 }
 
+fn global_leaf_search_cache() -> &'static LeafSearchCache {
+    static INSTANCE: OnceCell<LeafSearchCache> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let config = get_searcher_config_instance();
+        LeafSearchCache::new(config.leaf_search_cache_capacity.get_bytes() as usize)
+    })
+}
+
 async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
@@ -169,11 +180,22 @@ pub(crate) async fn warmup(
     fast_field_names: &HashSet<String>,
     term_dict_field_names: &HashSet<String>,
 ) -> anyhow::Result<()> {
+    // Geo queries on fast `geo_point` fields filter their matches by reading the points from the
+    // fast field and the term dictionary.
+    let geo_point_field_names = geo_point_filter_field_names(searcher, query);
+    let fast_field_names: HashSet<String> = fast_field_names
+        .union(&geo_point_field_names)
+        .cloned()
+        .collect();
+    let term_dict_field_names: HashSet<String> = term_dict_field_names
+        .union(&geo_point_field_names)
+        .cloned()
+        .collect();
     let warm_up_terms_future =
         warm_up_terms(searcher, query).instrument(debug_span!("warm_up_terms"));
-    let warm_up_term_dict_future = warm_up_term_dict_fields(searcher, term_dict_field_names)
+    let warm_up_term_dict_future = warm_up_term_dict_fields(searcher, &term_dict_field_names)
         .instrument(debug_span!("warm_up_term_dicts"));
-    let warm_up_fastfields_future = warm_up_fastfields(searcher, fast_field_names)
+    let warm_up_fastfields_future = warm_up_fastfields(searcher, &fast_field_names)
         .instrument(debug_span!("warm_up_fastfields"));
     let (warm_up_terms_res, warm_up_fastfields_res, warm_up_term_dict_res) = tokio::join!(
         warm_up_terms_future,
@@ -186,6 +208,22 @@ pub(crate) async fn warmup(
     Ok(())
 }
 
+/// Returns the names of the fast `geo_point` fields targeted by the query.
+fn geo_point_filter_field_names(searcher: &Searcher, query: &dyn Query) -> HashSet<String> {
+    let mut terms: BTreeMap<Term, bool> = Default::default();
+    query.query_terms(&mut terms);
+    let schema = searcher.schema();
+    terms
+        .keys()
+        .map(|term| term.field())
+        .filter(|&field| {
+            let field_entry = schema.get_field_entry(field);
+            is_geo_point_field(field_entry) && field_entry.is_fast()
+        })
+        .map(|field| schema.get_field_name(field).to_string())
+        .collect()
+}
+
 async fn warm_up_term_dict_fields(
     searcher: &Searcher,
     term_dict_field_names: &HashSet<String>,
@@ -324,7 +362,8 @@ async fn leaf_search_single_split(
     leaf_split_search_permit: SemaphorePermit<'static>,
 ) -> crate::Result<LeafSearchResponse> {
     let split_id = split.split_id.to_string();
-    let index = open_index(storage, &split).await?;
+    let mut index = open_index(storage, &split).await?;
+    index.set_tokenizers(doc_mapper.tokenizer_manager().clone());
     let split_schema = index.schema();
     let quickwit_collector = make_collector_for_split(
         split_id.clone(),
@@ -364,18 +403,26 @@ async fn leaf_search_single_split(
 /// [PartialHit](quickwit_proto::PartialHit) candidates. The root will be in
 /// charge to consolidate, identify the actual final top hits to display, and
 /// fetch the actual documents to convert the partial hits into actual Hits.
+///
+/// If a timeout is given, the splits that have not been searched when it expires are reported as
+/// failed, and the response only covers the other splits.
 pub async fn leaf_search(
     request: &SearchRequest,
     index_storage: Arc<dyn Storage>,
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
+    timeout_opt: Option<Duration>,
 ) -> Result<LeafSearchResponse, SearchError> {
+    let deadline_opt = timeout_opt.map(|timeout| Instant::now() + timeout);
     let leaf_search_single_split_futures: Vec<_> = splits
         .iter()
         .map(|split| {
             let doc_mapper_clone = doc_mapper.clone();
             let index_storage_clone = index_storage.clone();
-            async move {
+            let leaf_search_single_split_future = async move {
+                if let Some(cached_response) = global_leaf_search_cache().get(split, request) {
+                    return Ok(cached_response);
+                }
                 let leaf_split_search_permit = get_leaf_search_split_semaphore().await;
                 crate::SEARCH_METRICS.leaf_searches_splits_total.inc();
                 let timer = crate::SEARCH_METRICS
@@ -390,7 +437,32 @@ pub async fn leaf_search(
                 )
                 .await;
                 timer.observe_duration();
-                leaf_search_single_split_res.map_err(|err| (split.split_id.clone(), err))
+                if let Ok(leaf_search_response) = &leaf_search_single_split_res {
+                    if leaf_search_response.failed_splits.is_empty() {
+                        global_leaf_search_cache().put(split, request, leaf_search_response);
+                    }
+                }
+                leaf_search_single_split_res.map_err(|err| SplitSearchError {
+                    split_id: split.split_id.clone(),
+                    error: format!("{}", err),
+                    retryable_error: true,
+                })
+            };
+            async move {
+                let deadline = match deadline_opt {
+                    Some(deadline) => deadline,
+                    None => return leaf_search_single_split_future.await,
+                };
+                tokio::time::timeout_at(deadline, leaf_search_single_split_future)
+                    .await
+                    .unwrap_or_else(|_| {
+                        // The time budget of the request is spent: retrying would not help.
+                        Err(SplitSearchError {
+                            split_id: split.split_id.clone(),
+                            error: "Split search timed out.".to_string(),
+                            retryable_error: false,
+                        })
+                    })
             }
         })
         .collect();
@@ -400,7 +472,7 @@ pub async fn leaf_search(
     // (Vec<tantivy::Result<LeafSearchResponse>>)
     let (split_search_responses, errors): (
         Vec<tantivy::Result<LeafSearchResponse>>,
-        Vec<(String, SearchError)>,
+        Vec<SplitSearchError>,
     ) = split_search_results
         .into_iter()
         .partition_map(|split_search_res| match split_search_res {
@@ -419,12 +491,6 @@ pub async fn leaf_search(
             .await
             .context("Failed to merge split search responses.")??;
 
-    merged_search_response
-        .failed_splits
-        .extend(errors.iter().map(|(split_id, err)| SplitSearchError {
-            split_id: split_id.to_string(),
-            error: format!("{}", err),
-            retryable_error: true,
-        }));
+    merged_search_response.failed_splits.extend(errors);
     Ok(merged_search_response)
 }
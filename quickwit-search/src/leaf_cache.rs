@@ -0,0 +1,276 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_proto::prost::Message;
+use quickwit_proto::{LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets};
+use quickwit_storage::{MemorySizedCache, OwnedBytes};
+
+/// A cache of leaf search responses, with one entry per split and request.
+///
+/// Published splits are immutable, so entries never need to be invalidated: they are only
+/// evicted when the cache runs out of capacity.
+pub(crate) struct LeafSearchCache {
+    content: MemorySizedCache<CacheKey>,
+}
+
+impl LeafSearchCache {
+    pub fn new(capacity_in_bytes: usize) -> Self {
+        LeafSearchCache {
+            content: MemorySizedCache::with_capacity_in_bytes(
+                capacity_in_bytes,
+                &quickwit_storage::STORAGE_METRICS.leaf_search_cache,
+            ),
+        }
+    }
+
+    pub fn get(
+        &self,
+        split: &SplitIdAndFooterOffsets,
+        request: &SearchRequest,
+    ) -> Option<LeafSearchResponse> {
+        let key = CacheKey::from_split_and_request(split, request);
+        let encoded_response = self.content.get(&key)?;
+        LeafSearchResponse::decode(encoded_response.as_slice()).ok()
+    }
+
+    pub fn put(
+        &self,
+        split: &SplitIdAndFooterOffsets,
+        request: &SearchRequest,
+        response: &LeafSearchResponse,
+    ) {
+        let key = CacheKey::from_split_and_request(split, request);
+        let key_num_bytes = key.num_bytes();
+        let encoded_response = OwnedBytes::new(response.encode_to_vec());
+        self.content
+            .put_with_key_num_bytes(key, key_num_bytes, encoded_response);
+    }
+}
+
+/// Identifies the result of a search request on a split.
+///
+/// The request is normalized so that requests yielding the same result on the split share the
+/// same key. The index ID and the timeout of the request are left out: the split ID already
+/// identifies the index, and the timeout does not change the result of a completed search.
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct CacheKey {
+    split_id: String,
+    /// Parsed query, which does not depend on the formatting of the query string.
+    query_ast: String,
+    /// Sorted and deduplicated search fields.
+    search_fields: Vec<String>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    max_hits: u64,
+    start_offset: u64,
+    sort_order: Option<i32>,
+    sort_by_field: Option<String>,
+    aggregation_request: Option<String>,
+}
+
+impl CacheKey {
+    fn from_split_and_request(split: &SplitIdAndFooterOffsets, request: &SearchRequest) -> Self {
+        // An invalid query fails on every split and its response is never cached.
+        let query_ast = tantivy_query_grammar::parse_query(&request.query)
+            .map(|user_input_ast| format!("{user_input_ast:?}"))
+            .unwrap_or_else(|_| request.query.clone());
+        let mut search_fields = request.search_fields.clone();
+        search_fields.sort();
+        search_fields.dedup();
+        // Time bounds that do not cut through the time range of the split do not change the
+        // result. The split time range is inclusive while the request end timestamp is
+        // exclusive.
+        let start_timestamp = request.start_timestamp.filter(|start_timestamp| {
+            split.timestamp_start.map_or(true, |split_start_timestamp| {
+                *start_timestamp > split_start_timestamp
+            })
+        });
+        let end_timestamp = request.end_timestamp.filter(|end_timestamp| {
+            split.timestamp_end.map_or(true, |split_end_timestamp| {
+                *end_timestamp <= split_end_timestamp
+            })
+        });
+        CacheKey {
+            split_id: split.split_id.clone(),
+            query_ast,
+            search_fields,
+            start_timestamp,
+            end_timestamp,
+            max_hits: request.max_hits,
+            start_offset: request.start_offset,
+            sort_order: request.sort_order,
+            sort_by_field: request.sort_by_field.clone(),
+            aggregation_request: request.aggregation_request.clone(),
+        }
+    }
+
+    /// Number of bytes held by the key, accounted for in the cache capacity.
+    fn num_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.split_id.len()
+            + self.query_ast.len()
+            + self
+                .search_fields
+                .iter()
+                .map(|search_field| std::mem::size_of::<String>() + search_field.len())
+                .sum::<usize>()
+            + self.sort_by_field.as_ref().map_or(0, String::len)
+            + self.aggregation_request.as_ref().map_or(0, String::len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::PartialHit;
+
+    use super::*;
+
+    fn split(split_id: &str, timestamp_range: Option<(i64, i64)>) -> SplitIdAndFooterOffsets {
+        SplitIdAndFooterOffsets {
+            split_id: split_id.to_string(),
+            split_footer_start: 0,
+            split_footer_end: 100,
+            timestamp_start: timestamp_range.map(|(start, _)| start),
+            timestamp_end: timestamp_range.map(|(_, end)| end),
+        }
+    }
+
+    fn search_request(time_range: (Option<i64>, Option<i64>)) -> SearchRequest {
+        SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "body:test".to_string(),
+            search_fields: Vec::new(),
+            start_timestamp: time_range.0,
+            end_timestamp: time_range.1,
+            max_hits: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cache_key_normalizes_time_range() {
+        let split = split("split_1", Some((100, 200)));
+        let key_without_bounds =
+            CacheKey::from_split_and_request(&split, &search_request((None, None)));
+        let key_with_covering_bounds =
+            CacheKey::from_split_and_request(&split, &search_request((Some(100), Some(201))));
+        assert_eq!(key_with_covering_bounds, key_without_bounds);
+
+        let key_with_cutting_start =
+            CacheKey::from_split_and_request(&split, &search_request((Some(101), None)));
+        assert_ne!(key_with_cutting_start, key_without_bounds);
+
+        let key_with_cutting_end =
+            CacheKey::from_split_and_request(&split, &search_request((None, Some(200))));
+        assert_ne!(key_with_cutting_end, key_without_bounds);
+
+        let split_without_time_range = split("split_1", None);
+        let key_without_split_time_range = CacheKey::from_split_and_request(
+            &split_without_time_range,
+            &search_request((Some(100), Some(201))),
+        );
+        assert_eq!(key_without_split_time_range.start_timestamp, Some(100));
+        assert_eq!(key_without_split_time_range.end_timestamp, Some(201));
+    }
+
+    #[test]
+    fn test_cache_key_ignores_index_id() {
+        let split = split("split_1", None);
+        let request = search_request((None, None));
+        let other_index_request = SearchRequest {
+            index_id: "other-index".to_string(),
+            ..request.clone()
+        };
+        assert_eq!(
+            CacheKey::from_split_and_request(&split, &request),
+            CacheKey::from_split_and_request(&split, &other_index_request)
+        );
+    }
+
+    #[test]
+    fn test_cache_key_normalizes_query() {
+        let split = split("split_1", None);
+        let request = SearchRequest {
+            query: "body:test title:test".to_string(),
+            search_fields: vec!["body".to_string(), "title".to_string()],
+            timeout_millis: Some(1_000),
+            ..search_request((None, None))
+        };
+        let equivalent_request = SearchRequest {
+            query: "  body:test   title:test ".to_string(),
+            search_fields: vec!["title".to_string(), "body".to_string(), "title".to_string()],
+            timeout_millis: Some(2_000),
+            ..request.clone()
+        };
+        assert_eq!(
+            CacheKey::from_split_and_request(&split, &request),
+            CacheKey::from_split_and_request(&split, &equivalent_request)
+        );
+        let other_fields_request = SearchRequest {
+            search_fields: vec!["body".to_string()],
+            ..request.clone()
+        };
+        assert_ne!(
+            CacheKey::from_split_and_request(&split, &request),
+            CacheKey::from_split_and_request(&split, &other_fields_request)
+        );
+    }
+
+    #[test]
+    fn test_leaf_search_cache() {
+        let cache = LeafSearchCache::new(1_000_000);
+        let split_1 = split("split_1", Some((100, 200)));
+        let split_2 = split("split_2", Some((100, 200)));
+        let request = search_request((None, None));
+        let response = LeafSearchResponse {
+            num_hits: 3,
+            partial_hits: vec![PartialHit {
+                sorting_field_value: 0,
+                split_id: "split_1".to_string(),
+                segment_ord: 0,
+                doc_id: 1,
+            }],
+            failed_splits: Vec::new(),
+            num_attempted_splits: 1,
+            intermediate_aggregation_result: None,
+        };
+        assert!(cache.get(&split_1, &request).is_none());
+        cache.put(&split_1, &request, &response);
+        assert_eq!(cache.get(&split_1, &request), Some(response.clone()));
+        assert!(cache.get(&split_2, &request).is_none());
+
+        let other_query_request = SearchRequest {
+            query: "body:other".to_string(),
+            ..request.clone()
+        };
+        assert!(cache.get(&split_1, &other_query_request).is_none());
+
+        let covering_request = search_request((Some(0), Some(1_000)));
+        assert_eq!(cache.get(&split_1, &covering_request), Some(response));
+    }
+
+    #[test]
+    fn test_leaf_search_cache_with_zero_capacity() {
+        let cache = LeafSearchCache::new(0);
+        let split = split("split_1", None);
+        let request = search_request((None, None));
+        cache.put(&split, &request, &LeafSearchResponse::default());
+        assert!(cache.get(&split, &request).is_none());
+    }
+}
@@ -21,6 +21,7 @@
 #![warn(missing_docs)]
 #![allow(clippy::bool_assert_comparison)]
 
+mod aggregation;
 mod client;
 mod cluster_client;
 mod collector;
@@ -28,6 +29,7 @@ mod error;
 mod fetch_docs;
 mod filters;
 mod leaf;
+mod leaf_cache;
 mod rendezvous_hasher;
 mod retry;
 mod root;
@@ -61,11 +63,9 @@ use quickwit_metastore::{Metastore, SplitMetadata, SplitState};
 use quickwit_proto::{PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets};
 use quickwit_storage::StorageUriResolver;
 use serde_json::Value as JsonValue;
-use tantivy::aggregation::agg_req::Aggregations;
-use tantivy::aggregation::agg_result::AggregationResults;
-use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::DocAddress;
 
+use crate::aggregation::{QuickwitAggregations, QuickwitIntermediateAggregationResults};
 pub use crate::client::SearchServiceClient;
 pub use crate::cluster_client::ClusterClient;
 pub use crate::error::{parse_grpc_error, SearchError};
@@ -130,6 +130,14 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
         split_id: split_metadata.split_id.clone(),
         split_footer_start: split_metadata.footer_offsets.start as u64,
         split_footer_end: split_metadata.footer_offsets.end as u64,
+        timestamp_start: split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.start()),
+        timestamp_end: split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.end()),
     }
 }
 
@@ -210,6 +218,7 @@ pub async fn single_node_search(
         index_storage.clone(),
         &split_metadata[..],
         doc_mapper.clone(),
+        None,
     )
     .await
     .context("Failed to perform leaf search.")?;
@@ -229,10 +238,10 @@ pub async fn single_node_search(
     let aggregation = if let Some(intermediate_aggregation_result) =
         leaf_search_response.intermediate_aggregation_result
     {
-        let res: IntermediateAggregationResults =
+        let res: QuickwitIntermediateAggregationResults =
             serde_json::from_str(&intermediate_aggregation_result)?;
-        let req: Aggregations = serde_json::from_str(search_request.aggregation_request())?;
-        let res: AggregationResults = res.into_final_bucket_result(req)?;
+        let req = QuickwitAggregations::parse(search_request.aggregation_request())?;
+        let res = res.into_final_result(req)?;
         Some(serde_json::to_string(&res)?)
     } else {
         None
@@ -247,6 +256,8 @@ pub async fn single_node_search(
             .iter()
             .map(|error| format!("{:?}", error))
             .collect_vec(),
+        is_partial: !leaf_search_response.failed_splits.is_empty(),
+        failed_splits: leaf_search_response.failed_splits,
     })
 }
 
@@ -110,6 +110,7 @@ mod tests {
             split_id: "split_1".to_string(),
             split_footer_end: 100,
             split_footer_start: 0,
+            ..Default::default()
         };
         let client_for_retry = retry_client(
             &client_pool,
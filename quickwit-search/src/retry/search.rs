@@ -23,8 +23,8 @@ use super::RetryPolicy;
 use crate::SearchError;
 
 /// Retry policy for LeafSearchRequest.
-/// A retry is made either on an error or if there are some failing splits.
-/// In the last case, a retry request is built on failing splits only.
+/// A retry is made either on an error or if there are some retryable failing splits.
+/// In the last case, a retry request is built on these failing splits only.
 pub struct LeafSearchRetryPolicy {}
 
 impl RetryPolicy<LeafSearchRequest, LeafSearchResponse, SearchError> for LeafSearchRetryPolicy {
@@ -36,15 +36,15 @@ impl RetryPolicy<LeafSearchRequest, LeafSearchResponse, SearchError> for LeafSea
     ) -> Option<LeafSearchRequest> {
         match result {
             Ok(response) => {
-                if response.failed_splits.is_empty() {
-                    return None;
-                }
                 request.split_offsets.retain(|split_metadata| {
-                    response
-                        .failed_splits
-                        .iter()
-                        .any(|failed_split| failed_split.split_id == split_metadata.split_id)
+                    response.failed_splits.iter().any(|failed_split| {
+                        failed_split.retryable_error
+                            && failed_split.split_id == split_metadata.split_id
+                    })
                 });
+                if request.split_offsets.is_empty() {
+                    return None;
+                }
                 Some(request)
             }
             Err(_) => Some(request),
@@ -77,16 +77,19 @@ mod tests {
             }),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            timeout_millis: None,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
                     split_footer_end: 100,
                     split_footer_start: 0,
+                    ..Default::default()
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_end: 100,
                     split_footer_start: 0,
+                    ..Default::default()
                 },
             ],
         }
@@ -144,4 +147,26 @@ mod tests {
         assert_eq!(retry_request_opt, Some(expected_retry_request));
         Ok(())
     }
+
+    #[test]
+    fn test_should_not_retry_on_non_retryable_failed_splits() -> anyhow::Result<()> {
+        let retry_policy = LeafSearchRetryPolicy {};
+        let request = mock_leaf_search_request();
+        let split_error = SplitSearchError {
+            error: "Split search timed out.".to_string(),
+            split_id: "split_2".to_string(),
+            retryable_error: false,
+        };
+        let leaf_response = LeafSearchResponse {
+            num_hits: 0,
+            partial_hits: vec![],
+            failed_splits: vec![split_error],
+            num_attempted_splits: 1,
+            ..Default::default()
+        };
+        let result = Result::<LeafSearchResponse, SearchError>::Ok(leaf_response);
+        let retry_request_opt = retry_policy.retry_request(request, result.as_ref());
+        assert!(retry_request_opt.is_none());
+        Ok(())
+    }
 }
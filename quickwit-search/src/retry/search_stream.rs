@@ -112,11 +112,13 @@ mod tests {
                 split_id: "split_1".to_string(),
                 split_footer_end: 100,
                 split_footer_start: 0,
+                ..Default::default()
             },
             SplitIdAndFooterOffsets {
                 split_id: "split_2".to_string(),
                 split_footer_end: 100,
                 split_footer_start: 0,
+                ..Default::default()
             },
         ];
 
@@ -19,23 +19,24 @@
 
 use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use futures::future::try_join_all;
+use futures::stream::FuturesUnordered;
+use futures::{Future, StreamExt};
 use itertools::Itertools;
-use quickwit_config::build_doc_mapper;
+use quickwit_config::{build_doc_mapper, get_searcher_config_instance};
 use quickwit_metastore::{Metastore, SplitMetadata};
 use quickwit_proto::{
     FetchDocsRequest, FetchDocsResponse, LeafSearchRequest, LeafSearchResponse, PartialHit,
-    SearchRequest, SearchResponse, SplitIdAndFooterOffsets,
+    SearchRequest, SearchResponse, SplitIdAndFooterOffsets, SplitSearchError,
 };
-use tantivy::aggregation::agg_req::Aggregations;
-use tantivy::aggregation::agg_result::AggregationResults;
-use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::collector::Collector;
 use tantivy::TantivyError;
 use tokio::task::spawn_blocking;
-use tracing::{debug, error, instrument};
+use tokio::time::Instant;
+use tracing::{debug, error, instrument, warn};
 
+use crate::aggregation::{QuickwitAggregations, QuickwitIntermediateAggregationResults};
 use crate::cluster_client::ClusterClient;
 use crate::collector::make_merge_collector;
 use crate::search_client_pool::Job;
@@ -111,7 +112,7 @@ impl From<FetchDocsJob> for SplitIdAndFooterOffsets {
 
 fn validate_request(search_request: &SearchRequest) -> crate::Result<()> {
     if let Some(agg) = search_request.aggregation_request.as_ref() {
-        let _agg: Aggregations = serde_json::from_str(agg)
+        QuickwitAggregations::parse(agg)
             .map_err(|err| SearchError::InvalidAggregationRequest(err.to_string()))?;
     };
 
@@ -129,14 +130,112 @@ fn validate_request(search_request: &SearchRequest) -> crate::Result<()> {
         )));
     }
 
+    if search_request.timeout_millis == Some(0) {
+        return Err(SearchError::InvalidArgument(
+            "timeout_millis must be greater than 0".to_string(),
+        ));
+    }
+
     Ok(())
 }
 
+/// Returns the timeout of the search request, falling back to the searcher default.
+fn search_timeout(search_request: &SearchRequest) -> Option<Duration> {
+    search_request
+        .timeout_millis
+        .or(get_searcher_config_instance().search_timeout_millis)
+        .map(Duration::from_millis)
+}
+
+/// Share of the time left to a search request that is given to the leaves, the rest being kept
+/// for the round trip and the merge of their responses.
+const LEAF_SEARCH_TIMEOUT_RATIO: f64 = 0.8;
+
+/// Returns the time budget given to the leaves to search their splits, so that they return the
+/// results of the splits they searched before the root gives up on them.
+fn leaf_search_timeout(deadline: Instant) -> Duration {
+    deadline
+        .saturating_duration_since(Instant::now())
+        .mul_f64(LEAF_SEARCH_TIMEOUT_RATIO)
+}
+
+/// Polls the futures until they all complete or the deadline, if any, is reached. Each future
+/// comes with the IDs of the splits it handles.
+///
+/// Returns the outputs of the futures that completed successfully, in the order of the futures,
+/// the errors of the splits whose futures failed, and the IDs of the splits whose futures did not
+/// complete in time. Those futures are dropped, which resets the underlying gRPC streams: the
+/// leaves drop their requests, cancelling the pending split downloads and warmups and skipping
+/// the splits still queued for the search thread pool. The splits already being searched on a
+/// thread of the pool are not interrupted, and their results are discarded.
+async fn join_until_deadline<T, F>(
+    futures: Vec<(Vec<String>, F)>,
+    deadline: Option<Instant>,
+) -> (Vec<T>, Vec<SplitSearchError>, Vec<String>)
+where
+    F: Future<Output = crate::Result<T>>,
+{
+    let mut pending_split_ids: HashMap<usize, Vec<String>> = HashMap::new();
+    let mut pending_futures = FuturesUnordered::new();
+    for (future_ord, (split_ids, future)) in futures.into_iter().enumerate() {
+        pending_split_ids.insert(future_ord, split_ids);
+        pending_futures.push(async move { (future_ord, future.await) });
+    }
+    let mut outputs: Vec<(usize, T)> = Vec::new();
+    let mut failed_splits: Vec<SplitSearchError> = Vec::new();
+    loop {
+        let next_output_opt = if let Some(deadline) = deadline {
+            match tokio::time::timeout_at(deadline, pending_futures.next()).await {
+                Ok(next_output_opt) => next_output_opt,
+                Err(_) => break,
+            }
+        } else {
+            pending_futures.next().await
+        };
+        let (future_ord, output) = match next_output_opt {
+            Some(next_output) => next_output,
+            None => break,
+        };
+        let split_ids = pending_split_ids.remove(&future_ord).unwrap_or_default();
+        match output {
+            Ok(output) => outputs.push((future_ord, output)),
+            Err(error) => {
+                failed_splits.extend(split_ids.into_iter().map(|split_id| SplitSearchError {
+                    error: error.to_string(),
+                    split_id,
+                    retryable_error: true,
+                }));
+            }
+        }
+    }
+    outputs.sort_by_key(|(future_ord, _)| *future_ord);
+    let outputs = outputs.into_iter().map(|(_, output)| output).collect();
+    failed_splits.sort_by(|left, right| left.split_id.cmp(&right.split_id));
+    let timed_out_split_ids = pending_split_ids.into_values().flatten().sorted().collect();
+    (outputs, failed_splits, timed_out_split_ids)
+}
+
+/// Reports the splits that timed out. As with the splits that time out on the leaves, retrying
+/// them would not help: the time budget of the request is spent.
+fn timed_out_split_errors(split_ids: Vec<String>, error: &str) -> Vec<SplitSearchError> {
+    split_ids
+        .into_iter()
+        .map(|split_id| SplitSearchError {
+            error: error.to_string(),
+            split_id,
+            retryable_error: false,
+        })
+        .collect()
+}
+
 /// Performs a distributed search.
 /// 1. Sends leaf request over gRPC to multiple leaf nodes.
 /// 2. Merges the search results.
 /// 3. Sends fetch docs requests to multiple leaf nodes.
 /// 4. Builds the response with docs and returns.
+///
+/// If the request times out, the splits that have not been searched or whose documents have not
+/// been fetched yet are given up, and the response is flagged as partial.
 #[instrument(skip(search_request, cluster_client, client_pool, metastore))]
 pub async fn root_search(
     search_request: &SearchRequest,
@@ -144,7 +243,8 @@ pub async fn root_search(
     cluster_client: &ClusterClient,
     client_pool: &SearchClientPool,
 ) -> crate::Result<SearchResponse> {
-    let start_instant = tokio::time::Instant::now();
+    let start_instant = Instant::now();
+    let deadline = search_timeout(search_request).map(|timeout| start_instant + timeout);
 
     let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
 
@@ -182,20 +282,34 @@ pub async fn root_search(
     let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
     let assigned_leaf_search_jobs = client_pool.assign_jobs(jobs, &HashSet::default())?;
     debug!(assigned_leaf_search_jobs=?assigned_leaf_search_jobs, "Assigned leaf search jobs.");
-    let leaf_search_responses: Vec<LeafSearchResponse> = try_join_all(
-        assigned_leaf_search_jobs
-            .into_iter()
-            .map(|(client, client_jobs)| {
-                let leaf_request = jobs_to_leaf_request(
-                    search_request,
-                    &doc_mapper_str,
-                    &index_metadata.index_uri,
-                    client_jobs,
-                );
-                cluster_client.leaf_search(leaf_request, client)
-            }),
-    )
-    .await?;
+    let leaf_search_timeout_opt = deadline.map(leaf_search_timeout);
+    let leaf_search_futures: Vec<_> = assigned_leaf_search_jobs
+        .into_iter()
+        .map(|(client, client_jobs)| {
+            let leaf_request = jobs_to_leaf_request(
+                search_request,
+                &doc_mapper_str,
+                &index_metadata.index_uri,
+                client_jobs,
+                leaf_search_timeout_opt,
+            );
+            let split_ids: Vec<String> = leaf_request
+                .split_offsets
+                .iter()
+                .map(|split_offsets| split_offsets.split_id.clone())
+                .collect();
+            (split_ids, cluster_client.leaf_search(leaf_request, client))
+        })
+        .collect();
+    let (leaf_search_responses, mut errored_splits, timed_out_split_ids): (
+        Vec<LeafSearchResponse>,
+        Vec<SplitSearchError>,
+        Vec<String>,
+    ) = join_until_deadline(leaf_search_futures, deadline).await;
+    if !timed_out_split_ids.is_empty() {
+        warn!(timed_out_split_ids=?timed_out_split_ids, "Leaf search timed out on some splits.");
+    }
+    let mut failed_splits = timed_out_split_errors(timed_out_split_ids, "Split search timed out.");
 
     // Creates a collector which merges responses into one
     let merge_collector = make_merge_collector(search_request)?;
@@ -206,23 +320,37 @@ pub async fn root_search(
     // Wrap into result for merge_fruits
     let leaf_search_responses: Vec<tantivy::Result<LeafSearchResponse>> =
         leaf_search_responses.into_iter().map(Ok).collect_vec();
-    let leaf_search_response =
+    let mut leaf_search_response =
         spawn_blocking(move || merge_collector.merge_fruits(leaf_search_responses))
             .await?
             .map_err(|merge_error: TantivyError| {
                 crate::SearchError::InternalError(format!("{}", merge_error))
             })?;
     debug!(leaf_search_response = ?leaf_search_response, "Merged leaf search response.");
+    leaf_search_response
+        .failed_splits
+        .append(&mut errored_splits);
 
     if !leaf_search_response.failed_splits.is_empty() {
         error!(failed_splits = ?leaf_search_response.failed_splits, "Leaf search response contains at least one failed split.");
-        let errors: String = leaf_search_response
+        // The response is partial if some splits were searched, and useless if they all failed
+        // with an error. The splits that timed out, reported as not retryable, are given up as
+        // when the root times out.
+        let num_errored_splits = leaf_search_response
             .failed_splits
             .iter()
-            .map(|splits| format!("{}", splits))
-            .collect::<Vec<_>>()
-            .join(", ");
-        return Err(SearchError::InternalError(errors));
+            .filter(|split_error| split_error.retryable_error)
+            .count();
+        if num_errored_splits >= split_metadatas.len() {
+            let errors: String = leaf_search_response
+                .failed_splits
+                .iter()
+                .map(|splits| format!("{}", splits))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(SearchError::InternalError(errors));
+        }
+        failed_splits.append(&mut leaf_search_response.failed_splits);
     }
 
     let client_fetch_docs_task: Vec<(SearchServiceClient, Vec<FetchDocsJob>)> =
@@ -232,28 +360,50 @@ pub async fn root_search(
             client_pool,
         )?;
 
-    let fetch_docs_resp_futures =
-        client_fetch_docs_task
-            .into_iter()
-            .map(|(client, fetch_docs_jobs)| {
-                let partial_hits: Vec<PartialHit> = fetch_docs_jobs
-                    .iter()
-                    .flat_map(|fetch_doc_job| fetch_doc_job.partial_hits.iter().cloned())
-                    .collect();
-                let split_offsets: Vec<SplitIdAndFooterOffsets> = fetch_docs_jobs
-                    .into_iter()
-                    .map(|fetch_doc_job| fetch_doc_job.into())
-                    .collect();
-                let fetch_docs_req = FetchDocsRequest {
-                    partial_hits,
-                    index_id: search_request.index_id.to_string(),
-                    split_offsets,
-                    index_uri: index_metadata.index_uri.to_string(),
-                };
-                cluster_client.fetch_docs(fetch_docs_req, client)
-            });
+    let fetch_docs_resp_futures: Vec<_> = client_fetch_docs_task
+        .into_iter()
+        .map(|(client, fetch_docs_jobs)| {
+            let partial_hits: Vec<PartialHit> = fetch_docs_jobs
+                .iter()
+                .flat_map(|fetch_doc_job| fetch_doc_job.partial_hits.iter().cloned())
+                .collect();
+            let split_offsets: Vec<SplitIdAndFooterOffsets> = fetch_docs_jobs
+                .into_iter()
+                .map(|fetch_doc_job| fetch_doc_job.into())
+                .collect();
+            let split_ids: Vec<String> = split_offsets
+                .iter()
+                .map(|split_offsets| split_offsets.split_id.clone())
+                .collect();
+            let fetch_docs_req = FetchDocsRequest {
+                partial_hits,
+                index_id: search_request.index_id.to_string(),
+                split_offsets,
+                index_uri: index_metadata.index_uri.to_string(),
+            };
+            (split_ids, cluster_client.fetch_docs(fetch_docs_req, client))
+        })
+        .collect();
 
-    let fetch_docs_resps: Vec<FetchDocsResponse> = try_join_all(fetch_docs_resp_futures).await?;
+    let (fetch_docs_resps, mut errored_splits, timed_out_split_ids): (
+        Vec<FetchDocsResponse>,
+        Vec<SplitSearchError>,
+        Vec<String>,
+    ) = join_until_deadline(fetch_docs_resp_futures, deadline).await;
+    if !errored_splits.is_empty() {
+        error!(failed_splits = ?errored_splits, "Fetching documents failed on some splits.");
+    }
+    if !timed_out_split_ids.is_empty() {
+        warn!(
+            timed_out_split_ids=?timed_out_split_ids,
+            "Fetching documents timed out on some splits."
+        );
+    }
+    failed_splits.append(&mut errored_splits);
+    failed_splits.extend(timed_out_split_errors(
+        timed_out_split_ids,
+        "Fetching documents timed out.",
+    ));
 
     // Merge the fetched docs.
     let leaf_hits = fetch_docs_resps
@@ -278,10 +428,10 @@ pub async fn root_search(
     let aggregation = if let Some(intermediate_aggregation_result) =
         leaf_search_response.intermediate_aggregation_result
     {
-        let res: IntermediateAggregationResults =
+        let res: QuickwitIntermediateAggregationResults =
             serde_json::from_str(&intermediate_aggregation_result)?;
-        let req: Aggregations = serde_json::from_str(search_request.aggregation_request())?;
-        let res: AggregationResults = res.into_final_bucket_result(req)?;
+        let req = QuickwitAggregations::parse(search_request.aggregation_request())?;
+        let res = res.into_final_result(req)?;
         Some(serde_json::to_string(&res)?)
     } else {
         None
@@ -293,6 +443,8 @@ pub async fn root_search(
         hits,
         elapsed_time_micros: elapsed.as_micros() as u64,
         errors: vec![],
+        is_partial: !failed_splits.is_empty(),
+        failed_splits,
     })
 }
 
@@ -343,6 +495,7 @@ fn jobs_to_leaf_request(
     doc_mapper_str: &str,
     index_uri: &str,
     jobs: Vec<SearchJob>,
+    timeout_opt: Option<Duration>,
 ) -> LeafSearchRequest {
     let mut request_with_offset_0 = request.clone();
     request_with_offset_0.start_offset = 0;
@@ -352,6 +505,7 @@ fn jobs_to_leaf_request(
         split_offsets: jobs.into_iter().map(|job| job.offsets).collect(),
         doc_mapper: doc_mapper_str.to_string(),
         index_uri: index_uri.to_string(),
+        timeout_millis: timeout_opt.map(|timeout| timeout.as_millis() as u64),
     }
 }
 
@@ -360,9 +514,9 @@ mod tests {
     use std::ops::Range;
     use std::sync::Arc;
 
+    use futures::future::BoxFuture;
     use quickwit_indexing::mock_split;
     use quickwit_metastore::{IndexMetadata, MockMetastore, SplitState};
-    use quickwit_proto::SplitSearchError;
 
     use super::*;
     use crate::MockSearchService;
@@ -982,6 +1136,77 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_partial_response_on_failed_split() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::SearchRequest {
+            index_id: "test-idx".to_string(),
+            query: "test".to_string(),
+            search_fields: vec!["body".to_string()],
+            max_hits: 10,
+            timeout_millis: Some(10_000),
+            ..Default::default()
+        };
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "test-idx",
+                    "file:///path/to/index/test-idx",
+                ))
+            });
+        metastore.expect_list_splits().returning(
+            |_index_id: &str, _split_state: SplitState, _time_range: Option<Range<i64>>, _tags| {
+                Ok(vec![mock_split("split1"), mock_split("split2")])
+            },
+        );
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::LeafSearchRequest| {
+                // The leaf gets the time left to the request, minus a margin for the root.
+                let timeout_millis = leaf_search_req.timeout_millis.unwrap();
+                assert!(timeout_millis > 0 && timeout_millis <= 8_000);
+                let mut leaf_search_response = quickwit_proto::LeafSearchResponse {
+                    num_attempted_splits: leaf_search_req.split_offsets.len() as u64,
+                    ..Default::default()
+                };
+                for split_offsets in &leaf_search_req.split_offsets {
+                    if split_offsets.split_id == "split1" {
+                        leaf_search_response.num_hits += 2;
+                        leaf_search_response.partial_hits.extend([
+                            mock_partial_hit("split1", 2, 1),
+                            mock_partial_hit("split1", 1, 2),
+                        ]);
+                    } else {
+                        leaf_search_response.failed_splits.push(SplitSearchError {
+                            error: "mock_error".to_string(),
+                            split_id: split_offsets.split_id.clone(),
+                            retryable_error: true,
+                        });
+                    }
+                }
+                Ok(leaf_search_response)
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
+                Ok(quickwit_proto::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let client_pool = SearchClientPool::from_mocks(vec![Arc::new(mock_search_service)]).await?;
+        let cluster_client = ClusterClient::new(client_pool.clone());
+        let search_response =
+            root_search(&search_request, &metastore, &cluster_client, &client_pool).await?;
+        assert_eq!(search_response.num_hits, 2);
+        assert_eq!(search_response.hits.len(), 2);
+        assert!(search_response.is_partial);
+        assert_eq!(search_response.failed_splits.len(), 1);
+        assert_eq!(search_response.failed_splits[0].split_id, "split2");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_root_search_one_splits_two_nodes_but_one_is_failing_for_split(
     ) -> anyhow::Result<()> {
@@ -1313,6 +1538,74 @@ mod tests {
             "Invalid argument: max value for max_hits is 10_000, but got 20000",
         );
 
+        let search_request = quickwit_proto::SearchRequest {
+            index_id: "test-idx".to_string(),
+            query: "test".to_string(),
+            max_hits: 10,
+            timeout_millis: Some(0),
+            ..Default::default()
+        };
+
+        let search_response =
+            root_search(&search_request, &metastore, &cluster_client, &client_pool).await;
+        assert_eq!(
+            search_response.unwrap_err().to_string(),
+            "Invalid argument: timeout_millis must be greater than 0",
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_join_until_deadline() -> anyhow::Result<()> {
+        {
+            let futures: Vec<(Vec<String>, BoxFuture<'static, crate::Result<usize>>)> = vec![
+                (vec!["split1".to_string()], Box::pin(async { Ok(1) })),
+                (vec!["split2".to_string()], Box::pin(async { Ok(2) })),
+            ];
+            let (outputs, failed_splits, timed_out_split_ids) =
+                join_until_deadline(futures, None).await;
+            assert_eq!(outputs, vec![1, 2]);
+            assert!(failed_splits.is_empty());
+            assert!(timed_out_split_ids.is_empty());
+        }
+        {
+            let futures: Vec<(Vec<String>, BoxFuture<'static, crate::Result<usize>>)> = vec![
+                (
+                    vec!["split3".to_string(), "split2".to_string()],
+                    Box::pin(futures::future::pending()),
+                ),
+                (vec!["split1".to_string()], Box::pin(async { Ok(1) })),
+                (vec!["split4".to_string()], Box::pin(async { Ok(4) })),
+            ];
+            let deadline = Instant::now() + Duration::from_millis(50);
+            let (outputs, failed_splits, timed_out_split_ids) =
+                join_until_deadline(futures, Some(deadline)).await;
+            assert_eq!(outputs, vec![1, 4]);
+            assert!(failed_splits.is_empty());
+            assert_eq!(timed_out_split_ids, vec!["split2", "split3"]);
+        }
+        {
+            let futures: Vec<(Vec<String>, BoxFuture<'static, crate::Result<usize>>)> = vec![
+                (
+                    vec!["split1".to_string()],
+                    Box::pin(futures::future::pending()),
+                ),
+                (
+                    vec!["split2".to_string()],
+                    Box::pin(async { Err(SearchError::InternalError("error".to_string())) }),
+                ),
+                (vec!["split3".to_string()], Box::pin(async { Ok(3) })),
+            ];
+            let deadline = Instant::now() + Duration::from_millis(50);
+            let (outputs, failed_splits, timed_out_split_ids) =
+                join_until_deadline(futures, Some(deadline)).await;
+            assert_eq!(outputs, vec![3]);
+            assert_eq!(failed_splits.len(), 1);
+            assert_eq!(failed_splits[0].split_id, "split2");
+            assert!(failed_splits[0].retryable_error);
+            assert_eq!(timed_out_split_ids, vec!["split1"]);
+        }
         Ok(())
     }
 }
@@ -27,6 +27,7 @@ use std::sync::{Arc, RwLock};
 use anyhow::bail;
 use http::Uri;
 use quickwit_cluster::{Cluster, QuickwitService};
+use quickwit_config::get_searcher_config_instance;
 use quickwit_proto::tonic;
 use tokio_stream::StreamExt;
 use tonic::transport::Endpoint;
@@ -70,12 +71,24 @@ pub trait Job {
 }
 
 /// Search client pool implementation.
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct SearchClientPool {
     /// Search clients.
     /// A hash map with gRPC's SocketAddr as the key and SearchServiceClient as the value.
     /// It is not the cluster listen address.
     clients: Arc<RwLock<HashMap<SocketAddr, SearchServiceClient>>>,
+    /// Number of nodes, ranked by rendez-vous hashing, among which the jobs of a split are
+    /// spread.
+    replication_factor: usize,
+}
+
+impl Default for SearchClientPool {
+    fn default() -> Self {
+        Self {
+            clients: Default::default(),
+            replication_factor: get_searcher_config_instance().split_replication_factor,
+        }
+    }
 }
 
 /// Update the client pool given a new list of members.
@@ -125,6 +138,7 @@ impl SearchClientPool {
         }
         Ok(SearchClientPool {
             clients: Arc::new(RwLock::from(clients_map)),
+            ..Default::default()
         })
     }
 
@@ -156,9 +170,16 @@ impl SearchClientPool {
 
         Ok(SearchClientPool {
             clients: Arc::new(RwLock::new(mock_clients)),
+            ..Default::default()
         })
     }
 
+    #[cfg(test)]
+    fn with_replication_factor(mut self, replication_factor: usize) -> Self {
+        self.replication_factor = replication_factor;
+        self
+    }
+
     /// Create a search client pool given a cluster.
     /// When a client pool is created, the thread that monitors cluster members
     /// will be started at the same time.
@@ -204,6 +225,28 @@ struct Node {
     pub load: u64,
 }
 
+/// Among the first `replication_factor` nodes, which are expected to be sorted by rendez-vous
+/// hashing, returns the index of the least loaded node that is not excluded. Ties are broken by
+/// affinity, which keeps the split caches of an idle cluster warm.
+///
+/// When all the replicas are excluded, falls back to the node with the highest affinity that is not
+/// excluded.
+fn select_replica(
+    nodes: &[Node],
+    replication_factor: usize,
+    exclude_addresses: &HashSet<SocketAddr>,
+) -> Option<usize> {
+    let is_available = |node: &Node| !exclude_addresses.contains(&node.peer_grpc_addr);
+    let num_replicas = replication_factor.max(1).min(nodes.len());
+    nodes[..num_replicas]
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| is_available(node))
+        .min_by_key(|(_, node)| node.load)
+        .map(|(node_index, _)| node_index)
+        .or_else(|| nodes.iter().position(is_available))
+}
+
 impl Hash for Node {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.peer_grpc_addr.hash(state);
@@ -226,28 +269,29 @@ impl SearchClientPool {
         let mut nodes: Vec<Node> = Vec::new();
         let mut socket_to_client: HashMap<SocketAddr, SearchServiceClient> = Default::default();
 
-        {
-            // TODO optimize the case where there are few jobs and many clients.
-            let clients = self.clients();
-
-            // when exclude_addresses excludes all adresses we discard it
-            let empty_set = HashSet::default();
-            let exclude_addresses_if_not_saturated = if exclude_addresses.len() == clients.len() {
-                &empty_set
-            } else {
-                exclude_addresses
-            };
+        // TODO optimize the case where there are few jobs and many clients.
+        let clients = self.clients();
 
-            for (grpc_addr, client) in clients
-                .into_iter()
-                .filter(|(grpc_addr, _)| !exclude_addresses_if_not_saturated.contains(grpc_addr))
-            {
-                nodes.push(Node {
-                    peer_grpc_addr: grpc_addr,
-                    load: 0,
-                });
-                socket_to_client.insert(grpc_addr, client);
-            }
+        // when exclude_addresses excludes all adresses we discard it
+        let empty_set = HashSet::default();
+        let exclude_addresses = if clients
+            .keys()
+            .all(|grpc_addr| exclude_addresses.contains(grpc_addr))
+        {
+            &empty_set
+        } else {
+            exclude_addresses
+        };
+
+        // Excluded nodes are kept so that the replicas of a split do not change when one of them
+        // is excluded. The load of a node starts from the cost of the jobs that other requests
+        // assigned to it and that are still in flight.
+        for (grpc_addr, client) in clients {
+            nodes.push(Node {
+                peer_grpc_addr: grpc_addr,
+                load: client.node_load(),
+            });
+            socket_to_client.insert(grpc_addr, client);
         }
 
         if nodes.is_empty() {
@@ -262,16 +306,10 @@ impl SearchClientPool {
 
         for job in jobs {
             sort_by_rendez_vous_hash(&mut nodes, job.split_id());
-            // choose one of the the first two nodes based on least loaded
-            let chosen_node_index: usize = if nodes.len() >= 2 {
-                if nodes[0].load > nodes[1].load {
-                    1
-                } else {
-                    0
-                }
-            } else {
-                0
-            };
+            // choose the least loaded of the nodes replicating the split
+            let chosen_node_index =
+                select_replica(&nodes, self.replication_factor, exclude_addresses)
+                    .expect("At least one node should not be excluded.");
 
             // update node load for next round
             nodes[chosen_node_index].load += job.cost() as u64;
@@ -287,7 +325,10 @@ impl SearchClientPool {
         for (socket_addr, jobs) in splits_groups {
             // Removing the client in order to ensure a 1:1 cardinality on grpc_addr and clients
             if let Some(client) = socket_to_client.remove(&socket_addr) {
-                client_to_jobs.push((client, jobs));
+                // The cost of the jobs is held on the node until the client is dropped, i.e. until
+                // the jobs are done.
+                let cost: u64 = jobs.iter().map(|job| job.cost() as u64).sum();
+                client_to_jobs.push((client.with_in_flight_load(cost), jobs));
             } else {
                 error!("Client is missing. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
             }
@@ -327,9 +368,9 @@ mod tests {
     use itertools::Itertools;
     use quickwit_cluster::{create_cluster_for_test, grpc_addr_from_listen_addr_for_test, Cluster};
 
-    use super::create_search_service_client;
+    use super::{create_search_service_client, select_replica, Node};
     use crate::root::SearchJob;
-    use crate::SearchClientPool;
+    use crate::{MockSearchService, SearchClientPool, SearchService, SearchServiceClient};
 
     async fn create_cluster_simple_for_test(
         transport: &dyn Transport,
@@ -407,4 +448,92 @@ mod tests {
         );
         Ok(())
     }
+
+    fn test_node(last_byte: u8, load: u64) -> Node {
+        Node {
+            peer_grpc_addr: ([127, 0, 0, last_byte], 10_000u16).into(),
+            load,
+        }
+    }
+
+    #[test]
+    fn test_select_replica() {
+        let no_exclusion = HashSet::new();
+        let nodes = vec![test_node(1, 3), test_node(2, 1), test_node(3, 0)];
+        assert_eq!(select_replica(&nodes, 0, &no_exclusion), Some(0));
+        assert_eq!(select_replica(&nodes, 1, &no_exclusion), Some(0));
+        assert_eq!(select_replica(&nodes, 2, &no_exclusion), Some(1));
+        assert_eq!(select_replica(&nodes, 3, &no_exclusion), Some(2));
+        assert_eq!(select_replica(&nodes, 10, &no_exclusion), Some(2));
+
+        let exclude_first = HashSet::from([nodes[0].peer_grpc_addr]);
+        assert_eq!(select_replica(&nodes, 1, &exclude_first), Some(1));
+        assert_eq!(select_replica(&nodes, 2, &exclude_first), Some(1));
+
+        let exclude_all: HashSet<SocketAddr> =
+            nodes.iter().map(|node| node.peer_grpc_addr).collect();
+        assert_eq!(select_replica(&nodes, 3, &exclude_all), None);
+
+        let nodes = vec![test_node(1, 2), test_node(2, 1), test_node(3, 1)];
+        assert_eq!(select_replica(&nodes, 3, &no_exclusion), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_search_client_pool_assign_jobs_across_requests() -> anyhow::Result<()> {
+        let mock_services: Vec<Arc<dyn SearchService>> = (0..3)
+            .map(|_| Arc::new(MockSearchService::new()) as Arc<dyn SearchService>)
+            .collect();
+        let client_pool = SearchClientPool::from_mocks(mock_services)
+            .await?
+            .with_replication_factor(2);
+        let assign_hot_split_job = |exclude_addresses: &HashSet<SocketAddr>| {
+            let mut assigned_jobs = client_pool
+                .assign_jobs(vec![SearchJob::for_test("hot-split", 1)], exclude_addresses)
+                .unwrap();
+            assert_eq!(assigned_jobs.len(), 1);
+            assigned_jobs.pop().unwrap().0
+        };
+        // While the requests are in flight, their jobs are spread over the two replicas of the
+        // split.
+        let in_flight_clients: Vec<SearchServiceClient> = (0..6)
+            .map(|_| assign_hot_split_job(&HashSet::new()))
+            .collect();
+        let num_jobs_per_node: Vec<(SocketAddr, usize)> = in_flight_clients
+            .iter()
+            .map(|client| client.grpc_addr())
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect();
+        assert_eq!(num_jobs_per_node.len(), 2);
+        assert!(num_jobs_per_node.iter().all(|(_, num_jobs)| *num_jobs == 3));
+        let replicas: Vec<SocketAddr> = num_jobs_per_node
+            .into_iter()
+            .map(|(grpc_addr, _)| grpc_addr)
+            .collect();
+
+        // When a replica is excluded, the jobs land on the remaining replica rather than on a
+        // node that does not replicate the split.
+        let exclude_addresses = HashSet::from([replicas[0]]);
+        for _ in 0..3 {
+            assert_eq!(
+                assign_hot_split_job(&exclude_addresses).grpc_addr(),
+                replicas[1]
+            );
+        }
+
+        // Once the requests are done, the load of the nodes is released, and the jobs go back to
+        // the replica with the highest affinity.
+        let clients = client_pool.clients();
+        assert!(clients.values().any(|client| client.node_load() > 0));
+        drop(in_flight_clients);
+        assert!(clients.values().all(|client| client.node_load() == 0));
+        let top_replica = assign_hot_split_job(&HashSet::new()).grpc_addr();
+        assert_eq!(
+            assign_hot_split_job(&HashSet::new()).grpc_addr(),
+            top_replica
+        );
+        assert!(replicas.contains(&top_replica));
+        Ok(())
+    }
 }
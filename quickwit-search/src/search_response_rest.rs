@@ -19,6 +19,7 @@
 
 use std::convert::TryFrom;
 
+use quickwit_proto::SplitSearchError;
 use serde::Serialize;
 
 use crate::error::SearchError;
@@ -35,6 +36,10 @@ pub struct SearchResponseRest {
     pub elapsed_time_micros: u64,
     /// Search errors.
     pub errors: Vec<String>,
+    /// Whether some splits could not be searched, in which case the response is partial.
+    pub is_partial: bool,
+    /// Splits that could not be searched.
+    pub failed_splits: Vec<SplitSearchError>,
     /// Aggregations.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<serde_json::Value>,
@@ -61,6 +66,8 @@ impl TryFrom<quickwit_proto::SearchResponse> for SearchResponseRest {
             hits,
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
+            is_partial: search_response.is_partial,
+            failed_splits: search_response.failed_splits,
             aggregations: search_response
                 .aggregation
                 .map(|agg| serde_json::from_str(&agg))
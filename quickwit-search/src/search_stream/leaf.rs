@@ -480,6 +480,7 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                ..Default::default()
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
@@ -533,6 +534,7 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                ..Default::default()
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
@@ -628,6 +630,7 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                ..Default::default()
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
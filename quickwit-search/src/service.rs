@@ -19,6 +19,7 @@
 
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -138,9 +139,18 @@ impl SearchService for SearchServiceImpl {
             .resolve(&leaf_search_request.index_uri)?;
         let split_ids = leaf_search_request.split_offsets;
         let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
+        let timeout_opt = leaf_search_request
+            .timeout_millis
+            .map(Duration::from_millis);
 
-        let leaf_search_response =
-            leaf_search(&search_request, storage.clone(), &split_ids[..], doc_mapper).await?;
+        let leaf_search_response = leaf_search(
+            &search_request,
+            storage.clone(),
+            &split_ids[..],
+            doc_mapper,
+            timeout_opt,
+        )
+        .await?;
 
         Ok(leaf_search_response)
     }
@@ -512,6 +512,7 @@ async fn test_search_dynamic_util(test_sandbox: &TestSandbox, query: &str) -> Ve
             split_id: split_meta.split_id().to_string(),
             split_footer_start: split_meta.split_metadata.footer_offsets.start,
             split_footer_end: split_meta.split_metadata.footer_offsets.end,
+            ..Default::default()
         })
         .collect();
     let request = quickwit_proto::SearchRequest {
@@ -525,6 +526,7 @@ async fn test_search_dynamic_util(test_sandbox: &TestSandbox, query: &str) -> Ve
         test_sandbox.storage(),
         &splits_offsets,
         test_sandbox.doc_mapper(),
+        None,
     )
     .await
     .unwrap();
@@ -24,6 +24,7 @@ use quickwit_actors::AskError;
 use quickwit_core::IndexServiceError;
 use quickwit_indexing::IndexingServiceError;
 use quickwit_ingest_api::IngestApiError;
+use quickwit_metastore::MetastoreError;
 use quickwit_proto::tonic;
 use quickwit_search::SearchError;
 use warp::http;
@@ -39,6 +40,7 @@ pub enum ServiceErrorCode {
     MethodNotAllowed,
     UnsupportedMediaType,
     BadRequest,
+    Timeout,
 }
 
 impl ServiceErrorCode {
@@ -49,6 +51,7 @@ impl ServiceErrorCode {
             ServiceErrorCode::BadRequest => tonic::Code::InvalidArgument,
             ServiceErrorCode::MethodNotAllowed => tonic::Code::InvalidArgument,
             ServiceErrorCode::UnsupportedMediaType => tonic::Code::InvalidArgument,
+            ServiceErrorCode::Timeout => tonic::Code::DeadlineExceeded,
         }
     }
     pub(crate) fn to_http_status_code(self) -> http::StatusCode {
@@ -58,6 +61,7 @@ impl ServiceErrorCode {
             ServiceErrorCode::BadRequest => http::StatusCode::BAD_REQUEST,
             ServiceErrorCode::MethodNotAllowed => http::StatusCode::METHOD_NOT_ALLOWED,
             ServiceErrorCode::UnsupportedMediaType => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ServiceErrorCode::Timeout => http::StatusCode::GATEWAY_TIMEOUT,
         }
     }
 }
@@ -83,6 +87,8 @@ impl ServiceError for IndexingServiceError {
             Self::StorageError(_) => ServiceErrorCode::Internal,
             Self::MetastoreError(_) => ServiceErrorCode::Internal,
             Self::InvalidParams(_) => ServiceErrorCode::BadRequest,
+            Self::MissingDeadLetterQueue { .. } => ServiceErrorCode::NotFound,
+            Self::DeadLetterQueueError(_) => ServiceErrorCode::Internal,
         }
     }
 }
@@ -94,6 +100,7 @@ impl ServiceError for IngestApiError {
             IngestApiError::IndexDoesNotExist { .. } => ServiceErrorCode::NotFound,
             IngestApiError::IndexAlreadyExists { .. } => ServiceErrorCode::BadRequest,
             IngestApiError::IngestAPIServiceDown => ServiceErrorCode::Internal,
+            IngestApiError::CommitTimeout => ServiceErrorCode::Timeout,
         }
     }
 }
@@ -112,9 +119,14 @@ impl ServiceError for IndexServiceError {
     fn status_code(&self) -> ServiceErrorCode {
         match self {
             Self::StorageError(_) => ServiceErrorCode::Internal,
+            Self::MetastoreError(
+                MetastoreError::IndexDoesNotExist { .. }
+                | MetastoreError::SourceDoesNotExist { .. },
+            ) => ServiceErrorCode::NotFound,
             Self::MetastoreError(_) => ServiceErrorCode::Internal,
             Self::SplitDeletionError(_) => ServiceErrorCode::Internal,
             Self::InvalidIndexConfig(_) => ServiceErrorCode::BadRequest,
+            Self::InvalidCheckpointReset(_) => ServiceErrorCode::BadRequest,
         }
     }
 }
@@ -18,22 +18,42 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::convert::Infallible;
+use std::ops::Range;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use quickwit_actors::{AskError, Mailbox};
 use quickwit_core::IndexService;
+use quickwit_doc_mapper::tag_pruning::{self, TagFilterAst};
+use quickwit_indexing::actors::IndexingService;
+use quickwit_indexing::models::{
+    IndexingPipelineId, ObservePipeline, ShutdownPipeline, SpawnPipeline,
+};
+use quickwit_indexing::{CheckpointResetTarget, IndexingServiceError};
+use quickwit_metastore::checkpoint::PartitionId;
+use quickwit_metastore::{ListSplitsQuery, SplitState};
 use quickwit_search::SearchError;
+use serde::{de, Deserialize, Deserializer, Serialize};
 use tracing::info;
 use warp::{Filter, Rejection};
 
-use crate::format::Format;
+use crate::error::ServiceErrorCode;
+use crate::format::{Format, FormatError};
 use crate::with_arg;
 
 pub fn index_management_handlers(
     index_service: Arc<IndexService>,
+    indexing_service_opt: Option<Mailbox<IndexingService>>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
     get_index_metadata_handler(index_service.clone())
         .or(get_indexes_metadatas_handler(index_service.clone()))
-        .or(get_all_splits_handler(index_service))
+        .or(list_splits_handler(index_service.clone()))
+        .or(index_stats_handler(index_service.clone()))
+        .or(get_source_checkpoint_handler(index_service.clone()))
+        .or(reset_source_checkpoint_handler(
+            index_service,
+            indexing_service_opt,
+        ))
     // TODO: comment create/delete handlers and reactivate/update them once we implemented the logic
     // of routing these requests to the right node, see https://github.com/quickwit-oss/quickwit/issues/1481.
     //.or(create_index_handler(index_service.clone()))
@@ -67,22 +87,316 @@ fn get_indexes_metadatas_handler(
         .and_then(get_indexes_metadatas)
 }
 
-async fn get_all_splits(
+fn from_split_state_list<'de, D>(deserializer: D) -> Result<Vec<SplitState>, D::Error>
+where D: Deserializer<'de> {
+    let str_sequence = String::deserialize(deserializer)?;
+    str_sequence
+        .trim_matches(',')
+        .split(',')
+        .map(|split_state_str| SplitState::from_str(split_state_str).map_err(de::Error::custom))
+        .collect()
+}
+
+fn from_tag_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where D: Deserializer<'de> {
+    let str_sequence = String::deserialize(deserializer)?;
+    let tags = str_sequence
+        .trim_matches(',')
+        .split(',')
+        .map(|tag| tag.to_string())
+        .collect();
+    Ok(Some(tags))
+}
+
+/// This struct represents the QueryString passed to
+/// the rest API to list the splits of an index.
+#[derive(Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+struct ListSplitsQueryString {
+    /// Comma-separated list of the split states to keep.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_split_state_list")]
+    pub split_states: Vec<SplitState>,
+    /// If set, keep the splits containing documents with a `timestamp >= start_timestamp`.
+    pub start_timestamp: Option<i64>,
+    /// If set, keep the splits containing documents with a `timestamp < end_timestamp`.
+    pub end_timestamp: Option<i64>,
+    /// If set, keep the splits created at or after this timestamp.
+    pub start_create_timestamp: Option<i64>,
+    /// If set, keep the splits created before this timestamp.
+    pub end_create_timestamp: Option<i64>,
+    /// If set, keep the splits last updated at or after this timestamp.
+    pub start_update_timestamp: Option<i64>,
+    /// If set, keep the splits last updated before this timestamp.
+    pub end_update_timestamp: Option<i64>,
+    /// Comma-separated list of tags that the splits must all have.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_tag_list")]
+    pub tags: Option<Vec<String>>,
+    /// If set, keep the splits whose ID starts with this prefix.
+    pub split_id_prefix: Option<String>,
+    /// Cursor returned with the previous page.
+    pub cursor: Option<String>,
+    /// Maximum number of splits to return. All the splits are returned if not set.
+    pub limit: Option<usize>,
+}
+
+impl ListSplitsQueryString {
+    /// Returns true if the request asks for a page of splits, in which case the response is a
+    /// page object rather than the array of all the matching splits.
+    fn is_paginated(&self) -> bool {
+        self.cursor.is_some() || self.limit.is_some()
+    }
+}
+
+fn timestamp_range(start_opt: Option<i64>, end_opt: Option<i64>) -> Option<Range<i64>> {
+    if start_opt.is_none() && end_opt.is_none() {
+        return None;
+    }
+    Some(start_opt.unwrap_or(i64::MIN)..end_opt.unwrap_or(i64::MAX))
+}
+
+impl From<ListSplitsQueryString> for ListSplitsQuery {
+    fn from(query_string: ListSplitsQueryString) -> Self {
+        ListSplitsQuery {
+            split_states: query_string.split_states,
+            time_range: timestamp_range(query_string.start_timestamp, query_string.end_timestamp),
+            create_timestamp: timestamp_range(
+                query_string.start_create_timestamp,
+                query_string.end_create_timestamp,
+            ),
+            update_timestamp: timestamp_range(
+                query_string.start_update_timestamp,
+                query_string.end_update_timestamp,
+            ),
+            tags: query_string
+                .tags
+                .map(|tags| TagFilterAst::And(tags.into_iter().map(tag_pruning::tag).collect())),
+            split_id_prefix: query_string.split_id_prefix,
+            cursor: query_string.cursor,
+            limit: query_string.limit,
+            ..Default::default()
+        }
+    }
+}
+
+async fn list_splits(
     index_id: String,
+    query_string: ListSplitsQueryString,
     index_service: Arc<IndexService>,
 ) -> Result<impl warp::Reply, Infallible> {
-    info!(index_id = %index_id, "get-index");
-    let splits = index_service.get_all_splits(&index_id).await;
-    Ok(Format::default().make_rest_reply_non_serializable_error(splits))
+    info!(index_id = %index_id, "list-splits");
+    let is_paginated = query_string.is_paginated();
+    let splits_page_res = index_service
+        .list_splits_page(&index_id, query_string.into())
+        .await;
+    if is_paginated {
+        return Ok(Format::default().make_rest_reply_non_serializable_error(splits_page_res));
+    }
+    let splits_res = splits_page_res.map(|splits_page| splits_page.splits);
+    Ok(Format::default().make_rest_reply_non_serializable_error(splits_res))
 }
 
-fn get_all_splits_handler(
+fn list_splits_handler(
     index_service: Arc<IndexService>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
     warp::path!("indexes" / String / "splits")
         .and(warp::get())
-        .and(warp::path::end().map(move || index_service.clone()))
-        .and_then(get_all_splits)
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(index_service))
+        .and_then(list_splits)
+}
+
+async fn index_stats(
+    index_id: String,
+    index_service: Arc<IndexService>,
+) -> Result<impl warp::Reply, Infallible> {
+    info!(index_id = %index_id, "index-stats");
+    let index_stats = index_service.index_stats(&index_id).await;
+    Ok(Format::default().make_rest_reply_non_serializable_error(index_stats))
+}
+
+fn index_stats_handler(
+    index_service: Arc<IndexService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "stats")
+        .and(warp::get())
+        .and(with_arg(index_service))
+        .and_then(index_stats)
+}
+
+async fn get_source_checkpoint(
+    index_id: String,
+    source_id: String,
+    index_service: Arc<IndexService>,
+) -> Result<impl warp::Reply, Infallible> {
+    info!(index_id = %index_id, source_id = %source_id, "get-source-checkpoint");
+    let source_checkpoint = index_service.source_checkpoint(&index_id, &source_id).await;
+    Ok(Format::default().make_rest_reply_non_serializable_error(source_checkpoint))
+}
+
+fn get_source_checkpoint_handler(
+    index_service: Arc<IndexService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "checkpoint")
+        .and(warp::get())
+        .and(with_arg(index_service))
+        .and_then(get_source_checkpoint)
+}
+
+/// Body of a source checkpoint reset request.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResetSourceCheckpointRequest {
+    /// Position to reset the partitions to.
+    to: CheckpointResetTarget,
+    /// Resets only these partitions. All the partitions of the source are reset if empty.
+    #[serde(default)]
+    partitions: Vec<String>,
+    /// Only computes the new positions without applying them.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Reset of a partition, as returned by the source checkpoint reset endpoint.
+#[derive(Debug, Serialize)]
+struct PartitionResetResponse {
+    partition_id: String,
+    previous_position: String,
+    position: String,
+}
+
+async fn reset_source_checkpoint(
+    index_id: String,
+    source_id: String,
+    request: ResetSourceCheckpointRequest,
+    index_service: Arc<IndexService>,
+    indexing_service_opt: Option<Mailbox<IndexingService>>,
+) -> Result<impl warp::Reply, Infallible> {
+    info!(
+        index_id = %index_id,
+        source_id = %source_id,
+        request = ?request,
+        "reset-source-checkpoint"
+    );
+    let partition_resets = reset_source_checkpoint_inner(
+        index_id,
+        source_id,
+        request,
+        index_service,
+        indexing_service_opt,
+    )
+    .await;
+    Ok(Format::default().make_rest_reply(partition_resets))
+}
+
+/// Resets the checkpoint of a source. The pipeline indexing the source on this node, if any, is
+/// shut down beforehand so that it cannot publish splits read from its previous positions, and
+/// respawned afterwards to resume from the new positions.
+async fn reset_source_checkpoint_inner(
+    index_id: String,
+    source_id: String,
+    request: ResetSourceCheckpointRequest,
+    index_service: Arc<IndexService>,
+    indexing_service_opt: Option<Mailbox<IndexingService>>,
+) -> Result<Vec<PartitionResetResponse>, FormatError> {
+    let indexing_service_opt = indexing_service_opt.filter(|_| !request.dry_run);
+    let mut is_pipeline_shut_down = false;
+    if let Some(indexing_service) = &indexing_service_opt {
+        let pipeline_id = IndexingPipelineId {
+            index_id: index_id.clone(),
+            source_id: source_id.clone(),
+        };
+        match indexing_service
+            .ask_for_res(ObservePipeline { pipeline_id })
+            .await
+        {
+            Ok(_) => is_pipeline_shut_down = true,
+            Err(AskError::ErrorReply(IndexingServiceError::MissingPipeline { .. })) => {}
+            Err(error) => return Err(FormatError::wrap(error)),
+        }
+        if is_pipeline_shut_down {
+            indexing_service
+                .ask_for_res(ShutdownPipeline {
+                    index_id: index_id.clone(),
+                    source_id: source_id.clone(),
+                })
+                .await
+                .map_err(FormatError::wrap)?;
+        }
+    }
+    let partition_ids: Vec<PartitionId> = request
+        .partitions
+        .into_iter()
+        .map(PartitionId::from)
+        .collect();
+    let reset_result = index_service
+        .reset_source_checkpoint(
+            &index_id,
+            &source_id,
+            request.to,
+            &partition_ids,
+            request.dry_run,
+        )
+        .await;
+    // The pipeline is respawned even if the reset failed, in which case it resumes from its
+    // previous positions.
+    if let Some(indexing_service) = indexing_service_opt.filter(|_| is_pipeline_shut_down) {
+        respawn_pipeline(&index_id, &source_id, &index_service, &indexing_service).await?;
+    }
+    let partition_resets = reset_result
+        .map_err(FormatError::wrap)?
+        .iter()
+        .map(
+            |(partition_id, previous_position, position)| PartitionResetResponse {
+                partition_id: partition_id.0.to_string(),
+                previous_position: previous_position.as_str().to_string(),
+                position: position.as_str().to_string(),
+            },
+        )
+        .collect();
+    Ok(partition_resets)
+}
+
+async fn respawn_pipeline(
+    index_id: &str,
+    source_id: &str,
+    index_service: &IndexService,
+    indexing_service: &Mailbox<IndexingService>,
+) -> Result<(), FormatError> {
+    let index_metadata = index_service
+        .get_index(index_id)
+        .await
+        .map_err(FormatError::wrap)?;
+    let source = index_metadata
+        .sources
+        .get(source_id)
+        .cloned()
+        .ok_or_else(|| FormatError {
+            code: ServiceErrorCode::NotFound,
+            error: format!("Source `{source_id}` does not exist."),
+        })?;
+    indexing_service
+        .ask_for_res(SpawnPipeline {
+            index_id: index_id.to_string(),
+            source,
+        })
+        .await
+        .map_err(FormatError::wrap)?;
+    Ok(())
+}
+
+fn reset_source_checkpoint_handler(
+    index_service: Arc<IndexService>,
+    indexing_service_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "checkpoint" / "reset")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and(with_arg(index_service))
+        .and(with_arg(indexing_service_opt))
+        .and_then(reset_source_checkpoint)
 }
 
 async fn get_indexes_metadatas(
@@ -141,8 +455,10 @@ async fn get_indexes_metadatas(
 mod tests {
     use assert_json_diff::assert_json_include;
     use quickwit_common::uri::Uri;
+    use quickwit_config::{SourceConfig, SourceParams};
     use quickwit_indexing::mock_split;
-    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_metastore::checkpoint::{CheckpointDelta, CheckpointReset, Position};
+    use quickwit_metastore::{IndexMetadata, IndexStats, MockMetastore, SplitCounts, SplitsPage};
     use quickwit_storage::StorageUriResolver;
 
     use super::*;
@@ -165,7 +481,7 @@ mod tests {
             Uri::new("file:///default-index-uri".to_string()),
         );
         let index_management_handler =
-            super::index_management_handlers(Arc::new(index_service)).recover(recover_fn);
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index")
             .reply(&index_management_handler)
@@ -181,20 +497,75 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_rest_get_all_splits() -> anyhow::Result<()> {
+    async fn test_rest_list_splits() -> anyhow::Result<()> {
         let mut metastore = MockMetastore::new();
         metastore
-            .expect_list_all_splits()
-            .returning(|_index_id: &str| Ok(vec![mock_split("split_1")]));
+            .expect_list_splits_page()
+            .withf(|index_id: &str, query: &ListSplitsQuery| {
+                index_id == "quickwit-demo-index"
+                    && query.split_states == [SplitState::Published]
+                    && query.time_range == Some(i64::MIN..100)
+                    && query.cursor.as_deref() == Some("split_0")
+                    && query.limit == Some(1)
+            })
+            .returning(|_index_id: &str, _query: ListSplitsQuery| {
+                Ok(SplitsPage {
+                    splits: vec![mock_split("split_1")],
+                    next_cursor: Some("split_1".to_string()),
+                })
+            });
         let index_service = IndexService::new(
             Arc::new(metastore),
             StorageUriResolver::for_test(),
             Uri::try_new("file:///default-index-uri").unwrap(),
         );
         let index_management_handler =
-            super::index_management_handlers(Arc::new(index_service)).recover(recover_fn);
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
         let resp = warp::test::request()
-            .path("/indexes/quickwit-demo-index/splits")
+            .path(
+                "/indexes/quickwit-demo-index/splits?split_states=Published&end_timestamp=100&\
+                 cursor=split_0&limit=1",
+            )
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "splits": [{
+                "create_timestamp": 0,
+                "split_id": "split_1",
+            }],
+            "next_cursor": "split_1",
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_list_splits_without_pagination() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_list_splits_page()
+            .withf(|_index_id: &str, query: &ListSplitsQuery| {
+                query.tags == Some(TagFilterAst::And(vec![tag_pruning::tag("tenant:1")]))
+                    && query.update_timestamp == Some(10..i64::MAX)
+                    && query.limit.is_none()
+            })
+            .returning(|_index_id: &str, _query: ListSplitsQuery| {
+                Ok(SplitsPage {
+                    splits: vec![mock_split("split_1")],
+                    next_cursor: None,
+                })
+            });
+        let index_service = IndexService::new(
+            Arc::new(metastore),
+            StorageUriResolver::for_test(),
+            Uri::try_new("file:///default-index-uri").unwrap(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits?tags=tenant:1&start_update_timestamp=10")
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
@@ -207,6 +578,185 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_index_stats() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_stats()
+            .withf(|index_id: &str| index_id == "quickwit-demo-index")
+            .returning(|index_id: &str| {
+                Ok(IndexStats {
+                    index_id: index_id.to_string(),
+                    num_splits: SplitCounts {
+                        published: 2,
+                        ..Default::default()
+                    },
+                    num_docs: 10,
+                    time_range: Some(0..=100),
+                    ..Default::default()
+                })
+            });
+        let index_service = IndexService::new(
+            Arc::new(metastore),
+            StorageUriResolver::for_test(),
+            Uri::try_new("file:///default-index-uri").unwrap(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/stats")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "index_id": "quickwit-demo-index",
+            "num_splits": {
+                "staged": 0,
+                "published": 2,
+                "marked_for_deletion": 0,
+            },
+            "num_docs": 10,
+            "time_range": {"start": 0, "end": 100},
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+        Ok(())
+    }
+
+    fn index_metadata_with_source_checkpoint() -> IndexMetadata {
+        let mut index_metadata = IndexMetadata::for_test(
+            "quickwit-demo-index",
+            "file:///path/to/index/quickwit-demo-index",
+        );
+        index_metadata.sources.insert(
+            "file-source".to_string(),
+            SourceConfig {
+                source_id: "file-source".to_string(),
+                source_params: SourceParams::file("path/to/file"),
+            },
+        );
+        let checkpoint_delta = CheckpointDelta::from_partition_delta(
+            PartitionId::from("path/to/file"),
+            Position::Beginning,
+            Position::from(42u64),
+        );
+        index_metadata
+            .checkpoint
+            .try_apply_delta("file-source", checkpoint_delta)
+            .unwrap();
+        index_metadata
+    }
+
+    #[tokio::test]
+    async fn test_rest_get_source_checkpoint() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| Ok(index_metadata_with_source_checkpoint()));
+        let index_service = IndexService::new(
+            Arc::new(metastore),
+            StorageUriResolver::for_test(),
+            Uri::try_new("file:///default-index-uri").unwrap(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/sources/file-source/checkpoint")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "path/to/file": "00000000000000000042",
+        });
+        assert_eq!(resp_json, expected_response_json);
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/sources/source-does-not-exist/checkpoint")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_reset_source_checkpoint() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| Ok(index_metadata_with_source_checkpoint()));
+        metastore
+            .expect_reset_source_checkpoint()
+            .withf(
+                |index_id: &str, source_id: &str, checkpoint_reset: &CheckpointReset| {
+                    index_id == "quickwit-demo-index"
+                        && source_id == "file-source"
+                        && checkpoint_reset.num_partitions() == 1
+                },
+            )
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        let index_service = IndexService::new(
+            Arc::new(metastore),
+            StorageUriResolver::for_test(),
+            Uri::try_new("file:///default-index-uri").unwrap(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/sources/file-source/checkpoint/reset")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"to": "beginning"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!([{
+            "partition_id": "path/to/file",
+            "previous_position": "00000000000000000042",
+            "position": "",
+        }]);
+        assert_eq!(resp_json, expected_response_json);
+
+        // The file source does not support resetting to an offset.
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/sources/file-source/checkpoint/reset")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"to": {"offset": 10}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_splits_query_string() {
+        let query_string: ListSplitsQueryString = serde_qs::from_str(
+            "split_states=Staged,Published&start_create_timestamp=10&end_update_timestamp=20&\
+             tags=tenant:1,app:web&split_id_prefix=split",
+        )
+        .unwrap();
+        assert!(!query_string.is_paginated());
+        let query = ListSplitsQuery::from(query_string);
+        assert_eq!(
+            query,
+            ListSplitsQuery {
+                split_states: vec![SplitState::Staged, SplitState::Published],
+                create_timestamp: Some(10..i64::MAX),
+                update_timestamp: Some(i64::MIN..20),
+                tags: Some(TagFilterAst::And(vec![
+                    tag_pruning::tag("tenant:1"),
+                    tag_pruning::tag("app:web"),
+                ])),
+                split_id_prefix: Some("split".to_string()),
+                ..Default::default()
+            }
+        );
+        assert!(serde_qs::from_str::<ListSplitsQueryString>("split_states=Unknown").is_err());
+    }
+
     #[tokio::test]
     async fn test_rest_get_list_indexes() -> anyhow::Result<()> {
         let mut metastore = MockMetastore::new();
@@ -222,7 +772,7 @@ mod tests {
             Uri::new("file:///default-index-uri".to_string()),
         );
         let index_management_handler =
-            super::index_management_handlers(Arc::new(index_service)).recover(recover_fn);
+            super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .reply(&index_management_handler)
@@ -270,7 +820,7 @@ mod tests {
     //         Uri::new("file:///default-index-uri".to_string()),
     //     );
     //     let index_management_handler =
-    //         super::index_management_handlers(Arc::new(index_service)).recover(recover_fn);
+    //         super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
     //     let resp = warp::test::request()
     //         .path("/indexes/quickwit-demo-index")
     //         .method("DELETE")
@@ -305,8 +855,8 @@ mod tests {
     //         StorageUriResolver::for_test(),
     //         Uri::new("file:///default-index-uri".to_string()),
     //     );
-    //     let index_management_handler = super::index_management_handlers(Arc::new(index_service));
-    //     let resp = warp::test::request()
+    //     let index_management_handler = super::index_management_handlers(Arc::new(index_service),
+    // None);     let resp = warp::test::request()
     //         .path("/indexes")
     //         .method("POST")
     //         .json(&true)
@@ -332,7 +882,7 @@ mod tests {
     //         Uri::new("file:///default-index-uri".to_string()),
     //     );
     //     let index_management_handler =
-    //         super::index_management_handlers(Arc::new(index_service)).recover(recover_fn);
+    //         super::index_management_handlers(Arc::new(index_service), None).recover(recover_fn);
     //     let resp = warp::test::request()
     //         .path("/indexes")
     //         .method("POST")
@@ -19,4 +19,7 @@
 
 mod rest_handler;
 
-pub use rest_handler::indexing_get_handler;
+pub use rest_handler::{
+    indexing_get_handler, indexing_pipeline_action_handler, inspect_dead_letter_queue_handler,
+    replay_dead_letter_queue_handler,
+};
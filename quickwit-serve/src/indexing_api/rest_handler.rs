@@ -18,13 +18,18 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::convert::Infallible;
+use std::str::FromStr;
 
 use quickwit_actors::Mailbox;
 use quickwit_indexing::actors::IndexingService;
-use quickwit_indexing::models::Observe;
+use quickwit_indexing::models::{
+    CommitPipeline, IndexingPipelineId, InspectDeadLetterQueue, Observe, PausePipeline,
+    ReplayDeadLetterQueue, RestartPipeline, ResumePipeline,
+};
+use tracing::info;
 use warp::{Filter, Rejection};
 
-use crate::format::Format;
+use crate::format::{Format, FormatError};
 use crate::require;
 
 async fn indexing_endpoint(
@@ -35,7 +40,7 @@ async fn indexing_endpoint(
 }
 
 fn indexing_get_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone {
-    warp::path!("api" / "v1" / "indexing").and(warp::get())
+    warp::path!("indexing").and(warp::get())
 }
 
 pub fn indexing_get_handler(
@@ -45,3 +50,185 @@ pub fn indexing_get_handler(
         .and(require(indexing_service_mailbox_opt))
         .and_then(indexing_endpoint)
 }
+
+/// Action that can be applied to a single indexing pipeline.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum PipelineAction {
+    Pause,
+    Resume,
+    Restart,
+    Commit,
+}
+
+impl FromStr for PipelineAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        match action {
+            "pause" => Ok(Self::Pause),
+            "resume" => Ok(Self::Resume),
+            "restart" => Ok(Self::Restart),
+            "commit" => Ok(Self::Commit),
+            _ => Err(format!("Unknown pipeline action `{action}`.")),
+        }
+    }
+}
+
+fn indexing_pipeline_action_filter(
+) -> impl Filter<Extract = (String, String, PipelineAction), Error = Rejection> + Clone {
+    warp::path!("indexing" / String / String / PipelineAction).and(warp::post())
+}
+
+async fn indexing_pipeline_action_endpoint(
+    index_id: String,
+    source_id: String,
+    action: PipelineAction,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<impl warp::Reply, Infallible> {
+    info!(index_id = %index_id, source_id = %source_id, action = ?action, "indexing-pipeline-action");
+    let pipeline_id = IndexingPipelineId {
+        index_id,
+        source_id,
+    };
+    let action_res = match action {
+        PipelineAction::Pause => {
+            indexing_service_mailbox
+                .ask_for_res(PausePipeline { pipeline_id })
+                .await
+        }
+        PipelineAction::Resume => {
+            indexing_service_mailbox
+                .ask_for_res(ResumePipeline { pipeline_id })
+                .await
+        }
+        PipelineAction::Restart => {
+            indexing_service_mailbox
+                .ask_for_res(RestartPipeline { pipeline_id })
+                .await
+        }
+        PipelineAction::Commit => {
+            indexing_service_mailbox
+                .ask_for_res(CommitPipeline { pipeline_id })
+                .await
+        }
+    };
+    Ok(Format::PrettyJson.make_rest_reply(action_res.map_err(FormatError::wrap)))
+}
+
+pub fn indexing_pipeline_action_handler(
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    indexing_pipeline_action_filter()
+        .and(require(indexing_service_mailbox_opt))
+        .and_then(indexing_pipeline_action_endpoint)
+}
+
+fn inspect_dead_letter_queue_filter() -> impl Filter<Extract = (String,), Error = Rejection> + Clone
+{
+    warp::path!("indexing" / String / "dead-letter").and(warp::get())
+}
+
+async fn inspect_dead_letter_queue_endpoint(
+    index_id: String,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<impl warp::Reply, Infallible> {
+    let records = indexing_service_mailbox
+        .ask_for_res(InspectDeadLetterQueue { index_id })
+        .await
+        .map_err(FormatError::wrap);
+    Ok(Format::PrettyJson.make_rest_reply(records))
+}
+
+pub fn inspect_dead_letter_queue_handler(
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    inspect_dead_letter_queue_filter()
+        .and(require(indexing_service_mailbox_opt))
+        .and_then(inspect_dead_letter_queue_endpoint)
+}
+
+fn replay_dead_letter_queue_filter() -> impl Filter<Extract = (String,), Error = Rejection> + Clone
+{
+    warp::path!("indexing" / String / "dead-letter" / "replay").and(warp::post())
+}
+
+async fn replay_dead_letter_queue_endpoint(
+    index_id: String,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<impl warp::Reply, Infallible> {
+    info!(index_id = %index_id, "replay-dead-letter-queue");
+    let ingest_resp = indexing_service_mailbox
+        .ask_for_res(ReplayDeadLetterQueue { index_id })
+        .await
+        .map_err(FormatError::wrap);
+    Ok(Format::PrettyJson.make_rest_reply(ingest_resp))
+}
+
+pub fn replay_dead_letter_queue_handler(
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    replay_dead_letter_queue_filter()
+        .and(require(indexing_service_mailbox_opt))
+        .and_then(replay_dead_letter_queue_endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_indexing_get_filter() {
+        assert!(warp::test::request()
+            .path("/indexing")
+            .filter(&indexing_get_filter())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_indexing_pipeline_action_filter() {
+        let (index_id, source_id, action) = warp::test::request()
+            .method("POST")
+            .path("/indexing/my-index/my-source/restart")
+            .filter(&indexing_pipeline_action_filter())
+            .await
+            .unwrap();
+        assert_eq!(index_id, "my-index");
+        assert_eq!(source_id, "my-source");
+        assert_eq!(action, PipelineAction::Restart);
+
+        let rejection = warp::test::request()
+            .method("POST")
+            .path("/indexing/my-index/my-source/explode")
+            .filter(&indexing_pipeline_action_filter())
+            .await
+            .unwrap_err();
+        assert!(rejection.is_not_found());
+
+        let rejection = warp::test::request()
+            .method("GET")
+            .path("/indexing/my-index/my-source/pause")
+            .filter(&indexing_pipeline_action_filter())
+            .await
+            .unwrap_err();
+        assert!(!rejection.is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_queue_filters() {
+        let index_id = warp::test::request()
+            .path("/indexing/my-index/dead-letter")
+            .filter(&inspect_dead_letter_queue_filter())
+            .await
+            .unwrap();
+        assert_eq!(index_id, "my-index");
+
+        let index_id = warp::test::request()
+            .method("POST")
+            .path("/indexing/my-index/dead-letter/replay")
+            .filter(&replay_dead_letter_queue_filter())
+            .await
+            .unwrap();
+        assert_eq!(index_id, "my-index");
+    }
+}
@@ -17,8 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use quickwit_actors::Mailbox;
+use quickwit_core::IndexService;
 use quickwit_ingest_api::IngestApiService;
 use quickwit_proto::ingest_api::{
     ingest_api_service_server as grpc, FetchRequest, FetchResponse, IngestRequest, IngestResponse,
@@ -29,11 +32,20 @@ use quickwit_proto::tonic;
 use crate::error::convert_to_grpc_result;
 
 #[derive(Clone)]
-pub struct GrpcIngestApiAdapter(Mailbox<IngestApiService>);
+pub struct GrpcIngestApiAdapter {
+    ingest_api_mailbox: Mailbox<IngestApiService>,
+    index_service: Arc<IndexService>,
+}
 
-impl From<Mailbox<IngestApiService>> for GrpcIngestApiAdapter {
-    fn from(ingest_api_service: Mailbox<IngestApiService>) -> Self {
-        GrpcIngestApiAdapter(ingest_api_service)
+impl GrpcIngestApiAdapter {
+    pub fn new(
+        ingest_api_mailbox: Mailbox<IngestApiService>,
+        index_service: Arc<IndexService>,
+    ) -> Self {
+        GrpcIngestApiAdapter {
+            ingest_api_mailbox,
+            index_service,
+        }
     }
 }
 
@@ -44,7 +56,8 @@ impl grpc::IngestApiService for GrpcIngestApiAdapter {
         request: tonic::Request<IngestRequest>,
     ) -> Result<tonic::Response<IngestResponse>, tonic::Status> {
         let ingest_req = request.into_inner();
-        let ingest_reply = self.0.ask_for_res(ingest_req).await;
+        let ingest_reply =
+            super::ingest(&self.ingest_api_mailbox, &self.index_service, ingest_req).await;
         convert_to_grpc_result(ingest_reply)
     }
 
@@ -53,7 +66,7 @@ impl grpc::IngestApiService for GrpcIngestApiAdapter {
         request: tonic::Request<FetchRequest>,
     ) -> Result<tonic::Response<FetchResponse>, tonic::Status> {
         let fetch_req = request.into_inner();
-        let fetch_reply = self.0.ask_for_res(fetch_req).await;
+        let fetch_reply = self.ingest_api_mailbox.ask_for_res(fetch_req).await;
         convert_to_grpc_result(fetch_reply)
     }
 
@@ -62,7 +75,7 @@ impl grpc::IngestApiService for GrpcIngestApiAdapter {
         request: tonic::Request<TailRequest>,
     ) -> Result<tonic::Response<FetchResponse>, tonic::Status> {
         let tail_req = request.into_inner();
-        let tail_reply = self.0.ask_for_res(tail_req).await;
+        let tail_reply = self.ingest_api_mailbox.ask_for_res(tail_req).await;
         convert_to_grpc_result(tail_reply)
     }
 }
@@ -20,4 +20,40 @@
 mod grpc_adapter;
 mod rest_handler;
 
+use std::collections::HashSet;
+use std::time::Duration;
+
+use quickwit_actors::Mailbox;
+use quickwit_core::IndexService;
+use quickwit_ingest_api::IngestApiService;
+use quickwit_proto::ingest_api::{CommitType, IngestRequest, IngestResponse};
 pub use rest_handler::{elastic_bulk_handler, ingest_handler, tail_handler, BulkApiError};
+
+use crate::format::FormatError;
+
+/// Ingests the documents of the request and, depending on its commit type, waits until they
+/// are published for at most the largest commit timeout of the targeted indexes plus a margin.
+pub(crate) async fn ingest(
+    ingest_api_mailbox: &Mailbox<IngestApiService>,
+    index_service: &IndexService,
+    ingest_req: IngestRequest,
+) -> Result<IngestResponse, FormatError> {
+    let mut commit_timeout = Duration::ZERO;
+    if ingest_req.commit() != CommitType::Auto {
+        let index_ids: HashSet<&str> = ingest_req
+            .doc_batches
+            .iter()
+            .map(|doc_batch| doc_batch.index_id.as_str())
+            .collect();
+        for index_id in index_ids {
+            let index_metadata = index_service
+                .get_index(index_id)
+                .await
+                .map_err(FormatError::wrap)?;
+            commit_timeout = commit_timeout.max(index_metadata.indexing_settings.commit_timeout());
+        }
+    }
+    quickwit_ingest_api::ingest(ingest_api_mailbox, ingest_req, commit_timeout)
+        .await
+        .map_err(FormatError::wrap)
+}
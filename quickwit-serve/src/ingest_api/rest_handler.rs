@@ -19,18 +19,26 @@
 
 use std::collections::HashMap;
 use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use quickwit_actors::Mailbox;
+use quickwit_config::build_doc_mapper;
+use quickwit_core::IndexService;
+use quickwit_doc_mapper::{DocMapper, InputFormat, TextRecordConverter};
 use quickwit_ingest_api::{add_doc, IngestApiService};
-use quickwit_proto::ingest_api::{DocBatch, IngestRequest, TailRequest};
+use quickwit_proto::ingest_api::{
+    CommitType, DocBatch, IngestRequest, IngestResponse, TailRequest,
+};
 use serde::Deserialize;
 use serde_json::Value;
 use thiserror::Error;
 use warp::{reject, Filter, Rejection};
 
+use crate::error::ServiceErrorCode;
 use crate::format::FormatError;
-use crate::{require, Format};
+use crate::{require, with_arg, Format};
 
 #[derive(Debug, Error)]
 #[error("Body is not utf-8.")]
@@ -46,6 +54,9 @@ impl warp::reject::Reject for IngestApiServiceUnavailable {}
 
 const CONTENT_LENGTH_LIMIT: u64 = 10_000_000; // 10M
 
+/// Time after which the doc mapper of an index is built again from its metadata.
+const DOC_MAPPER_CACHE_TTL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Error)]
 pub enum BulkApiError {
     #[error("Could not parse action `{0}`.")]
@@ -80,17 +91,84 @@ struct BulkActionMeta {
     id: String,
 }
 
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+struct IngestQueryString {
+    /// Format of the records of the body.
+    #[serde(default)]
+    input_format: InputFormat,
+    /// Whether the request waits for the documents to be committed before returning.
+    #[serde(default)]
+    commit: CommitType,
+}
+
+/// Caches the doc mappers used to coerce the documents converted from untyped input formats, so
+/// that they are not built again for every request. The doc mappers expire after
+/// `DOC_MAPPER_CACHE_TTL`, so that an index deleted and created again with another doc mapping is
+/// eventually taken into account.
+struct DocMapperCache {
+    index_service: Arc<IndexService>,
+    doc_mappers: Mutex<HashMap<String, (Instant, Arc<dyn DocMapper>)>>,
+}
+
+impl DocMapperCache {
+    fn new(index_service: Arc<IndexService>) -> Self {
+        Self {
+            index_service,
+            doc_mappers: Mutex::default(),
+        }
+    }
+
+    async fn get(&self, index_id: &str) -> Result<Arc<dyn DocMapper>, FormatError> {
+        let cached_doc_mapper_opt = self
+            .doc_mappers
+            .lock()
+            .unwrap()
+            .get(index_id)
+            .filter(|(built_at, _)| built_at.elapsed() < DOC_MAPPER_CACHE_TTL)
+            .map(|(_, doc_mapper)| doc_mapper.clone());
+        if let Some(doc_mapper) = cached_doc_mapper_opt {
+            return Ok(doc_mapper);
+        }
+        let index_metadata = self
+            .index_service
+            .get_index(index_id)
+            .await
+            .map_err(FormatError::wrap)?;
+        let doc_mapper = build_doc_mapper(
+            &index_metadata.doc_mapping,
+            &index_metadata.search_settings,
+            &index_metadata.indexing_settings,
+        )
+        .map_err(|error| FormatError {
+            code: ServiceErrorCode::Internal,
+            error: error.to_string(),
+        })?;
+        self.doc_mappers
+            .lock()
+            .unwrap()
+            .insert(index_id.to_string(), (Instant::now(), doc_mapper.clone()));
+        Ok(doc_mapper)
+    }
+}
+
 pub fn ingest_handler(
     ingest_api_mailbox_opt: Option<Mailbox<IngestApiService>>,
+    index_service: Arc<IndexService>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let doc_mapper_cache = Arc::new(DocMapperCache::new(index_service.clone()));
     ingest_filter()
         .and(require(ingest_api_mailbox_opt))
+        .and(with_arg(index_service))
+        .and(with_arg(doc_mapper_cache))
         .and_then(ingest)
 }
 
-fn ingest_filter() -> impl Filter<Extract = (String, String), Error = Rejection> + Clone {
+fn ingest_filter(
+) -> impl Filter<Extract = (String, IngestQueryString, String), Error = Rejection> + Clone {
     warp::path!(String / "ingest")
         .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
         .and(warp::body::bytes().and_then(|body: Bytes| async move {
             if let Ok(body_str) = std::str::from_utf8(&*body) {
@@ -113,24 +191,71 @@ fn lines(body: &str) -> impl Iterator<Item = &str> {
 
 async fn ingest(
     index_id: String,
+    ingest_query_string: IngestQueryString,
     payload: String,
     ingest_api_mailbox: Mailbox<IngestApiService>,
+    index_service: Arc<IndexService>,
+    doc_mapper_cache: Arc<DocMapperCache>,
 ) -> Result<impl warp::Reply, Infallible> {
+    let ingest_resp = ingest_docs(
+        index_id,
+        ingest_query_string.input_format,
+        ingest_query_string.commit,
+        payload,
+        ingest_api_mailbox,
+        index_service,
+        doc_mapper_cache,
+    )
+    .await;
+    Ok(Format::PrettyJson.make_rest_reply(ingest_resp))
+}
+
+async fn ingest_docs(
+    index_id: String,
+    input_format: InputFormat,
+    commit_type: CommitType,
+    payload: String,
+    ingest_api_mailbox: Mailbox<IngestApiService>,
+    index_service: Arc<IndexService>,
+    doc_mapper_cache: Arc<DocMapperCache>,
+) -> Result<IngestResponse, FormatError> {
     let mut doc_batch = DocBatch {
         index_id,
         ..Default::default()
     };
-    for doc_payload in lines(&payload) {
-        add_doc(doc_payload.as_bytes(), &mut doc_batch);
+    if input_format == InputFormat::Json {
+        for doc_payload in lines(&payload) {
+            add_doc(doc_payload.as_bytes(), &mut doc_batch);
+        }
+    } else {
+        // The documents are converted and coerced here rather than by the indexer, because the
+        // ingest API source feeding the indexer only carries JSON documents.
+        let doc_mapper = doc_mapper_cache.get(&doc_batch.index_id).await?;
+        let mut converter = TextRecordConverter::new(input_format);
+        // The lines are fed with their newline character, which belongs to the value of a CSV
+        // record whose quoted values span several lines.
+        let mut doc_jsons: Vec<String> = payload
+            .split_inclusive('\n')
+            .flat_map(|line| converter.convert_line(line.to_string()))
+            .collect();
+        doc_jsons.extend(converter.finish());
+        for doc_json in doc_jsons {
+            let doc_payload = match serde_json::from_str(&doc_json) {
+                Ok(mut json_obj) => {
+                    doc_mapper.coerce_string_values(&mut json_obj);
+                    Value::Object(json_obj).to_string()
+                }
+                // Records that failed to convert are ingested as is and rejected by the indexer.
+                Err(_) => doc_json.trim_end().to_string(),
+            };
+            add_doc(doc_payload.as_bytes(), &mut doc_batch);
+        }
     }
     let ingest_req = IngestRequest {
         doc_batches: vec![doc_batch],
+        commit: commit_type as i32,
     };
-    let ingest_resp = ingest_api_mailbox
-        .ask_for_res(ingest_req)
-        .await
-        .map_err(FormatError::wrap);
-    Ok(Format::PrettyJson.make_rest_reply(ingest_resp))
+    super::ingest(&ingest_api_mailbox, &index_service, ingest_req).await
 }
 
 pub fn tail_handler(
@@ -156,9 +281,40 @@ async fn tail_endpoint(
     Ok(Format::PrettyJson.make_rest_reply(tail_res))
 }
 
-fn elastic_bulk_filter() -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+/// Values of the `refresh` parameter of the Elasticsearch bulk API.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+enum ElasticRefresh {
+    #[default]
+    #[serde(rename = "false")]
+    False,
+    #[serde(rename = "true", alias = "")]
+    True,
+    #[serde(rename = "wait_for")]
+    WaitFor,
+}
+
+impl From<ElasticRefresh> for CommitType {
+    fn from(refresh: ElasticRefresh) -> Self {
+        match refresh {
+            ElasticRefresh::False => CommitType::Auto,
+            ElasticRefresh::True => CommitType::Force,
+            ElasticRefresh::WaitFor => CommitType::WaitFor,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+struct ElasticBulkQueryString {
+    /// Whether the request waits for the documents to be committed before returning.
+    #[serde(default)]
+    refresh: ElasticRefresh,
+}
+
+fn elastic_bulk_filter(
+) -> impl Filter<Extract = (ElasticBulkQueryString, String), Error = Rejection> + Clone {
     warp::path!("_bulk")
         .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
         .and(warp::body::bytes().and_then(|body: Bytes| async move {
             if let Ok(body_str) = std::str::from_utf8(&*body) {
@@ -171,15 +327,19 @@ fn elastic_bulk_filter() -> impl Filter<Extract = (String,), Error = Rejection>
 
 pub fn elastic_bulk_handler(
     ingest_api_mailbox_opt: Option<Mailbox<IngestApiService>>,
+    index_service: Arc<IndexService>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
     elastic_bulk_filter()
         .and(require(ingest_api_mailbox_opt))
+        .and(with_arg(index_service))
         .and_then(elastic_ingest)
 }
 
 async fn elastic_ingest(
+    bulk_query_string: ElasticBulkQueryString,
     payload: String,
     ingest_api_mailbox: Mailbox<IngestApiService>,
+    index_service: Arc<IndexService>,
 ) -> Result<impl warp::Reply, Rejection> {
     let mut batches = HashMap::new();
     let mut payload_lines = lines(&payload);
@@ -206,19 +366,135 @@ async fn elastic_ingest(
         add_doc(source.to_string().as_bytes(), doc_batch);
     }
 
+    let commit_type = CommitType::from(bulk_query_string.refresh);
     let ingest_req = IngestRequest {
         doc_batches: batches.into_iter().map(|(_, batch)| batch).collect(),
+        commit: commit_type as i32,
     };
-    let ingest_resp = ingest_api_mailbox
-        .ask_for_res(ingest_req)
-        .await
-        .map_err(FormatError::wrap);
+    let ingest_resp = super::ingest(&ingest_api_mailbox, &index_service, ingest_req).await;
     Ok(Format::PrettyJson.make_rest_reply(ingest_resp))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{BulkAction, BulkActionMeta};
+    use quickwit_doc_mapper::InputFormat;
+    use quickwit_proto::ingest_api::CommitType;
+
+    use super::{
+        elastic_bulk_filter, ingest_filter, BulkAction, BulkActionMeta, ElasticRefresh,
+        IngestQueryString,
+    };
+
+    #[tokio::test]
+    async fn test_ingest_filter() {
+        let (index_id, ingest_query_string, payload) = warp::test::request()
+            .path("/my-index/ingest?input_format=csv")
+            .method("POST")
+            .body("ts,body\n1,foo")
+            .filter(&ingest_filter())
+            .await
+            .unwrap();
+        assert_eq!(index_id, "my-index");
+        assert_eq!(
+            ingest_query_string,
+            IngestQueryString {
+                input_format: InputFormat::Csv,
+                commit: CommitType::Auto,
+            }
+        );
+        assert_eq!(payload, "ts,body\n1,foo");
+
+        let (_, ingest_query_string, _) = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body("{}")
+            .filter(&ingest_filter())
+            .await
+            .unwrap();
+        assert_eq!(ingest_query_string.input_format, InputFormat::Json);
+
+        let (_, ingest_query_string, _) = warp::test::request()
+            .path("/my-index/ingest?commit=wait_for")
+            .method("POST")
+            .body("{}")
+            .filter(&ingest_filter())
+            .await
+            .unwrap();
+        assert_eq!(ingest_query_string.commit, CommitType::WaitFor);
+
+        let (_, ingest_query_string, _) = warp::test::request()
+            .path("/my-index/ingest?input_format=logfmt&commit=force")
+            .method("POST")
+            .body("level=info")
+            .filter(&ingest_filter())
+            .await
+            .unwrap();
+        assert_eq!(
+            ingest_query_string,
+            IngestQueryString {
+                input_format: InputFormat::Logfmt,
+                commit: CommitType::Force,
+            }
+        );
+
+        let rejection = warp::test::request()
+            .path("/my-index/ingest?commit=now")
+            .method("POST")
+            .body("{}")
+            .filter(&ingest_filter())
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<serde_qs::Error>().is_some());
+
+        let rejection = warp::test::request()
+            .path("/my-index/ingest?input_format=xml")
+            .method("POST")
+            .body("<doc/>")
+            .filter(&ingest_filter())
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<serde_qs::Error>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_elastic_bulk_filter() {
+        let (bulk_query_string, _) = warp::test::request()
+            .path("/_bulk")
+            .method("POST")
+            .body("{}")
+            .filter(&elastic_bulk_filter())
+            .await
+            .unwrap();
+        assert_eq!(bulk_query_string.refresh, ElasticRefresh::False);
+        assert_eq!(
+            CommitType::from(bulk_query_string.refresh),
+            CommitType::Auto
+        );
+
+        let (bulk_query_string, _) = warp::test::request()
+            .path("/_bulk?refresh=true")
+            .method("POST")
+            .body("{}")
+            .filter(&elastic_bulk_filter())
+            .await
+            .unwrap();
+        assert_eq!(
+            CommitType::from(bulk_query_string.refresh),
+            CommitType::Force
+        );
+
+        let (bulk_query_string, _) = warp::test::request()
+            .path("/_bulk?refresh=wait_for")
+            .method("POST")
+            .body("{}")
+            .filter(&elastic_bulk_filter())
+            .await
+            .unwrap();
+        assert_eq!(
+            CommitType::from(bulk_query_string.refresh),
+            CommitType::WaitFor
+        );
+    }
 
     #[test]
     fn test_deserialize() {
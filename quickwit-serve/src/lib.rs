@@ -45,12 +45,15 @@ use quickwit_common::uri::{Uri, FILE_PROTOCOL, S3_PROTOCOL};
 use quickwit_config::QuickwitConfig;
 use quickwit_core::IndexService;
 use quickwit_indexing::actors::IndexingService;
+use quickwit_indexing::models::{IndexerNodes, UpdateIndexerNodes};
 use quickwit_indexing::start_indexer_service;
 use quickwit_ingest_api::{init_ingest_api, IngestApiService};
 use quickwit_metastore::{quickwit_metastore_uri_resolver, Metastore};
 use quickwit_search::{start_searcher_service, SearchService};
 use quickwit_storage::quickwit_storage_uri_resolver;
 use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt;
+use tracing::error;
 use warp::{Filter, Rejection};
 
 pub use crate::args::ServeArgs;
@@ -105,25 +108,37 @@ pub async fn serve_quickwit(
 
     let universe = Universe::new();
 
-    let ingest_api_service: Option<Mailbox<IngestApiService>> = if services
-        .contains(&QuickwitService::Indexer)
-    {
-        let ingest_api_service = init_ingest_api(&universe, &config.data_dir_path.join("queues"))?;
-        Some(ingest_api_service)
-    } else {
-        None
-    };
+    let ingest_api_service: Option<Mailbox<IngestApiService>> =
+        if services.contains(&QuickwitService::Indexer) {
+            let ingest_api_service = init_ingest_api(
+                &universe,
+                &config.data_dir_path.join("queues"),
+                config.indexer_config.ingest_api_fsync,
+            )?;
+            Some(ingest_api_service)
+        } else {
+            None
+        };
 
     let indexer_service: Option<Mailbox<IndexingService>> =
         if services.contains(&QuickwitService::Indexer) {
+            let indexer_node_ids = cluster
+                .members_node_ids_for_service(QuickwitService::Indexer)
+                .await;
             let indexer_service = start_indexer_service(
                 &universe,
                 &config,
                 metastore.clone(),
                 storage_resolver.clone(),
                 ingest_api_service.clone(),
+                indexer_node_ids,
             )
             .await?;
+            spawn_indexer_nodes_watcher(
+                cluster.clone(),
+                indexer_service.clone(),
+                config.node_id.clone(),
+            );
             Some(indexer_service)
         } else {
             None
@@ -163,6 +178,30 @@ pub async fn serve_quickwit(
     Ok(())
 }
 
+/// Keeps the indexing service informed of the indexer nodes of the cluster, so that the sources
+/// and partitions are reassigned when indexers join or leave the cluster.
+fn spawn_indexer_nodes_watcher(
+    cluster: Arc<Cluster>,
+    indexer_service: Mailbox<IndexingService>,
+    node_id: String,
+) {
+    tokio::spawn(async move {
+        let mut member_change_stream = cluster.member_change_watcher();
+        while member_change_stream.next().await.is_some() {
+            let indexer_node_ids = cluster
+                .members_node_ids_for_service(QuickwitService::Indexer)
+                .await;
+            let indexer_nodes = IndexerNodes::new(node_id.clone(), indexer_node_ids);
+            if let Err(error) = indexer_service
+                .ask_for_res(UpdateIndexerNodes { indexer_nodes })
+                .await
+            {
+                error!(error = ?error, "Failed to reassign the sources to the indexer nodes.");
+            }
+        }
+    });
+}
+
 /// Checks if the conditions required to smoothly run a Quickwit cluster are met.
 /// Currently we don't allow cluster feature upon using:
 /// - A FileBacked metastore
@@ -29,7 +29,10 @@ use crate::error::ServiceErrorCode;
 use crate::format::FormatError;
 use crate::health_check_api::liveness_check_handler;
 use crate::index_api::index_management_handlers;
-use crate::indexing_api::indexing_get_handler;
+use crate::indexing_api::{
+    indexing_get_handler, indexing_pipeline_action_handler, inspect_dead_letter_queue_handler,
+    replay_dead_letter_queue_handler,
+};
 use crate::ingest_api::{elastic_bulk_handler, ingest_handler, tail_handler};
 use crate::node_info_handler::node_info_handler;
 use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
@@ -57,6 +60,15 @@ pub(crate) async fn start_rest_server(
         .or(indexing_get_handler(
             quickwit_services.indexer_service.clone(),
         ))
+        .or(indexing_pipeline_action_handler(
+            quickwit_services.indexer_service.clone(),
+        ))
+        .or(inspect_dead_letter_queue_handler(
+            quickwit_services.indexer_service.clone(),
+        ))
+        .or(replay_dead_letter_queue_handler(
+            quickwit_services.indexer_service.clone(),
+        ))
         .or(search_get_handler(quickwit_services.search_service.clone()))
         .or(search_post_handler(
             quickwit_services.search_service.clone(),
@@ -64,13 +76,18 @@ pub(crate) async fn start_rest_server(
         .or(search_stream_handler(
             quickwit_services.search_service.clone(),
         ))
-        .or(ingest_handler(quickwit_services.ingest_api_service.clone()))
+        .or(ingest_handler(
+            quickwit_services.ingest_api_service.clone(),
+            quickwit_services.index_service.clone(),
+        ))
         .or(tail_handler(quickwit_services.ingest_api_service.clone()))
         .or(elastic_bulk_handler(
             quickwit_services.ingest_api_service.clone(),
+            quickwit_services.index_service.clone(),
         ))
         .or(index_management_handlers(
             quickwit_services.index_service.clone(),
+            quickwit_services.indexer_service.clone(),
         ));
     let api_v1_root_route = api_v1_root_url.and(api_v1_routes);
     let redirect_root_to_ui_route =
@@ -111,6 +111,9 @@ pub struct SearchRequestQueryString {
     #[serde(deserialize_with = "sort_by_field_mini_dsl")]
     #[serde(default)]
     sort_by_field: Option<SortByField>,
+    /// If set, the splits that have not been searched after `timeout_millis` milliseconds are
+    /// given up and a partial response is returned.
+    pub timeout_millis: Option<u64>,
 }
 
 fn get_proto_search_by(search_request: &SearchRequestQueryString) -> (Option<i32>, Option<String>) {
@@ -144,6 +147,7 @@ async fn search_endpoint(
             .map(|agg| serde_json::to_string(&agg).expect("could not serialize serde_json::Value")),
         sort_order,
         sort_by_field,
+        timeout_millis: search_request.timeout_millis,
     };
     let search_response = search_service.root_search(search_request).await?;
     let search_response_rest = SearchResponseRest::try_from(search_response)?;
@@ -348,6 +352,8 @@ mod tests {
             hits: Vec::new(),
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
+            is_partial: false,
+            failed_splits: Vec::new(),
             aggregations: None,
         };
         let search_response_json: serde_json::Value = serde_json::to_value(&search_response)?;
@@ -627,6 +633,48 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_timeout_and_partial_response() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.timeout_millis == Some(500)
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    num_hits: 3,
+                    is_partial: true,
+                    failed_splits: vec![quickwit_proto::SplitSearchError {
+                        error: "Split search timed out.".to_string(),
+                        split_id: "split_1".to_string(),
+                        retryable_error: true,
+                    }],
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&timeout_millis=500")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "num_hits": 3,
+            "is_partial": true,
+            "failed_splits": [{
+                "error": "Split search timed out.",
+                "split_id": "split_1",
+                "retryable_error": true,
+            }],
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_with_index_does_not_exist() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
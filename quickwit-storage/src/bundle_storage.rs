@@ -35,6 +35,7 @@ use tantivy::HasLen;
 use thiserror::Error;
 use tracing::error;
 
+use crate::storage::sort_listed_paths;
 use crate::{OwnedBytes, Storage, StorageError, StorageResult};
 
 /// BundleStorage bundles together multiple files into a single file.
@@ -245,6 +246,19 @@ impl Storage for BundleStorage {
         Ok(file_range.end - file_range.start as u64)
     }
 
+    async fn list(&self, prefix: &Path, start_after: Option<&Path>) -> StorageResult<Vec<PathBuf>> {
+        let prefix_str = prefix.to_string_lossy();
+        let mut paths: Vec<PathBuf> = self
+            .metadata
+            .files
+            .keys()
+            .filter(|path| path.to_string_lossy().starts_with(prefix_str.as_ref()))
+            .cloned()
+            .collect();
+        sort_listed_paths(&mut paths, start_after);
+        Ok(paths)
+    }
+
     fn uri(&self) -> String {
         self.storage.uri()
     }
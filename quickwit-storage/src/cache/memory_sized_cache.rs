@@ -129,26 +129,27 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
     }
 
     /// Attempt to put the given amount of data in the cache.
-    /// This may fail silently if the owned_bytes slice is larger than the cache
+    /// This may fail silently if the owned_bytes slice and the key are larger than the cache
     /// capacity.
-    fn put(&mut self, key: K, bytes: OwnedBytes) {
-        if self.capacity.exceeds_capacity(bytes.len()) {
+    fn put(&mut self, key: K, key_num_bytes: usize, bytes: OwnedBytes) {
+        let num_bytes = key_num_bytes + bytes.len();
+        if self.capacity.exceeds_capacity(num_bytes) {
             // The value does not fit in the cache. We simply don't store it.
             warn!(
                 capacity_in_bytes = ?self.capacity,
-                len = bytes.len(),
+                len = num_bytes,
                 "Downloaded a byte slice larger than the cache capacity."
             );
             return;
         }
         if let Some(previous_data) = self.lru_cache.pop(&key) {
-            self.drop_item(previous_data.len() as u64);
+            self.drop_item(previous_data.num_bytes() as u64);
         }
 
         let now = Instant::now();
         while self
             .capacity
-            .exceeds_capacity(self.num_bytes as usize + bytes.len())
+            .exceeds_capacity(self.num_bytes as usize + num_bytes)
         {
             if let Some((_, candidate_for_eviction)) = self.lru_cache.peek_lru() {
                 let time_since_last_access =
@@ -161,7 +162,7 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
                 }
             }
             if let Some((_, bytes)) = self.lru_cache.pop_lru() {
-                self.drop_item(bytes.len() as u64);
+                self.drop_item(bytes.num_bytes() as u64);
             } else {
                 error!(
                     "Logical error. Even after removing all of the items in the cache the \
@@ -171,8 +172,9 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
                 return;
             }
         }
-        self.record_item(bytes.len() as u64);
-        self.lru_cache.put(key, StoredItem::new(bytes, now));
+        self.record_item(num_bytes as u64);
+        self.lru_cache
+            .put(key, StoredItem::new(bytes, key_num_bytes, now));
     }
 }
 
@@ -218,7 +220,13 @@ impl<K: Hash + Eq> MemorySizedCache<K> {
     /// This may fail silently if the owned_bytes slice is larger than the cache
     /// capacity.
     pub fn put(&self, val: K, bytes: OwnedBytes) {
-        self.inner.lock().unwrap().put(val, bytes);
+        self.inner.lock().unwrap().put(val, 0, bytes);
+    }
+
+    /// Same as `put`, for keys holding an amount of memory that is not negligible compared to
+    /// the data. The `key_num_bytes` bytes of the key are accounted for in the cache capacity.
+    pub fn put_with_key_num_bytes(&self, val: K, key_num_bytes: usize, bytes: OwnedBytes) {
+        self.inner.lock().unwrap().put(val, key_num_bytes, bytes);
     }
 }
 
@@ -302,6 +310,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cache_counts_key_num_bytes() {
+        let cache = MemorySizedCache::with_capacity_in_bytes(10, &CACHE_METRICS_FOR_TESTS);
+        cache.put_with_key_num_bytes("key-1", 8, OwnedBytes::new(&b"abc"[..]));
+        assert!(cache.get(&"key-1").is_none());
+        cache.put_with_key_num_bytes("key-2", 7, OwnedBytes::new(&b"abc"[..]));
+        assert_eq!(cache.get(&"key-2").unwrap(), &b"abc"[..]);
+        // The cache is full: the new item is not stored as the previous one is too young to be
+        // evicted.
+        cache.put_with_key_num_bytes("key-3", 1, OwnedBytes::new(&b"a"[..]));
+        assert!(cache.get(&"key-3").is_none());
+    }
+
     #[test]
     fn test_cache() {
         let cache = MemorySizedCache::with_capacity_in_bytes(10_000, &CACHE_METRICS_FOR_TESTS);
@@ -25,13 +25,15 @@ use tokio::time::Instant;
 pub(super) struct StoredItem {
     last_access_time: Instant,
     payload: OwnedBytes,
+    key_num_bytes: usize,
 }
 
 impl StoredItem {
-    pub fn new(payload: OwnedBytes, now: Instant) -> Self {
+    pub fn new(payload: OwnedBytes, key_num_bytes: usize, now: Instant) -> Self {
         StoredItem {
             last_access_time: now,
             payload,
+            key_num_bytes,
         }
     }
 }
@@ -46,6 +48,11 @@ impl StoredItem {
         self.payload.len()
     }
 
+    /// Number of bytes accounted for in the cache capacity: the payload and the key.
+    pub fn num_bytes(&self) -> usize {
+        self.key_num_bytes + self.payload.len()
+    }
+
     pub fn last_access_time(&self) -> Instant {
         self.last_access_time
     }
@@ -169,6 +169,26 @@ impl<T: Storage> Storage for DebouncedStorage<T> {
             .await
     }
 
+    fn supports_conditional_put(&self) -> bool {
+        self.underlying.supports_conditional_put()
+    }
+
+    async fn get_all_with_version(&self, path: &Path) -> StorageResult<(OwnedBytes, String)> {
+        // Versioned reads are not debounced: a concurrent write may have happened in between.
+        self.underlying.get_all_with_version(path).await
+    }
+
+    async fn put_if_version(
+        &self,
+        path: &Path,
+        payload: Box<dyn crate::PutPayload>,
+        expected_version: Option<&str>,
+    ) -> StorageResult<String> {
+        self.underlying
+            .put_if_version(path, payload, expected_version)
+            .await
+    }
+
     fn uri(&self) -> String {
         self.underlying.uri()
     }
@@ -176,6 +196,10 @@ impl<T: Storage> Storage for DebouncedStorage<T> {
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
         self.underlying.file_num_bytes(path).await
     }
+
+    async fn list(&self, prefix: &Path, start_after: Option<&Path>) -> StorageResult<Vec<PathBuf>> {
+        self.underlying.list(prefix, start_after).await
+    }
 }
 
 #[cfg(test)]
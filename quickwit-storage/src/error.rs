@@ -37,6 +37,8 @@ pub enum StorageErrorKind {
     InternalError,
     /// Io error.
     Io,
+    /// The condition of a conditional write does not hold.
+    PreconditionFailed,
 }
 
 /// Generic Storage Resolver Error.
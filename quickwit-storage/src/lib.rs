@@ -95,6 +95,23 @@ pub async fn load_file(uri: &Uri) -> anyhow::Result<OwnedBytes> {
     Ok(bytes)
 }
 
+/// Writes an entire local or remote file, overwriting it if it exists.
+pub async fn save_file(uri: &Uri, content: Vec<u8>) -> anyhow::Result<()> {
+    let path = Path::new(uri.as_ref());
+    let parent_uri = path
+        .parent()
+        .with_context(|| format!("`{}` is not a valid file URI.", uri))?
+        .to_str()
+        .with_context(|| format!("Failed to convert URI `{}` to str.", uri))?;
+
+    let storage = quickwit_storage_uri_resolver().resolve(parent_uri)?;
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("`{}` is not a valid file URI.", uri))?;
+    storage.put(Path::new(file_name), Box::new(content)).await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,7 +132,7 @@ mod tests {
 #[cfg(any(test, feature = "testsuite"))]
 pub(crate) mod test_suite {
 
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use anyhow::Context;
 
@@ -232,6 +249,43 @@ pub(crate) mod test_suite {
         Ok(())
     }
 
+    async fn test_list(storage: &mut dyn Storage) -> anyhow::Result<()> {
+        for test_path in [
+            "list/2021/12.json",
+            "list/2022/01.json",
+            "list/2022-02.json",
+        ] {
+            storage
+                .put(Path::new(test_path), Box::new(b"{}"[..].to_vec()))
+                .await?;
+        }
+        assert_eq!(
+            storage.list(Path::new("list/2022"), None).await?,
+            vec![
+                PathBuf::from("list/2022-02.json"),
+                PathBuf::from("list/2022/01.json")
+            ]
+        );
+        assert_eq!(storage.list(Path::new("list/"), None).await?.len(), 3);
+        assert!(storage.list(Path::new("list/2023"), None).await?.is_empty());
+        assert_eq!(
+            storage
+                .list(Path::new("list/"), Some(Path::new("list/2021/12.json")))
+                .await?,
+            vec![
+                PathBuf::from("list/2022-02.json"),
+                PathBuf::from("list/2022/01.json")
+            ]
+        );
+        assert_eq!(
+            storage
+                .list(Path::new("list/"), Some(Path::new("list/2022-02.json")))
+                .await?,
+            vec![PathBuf::from("list/2022/01.json")]
+        );
+        Ok(())
+    }
+
     /// Generic test suite for a storage.
     pub async fn storage_test_suite(storage: &mut dyn Storage) -> anyhow::Result<()> {
         test_get_inexistent_file(storage)
@@ -259,6 +313,7 @@ pub(crate) mod test_suite {
         test_delete_missing_file(storage)
             .await
             .with_context(|| "delete_missing_file")?;
+        test_list(storage).await.with_context(|| "list")?;
         Ok(())
     }
 }
@@ -21,19 +21,28 @@ use std::io::{ErrorKind, SeekFrom};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{fmt, io};
 
 use async_trait::async_trait;
 use futures::future::{BoxFuture, FutureExt};
 use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
-use tracing::warn;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::{error, warn};
 
+use crate::storage::sort_listed_paths;
 use crate::{
     DebouncedStorage, OwnedBytes, Storage, StorageError, StorageErrorKind, StorageFactory,
     StorageResult,
 };
 
+/// Maximum amount of time [`LocalFileStorage::put_if_version`] waits for the lock file of
+/// another writer to be removed.
+const LOCK_FILE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between two attempts to create a lock file held by another writer.
+const LOCK_FILE_POLLING_INTERVAL: Duration = Duration::from_millis(10);
+
 /// File system compatible storage implementation.
 #[derive(Clone)]
 pub struct LocalFileStorage {
@@ -150,6 +159,97 @@ fn delete_all_dirs(root: PathBuf, path: &Path) -> BoxFuture<'_, std::io::Result<
     .boxed()
 }
 
+/// Returns the version of a file, i.e. the MD5 digest of its content, as S3 ETags do.
+fn file_version(content: &[u8]) -> String {
+    format!("{:x}", md5::compute(content))
+}
+
+/// Returns the path `{full_path}{suffix}`.
+fn sibling_path(full_path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = full_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    full_path.with_file_name(file_name)
+}
+
+/// Creates the lock file `{full_path}.lock`, waiting for the lock file of another writer to be
+/// removed, and returns its path.
+///
+/// The lock file is created exclusively (`O_EXCL`), so at most one writer holds it at a time.
+/// A writer that crashes while holding it leaves it behind: the writes to the file then fail
+/// until the lock file is removed manually.
+async fn create_lock_file(full_path: &Path) -> StorageResult<PathBuf> {
+    let lock_path = sibling_path(full_path, ".lock");
+    let deadline = Instant::now() + LOCK_FILE_TIMEOUT;
+    loop {
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .await
+        {
+            Ok(_) => return Ok(lock_path),
+            Err(io_err) if io_err.kind() == ErrorKind::AlreadyExists => {
+                if Instant::now() >= deadline {
+                    return Err(StorageErrorKind::Io.with_error(anyhow::anyhow!(
+                        "Failed to create lock file `{}` within {:?}. If no other process is \
+                         writing `{}`, the lock file was left behind by a crashed writer and must \
+                         be removed.",
+                        lock_path.display(),
+                        LOCK_FILE_TIMEOUT,
+                        full_path.display()
+                    )));
+                }
+                tokio::time::sleep(LOCK_FILE_POLLING_INTERVAL).await;
+            }
+            Err(io_err) => return Err(io_err.into()),
+        }
+    }
+}
+
+/// Writes `payload` to `full_path` if its current version is `expected_version`. The caller
+/// must hold the lock file of `full_path`.
+///
+/// The payload is written to a temporary file, which is then renamed, so readers never observe
+/// a partially written file.
+async fn put_if_version_locked(
+    full_path: &Path,
+    payload: &[u8],
+    expected_version: Option<&str>,
+) -> StorageResult<String> {
+    let current_version = match fs::read(full_path).await {
+        Ok(content) => Some(file_version(&content)),
+        Err(io_err) if io_err.kind() == ErrorKind::NotFound => None,
+        Err(io_err) => return Err(io_err.into()),
+    };
+    if current_version.as_deref() != expected_version {
+        return Err(
+            StorageErrorKind::PreconditionFailed.with_error(anyhow::anyhow!(
+                "Expected version {:?} of file `{}`, but found {:?}.",
+                expected_version,
+                full_path.display(),
+                current_version
+            )),
+        );
+    }
+    let temp_path = sibling_path(full_path, &format!(".{:016x}.tmp", rand::random::<u64>()));
+    let write_res = async {
+        let mut temp_file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .await?;
+        temp_file.write_all(payload).await?;
+        temp_file.sync_all().await?;
+        fs::rename(&temp_path, full_path).await
+    }
+    .await;
+    if write_res.is_err() {
+        missing_file_is_ok(fs::remove_file(&temp_path).await)?;
+    }
+    write_res?;
+    Ok(file_version(payload))
+}
+
 fn missing_file_is_ok(io_result: io::Result<()>) -> io::Result<()> {
     match io_result {
         Ok(()) => Ok(()),
@@ -231,6 +331,36 @@ impl Storage for LocalFileStorage {
         Ok(OwnedBytes::new(content_bytes))
     }
 
+    fn supports_conditional_put(&self) -> bool {
+        true
+    }
+
+    async fn get_all_with_version(&self, path: &Path) -> StorageResult<(OwnedBytes, String)> {
+        let content = self.get_all(path).await?;
+        let version = file_version(content.as_slice());
+        Ok((content, version))
+    }
+
+    async fn put_if_version(
+        &self,
+        path: &Path,
+        payload: Box<dyn crate::PutPayload>,
+        expected_version: Option<&str>,
+    ) -> StorageResult<String> {
+        let full_path = self.root.join(path);
+        if let Some(parent_dir) = full_path.parent() {
+            fs::create_dir_all(parent_dir).await?;
+        }
+        let payload_bytes = payload.read_all().await?;
+        let lock_path = create_lock_file(&full_path).await?;
+        let put_res =
+            put_if_version_locked(&full_path, payload_bytes.as_slice(), expected_version).await;
+        if let Err(io_err) = fs::remove_file(&lock_path).await {
+            error!(error = ?io_err, lock = %lock_path.display(), "Failed to remove lock file.");
+        }
+        put_res
+    }
+
     fn uri(&self) -> String {
         format!("file://{}", self.root.to_string_lossy())
     }
@@ -257,6 +387,45 @@ impl Storage for LocalFileStorage {
             }
         }
     }
+
+    async fn list(&self, prefix: &Path, start_after: Option<&Path>) -> StorageResult<Vec<PathBuf>> {
+        let prefix_str = prefix.to_string_lossy();
+        // Only the directories that may contain matching files are walked.
+        let may_contain_matches = |relative_dir: &Path| {
+            let relative_dir_str = format!("{}/", relative_dir.to_string_lossy());
+            relative_dir_str.starts_with(prefix_str.as_ref())
+                || prefix_str.starts_with(&relative_dir_str)
+        };
+        let mut paths = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let full_path = entry.path();
+                let relative_path = full_path
+                    .strip_prefix(&self.root)
+                    .expect("Walked paths should be under the root directory.")
+                    .to_path_buf();
+                if entry.file_type().await?.is_dir() {
+                    if may_contain_matches(&relative_path) {
+                        dirs.push(full_path);
+                    }
+                } else if relative_path
+                    .to_string_lossy()
+                    .starts_with(prefix_str.as_ref())
+                {
+                    paths.push(relative_path);
+                }
+            }
+        }
+        sort_listed_paths(&mut paths, start_after);
+        Ok(paths)
+    }
 }
 
 /// A File storage resolver
@@ -357,4 +526,80 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_local_file_storage_put_if_version() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let file_storage = LocalFileStorage::from(temp_dir.path().to_path_buf());
+        let path = Path::new("my-index/metastore.json");
+        assert!(file_storage.supports_conditional_put());
+
+        let version = file_storage
+            .put_if_version(path, Box::new(b"v1".to_vec()), None)
+            .await?;
+        let put_error = file_storage
+            .put_if_version(path, Box::new(b"v1bis".to_vec()), None)
+            .await
+            .unwrap_err();
+        assert_eq!(put_error.kind(), StorageErrorKind::PreconditionFailed);
+
+        let (payload, fetched_version) = file_storage.get_all_with_version(path).await?;
+        assert_eq!(payload.as_slice(), b"v1");
+        assert_eq!(fetched_version, version);
+
+        let new_version = file_storage
+            .put_if_version(path, Box::new(b"v2".to_vec()), Some(&version))
+            .await?;
+        assert_ne!(new_version, version);
+        let put_error = file_storage
+            .put_if_version(path, Box::new(b"v3".to_vec()), Some(&version))
+            .await
+            .unwrap_err();
+        assert_eq!(put_error.kind(), StorageErrorKind::PreconditionFailed);
+        assert_eq!(file_storage.get_all(path).await?.as_slice(), b"v2");
+
+        // Neither the lock file nor the temporary files are left behind.
+        assert_eq!(
+            file_storage.list(Path::new("my-index/"), None).await?,
+            vec![path.to_path_buf()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_local_file_storage_put_if_version_concurrent_writers() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let file_storage = Arc::new(LocalFileStorage::from(temp_dir.path().to_path_buf()));
+        let path = Path::new("counter");
+        file_storage
+            .put_if_version(path, Box::new(b"0".to_vec()), None)
+            .await?;
+
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let file_storage = file_storage.clone();
+            handles.push(tokio::spawn(async move {
+                let mut num_increments = 0;
+                while num_increments < 10 {
+                    let (content, version) = file_storage.get_all_with_version(path).await?;
+                    let counter: u64 = std::str::from_utf8(content.as_slice())?.parse()?;
+                    let new_content = (counter + 1).to_string().into_bytes();
+                    match file_storage
+                        .put_if_version(path, Box::new(new_content), Some(&version))
+                        .await
+                    {
+                        Ok(_) => num_increments += 1,
+                        Err(error) if error.kind() == StorageErrorKind::PreconditionFailed => {}
+                        Err(error) => return Err(anyhow::anyhow!(error)),
+                    }
+                }
+                Ok::<_, anyhow::Error>(())
+            }));
+        }
+        for handle in handles {
+            handle.await??;
+        }
+        assert_eq!(file_storage.get_all(path).await?.as_slice(), b"40");
+        Ok(())
+    }
 }
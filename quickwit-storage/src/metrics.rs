@@ -27,6 +27,7 @@ pub struct StorageMetrics {
     pub shortlived_cache: CacheMetrics,
     pub fast_field_cache: CacheMetrics,
     pub split_footer_cache: CacheMetrics,
+    pub leaf_search_cache: CacheMetrics,
     pub object_storage_get_total: IntCounter,
     pub object_storage_put_total: IntCounter,
     pub object_storage_put_parts: IntCounter,
@@ -39,6 +40,7 @@ impl Default for StorageMetrics {
             fast_field_cache: CacheMetrics::for_component("fastfields"),
             shortlived_cache: CacheMetrics::for_component("shortlived"),
             split_footer_cache: CacheMetrics::for_component("splitfooter"),
+            leaf_search_cache: CacheMetrics::for_component("leafsearch"),
             object_storage_get_total: new_counter(
                 "object_storage_gets_total",
                 "Number of objects fetched.",
@@ -46,6 +46,7 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tracing::{debug, error, info, instrument, warn};
 
 use crate::object_storage::MultiPartPolicy;
+use crate::storage::sort_listed_paths;
 use crate::{OwnedBytes, Storage, StorageError, StorageErrorKind, StorageResult};
 
 /// Default region to use, if none has been configured.
@@ -742,6 +743,44 @@ impl Storage for S3CompatibleObjectStorage {
             Err(err) => Err(err.into()),
         }
     }
+
+    async fn list(&self, prefix: &Path, start_after: Option<&Path>) -> StorageResult<Vec<PathBuf>> {
+        let key_prefix = self.key(prefix);
+        let start_after_key = start_after.map(|start_after| self.key(start_after));
+        let mut paths = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let list_objects_req = ListObjectsV2Request {
+                bucket: self.bucket.clone(),
+                prefix: Some(key_prefix.clone()),
+                start_after: start_after_key.clone(),
+                continuation_token: continuation_token.take(),
+                ..Default::default()
+            };
+            let list_objects_output = retry(&self.retry_params, || async {
+                self.s3_client
+                    .list_objects_v2(list_objects_req.clone())
+                    .await
+                    .map_err(RusotoErrorWrapper::from)
+            })
+            .await?;
+            for object in list_objects_output.contents.unwrap_or_default() {
+                if let Some(key) = object.key {
+                    if let Ok(relative_path) = Path::new(&key).strip_prefix(&self.prefix) {
+                        paths.push(relative_path.to_path_buf());
+                    }
+                }
+            }
+            continuation_token = list_objects_output.next_continuation_token;
+            if list_objects_output.is_truncated != Some(true) || continuation_token.is_none() {
+                break;
+            }
+        }
+        sort_listed_paths(&mut paths, start_after);
+        Ok(paths)
+    }
+
     fn uri(&self) -> String {
         format!("s3://{}/{}", self.bucket, self.prefix.to_string_lossy())
     }
@@ -72,6 +72,30 @@ impl Storage for PrefixStorage {
         self.storage.exists(&self.prefix.join(path)).await
     }
 
+    fn supports_conditional_put(&self) -> bool {
+        self.storage.supports_conditional_put()
+    }
+
+    async fn get_all_with_version(
+        &self,
+        path: &Path,
+    ) -> crate::StorageResult<(OwnedBytes, String)> {
+        self.storage
+            .get_all_with_version(&self.prefix.join(path))
+            .await
+    }
+
+    async fn put_if_version(
+        &self,
+        path: &Path,
+        payload: Box<dyn crate::PutPayload>,
+        expected_version: Option<&str>,
+    ) -> crate::StorageResult<String> {
+        self.storage
+            .put_if_version(&self.prefix.join(path), payload, expected_version)
+            .await
+    }
+
     fn uri(&self) -> String {
         Path::new(&self.storage.uri())
             .join(&self.prefix)
@@ -82,6 +106,26 @@ impl Storage for PrefixStorage {
     async fn file_num_bytes(&self, path: &Path) -> crate::StorageResult<u64> {
         self.storage.file_num_bytes(&self.prefix.join(path)).await
     }
+
+    async fn list(
+        &self,
+        prefix: &Path,
+        start_after: Option<&Path>,
+    ) -> crate::StorageResult<Vec<PathBuf>> {
+        let start_after = start_after.map(|start_after| self.prefix.join(start_after));
+        let paths = self
+            .storage
+            .list(&self.prefix.join(prefix), start_after.as_deref())
+            .await?
+            .into_iter()
+            .filter_map(|path| {
+                path.strip_prefix(&self.prefix)
+                    .ok()
+                    .map(|relative_path| relative_path.to_path_buf())
+            })
+            .collect();
+        Ok(paths)
+    }
 }
 
 /// Creates a [`PrefixStorage`] using an underlying storage and a prefix.
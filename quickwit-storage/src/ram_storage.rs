@@ -17,8 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt;
+use std::hash::Hasher;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -28,6 +30,7 @@ use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 
+use crate::storage::sort_listed_paths;
 use crate::{
     add_prefix_to_storage, OwnedBytes, Storage, StorageErrorKind, StorageFactory, StorageResult,
 };
@@ -60,6 +63,13 @@ impl RamStorage {
         self.files.read().await.get(path).cloned()
     }
 
+    /// Returns the version of a file, derived from its content like the ETag of an object.
+    fn file_version(payload_bytes: &OwnedBytes) -> String {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(payload_bytes.as_slice());
+        format!("{:016x}", hasher.finish())
+    }
+
     /// Returns the list of files that are present in the RamStorage.
     pub async fn list_files(&self) -> Vec<PathBuf> {
         self.files.read().await.keys().cloned().collect()
@@ -114,6 +124,40 @@ impl Storage for RamStorage {
         Ok(payload_bytes)
     }
 
+    fn supports_conditional_put(&self) -> bool {
+        true
+    }
+
+    async fn get_all_with_version(&self, path: &Path) -> StorageResult<(OwnedBytes, String)> {
+        let payload_bytes = self.get_all(path).await?;
+        let version = Self::file_version(&payload_bytes);
+        Ok((payload_bytes, version))
+    }
+
+    async fn put_if_version(
+        &self,
+        path: &Path,
+        payload: Box<dyn crate::PutPayload>,
+        expected_version: Option<&str>,
+    ) -> StorageResult<String> {
+        let payload_bytes = payload.read_all().await?;
+        let mut files_wlock = self.files.write().await;
+        let current_version = files_wlock.get(path).map(Self::file_version);
+        if current_version.as_deref() != expected_version {
+            return Err(
+                StorageErrorKind::PreconditionFailed.with_error(anyhow::anyhow!(
+                    "Expected version {:?} of file `{}`, but found {:?}.",
+                    expected_version,
+                    path.display(),
+                    current_version
+                )),
+            );
+        }
+        let version = Self::file_version(&payload_bytes);
+        files_wlock.insert(path.to_path_buf(), payload_bytes);
+        Ok(version)
+    }
+
     fn uri(&self) -> String {
         "ram://".to_string()
     }
@@ -126,6 +170,20 @@ impl Storage for RamStorage {
             Err(StorageErrorKind::DoesNotExist.with_error(err))
         }
     }
+
+    async fn list(&self, prefix: &Path, start_after: Option<&Path>) -> StorageResult<Vec<PathBuf>> {
+        let prefix_str = prefix.to_string_lossy();
+        let mut paths: Vec<PathBuf> = self
+            .files
+            .read()
+            .await
+            .keys()
+            .filter(|path| path.to_string_lossy().starts_with(prefix_str.as_ref()))
+            .cloned()
+            .collect();
+        sort_listed_paths(&mut paths, start_after);
+        Ok(paths)
+    }
 }
 
 /// Builder to create a prepopulated [`RamStorage`]. This is mostly useful for tests.
@@ -198,6 +256,38 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_ram_storage_put_if_version() -> anyhow::Result<()> {
+        let ram_storage = RamStorage::default();
+        let path = Path::new("metastore.json");
+        assert!(ram_storage.supports_conditional_put());
+
+        let version = ram_storage
+            .put_if_version(path, Box::new(b"v1".to_vec()), None)
+            .await?;
+        let put_error = ram_storage
+            .put_if_version(path, Box::new(b"v1bis".to_vec()), None)
+            .await
+            .unwrap_err();
+        assert_eq!(put_error.kind(), StorageErrorKind::PreconditionFailed);
+
+        let (payload, fetched_version) = ram_storage.get_all_with_version(path).await?;
+        assert_eq!(payload.as_slice(), b"v1");
+        assert_eq!(fetched_version, version);
+
+        let new_version = ram_storage
+            .put_if_version(path, Box::new(b"v2".to_vec()), Some(&version))
+            .await?;
+        assert_ne!(new_version, version);
+        let put_error = ram_storage
+            .put_if_version(path, Box::new(b"v3".to_vec()), Some(&version))
+            .await
+            .unwrap_err();
+        assert_eq!(put_error.kind(), StorageErrorKind::PreconditionFailed);
+        assert_eq!(ram_storage.get_all(path).await?.as_slice(), b"v2");
+        Ok(())
+    }
+
     #[test]
     fn test_ram_storage_factory() {
         let ram_storage_factory = RamStorageFactory::default();
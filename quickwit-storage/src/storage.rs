@@ -18,12 +18,21 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 
 use crate::{OwnedBytes, PutPayload, StorageErrorKind, StorageResult};
 
+/// Sorts listed paths as strings, the way object storages sort their keys, and drops the paths that
+/// do not come after `start_after`.
+pub(crate) fn sort_listed_paths(paths: &mut Vec<PathBuf>, start_after: Option<&Path>) {
+    if let Some(start_after) = start_after {
+        paths.retain(|path| path.as_os_str() > start_after.as_os_str());
+    }
+    paths.sort_by(|left, right| left.as_os_str().cmp(right.as_os_str()));
+}
+
 /// Storage meant to receive and serve quickwit's split.
 ///
 /// Object storage are the primary target implementation of this trait,
@@ -73,6 +82,47 @@ pub trait Storage: Send + Sync + 'static {
     /// Returns a file size.
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64>;
 
+    /// Lists the files whose path starts with `prefix` and, if `start_after` is set, comes after
+    /// `start_after`, sorted by path.
+    ///
+    /// Like object storage keys, paths are matched and compared as strings: `logs/2022` matches
+    /// both `logs/2022/01.json` and `logs/2022-01.json`, and the latter comes first.
+    async fn list(&self, prefix: &Path, start_after: Option<&Path>) -> StorageResult<Vec<PathBuf>>;
+
+    /// Returns whether the storage supports conditional writes, i.e.
+    /// [`Storage::get_all_with_version`] and [`Storage::put_if_version`].
+    fn supports_conditional_put(&self) -> bool {
+        false
+    }
+
+    /// Downloads the entire content of a "small" file along with its version, an opaque token
+    /// that changes whenever the file is written.
+    async fn get_all_with_version(&self, path: &Path) -> StorageResult<(OwnedBytes, String)> {
+        Err(StorageErrorKind::InternalError.with_error(anyhow::anyhow!(
+            "Storage `{}` does not support conditional writes. Failed to get `{}`.",
+            self.uri(),
+            path.display()
+        )))
+    }
+
+    /// Saves a file if its current version is `expected_version`, or if it does not exist when
+    /// `expected_version` is `None`, and returns the version of the new file.
+    ///
+    /// The check and the write must be atomic. The method returns an error of kind
+    /// `PreconditionFailed` if the condition does not hold.
+    async fn put_if_version(
+        &self,
+        path: &Path,
+        _payload: Box<dyn PutPayload>,
+        _expected_version: Option<&str>,
+    ) -> StorageResult<String> {
+        Err(StorageErrorKind::InternalError.with_error(anyhow::anyhow!(
+            "Storage `{}` does not support conditional writes. Failed to put `{}`.",
+            self.uri(),
+            path.display()
+        )))
+    }
+
     /// Returns an URI identifying the storage
     fn uri(&self) -> String;
 }